@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Saved baselines live next to the bench folders, one json file per name (e.g. a git ref).
+const BASELINES_DIR: &str = "bench-baselines";
+const CRITERION_DIR: &str = "target/criterion";
+const BOOTSTRAP_RESAMPLES: usize = 10_000;
+
+/// Nanoseconds per iteration for every sample criterion took, keyed by benchmark id.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Baseline {
+    pub benches: BTreeMap<String, Vec<f64>>,
+}
+
+#[derive(Deserialize)]
+struct CriterionSample {
+    iters: Vec<f64>,
+    times: Vec<f64>,
+}
+
+pub struct Comparison {
+    pub bench_name: String,
+    /// Relative change of the mean time per iteration, in percent. Positive means slower.
+    pub change_pct: f64,
+    /// 95% confidence interval of `change_pct`, obtained by bootstrapping.
+    pub ci_low_pct: f64,
+    pub ci_high_pct: f64,
+}
+
+impl Comparison {
+    /// Only report a regression if we are confident the slowdown is above the threshold.
+    pub fn is_regression(&self, threshold_pct: f64) -> bool {
+        self.ci_low_pct > threshold_pct
+    }
+}
+
+fn baseline_path(name: &str) -> PathBuf {
+    // git refs like `origin/main` are valid names, but not valid file names
+    Path::new(BASELINES_DIR).join(format!("{}.json", name.replace('/', "_")))
+}
+
+/// Collect the samples of the most recent criterion run.
+pub fn collect_current_results() -> Baseline {
+    let mut baseline = Baseline::default();
+
+    collect_samples(Path::new(CRITERION_DIR), &mut baseline);
+
+    if baseline.benches.is_empty() {
+        eprintln!(
+            "I did not find any benchmark results in {}, did the benchmarks run?",
+            CRITERION_DIR
+        );
+        std::process::exit(1);
+    }
+
+    baseline
+}
+
+fn collect_samples(dir: &Path, baseline: &mut Baseline) {
+    let sample_path = dir.join("new").join("sample.json");
+
+    if sample_path.exists() {
+        let bench_name = dir
+            .strip_prefix(CRITERION_DIR)
+            .expect("Benchmark results should be in the criterion dir")
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        let sample_str = fs::read_to_string(&sample_path)
+            .unwrap_or_else(|_| panic!("Failed to read {:?}", sample_path));
+        let sample: CriterionSample = serde_json::from_str(&sample_str)
+            .unwrap_or_else(|_| panic!("Failed to parse {:?}", sample_path));
+
+        let per_iter_times = sample
+            .times
+            .iter()
+            .zip(sample.iters.iter())
+            .map(|(time, iters)| time / iters)
+            .collect();
+
+        baseline.benches.insert(bench_name, per_iter_times);
+
+        return;
+    }
+
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            // criterion keeps its html report next to the results
+            if path.is_dir() && path.file_name().map_or(true, |name| name != "report") {
+                collect_samples(&path, baseline);
+            }
+        }
+    }
+}
+
+pub fn save_baseline(name: &str, baseline: &Baseline) {
+    let path = baseline_path(name);
+
+    fs::create_dir_all(BASELINES_DIR).expect("Failed to create the baselines dir");
+    fs::write(
+        &path,
+        serde_json::to_string_pretty(baseline).expect("Failed to serialize baseline"),
+    )
+    .unwrap_or_else(|_| panic!("Failed to write {:?}", path));
+
+    println!(
+        "Saved {} benchmark results as baseline {:?} in {:?}",
+        baseline.benches.len(),
+        name,
+        path
+    );
+}
+
+pub fn load_baseline(name: &str) -> Baseline {
+    let path = baseline_path(name);
+
+    match fs::read_to_string(&path) {
+        Ok(baseline_str) => serde_json::from_str(&baseline_str)
+            .unwrap_or_else(|_| panic!("Failed to parse baseline {:?}", path)),
+        Err(_) => {
+            eprintln!(
+                "I can't find a baseline named {:?} at {:?}.\nUse `--save-baseline {}` to create it.",
+                name, path, name
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Compare every benchmark that is present in both runs.
+pub fn compare(baseline: &Baseline, current: &Baseline) -> Vec<Comparison> {
+    let mut rng = XorShift(0x2545_F491_4F6C_DD1D);
+
+    current
+        .benches
+        .iter()
+        .filter_map(|(bench_name, current_times)| {
+            let baseline_times = baseline.benches.get(bench_name)?;
+
+            if baseline_times.is_empty() || current_times.is_empty() {
+                return None;
+            }
+
+            let change_pct = relative_change_pct(baseline_times, current_times);
+            let (ci_low_pct, ci_high_pct) =
+                bootstrap_ci_pct(baseline_times, current_times, &mut rng);
+
+            Some(Comparison {
+                bench_name: bench_name.clone(),
+                change_pct,
+                ci_low_pct,
+                ci_high_pct,
+            })
+        })
+        .collect()
+}
+
+pub fn print_comparisons(baseline_name: &str, comparisons: &[Comparison], threshold_pct: f64) {
+    println!("\n\nComparison against baseline {:?}:\n", baseline_name);
+
+    for comparison in comparisons {
+        let verdict = if comparison.is_regression(threshold_pct) {
+            "REGRESSED"
+        } else if comparison.ci_high_pct < -threshold_pct {
+            "improved"
+        } else {
+            "no significant change"
+        };
+
+        println!(
+            "{:<40} {:>+8.2}% [{:>+8.2}%, {:>+8.2}%] {}",
+            comparison.bench_name,
+            comparison.change_pct,
+            comparison.ci_low_pct,
+            comparison.ci_high_pct,
+            verdict
+        );
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn relative_change_pct(baseline_times: &[f64], current_times: &[f64]) -> f64 {
+    let baseline_mean = mean(baseline_times);
+
+    (mean(current_times) - baseline_mean) / baseline_mean * 100.0
+}
+
+/// 95% confidence interval of the relative change of the means, using a percentile bootstrap.
+fn bootstrap_ci_pct(
+    baseline_times: &[f64],
+    current_times: &[f64],
+    rng: &mut XorShift,
+) -> (f64, f64) {
+    let mut baseline_resample = vec![0.0; baseline_times.len()];
+    let mut current_resample = vec![0.0; current_times.len()];
+
+    let mut changes: Vec<f64> = (0..BOOTSTRAP_RESAMPLES)
+        .map(|_| {
+            rng.resample(baseline_times, &mut baseline_resample);
+            rng.resample(current_times, &mut current_resample);
+
+            relative_change_pct(&baseline_resample, &current_resample)
+        })
+        .collect();
+
+    changes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let low_index = (BOOTSTRAP_RESAMPLES as f64 * 0.025) as usize;
+    let high_index = (BOOTSTRAP_RESAMPLES as f64 * 0.975) as usize;
+
+    (changes[low_index], changes[high_index])
+}
+
+/// Tiny deterministic PRNG, so bootstrap results are reproducible between CI runs.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn resample(&mut self, source: &[f64], target: &mut [f64]) {
+        for value in target.iter_mut() {
+            *value = source[(self.next() % source.len() as u64) as usize];
+        }
+    }
+}
@@ -1,3 +1,5 @@
+mod baseline;
+
 use clap::Parser;
 use data_encoding::HEXUPPER;
 use is_executable::IsExecutable;
@@ -18,6 +20,12 @@ const BENCH_FOLDER_BRANCH: &str = "bench-folder-branch";
 fn main() {
     let optional_args: OptionalArgs = OptionalArgs::parse();
 
+    if optional_args.baseline.is_some() || optional_args.save_baseline.is_some() {
+        do_baseline_benches(&optional_args);
+
+        return;
+    }
+
     if Path::new(BENCH_FOLDER_MAIN).exists() && Path::new(BENCH_FOLDER_BRANCH).exists() {
         delete_old_bench_results();
 
@@ -76,6 +84,52 @@ fn finish(all_regressed_benches: HashSet<String>, nr_repeat_benchmarks: usize) {
     }
 }
 
+// runs the benchmarks of bench-folder-branch once, then saves the results under a name
+// and/or compares them against a previously saved baseline
+fn do_baseline_benches(optional_args: &OptionalArgs) {
+    if !Path::new(BENCH_FOLDER_BRANCH).exists() {
+        eprintln!(
+            r#"I can't find bench-folder-branch from the current directory.
+        I should be executed from the repo root.
+        Use `./ci/benchmarks/prep_folder.sh branch` to generate bench-folder-branch."#
+        );
+
+        process::exit(1)
+    }
+
+    delete_old_bench_results();
+    do_benchmark("branch");
+
+    let current_results = baseline::collect_current_results();
+
+    if let Some(name) = &optional_args.save_baseline {
+        baseline::save_baseline(name, &current_results);
+    }
+
+    if let Some(name) = &optional_args.baseline {
+        let baseline_results = baseline::load_baseline(name);
+        let comparisons = baseline::compare(&baseline_results, &current_results);
+        let threshold_pct = optional_args.regression_threshold;
+
+        baseline::print_comparisons(name, &comparisons, threshold_pct);
+
+        let regressed_benches: Vec<&str> = comparisons
+            .iter()
+            .filter(|comparison| comparison.is_regression(threshold_pct))
+            .map(|comparison| comparison.bench_name.as_str())
+            .collect();
+
+        if !regressed_benches.is_empty() {
+            eprintln!(
+                "\n\nFAILED: The following benchmarks regressed by more than {}% compared to baseline {:?}: {:?}\n",
+                threshold_pct, name, regressed_benches
+            );
+
+            process::exit(1);
+        }
+    }
+}
+
 // returns all benchmarks that have regressed
 fn do_all_benches(nr_repeat_benchmarks: usize) -> HashSet<String> {
     delete_old_bench_results();
@@ -172,6 +226,15 @@ struct OptionalArgs {
     /// Do not run full benchmarks if no benchmark executable has changed
     #[clap(long)]
     check_executables_changed: bool,
+    /// Compare the results of bench-folder-branch against the baseline saved under this name (e.g. a git ref like `main`)
+    #[clap(long)]
+    baseline: Option<String>,
+    /// Save the results of bench-folder-branch as a baseline under this name (e.g. a git ref like `main`)
+    #[clap(long)]
+    save_baseline: Option<String>,
+    /// Only report a regression if the slowdown compared to the baseline is above this percentage with 95% confidence.
+    #[clap(long, default_value = "5.0")]
+    regression_threshold: f64,
 }
 
 fn sha256_digest<R: Read>(mut reader: R) -> Result<Digest, io::Error> {
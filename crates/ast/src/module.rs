@@ -16,6 +16,9 @@ pub fn load_module(
         palette: DEFAULT_PALETTE,
         threading,
         exec_mode: ExecutionMode::Check,
+        platform: None,
+        max_type_errors: None,
+        keep_module_types: false,
     };
 
     let arena = Bump::new();
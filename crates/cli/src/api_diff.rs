@@ -0,0 +1,142 @@
+use std::path::Path;
+
+use bumpalo::Bump;
+use roc_build::program::report_problems_typechecked;
+use roc_load::api_diff::{self, Change, ChangeKind, VersionBump};
+use roc_load::{ExecutionMode, LoadConfig, LoadingProblem, Threading};
+use roc_packaging::cache::{self, RocCacheDir};
+use roc_problem::Severity;
+use roc_reporting::report::{RenderTarget, DEFAULT_PALETTE};
+use roc_target::TargetInfo;
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiDiffFormat {
+    Human,
+    Json,
+}
+
+impl ApiDiffFormat {
+    pub const NAMES: [&'static str; 2] = ["human", "json"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "human" => Some(ApiDiffFormat::Human),
+            "json" => Some(ApiDiffFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Prints how the exposed values of the package at `old_path` changed in the one at `new_path`,
+/// and which part of the package's version that calls for increasing.
+pub fn api_diff(old_path: &Path, new_path: &Path, format: ApiDiffFormat) -> i32 {
+    let (old, new) = match (load_modules(old_path), load_modules(new_path)) {
+        (Ok(old), Ok(new)) => (old, new),
+        (Err(exit_code), _) | (_, Err(exit_code)) => return exit_code,
+    };
+
+    let changes = api_diff::diff(&old, &new);
+
+    match format {
+        ApiDiffFormat::Human => print_changes(&changes),
+        ApiDiffFormat::Json => println!("{}", api_diff::to_json(&changes)),
+    }
+
+    0
+}
+
+/// The solved types of every module in the package, see [roc_load::solved_types].
+fn load_modules(path: &Path) -> Result<Vec<Value>, i32> {
+    let arena = Bump::new();
+    let load_config = LoadConfig {
+        // Like `roc check`, this only needs to type-check, so the target doesn't matter.
+        target_info: TargetInfo::default_x86_64(),
+        render: RenderTarget::ColorTerminal,
+        palette: DEFAULT_PALETTE,
+        threading: Threading::AllAvailable,
+        exec_mode: ExecutionMode::Check,
+        platform: None,
+        max_type_errors: None,
+        keep_module_types: true,
+    };
+
+    let mut loaded = match roc_load::load_and_typecheck(
+        &arena,
+        path.to_path_buf(),
+        RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
+        load_config,
+    ) {
+        Ok(loaded) => loaded,
+        Err(LoadingProblem::FormattedReport(report)) => {
+            eprintln!("{}", report);
+
+            return Err(1);
+        }
+        Err(other) => {
+            eprintln!("I could not load {}: {:?}", path.display(), other);
+
+            return Err(1);
+        }
+    };
+
+    // The types of a package with errors in it can't be trusted. Warnings don't matter here, and
+    // aren't printed so they don't end up in the middle of JSON output.
+    let can_errors = (loaded.can_problems.values().flatten())
+        .any(|problem| problem.severity() != Severity::Warning);
+    let type_errors = (loaded.type_problems.values().flatten())
+        .any(|problem| problem.severity() != Severity::Warning);
+
+    if can_errors || type_errors {
+        report_problems_typechecked(&mut loaded, None, None);
+        eprintln!(
+            "\nThere are errors in {}, so I can't compare its types.",
+            path.display()
+        );
+
+        return Err(1);
+    }
+
+    Ok(roc_load::solved_types::modules_json(&mut loaded))
+}
+
+fn print_changes(changes: &[Change]) {
+    let sections = [
+        ("Breaking changes", ChangeKind::Removed),
+        ("Breaking changes", ChangeKind::Breaking),
+        ("Compatible changes", ChangeKind::Compatible),
+        ("Added", ChangeKind::Added),
+    ];
+    let mut printed_heading = None;
+
+    for (heading, kind) in sections {
+        for change in changes.iter().filter(|change| change.kind == kind) {
+            if printed_heading != Some(heading) {
+                println!("\n{heading}:\n");
+                printed_heading = Some(heading);
+            }
+
+            let name = format!("{}.{}", change.module, change.name);
+            let old_type = change.old_type.as_deref().unwrap_or_default();
+            let new_type = change.new_type.as_deref().unwrap_or_default();
+
+            match kind {
+                ChangeKind::Removed => {
+                    println!("    {name} was removed. It was:\n        {old_type}")
+                }
+                ChangeKind::Added => println!("    {name} : {new_type}"),
+                ChangeKind::Breaking | ChangeKind::Compatible => println!(
+                    "    {name} changed from:\n        {old_type}\n    to:\n        {new_type}"
+                ),
+            }
+        }
+    }
+
+    match VersionBump::of(changes) {
+        VersionBump::Major => println!("\nThese changes need a new major version.\n"),
+        VersionBump::Minor => println!("\nThese changes need a new minor version.\n"),
+        VersionBump::Patch => {
+            println!("No exposed values changed, so a new patch version is enough.")
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use roc_packaging::cache::{self, CacheContents};
+
+/// Prints where the cache is, and how much space downloaded packages and everything else take up.
+pub fn print_cache_summary() -> io::Result<i32> {
+    let root = cache::roc_cache_root();
+
+    println!("{}", root.display());
+
+    for (name, contents) in [
+        ("packages", CacheContents::Packages),
+        ("builds", CacheContents::Builds),
+    ] {
+        let size: u64 = cache::cache_entries(&root, contents)?
+            .iter()
+            .map(|path| cache::disk_usage(path))
+            .sum();
+
+        println!("    {name:<10}{}", format_bytes(size));
+    }
+
+    Ok(0)
+}
+
+pub fn print_cache_dir() -> io::Result<i32> {
+    println!("{}", cache::roc_cache_root().display());
+
+    Ok(0)
+}
+
+/// Deletes these contents of the cache, and prints how much space that freed up.
+pub fn clean_cache(contents: CacheContents) -> io::Result<i32> {
+    let root = cache::roc_cache_root();
+    let entries = cache::cache_entries(&root, contents)?;
+
+    if entries.is_empty() {
+        println!("Nothing to clean in {}", root.display());
+
+        return Ok(0);
+    }
+
+    let mut total = 0;
+
+    for path in entries {
+        let size = cache::disk_usage(&path);
+
+        remove(&path)?;
+
+        println!("Removed {} ({})", path.display(), format_bytes(size));
+
+        total += size;
+    }
+
+    println!("Freed {} in {}", format_bytes(total), root.display());
+
+    Ok(0)
+}
+
+fn remove(path: &Path) -> io::Result<()> {
+    // symlink_metadata, so that we delete a symlink to a directory rather than what it points to
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{bytes} bytes")
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    } else if bytes < 1024 * 1024 * 1024 {
+        format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1} GiB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    }
+}
@@ -0,0 +1,291 @@
+//! Per-project defaults for command-line flags, from a `roc.toml` file like this one:
+//!
+//! ```toml
+//! # roc build, run, dev, test, and plain `roc main.roc`
+//! [build]
+//! opt-level = "speed" # or "size", or "dev"
+//! linker = "legacy"
+//! jobs = 4
+//!
+//! [test]
+//! filter = "Parser"
+//! module = "Json"
+//! timeout-ms = 2000
+//!
+//! [check]
+//! deny = ["dead-expect"]
+//! max-type-errors = 20
+//!
+//! [format]
+//! sort-imports = true
+//! group-imports = true
+//! align-annotations = true
+//! ```
+//!
+//! A flag given on the command line always wins over the file.
+use clap::{parser::ValueSource, ArgMatches, Command};
+use roc_error_macros::user_error;
+use serde::Deserialize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    CMD_BUILD, CMD_CHECK, CMD_DEV, CMD_FORMAT, CMD_RUN, CMD_TEST, FLAG_ALIGN_ANNOTATIONS,
+    FLAG_BOTH, FLAG_DENY, FLAG_DEV, FLAG_FILTER, FLAG_GROUP_IMPORTS, FLAG_JOBS, FLAG_LINKER,
+    FLAG_MAX_TYPE_ERRORS, FLAG_MODULE, FLAG_OPTIMIZE, FLAG_OPT_SIZE, FLAG_SORT_IMPORTS,
+    FLAG_TIMEOUT_MS, ROC_FILE,
+};
+
+/// The file with a project's default flags. It applies to the .roc files in the directory
+/// it's in, and in every directory below that.
+pub const PROJECT_CONFIG_FILE: &str = "roc.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ProjectConfig {
+    build: BuildDefaults,
+    test: TestDefaults,
+    check: CheckDefaults,
+    format: FormatDefaults,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct BuildDefaults {
+    opt_level: Option<OptLevelName>,
+    linker: Option<LinkerName>,
+    jobs: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct TestDefaults {
+    filter: Option<String>,
+    module: Option<String>,
+    timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct CheckDefaults {
+    deny: Vec<DenyName>,
+    max_type_errors: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct FormatDefaults {
+    sort_imports: bool,
+    group_imports: bool,
+    align_annotations: bool,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum OptLevelName {
+    Dev,
+    Speed,
+    Size,
+}
+
+impl OptLevelName {
+    fn flag(self) -> &'static str {
+        match self {
+            OptLevelName::Dev => FLAG_DEV,
+            OptLevelName::Speed => FLAG_OPTIMIZE,
+            OptLevelName::Size => FLAG_OPT_SIZE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum LinkerName {
+    Surgical,
+    Legacy,
+}
+
+impl LinkerName {
+    fn as_str(self) -> &'static str {
+        match self {
+            LinkerName::Surgical => "surgical",
+            LinkerName::Legacy => "legacy",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum DenyName {
+    DeadExpect,
+}
+
+impl DenyName {
+    fn as_str(self) -> &'static str {
+        match self {
+            DenyName::DeadExpect => "dead-expect",
+        }
+    }
+}
+
+/// Parses the command line like `Command::get_matches`, then fills in the defaults from the
+/// nearest roc.toml for every flag the command line didn't set.
+pub fn get_matches_with_config(app: Command) -> ArgMatches {
+    let mut args: Vec<OsString> = std::env::args_os().collect();
+    let matches = app.clone().get_matches_from(&args);
+
+    let (subcommand, sub_matches) = match matches.subcommand() {
+        Some((name, sub_matches)) => (Some(name), sub_matches),
+        None => (None, &matches),
+    };
+
+    let config = match find_config(&start_dir(sub_matches)) {
+        Some(config_file) => read_config(&config_file),
+        None => return matches,
+    };
+
+    let defaults = config.args_for(subcommand, sub_matches);
+
+    if defaults.is_empty() {
+        return matches;
+    }
+
+    // Right after the subcommand, the defaults can't be mistaken for arguments to the app.
+    let insert_at = match subcommand {
+        None => 1,
+        Some(name) => args
+            .iter()
+            .skip(1)
+            .position(|arg| arg == name)
+            .map_or(1, |index| index + 2),
+    };
+
+    args.splice(
+        insert_at..insert_at,
+        defaults.into_iter().map(OsString::from),
+    );
+
+    app.get_matches_from(args)
+}
+
+/// Where to start looking for roc.toml: the directory of the .roc file the command is about,
+/// or the current directory for commands that aren't about one.
+fn start_dir(matches: &ArgMatches) -> PathBuf {
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+
+    let roc_file = matches
+        .try_get_many::<PathBuf>(ROC_FILE)
+        .ok()
+        .flatten()
+        .and_then(|mut roc_files| roc_files.next());
+
+    match roc_file.and_then(|roc_file| roc_file.parent()) {
+        Some(dir) => cwd.join(dir),
+        None => cwd,
+    }
+}
+
+fn find_config(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+fn read_config(config_file: &Path) -> ProjectConfig {
+    let config = std::fs::read_to_string(config_file).unwrap_or_else(|error| {
+        user_error!("I could not read {}: {}", config_file.display(), error)
+    });
+
+    toml::from_str(&config).unwrap_or_else(|error| {
+        user_error!(
+            "I could not use the default flags in {}: {}",
+            config_file.display(),
+            error
+        )
+    })
+}
+
+impl ProjectConfig {
+    /// The flags this file adds to the given subcommand, leaving out the ones the command
+    /// line already sets. Only asks `matches` about flags that subcommand has.
+    fn args_for(&self, subcommand: Option<&str>, matches: &ArgMatches) -> Vec<String> {
+        let given = |flag: &str| matches.value_source(flag) == Some(ValueSource::CommandLine);
+        let mut args = Vec::new();
+
+        match subcommand {
+            None | Some(CMD_BUILD | CMD_RUN | CMD_DEV | CMD_TEST) => {
+                let is_test = subcommand == Some(CMD_TEST);
+                let build = &self.build;
+
+                if let Some(opt_level) = build.opt_level {
+                    let opt_level_given = [FLAG_OPTIMIZE, FLAG_OPT_SIZE, FLAG_DEV]
+                        .into_iter()
+                        .any(given)
+                        // --both picks its own optimization levels
+                        || (is_test && given(FLAG_BOTH));
+
+                    if !opt_level_given {
+                        args.push(format!("--{}", opt_level.flag()));
+                    }
+                }
+
+                if let (Some(linker), false) = (build.linker, given(FLAG_LINKER)) {
+                    args.push(format!("--{}={}", FLAG_LINKER, linker.as_str()));
+                }
+
+                if let (Some(jobs), false) = (build.jobs, given(FLAG_JOBS)) {
+                    args.push(format!("--{}={}", FLAG_JOBS, jobs));
+                }
+
+                if is_test {
+                    let test = &self.test;
+
+                    if let (Some(filter), false) = (&test.filter, given(FLAG_FILTER)) {
+                        args.push(format!("--{}={}", FLAG_FILTER, filter));
+                    }
+
+                    if let (Some(module), false) = (&test.module, given(FLAG_MODULE)) {
+                        args.push(format!("--{}={}", FLAG_MODULE, module));
+                    }
+
+                    if let (Some(timeout_ms), false) = (test.timeout_ms, given(FLAG_TIMEOUT_MS)) {
+                        args.push(format!("--{}={}", FLAG_TIMEOUT_MS, timeout_ms));
+                    }
+                }
+            }
+            Some(CMD_CHECK) => {
+                let check = &self.check;
+
+                if !given(FLAG_DENY) {
+                    for deny in check.deny.iter() {
+                        args.push(format!("--{}={}", FLAG_DENY, deny.as_str()));
+                    }
+                }
+
+                if let (Some(max), false) = (check.max_type_errors, given(FLAG_MAX_TYPE_ERRORS)) {
+                    args.push(format!("--{}={}", FLAG_MAX_TYPE_ERRORS, max));
+                }
+
+                if let (Some(jobs), false) = (self.build.jobs, given(FLAG_JOBS)) {
+                    args.push(format!("--{}={}", FLAG_JOBS, jobs));
+                }
+            }
+            Some(CMD_FORMAT) => {
+                let format = &self.format;
+
+                for (flag, on) in [
+                    (FLAG_SORT_IMPORTS, format.sort_imports),
+                    (FLAG_GROUP_IMPORTS, format.group_imports),
+                    (FLAG_ALIGN_ANNOTATIONS, format.align_annotations),
+                ] {
+                    if on && !given(flag) {
+                        args.push(format!("--{}", flag));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        args
+    }
+}
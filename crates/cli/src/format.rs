@@ -5,9 +5,9 @@ use crate::FormatMode;
 use bumpalo::Bump;
 use roc_error_macros::{internal_error, user_error};
 use roc_fmt::def::fmt_defs;
-use roc_fmt::module::fmt_module;
+use roc_fmt::module::{fmt_module, reorder_header};
 use roc_fmt::spaces::RemoveSpaces;
-use roc_fmt::{Ast, Buf};
+use roc_fmt::{Ast, Buf, FormatOptions};
 use roc_parse::{
     module::{self, module_defs},
     parser::{Parser, SyntaxError},
@@ -58,7 +58,55 @@ fn is_roc_file(path: &Path) -> bool {
     matches!(path.extension().and_then(OsStr::to_str), Some("roc"))
 }
 
-pub fn format(files: std::vec::Vec<PathBuf>, mode: FormatMode) -> Result<(), String> {
+/// The file that turns on opt-in formatting rules for every .roc file below the directory
+/// it's in: one rule per line, e.g. `sort-imports`, with `#` starting a comment.
+const FORMAT_CONFIG_FILE: &str = ".roc-format";
+
+/// Reads the opt-in formatting rules from the nearest .roc-format file in `dir` or one of its
+/// ancestors. Without one, all of them are off.
+pub fn load_format_options(dir: &Path) -> FormatOptions {
+    let mut options = FormatOptions::default();
+
+    let config_file = match dir
+        .ancestors()
+        .map(|dir| dir.join(FORMAT_CONFIG_FILE))
+        .find(|path| path.is_file())
+    {
+        Some(config_file) => config_file,
+        None => return options,
+    };
+
+    let config = std::fs::read_to_string(&config_file).unwrap_or_else(|error| {
+        user_error!("I could not read {}: {}", config_file.display(), error)
+    });
+
+    for line in config.lines() {
+        let rule = match line.split_once('#') {
+            Some((rule, _comment)) => rule.trim(),
+            None => line.trim(),
+        };
+
+        match rule {
+            "" => {}
+            "sort-imports" => options.sort_imports = true,
+            "group-imports" => options.group_imports = true,
+            "align-annotations" => options.align_annotations = true,
+            other => user_error!(
+                "{} turns on a formatting rule I don't know: `{}`\n\nThe rules I know are sort-imports, group-imports, and align-annotations.",
+                config_file.display(),
+                other
+            ),
+        }
+    }
+
+    options
+}
+
+pub fn format(
+    files: std::vec::Vec<PathBuf>,
+    mode: FormatMode,
+    options: FormatOptions,
+) -> Result<(), String> {
     let files = flatten_directories(files);
 
     for file in files {
@@ -66,10 +114,15 @@ pub fn format(files: std::vec::Vec<PathBuf>, mode: FormatMode) -> Result<(), Str
 
         let src = std::fs::read_to_string(&file).unwrap();
 
-        let ast = arena.alloc(parse_all(&arena, &src).unwrap_or_else(|e| {
+        let mut ast = parse_all(&arena, &src).unwrap_or_else(|e| {
             user_error!("Unexpected parse failure when parsing this formatting:\n\n{:?}\n\nParse error was:\n\n{:?}\n\n", src, e)
-        }));
-        let mut buf = Buf::new_in(&arena);
+        });
+
+        // Reordering the header changes the tree, so the reparsed tree is compared to this one.
+        ast.module = reorder_header(&arena, &ast.module, options);
+
+        let ast = arena.alloc(ast);
+        let mut buf = Buf::new_with_options_in(&arena, options);
         fmt_all(&mut buf, ast);
 
         let reparsed_ast = arena.alloc(parse_all(&arena, buf.as_str()).unwrap_or_else(|e| {
@@ -116,7 +169,7 @@ pub fn format(files: std::vec::Vec<PathBuf>, mode: FormatMode) -> Result<(), Str
         }
 
         // Now verify that the resultant formatting is _stable_ - i.e. that it doesn't change again if re-formatted
-        let mut reformatted_buf = Buf::new_in(&arena);
+        let mut reformatted_buf = Buf::new_with_options_in(&arena, options);
         fmt_all(&mut reformatted_buf, reparsed_ast);
         if buf.as_str() != reformatted_buf.as_str() {
             let mut unstable_1_file = file.clone();
@@ -0,0 +1,259 @@
+use std::path::{Path, PathBuf};
+
+use bumpalo::Bump;
+use roc_collections::{MutMap, MutSet};
+use roc_load::{ExecutionMode, LoadConfig, LoadedModule, LoadingProblem, Threading};
+use roc_module::symbol::ModuleId;
+use roc_packaging::cache::{self, RocCacheDir};
+use roc_reporting::report::{RenderTarget, DEFAULT_PALETTE};
+use roc_target::TargetInfo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    Json,
+}
+
+impl GraphFormat {
+    pub const NAMES: [&'static str; 2] = ["dot", "json"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "dot" => Some(GraphFormat::Dot),
+            "json" => Some(GraphFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Prints which modules the module at `roc_file_path` imports, directly or indirectly.
+/// Builtin modules are included, but not what they import.
+pub fn graph(roc_file_path: PathBuf, format: GraphFormat) -> i32 {
+    let arena = Bump::new();
+    let load_config = LoadConfig {
+        // Like `roc check`, this only needs to type-check, so the target doesn't matter.
+        target_info: TargetInfo::default_x86_64(),
+        render: RenderTarget::ColorTerminal,
+        palette: DEFAULT_PALETTE,
+        threading: Threading::AllAvailable,
+        exec_mode: ExecutionMode::Check,
+        platform: None,
+        max_type_errors: None,
+        keep_module_types: false,
+    };
+
+    let loaded = match roc_load::load_and_typecheck(
+        &arena,
+        roc_file_path.clone(),
+        RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
+        load_config,
+    ) {
+        Ok(loaded) => loaded,
+        Err(LoadingProblem::FormattedReport(report)) => {
+            eprintln!("{}", report);
+
+            return 1;
+        }
+        Err(other) => {
+            eprintln!("I could not load {}: {:?}", roc_file_path.display(), other);
+
+            return 1;
+        }
+    };
+
+    let graph = ModuleGraph::new(&loaded, &roc_file_path);
+
+    match format {
+        GraphFormat::Dot => print!("{}", graph.to_dot()),
+        GraphFormat::Json => println!("{}", graph.to_json()),
+    }
+
+    0
+}
+
+struct GraphModule {
+    /// Qualified with the module's package, like `pf.Task`, so names are unique in the graph.
+    name: String,
+    package: Option<String>,
+    builtin: bool,
+    path: Option<PathBuf>,
+}
+
+struct Import {
+    from: usize,
+    to: usize,
+}
+
+struct ModuleGraph {
+    /// The root module comes first, and the rest are sorted by name.
+    modules: Vec<GraphModule>,
+    imports: Vec<Import>,
+}
+
+impl ModuleGraph {
+    fn new(loaded: &LoadedModule, root_path: &Path) -> Self {
+        let import_graph = &loaded.import_graph;
+
+        // Find every module the root depends on. Builtins import each other, but that's an
+        // implementation detail, so their imports are left out.
+        let mut reachable = MutSet::default();
+        let mut stack = vec![loaded.module_id];
+
+        while let Some(module_id) = stack.pop() {
+            if !reachable.insert(module_id) || module_id.is_builtin() {
+                continue;
+            }
+
+            if let Some(imports) = import_graph.imports.get(&module_id) {
+                stack.extend(imports.iter().copied());
+            }
+        }
+
+        let mut modules: Vec<(ModuleId, GraphModule)> = reachable
+            .into_iter()
+            .map(|module_id| {
+                let package = import_graph.packages.get(&module_id).cloned();
+                let mut name = loaded.interns.module_name(module_id).as_str().to_string();
+
+                if name.is_empty() {
+                    // The root module of an app doesn't have a name, so use its file's.
+                    name = root_path
+                        .file_stem()
+                        .map_or("app".into(), |stem| stem.to_string_lossy().into_owned());
+                }
+
+                if let Some(shorthand) = &package {
+                    name = format!("{shorthand}.{name}");
+                }
+
+                let module = GraphModule {
+                    name,
+                    package,
+                    builtin: module_id.is_builtin(),
+                    path: loaded.sources.get(&module_id).map(|(path, _)| path.clone()),
+                };
+
+                (module_id, module)
+            })
+            .collect();
+
+        // Sort the modules so the output doesn't depend on hash map order.
+        modules.sort_by(|(a_id, a), (b_id, b)| {
+            let a_is_root = *a_id == loaded.module_id;
+            let b_is_root = *b_id == loaded.module_id;
+
+            b_is_root.cmp(&a_is_root).then_with(|| a.name.cmp(&b.name))
+        });
+
+        let (module_ids, modules): (Vec<ModuleId>, Vec<GraphModule>) = modules.into_iter().unzip();
+
+        let indices: MutMap<ModuleId, usize> = (module_ids.iter().enumerate())
+            .map(|(index, module_id)| (*module_id, index))
+            .collect();
+
+        let mut imports = Vec::new();
+
+        for (from, module_id) in module_ids.iter().enumerate() {
+            if module_id.is_builtin() {
+                continue;
+            }
+
+            if let Some(imported) = import_graph.imports.get(module_id) {
+                let mut targets: Vec<usize> = (imported.iter())
+                    .filter_map(|imported_id| indices.get(imported_id).copied())
+                    .filter(|to| *to != from)
+                    .collect();
+                targets.sort_unstable();
+
+                imports.extend(targets.into_iter().map(|to| Import { from, to }));
+            }
+        }
+
+        ModuleGraph { modules, imports }
+    }
+
+    fn to_dot(&self) -> String {
+        let mut buf = String::from("digraph imports {\n");
+        let mut by_package: Vec<(&str, Vec<&GraphModule>)> = Vec::new();
+
+        for module in self.modules.iter() {
+            let cluster = match (&module.package, module.builtin) {
+                (_, true) => Some("builtins"),
+                (Some(package), false) => Some(package.as_str()),
+                (None, false) => None,
+            };
+
+            match cluster {
+                Some(cluster) => match by_package.iter_mut().find(|(name, _)| *name == cluster) {
+                    Some((_, modules)) => modules.push(module),
+                    None => by_package.push((cluster, vec![module])),
+                },
+                None => buf.push_str(&format!("    {:?};\n", module.name)),
+            }
+        }
+
+        // Each package is a cluster, so the boundaries between packages are visible.
+        for (cluster, modules) in by_package {
+            buf.push_str(&format!(
+                "    subgraph {:?} {{\n        label = {:?};\n",
+                format!("cluster_{cluster}"),
+                cluster
+            ));
+
+            if cluster == "builtins" {
+                buf.push_str("        style = dashed;\n");
+            }
+
+            for module in modules {
+                buf.push_str(&format!("        {:?};\n", module.name));
+            }
+
+            buf.push_str("    }\n");
+        }
+
+        for Import { from, to } in self.imports.iter() {
+            let attributes = if self.modules[*to].builtin {
+                " [style = dashed]"
+            } else {
+                ""
+            };
+
+            buf.push_str(&format!(
+                "    {:?} -> {:?}{};\n",
+                self.modules[*from].name, self.modules[*to].name, attributes
+            ));
+        }
+
+        buf.push_str("}\n");
+
+        buf
+    }
+
+    fn to_json(&self) -> String {
+        let modules: Vec<serde_json::Value> = (self.modules.iter())
+            .map(|module| {
+                serde_json::json!({
+                    "name": module.name,
+                    "package": module.package,
+                    "builtin": module.builtin,
+                    "path": module.path.as_ref().map(|path| path.display().to_string()),
+                })
+            })
+            .collect();
+        let imports: Vec<serde_json::Value> = (self.imports.iter())
+            .map(|Import { from, to }| {
+                serde_json::json!({
+                    "from": self.modules[*from].name,
+                    "to": self.modules[*to].name,
+                })
+            })
+            .collect();
+
+        serde_json::to_string_pretty(&serde_json::json!({
+            "root": self.modules[0].name,
+            "modules": modules,
+            "imports": imports,
+        }))
+        .unwrap()
+    }
+}
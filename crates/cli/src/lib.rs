@@ -8,17 +8,17 @@ use clap::{
     builder::PossibleValuesParser, parser::ValueSource, value_parser, Arg, ArgAction, ArgMatches,
     Command,
 };
-use roc_build::link::{LinkType, LinkingStrategy};
+use roc_build::link::{LinkType, LinkingStrategy, Sanitizers};
 use roc_build::program::{
     handle_error_module, handle_loading_problem, standard_load_config, BuildFileError,
-    BuildOrdering, BuiltFile, CodeGenBackend, CodeGenOptions, DEFAULT_ROC_FILENAME,
+    BuildOrdering, BuildStats, BuiltFile, CodeGenBackend, CodeGenOptions, DEFAULT_ROC_FILENAME,
 };
+use roc_docs::Theme;
 use roc_error_macros::{internal_error, user_error};
 use roc_gen_dev::AssemblyBackendMode;
-use roc_gen_llvm::llvm::build::LlvmBackendMode;
 use roc_load::{ExpectMetadata, Threading};
 use roc_mono::ir::OptLevel;
-use roc_packaging::cache::RocCacheDir;
+use roc_packaging::cache::{CacheContents, RocCacheDir, ROC_CACHE_DIR_VAR};
 use roc_packaging::tarball::Compression;
 use std::env;
 use std::ffi::{CString, OsStr, OsString};
@@ -27,22 +27,37 @@ use std::mem::ManuallyDrop;
 use std::os::raw::{c_char, c_int};
 use std::path::{Path, PathBuf};
 use std::process;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use strum::{EnumIter, IntoEnumIterator, IntoStaticStr};
 use target_lexicon::BinaryFormat;
 use target_lexicon::{
-    Architecture, Environment, OperatingSystem, Triple, Vendor, X86_32Architecture,
+    Architecture, ArmArchitecture, Environment, OperatingSystem, Riscv64Architecture, Triple,
+    Vendor, X86_32Architecture,
 };
 #[cfg(not(target_os = "linux"))]
 use tempfile::TempDir;
 
+#[cfg(feature = "llvm")]
+use roc_gen_llvm::llvm::build::LlvmBackendMode;
+
+mod api_diff;
+mod cache;
+mod config;
 mod format;
-pub use format::format;
+mod graph;
+#[cfg(all(not(windows), feature = "llvm"))]
+mod watch;
+pub use api_diff::{api_diff, ApiDiffFormat};
+pub use cache::{clean_cache, print_cache_dir, print_cache_summary};
+pub use config::get_matches_with_config;
+pub use format::{format, load_format_options};
+pub use graph::{graph, GraphFormat};
 
 pub const CMD_BUILD: &str = "build";
 pub const CMD_RUN: &str = "run";
 pub const CMD_DEV: &str = "dev";
 pub const CMD_REPL: &str = "repl";
+pub const CMD_EVAL: &str = "eval";
 pub const CMD_EDIT: &str = "edit";
 pub const CMD_DOCS: &str = "docs";
 pub const CMD_CHECK: &str = "check";
@@ -51,12 +66,20 @@ pub const CMD_FORMAT: &str = "format";
 pub const CMD_TEST: &str = "test";
 pub const CMD_GLUE: &str = "glue";
 pub const CMD_GEN_STUB_LIB: &str = "gen-stub-lib";
+pub const CMD_LSP: &str = "lsp";
+pub const CMD_GRAPH: &str = "graph";
+pub const CMD_API_DIFF: &str = "api-diff";
+pub const CMD_INDEX: &str = "index";
+pub const CMD_CACHE: &str = "cache";
+pub const CMD_INSPECT: &str = "inspect";
+pub const CMD_CACHE_DIR: &str = "dir";
+pub const CMD_CACHE_CLEAN: &str = "clean";
 
 pub const FLAG_DEBUG: &str = "debug";
 pub const FLAG_BUNDLE: &str = "bundle";
 pub const FLAG_DEV: &str = "dev";
 pub const FLAG_OPTIMIZE: &str = "optimize";
-pub const FLAG_MAX_THREADS: &str = "max-threads";
+pub const FLAG_JOBS: &str = "jobs";
 pub const FLAG_OPT_SIZE: &str = "opt-size";
 pub const FLAG_LIB: &str = "lib";
 pub const FLAG_NO_LINK: &str = "no-link";
@@ -66,12 +89,70 @@ pub const FLAG_LINKER: &str = "linker";
 pub const FLAG_PREBUILT: &str = "prebuilt-platform";
 pub const FLAG_CHECK: &str = "check";
 pub const FLAG_WASM_STACK_SIZE_KB: &str = "wasm-stack-size-kb";
+pub const FLAG_STDIN_FILE: &str = "stdin-file";
+pub const FLAG_EXPECT_STDOUT: &str = "expect-stdout";
+pub const FLAG_EXPECT_STDERR: &str = "expect-stderr";
+pub const FLAG_EXPECT_EXIT_CODE: &str = "expect-exit-code";
+pub const FLAG_PRELUDE: &str = "prelude";
+pub const FLAG_PLATFORM: &str = "platform";
+pub const FLAG_LIST_GROWTH: &str = "list-growth";
+pub const FLAG_MAX_MEMORY: &str = "max-memory";
+pub const FLAG_EMBED_METADATA: &str = "embed-metadata";
+pub const FLAG_APP_VERSION: &str = "app-version";
+pub const FLAG_OUTPUT: &str = "output";
+pub const FLAG_DEP: &str = "dep";
+pub const FLAG_THEME: &str = "theme";
+pub const FLAG_EMBED: &str = "embed";
+pub const FLAG_TARGETS: &str = "targets";
+pub const FLAG_MANIFEST: &str = "manifest";
+pub const FLAG_SANDBOX: &str = "sandbox";
+pub const FLAG_BOTH: &str = "both";
+pub const FLAG_DUMP_FRAMES: &str = "dump-frames";
+pub const FLAG_PROFILE_EXPECTS: &str = "profile-expects";
+pub const FLAG_TIMEOUT_MS: &str = "timeout-ms";
+pub const FLAG_FX_TIMEOUT: &str = "fx-timeout";
+pub const FLAG_VERBOSE: &str = "verbose";
+pub const FLAG_FILTER: &str = "filter";
+pub const FLAG_MODULE: &str = "module";
+pub const FLAG_WATCH: &str = "watch";
+pub const FLAG_SHUFFLE: &str = "shuffle";
+pub const FLAG_SEED: &str = "seed";
+pub const FLAG_COVERAGE: &str = "coverage";
+pub const FLAG_FAIL_FAST: &str = "fail-fast";
+pub const FLAG_MAX_FAILURES: &str = "max-failures";
+pub const FLAG_SHOW_TIMES: &str = "show-times";
+pub const FLAG_LIST: &str = "list";
+pub const FLAG_MAX_VALUE_DEPTH: &str = "max-value-depth";
+pub const FLAG_MAX_VALUE_LENGTH: &str = "max-value-length";
+pub const FLAG_CLEAN_SHM: &str = "clean-shm";
+pub const FLAG_TRACE_EFFECTS: &str = "trace-effects";
+pub const FLAG_HARDENING: &str = "hardening";
+pub const FLAG_SANITIZE: &str = "sanitize";
+pub const FLAG_BASELINE: &str = "baseline";
+pub const FLAG_WRITE_BASELINE: &str = "write-baseline";
+pub const FLAG_DENY: &str = "deny";
+pub const FLAG_MAX_TYPE_ERRORS: &str = "max-type-errors";
+pub const FLAG_EMIT_TYPES: &str = "emit-types";
+pub const FLAG_LSIF: &str = "lsif";
+pub const FLAG_FORMAT: &str = "format";
+pub const FLAG_JUNIT_FILE: &str = "junit-file";
+pub const FLAG_SORT_IMPORTS: &str = "sort-imports";
+pub const FLAG_GROUP_IMPORTS: &str = "group-imports";
+pub const FLAG_ALIGN_ANNOTATIONS: &str = "align-annotations";
+pub const FLAG_CACHE_DIR: &str = "cache-dir";
+pub const FLAG_PACKAGES: &str = "packages";
+pub const FLAG_BUILDS: &str = "builds";
+pub const FLAG_ALL: &str = "all";
 pub const ROC_FILE: &str = "ROC_FILE";
+pub const EXPR: &str = "EXPR";
 pub const ROC_DIR: &str = "ROC_DIR";
+pub const OLD_ROC_FILE: &str = "OLD_ROC_FILE";
+pub const NEW_ROC_FILE: &str = "NEW_ROC_FILE";
 pub const GLUE_DIR: &str = "GLUE_DIR";
 pub const GLUE_SPEC: &str = "GLUE_SPEC";
 pub const DIRECTORY_OR_FILES: &str = "DIRECTORY_OR_FILES";
 pub const ARGS_FOR_APP: &str = "ARGS_FOR_APP";
+pub const BINARY_FILE: &str = "BINARY_FILE";
 
 const VERSION: &str = include_str!("../../../version.txt");
 
@@ -82,9 +163,12 @@ pub fn build_app() -> Command {
         .action(ArgAction::SetTrue)
         .required(false);
 
-    let flag_max_threads = Arg::new(FLAG_MAX_THREADS)
-        .long(FLAG_MAX_THREADS)
-        .help("Limit the number of threads (and hence cores) used during compilation")
+    let flag_jobs = Arg::new(FLAG_JOBS)
+        .long(FLAG_JOBS)
+        .short('j')
+        // what this flag was called before
+        .alias("max-threads")
+        .help("Limit the number of threads (and hence cores) used during compilation\n(Defaults to the number of available cores. With -j 1, the platform is rebuilt before the app is compiled, instead of at the same time.)")
         .value_parser(value_parser!(usize))
         .required(false);
 
@@ -100,10 +184,21 @@ pub fn build_app() -> Command {
         .action(ArgAction::SetTrue)
         .required(false);
 
+    let flag_trace_effects = Arg::new(FLAG_TRACE_EFFECTS)
+        .long(FLAG_TRACE_EFFECTS)
+        .help("Log every effect the program runs to stderr, with its arguments and duration")
+        .action(ArgAction::SetTrue)
+        .conflicts_with(FLAG_DEV)
+        .required(false);
+
     let flag_debug = Arg::new(FLAG_DEBUG)
         .long(FLAG_DEBUG)
-        .help("Store LLVM debug information in the generated program")
-        .action(ArgAction::SetTrue)
+        .help("Store LLVM debug information in the generated program\n(--debug=specializations instead lists every specialized procedure with its layouts and estimated size, and --debug=layouts reports layout interner statistics. Combine them like --debug=info,specializations.)")
+        .value_parser(["info", "specializations", "layouts"])
+        .value_delimiter(',')
+        .num_args(0..)
+        .require_equals(true)
+        .default_missing_value("info")
         .required(false);
 
     let flag_time = Arg::new(FLAG_TIME)
@@ -124,12 +219,54 @@ pub fn build_app() -> Command {
         .action(ArgAction::SetTrue)
         .required(false);
 
+    let flag_platform = Arg::new(FLAG_PLATFORM)
+        .long(FLAG_PLATFORM)
+        .help("Use this platform (a URL or a path) instead of the one in the app header\n(The app is type-checked against this platform's `requires` instead, e.g. to run it on a test platform.)")
+        .required(false);
+
+    let flag_list_growth = Arg::new(FLAG_LIST_GROWTH)
+        .long(FLAG_LIST_GROWTH)
+        .help("How much a full list grows, as a percentage of its capacity, e.g. 200 to double it\n(By default, lists double while small or huge and grow by half in between. Strings grow the same way.)")
+        .value_parser(value_parser!(u32).range(101..=1000))
+        .required(false);
+
+    let flag_max_memory = Arg::new(FLAG_MAX_MEMORY)
+        .long(FLAG_MAX_MEMORY)
+        .help("Limit how much memory the program may use, e.g. 512M or 2G\n(This limits the program's address space, so an allocation over the limit fails. Only on Linux for now.)")
+        .value_parser(parse_byte_size)
+        .required(false);
+
     let flag_wasm_stack_size_kb = Arg::new(FLAG_WASM_STACK_SIZE_KB)
         .long(FLAG_WASM_STACK_SIZE_KB)
         .help("Stack size in kilobytes for wasm32 target\n(This only applies when --dev also provided.)")
         .value_parser(value_parser!(u32))
         .required(false);
 
+    let flag_stdin_file = Arg::new(FLAG_STDIN_FILE)
+        .long(FLAG_STDIN_FILE)
+        .help("Feed the contents of this file to the program's stdin")
+        .value_parser(value_parser!(PathBuf))
+        .required(false);
+
+    let flag_expect_stdout = Arg::new(FLAG_EXPECT_STDOUT)
+        .long(FLAG_EXPECT_STDOUT)
+        .help("Capture the program's stdout and check that it matches the contents of this file\n(If it does not match, return a non-zero exit code.)")
+        .value_parser(value_parser!(PathBuf))
+        .required(false);
+
+    let flag_expect_stderr = Arg::new(FLAG_EXPECT_STDERR)
+        .long(FLAG_EXPECT_STDERR)
+        .help("Capture the program's stderr and check that it matches the contents of this file\n(If it does not match, return a non-zero exit code.)")
+        .value_parser(value_parser!(PathBuf))
+        .required(false);
+
+    let flag_expect_exit_code = Arg::new(FLAG_EXPECT_EXIT_CODE)
+        .long(FLAG_EXPECT_EXIT_CODE)
+        .help("Check that the program exits with this exit code\n(If it does not, return a non-zero exit code.)")
+        .value_parser(value_parser!(i32))
+        .allow_negative_numbers(true)
+        .required(false);
+
     let roc_file_to_run = Arg::new(ROC_FILE)
         .help("The .roc file of an app to run")
         .value_parser(value_parser!(PathBuf))
@@ -148,17 +285,51 @@ pub fn build_app() -> Command {
         .version(concatcp!(VERSION, "\n"))
         .about("Run the given .roc file, if there are no compilation errors.\nYou can use one of the SUBCOMMANDS below to do something else!")
         .args_conflicts_with_subcommands(true)
+        .arg(
+            Arg::new(FLAG_CACHE_DIR)
+                .long(FLAG_CACHE_DIR)
+                .help(concatcp!("Keep downloaded packages and other cached files in this directory\n(Can also be set with the ", ROC_CACHE_DIR_VAR, " environment variable.)"))
+                .value_parser(value_parser!(PathBuf))
+                .global(true)
+                .required(false),
+        )
         .subcommand(Command::new(CMD_BUILD)
             .about("Build a binary from the given .roc file, but don't run it")
             .arg(flag_optimize.clone())
-            .arg(flag_max_threads.clone())
+            .arg(flag_jobs.clone())
             .arg(flag_opt_size.clone())
             .arg(flag_dev.clone())
             .arg(flag_debug.clone())
             .arg(flag_time.clone())
             .arg(flag_linker.clone())
             .arg(flag_prebuilt.clone())
+            .arg(flag_platform.clone())
+            .arg(flag_list_growth.clone())
             .arg(flag_wasm_stack_size_kb)
+            .arg(
+                Arg::new(FLAG_HARDENING)
+                    .long(FLAG_HARDENING)
+                    .help("Link Linux executables with full RELRO and a non-executable stack, and warn if the platform's host lacks them")
+                    .value_parser(["on", "off"])
+                    .default_value("on")
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_SANITIZE)
+                    .long(FLAG_SANITIZE)
+                    .help("Build the Roc code and the platform's host glue with sanitizers, e.g. --sanitize=address,undefined\n(Only for Linux executables. Implies --linker=legacy. `undefined` only affects the host, because Roc code already checks for overflow.)")
+                    .value_parser(["address", "undefined"])
+                    .value_delimiter(',')
+                    .require_equals(true)
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_VERBOSE)
+                    .long(FLAG_VERBOSE)
+                    .help("Print a summary after building: cached vs. fresh modules, specializations, code gen and link time, binary size, and the compiler's peak memory use after each phase")
+                    .action(ArgAction::SetTrue)
+                    .required(false)
+            )
             .arg(
                 Arg::new(FLAG_TARGET)
                     .long(FLAG_TARGET)
@@ -189,24 +360,229 @@ pub fn build_app() -> Command {
                     .action(ArgAction::SetTrue)
                     .required(false),
             )
+            .arg(
+                Arg::new(FLAG_EMBED_METADATA)
+                    .long(FLAG_EMBED_METADATA)
+                    .help("Store the app's name, the platform's path and hash, and the compiler version in the executable, for `roc inspect` to show\n(Only for ELF and wasm executables for now.)")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all([FLAG_LIB, FLAG_NO_LINK, FLAG_BUNDLE])
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_APP_VERSION)
+                    .long(FLAG_APP_VERSION)
+                    .help("The version of the app to store in the executable, e.g. 1.4.2 or a git commit")
+                    .requires(FLAG_EMBED_METADATA)
+                    .required(false),
+            )
             .arg(
                 Arg::new(ROC_FILE)
-                    .help("The .roc file to build")
+                    .help("The .roc files to build\n(Apps on the same platform only rebuild its host once.)")
                     .value_parser(value_parser!(PathBuf))
+                    .num_args(1..)
                     .required(false)
                     .default_value(DEFAULT_ROC_FILENAME),
             )
         )
         .subcommand(Command::new(CMD_TEST)
-            .about("Run all top-level `expect`s in a main module and any modules it imports")
+            .about("Run all top-level `expect`s in a main module, any modules it imports, and the `*Test.roc` modules in its `Tests` directory")
             .arg(flag_optimize.clone())
-            .arg(flag_max_threads.clone())
+            .arg(flag_jobs.clone())
             .arg(flag_opt_size.clone())
             .arg(flag_dev.clone())
             .arg(flag_debug.clone())
             .arg(flag_time.clone())
             .arg(flag_linker.clone())
             .arg(flag_prebuilt.clone())
+            .arg(
+                Arg::new(FLAG_SANDBOX)
+                    .long(FLAG_SANDBOX)
+                    .help("Confine effectful expects, e.g. --sandbox=network,filesystem\n(`network` only allows unix sockets, `filesystem` only allows writing to a temporary directory. Plain --sandbox enables both.)")
+                    .value_parser(["network", "filesystem"])
+                    .value_delimiter(',')
+                    .num_args(0..)
+                    .require_equals(true)
+                    .default_missing_values(["network", "filesystem"])
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_TIMEOUT_MS)
+                    .long(FLAG_TIMEOUT_MS)
+                    .help("Stop an expect after it ran for this many milliseconds, and report it as timed out; 0 means no limit\n(Only on Linux for now. Effectful expects use --fx-timeout instead.)")
+                    .value_parser(value_parser!(u64))
+                    .default_value("10000")
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_MAX_MEMORY)
+                    .long(FLAG_MAX_MEMORY)
+                    .help("Crash an expect that has more than this much memory allocated at once, e.g. 512M or 2G\n(The crash is reported with how much the expect had allocated. Effectful expects are limited too, but their crash doesn't say how much.)")
+                    .value_parser(parse_byte_size)
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_FX_TIMEOUT)
+                    .long(FLAG_FX_TIMEOUT)
+                    .help("Stop an effectful expect, and every process it started, after this many seconds\n(The expect then counts as failed.)")
+                    .value_parser(value_parser!(u64).range(1..))
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_BOTH)
+                    .long(FLAG_BOTH)
+                    .help("Run the expects both without and with optimizations, and report every expect that only fails in one of them\n(A difference means the optimizer miscompiled something.)")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all([FLAG_OPTIMIZE, FLAG_OPT_SIZE])
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_DUMP_FRAMES)
+                    .long(FLAG_DUMP_FRAMES)
+                    .help("Write the raw shared memory of each failed expect, and what it was decoded as, to this directory\n(This is for reporting bugs in how expect failures are printed.)")
+                    .value_parser(value_parser!(PathBuf))
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_PROFILE_EXPECTS)
+                    .long(FLAG_PROFILE_EXPECTS)
+                    .help("Sample where each expect spends its time, and print the functions it spent the most time in\n(Expects that finish within a millisecond of CPU time are not reported.)")
+                    .action(ArgAction::SetTrue)
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_COVERAGE)
+                    .long(FLAG_COVERAGE)
+                    .help("After running the expects, list the definitions exposed by the package's modules that no expect used")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with(FLAG_WATCH)
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_VERBOSE)
+                    .long(FLAG_VERBOSE)
+                    .help("Print the compiler's peak memory use after each phase")
+                    .action(ArgAction::SetTrue)
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_FILTER)
+                    .long(FLAG_FILTER)
+                    .help("Only compile and run the expects whose source code contains this text\n(The source code of an expect includes the comment right above it, so that comment can be used as its name.)")
+                    .value_parser(value_parser!(String))
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_MODULE)
+                    .long(FLAG_MODULE)
+                    .help("Only compile and run the expects in this module\n(Either the module's name, or the name of its file without the .roc extension.)")
+                    .value_parser(value_parser!(String))
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_SHUFFLE)
+                    .long(FLAG_SHUFFLE)
+                    .help("Run the expects in a random order, to find expects that only pass when some other expect ran first\n(The order depends on a seed that gets printed, see --seed.)")
+                    .action(ArgAction::SetTrue)
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_SEED)
+                    .long(FLAG_SEED)
+                    .help("Shuffle the expects like --shuffle does, but with this seed instead of a random one\n(Use the seed that a shuffled run printed to run the expects in the same order again.)")
+                    .value_parser(value_parser!(u64))
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_FAIL_FAST)
+                    .long(FLAG_FAIL_FAST)
+                    .help("Stop running expects after the first one that fails\n(The same as --max-failures=1.)")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all([FLAG_MAX_FAILURES, FLAG_BOTH])
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_MAX_FAILURES)
+                    .long(FLAG_MAX_FAILURES)
+                    .help("Stop running expects after this many of them failed\n(The summary says how many were skipped.)")
+                    .value_parser(value_parser!(u64).range(1..))
+                    .conflicts_with(FLAG_BOTH)
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_LIST)
+                    .long(FLAG_LIST)
+                    .help("Print every expect that --filter and --module let through, without running any of them\n(Both top-level expects and expects inside definitions, with where they are and whether they're effectful. With --format json, one JSON object per line.)")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all([FLAG_BOTH, FLAG_WATCH, FLAG_COVERAGE, FLAG_PROFILE_EXPECTS, FLAG_SHOW_TIMES])
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_SHOW_TIMES)
+                    .long(FLAG_SHOW_TIMES)
+                    .help("After the summary, list the expects that took the longest to run, slowest first\n(Lists the 10 slowest, unless given another number, like --show-times=3. Not supported with --format json or tap.)")
+                    .value_parser(value_parser!(u64).range(1..))
+                    .num_args(0..=1)
+                    .require_equals(true)
+                    .default_missing_value("10")
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_MAX_VALUE_DEPTH)
+                    .long(FLAG_MAX_VALUE_DEPTH)
+                    .help("Show this many levels of nested lists, records, tuples, and tags in the values of failed expects and dbg, and deeper ones as …; 0 means no limit\n(Defaults to 10. JSON output always has the whole value.)")
+                    .value_parser(value_parser!(u64))
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_MAX_VALUE_LENGTH)
+                    .long(FLAG_MAX_VALUE_LENGTH)
+                    .help("Show this many elements of each list in the values of failed expects and dbg, and only count the rest; 0 means no limit\n(Defaults to 100. JSON output always has the whole value.)")
+                    .value_parser(value_parser!(u64))
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_CLEAN_SHM)
+                    .long(FLAG_CLEAN_SHM)
+                    .help("Remove the shared memory buffers that the expects of crashed or killed roc processes left behind, and exit without running any expects")
+                    .action(ArgAction::SetTrue)
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_WATCH)
+                    .long(FLAG_WATCH)
+                    .help("Keep running, and whenever a module changes, run the expects of that module and of every module that imports it again")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all([FLAG_BOTH, FLAG_VERBOSE])
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_TARGET)
+                    .long(FLAG_TARGET)
+                    .help("Run the expects compiled for this target\n(`wasm32` runs them in a WebAssembly interpreter. Effectful expects are skipped there.)")
+                    .value_parser(PossibleValuesParser::new(
+                        [Target::System, Target::Wasm32].map(Into::<&'static str>::into),
+                    ))
+                    .default_value(Into::<&'static str>::into(Target::default()))
+                    .conflicts_with_all([FLAG_SANDBOX, FLAG_FX_TIMEOUT, FLAG_PROFILE_EXPECTS])
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_FORMAT)
+                    .long(FLAG_FORMAT)
+                    .help("How to report failed expects\n(`json` prints one JSON object per line for each failure and crash, with its location, source, and the values it looked up, followed by a summary. `junit` reports failures like `human` does, and also writes a JUnit XML report for CI systems, see --junit-file. `tap` prints a TAP version 13 stream, for harnesses like `prove`.)")
+                    .value_parser(["human", "json", "junit", "tap"])
+                    .default_value("human")
+                    .conflicts_with_all([FLAG_BOTH, FLAG_PROFILE_EXPECTS, FLAG_VERBOSE, FLAG_WATCH])
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_JUNIT_FILE)
+                    .long(FLAG_JUNIT_FILE)
+                    .help("Where --format junit writes its report\n(It has a test case for each expect that ran, grouped by module.)")
+                    .value_parser(value_parser!(PathBuf))
+                    .default_value("junit.xml")
+                    .required(false)
+            )
             .arg(
                 Arg::new(ROC_FILE)
                     .help("The .roc file for the main module")
@@ -218,30 +594,106 @@ pub fn build_app() -> Command {
         )
         .subcommand(Command::new(CMD_REPL)
             .about("Launch the interactive Read Eval Print Loop (REPL)")
+            .arg(
+                Arg::new(FLAG_PRELUDE)
+                    .long(FLAG_PRELUDE)
+                    .help("A file of definitions to load before the first prompt\n(Separate the definitions with blank lines. Defaults to the file in the ROC_REPL_PRELUDE environment variable, if set.)")
+                    .value_parser(value_parser!(PathBuf))
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_PLATFORM)
+                    .long(FLAG_PLATFORM)
+                    .help("The main .roc file of a platform, to run expressions that use its effects (like `Stdout.line \"hi\"`) with its host")
+                    .value_parser(value_parser!(PathBuf))
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_OUTPUT)
+                    .long(FLAG_OUTPUT)
+                    .help("How to print values\n(`json` prints only each value, as JSON, e.g. for `echo '[1, 2]' | roc repl --output json | jq`. In the REPL, `:set output json` does the same.)")
+                    .value_parser(["roc", "json"])
+                    .default_value("roc")
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_TIMEOUT_MS)
+                    .long(FLAG_TIMEOUT_MS)
+                    .help("Stop evaluating an expression after it ran for this many milliseconds; 0 means no limit\n(Ctrl-C stops it too, without leaving the REPL. Only on Linux for now.)")
+                    .value_parser(value_parser!(u64))
+                    .default_value("0")
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_MAX_MEMORY)
+                    .long(FLAG_MAX_MEMORY)
+                    .help("Stop evaluating an expression that has more than this much memory allocated at once, e.g. 512M or 2G")
+                    .value_parser(parse_byte_size)
+                    .required(false),
+            )
+        )
+        .subcommand(Command::new(CMD_EVAL)
+            .about("Evaluate a single expression and print its value, like the REPL does")
+            .arg(
+                Arg::new(FLAG_DEP)
+                    .long(FLAG_DEP)
+                    .help("A module for the expression to import, e.g. --dep Parser.roc for `Parser.parse \"1\"`\n(Can be given multiple times. The modules have to be in the same directory.)")
+                    .value_parser(value_parser!(PathBuf))
+                    .action(ArgAction::Append)
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_OUTPUT)
+                    .long(FLAG_OUTPUT)
+                    .help("How to print the value\n(`json` prints it as JSON, e.g. to pipe it into `jq`.)")
+                    .value_parser(["roc", "json"])
+                    .default_value("roc")
+                    .required(false),
+            )
+            .arg(
+                Arg::new(EXPR)
+                    .help("The expression to evaluate, e.g. \"1 + 2\"")
+                    .required(true),
+            )
         )
         .subcommand(Command::new(CMD_RUN)
             .about("Run a .roc file even if it has build errors")
             .arg(flag_optimize.clone())
-            .arg(flag_max_threads.clone())
+            .arg(flag_jobs.clone())
             .arg(flag_opt_size.clone())
             .arg(flag_dev.clone())
             .arg(flag_debug.clone())
             .arg(flag_time.clone())
             .arg(flag_linker.clone())
             .arg(flag_prebuilt.clone())
+            .arg(flag_platform.clone())
+            .arg(flag_list_growth.clone())
+            .arg(flag_max_memory.clone())
+            .arg(flag_stdin_file.clone())
+            .arg(flag_expect_stdout.clone())
+            .arg(flag_expect_stderr.clone())
+            .arg(flag_expect_exit_code.clone())
             .arg(roc_file_to_run.clone())
             .arg(args_for_app.clone().last(true))
         )
         .subcommand(Command::new(CMD_DEV)
             .about("`check` a .roc file, and then run it if there were no errors")
             .arg(flag_optimize.clone())
-            .arg(flag_max_threads.clone())
+            .arg(flag_jobs.clone())
             .arg(flag_opt_size.clone())
             .arg(flag_dev.clone())
             .arg(flag_debug.clone())
             .arg(flag_time.clone())
             .arg(flag_linker.clone())
             .arg(flag_prebuilt.clone())
+            .arg(flag_platform.clone())
+            .arg(flag_list_growth.clone())
+            .arg(flag_max_memory.clone())
+            .arg(flag_stdin_file.clone())
+            .arg(flag_expect_stdout.clone())
+            .arg(flag_expect_stderr.clone())
+            .arg(flag_expect_exit_code.clone())
+            .arg(flag_trace_effects)
             .arg(roc_file_to_run.clone())
             .arg(args_for_app.clone().last(true))
         )
@@ -260,13 +712,121 @@ pub fn build_app() -> Command {
                     .action(ArgAction::SetTrue)
                     .required(false),
             )
+            .arg(
+                Arg::new(FLAG_SORT_IMPORTS)
+                    .long(FLAG_SORT_IMPORTS)
+                    .help("Sort the exposes and imports lists of module headers alphabetically\n(Can also be turned on with a `sort-imports` line in a .roc-format file.)")
+                    .action(ArgAction::SetTrue)
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_GROUP_IMPORTS)
+                    .long(FLAG_GROUP_IMPORTS)
+                    .help("Put imports from packages before imports of local modules\n(Can also be turned on with a `group-imports` line in a .roc-format file.)")
+                    .action(ArgAction::SetTrue)
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_ALIGN_ANNOTATIONS)
+                    .long(FLAG_ALIGN_ANNOTATIONS)
+                    .help("Line up the colons of type annotations on consecutive lines\n(Can also be turned on with an `align-annotations` line in a .roc-format file.)")
+                    .action(ArgAction::SetTrue)
+                    .required(false),
+            )
         )
         .subcommand(Command::new(CMD_VERSION)
             .about(concatcp!("Print the Roc compiler’s version, which is currently ", VERSION)))
+        .subcommand(Command::new(CMD_LSP)
+            .about("Start a language server, which editors talk to over stdin and stdout"))
+        .subcommand(Command::new(CMD_INDEX)
+            .about("Write the definitions, references, hovers and problems of every module in a directory to a file, for code browsers")
+            .arg(
+                Arg::new(FLAG_LSIF)
+                    .long(FLAG_LSIF)
+                    .help("The file to write an LSIF dump to")
+                    .value_parser(value_parser!(PathBuf))
+                    .required(true),
+            )
+            .arg(
+                Arg::new(ROC_DIR)
+                    .help("The directory of .roc files to index")
+                    .value_parser(value_parser!(PathBuf))
+                    .required(false)
+                    .default_value("."),
+            )
+        )
+        .subcommand(Command::new(CMD_CACHE)
+            .about("Show where downloaded packages and other cached files are kept, and how much space they take up")
+            .subcommand(Command::new(CMD_CACHE_DIR)
+                .about("Print the directory that Roc keeps its cache in"))
+            .subcommand(Command::new(CMD_CACHE_CLEAN)
+                .about("Delete cached files to free up space\n(They get downloaded or rebuilt again the next time they're needed.)")
+                .arg(
+                    Arg::new(FLAG_PACKAGES)
+                        .long(FLAG_PACKAGES)
+                        .help("Only delete downloaded packages")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with_all([FLAG_BUILDS, FLAG_ALL])
+                        .required(false),
+                )
+                .arg(
+                    Arg::new(FLAG_BUILDS)
+                        .long(FLAG_BUILDS)
+                        .help("Only delete what Roc compiled, and keep downloaded packages")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with(FLAG_ALL)
+                        .required(false),
+                )
+                .arg(
+                    Arg::new(FLAG_ALL)
+                        .long(FLAG_ALL)
+                        .help("Delete everything in the cache\n(This is the default.)")
+                        .action(ArgAction::SetTrue)
+                        .required(false),
+                )
+            )
+        )
         .subcommand(Command::new(CMD_CHECK)
             .about("Check the code for problems, but don’t build or run it")
             .arg(flag_time.clone())
-            .arg(flag_max_threads.clone())
+            .arg(flag_jobs.clone())
+            .arg(
+                Arg::new(FLAG_BASELINE)
+                    .long(FLAG_BASELINE)
+                    .help("A file listing warnings to ignore, so that only new warnings are reported")
+                    .value_parser(value_parser!(PathBuf))
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_WRITE_BASELINE)
+                    .long(FLAG_WRITE_BASELINE)
+                    .help("Write every current warning to the --baseline file, replacing its contents")
+                    .action(ArgAction::SetTrue)
+                    .requires(FLAG_BASELINE)
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_DENY)
+                    .long(FLAG_DENY)
+                    .help("Turn on an extra check and report what it finds as errors\n(`dead-expect` finds expects whose condition is always true or always false, like `expect x == x`.)")
+                    .value_parser(["dead-expect"])
+                    .action(ArgAction::Append)
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_MAX_TYPE_ERRORS)
+                    .long(FLAG_MAX_TYPE_ERRORS)
+                    .help("Stop type checking a module after this many type errors, and hide errors that follow from earlier ones")
+                    .value_parser(value_parser!(usize).range(1..))
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_EMIT_TYPES)
+                    .long(FLAG_EMIT_TYPES)
+                    .help("Write the solved type of every top-level def in this package to a JSON file, e.g. to notice when its public types change")
+                    .value_parser(value_parser!(PathBuf))
+                    .required(false),
+            )
             .arg(
                 Arg::new(ROC_FILE)
                     .help("The .roc file of an app to check")
@@ -275,6 +835,56 @@ pub fn build_app() -> Command {
                     .default_value(DEFAULT_ROC_FILENAME),
             )
             )
+        .subcommand(Command::new(CMD_GRAPH)
+            .about("Print which modules a module imports, directly or indirectly, as a graph")
+            .arg(
+                Arg::new(FLAG_FORMAT)
+                    .long(FLAG_FORMAT)
+                    .help("The format to print the graph in\n(`dot` is for Graphviz.)")
+                    .value_parser(GraphFormat::NAMES)
+                    .default_value(GraphFormat::NAMES[0])
+                    .required(false),
+            )
+            .arg(
+                Arg::new(ROC_FILE)
+                    .help("The .roc file of the module to start from")
+                    .value_parser(value_parser!(PathBuf))
+                    .required(false)
+                    .default_value(DEFAULT_ROC_FILENAME),
+            )
+        )
+        .subcommand(Command::new(CMD_INSPECT)
+            .about("Show the metadata `roc build --embed-metadata` stored in an executable")
+            .arg(
+                Arg::new(BINARY_FILE)
+                    .help("The executable to inspect")
+                    .value_parser(value_parser!(PathBuf))
+                    .required(true),
+            )
+        )
+        .subcommand(Command::new(CMD_API_DIFF)
+            .about("Compare the exposed values of two versions of a package, and print which part of its version the changes call for increasing")
+            .arg(
+                Arg::new(FLAG_FORMAT)
+                    .long(FLAG_FORMAT)
+                    .help("How to print the changes\n(`json` prints an object with the version bump and the changes, for release automation.)")
+                    .value_parser(ApiDiffFormat::NAMES)
+                    .default_value(ApiDiffFormat::NAMES[0])
+                    .required(false),
+            )
+            .arg(
+                Arg::new(OLD_ROC_FILE)
+                    .help("The main .roc file of the old version of the package")
+                    .value_parser(value_parser!(PathBuf))
+                    .required(true),
+            )
+            .arg(
+                Arg::new(NEW_ROC_FILE)
+                    .help("The main .roc file of the new version of the package")
+                    .value_parser(value_parser!(PathBuf))
+                    .required(true),
+            )
+        )
         .subcommand(
             Command::new(CMD_DOCS)
                 .about("Generate documentation for a Roc package")
@@ -284,6 +894,21 @@ pub fn build_app() -> Command {
                     .required(false)
                     .default_value(DEFAULT_ROC_FILENAME),
                 )
+                .arg(
+                    Arg::new(FLAG_THEME)
+                        .long(FLAG_THEME)
+                        .help("Choose the color scheme of the generated docs\n(By default, it follows the reader's system preference.)")
+                        .value_parser(Theme::NAMES)
+                        .default_value(Theme::Auto.as_str())
+                        .required(false),
+                )
+                .arg(
+                    Arg::new(FLAG_EMBED)
+                        .long(FLAG_EMBED)
+                        .help("Only generate the documentation body, without the sidebar, header, and footer\n(Useful for including the docs in an existing static site.)")
+                        .action(ArgAction::SetTrue)
+                        .required(false),
+                )
         )
         .subcommand(Command::new(CMD_GLUE)
             .about("Generate glue code between a platform's Roc API and its host language")
@@ -300,6 +925,20 @@ pub fn build_app() -> Command {
                     .value_parser(value_parser!(PathBuf))
                     .required(true)
             )
+            .arg(
+                Arg::new(FLAG_TARGETS)
+                    .long(FLAG_TARGETS)
+                    .help("Generate glue for these comma-separated targets, e.g. x86_64-linux,aarch64-macos,wasm32\n(Defaults to every architecture, on the current operating system.)")
+                    .value_delimiter(',')
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_MANIFEST)
+                    .long(FLAG_MANIFEST)
+                    .help("Also write glue-manifest.json, which describes every entry point's symbol, argument and return layouts, and refcounting, for hosts in any language")
+                    .action(ArgAction::SetTrue)
+                    .required(false)
+            )
             .arg(
                 Arg::new(ROC_FILE)
                     .help("The .roc file whose exposed types should be translated.")
@@ -326,13 +965,20 @@ pub fn build_app() -> Command {
             )
         )
         .arg(flag_optimize)
-        .arg(flag_max_threads)
+        .arg(flag_jobs)
         .arg(flag_opt_size)
         .arg(flag_dev)
         .arg(flag_debug)
         .arg(flag_time)
         .arg(flag_linker)
         .arg(flag_prebuilt)
+        .arg(flag_platform)
+        .arg(flag_list_growth)
+        .arg(flag_max_memory)
+        .arg(flag_stdin_file)
+        .arg(flag_expect_stdout)
+        .arg(flag_expect_stderr)
+        .arg(flag_expect_exit_code)
         .arg(roc_file_to_run)
         .arg(args_for_app.trailing_var_arg(true));
 
@@ -371,36 +1017,80 @@ fn opt_level_from_flags(matches: &ArgMatches) -> OptLevel {
         matches.get_flag(FLAG_OPT_SIZE),
         matches.get_flag(FLAG_DEV),
     ) {
-        (true, false, false) => OptLevel::Optimize,
-        (false, true, false) => OptLevel::Size,
+        // `--dev` picks the dev backend (see below), which can still shrink its wasm output
+        (true, false, _) => OptLevel::Optimize,
+        (false, true, _) => OptLevel::Size,
         (false, false, true) => OptLevel::Development,
         (false, false, false) => OptLevel::Normal,
-        _ => user_error!("build can be only one of `--dev`, `--optimize`, or `--opt-size`"),
+        _ => user_error!("build can be only one of `--optimize` or `--opt-size`"),
+    }
+}
+
+/// Which part of the cache `roc cache clean` should delete, which is all of it by default
+pub fn cache_contents_from_flags(matches: &ArgMatches) -> CacheContents {
+    if matches.get_flag(FLAG_PACKAGES) {
+        CacheContents::Packages
+    } else if matches.get_flag(FLAG_BUILDS) {
+        CacheContents::Builds
+    } else {
+        CacheContents::All
+    }
+}
+
+/// How many threads `-j`/`--jobs` allows, which is all of the available cores by default
+pub fn threading_from_flags(matches: &ArgMatches) -> Threading {
+    match matches.get_one::<usize>(FLAG_JOBS) {
+        None => Threading::AllAvailable,
+        Some(0) => user_error!("cannot build with at most 0 threads"),
+        Some(1) => Threading::Single,
+        Some(n) => Threading::AtMost(*n),
     }
 }
 
 #[cfg(windows)]
 pub fn test(_matches: &ArgMatches, _triple: Triple) -> io::Result<i32> {
-    todo!("running tests does not work on windows right now")
+    // The expects report their failures through POSIX shared memory and signals, and
+    // effectful expects run in a forked process; none of that has a Windows version yet.
+    user_error!("`roc test` does not work on Windows yet.")
+}
+
+#[cfg(all(not(windows), not(feature = "llvm")))]
+pub fn test(_matches: &ArgMatches, _triple: Triple) -> io::Result<i32> {
+    user_error!("`roc test` needs LLVM, but this roc was built without the `llvm` feature.")
 }
 
-#[cfg(not(windows))]
+#[cfg(all(not(windows), feature = "llvm"))]
 pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
-    use roc_build::program::report_problems_monomorphized;
-    use roc_load::{ExecutionMode, LoadConfig, LoadMonomorphizedError};
-    use roc_packaging::cache;
-    use roc_target::TargetInfo;
+    use roc_build::memory::MemoryUsage;
+    use roc_repl_expect::frame_dump::FrameDump;
+    use roc_repl_expect::json::FailureFormat;
+    use roc_repl_expect::run::{ExpectFilter, ExpectResults};
+    use roc_repl_expect::sandbox::Sandbox;
+    use std::io::Write;
+    use watch::WatchedFiles;
+
+    if matches.get_flag(FLAG_CLEAN_SHM) {
+        return match roc_repl_expect::shm::clean_stale() {
+            Ok(removed) => {
+                println!(
+                    "Removed {} leftover shared memory buffer{}.",
+                    removed.len(),
+                    if removed.len() == 1 { "" } else { "s" }
+                );
+
+                Ok(0)
+            }
+            Err(error) if error.kind() == io::ErrorKind::Unsupported => {
+                user_error!("`roc test --clean-shm` does not work on this operating system yet.")
+            }
+            Err(error) => Err(error),
+        };
+    }
 
     let start_time = Instant::now();
-    let arena = Bump::new();
     let opt_level = opt_level_from_flags(matches);
 
-    let threading = match matches.get_one::<usize>(FLAG_MAX_THREADS) {
-        None => Threading::AllAvailable,
-        Some(0) => user_error!("cannot build with at most 0 threads"),
-        Some(1) => Threading::Single,
-        Some(n) => Threading::AtMost(*n),
-    };
+    let threading = threading_from_flags(matches);
 
     let path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
 
@@ -427,9 +1117,441 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
         process::exit(1);
     }
 
-    let arena = &arena;
-    let target = &triple;
-    let opt_level = opt_level;
+    let sandbox = match matches.get_many::<String>(FLAG_SANDBOX) {
+        Some(restrictions) => {
+            let restrictions: Vec<&String> = restrictions.collect();
+
+            Sandbox {
+                deny_network: restrictions.iter().any(|r| *r == "network"),
+                temp_dir_only: restrictions.iter().any(|r| *r == "filesystem"),
+            }
+        }
+        None => Sandbox::NONE,
+    };
+
+    let timeout = matches
+        .get_one::<u64>(FLAG_TIMEOUT_MS)
+        .filter(|millis| **millis > 0)
+        .map(|millis| std::time::Duration::from_millis(*millis));
+
+    let fx_timeout = matches
+        .get_one::<u64>(FLAG_FX_TIMEOUT)
+        .map(|secs| std::time::Duration::from_secs(*secs));
+
+    let max_memory = matches.get_one::<u64>(FLAG_MAX_MEMORY).copied();
+
+    if max_memory.is_some() && triple.architecture == Architecture::Wasm32 {
+        user_error!(
+            "`roc test --{FLAG_MAX_MEMORY}` is not supported with `--{FLAG_TARGET}=wasm32` yet."
+        )
+    }
+
+    let coverage = matches.get_flag(FLAG_COVERAGE);
+
+    if coverage && triple.architecture == Architecture::Wasm32 {
+        user_error!(
+            "`roc test --{FLAG_COVERAGE}` is not supported with `--{FLAG_TARGET}=wasm32` yet."
+        )
+    }
+
+    let frame_dump = match matches.get_one::<PathBuf>(FLAG_DUMP_FRAMES) {
+        Some(dir) => Some(FrameDump::create(dir)?),
+        None => None,
+    };
+
+    let profile = matches.get_flag(FLAG_PROFILE_EXPECTS);
+
+    let format = matches
+        .get_one::<String>(FLAG_FORMAT)
+        .and_then(|name| FailureFormat::from_name(name))
+        .unwrap();
+
+    if profile && !roc_repl_expect::profile::IS_SUPPORTED {
+        user_error!("`roc test --{FLAG_PROFILE_EXPECTS}` is not supported on this platform yet.")
+    }
+
+    let filter = ExpectFilter {
+        pattern: matches.get_one::<String>(FLAG_FILTER).cloned(),
+        module: matches.get_one::<String>(FLAG_MODULE).cloned(),
+        paths: None,
+    };
+
+    if matches.get_flag(FLAG_LIST) {
+        if !matches!(format, FailureFormat::Human | FailureFormat::Json) {
+            user_error!("`roc test --{FLAG_LIST}` only supports `--{FLAG_FORMAT}=human` and `--{FLAG_FORMAT}=json`.")
+        }
+
+        return list_expects_in_file(path, &triple, threading, &filter, format);
+    }
+
+    let shuffle_seed = match matches.get_one::<u64>(FLAG_SEED) {
+        Some(seed) => Some(*seed),
+        None if matches.get_flag(FLAG_SHUFFLE) => Some(roc_repl_expect::run::random_shuffle_seed()),
+        None => None,
+    };
+
+    if let Some(seed) = shuffle_seed.filter(|_| format.allows_notes()) {
+        println!("Running the expects in a random order, with seed {seed}.\n");
+    }
+
+    if format == FailureFormat::Tap {
+        println!("{}", roc_repl_expect::tap::VERSION_LINE);
+
+        if let Some(seed) = shuffle_seed {
+            println!("# Running the expects in a random order, with seed {seed}.");
+        }
+    }
+
+    let max_failures = match matches.get_one::<u64>(FLAG_MAX_FAILURES) {
+        Some(max_failures) => Some(*max_failures as usize),
+        None if matches.get_flag(FLAG_FAIL_FAST) => Some(1),
+        None => None,
+    };
+
+    let show_times = matches
+        .get_one::<u64>(FLAG_SHOW_TIMES)
+        .map(|count| *count as usize);
+
+    if show_times.is_some() && !format.allows_notes() {
+        user_error!("`roc test --{FLAG_SHOW_TIMES}` is not supported with `--{FLAG_FORMAT}=json` or `--{FLAG_FORMAT}=tap`.")
+    }
+
+    let value_limits = {
+        use roc_reporting::error::expect::ValueLimits;
+
+        let default = ValueLimits::default();
+        let limit = |flag, default| match matches.get_one::<u64>(flag) {
+            Some(0) => None,
+            Some(limit) => Some(*limit as usize),
+            None => default,
+        };
+
+        ValueLimits {
+            max_depth: limit(FLAG_MAX_VALUE_DEPTH, default.max_depth),
+            max_length: limit(FLAG_MAX_VALUE_LENGTH, default.max_length),
+        }
+    };
+
+    let watch = matches.get_flag(FLAG_WATCH);
+    let mut watched = WatchedFiles::new(path);
+
+    let mut writer = std::io::stdout();
+    let mut memory = MemoryUsage::default();
+
+    let results = match run_expects_in_file(
+        &mut writer,
+        path,
+        &triple,
+        opt_level,
+        threading,
+        sandbox,
+        timeout,
+        fx_timeout,
+        max_memory,
+        frame_dump.as_ref(),
+        profile,
+        coverage,
+        format,
+        &filter,
+        shuffle_seed,
+        max_failures,
+        value_limits,
+        Some(start_time),
+        Some(&mut memory),
+        Some(&mut watched),
+    ) {
+        Ok(results) => results,
+        // In watch mode, the errors get fixed while we wait.
+        Err(exit_code) if watch => {
+            exit_code?;
+            ExpectResults::default()
+        }
+        Err(exit_code) => return exit_code,
+    };
+
+    if watch {
+        if results.failed + results.passed > 0 {
+            print_test_summary(&results, start_time.elapsed(), shuffle_seed, show_times);
+        }
+
+        loop {
+            println!("Waiting for changes…\n");
+
+            let changed = watched.wait_for_changes();
+            let start_time = Instant::now();
+            let filter = ExpectFilter {
+                paths: Some(watched.affected()),
+                ..filter.clone()
+            };
+
+            match changed.as_slice() {
+                [path] => println!("{} changed.\n", path.display()),
+                _ => println!("{} files changed.\n", changed.len()),
+            }
+
+            let results = run_expects_in_file(
+                &mut writer,
+                path,
+                &triple,
+                opt_level,
+                threading,
+                sandbox,
+                timeout,
+                fx_timeout,
+                max_memory,
+                frame_dump.as_ref(),
+                profile,
+                coverage,
+                format,
+                &filter,
+                shuffle_seed,
+                max_failures,
+                value_limits,
+                Some(start_time),
+                None,
+                Some(&mut watched),
+            );
+
+            match results {
+                Ok(results) => {
+                    watched.clear_pending();
+
+                    if results.failed + results.passed > 0 {
+                        print_test_summary(
+                            &results,
+                            start_time.elapsed(),
+                            shuffle_seed,
+                            show_times,
+                        );
+                    } else {
+                        println!("The changed modules have no expectations.\n");
+                    }
+                }
+                Err(exit_code) => {
+                    exit_code?;
+                }
+            }
+        }
+    }
+
+    let mut mismatches = 0;
+
+    if matches.get_flag(FLAG_BOTH) {
+        // Every failure of the optimized run is either one we just printed, or a mismatch
+        // that gets reported below, so don't print its failures again.
+        let optimized_results = match run_expects_in_file(
+            &mut std::io::sink(),
+            path,
+            &triple,
+            OptLevel::Optimize,
+            threading,
+            sandbox,
+            timeout,
+            fx_timeout,
+            max_memory,
+            None,
+            false,
+            false,
+            FailureFormat::Human,
+            &filter,
+            shuffle_seed,
+            None,
+            value_limits,
+            None,
+            None,
+            None,
+        ) {
+            Ok(results) => results,
+            Err(exit_code) => return exit_code,
+        };
+
+        mismatches = report_optimization_mismatches(&results, &optimized_results);
+    }
+
+    let failed = results.failed;
+    let passed = results.passed;
+    let total_time = start_time.elapsed();
+
+    if matches.get_flag(FLAG_VERBOSE) {
+        memory.print_to_stdout();
+    }
+
+    if format == FailureFormat::Junit {
+        let junit_file = matches.get_one::<PathBuf>(FLAG_JUNIT_FILE).unwrap();
+        let mut file = std::fs::File::create(junit_file)
+            .map(io::BufWriter::new)
+            .unwrap_or_else(|error| {
+                user_error!("I could not create {}: {}", junit_file.display(), error)
+            });
+
+        roc_repl_expect::junit::write_report(&mut file, &results.cases, total_time)?;
+        file.flush()?;
+    }
+
+    if format == FailureFormat::Tap {
+        roc_repl_expect::tap::write_plan(
+            &mut std::io::stdout(),
+            &results.cases,
+            results.filtered_out,
+            results.skipped,
+        )?;
+
+        // like below, finding no expects at all is an error
+        return Ok(match (failed, passed) {
+            (0, 0) => 2,
+            (0, _) => 0,
+            _ => 1,
+        });
+    }
+
+    if format == FailureFormat::Json {
+        if let Some(coverage) = &results.coverage {
+            roc_repl_expect::json::write_coverage(&mut std::io::stdout(), coverage)?;
+        }
+
+        roc_repl_expect::json::write_summary(
+            &mut std::io::stdout(),
+            failed,
+            passed,
+            results.skipped,
+            total_time.as_millis(),
+            shuffle_seed,
+        )?;
+
+        // like below, finding no expects at all is an error
+        return Ok(match (failed, passed) {
+            (0, 0) => 2,
+            (0, _) => 0,
+            _ => 1,
+        });
+    }
+
+    if failed == 0 && passed == 0 {
+        // TODO print this in a more nicely formatted way!
+        if results.filtered_out > 0 {
+            println!(
+                "None of the {} expectations matched the filter.",
+                results.filtered_out
+            );
+        } else {
+            println!("No expectations were found.");
+        }
+
+        // If no tests ran, treat that as an error. This is perhaps
+        // briefly annoying at the very beginning of a project when
+        // you actually have zero tests, but it can save you from
+        // having a change to your CI script accidentally stop
+        // running tests altogether!
+        Ok(2)
+    } else {
+        print_test_summary(&results, total_time, shuffle_seed, show_times);
+
+        if let Some(coverage) = &results.coverage {
+            println!(
+                "{}",
+                coverage.render(roc_reporting::report::RenderTarget::ColorTerminal)
+            );
+        }
+
+        if let Some(frame_dump) = frame_dump.filter(|dump| dump.frames_written() > 0) {
+            println!(
+                "Wrote {} expect frames to {}\n",
+                frame_dump.frames_written(),
+                frame_dump.dir().display()
+            );
+        }
+
+        Ok((failed > 0 || mismatches > 0) as i32)
+    }
+}
+
+/// Prints how many expects failed, passed, and were left out, and how long that took. If some
+/// failed after they were shuffled, this also says how to run them in the same order again, and
+/// with `show_times`, it lists that many of the slowest expects.
+#[cfg(all(not(windows), feature = "llvm"))]
+fn print_test_summary(
+    results: &roc_repl_expect::run::ExpectResults,
+    total_time: Duration,
+    shuffle_seed: Option<u64>,
+    show_times: Option<usize>,
+) {
+    let failed = results.failed;
+    let passed = results.passed;
+
+    let failed_color = if failed == 0 {
+        32 // green
+    } else {
+        31 // red
+    };
+
+    let mut left_out = Vec::new();
+
+    if results.filtered_out > 0 {
+        left_out.push(format!("{} filtered out", results.filtered_out));
+    }
+
+    if results.skipped > 0 {
+        left_out.push(format!(
+            "\x1B[33m{}\x1B[39m skipped after {failed} failed",
+            results.skipped
+        ));
+    }
+
+    let left_out = match left_out.is_empty() {
+        true => String::new(),
+        false => format!(" ({})", left_out.join(", ")),
+    };
+
+    println!(
+        "\n\x1B[{failed_color}m{failed}\x1B[39m failed and \x1B[32m{passed}\x1B[39m passed in {} ms{left_out}.\n",
+        total_time.as_millis(),
+    );
+
+    if let Some(seed) = shuffle_seed.filter(|_| failed > 0) {
+        println!("The expects ran in a random order. To run them in the same order again, use `roc test --{FLAG_SEED}={seed}`.\n");
+    }
+
+    if let Some(count) = show_times {
+        let _ =
+            roc_repl_expect::times::write_slowest(&mut std::io::stdout(), &results.times, count);
+    }
+}
+
+/// Compiles the program at `path` and runs its top-level expects, writing failures to `writer`.
+/// Warnings are only reported if `start_time` is given and failures are reported for people,
+/// so that JSON output isn't mixed with anything else; on errors, this returns the exit code.
+/// If `memory` is given, the memory used by the end of each phase is recorded in it, and if
+/// `watched` is given, it's updated to watch the files that were loaded.
+#[cfg(all(not(windows), feature = "llvm"))]
+#[allow(clippy::too_many_arguments)]
+fn run_expects_in_file<W: std::io::Write>(
+    writer: &mut W,
+    path: &Path,
+    target: &Triple,
+    opt_level: OptLevel,
+    threading: Threading,
+    sandbox: roc_repl_expect::sandbox::Sandbox,
+    timeout: Option<std::time::Duration>,
+    fx_timeout: Option<std::time::Duration>,
+    max_memory: Option<u64>,
+    frame_dump: Option<&roc_repl_expect::frame_dump::FrameDump>,
+    profile: bool,
+    coverage: bool,
+    format: roc_repl_expect::json::FailureFormat,
+    filter: &roc_repl_expect::run::ExpectFilter,
+    shuffle_seed: Option<u64>,
+    max_failures: Option<usize>,
+    value_limits: roc_reporting::error::expect::ValueLimits,
+    start_time: Option<Instant>,
+    mut memory: Option<&mut roc_build::memory::MemoryUsage>,
+    watched: Option<&mut watch::WatchedFiles>,
+) -> Result<roc_repl_expect::run::ExpectResults, io::Result<i32>> {
+    use roc_build::program::report_problems_monomorphized;
+    use roc_load::{ExecutionMode, LoadConfig, LoadMonomorphizedError};
+    use roc_packaging::cache;
+    use roc_target::TargetInfo;
+
+    let arena = &Bump::new();
     let target_info = TargetInfo::from(target);
 
     // Step 1: compile the app and generate the .o file
@@ -440,6 +1562,9 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
         palette: roc_reporting::report::DEFAULT_PALETTE,
         threading,
         exec_mode: ExecutionMode::Test,
+        platform: None,
+        max_type_errors: None,
+        keep_module_types: false,
     };
     let load_result = roc_load::load_and_monomorphize(
         arena,
@@ -451,83 +1576,245 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
     let mut loaded = match load_result {
         Ok(loaded) => loaded,
         Err(LoadMonomorphizedError::LoadingProblem(problem)) => {
-            return handle_loading_problem(problem);
+            return Err(handle_loading_problem(problem));
         }
         Err(LoadMonomorphizedError::ErrorModule(module)) => {
-            return handle_error_module(module, start_time.elapsed(), path.as_os_str(), false);
+            if let Some(watched) = watched {
+                watched.update(&module.sources, &module.import_graph.imports);
+            }
+
+            let total_time = start_time.map(|start| start.elapsed()).unwrap_or_default();
+
+            return Err(handle_error_module(
+                module,
+                total_time,
+                path.as_os_str(),
+                false,
+            ));
         }
     };
-    let problems = report_problems_monomorphized(&mut loaded);
+
+    if let Some(start_time) = start_time.filter(|_| format.allows_notes()) {
+        let problems = report_problems_monomorphized(&mut loaded);
+
+        // Print warnings before running tests.
+        debug_assert_eq!(
+            problems.errors, 0,
+            "if there were errors, we would have already exited."
+        );
+        if problems.warnings > 0 {
+            problems.print_to_stdout(start_time.elapsed());
+            println!(".\n\nRunning tests…\n\n\x1B[36m{}\x1B[39m", "─".repeat(80));
+        }
+    }
+
+    if let Some(memory) = memory.as_deref_mut() {
+        memory.record("Load, type check, and specialize", arena);
+    }
+
+    if let Some(watched) = watched {
+        watched.update(&loaded.sources, &loaded.imports);
+    }
 
     let mut expectations = std::mem::take(&mut loaded.expectations);
 
     let interns = loaded.interns.clone();
 
-    let (lib, expects, layout_interner) = roc_repl_expect::run::expect_mono_module_to_dylib(
+    if target.architecture == Architecture::Wasm32 {
+        let (wasm_bytes, mut expects, layout_interner) =
+            roc_repl_expect::run::expect_mono_module_to_wasm(arena, loaded, opt_level, filter)
+                .map_err(Err)?;
+
+        if let Some(seed) = shuffle_seed {
+            expects.shuffle(seed);
+        }
+
+        expects.max_failures = max_failures;
+        expects.value_limits = value_limits;
+
+        if let Some(memory) = memory.as_deref_mut() {
+            memory.record("Generate code", arena);
+        }
+
+        let run_arena = &bumpalo::Bump::new();
+        let interns = run_arena.alloc(interns);
+
+        let results = roc_repl_expect::wasm::run_toplevel_expects_wasm(
+            writer,
+            roc_reporting::report::RenderTarget::ColorTerminal,
+            run_arena,
+            interns,
+            &layout_interner.into_global(),
+            &wasm_bytes,
+            &mut expectations,
+            expects,
+            frame_dump,
+            format,
+        )
+        .map_err(Err)?;
+
+        if let Some(memory) = memory {
+            memory.record("Run expects", arena);
+        }
+
+        return Ok(results);
+    }
+
+    let (lib, mut expects, layout_interner) = roc_repl_expect::run::expect_mono_module_to_dylib(
         arena,
         target.clone(),
         loaded,
         opt_level,
         LlvmBackendMode::CliTest,
+        profile,
+        coverage,
+        max_memory,
+        filter,
     )
     .unwrap();
 
-    // Print warnings before running tests.
-    {
-        debug_assert_eq!(
-            problems.errors, 0,
-            "if there were errors, we would have already exited."
-        );
-        if problems.warnings > 0 {
-            problems.print_to_stdout(start_time.elapsed());
-            println!(".\n\nRunning tests…\n\n\x1B[36m{}\x1B[39m", "─".repeat(80));
-        }
+    if let Some(seed) = shuffle_seed {
+        expects.shuffle(seed);
     }
 
-    // Run the tests.
-    let arena = &bumpalo::Bump::new();
-    let interns = arena.alloc(interns);
+    expects.max_failures = max_failures;
+    expects.value_limits = value_limits;
 
-    let mut writer = std::io::stdout();
+    if let Some(memory) = memory.as_deref_mut() {
+        memory.record("Generate code", arena);
+    }
 
-    let (failed, passed) = roc_repl_expect::run::run_toplevel_expects(
-        &mut writer,
+    // Run the tests.
+    let run_arena = &bumpalo::Bump::new();
+    let interns = run_arena.alloc(interns);
+
+    let results = roc_repl_expect::run::run_toplevel_expects(
+        writer,
         roc_reporting::report::RenderTarget::ColorTerminal,
-        arena,
+        run_arena,
         interns,
         &layout_interner.into_global(),
         &lib,
         &mut expectations,
         expects,
+        sandbox,
+        timeout,
+        fx_timeout,
+        frame_dump,
+        format,
     )
-    .unwrap();
+    .map_err(Err)?;
 
-    let total_time = start_time.elapsed();
+    if let Some(memory) = memory {
+        memory.record("Run expects", arena);
+    }
 
-    if failed == 0 && passed == 0 {
-        // TODO print this in a more nicely formatted way!
-        println!("No expectations were found.");
+    Ok(results)
+}
+
+/// Compiles the program at `path` as far as `roc test` does before generating code, and prints
+/// its expects instead of running them.
+#[cfg(all(not(windows), feature = "llvm"))]
+fn list_expects_in_file(
+    path: &Path,
+    target: &Triple,
+    threading: Threading,
+    filter: &roc_repl_expect::run::ExpectFilter,
+    format: roc_repl_expect::json::FailureFormat,
+) -> io::Result<i32> {
+    use roc_load::{ExecutionMode, LoadConfig, LoadMonomorphizedError};
+    use roc_packaging::cache;
+    use roc_repl_expect::json::FailureFormat;
+    use roc_repl_expect::list;
+    use roc_target::TargetInfo;
+
+    let arena = &Bump::new();
+    let start_time = Instant::now();
+    let load_config = LoadConfig {
+        target_info: TargetInfo::from(target),
+        render: roc_reporting::report::RenderTarget::ColorTerminal,
+        palette: roc_reporting::report::DEFAULT_PALETTE,
+        threading,
+        exec_mode: ExecutionMode::Test,
+        platform: None,
+        max_type_errors: None,
+        keep_module_types: false,
+    };
+    let load_result = roc_load::load_and_monomorphize(
+        arena,
+        path.to_path_buf(),
+        RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
+        load_config,
+    );
+
+    let loaded = match load_result {
+        Ok(loaded) => loaded,
+        Err(LoadMonomorphizedError::LoadingProblem(problem)) => {
+            return handle_loading_problem(problem);
+        }
+        Err(LoadMonomorphizedError::ErrorModule(module)) => {
+            return handle_error_module(module, start_time.elapsed(), path.as_os_str(), false);
+        }
+    };
+
+    let listed = list::list_expects(&loaded, filter);
+    let mut stdout = std::io::stdout();
+
+    match format {
+        FailureFormat::Json => list::write_json(&mut stdout, &loaded, &listed)?,
+        _ => list::write_human(&mut stdout, &loaded, &listed)?,
+    }
+
+    Ok(0)
+}
+
+/// Prints every expect that failed in only one of the two runs, and returns how many there were.
+#[cfg(all(not(windows), feature = "llvm"))]
+fn report_optimization_mismatches(
+    unoptimized: &roc_repl_expect::run::ExpectResults,
+    optimized: &roc_repl_expect::run::ExpectResults,
+) -> usize {
+    use roc_region::all::LineInfo;
 
-        // If no tests ran, treat that as an error. This is perhaps
-        // briefly annoying at the very beginning of a project when
-        // you actually have zero tests, but it can save you from
-        // having a change to your CI script accidentally stop
-        // running tests altogether!
-        Ok(2)
-    } else {
-        let failed_color = if failed == 0 {
-            32 // green
-        } else {
-            31 // red
-        };
+    let only_unoptimized = unoptimized
+        .failures
+        .iter()
+        .filter(|failure| !optimized.failures.contains(failure))
+        .map(|failure| (failure, "without optimizations"));
+    let only_optimized = optimized
+        .failures
+        .iter()
+        .filter(|failure| !unoptimized.failures.contains(failure))
+        .map(|failure| (failure, "with optimizations"));
+    let mismatches: Vec<_> = only_unoptimized.chain(only_optimized).collect();
 
+    if !mismatches.is_empty() {
         println!(
-            "\n\x1B[{failed_color}m{failed}\x1B[39m failed and \x1B[32m{passed}\x1B[39m passed in {} ms.\n",
-            total_time.as_millis(),
+            "\n\x1B[31m{}\x1B[39m {} behaved differently with and without optimizations:\n",
+            mismatches.len(),
+            if mismatches.len() == 1 {
+                "expect"
+            } else {
+                "expects"
+            },
         );
 
-        Ok((failed > 0) as i32)
+        for ((path, region), failed_when) in mismatches.iter() {
+            let line = match std::fs::read_to_string(path) {
+                Ok(src) => LineInfo::new(&src).convert_region(*region).start.line + 1,
+                Err(_) => 0,
+            };
+
+            println!(
+                "    {}:{} only failed {}",
+                path.display(),
+                line,
+                failed_when
+            );
+        }
     }
+
+    mismatches.len()
 }
 
 /// Find the element of `options` with the smallest edit distance to
@@ -548,11 +1835,14 @@ pub fn build(
     roc_cache_dir: RocCacheDir<'_>,
     link_type: LinkType,
 ) -> io::Result<i32> {
-    use roc_build::program::build_file;
+    use roc_build::program::{build_file, build_files};
     use BuildConfig::*;
 
-    let path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
-    {
+    // only `roc build` takes more than one file
+    let paths: Vec<&PathBuf> = matches.get_many::<PathBuf>(ROC_FILE).unwrap().collect();
+    let path = paths[0];
+
+    for path in paths.iter() {
         // Spawn the root task
         if !path.exists() {
             let current_dir = env::current_dir().unwrap();
@@ -600,49 +1890,53 @@ pub fn build(
 
             process::exit(1);
         }
+    }
+
+    if config == BuildConfig::BuildOnly && matches.contains_id(FLAG_BUNDLE) {
+        if paths.len() > 1 {
+            user_error!("--{} can only bundle one package at a time.", FLAG_BUNDLE);
+        }
 
-        if config == BuildConfig::BuildOnly && matches.contains_id(FLAG_BUNDLE) {
-            let start_time = Instant::now();
+        let start_time = Instant::now();
 
-            let compression =
-                Compression::try_from(matches.get_one::<String>(FLAG_BUNDLE).unwrap().as_str())
-                    .unwrap();
-
-            // Print a note of advice. This is mainly here because brotli takes so long but produces
-            // such smaller output files; the idea is to encourage people to wait for brotli,
-            // so that downloads go faster. The compression only happens once, but the network
-            // transfer and decompression will happen many more times!
-            match compression {
-                Compression::Brotli => {
-                    println!("Compressing with Brotli at maximum quality level…\n\n(Note: Brotli compression can take awhile! Using --{FLAG_BUNDLE} .tar.gz takes less time, but usually produces a significantly larger output file. Brotli is generally worth the up-front wait if this is a file people will be downloading!)\n");
-                }
-                Compression::Gzip => {
-                    println!("Compressing with gzip at minimum quality…\n\n(Note: Gzip usually runs faster than Brotli but typically produces significantly larger output files. Consider using --{FLAG_BUNDLE} .tar.br if this is a file people will be downloading!)\n");
-                }
-                Compression::Uncompressed => {
-                    println!("Building .tar archive without compression…\n\n(Note: Compression takes more time to run but typically produces much smaller output files. Consider using --{FLAG_BUNDLE} .tar.br if this is a file people will be downloading!)\n");
-                }
+        let compression =
+            Compression::try_from(matches.get_one::<String>(FLAG_BUNDLE).unwrap().as_str())
+                .unwrap();
+
+        // Print a note of advice. This is mainly here because brotli takes so long but produces
+        // such smaller output files; the idea is to encourage people to wait for brotli,
+        // so that downloads go faster. The compression only happens once, but the network
+        // transfer and decompression will happen many more times!
+        match compression {
+            Compression::Brotli => {
+                println!("Compressing with Brotli at maximum quality level…\n\n(Note: Brotli compression can take awhile! Using --{FLAG_BUNDLE} .tar.gz takes less time, but usually produces a significantly larger output file. Brotli is generally worth the up-front wait if this is a file people will be downloading!)\n");
             }
+            Compression::Gzip => {
+                println!("Compressing with gzip at minimum quality…\n\n(Note: Gzip usually runs faster than Brotli but typically produces significantly larger output files. Consider using --{FLAG_BUNDLE} .tar.br if this is a file people will be downloading!)\n");
+            }
+            Compression::Uncompressed => {
+                println!("Building .tar archive without compression…\n\n(Note: Compression takes more time to run but typically produces much smaller output files. Consider using --{FLAG_BUNDLE} .tar.br if this is a file people will be downloading!)\n");
+            }
+        }
 
-            // Rather than building an executable or library, we're building
-            // a tarball so this code can be distributed via a HTTPS
-            let filename = roc_packaging::tarball::build(path, compression)?;
-            let total_time_ms = start_time.elapsed().as_millis();
-            let total_time = if total_time_ms > 1000 {
-                format!("{}s {}ms", total_time_ms / 1000, total_time_ms % 1000)
-            } else {
-                format!("{total_time_ms} ms")
-            };
-            let created_path = path.with_file_name(&filename);
+        // Rather than building an executable or library, we're building
+        // a tarball so this code can be distributed via a HTTPS
+        let filename = roc_packaging::tarball::build(path, compression)?;
+        let total_time_ms = start_time.elapsed().as_millis();
+        let total_time = if total_time_ms > 1000 {
+            format!("{}s {}ms", total_time_ms / 1000, total_time_ms % 1000)
+        } else {
+            format!("{total_time_ms} ms")
+        };
+        let created_path = path.with_file_name(&filename);
 
-            println!(
-                "\nBundled \x1B[33m{}\x1B[39m and its dependent files into the following archive in {total_time}:\n\n\t\x1B[33m{}\x1B[39m\n\nTo distribute this archive as a package, upload this to some URL and then add it as a dependency with:\n\n\t\x1B[32m\"https://your-url-goes-here/{filename}\"\x1B[39m\n",
-                path.to_string_lossy(),
-                created_path.to_string_lossy()
-            );
+        println!(
+            "\nBundled \x1B[33m{}\x1B[39m and its dependent files into the following archive in {total_time}:\n\n\t\x1B[33m{}\x1B[39m\n\nTo distribute this archive as a package, upload this to some URL and then add it as a dependency with:\n\n\t\x1B[32m\"https://your-url-goes-here/{filename}\"\x1B[39m\n",
+            path.to_string_lossy(),
+            created_path.to_string_lossy()
+        );
 
-            return Ok(0);
-        }
+        return Ok(0);
     }
 
     // the process will end after this function,
@@ -664,30 +1958,118 @@ pub fn build(
             CodeGenBackend::Assembly(AssemblyBackendMode::Binary)
         }
     } else {
-        let backend_mode = match opt_level {
-            OptLevel::Development => LlvmBackendMode::BinaryDev,
-            OptLevel::Normal | OptLevel::Size | OptLevel::Optimize => LlvmBackendMode::Binary,
-        };
-
-        CodeGenBackend::Llvm(backend_mode)
+        llvm_backend(opt_level)
     };
 
-    let emit_debug_info = matches.get_flag(FLAG_DEBUG);
+    if matches!(triple.architecture, Architecture::Riscv64(_)) && matches.get_flag(FLAG_DEV) {
+        user_error!("--{} can't be used for riscv64, because the dev backends only generate code for x86_64, aarch64 and wasm32.", FLAG_DEV);
+    }
+
+    if triple.operating_system == OperatingSystem::None_ {
+        if matches.get_flag(FLAG_DEV) {
+            user_error!("--{} can't be used for bare-metal targets, because the dev backends only generate code for x86_64, aarch64 and wasm32.", FLAG_DEV);
+        }
+
+        // there is no OS whose libraries we could link against; the host's own toolchain
+        // (and linker script) turns the object file into firmware
+        if link_type != LinkType::None {
+            user_error!("Roc can't link programs for bare-metal targets. Use --{} to get an object file, and link it into your host with its toolchain.", FLAG_NO_LINK);
+        }
+    }
+
+    let debug: Vec<&String> = matches
+        .get_many::<String>(FLAG_DEBUG)
+        .map_or_else(Vec::new, |debug| debug.collect());
+    let emit_debug_info = debug.iter().any(|d| *d == "info");
+    let dump_specializations = debug.iter().any(|d| *d == "specializations");
+    let dump_layouts = debug.iter().any(|d| *d == "layouts");
     let emit_timings = matches.get_flag(FLAG_TIME);
 
-    let threading = match matches.get_one::<usize>(FLAG_MAX_THREADS) {
-        None => Threading::AllAvailable,
-        Some(0) => user_error!("cannot build with at most 0 threads"),
-        Some(1) => Threading::Single,
-        Some(n) => Threading::AtMost(*n),
+    // only `roc dev` has this flag
+    let trace_effects = matches
+        .try_get_one::<bool>(FLAG_TRACE_EFFECTS)
+        .ok()
+        .flatten()
+        .copied()
+        .unwrap_or(false);
+
+    // only `roc build` has this flag
+    let hardening = matches
+        .try_get_one::<String>(FLAG_HARDENING)
+        .ok()
+        .flatten()
+        .map_or(true, |hardening| hardening == "on");
+
+    // only `roc build` has this flag
+    let sanitizers = match matches.try_get_many::<String>(FLAG_SANITIZE).ok().flatten() {
+        Some(sanitizers) => {
+            let sanitizers: Vec<&String> = sanitizers.collect();
+
+            Sanitizers {
+                address: sanitizers.iter().any(|s| *s == "address"),
+                undefined: sanitizers.iter().any(|s| *s == "undefined"),
+            }
+        }
+        None => Sanitizers::default(),
     };
 
+    if !sanitizers.is_empty() {
+        let native_linux = triple == Triple::host()
+            && triple.operating_system == OperatingSystem::Linux
+            && matches!(
+                triple.architecture,
+                Architecture::X86_64 | Architecture::Aarch64(_)
+            );
+
+        if !native_linux {
+            user_error!("--{} is only supported when building for the current machine on x86_64 or aarch64 Linux.", FLAG_SANITIZE);
+        }
+
+        if matches!(code_gen_backend, CodeGenBackend::Assembly(_)) {
+            user_error!("--{} can't be used with --{}, because only the LLVM backend can instrument Roc code.", FLAG_SANITIZE, FLAG_DEV);
+        }
+
+        if link_type != LinkType::Executable {
+            user_error!(
+                "--{} can only be used when building an executable.",
+                FLAG_SANITIZE
+            );
+        }
+    }
+
+    // only `roc build` has this flag
+    let embed_metadata = matches
+        .try_get_one::<bool>(FLAG_EMBED_METADATA)
+        .ok()
+        .flatten()
+        .copied()
+        .unwrap_or(false);
+
+    let list_growth_percent = matches
+        .try_get_one::<u32>(FLAG_LIST_GROWTH)
+        .ok()
+        .flatten()
+        .copied();
+
+    if list_growth_percent.is_some()
+        && matches!(
+            code_gen_backend,
+            CodeGenBackend::Assembly(_) | CodeGenBackend::Wasm
+        )
+    {
+        user_error!("--{} can't be used with --{}, because the dev backends link the builtins as they were compiled.", FLAG_LIST_GROWTH, FLAG_DEV);
+    }
+
+    let threading = threading_from_flags(matches);
+
     let wasm_dev_backend = matches!(code_gen_backend, CodeGenBackend::Wasm);
 
     let linking_strategy = if wasm_dev_backend {
         LinkingStrategy::Additive
     } else if !roc_linker::supported(link_type, &triple)
         || matches.get_one::<String>(FLAG_LINKER).map(|s| s.as_str()) == Some("legacy")
+        // the surgical linker can't add the sanitizer runtimes
+        || !sanitizers.is_empty()
     {
         LinkingStrategy::Legacy
     } else {
@@ -721,9 +2103,89 @@ pub fn build(
         backend: code_gen_backend,
         opt_level,
         emit_debug_info,
+        trace_effects,
+        hardening,
+        sanitizers,
+        dump_specializations,
+        dump_layouts,
+        list_growth_percent,
     };
 
-    let load_config = standard_load_config(&triple, build_ordering, threading);
+    let mut load_config = standard_load_config(&triple, build_ordering, threading);
+
+    // only `roc build`, `roc run` and `roc dev` have this flag
+    load_config.platform = matches
+        .try_get_one::<String>(FLAG_PLATFORM)
+        .ok()
+        .flatten()
+        .map(|platform| {
+            if platform.starts_with("https://") {
+                platform.to_owned()
+            } else {
+                // Packages are looked up relative to the app, but a path on the command line
+                // should be relative to where `roc` was run from.
+                env::current_dir()
+                    .unwrap()
+                    .join(platform)
+                    .to_string_lossy()
+                    .into_owned()
+            }
+        });
+
+    if paths.len() > 1 {
+        let paths: Vec<PathBuf> = paths.into_iter().cloned().collect();
+
+        let results = build_files(
+            &arena,
+            &triple,
+            &paths,
+            code_gen_options,
+            emit_timings,
+            link_type,
+            linking_strategy,
+            prebuilt,
+            wasm_dev_stack_bytes,
+            roc_cache_dir,
+            load_config,
+        );
+
+        let mut exit_code = 0;
+
+        for (path, result) in paths.iter().zip(results) {
+            let code = match result {
+                Ok(built) => {
+                    if embed_metadata {
+                        embed_build_metadata(matches, &built.binary_path, &built.host_path);
+                    }
+
+                    report_built_file(
+                        matches,
+                        &built.binary_path,
+                        &built.problems,
+                        built.total_time,
+                        &built.stats,
+                    )
+                }
+                Err(BuildFileError::ErrorModule { module, total_time }) => {
+                    handle_error_module(module, total_time, path.as_os_str(), true)?
+                }
+                Err(BuildFileError::LoadingProblem(problem)) => handle_loading_problem(problem)?,
+            };
+
+            exit_code = exit_code.max(code);
+        }
+
+        return Ok(exit_code);
+    }
+
+    let scripted_io = ScriptedIo::from_matches(matches);
+
+    // `roc build` does not have this flag
+    let max_memory = matches
+        .try_get_one::<u64>(FLAG_MAX_MEMORY)
+        .ok()
+        .flatten()
+        .copied();
 
     let res_binary_path = build_file(
         &arena,
@@ -742,28 +2204,25 @@ pub fn build(
     match res_binary_path {
         Ok(BuiltFile {
             binary_path,
+            host_path,
             problems,
             total_time,
             expect_metadata,
+            stats,
         }) => {
             match config {
                 BuildOnly => {
-                    // If possible, report the generated executable name relative to the current dir.
-                    let generated_filename = binary_path
-                        .strip_prefix(env::current_dir().unwrap())
-                        .unwrap_or(&binary_path)
-                        .to_str()
-                        .unwrap();
-
-                    // No need to waste time freeing this memory,
-                    // since the process is about to exit anyway.
-                    // std::mem::forget(arena);
-
-                    problems.print_to_stdout(total_time);
-                    println!(" while successfully building:\n\n    {generated_filename}");
+                    if embed_metadata {
+                        embed_build_metadata(matches, &binary_path, &host_path);
+                    }
 
-                    // Return a nonzero exit code if there were problems
-                    Ok(problems.exit_code())
+                    Ok(report_built_file(
+                        matches,
+                        &binary_path,
+                        &problems,
+                        total_time,
+                        &stats,
+                    ))
                 }
                 BuildAndRun => {
                     if problems.fatally_errored {
@@ -793,7 +2252,16 @@ pub fn build(
                     // ManuallyDrop will leak the bytes because we don't drop manually
                     let bytes = &ManuallyDrop::new(std::fs::read(&binary_path).unwrap());
 
-                    roc_run(&arena, opt_level, triple, args, bytes, expect_metadata)
+                    roc_run(
+                        &arena,
+                        opt_level,
+                        triple,
+                        args,
+                        bytes,
+                        expect_metadata,
+                        &scripted_io,
+                        max_memory,
+                    )
                 }
                 BuildAndRunIfNoErrors => {
                     if problems.fatally_errored {
@@ -828,7 +2296,16 @@ pub fn build(
                     // ManuallyDrop will leak the bytes because we don't drop manually
                     let bytes = &ManuallyDrop::new(std::fs::read(&binary_path).unwrap());
 
-                    roc_run(&arena, opt_level, triple, args, bytes, expect_metadata)
+                    roc_run(
+                        &arena,
+                        opt_level,
+                        triple,
+                        args,
+                        bytes,
+                        expect_metadata,
+                        &scripted_io,
+                        max_memory,
+                    )
                 }
             }
         }
@@ -839,6 +2316,188 @@ pub fn build(
     }
 }
 
+/// Stores the metadata `roc inspect` shows in an executable `roc build` made.
+fn embed_build_metadata(matches: &ArgMatches, binary_path: &Path, host_path: &Path) {
+    use roc_build::metadata::{embed, hash_host, BuildMetadata};
+
+    let platform_hash = hash_host(host_path).unwrap_or_else(|error| {
+        user_error!(
+            "I could not read the platform's host at {} to hash it: {}",
+            host_path.display(),
+            error
+        )
+    });
+
+    let metadata = BuildMetadata {
+        app_name: binary_path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned(),
+        app_version: matches.get_one::<String>(FLAG_APP_VERSION).cloned(),
+        platform: host_path
+            .parent()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default(),
+        platform_hash,
+        compiler_version: VERSION.trim().to_string(),
+    };
+
+    if let Err(error) = embed(binary_path, &metadata) {
+        user_error!(
+            "I could not store the build metadata in {}: {}",
+            binary_path.display(),
+            error
+        )
+    }
+}
+
+/// Prints the metadata `roc build --embed-metadata` stored in an executable.
+pub fn inspect(binary_path: &Path) -> io::Result<i32> {
+    let binary = std::fs::read(binary_path).unwrap_or_else(|error| {
+        user_error!("I could not read {}: {}", binary_path.display(), error)
+    });
+
+    match roc_build::metadata::read(&binary) {
+        Ok(Some(metadata)) => {
+            for (key, value) in metadata.fields() {
+                println!("{key}: {value}");
+            }
+
+            Ok(0)
+        }
+        Ok(None) => {
+            eprintln!(
+                "{} has no build metadata.\n\nTo store it, build with `roc {CMD_BUILD} --{FLAG_EMBED_METADATA}`.",
+                binary_path.display()
+            );
+
+            Ok(1)
+        }
+        Err(error) => {
+            eprintln!(
+                "I could not read the build metadata of {}: {}",
+                binary_path.display(),
+                error
+            );
+
+            Ok(1)
+        }
+    }
+}
+
+/// Prints what `roc build` made, and returns a nonzero exit code if there were problems.
+fn report_built_file(
+    matches: &ArgMatches,
+    binary_path: &Path,
+    problems: &roc_reporting::cli::Problems,
+    total_time: Duration,
+    stats: &BuildStats,
+) -> i32 {
+    // If possible, report the generated executable name relative to the current dir.
+    let generated_filename = binary_path
+        .strip_prefix(env::current_dir().unwrap())
+        .unwrap_or(binary_path)
+        .to_str()
+        .unwrap();
+
+    problems.print_to_stdout(total_time);
+    println!(" while successfully building:\n\n    {generated_filename}");
+
+    if matches.get_flag(FLAG_VERBOSE) {
+        stats.print_to_stdout();
+    }
+
+    problems.exit_code()
+}
+
+#[cfg(feature = "llvm")]
+fn llvm_backend(opt_level: OptLevel) -> CodeGenBackend {
+    let backend_mode = match opt_level {
+        OptLevel::Development => LlvmBackendMode::BinaryDev,
+        OptLevel::Normal | OptLevel::Size | OptLevel::Optimize => LlvmBackendMode::Binary,
+    };
+
+    CodeGenBackend::Llvm(backend_mode)
+}
+
+#[cfg(not(feature = "llvm"))]
+fn llvm_backend(_opt_level: OptLevel) -> CodeGenBackend {
+    user_error!("This roc was built without the `llvm` feature, so it can only build with `--dev`.")
+}
+
+/// Parses a number of bytes like `1048576`, `64K`, `512M`, or `2GiB`, where the units are
+/// powers of 1024.
+fn parse_byte_size(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let digits_end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (digits, unit) = text.split_at(digits_end);
+
+    let number: u64 = digits
+        .parse()
+        .map_err(|_| format!("`{text}` is not a size like 512M or 2G"))?;
+
+    let multiplier: u64 = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1024,
+        "M" | "MB" | "MIB" => 1024 * 1024,
+        "G" | "GB" | "GIB" => 1024 * 1024 * 1024,
+        _ => return Err(format!("`{unit}` is not a unit I know; use K, M, or G")),
+    };
+
+    match number.checked_mul(multiplier) {
+        Some(0) => Err("the limit must be more than 0 bytes".to_string()),
+        Some(bytes) => Ok(bytes),
+        None => Err(format!("`{text}` is too big")),
+    }
+}
+
+/// Files and an exit code used to script a `roc run`, so a platform's CI can check an
+/// app end-to-end without a separate test harness, e.g.
+///
+/// `roc run app.roc --stdin-file in.txt --expect-stdout out.txt`
+#[derive(Debug, Default)]
+pub struct ScriptedIo {
+    pub stdin_file: Option<PathBuf>,
+    pub expected_stdout: Option<PathBuf>,
+    pub expected_stderr: Option<PathBuf>,
+    pub expected_exit_code: Option<i32>,
+}
+
+impl ScriptedIo {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        // `roc build` does not define these flags, so use `try_get_one`
+        let path = |flag| matches.try_get_one::<PathBuf>(flag).ok().flatten().cloned();
+
+        Self {
+            stdin_file: path(FLAG_STDIN_FILE),
+            expected_stdout: path(FLAG_EXPECT_STDOUT),
+            expected_stderr: path(FLAG_EXPECT_STDERR),
+            expected_exit_code: matches
+                .try_get_one::<i32>(FLAG_EXPECT_EXIT_CODE)
+                .ok()
+                .flatten()
+                .copied(),
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.stdin_file.is_some()
+            || self.expected_stdout.is_some()
+            || self.expected_stderr.is_some()
+            || self.expected_exit_code.is_some()
+    }
+
+    fn has_checks(&self) -> bool {
+        self.expected_stdout.is_some()
+            || self.expected_stderr.is_some()
+            || self.expected_exit_code.is_some()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn roc_run<'a, I: IntoIterator<Item = &'a OsStr>>(
     arena: &Bump,
     opt_level: OptLevel,
@@ -846,7 +2505,17 @@ fn roc_run<'a, I: IntoIterator<Item = &'a OsStr>>(
     args: I,
     binary_bytes: &[u8],
     expect_metadata: ExpectMetadata,
+    scripted_io: &ScriptedIo,
+    max_memory: Option<u64>,
 ) -> io::Result<i32> {
+    if scripted_io.is_active() || max_memory.is_some() {
+        if matches!(triple.architecture, Architecture::Wasm32) {
+            user_error!("`--{FLAG_STDIN_FILE}`, `--{FLAG_MAX_MEMORY}`, and the `--expect-*` flags are not supported when running wasm");
+        }
+
+        return roc_run_scripted(args, binary_bytes, scripted_io, max_memory);
+    }
+
     match triple.architecture {
         Architecture::Wasm32 => {
             let executable = roc_run_executable_file_path(binary_bytes)?;
@@ -884,6 +2553,102 @@ fn roc_run<'a, I: IntoIterator<Item = &'a OsStr>>(
     }
 }
 
+/// Run the program as a child process rather than replacing the current one,
+/// so that its output and exit code can be checked against the expected values,
+/// and so that it can be limited to `max_memory` bytes.
+fn roc_run_scripted<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(
+    args: I,
+    binary_bytes: &[u8],
+    scripted_io: &ScriptedIo,
+    max_memory: Option<u64>,
+) -> io::Result<i32> {
+    use std::process::{Command, Stdio};
+
+    let read_expected = |path: &Path| match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) => user_error!("Could not read {}: {}", path.display(), e),
+    };
+
+    let expected_stdout = scripted_io.expected_stdout.as_deref().map(read_expected);
+    let expected_stderr = scripted_io.expected_stderr.as_deref().map(read_expected);
+
+    let stdin = match &scripted_io.stdin_file {
+        Some(path) => match std::fs::File::open(path) {
+            Ok(file) => Stdio::from(file),
+            Err(e) => user_error!("Could not open {}: {}", path.display(), e),
+        },
+        None => Stdio::inherit(),
+    };
+
+    // Only capture the streams we need to compare; the rest go straight to the terminal.
+    let piped_if = |capture: bool| {
+        if capture {
+            Stdio::piped()
+        } else {
+            Stdio::inherit()
+        }
+    };
+
+    let executable = roc_run_executable_file_path(binary_bytes)?;
+    let mut command = Command::new(executable.as_path());
+
+    command
+        .args(args)
+        .stdin(stdin)
+        .stdout(piped_if(expected_stdout.is_some()))
+        .stderr(piped_if(expected_stderr.is_some()));
+
+    if let Some(max_bytes) = max_memory {
+        limit_memory(&mut command, max_bytes);
+    }
+
+    let output = command.output()?;
+
+    if let Some(max_bytes) = max_memory {
+        report_memory_limit(output.status, max_bytes);
+    }
+
+    if !scripted_io.has_checks() {
+        return Ok(output.status.code().unwrap_or(1));
+    }
+
+    let mut mismatches = 0;
+
+    let mut check_stream = |name: &str, expected: Option<Vec<u8>>, actual: &[u8]| {
+        if let Some(expected) = expected {
+            if expected != actual {
+                mismatches += 1;
+
+                eprintln!(
+                    "\n\x1B[31m{name} did not match.\x1B[39m\n\nExpected:\n\n{}\n\nActual:\n\n{}\n",
+                    String::from_utf8_lossy(&expected),
+                    String::from_utf8_lossy(actual),
+                );
+            }
+        }
+    };
+
+    check_stream("stdout", expected_stdout, &output.stdout);
+    check_stream("stderr", expected_stderr, &output.stderr);
+
+    if let Some(expected_code) = scripted_io.expected_exit_code {
+        if output.status.code() != Some(expected_code) {
+            mismatches += 1;
+
+            match output.status.code() {
+                Some(code) => eprintln!(
+                    "\n\x1B[31mThe program exited with code {code}, but {expected_code} was expected.\x1B[39m\n"
+                ),
+                None => eprintln!(
+                    "\n\x1B[31mThe program was terminated by a signal, but exit code {expected_code} was expected.\x1B[39m\n"
+                ),
+            }
+        }
+    }
+
+    Ok((mismatches > 0) as i32)
+}
+
 #[cfg(target_family = "unix")]
 fn os_str_as_utf8_bytes(os_str: &OsStr) -> &[u8] {
     use std::os::unix::ffi::OsStrExt;
@@ -940,6 +2705,51 @@ fn make_argv_envp<'a, I: IntoIterator<Item = S>, S: AsRef<OsStr>>(
     (argv_cstrings, envp_cstrings)
 }
 
+/// Make the program's allocations fail once its address space is `max_bytes` big.
+#[cfg(target_os = "linux")]
+fn limit_memory(command: &mut std::process::Command, max_bytes: u64) {
+    use std::os::unix::process::CommandExt;
+
+    let mut limit: libc::rlimit = unsafe { std::mem::zeroed() };
+
+    if unsafe { libc::getrlimit(libc::RLIMIT_AS, &mut limit) } != 0 {
+        internal_error!("libc::getrlimit failed: {:?}", errno::errno());
+    }
+
+    // The soft limit can't be above the hard one.
+    limit.rlim_cur = (max_bytes as libc::rlim_t).min(limit.rlim_max);
+
+    // This runs in the child, between fork and exec.
+    unsafe {
+        command.pre_exec(move || match libc::setrlimit(libc::RLIMIT_AS, &limit) {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        });
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn limit_memory(_command: &mut std::process::Command, _max_bytes: u64) {
+    user_error!("`--{FLAG_MAX_MEMORY}` is only supported on Linux for now.")
+}
+
+/// An allocation over the limit fails, which usually crashes the program with a signal, so
+/// say that this may be why.
+#[cfg(target_os = "linux")]
+fn report_memory_limit(status: std::process::ExitStatus, max_bytes: u64) {
+    use std::os::unix::process::ExitStatusExt;
+
+    if let Some(signal) = status.signal() {
+        eprintln!(
+            "\n\x1B[31mThe program was stopped by signal {signal}. It may have tried to use more than the {} of memory that --{FLAG_MAX_MEMORY} allows.\x1B[39m\n",
+            cache::format_bytes(max_bytes),
+        );
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn report_memory_limit(_status: std::process::ExitStatus, _max_bytes: u64) {}
+
 /// Run on the native OS (not on wasm)
 #[cfg(target_family = "unix")]
 fn roc_run_native<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(
@@ -1041,7 +2851,7 @@ impl ExecutableFile {
 }
 
 // with Expect
-#[cfg(target_family = "unix")]
+#[cfg(all(target_family = "unix", feature = "llvm"))]
 fn roc_dev_native(
     arena: &Bump,
     executable: ExecutableFile,
@@ -1059,7 +2869,8 @@ fn roc_dev_native(
         layout_interner,
     } = expect_metadata;
 
-    // let shm_name =
+    // The platform's host opens the buffer by this name, using the id of its parent.
+    // It's removed when `memory` is dropped, which `std::process::exit` would skip.
     let shm_name = format!("/roc_expect_buffer_{}", std::process::id());
     let mut memory = ExpectMemory::create_or_reuse_mmap(&shm_name);
 
@@ -1084,6 +2895,7 @@ fn roc_dev_native(
             // Display a human-friendly error message
             println!("Error {:?}", std::io::Error::last_os_error());
 
+            drop(memory);
             std::process::exit(1)
         }
         1.. => {
@@ -1091,6 +2903,9 @@ fn roc_dev_native(
             signal_hook::flag::register(signal_hook::consts::SIGCHLD, Arc::clone(&sigchld))
                 .unwrap();
 
+            // Effects can run inside other effects, when a host calls back into Roc.
+            let mut effects_running = Vec::new();
+
             loop {
                 match memory.wait_for_child(sigchld.clone()) {
                     ChildProcessMsg::Terminate => break,
@@ -1120,15 +2935,45 @@ fn roc_dev_native(
 
                         memory.reset();
                     }
+                    ChildProcessMsg::EffectCalled => {
+                        let call = memory.effect_call();
+
+                        // The child waits for the reset before running the effect,
+                        // so start timing right before it.
+                        effects_running.push((call, Instant::now()));
+
+                        memory.reset();
+                    }
+                    ChildProcessMsg::EffectReturned => {
+                        if let Some((call, start)) = effects_running.pop() {
+                            eprintln!("[effect] {} took {:.2?}", call, start.elapsed());
+                        }
+
+                        memory.reset();
+                    }
+                    ChildProcessMsg::BufferFull => memory.grow(),
                 }
             }
 
+            drop(memory);
             std::process::exit(0)
         }
         _ => unreachable!(),
     }
 }
 
+// Rendering expect failures needs roc_repl_expect, which is only available with LLVM.
+#[cfg(all(target_family = "unix", not(feature = "llvm")))]
+fn roc_dev_native(
+    _arena: &Bump,
+    _executable: ExecutableFile,
+    _argv: bumpalo::collections::Vec<*const c_char>,
+    _envp: bumpalo::collections::Vec<*const c_char>,
+    _expect_metadata: ExpectMetadata,
+) -> ! {
+    user_error!("Running a program with expects needs LLVM, but this roc was built without the `llvm` feature. Try `--optimize`.")
+}
+
 #[cfg(target_os = "linux")]
 fn roc_run_executable_file_path(binary_bytes: &[u8]) -> std::io::Result<ExecutableFile> {
     // on linux, we use the `memfd_create` function to create an in-memory anonymous file.
@@ -1234,7 +3079,7 @@ fn roc_run_native<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(
         match opt_level {
             OptLevel::Development => {
                 // roc_run_native_debug(executable, &argv, &envp, expectations, interns)
-                internal_error!("running `expect`s does not currently work on windows")
+                user_error!("Running a program with `roc dev` does not work on Windows yet, because its `expect`s can't report failures there. Use `roc run` instead.")
             }
             OptLevel::Normal | OptLevel::Size | OptLevel::Optimize => {
                 roc_run_native_fast(executable, &argv, &envp);
@@ -1289,6 +3134,10 @@ pub enum Target {
     Windows64,
     #[strum(serialize = "wasm32")]
     Wasm32,
+    #[strum(serialize = "thumbv7em")]
+    Thumbv7em,
+    #[strum(serialize = "riscv64")]
+    Riscv64,
 }
 
 impl Target {
@@ -1325,6 +3174,20 @@ impl Target {
                 environment: Environment::Unknown,
                 binary_format: BinaryFormat::Wasm,
             },
+            Thumbv7em => Triple {
+                architecture: Architecture::Arm(ArmArchitecture::Thumbv7em),
+                vendor: Vendor::Unknown,
+                operating_system: OperatingSystem::None_,
+                environment: Environment::Eabihf,
+                binary_format: BinaryFormat::Elf,
+            },
+            Riscv64 => Triple {
+                architecture: Architecture::Riscv64(Riscv64Architecture::Riscv64gc),
+                vendor: Vendor::Unknown,
+                operating_system: OperatingSystem::Linux,
+                environment: Environment::Gnu,
+                binary_format: BinaryFormat::Elf,
+            },
         }
     }
 }
@@ -1351,6 +3214,8 @@ impl std::str::FromStr for Target {
             "linux64" => Ok(Target::Linux64),
             "windows64" => Ok(Target::Windows64),
             "wasm32" => Ok(Target::Wasm32),
+            "thumbv7em" => Ok(Target::Thumbv7em),
+            "riscv64" => Ok(Target::Riscv64),
             _ => Err(format!("Roc does not know how to compile to {}", string)),
         }
     }
@@ -1,18 +1,24 @@
 //! The `roc` binary that brings together all functionality in the Roc toolset.
+use clap::ArgMatches;
 use roc_build::link::LinkType;
-use roc_build::program::{check_file, CodeGenBackend};
+use roc_build::program::check_file;
 use roc_cli::{
-    build_app, format, test, BuildConfig, FormatMode, Target, CMD_BUILD, CMD_CHECK, CMD_DEV,
-    CMD_DOCS, CMD_EDIT, CMD_FORMAT, CMD_GEN_STUB_LIB, CMD_GLUE, CMD_REPL, CMD_RUN, CMD_TEST,
-    CMD_VERSION, DIRECTORY_OR_FILES, FLAG_CHECK, FLAG_DEV, FLAG_LIB, FLAG_NO_LINK, FLAG_TARGET,
-    FLAG_TIME, GLUE_DIR, GLUE_SPEC, ROC_FILE,
+    api_diff, build_app, cache_contents_from_flags, clean_cache, format, get_matches_with_config,
+    graph, inspect, load_format_options, print_cache_dir, print_cache_summary, test, ApiDiffFormat,
+    BuildConfig, FormatMode, GraphFormat, Target, BINARY_FILE, CMD_API_DIFF, CMD_BUILD, CMD_CACHE,
+    CMD_CACHE_CLEAN, CMD_CACHE_DIR, CMD_CHECK, CMD_DEV, CMD_DOCS, CMD_EDIT, CMD_EVAL, CMD_FORMAT,
+    CMD_GEN_STUB_LIB, CMD_GLUE, CMD_GRAPH, CMD_INDEX, CMD_INSPECT, CMD_LSP, CMD_REPL, CMD_RUN,
+    CMD_TEST, CMD_VERSION, DIRECTORY_OR_FILES, EXPR, FLAG_ALIGN_ANNOTATIONS, FLAG_BASELINE,
+    FLAG_CACHE_DIR, FLAG_CHECK, FLAG_DENY, FLAG_DEP, FLAG_EMBED, FLAG_FORMAT, FLAG_GROUP_IMPORTS,
+    FLAG_LIB, FLAG_LSIF, FLAG_MAX_MEMORY, FLAG_NO_LINK, FLAG_OUTPUT, FLAG_PLATFORM, FLAG_PRELUDE,
+    FLAG_SORT_IMPORTS, FLAG_TARGET, FLAG_THEME, FLAG_TIME, FLAG_TIMEOUT_MS, FLAG_WRITE_BASELINE,
+    NEW_ROC_FILE, OLD_ROC_FILE, ROC_DIR, ROC_FILE,
 };
-use roc_docs::generate_docs_html;
+use roc_docs::{generate_docs_html, DocsOptions, Theme};
 use roc_error_macros::user_error;
-use roc_gen_dev::AssemblyBackendMode;
-use roc_gen_llvm::llvm::build::LlvmBackendMode;
-use roc_load::{LoadingProblem, Threading};
+use roc_load::LoadingProblem;
 use roc_packaging::cache::{self, RocCacheDir};
+use roc_reporting::baseline::Baseline;
 use std::fs::{self, FileType};
 use std::io;
 use std::path::{Path, PathBuf};
@@ -37,7 +43,13 @@ fn main() -> io::Result<()> {
         .get_subcommands()
         .map(|c| c.get_name().to_owned())
         .collect();
-    let matches = app.get_matches();
+    let matches = get_matches_with_config(app);
+
+    // Everything that uses the cache finds it through roc_cache_dir(), which checks this
+    // environment variable first, so setting it here makes --cache-dir apply everywhere.
+    if let Some(cache_dir) = matches.get_one::<PathBuf>(FLAG_CACHE_DIR) {
+        std::env::set_var(cache::ROC_CACHE_DIR_VAR, cache_dir);
+    }
 
     let exit_code = match matches.subcommand() {
         None => {
@@ -74,7 +86,12 @@ fn main() -> io::Result<()> {
         }
         Some((CMD_TEST, matches)) => {
             if matches.contains_id(ROC_FILE) {
-                test(matches, Triple::host())
+                let target = matches
+                    .get_one::<String>(FLAG_TARGET)
+                    .and_then(|s| Target::from_str(s).ok())
+                    .unwrap_or_default();
+
+                test(matches, target.to_triple())
             } else {
                 eprintln!("What .roc file do you want to test? Specify it at the end of the `roc test` command.");
 
@@ -97,25 +114,7 @@ fn main() -> io::Result<()> {
                 Ok(1)
             }
         }
-        Some((CMD_GLUE, matches)) => {
-            let input_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
-            let output_path = matches.get_one::<PathBuf>(GLUE_DIR).unwrap();
-            let spec_path = matches.get_one::<PathBuf>(GLUE_SPEC).unwrap();
-
-            // have the backend supply `roc_alloc` and friends
-            let backend = match matches.get_flag(FLAG_DEV) {
-                true => CodeGenBackend::Assembly(AssemblyBackendMode::Test),
-                false => CodeGenBackend::Llvm(LlvmBackendMode::BinaryGlue),
-            };
-
-            if !output_path.exists() || output_path.is_dir() {
-                roc_glue::generate(input_path, output_path, spec_path, backend)
-            } else {
-                eprintln!("`roc glue` must be given a directory to output into, because the glue might generate multiple files.");
-
-                Ok(1)
-            }
-        }
+        Some((CMD_GLUE, matches)) => glue(matches),
         Some((CMD_GEN_STUB_LIB, matches)) => {
             let input_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
             let target = matches
@@ -154,11 +153,33 @@ fn main() -> io::Result<()> {
 
             let emit_timings = matches.get_flag(FLAG_TIME);
             let roc_file_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
-            let threading = match matches.get_one::<usize>(roc_cli::FLAG_MAX_THREADS) {
-                None => Threading::AllAvailable,
-                Some(0) => user_error!("cannot build with at most 0 threads"),
-                Some(1) => Threading::Single,
-                Some(n) => Threading::AtMost(*n),
+            let threading = roc_cli::threading_from_flags(matches);
+            let baseline_path = matches.get_one::<PathBuf>(FLAG_BASELINE);
+            let write_baseline = matches.get_flag(FLAG_WRITE_BASELINE);
+            let deny_dead_expects = matches
+                .get_many::<String>(FLAG_DENY)
+                .map_or(false, |mut lints| lints.any(|lint| lint == "dead-expect"));
+            let max_type_errors = matches
+                .get_one::<usize>(roc_cli::FLAG_MAX_TYPE_ERRORS)
+                .copied();
+            let emit_types_path = matches.get_one::<PathBuf>(roc_cli::FLAG_EMIT_TYPES);
+            let mut baseline = match baseline_path {
+                // Start from an empty baseline, so that every warning gets recorded.
+                Some(_) if write_baseline => Some(Baseline::default()),
+                Some(path) => {
+                    let contents = fs::read_to_string(path).unwrap_or_else(|err| {
+                        user_error!(
+                            "Could not read the baseline file {}: {}",
+                            path.display(),
+                            err
+                        )
+                    });
+
+                    Some(Baseline::parse(&contents).unwrap_or_else(|err| {
+                        user_error!("The baseline file {} is invalid: {}", path.display(), err)
+                    }))
+                }
+                None => None,
             };
 
             match check_file(
@@ -167,7 +188,30 @@ fn main() -> io::Result<()> {
                 emit_timings,
                 RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
                 threading,
+                baseline.as_mut(),
+                deny_dead_expects,
+                max_type_errors,
+                emit_types_path.map(|path| path.as_path()),
             ) {
+                Ok((problems, _)) if write_baseline => {
+                    let baseline = baseline.unwrap();
+                    let path = baseline_path.unwrap();
+
+                    fs::write(path, baseline.to_file_contents())?;
+
+                    println!(
+                        "Wrote {} {} to {}.",
+                        baseline.warnings_seen(),
+                        if baseline.warnings_seen() == 1 {
+                            "warning"
+                        } else {
+                            "warnings"
+                        },
+                        path.display()
+                    );
+
+                    Ok(problems.errors.min(1) as i32)
+                }
                 Ok((problems, total_time)) => {
                     println!(
                         "\x1B[{}m{}\x1B[39m {} and \x1B[{}m{}\x1B[39m {} found in {} ms.",
@@ -196,6 +240,30 @@ fn main() -> io::Result<()> {
                         total_time.as_millis(),
                     );
 
+                    if problems.baselined_warnings > 0 {
+                        println!(
+                            "({} more {} in the baseline.)",
+                            problems.baselined_warnings,
+                            if problems.baselined_warnings == 1 {
+                                "warning is"
+                            } else {
+                                "warnings are"
+                            },
+                        );
+                    }
+
+                    if problems.hidden_errors > 0 {
+                        println!(
+                            "({} follow-on {} hidden.)",
+                            problems.hidden_errors,
+                            if problems.hidden_errors == 1 {
+                                "error"
+                            } else {
+                                "errors"
+                            },
+                        );
+                    }
+
                     Ok(problems.exit_code())
                 }
 
@@ -209,7 +277,29 @@ fn main() -> io::Result<()> {
                 }
             }
         }
-        Some((CMD_REPL, _)) => Ok(roc_repl_cli::main()),
+        Some((CMD_REPL, matches)) => Ok(launch_repl(
+            matches
+                .get_one::<PathBuf>(FLAG_PRELUDE)
+                .map(PathBuf::as_path),
+            matches
+                .get_one::<PathBuf>(FLAG_PLATFORM)
+                .map(PathBuf::as_path),
+            matches.get_one::<String>(FLAG_OUTPUT).unwrap(),
+            *matches.get_one::<u64>(FLAG_TIMEOUT_MS).unwrap(),
+            matches.get_one::<u64>(FLAG_MAX_MEMORY).copied(),
+        )),
+        Some((CMD_EVAL, matches)) => {
+            let deps: Vec<PathBuf> = matches
+                .get_many::<PathBuf>(FLAG_DEP)
+                .map(|deps| deps.cloned().collect())
+                .unwrap_or_default();
+
+            Ok(eval(
+                matches.get_one::<String>(EXPR).unwrap(),
+                &deps,
+                matches.get_one::<String>(FLAG_OUTPUT).unwrap(),
+            ))
+        }
         Some((CMD_EDIT, matches)) => {
             match matches
                 .get_many::<OsString>(DIRECTORY_OR_FILES)
@@ -228,8 +318,15 @@ fn main() -> io::Result<()> {
         }
         Some((CMD_DOCS, matches)) => {
             let root_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
+            let options = DocsOptions {
+                theme: matches
+                    .get_one::<String>(FLAG_THEME)
+                    .map(|theme| Theme::from_str(theme).unwrap())
+                    .unwrap_or_default(),
+                embed: matches.get_flag(FLAG_EMBED),
+            };
 
-            generate_docs_html(root_path.to_owned());
+            generate_docs_html(root_path.to_owned(), options);
 
             Ok(0)
         }
@@ -269,7 +366,13 @@ fn main() -> io::Result<()> {
                 false => FormatMode::Format,
             };
 
-            let format_exit_code = match format(roc_files, format_mode) {
+            let mut format_options = load_format_options(&std::env::current_dir()?);
+
+            format_options.sort_imports |= matches.get_flag(FLAG_SORT_IMPORTS);
+            format_options.group_imports |= matches.get_flag(FLAG_GROUP_IMPORTS);
+            format_options.align_annotations |= matches.get_flag(FLAG_ALIGN_ANNOTATIONS);
+
+            let format_exit_code = match format(roc_files, format_mode, format_options) {
                 Ok(_) => 0,
                 Err(message) => {
                     eprintln!("{}", message);
@@ -279,6 +382,39 @@ fn main() -> io::Result<()> {
 
             Ok(format_exit_code)
         }
+        Some((CMD_LSP, _)) => Ok(roc_lang_srv::main()),
+        Some((CMD_INDEX, matches)) => Ok(roc_lang_srv::index(
+            matches.get_one::<PathBuf>(ROC_DIR).unwrap(),
+            matches.get_one::<PathBuf>(FLAG_LSIF).unwrap(),
+        )),
+        Some((CMD_GRAPH, matches)) => {
+            let roc_file_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
+            let format = matches
+                .get_one::<String>(FLAG_FORMAT)
+                .and_then(|name| GraphFormat::from_name(name))
+                .unwrap();
+
+            Ok(graph(roc_file_path.to_owned(), format))
+        }
+        Some((CMD_API_DIFF, matches)) => {
+            let format = matches
+                .get_one::<String>(FLAG_FORMAT)
+                .and_then(|name| ApiDiffFormat::from_name(name))
+                .unwrap();
+
+            Ok(api_diff(
+                matches.get_one::<PathBuf>(OLD_ROC_FILE).unwrap(),
+                matches.get_one::<PathBuf>(NEW_ROC_FILE).unwrap(),
+                format,
+            ))
+        }
+        Some((CMD_INSPECT, matches)) => inspect(matches.get_one::<PathBuf>(BINARY_FILE).unwrap()),
+        Some((CMD_CACHE, matches)) => match matches.subcommand() {
+            None => print_cache_summary(),
+            Some((CMD_CACHE_DIR, _)) => print_cache_dir(),
+            Some((CMD_CACHE_CLEAN, matches)) => clean_cache(cache_contents_from_flags(matches)),
+            _ => unreachable!(),
+        },
         Some((CMD_VERSION, _)) => {
             print!(
                 "{}",
@@ -331,6 +467,99 @@ fn roc_files_recursive<P: AsRef<Path>>(
     Ok(())
 }
 
+#[cfg(feature = "llvm")]
+fn glue(matches: &ArgMatches) -> io::Result<i32> {
+    use roc_build::program::CodeGenBackend;
+    use roc_cli::{FLAG_DEV, FLAG_MANIFEST, FLAG_TARGETS, GLUE_DIR, GLUE_SPEC};
+    use roc_gen_dev::AssemblyBackendMode;
+    use roc_gen_llvm::llvm::build::LlvmBackendMode;
+
+    let input_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
+    let output_path = matches.get_one::<PathBuf>(GLUE_DIR).unwrap();
+    let spec_path = matches.get_one::<PathBuf>(GLUE_SPEC).unwrap();
+
+    // have the backend supply `roc_alloc` and friends
+    let backend = match matches.get_flag(FLAG_DEV) {
+        true => CodeGenBackend::Assembly(AssemblyBackendMode::Test),
+        false => CodeGenBackend::Llvm(LlvmBackendMode::BinaryGlue),
+    };
+
+    let targets = match matches.get_many::<String>(FLAG_TARGETS) {
+        Some(target_strs) => target_strs
+            .map(|target| {
+                roc_glue::load::parse_target(target)
+                    .unwrap_or_else(|message| user_error!("{}", message))
+            })
+            .collect(),
+        None => roc_glue::load::host_os_targets(),
+    };
+
+    if !output_path.exists() || output_path.is_dir() {
+        roc_glue::generate(
+            input_path,
+            output_path,
+            spec_path,
+            backend,
+            &targets,
+            matches.get_flag(FLAG_MANIFEST),
+        )
+    } else {
+        eprintln!("`roc glue` must be given a directory to output into, because the glue might generate multiple files.");
+
+        Ok(1)
+    }
+}
+
+#[cfg(not(feature = "llvm"))]
+fn glue(_matches: &ArgMatches) -> io::Result<i32> {
+    user_error!("`roc glue` needs LLVM, but this roc was built without the `llvm` feature.")
+}
+
+#[cfg(feature = "llvm")]
+fn launch_repl(
+    prelude_path: Option<&Path>,
+    platform_path: Option<&Path>,
+    output: &str,
+    timeout_ms: u64,
+    max_memory: Option<u64>,
+) -> i32 {
+    use roc_repl_cli::limits::EvalLimits;
+    use roc_repl_cli::repl_state::OutputFormat;
+
+    let output_format = OutputFormat::from_name(output).unwrap();
+    let limits = EvalLimits {
+        timeout: (timeout_ms > 0).then(|| std::time::Duration::from_millis(timeout_ms)),
+        max_memory,
+    };
+
+    roc_repl_cli::main(prelude_path, platform_path, output_format, limits)
+}
+
+#[cfg(feature = "llvm")]
+fn eval(expr: &str, deps: &[PathBuf], output: &str) -> i32 {
+    use roc_repl_cli::repl_state::OutputFormat;
+
+    let output_format = OutputFormat::from_name(output).unwrap();
+
+    roc_repl_cli::eval(expr, deps, output_format)
+}
+
+#[cfg(not(feature = "llvm"))]
+fn eval(_expr: &str, _deps: &[PathBuf], _output: &str) -> i32 {
+    user_error!("`roc eval` needs LLVM, but this roc was built without the `llvm` feature.")
+}
+
+#[cfg(not(feature = "llvm"))]
+fn launch_repl(
+    _prelude_path: Option<&Path>,
+    _platform_path: Option<&Path>,
+    _output: &str,
+    _timeout_ms: u64,
+    _max_memory: Option<u64>,
+) -> i32 {
+    user_error!("`roc repl` needs LLVM, but this roc was built without the `llvm` feature.")
+}
+
 #[cfg(feature = "editor")]
 fn launch_editor(project_dir_path: Option<&Path>) -> io::Result<()> {
     roc_editor::launch(project_dir_path)
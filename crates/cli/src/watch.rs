@@ -0,0 +1,125 @@
+//! Noticing which source files changed, for `roc test --watch`.
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use roc_collections::{MutMap, MutSet};
+use roc_module::symbol::ModuleId;
+
+/// How often to check whether a watched file changed.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The source files of the last load, and which of them import which.
+#[derive(Debug, Default)]
+pub struct WatchedFiles {
+    modified: MutMap<PathBuf, Option<SystemTime>>,
+    /// The files that import each file.
+    importers: MutMap<PathBuf, Vec<PathBuf>>,
+    /// The files that changed since the last time their expects ran.
+    pending: MutSet<PathBuf>,
+}
+
+impl WatchedFiles {
+    /// Watches just `root`, until a load tells us what else it depends on.
+    pub fn new(root: &Path) -> Self {
+        let mut watched = Self::default();
+
+        watched
+            .modified
+            .insert(root.to_path_buf(), modified_time(root));
+
+        watched
+    }
+
+    /// Watches the files of a load. Builtin modules have no file on disk, and are skipped.
+    pub fn update(
+        &mut self,
+        sources: &MutMap<ModuleId, (PathBuf, Box<str>)>,
+        imports: &MutMap<ModuleId, MutSet<ModuleId>>,
+    ) {
+        let path_of = |module_id: &ModuleId| {
+            sources
+                .get(module_id)
+                .map(|(path, _)| path)
+                .filter(|path| path.is_file())
+        };
+
+        self.modified = sources
+            .values()
+            .filter(|(path, _)| path.is_file())
+            .map(|(path, _)| (path.clone(), modified_time(path)))
+            .collect();
+
+        self.importers.clear();
+
+        for (module_id, imported) in imports.iter() {
+            let importer = match path_of(module_id) {
+                Some(path) => path,
+                None => continue,
+            };
+
+            for path in imported.iter().filter_map(path_of) {
+                self.importers
+                    .entry(path.clone())
+                    .or_default()
+                    .push(importer.clone());
+            }
+        }
+    }
+
+    /// Marks the expects of every changed file as having run.
+    pub fn clear_pending(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Blocks until at least one watched file changed, and returns the changed files.
+    pub fn wait_for_changes(&mut self) -> Vec<PathBuf> {
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+
+            let mut changed = Vec::new();
+
+            for (path, modified) in self.modified.iter_mut() {
+                let now = modified_time(path);
+
+                if now != *modified {
+                    *modified = now;
+                    changed.push(path.clone());
+                }
+            }
+
+            if !changed.is_empty() {
+                self.pending.extend(changed.iter().cloned());
+
+                return changed;
+            }
+        }
+    }
+
+    /// The files whose expects have to run again: every file that changed since the last
+    /// successful run, and every file that imports one of those, directly or indirectly.
+    pub fn affected(&self) -> Vec<PathBuf> {
+        let mut affected: Vec<PathBuf> = self.pending.iter().cloned().collect();
+        let mut index = 0;
+
+        while index < affected.len() {
+            let path = affected[index].clone();
+
+            for importer in self.importers.get(&path).into_iter().flatten() {
+                if !affected.contains(importer) {
+                    affected.push(importer.clone());
+                }
+            }
+
+            index += 1;
+        }
+
+        affected
+    }
+}
+
+/// `None` if the file is gone, which also counts as a change.
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
@@ -16,7 +16,10 @@ mod cli_run {
     };
     use const_format::concatcp;
     use indoc::indoc;
-    use roc_cli::{CMD_BUILD, CMD_CHECK, CMD_DEV, CMD_FORMAT, CMD_RUN, CMD_TEST};
+    use roc_cli::{
+        CMD_BUILD, CMD_CACHE, CMD_CACHE_CLEAN, CMD_CACHE_DIR, CMD_CHECK, CMD_DEV, CMD_FORMAT,
+        CMD_GRAPH, CMD_RUN, CMD_TEST,
+    };
     use roc_test_utils::assert_multiline_str_eq;
     use serial_test::serial;
     use std::iter;
@@ -52,7 +55,13 @@ mod cli_run {
     const OPTIMIZE_FLAG: &str = concatcp!("--", roc_cli::FLAG_OPTIMIZE);
     const LINKER_FLAG: &str = concatcp!("--", roc_cli::FLAG_LINKER);
     const CHECK_FLAG: &str = concatcp!("--", roc_cli::FLAG_CHECK);
+    const GRAPH_FORMAT_FLAG: &str = concatcp!("--", roc_cli::FLAG_FORMAT);
+    const CACHE_DIR_FLAG: &str = concatcp!("--", roc_cli::FLAG_CACHE_DIR);
+    const BUILDS_FLAG: &str = concatcp!("--", roc_cli::FLAG_BUILDS);
     const PREBUILT_PLATFORM: &str = concatcp!("--", roc_cli::FLAG_PREBUILT);
+    const STDIN_FILE_FLAG: &str = concatcp!("--", roc_cli::FLAG_STDIN_FILE);
+    const EXPECT_STDOUT_FLAG: &str = concatcp!("--", roc_cli::FLAG_EXPECT_STDOUT);
+    const EXPECT_EXIT_CODE_FLAG: &str = concatcp!("--", roc_cli::FLAG_EXPECT_EXIT_CODE);
     #[allow(dead_code)]
     const TARGET_FLAG: &str = concatcp!("--", roc_cli::FLAG_TARGET);
 
@@ -191,9 +200,9 @@ mod cli_run {
             let flags = {
                 let mut vec = flags.to_vec();
 
-                // max-threads segfaults on windows right now
+                // limiting the threads segfaults on windows right now
                 if !cfg!(windows) {
-                    vec.push("--max-threads=1");
+                    vec.push("--jobs=1");
                 }
 
                 vec.into_iter()
@@ -633,6 +642,197 @@ mod cli_run {
         );
     }
 
+    #[test]
+    #[cfg_attr(windows, ignore)]
+    fn test_filter() {
+        let path = file_path_from_root("crates/cli_testing_examples/expects", "expects.roc");
+        let path = path.to_str().unwrap();
+
+        let out = run_roc([CMD_TEST, "--filter", "a == b", path], &[], &[]);
+        let stdout = strip_colors(&out.stdout);
+        assert_eq!(out.status.code(), Some(1));
+        assert!(stdout.contains("1 failed and 0 passed"));
+
+        let out = run_roc([CMD_TEST, "--filter", "a != b", path], &[], &[]);
+        let stdout = strip_colors(&out.stdout);
+        assert_eq!(out.status.code(), Some(2));
+        assert!(stdout.contains("None of the 1 expectations matched the filter."));
+
+        let out = run_roc([CMD_TEST, "--module", "expects", path], &[], &[]);
+        let stdout = strip_colors(&out.stdout);
+        assert!(stdout.contains("1 failed and 0 passed"));
+
+        let out = run_roc([CMD_TEST, "--module", "Other", path], &[], &[]);
+        assert_eq!(out.status.code(), Some(2));
+    }
+
+    #[test]
+    #[cfg_attr(windows, ignore)]
+    fn test_shuffle() {
+        let path = file_path_from_root("crates/cli_testing_examples/expects", "expects.roc");
+        let path = path.to_str().unwrap();
+
+        let out = run_roc([CMD_TEST, "--seed", "42", path], &[], &[]);
+        let stdout = strip_colors(&out.stdout);
+        assert_eq!(out.status.code(), Some(1));
+        assert!(stdout.contains("Running the expects in a random order, with seed 42."));
+        assert!(stdout.contains("roc test --seed=42"));
+
+        // the same seed gives the same order
+        let again = run_roc([CMD_TEST, "--seed", "42", path], &[], &[]);
+        assert_eq!(
+            out.stdout.split(" passed in ").next(),
+            again.stdout.split(" passed in ").next()
+        );
+
+        let out = run_roc([CMD_TEST, "--shuffle", path], &[], &[]);
+        let stdout = strip_colors(&out.stdout);
+        assert!(stdout.contains("in a random order, with seed "));
+    }
+
+    #[test]
+    #[cfg_attr(windows, ignore)]
+    fn test_show_times() {
+        let path = file_path_from_root("crates/cli_testing_examples/expects", "expects.roc");
+        let path = path.to_str().unwrap();
+
+        let out = run_roc([CMD_TEST, "--show-times=2", path], &[], &[]);
+        let stdout = strip_colors(&out.stdout);
+        let listed = stdout.split("The 2 slowest expects:\n\n").nth(1).unwrap();
+        assert_eq!(
+            listed
+                .lines()
+                .take_while(|line| line.contains(" ms  "))
+                .count(),
+            2
+        );
+        assert!(listed.contains("expects.roc:"));
+
+        let out = run_roc(
+            [CMD_TEST, "--show-times", "--format", "json", path],
+            &[],
+            &[],
+        );
+        assert_eq!(out.status.code(), Some(1));
+        assert!(out.stderr.contains("--show-times"));
+    }
+
+    #[test]
+    #[cfg_attr(windows, ignore)]
+    fn test_list() {
+        let path = file_path_from_root("crates/cli_testing_examples/expects", "expects.roc");
+        let path = path.to_str().unwrap();
+
+        // nothing runs, so nothing fails
+        let out = run_roc([CMD_TEST, "--list", path], &[], &[]);
+        assert_eq!(out.status.code(), Some(0));
+        assert!(out.stdout.contains("expects.roc:"));
+        assert!(!out.stdout.contains("EXPECT FAILED"));
+
+        let out = run_roc([CMD_TEST, "--list", "--format", "json", path], &[], &[]);
+        for line in out.stdout.lines() {
+            let record: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(record["kind"], "expect");
+        }
+    }
+
+    #[test]
+    #[cfg_attr(windows, ignore)]
+    fn test_fail_fast() {
+        // both of the expects in config.roc fail, but its roc.toml filters out the second one
+        let path = file_path_from_root(
+            "crates/cli_testing_examples/expects/project-config",
+            "config.roc",
+        );
+        let path = path.to_str().unwrap();
+
+        let out = run_roc([CMD_TEST, "--filter", "==", "--fail-fast", path], &[], &[]);
+        let stdout = strip_colors(&out.stdout);
+        assert_eq!(out.status.code(), Some(1));
+        assert!(stdout.contains("a = 1"));
+        assert!(!stdout.contains("c = 3"));
+        assert!(stdout.contains("1 skipped after 1 failed"));
+
+        let out = run_roc(
+            [CMD_TEST, "--filter", "==", "--max-failures", "2", path],
+            &[],
+            &[],
+        );
+        let stdout = strip_colors(&out.stdout);
+        assert!(stdout.contains("2 failed and 0 passed"));
+        assert!(!stdout.contains("skipped"));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_clean_shm() {
+        // a buffer that a roc process which is no longer running left behind
+        let stale = std::path::Path::new("/dev/shm/roc_expect_buffer_999999999");
+        std::fs::write(stale, []).unwrap();
+
+        let out = run_roc([CMD_TEST, "--clean-shm"], &[], &[]);
+        let stdout = strip_colors(&out.stdout);
+        assert_eq!(out.status.code(), Some(0));
+        assert!(stdout.contains("leftover shared memory buffer"));
+        assert!(!stale.exists());
+    }
+
+    #[test]
+    #[cfg_attr(windows, ignore)]
+    fn test_project_config() {
+        // project-config/roc.toml sets --filter "a == b"
+        let path = file_path_from_root(
+            "crates/cli_testing_examples/expects/project-config",
+            "config.roc",
+        );
+        let path = path.to_str().unwrap();
+
+        let out = run_roc([CMD_TEST, path], &[], &[]);
+        let stdout = strip_colors(&out.stdout);
+        assert_eq!(out.status.code(), Some(1));
+        assert!(stdout.contains("a = 1"));
+        assert!(stdout.contains("1 failed and 0 passed"));
+
+        let out = run_roc([CMD_TEST, "--filter", "c == d", path], &[], &[]);
+        let stdout = strip_colors(&out.stdout);
+        assert_eq!(out.status.code(), Some(1));
+        assert!(stdout.contains("c = 3"));
+        assert!(stdout.contains("1 failed and 0 passed"));
+    }
+
+    #[test]
+    #[cfg_attr(windows, ignore)]
+    fn test_wasm32() {
+        let path = file_path_from_root("crates/cli_testing_examples/expects", "expects.roc");
+        let path = path.to_str().unwrap();
+
+        let out = run_roc(
+            [CMD_TEST, "--target", "wasm32", "--filter", "a == b", path],
+            &[],
+            &[],
+        );
+        let stdout = strip_colors(&out.stdout);
+        assert_eq!(out.status.code(), Some(1));
+        assert!(stdout.contains("This expectation failed:"));
+        assert!(stdout.contains("a = 1"));
+        assert!(stdout.contains("b = 2"));
+        assert!(stdout.contains("1 failed and 0 passed"));
+    }
+
+    #[test]
+    #[cfg_attr(not(target_os = "linux"), ignore)]
+    fn test_timeout() {
+        let path = file_path_from_root("crates/cli_testing_examples/expects", "timeout.roc");
+        let path = path.to_str().unwrap();
+
+        let out = run_roc([CMD_TEST, "--timeout-ms", "200", path], &[], &[]);
+        let stdout = strip_colors(&out.stdout);
+        assert_eq!(out.status.code(), Some(1));
+        assert!(stdout.contains("EXPECT TIMED OUT"));
+        assert!(stdout.contains("This expectation took longer than 200 ms, so I stopped it:"));
+        assert!(stdout.contains("1 failed and 1 passed"));
+    }
+
     #[test]
     #[cfg_attr(
         windows,
@@ -696,6 +896,56 @@ mod cli_run {
         )
     }
 
+    #[test]
+    #[cfg_attr(windows, ignore)]
+    #[serial(breakout)]
+    fn build_several_apps_on_one_platform() {
+        let breakout = file_path_from_root("examples/gui/breakout", "breakout.roc");
+        let hello_gui = file_path_from_root("examples/gui/breakout", "hello-gui.roc");
+
+        let out = run_roc(
+            [
+                CMD_BUILD,
+                breakout.to_str().unwrap(),
+                hello_gui.to_str().unwrap(),
+            ],
+            &[],
+            &[],
+        );
+
+        assert!(out.status.success(), "{}", out.stderr);
+
+        // the second app links against the host the first one rebuilt
+        assert_eq!(out.stderr.matches("Rebuilding platform").count(), 1);
+        assert!(breakout.with_file_name("breakout").exists());
+        assert!(hello_gui.with_file_name("hello-gui").exists());
+    }
+
+    #[test]
+    #[cfg_attr(windows, ignore)]
+    fn build_for_bare_metal() {
+        let fibonacci =
+            file_path_from_root("crates/cli_testing_examples/algorithms", "fibonacci.roc");
+
+        let out = run_roc(
+            [
+                CMD_BUILD,
+                "--target=thumbv7em",
+                "--no-link",
+                fibonacci.to_str().unwrap(),
+            ],
+            &[],
+            &[],
+        );
+
+        assert!(out.status.success(), "{}", out.stderr);
+
+        // a 32-bit, little-endian ELF object for ARM, which a C host's toolchain can link
+        let object = std::fs::read(fibonacci.with_file_name("fibonacci.o")).unwrap();
+        assert_eq!(&object[..6], b"\x7fELF\x01\x01");
+        assert_eq!(u16::from_le_bytes([object[18], object[19]]), 40); // EM_ARM
+    }
+
     #[test]
     #[cfg_attr(windows, ignore)]
     fn quicksort() {
@@ -775,6 +1025,39 @@ mod cli_run {
         )
     }
 
+    #[test]
+    #[serial(cli_platform)]
+    #[cfg_attr(windows, ignore)]
+    fn scripted_io() {
+        let app = file_path_from_root("examples/cli", "effects.roc");
+        let stdin_file = fixture_file("scripted-io", "stdin.txt");
+        let run_scripted = |expected_stdout: &str| {
+            let expected_stdout = fixture_file("scripted-io", expected_stdout);
+
+            run_roc(
+                [
+                    CMD_RUN,
+                    STDIN_FILE_FLAG,
+                    stdin_file.to_str().unwrap(),
+                    EXPECT_STDOUT_FLAG,
+                    expected_stdout.to_str().unwrap(),
+                    EXPECT_EXIT_CODE_FLAG,
+                    "0",
+                    app.to_str().unwrap(),
+                ],
+                &[],
+                &[],
+            )
+        };
+
+        let out = run_scripted("expected-stdout.txt");
+        assert!(out.status.success(), "{}", out.stderr);
+
+        let out = run_scripted("wrong-stdout.txt");
+        assert!(!out.status.success());
+        assert!(strip_colors(&out.stderr).contains("stdout did not match."));
+    }
+
     #[test]
     #[cfg_attr(windows, ignore)]
     // tea = The Elm Architecture
@@ -1461,6 +1744,40 @@ mod cli_run {
         );
     }
 
+    #[test]
+    fn dead_expect() {
+        check_compile_error(
+            &known_bad_file("DeadExpect.roc"),
+            &["--deny", "dead-expect"],
+            indoc!(
+                r#"
+                ── DEAD EXPECT ──────────────────────────────── tests/known_bad/DeadExpect.roc ─
+
+                This expect can never fail:
+
+                7│  expect answer == answer
+                           ^^^^^^^^^^^^^^^^
+
+                Its condition is always true, no matter what the program does. This is
+                often a copy-paste mistake, like comparing a value to itself.
+
+                ── DEAD EXPECT ──────────────────────────────── tests/known_bad/DeadExpect.roc ─
+
+                This expect always fails:
+
+                9│  expect 1 + 1 == 3
+                           ^^^^^^^^^^
+
+                Its condition is always false, no matter what the program does. This
+                is often a copy-paste mistake, like comparing a value to itself.
+
+                ────────────────────────────────────────────────────────────────────────────────
+
+                2 errors and 0 warnings found in <ignored for test> ms."#
+            ),
+        );
+    }
+
     #[test]
     fn unknown_generates_with() {
         check_compile_error(
@@ -1485,6 +1802,78 @@ mod cli_run {
         );
     }
 
+    #[test]
+    #[serial(multi_dep_thunk)]
+    fn graph_shows_packages_and_builtins() {
+        let path = fixture_file("packages", "app.roc");
+
+        let out = run_roc([CMD_GRAPH, path.to_str().unwrap()], &[], &[]);
+        assert!(out.status.success());
+        assert!(out.stdout.contains("subgraph \"cluster_json\""));
+        assert!(out.stdout.contains("\"app\" -> \"json.JsonParser\";"));
+        assert!(out.stdout.contains("\"app\" -> \"Str\" [style = dashed];"));
+
+        let out = run_roc(
+            [CMD_GRAPH, GRAPH_FORMAT_FLAG, "json", path.to_str().unwrap()],
+            &[],
+            &[],
+        );
+        assert!(out.status.success());
+        assert!(out.stdout.contains("\"root\": \"app\""));
+        assert!(out.stdout.contains("\"to\": \"csv.Csv\""));
+    }
+
+    #[test]
+    fn cache_dir_and_clean() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let packages_dir = cache_dir.path().join("packages");
+        let builds_dir = cache_dir.path().join("builds");
+
+        std::fs::create_dir_all(packages_dir.join("example.com")).unwrap();
+        std::fs::write(packages_dir.join("example.com").join("main.roc"), "abc").unwrap();
+        std::fs::create_dir_all(&builds_dir).unwrap();
+        std::fs::write(builds_dir.join("app.o"), [0; 2048]).unwrap();
+
+        let env = [("ROC_CACHE_DIR", cache_dir.path().to_str().unwrap())];
+
+        let out = run_roc([CMD_CACHE, CMD_CACHE_DIR], &[], &env);
+        assert!(out.status.success());
+        assert_eq!(out.stdout.trim(), cache_dir.path().to_str().unwrap());
+
+        let out = run_roc([CMD_CACHE], &[], &env);
+        assert!(out.status.success());
+        assert!(out.stdout.contains("packages  3 bytes"));
+        assert!(out.stdout.contains("builds    2.0 KiB"));
+
+        // the flag takes precedence over the environment variable
+        let other_dir = tempfile::tempdir().unwrap();
+        let out = run_roc(
+            [
+                CMD_CACHE,
+                CMD_CACHE_DIR,
+                CACHE_DIR_FLAG,
+                other_dir.path().to_str().unwrap(),
+            ],
+            &[],
+            &env,
+        );
+        assert_eq!(out.stdout.trim(), other_dir.path().to_str().unwrap());
+
+        let out = run_roc([CMD_CACHE, CMD_CACHE_CLEAN, BUILDS_FLAG], &[], &env);
+        assert!(out.status.success());
+        assert!(out.stdout.contains("Freed 2.0 KiB"));
+        assert!(!builds_dir.exists());
+        assert!(packages_dir.exists());
+
+        let out = run_roc([CMD_CACHE, CMD_CACHE_CLEAN], &[], &env);
+        assert!(out.status.success());
+        assert!(out.stdout.contains("Freed 3 bytes"));
+        assert!(!packages_dir.exists());
+
+        let out = run_roc([CMD_CACHE, CMD_CACHE_CLEAN], &[], &env);
+        assert!(out.stdout.contains("Nothing to clean"));
+    }
+
     #[test]
     fn format_check_good() {
         check_format_check_as_expected(&fixture_file("format", "Formatted.roc"), true);
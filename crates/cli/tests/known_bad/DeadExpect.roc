@@ -0,0 +1,9 @@
+interface DeadExpect
+    exposes [answer]
+    imports []
+
+answer = 42
+
+expect answer == answer
+
+expect 1 + 1 == 3
@@ -1,7 +1,7 @@
 hosted Effect
-    exposes [Effect, after, map, always, forever, loop, putLine, putInt, getInt]
+    exposes [Effect, after, map, always, forever, loop, all, putLine, putInt, getInt]
     imports []
-    generates Effect with [after, map, always, forever, loop]
+    generates Effect with [after, map, always, forever, loop, all]
 
 putLine : Str -> Effect {}
 
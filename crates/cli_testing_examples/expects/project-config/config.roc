@@ -0,0 +1,18 @@
+app "project-config-test"
+    packages { pf: "../zig-platform/main.roc" }
+    imports []
+    provides [main] to pf
+
+expect
+    a = 1
+    b = 2
+
+    a == b
+
+expect
+    c = 3
+    d = 4
+
+    c == d
+
+main = "Program finished!\n"
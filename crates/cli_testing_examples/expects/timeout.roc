@@ -0,0 +1,17 @@
+app "timeout-test"
+    packages { pf: "zig-platform/main.roc" }
+    imports []
+    provides [main] to pf
+
+spin : U64 -> U64
+spin = \n ->
+    if n == 0 then
+        0
+    else
+        spin (n + 1)
+
+expect spin 1 == 0
+
+expect 1 + 1 == 2
+
+main = "Program finished!\n"
@@ -3,5 +3,7 @@
 // See github.com/roc-lang/roc/issues/800 for discussion of the large_enum_variant check.
 #![allow(clippy::large_enum_variant)]
 pub mod link;
+pub mod memory;
+pub mod metadata;
 pub mod program;
 pub mod target;
@@ -1,5 +1,4 @@
 use crate::target::{arch_str, target_zig_str};
-use libloading::{Error, Library};
 use roc_command_utils::{cargo, clang, rustup, zig};
 use roc_error_macros::internal_error;
 use roc_mono::ir::OptLevel;
@@ -12,6 +11,9 @@ use std::{env, fs};
 use target_lexicon::{Architecture, OperatingSystem, Triple};
 use wasi_libc_sys::{WASI_COMPILER_RT_PATH, WASI_LIBC_PATH};
 
+#[cfg(feature = "llvm")]
+use libloading::{Error, Library};
+
 pub use roc_linker::LinkType;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -24,12 +26,113 @@ pub enum LinkingStrategy {
     Additive,
 }
 
+/// Sanitizers to compile the app and its host glue with, e.g. for `roc build --sanitize=address`.
+/// Only Linux executables built with the legacy linker get the sanitizer runtimes linked in.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Sanitizers {
+    /// AddressSanitizer: out-of-bounds accesses, use-after-free and leaks
+    pub address: bool,
+    /// UndefinedBehaviorSanitizer. Roc code already checks for overflow and the like,
+    /// so this only instruments the host glue.
+    pub undefined: bool,
+}
+
+impl Sanitizers {
+    pub fn is_empty(&self) -> bool {
+        !self.address && !self.undefined
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+
+        if self.address {
+            names.push("address");
+        }
+
+        if self.undefined {
+            names.push("undefined");
+        }
+
+        names
+    }
+
+    /// Flags for compiling C host glue with clang
+    fn clang_args(&self) -> Vec<String> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+
+        vec![
+            format!("-fsanitize={}", self.names().join(",")),
+            "-fno-omit-frame-pointer".to_string(),
+            "-g".to_string(),
+        ]
+    }
+
+    /// Arguments for `ld` that link in the static sanitizer runtimes. The AddressSanitizer
+    /// runtime includes the UndefinedBehaviorSanitizer one.
+    fn runtime_link_args(&self, target: &Triple) -> Vec<String> {
+        let runtime = if self.address {
+            "asan"
+        } else if self.undefined {
+            "ubsan_standalone"
+        } else {
+            return Vec::new();
+        };
+
+        let library = clang_runtime_library(runtime, &target.architecture.to_string());
+
+        vec![
+            "--whole-archive".to_string(),
+            library.to_str().unwrap().to_string(),
+            "--no-whole-archive".to_string(),
+        ]
+    }
+}
+
+/// Find one of clang's static runtime libraries, e.g. `libclang_rt.asan-x86_64.a`.
+fn clang_runtime_library(runtime: &str, arch: &str) -> PathBuf {
+    // Older versions of clang put the architecture in the file name, newer ones in the directory.
+    let file_names = [
+        format!("libclang_rt.{}-{}.a", runtime, arch),
+        format!("libclang_rt.{}.a", runtime),
+    ];
+
+    for file_name in file_names.iter() {
+        let output = clang()
+            .arg(format!("-print-file-name={}", file_name))
+            .output();
+
+        if let Ok(output) = output {
+            let path = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+
+            // clang prints the file name unchanged when it can't find the file
+            if path.is_absolute() && path.exists() {
+                return path;
+            }
+        }
+    }
+
+    eprintln!(
+        "Couldn't find clang's {} runtime ({})!",
+        runtime,
+        file_names.join(" or ")
+    );
+    eprintln!("You may need to install compiler-rt for your version of clang.");
+    process::exit(1);
+}
+
 /// input_paths can include the host as well as the app. e.g. &["host.o", "roc_app.o"]
+///
+/// With `hardening`, Linux binaries get full RELRO and a non-executable stack. They are always
+/// position-independent. Linux executables also get the runtimes for `sanitizers` linked in.
 pub fn link(
     target: &Triple,
     output_path: PathBuf,
     input_paths: &[&str],
     link_type: LinkType,
+    hardening: bool,
+    sanitizers: Sanitizers,
 ) -> io::Result<(Child, PathBuf)> {
     match target {
         Triple {
@@ -39,7 +142,14 @@ pub fn link(
         Triple {
             operating_system: OperatingSystem::Linux,
             ..
-        } => link_linux(target, output_path, input_paths, link_type),
+        } => link_linux(
+            target,
+            output_path,
+            input_paths,
+            link_type,
+            hardening,
+            sanitizers,
+        ),
         Triple {
             operating_system: OperatingSystem::Darwin,
             ..
@@ -151,6 +261,10 @@ pub fn build_zig_host_native(
         zig_cmd.args([
             "build-exe",
             "-fPIE",
+            "-z",
+            "relro",
+            "-z",
+            "now",
             "-rdynamic", // make sure roc_alloc and friends are exposed
             shared_lib_path.to_str().unwrap(),
             builtins_host_path.to_str().unwrap(),
@@ -449,6 +563,7 @@ pub fn build_c_host_native(
                     // builtins_host_path,
                     "-fPIE",
                     "-pie",
+                    "-Wl,-z,relro,-z,now,-z,noexecstack",
                     "-lm",
                     "-lpthread",
                     "-ldl",
@@ -520,11 +635,14 @@ pub fn build_swift_host_native(
     command
 }
 
+/// With `sanitizers`, C and Zig hosts are compiled with sanitizer instrumentation. Other hosts are
+/// built as usual, with a warning.
 pub fn rebuild_host(
     opt_level: OptLevel,
     target: &Triple,
     platform_main_roc: &Path,
     shared_lib_path: Option<&Path>,
+    sanitizers: Sanitizers,
 ) -> PathBuf {
     let c_host_src = platform_main_roc.with_file_name("host.c");
     let c_host_dest = platform_main_roc.with_file_name("c_host.o");
@@ -540,6 +658,7 @@ pub fn rebuild_host(
         roc_target::OperatingSystem::Windows => "exe",
         roc_target::OperatingSystem::Unix => "",
         roc_target::OperatingSystem::Wasi => "",
+        roc_target::OperatingSystem::Freestanding => "",
     };
 
     let host_dest = if matches!(target.architecture, Architecture::Wasm32) {
@@ -565,7 +684,7 @@ pub fn rebuild_host(
 
     if zig_host_src.exists() {
         // Compile host.zig
-        let zig_cmd = match target.architecture {
+        let mut zig_cmd = match target.architecture {
             Architecture::Wasm32 => {
                 let emit_bin = if matches!(opt_level, OptLevel::Development) {
                     format!("-femit-bin={}", host_dest.to_str().unwrap())
@@ -601,7 +720,7 @@ pub fn rebuild_host(
                 shared_lib_path,
                 builtins_host_tempfile.path(),
             ),
-            Architecture::Aarch64(_) => build_zig_host_native(
+            Architecture::Aarch64(_) | Architecture::Riscv64(_) => build_zig_host_native(
                 &env_path,
                 &env_home,
                 host_dest.to_str().unwrap(),
@@ -614,9 +733,22 @@ pub fn rebuild_host(
             _ => internal_error!("Unsupported architecture {:?}", target.architecture),
         };
 
+        // Zig has no AddressSanitizer, but can sanitize undefined behavior in C code it compiles
+        if sanitizers.address {
+            eprintln!("Warning: Zig hosts can't be built with AddressSanitizer, so only the Roc code will be instrumented.");
+        }
+
+        if sanitizers.undefined {
+            zig_cmd.arg("-fsanitize-c");
+        }
+
         run_build_command(zig_cmd, "host.zig", 0);
     } else if cargo_host_src.exists() {
         // Compile and link Cargo.toml, if it exists
+        if !sanitizers.is_empty() {
+            eprintln!("Warning: Rust hosts need a nightly toolchain to use sanitizers, so only the Roc code and host.c will be instrumented.");
+        }
+
         let cargo_dir = platform_main_roc.parent().unwrap();
 
         let mut cargo_cmd = if cfg!(windows) {
@@ -669,7 +801,7 @@ pub fn rebuild_host(
         } else {
             // Cargo hosts depend on a c wrapper for the api. Compile host.c as well.
 
-            let clang_cmd = build_c_host_native(
+            let mut clang_cmd = build_c_host_native(
                 target,
                 &env_path,
                 &env_home,
@@ -680,6 +812,7 @@ pub fn rebuild_host(
                 shared_lib_path,
                 builtins_host_tempfile.path(),
             );
+            clang_cmd.args(sanitizers.clang_args());
 
             run_build_command(clang_cmd, "host.c", 0);
 
@@ -704,6 +837,10 @@ pub fn rebuild_host(
         }
     } else if rust_host_src.exists() {
         // Compile and link host.rs, if it exists
+        if !sanitizers.is_empty() {
+            eprintln!("Warning: Rust hosts need a nightly toolchain to use sanitizers, so only the Roc code and host.c will be instrumented.");
+        }
+
         let mut rustc_cmd = Command::new("rustc");
         rustc_cmd.args([
             rust_host_src.to_str().unwrap(),
@@ -721,7 +858,7 @@ pub fn rebuild_host(
         // Rust hosts depend on a c wrapper for the api. Compile host.c as well.
         if shared_lib_path.is_some() {
             // If compiling to executable, let c deal with linking as well.
-            let clang_cmd = build_c_host_native(
+            let mut clang_cmd = build_c_host_native(
                 target,
                 &env_path,
                 &env_home,
@@ -735,9 +872,10 @@ pub fn rebuild_host(
                 shared_lib_path,
                 builtins_host_tempfile.path(),
             );
+            clang_cmd.args(sanitizers.clang_args());
             run_build_command(clang_cmd, "host.c", 0);
         } else {
-            let clang_cmd = build_c_host_native(
+            let mut clang_cmd = build_c_host_native(
                 target,
                 &env_path,
                 &env_home,
@@ -748,6 +886,7 @@ pub fn rebuild_host(
                 shared_lib_path,
                 builtins_host_tempfile.path(),
             );
+            clang_cmd.args(sanitizers.clang_args());
 
             run_build_command(clang_cmd, "host.c", 0);
 
@@ -773,7 +912,7 @@ pub fn rebuild_host(
         }
     } else if c_host_src.exists() {
         // Compile host.c, if it exists
-        let clang_cmd = build_c_host_native(
+        let mut clang_cmd = build_c_host_native(
             target,
             &env_path,
             &env_home,
@@ -784,10 +923,15 @@ pub fn rebuild_host(
             shared_lib_path,
             builtins_host_tempfile.path(),
         );
+        clang_cmd.args(sanitizers.clang_args());
 
         run_build_command(clang_cmd, "host.c", 0);
     } else if swift_host_src.exists() {
         // Compile host.swift, if it exists
+        if !sanitizers.is_empty() {
+            eprintln!("Warning: Swift hosts can't be built with sanitizers yet, so only the Roc code will be instrumented.");
+        }
+
         let swiftc_cmd = build_swift_host_native(
             &env_path,
             &env_home,
@@ -926,8 +1070,14 @@ fn link_linux(
     output_path: PathBuf,
     input_paths: &[&str],
     link_type: LinkType,
+    hardening: bool,
+    sanitizers: Sanitizers,
 ) -> io::Result<(Child, PathBuf)> {
-    let architecture = format!("{}-linux-gnu", target.architecture);
+    let architecture = match target.architecture {
+        // Debian and friends name the directory after the base ISA, not after riscv64gc
+        Architecture::Riscv64(_) => "riscv64-linux-gnu".to_string(),
+        _ => format!("{}-linux-gnu", target.architecture),
+    };
 
     //    Command::new("cp")
     //        .args(&[input_paths[0], "/home/folkertdev/roc/wasm/host.o"])
@@ -1036,6 +1186,8 @@ fn link_linux(
             }
         }
         Architecture::Aarch64(_) => library_path(["/lib", "ld-linux-aarch64.so.1"]),
+        // the hard-float (lp64d) ABI that riscv64gc Linux distros use
+        Architecture::Riscv64(_) => library_path(["/lib", "ld-linux-riscv64-lp64d.so.1"]),
         _ => internal_error!(
             "TODO gracefully handle unsupported linux architecture: {:?}",
             target.architecture
@@ -1107,8 +1259,13 @@ fn link_linux(
             &*crtn_path.to_string_lossy(),
         ])
         .args(&base_args)
+        .args(if hardening { LINUX_HARDENING_ARGS } else { &[] })
         .args(["-dynamic-linker", ld_linux])
         .args(input_paths)
+        .args(match link_type {
+            LinkType::Executable => sanitizers.runtime_link_args(target),
+            _ => Vec::new(),
+        })
         // ld.lld requires this argument, and does not accept --arch
         // .args(&["-L/usr/lib/x86_64-linux-gnu"])
         .args([
@@ -1132,6 +1289,10 @@ fn link_linux(
     Ok((output, output_path))
 }
 
+/// Resolve every symbol at startup, so that the relocated data (like the GOT) can be made
+/// read-only before the program runs, and don't let the stack be executed.
+const LINUX_HARDENING_ARGS: &[&str] = &["-z", "relro", "-z", "now", "-z", "noexecstack"];
+
 fn link_macos(
     target: &Triple,
     output_path: PathBuf,
@@ -1344,11 +1505,35 @@ fn link_windows(
     }
 }
 
+#[cfg(feature = "llvm")]
 pub fn llvm_module_to_dylib(
     module: &inkwell::module::Module,
     target: &Triple,
     opt_level: OptLevel,
 ) -> Result<Library, Error> {
+    llvm_module_to_dylib_help(module, target, opt_level, |_| ()).map(|(lib, ())| lib)
+}
+
+/// Like [llvm_module_to_dylib], but also returns the contents of the dylib file, e.g. to read
+/// its symbol table. The file itself is deleted as soon as the dylib is loaded.
+#[cfg(feature = "llvm")]
+pub fn llvm_module_to_dylib_and_bytes(
+    module: &inkwell::module::Module,
+    target: &Triple,
+    opt_level: OptLevel,
+) -> Result<(Library, Vec<u8>), Error> {
+    llvm_module_to_dylib_help(module, target, opt_level, |dylib_path| {
+        fs::read(dylib_path).unwrap()
+    })
+}
+
+#[cfg(feature = "llvm")]
+fn llvm_module_to_dylib_help<T>(
+    module: &inkwell::module::Module,
+    target: &Triple,
+    opt_level: OptLevel,
+    inspect_dylib: impl FnOnce(&Path) -> T,
+) -> Result<(Library, T), Error> {
     use crate::target::{self, convert_opt_level};
     use inkwell::targets::{FileType, RelocMode};
 
@@ -1374,6 +1559,8 @@ pub fn llvm_module_to_dylib(
         app_o_file.clone(),
         &[app_o_file.to_str().unwrap()],
         LinkType::Dylib,
+        false,
+        Sanitizers::default(),
     )
     .unwrap();
 
@@ -1386,6 +1573,8 @@ pub fn llvm_module_to_dylib(
         child
     );
 
+    let inspected = inspect_dylib(&dylib_path);
+
     // Load the dylib
     let path = dylib_path.as_path().to_str().unwrap();
 
@@ -1397,14 +1586,67 @@ pub fn llvm_module_to_dylib(
         // to be okay.
         loop {
             match unsafe { Library::new(path) } {
-                Ok(lib) => return Ok(lib),
+                Ok(lib) => return Ok((lib, inspected)),
                 Err(Error::DlOpen { .. }) => continue,
                 Err(other) => return Err(other),
             }
         }
     }
 
-    unsafe { Library::new(path) }
+    unsafe { Library::new(path) }.map(|lib| (lib, inspected))
+}
+
+/// Compiles the module to the bytes of a wasm32 module, e.g. for running it in an interpreter.
+/// Functions it calls but doesn't define become imports from the `env` module. Besides what the
+/// module itself exports, the module also exports each of `exports`.
+#[cfg(feature = "llvm")]
+pub fn llvm_module_to_wasm(
+    module: &inkwell::module::Module,
+    opt_level: OptLevel,
+    exports: &[&str],
+) -> io::Result<Vec<u8>> {
+    use crate::target::{self, convert_opt_level};
+    use inkwell::targets::{FileType, RelocMode};
+
+    let dir = tempfile::tempdir()?;
+    let app_o_file = dir.path().join("app.o");
+    let app_wasm_file = dir.path().join("app.wasm");
+
+    let triple = Triple {
+        architecture: Architecture::Wasm32,
+        vendor: target_lexicon::Vendor::Unknown,
+        operating_system: OperatingSystem::Unknown,
+        environment: target_lexicon::Environment::Unknown,
+        binary_format: target_lexicon::BinaryFormat::Wasm,
+    };
+
+    let target_machine =
+        target::target_machine(&triple, convert_opt_level(opt_level), RelocMode::Default).unwrap();
+
+    target_machine
+        .write_to_file(module, FileType::Object, &app_o_file)
+        .map_err(|message| io::Error::new(io::ErrorKind::Other, message.to_string()))?;
+
+    let mut zig_cmd = zig();
+    zig_cmd.args([
+        "wasm-ld",
+        app_o_file.to_str().unwrap(),
+        WASI_LIBC_PATH,
+        WASI_COMPILER_RT_PATH, // builtins need __multi3, __udivti3, __fixdfti
+        "-o",
+        app_wasm_file.to_str().unwrap(),
+        "--export-dynamic",
+        "--allow-undefined",
+        "--no-entry",
+    ]);
+
+    for export in exports {
+        zig_cmd.arg(format!("--export={export}"));
+    }
+
+    run_build_command(zig_cmd, app_wasm_file.to_str().unwrap(), 0);
+
+    fs::read(app_wasm_file)
 }
 
 pub fn preprocess_host_wasm32(host_input_path: &Path, preprocessed_host_path: &Path) {
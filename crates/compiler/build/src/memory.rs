@@ -0,0 +1,82 @@
+//! Measures how much memory the compiler itself uses, so people who run out of memory (e.g. on
+//! small CI runners) can report numbers, and see which phase to split their modules for.
+
+use bumpalo::Bump;
+
+/// The memory used by the end of each phase of a build, in the order the phases ran.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryUsage {
+    phases: Vec<(&'static str, PhaseMemory)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PhaseMemory {
+    /// The most memory the process had resident at once, up to the end of this phase.
+    /// `None` on platforms where we can't measure it.
+    peak_rss: Option<u64>,
+    /// Bytes allocated in the main arena so far. Worker threads have arenas of their own.
+    arena_bytes: usize,
+}
+
+impl MemoryUsage {
+    pub fn record(&mut self, phase: &'static str, arena: &Bump) {
+        let memory = PhaseMemory {
+            peak_rss: peak_rss(),
+            arena_bytes: arena.allocated_bytes(),
+        };
+
+        self.phases.push((phase, memory));
+    }
+
+    pub fn print_to_stdout(&self) {
+        use std::fmt::Write;
+
+        let buf = &mut String::with_capacity(256);
+
+        for (phase, memory) in self.phases.iter() {
+            let peak_rss = match memory.peak_rss {
+                Some(bytes) => format_bytes(bytes),
+                None => "unknown".to_string(),
+            };
+
+            writeln!(
+                buf,
+                "        {:>10} peak RSS   {:>10} arena   {}",
+                peak_rss,
+                format_bytes(memory.arena_bytes as u64),
+                phase
+            )
+            .unwrap();
+        }
+
+        println!("Memory high-water mark after each phase:\n\n{}", buf);
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+/// The most memory this process has had resident at once so far, in bytes.
+#[cfg(unix)]
+fn peak_rss() -> Option<u64> {
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::uninit();
+
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()) } != 0 {
+        return None;
+    }
+
+    let max_rss = unsafe { usage.assume_init() }.ru_maxrss as u64;
+
+    // macOS reports this in bytes, everything else in kilobytes.
+    if cfg!(target_os = "macos") {
+        Some(max_rss)
+    } else {
+        Some(max_rss * 1024)
+    }
+}
+
+#[cfg(not(unix))]
+fn peak_rss() -> Option<u64> {
+    None
+}
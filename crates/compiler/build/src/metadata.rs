@@ -0,0 +1,390 @@
+//! Build metadata stored in a section of its own in a built executable, so a deployed binary can
+//! be traced back to the app, platform, and compiler it was built from, see `roc inspect`.
+//!
+//! The metadata is plain `key: value` lines, so `readelf -p .roc_metadata` shows it too. It's
+//! added after linking, in a section that isn't loaded when the program runs:
+//!
+//! - ELF gets a `.roc_metadata` section. The section data, the section names, and the section
+//!   header table are appended to the end of the file, and the ELF header is pointed at them.
+//! - wasm gets a `roc_metadata` custom section at the end of the module.
+//!
+//! Mach-O and PE executables would need room for another section that the linker didn't
+//! leave, so they're not supported yet.
+use std::io;
+use std::path::Path;
+
+/// The name of the section in ELF executables
+pub const ELF_SECTION_NAME: &str = ".roc_metadata";
+
+/// The name of the custom section in wasm modules
+pub const WASM_SECTION_NAME: &str = "roc_metadata";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildMetadata {
+    pub app_name: String,
+    /// Given with `roc build --app-version`
+    pub app_version: Option<String>,
+    /// The directory of the platform's main.roc
+    pub platform: String,
+    /// The blake3 hash of the prebuilt host that was linked into the executable
+    pub platform_hash: String,
+    pub compiler_version: String,
+}
+
+impl BuildMetadata {
+    const APP_NAME: &'static str = "app";
+    const APP_VERSION: &'static str = "app-version";
+    const PLATFORM: &'static str = "platform";
+    const PLATFORM_HASH: &'static str = "platform-hash";
+    const COMPILER_VERSION: &'static str = "compiler-version";
+
+    pub fn fields(&self) -> Vec<(&'static str, &str)> {
+        let mut fields = vec![(Self::APP_NAME, self.app_name.as_str())];
+
+        if let Some(version) = &self.app_version {
+            fields.push((Self::APP_VERSION, version.as_str()));
+        }
+
+        fields.extend([
+            (Self::PLATFORM, self.platform.as_str()),
+            (Self::PLATFORM_HASH, self.platform_hash.as_str()),
+            (Self::COMPILER_VERSION, self.compiler_version.as_str()),
+        ]);
+
+        fields
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut text = String::new();
+
+        for (key, value) in self.fields() {
+            // a value can't span several lines
+            let value = value.replace(['\r', '\n'], " ");
+
+            text.push_str(&format!("{key}: {value}\n"));
+        }
+
+        text.into_bytes()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let text = std::str::from_utf8(bytes).map_err(|_| invalid("the metadata is not UTF-8"))?;
+
+        let field = |key: &str| {
+            text.lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix(": "))
+                .map(str::to_string)
+        };
+
+        let required =
+            |key: &str| field(key).ok_or_else(|| invalid(&format!("the metadata has no `{key}`")));
+
+        Ok(Self {
+            app_name: required(Self::APP_NAME)?,
+            app_version: field(Self::APP_VERSION),
+            platform: required(Self::PLATFORM)?,
+            platform_hash: required(Self::PLATFORM_HASH)?,
+            compiler_version: required(Self::COMPILER_VERSION)?,
+        })
+    }
+}
+
+/// The hash to put in [BuildMetadata::platform_hash] for this host file.
+pub fn hash_host(host_path: &Path) -> io::Result<String> {
+    let bytes = std::fs::read(host_path)?;
+
+    Ok(format!("blake3:{}", blake3::hash(&bytes).to_hex()))
+}
+
+/// Adds the metadata to the executable at `binary_path`.
+pub fn embed(binary_path: &Path, metadata: &BuildMetadata) -> io::Result<()> {
+    let mut binary = std::fs::read(binary_path)?;
+    let data = metadata.to_bytes();
+
+    match Format::of(&binary) {
+        Some(Format::Elf(elf)) => elf.append_section(&mut binary, ELF_SECTION_NAME, &data)?,
+        Some(Format::Wasm) => append_wasm_custom_section(&mut binary, WASM_SECTION_NAME, &data),
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "only ELF and wasm executables can have build metadata for now",
+            ))
+        }
+    }
+
+    std::fs::write(binary_path, binary)
+}
+
+/// Reads back what [embed] added, or `None` if the executable has no build metadata.
+pub fn read(binary: &[u8]) -> io::Result<Option<BuildMetadata>> {
+    let data = match Format::of(binary) {
+        Some(Format::Elf(elf)) => elf.section(binary, ELF_SECTION_NAME)?,
+        Some(Format::Wasm) => wasm_custom_section(binary, WASM_SECTION_NAME)?,
+        None => return Err(invalid("this is not an ELF or wasm executable")),
+    };
+
+    data.map(BuildMetadata::from_bytes).transpose()
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+enum Format {
+    Elf(Elf),
+    Wasm,
+}
+
+impl Format {
+    fn of(binary: &[u8]) -> Option<Self> {
+        if binary.starts_with(b"\x7fELF") {
+            // little endian, like every target we build for
+            match (binary.get(4), binary.get(5)) {
+                (Some(1), Some(1)) => Some(Format::Elf(Elf::ELF32)),
+                (Some(2), Some(1)) => Some(Format::Elf(Elf::ELF64)),
+                _ => None,
+            }
+        } else if binary.starts_with(b"\0asm") {
+            Some(Format::Wasm)
+        } else {
+            None
+        }
+    }
+}
+
+/// Where things are in the ELF header and in a section header, for 32 or 64 bits.
+struct Elf {
+    word: usize,
+    shoff: usize,
+    shentsize: usize,
+    shnum: usize,
+    shstrndx: usize,
+    // offsets within a section header
+    sh_offset: usize,
+    sh_size: usize,
+}
+
+impl Elf {
+    const ELF32: Self = Self {
+        word: 4,
+        shoff: 0x20,
+        shentsize: 0x2E,
+        shnum: 0x30,
+        shstrndx: 0x32,
+        sh_offset: 0x10,
+        sh_size: 0x14,
+    };
+
+    const ELF64: Self = Self {
+        word: 8,
+        shoff: 0x28,
+        shentsize: 0x3A,
+        shnum: 0x3C,
+        shstrndx: 0x3E,
+        sh_offset: 0x18,
+        sh_size: 0x20,
+    };
+
+    const SHT_PROGBITS: u32 = 1;
+
+    fn section_headers(&self, binary: &[u8]) -> io::Result<(usize, usize, usize, usize)> {
+        let shoff = read_uint(binary, self.shoff, self.word)? as usize;
+        let entsize = read_uint(binary, self.shentsize, 2)? as usize;
+        let shnum = read_uint(binary, self.shnum, 2)? as usize;
+        let shstrndx = read_uint(binary, self.shstrndx, 2)? as usize;
+
+        // 0 means the real numbers are in the first section header, which linkers only do
+        // for object files with a huge number of sections.
+        if shnum == 0 || shstrndx >= shnum {
+            return Err(invalid("this executable has no section names"));
+        }
+
+        Ok((shoff, entsize, shnum, shstrndx))
+    }
+
+    /// The offset and size of a section's data
+    fn section_range(&self, binary: &[u8], header: usize) -> io::Result<(usize, usize)> {
+        let offset = read_uint(binary, header + self.sh_offset, self.word)? as usize;
+        let size = read_uint(binary, header + self.sh_size, self.word)? as usize;
+
+        match offset.checked_add(size) {
+            Some(end) if end <= binary.len() => Ok((offset, size)),
+            _ => Err(invalid("a section goes past the end of the file")),
+        }
+    }
+
+    fn section<'b>(&self, binary: &'b [u8], name: &str) -> io::Result<Option<&'b [u8]>> {
+        let (shoff, entsize, shnum, shstrndx) = self.section_headers(binary)?;
+        let (names_offset, names_size) = self.section_range(binary, shoff + shstrndx * entsize)?;
+        let names = &binary[names_offset..names_offset + names_size];
+
+        for index in 0..shnum {
+            let header = shoff + index * entsize;
+            let name_offset = read_uint(binary, header, 4)? as usize;
+
+            let section_name = names
+                .get(name_offset..)
+                .and_then(|rest| rest.split(|byte| *byte == 0).next());
+
+            if section_name == Some(name.as_bytes()) {
+                let (offset, size) = self.section_range(binary, header)?;
+
+                return Ok(Some(&binary[offset..offset + size]));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn append_section(&self, binary: &mut Vec<u8>, name: &str, data: &[u8]) -> io::Result<()> {
+        if self.section(binary, name)?.is_some() {
+            return Err(invalid(&format!(
+                "this executable already has a {name} section"
+            )));
+        }
+
+        let (shoff, entsize, shnum, shstrndx) = self.section_headers(binary)?;
+        let (names_offset, names_size) = self.section_range(binary, shoff + shstrndx * entsize)?;
+
+        // The section names, with the new one at the end
+        let new_names_offset = binary.len();
+        binary.extend_from_within(names_offset..names_offset + names_size);
+        binary.extend_from_slice(name.as_bytes());
+        binary.push(0);
+        let new_names_size = binary.len() - new_names_offset;
+
+        let data_offset = binary.len();
+        binary.extend_from_slice(data);
+
+        // The section headers, with the new one at the end
+        let aligned = (binary.len() + self.word - 1) / self.word * self.word;
+        binary.resize(aligned, 0);
+        let new_shoff = binary.len();
+        binary.extend_from_within(shoff..shoff + shnum * entsize);
+
+        let names_header = new_shoff + shstrndx * entsize;
+        write_uint(
+            binary,
+            names_header + self.sh_offset,
+            self.word,
+            new_names_offset,
+        );
+        write_uint(
+            binary,
+            names_header + self.sh_size,
+            self.word,
+            new_names_size,
+        );
+
+        let header = binary.len();
+        binary.resize(header + entsize, 0);
+        write_uint(binary, header, 4, names_size);
+        write_uint(binary, header + 4, 4, Self::SHT_PROGBITS as usize);
+        write_uint(binary, header + self.sh_offset, self.word, data_offset);
+        write_uint(binary, header + self.sh_size, self.word, data.len());
+        // sh_addralign, right before sh_entsize at the end
+        write_uint(binary, header + entsize - 2 * self.word, self.word, 1);
+
+        write_uint(binary, self.shoff, self.word, new_shoff);
+        write_uint(binary, self.shnum, 2, shnum + 1);
+
+        Ok(())
+    }
+}
+
+fn read_uint(binary: &[u8], offset: usize, width: usize) -> io::Result<u64> {
+    let bytes = binary
+        .get(offset..offset + width)
+        .ok_or_else(|| invalid("the file ends in the middle of a header"))?;
+
+    Ok(bytes
+        .iter()
+        .rev()
+        .fold(0, |value, byte| (value << 8) | *byte as u64))
+}
+
+fn write_uint(binary: &mut [u8], offset: usize, width: usize, value: usize) {
+    let bytes = (value as u64).to_le_bytes();
+
+    binary[offset..offset + width].copy_from_slice(&bytes[..width]);
+}
+
+fn append_wasm_custom_section(binary: &mut Vec<u8>, name: &str, data: &[u8]) {
+    let mut contents = Vec::new();
+    write_leb128(&mut contents, name.len());
+    contents.extend_from_slice(name.as_bytes());
+    contents.extend_from_slice(data);
+
+    // custom sections have id 0
+    binary.push(0);
+    write_leb128(binary, contents.len());
+    binary.extend_from_slice(&contents);
+}
+
+fn wasm_custom_section<'b>(binary: &'b [u8], name: &str) -> io::Result<Option<&'b [u8]>> {
+    // after the magic number and the version
+    let mut offset = 8;
+
+    while offset < binary.len() {
+        let id = binary[offset];
+        let size = read_leb128(binary, &mut offset, 1)?;
+        let end = offset + size;
+
+        let contents = binary
+            .get(offset..end)
+            .ok_or_else(|| invalid("a section goes past the end of the file"))?;
+
+        if id == 0 {
+            let mut name_offset = 0;
+            let name_len = read_leb128(contents, &mut name_offset, 0)?;
+
+            if contents.get(name_offset..name_offset + name_len) == Some(name.as_bytes()) {
+                return Ok(Some(&contents[name_offset + name_len..]));
+            }
+        }
+
+        offset = end;
+    }
+
+    Ok(None)
+}
+
+fn write_leb128(bytes: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            bytes.push(byte);
+            break;
+        }
+
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Reads the number that starts `skip` bytes after `offset`, and moves `offset` past it.
+fn read_leb128(bytes: &[u8], offset: &mut usize, skip: usize) -> io::Result<usize> {
+    let mut value = 0;
+    let mut shift = 0;
+
+    *offset += skip;
+
+    loop {
+        let byte = *bytes
+            .get(*offset)
+            .ok_or_else(|| invalid("the file ends in the middle of a number"))?;
+        *offset += 1;
+
+        if shift >= usize::BITS {
+            return Err(invalid("a number is too big"));
+        }
+
+        value |= ((byte & 0x7f) as usize) << shift;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+}
@@ -1,19 +1,22 @@
 use crate::link::{
     legacy_host_filename, link, preprocess_host_wasm32, rebuild_host, LinkType, LinkingStrategy,
+    Sanitizers,
 };
+use crate::memory::MemoryUsage;
 use bumpalo::Bump;
-use inkwell::memory_buffer::MemoryBuffer;
+use roc_collections::all::MutMap;
 use roc_error_macros::internal_error;
 use roc_gen_dev::AssemblyBackendMode;
-use roc_gen_llvm::llvm::build::{module_from_builtins, LlvmBackendMode};
-use roc_gen_llvm::llvm::externs::add_default_roc_externs;
 use roc_load::{
     EntryPoint, ExecutionMode, ExpectMetadata, LoadConfig, LoadMonomorphizedError, LoadedModule,
     LoadingProblem, MonomorphizedModule, Threading,
 };
+use roc_module::symbol::Symbol;
 use roc_mono::ir::{OptLevel, SingleEntryPoint};
+use roc_mono::layout::STLayoutInterner;
 use roc_packaging::cache::RocCacheDir;
 use roc_reporting::{
+    baseline::Baseline,
     cli::{report_problems, Problems},
     report::{RenderTarget, DEFAULT_PALETTE},
 };
@@ -30,6 +33,15 @@ use target_lexicon::Triple;
 #[cfg(feature = "target-wasm32")]
 use roc_collections::all::MutSet;
 
+#[cfg(feature = "llvm")]
+use inkwell::memory_buffer::MemoryBuffer;
+#[cfg(feature = "llvm")]
+use roc_gen_llvm::llvm::build::{module_from_builtins, LlvmBackendMode, ThreadSafety};
+#[cfg(feature = "llvm")]
+use roc_gen_llvm::llvm::externs::add_default_roc_externs;
+#[cfg(feature = "llvm")]
+use roc_parse::header::PlatformThreading;
+
 pub const DEFAULT_ROC_FILENAME: &str = "main.roc";
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -46,20 +58,29 @@ pub fn report_problems_monomorphized(loaded: &mut MonomorphizedModule) -> Proble
         &loaded.interns,
         &mut loaded.can_problems,
         &mut loaded.type_problems,
+        None,
+        None,
     )
 }
 
-pub fn report_problems_typechecked(loaded: &mut LoadedModule) -> Problems {
+pub fn report_problems_typechecked(
+    loaded: &mut LoadedModule,
+    baseline: Option<&mut Baseline>,
+    max_type_errors: Option<usize>,
+) -> Problems {
     report_problems(
         loaded.total_problems(),
         &loaded.sources,
         &loaded.interns,
         &mut loaded.can_problems,
         &mut loaded.type_problems,
+        baseline,
+        max_type_errors,
     )
 }
 
 pub enum CodeObject {
+    #[cfg(feature = "llvm")]
     MemoryBuffer(MemoryBuffer),
     Vector(Vec<u8>),
 }
@@ -69,6 +90,7 @@ impl Deref for CodeObject {
 
     fn deref(&self) -> &Self::Target {
         match self {
+            #[cfg(feature = "llvm")]
             CodeObject::MemoryBuffer(memory_buffer) => memory_buffer.as_slice(),
             CodeObject::Vector(vector) => vector.as_slice(),
         }
@@ -78,6 +100,7 @@ impl Deref for CodeObject {
 #[derive(Debug, Clone, Copy)]
 pub enum CodeGenBackend {
     Assembly(AssemblyBackendMode),
+    #[cfg(feature = "llvm")]
     Llvm(LlvmBackendMode),
     Wasm,
 }
@@ -87,11 +110,25 @@ pub struct CodeGenOptions {
     pub backend: CodeGenBackend,
     pub opt_level: OptLevel,
     pub emit_debug_info: bool,
+    /// Report every effect the program runs to `roc dev`; see [LlvmBackendMode::BinaryDev].
+    pub trace_effects: bool,
+    /// Link Linux executables with full RELRO and a non-executable stack; see [link].
+    pub hardening: bool,
+    /// Instrument the Roc code and the host glue with these sanitizers; see [Sanitizers].
+    pub sanitizers: Sanitizers,
+    /// Print every specialized procedure after monomorphization; see [report_specializations].
+    pub dump_specializations: bool,
+    /// Print statistics about the interned layouts after monomorphization; see [report_layouts].
+    pub dump_layouts: bool,
+    /// How much a full list grows, as a percentage of its capacity. Only the LLVM backend can
+    /// change this, because the other backends link the builtins as they were compiled.
+    pub list_growth_percent: Option<u32>,
 }
 
 type GenFromMono<'a> = (CodeObject, CodeGenTiming, ExpectMetadata<'a>);
 
 #[allow(clippy::too_many_arguments)]
+#[cfg_attr(not(feature = "llvm"), allow(unused_variables))]
 pub fn gen_from_mono_module<'a>(
     arena: &'a bumpalo::Bump,
     loaded: MonomorphizedModule<'a>,
@@ -101,10 +138,6 @@ pub fn gen_from_mono_module<'a>(
     preprocessed_host_path: &Path,
     wasm_dev_stack_bytes: Option<u32>,
 ) -> GenFromMono<'a> {
-    let path = roc_file_path;
-    let debug = code_gen_options.emit_debug_info;
-    let opt = code_gen_options.opt_level;
-
     match code_gen_options.backend {
         CodeGenBackend::Wasm => gen_from_mono_module_dev(
             arena,
@@ -113,6 +146,7 @@ pub fn gen_from_mono_module<'a>(
             preprocessed_host_path,
             wasm_dev_stack_bytes,
             AssemblyBackendMode::Binary, // dummy value, unused in practice
+            code_gen_options.opt_level,
         ),
         CodeGenBackend::Assembly(backend_mode) => gen_from_mono_module_dev(
             arena,
@@ -121,16 +155,50 @@ pub fn gen_from_mono_module<'a>(
             preprocessed_host_path,
             wasm_dev_stack_bytes,
             backend_mode,
+            code_gen_options.opt_level,
         ),
+        #[cfg(feature = "llvm")]
         CodeGenBackend::Llvm(backend_mode) => {
-            gen_from_mono_module_llvm(arena, loaded, path, target, opt, backend_mode, debug)
+            let path = roc_file_path;
+            let debug = code_gen_options.emit_debug_info;
+            let opt = code_gen_options.opt_level;
+            let trace = code_gen_options.trace_effects;
+            let sanitizers = code_gen_options.sanitizers;
+            let list_growth = code_gen_options.list_growth_percent;
+
+            gen_from_mono_module_llvm(
+                arena,
+                loaded,
+                path,
+                target,
+                opt,
+                backend_mode,
+                debug,
+                trace,
+                sanitizers,
+                list_growth,
+            )
         }
     }
 }
 
+/// Hosts get thread-safe code, unless their platform promises to only call from one thread.
+#[cfg(feature = "llvm")]
+pub fn thread_safety(
+    backend_mode: LlvmBackendMode,
+    platform_threading: PlatformThreading,
+) -> ThreadSafety {
+    match platform_threading {
+        PlatformThreading::SingleThreaded => ThreadSafety::SingleThreaded,
+        PlatformThreading::MultiThreaded => ThreadSafety::for_mode(backend_mode),
+    }
+}
+
 // TODO how should imported modules factor into this? What if those use builtins too?
 // TODO this should probably use more helper functions
 // TODO make this polymorphic in the llvm functions so it can be reused for another backend.
+#[cfg(feature = "llvm")]
+#[allow(clippy::too_many_arguments)]
 fn gen_from_mono_module_llvm<'a>(
     arena: &'a bumpalo::Bump,
     loaded: MonomorphizedModule<'a>,
@@ -139,6 +207,9 @@ fn gen_from_mono_module_llvm<'a>(
     opt_level: OptLevel,
     backend_mode: LlvmBackendMode,
     emit_debug_info: bool,
+    trace_effects: bool,
+    sanitizers: Sanitizers,
+    list_growth_percent: Option<u32>,
 ) -> GenFromMono<'a> {
     use crate::target::{self, convert_opt_level};
     use inkwell::attributes::{Attribute, AttributeLoc};
@@ -210,6 +281,10 @@ fn gen_from_mono_module_llvm<'a>(
             .keys()
             .copied()
             .collect(),
+        trace_effects,
+        thread_safety: thread_safety(backend_mode, loaded.platform_threading),
+        list_growth_percent,
+        expect_call_sites: MutMap::default(),
     };
 
     // does not add any externs for this mode (we have a host) but cleans up some functions around
@@ -271,7 +346,8 @@ fn gen_from_mono_module_llvm<'a>(
 
     // annotate the LLVM IR output with debug info
     // so errors are reported with the line number of the LLVM source
-    let memory_buffer = if cfg!(feature = "sanitizers") && std::env::var("ROC_SANITIZERS").is_ok() {
+    let env_sanitizers = cfg!(feature = "sanitizers") && std::env::var("ROC_SANITIZERS").is_ok();
+    let memory_buffer = if sanitizers.address || env_sanitizers {
         let dir = tempfile::tempdir().unwrap();
         let dir = dir.into_path();
 
@@ -279,6 +355,18 @@ fn gen_from_mono_module_llvm<'a>(
         let app_bc_file = dir.join("app.bc");
         let app_o_file = dir.join("app.o");
 
+        // AddressSanitizer only instruments functions that ask for it
+        if sanitizers.address {
+            let kind_id = Attribute::get_named_enum_kind_id("sanitize_address");
+            let sanitize_address = context.create_enum_attribute(kind_id, 0);
+
+            for function in module.get_functions() {
+                if function.count_basic_blocks() > 0 {
+                    function.add_attribute(AttributeLoc::Function, sanitize_address);
+                }
+            }
+        }
+
         // write the ll code to a file, so we can modify it
         module.print_to_file(&app_ll_file).unwrap();
 
@@ -288,10 +376,14 @@ fn gen_from_mono_module_llvm<'a>(
         let mut passes = vec![];
         let mut extra_args = vec![];
         let mut unrecognized = vec![];
+        if sanitizers.address {
+            passes.extend_from_slice(&["asan-module", "function(asan)"]);
+        }
         for sanitizer in std::env::var("ROC_SANITIZERS")
-            .unwrap()
+            .unwrap_or_default()
             .split(',')
             .map(|x| x.trim())
+            .filter(|x| !x.is_empty())
         {
             match sanitizer {
                 "address" => passes.push("asan-module"),
@@ -422,9 +514,12 @@ fn gen_from_mono_module_llvm<'a>(
         MemoryBuffer::create_from_file(&app_o_file).expect("memory buffer creation works")
     } else {
         // Emit the .o file
-        use target_lexicon::Architecture;
+        use target_lexicon::{Architecture, OperatingSystem};
         match target.architecture {
-            Architecture::X86_64 | Architecture::X86_32(_) | Architecture::Aarch64(_) => {
+            Architecture::X86_64
+            | Architecture::X86_32(_)
+            | Architecture::Aarch64(_)
+            | Architecture::Riscv64(_) => {
                 let reloc = RelocMode::PIC;
                 let target_machine =
                     target::target_machine(target, convert_opt_level(opt_level), reloc).unwrap();
@@ -433,6 +528,17 @@ fn gen_from_mono_module_llvm<'a>(
                     .write_to_memory_buffer(env.module, FileType::Object)
                     .expect("Writing .o file failed")
             }
+            Architecture::Arm(_) if target.operating_system == OperatingSystem::None_ => {
+                // there is no loader on bare metal, so the code runs where the host's linker
+                // script puts it
+                let reloc = RelocMode::Static;
+                let target_machine =
+                    target::target_machine(target, convert_opt_level(opt_level), reloc).unwrap();
+
+                target_machine
+                    .write_to_memory_buffer(env.module, FileType::Object)
+                    .expect("Writing .o file failed")
+            }
             Architecture::Wasm32 => {
                 // Useful for debugging
                 // module.print_to_file(app_ll_file);
@@ -471,6 +577,7 @@ fn gen_from_mono_module_dev<'a>(
     preprocessed_host_path: &Path,
     wasm_dev_stack_bytes: Option<u32>,
     backend_mode: AssemblyBackendMode,
+    opt_level: OptLevel,
 ) -> GenFromMono<'a> {
     use target_lexicon::Architecture;
 
@@ -480,6 +587,7 @@ fn gen_from_mono_module_dev<'a>(
             loaded,
             preprocessed_host_path,
             wasm_dev_stack_bytes,
+            opt_level,
         ),
         Architecture::X86_64 | Architecture::Aarch64(_) => {
             gen_from_mono_module_dev_assembly(arena, loaded, target, backend_mode)
@@ -496,6 +604,7 @@ pub fn gen_from_mono_module_dev<'a>(
     _host_input_path: &Path,
     _wasm_dev_stack_bytes: Option<u32>,
     backend_mode: AssemblyBackendMode,
+    _opt_level: OptLevel,
 ) -> GenFromMono<'a> {
     use target_lexicon::Architecture;
 
@@ -513,6 +622,7 @@ fn gen_from_mono_module_dev_wasm32<'a>(
     loaded: MonomorphizedModule<'a>,
     preprocessed_host_path: &Path,
     wasm_dev_stack_bytes: Option<u32>,
+    opt_level: OptLevel,
 ) -> GenFromMono<'a> {
     let all_code_gen_start = Instant::now();
     let MonomorphizedModule {
@@ -559,6 +669,7 @@ fn gen_from_mono_module_dev_wasm32<'a>(
         &mut interns,
         host_module,
         procedures,
+        matches!(opt_level, OptLevel::Optimize | OptLevel::Size),
     );
 
     let generate_final_ir = all_code_gen_start.elapsed();
@@ -606,6 +717,7 @@ fn gen_from_mono_module_dev_assembly<'a>(
         exposed_to_host: exposed_to_host.top_level_values.keys().copied().collect(),
         lazy_literals,
         mode: backend_mode,
+        register_allocation: roc_gen_dev::RegisterAllocation::LinearScan,
     };
 
     let module_object =
@@ -650,9 +762,176 @@ fn report_timing(buf: &mut String, label: &str, duration: Duration) {
 
 pub struct BuiltFile<'a> {
     pub binary_path: PathBuf,
+    /// The platform's prebuilt host that was linked into the binary
+    pub host_path: PathBuf,
     pub problems: Problems,
     pub total_time: Duration,
     pub expect_metadata: ExpectMetadata<'a>,
+    pub stats: BuildStats,
+}
+
+/// A summary of a build, to see whether caching actually helped and where the time and memory went.
+#[derive(Debug, Clone, Default)]
+pub struct BuildStats {
+    /// Modules whose types were restored from the cache
+    pub cached_modules: usize,
+    /// Modules that were type-checked from scratch
+    pub fresh_modules: usize,
+    /// Number of specialized procedures that were generated
+    pub specializations: usize,
+    pub code_gen: CodeGenTiming,
+    pub link_time: Duration,
+    /// `None` if nothing was written to disk (e.g. `--no-link` with the surgical linker)
+    pub binary_size: Option<u64>,
+    pub memory: MemoryUsage,
+}
+
+impl BuildStats {
+    pub fn print_to_stdout(&self) {
+        let buf = &mut String::with_capacity(512);
+
+        use std::fmt::Write;
+        writeln!(
+            buf,
+            "    Modules: {} from cache, {} fresh",
+            self.cached_modules, self.fresh_modules
+        )
+        .unwrap();
+        writeln!(buf, "    Specializations: {}", self.specializations).unwrap();
+        report_timing(
+            buf,
+            "Generate final IR from Mono IR",
+            self.code_gen.generate_final_ir,
+        );
+        report_timing(buf, "Generate object", self.code_gen.code_gen_object);
+        report_timing(buf, "Link", self.link_time);
+
+        if let Some(binary_size) = self.binary_size {
+            writeln!(buf, "    Binary size: {} bytes", binary_size).unwrap();
+        }
+
+        println!("\nBuild summary:\n\n{}", buf);
+
+        self.memory.print_to_stdout();
+    }
+}
+
+/// Lists every specialized procedure, grouped by the function it was specialized from and with the
+/// biggest functions first, to help diagnose specialization explosions. Sizes are counted in mono IR
+/// statements, so they are only an estimate of the generated code.
+pub fn report_specializations(loaded: &MonomorphizedModule) -> String {
+    use roc_mono::layout::LayoutInterner;
+    use std::fmt::Write;
+
+    let interner = &loaded.layout_interner;
+    let mut by_function: MutMap<Symbol, Vec<(String, usize)>> = MutMap::default();
+
+    for ((symbol, proc_layout), proc) in loaded.procedures.iter() {
+        let arguments: Vec<String> = proc_layout
+            .arguments
+            .iter()
+            .map(|argument| interner.dbg(*argument))
+            .collect();
+        let layout = format!(
+            "({}) -> {}",
+            arguments.join(", "),
+            interner.dbg(proc_layout.result)
+        );
+
+        by_function
+            .entry(*symbol)
+            .or_default()
+            .push((layout, proc.body.count_statements()));
+    }
+
+    let mut functions: Vec<(String, Vec<(String, usize)>, usize)> = by_function
+        .into_iter()
+        .map(|(symbol, mut copies)| {
+            copies.sort_by(|(l1, s1), (l2, s2)| s2.cmp(s1).then_with(|| l1.cmp(l2)));
+            let total: usize = copies.iter().map(|(_, size)| size).sum();
+            let name = symbol.fully_qualified(&loaded.interns, loaded.module_id);
+
+            (name.as_str().to_string(), copies, total)
+        })
+        .collect();
+
+    functions.sort_by(|(n1, _, t1), (n2, _, t2)| t2.cmp(t1).then_with(|| n1.cmp(n2)));
+
+    let buf = &mut String::with_capacity(1024);
+
+    writeln!(
+        buf,
+        "Specializations: {} procedures from {} functions, sizes in mono IR statements\n",
+        loaded.procedures.len(),
+        functions.len()
+    )
+    .unwrap();
+
+    for (name, copies, total) in functions {
+        let plural = if copies.len() == 1 { "copy" } else { "copies" };
+        writeln!(
+            buf,
+            "    {}  {} {}, {} statements",
+            name,
+            copies.len(),
+            plural,
+            total
+        )
+        .unwrap();
+
+        for (layout, size) in copies {
+            writeln!(buf, "        {}  {} statements", layout, size).unwrap();
+        }
+    }
+
+    buf.to_string()
+}
+
+/// Summarize the layout interner: how many layouts it holds, which equivalent layouts were
+/// interned more than once, and which layouts are the biggest.
+pub fn report_layouts(interner: &STLayoutInterner) -> String {
+    use roc_mono::layout::LayoutInterner;
+    use std::fmt::Write;
+
+    const MAX_DUPLICATE_GROUPS: usize = 20;
+    const BIGGEST_COUNT: usize = 10;
+
+    let stats = interner.stats(BIGGEST_COUNT);
+    let buf = &mut String::with_capacity(1024);
+
+    writeln!(
+        buf,
+        "Layouts: {} interned, {} map entries, {} lambda sets\n",
+        stats.layouts, stats.map_entries, stats.lambda_sets
+    )
+    .unwrap();
+
+    let duplicate_count: usize = stats.duplicates.iter().map(|group| group.len() - 1).sum();
+    writeln!(
+        buf,
+        "    Duplicates: {} equivalent layouts interned more than once, in {} groups",
+        duplicate_count,
+        stats.duplicates.len()
+    )
+    .unwrap();
+    for group in stats.duplicates.iter().take(MAX_DUPLICATE_GROUPS) {
+        writeln!(buf, "        {}x  {}", group.len(), interner.dbg(group[0])).unwrap();
+    }
+    if stats.duplicates.len() > MAX_DUPLICATE_GROUPS {
+        writeln!(
+            buf,
+            "        ...and {} more groups",
+            stats.duplicates.len() - MAX_DUPLICATE_GROUPS
+        )
+        .unwrap();
+    }
+
+    writeln!(buf, "\n    Biggest layouts, in stack bytes:").unwrap();
+    for (layout, size) in stats.biggest {
+        writeln!(buf, "        {:>6}  {}", size, interner.dbg(layout)).unwrap();
+    }
+
+    buf.to_string()
 }
 
 pub enum BuildOrdering {
@@ -694,7 +973,7 @@ pub fn handle_error_module(
 ) -> std::io::Result<i32> {
     debug_assert!(module.total_problems() > 0);
 
-    let problems = report_problems_typechecked(&mut module);
+    let problems = report_problems_typechecked(&mut module, None, None);
 
     problems.print_to_stdout(total_time);
 
@@ -746,6 +1025,9 @@ pub fn standard_load_config(
         palette: DEFAULT_PALETTE,
         threading,
         exec_mode,
+        platform: None,
+        max_type_errors: None,
+        keep_module_types: false,
     }
 }
 
@@ -764,6 +1046,7 @@ pub fn build_file<'a>(
     load_config: LoadConfig,
 ) -> Result<BuiltFile<'a>, BuildFileError<'a>> {
     let compilation_start = Instant::now();
+    let threading = load_config.threading;
 
     // Step 1: compile the app and generate the .o file
     let loaded =
@@ -782,9 +1065,64 @@ pub fn build_file<'a>(
         wasm_dev_stack_bytes,
         loaded,
         compilation_start,
+        threading,
+        &mut Vec::new(),
     )
 }
 
+/// Builds several apps one after the other, e.g. for `roc build a.roc b.roc`.
+///
+/// Each app is loaded on its own, because a platform is type checked against the app that uses
+/// it. The builtins come from the same cache for all of them, and a platform's host only gets
+/// rebuilt for the first app that uses it: the apps after that link against the same host.
+#[allow(clippy::too_many_arguments)]
+pub fn build_files<'a>(
+    arena: &'a Bump,
+    target: &Triple,
+    app_module_paths: &[PathBuf],
+    code_gen_options: CodeGenOptions,
+    emit_timings: bool,
+    link_type: LinkType,
+    linking_strategy: LinkingStrategy,
+    prebuilt_requested: bool,
+    wasm_dev_stack_bytes: Option<u32>,
+    roc_cache_dir: RocCacheDir<'_>,
+    load_config: LoadConfig,
+) -> Vec<Result<BuiltFile<'a>, BuildFileError<'a>>> {
+    let mut rebuilt_platforms = Vec::new();
+
+    app_module_paths
+        .iter()
+        .map(|app_module_path| {
+            let compilation_start = Instant::now();
+
+            let loaded = roc_load::load_and_monomorphize(
+                arena,
+                app_module_path.clone(),
+                roc_cache_dir,
+                load_config.clone(),
+            )
+            .map_err(|e| BuildFileError::from_mono_error(e, compilation_start))?;
+
+            build_loaded_file(
+                arena,
+                target,
+                app_module_path.clone(),
+                code_gen_options,
+                emit_timings,
+                link_type,
+                linking_strategy,
+                prebuilt_requested,
+                wasm_dev_stack_bytes,
+                loaded,
+                compilation_start,
+                load_config.threading,
+                &mut rebuilt_platforms,
+            )
+        })
+        .collect()
+}
+
 #[allow(clippy::too_many_arguments)]
 fn build_loaded_file<'a>(
     arena: &'a Bump,
@@ -798,7 +1136,12 @@ fn build_loaded_file<'a>(
     wasm_dev_stack_bytes: Option<u32>,
     loaded: roc_load::MonomorphizedModule<'a>,
     compilation_start: Instant,
+    threading: Threading,
+    rebuilt_platforms: &mut Vec<PathBuf>,
 ) -> Result<BuiltFile<'a>, BuildFileError<'a>> {
+    let mut memory = MemoryUsage::default();
+    memory.record("Load, type check, and specialize", arena);
+
     let operating_system = roc_target::OperatingSystem::from(target.operating_system);
 
     let platform_main_roc = match &loaded.entry_point {
@@ -820,8 +1163,13 @@ fn build_loaded_file<'a>(
     };
 
     // For example, if we're loading the platform from a URL, it's automatically prebuilt
-    // even if the --prebuilt-platform CLI flag wasn't set.
-    let is_platform_prebuilt = prebuilt_requested || loaded.uses_prebuilt_platform;
+    // even if the --prebuilt-platform CLI flag wasn't set. A host that an earlier app in this
+    // build already rebuilt counts as prebuilt too: the host only imports the symbols its
+    // platform requires, so every app that could be linked with a fresh build of it can be
+    // linked with that one.
+    let is_platform_prebuilt = prebuilt_requested
+        || loaded.uses_prebuilt_platform
+        || rebuilt_platforms.contains(&platform_main_roc);
 
     let cwd = app_module_path.parent().unwrap();
     let mut output_exe_path = cwd.join(&*loaded.output_path);
@@ -853,6 +1201,8 @@ fn build_loaded_file<'a>(
         // Also, we should no longer need to do this once we have platforms on
         // a package repository, as we can then get prebuilt platforms from there.
 
+        rebuilt_platforms.push(platform_main_roc.clone());
+
         let dll_stub_symbols = roc_linker::ExposedSymbols::from_exposed_to_host(
             &loaded.interns,
             &loaded.exposed_to_host,
@@ -866,6 +1216,7 @@ fn build_loaded_file<'a>(
             output_exe_path.clone(),
             target,
             dll_stub_symbols,
+            code_gen_options.sanitizers,
         );
 
         Some(join_handle)
@@ -896,6 +1247,18 @@ fn build_loaded_file<'a>(
         }
     }
 
+    let cached_modules = loaded.cached_module_count;
+    let fresh_modules = loaded.timings.len().saturating_sub(cached_modules);
+    let specializations = loaded.procedures.len();
+
+    if code_gen_options.dump_specializations {
+        println!("{}", report_specializations(&loaded));
+    }
+
+    if code_gen_options.dump_layouts {
+        println!("{}", report_layouts(&loaded.layout_interner));
+    }
+
     // This only needs to be mutable for report_problems. This can't be done
     // inside a nested scope without causing a borrow error!
     let mut loaded = loaded;
@@ -908,7 +1271,8 @@ fn build_loaded_file<'a>(
     }
 
     let opt_rebuild_timing = if let Some(rebuild_thread) = rebuild_thread {
-        if linking_strategy == LinkingStrategy::Additive {
+        // With a single thread, the platform can't be rebuilt while the app is being compiled
+        if linking_strategy == LinkingStrategy::Additive || threading == Threading::Single {
             let rebuild_duration = rebuild_thread
                 .join()
                 .expect("Failed to (re)build platform.");
@@ -938,6 +1302,8 @@ fn build_loaded_file<'a>(
         wasm_dev_stack_bytes,
     );
 
+    memory.record("Generate code", arena);
+
     buf.push('\n');
     buf.push_str("    ");
     buf.push_str("Code Generation");
@@ -989,6 +1355,7 @@ fn build_loaded_file<'a>(
                 &platform_main_roc,
                 &roc_app_bytes,
                 &output_exe_path,
+                code_gen_options.hardening,
             );
         }
         (LinkingStrategy::Additive, _) | (LinkingStrategy::Legacy, LinkType::None) => {
@@ -1028,8 +1395,15 @@ fn build_loaded_file<'a>(
                 inputs.push(builtins_host_tempfile.path().to_str().unwrap());
             }
 
-            let (mut child, _) = link(target, output_exe_path.clone(), &inputs, link_type)
-                .map_err(|_| todo!("gracefully handle `ld` failing to spawn."))?;
+            let (mut child, _) = link(
+                target,
+                output_exe_path.clone(),
+                &inputs,
+                link_type,
+                code_gen_options.hardening,
+                code_gen_options.sanitizers,
+            )
+            .map_err(|_| todo!("gracefully handle `ld` failing to spawn."))?;
 
             let exit_status = child
                 .wait()
@@ -1050,17 +1424,33 @@ fn build_loaded_file<'a>(
 
     let linking_time = link_start.elapsed();
 
+    memory.record("Link", arena);
+
     if emit_timings {
         println!("Finished linking in {} ms\n", linking_time.as_millis());
     }
 
     let total_time = compilation_start.elapsed();
 
+    let stats = BuildStats {
+        cached_modules,
+        fresh_modules,
+        specializations,
+        code_gen: code_gen_timing,
+        link_time: linking_time,
+        binary_size: std::fs::metadata(&output_exe_path)
+            .ok()
+            .map(|metadata| metadata.len()),
+        memory,
+    };
+
     Ok(BuiltFile {
         binary_path: output_exe_path,
+        host_path: preprocessed_host_path,
         problems,
         total_time,
         expect_metadata,
+        stats,
     })
 }
 
@@ -1105,6 +1495,7 @@ fn spawn_rebuild_thread(
     output_exe_path: PathBuf,
     target: &Triple,
     dll_stub_symbols: Vec<String>,
+    sanitizers: Sanitizers,
 ) -> std::thread::JoinHandle<u128> {
     let thread_local_target = target.clone();
     std::thread::spawn(move || {
@@ -1122,6 +1513,7 @@ fn spawn_rebuild_thread(
                     &thread_local_target,
                     platform_main_roc.as_path(),
                     None,
+                    sanitizers,
                 );
 
                 preprocess_host_wasm32(host_dest.as_path(), &preprocessed_host_path);
@@ -1133,6 +1525,7 @@ fn spawn_rebuild_thread(
                     platform_main_roc.as_path(),
                     preprocessed_host_path.as_path(),
                     &dll_stub_symbols,
+                    sanitizers,
                 );
 
                 // Copy preprocessed host to executable location.
@@ -1145,6 +1538,7 @@ fn spawn_rebuild_thread(
                     &thread_local_target,
                     platform_main_roc.as_path(),
                     None,
+                    sanitizers,
                 );
             }
         }
@@ -1168,6 +1562,7 @@ pub fn build_and_preprocess_host(
         platform_main_roc,
         preprocessed_host_path,
         &stub_dll_symbols,
+        Sanitizers::default(),
     )
 }
 
@@ -1177,13 +1572,20 @@ fn build_and_preprocess_host_lowlevel(
     platform_main_roc: &Path,
     preprocessed_host_path: &Path,
     stub_dll_symbols: &[String],
+    sanitizers: Sanitizers,
 ) {
     let stub_lib =
         roc_linker::generate_stub_lib_from_loaded(target, platform_main_roc, stub_dll_symbols);
 
     debug_assert!(stub_lib.exists());
 
-    rebuild_host(opt_level, target, platform_main_roc, Some(&stub_lib));
+    rebuild_host(
+        opt_level,
+        target,
+        platform_main_roc,
+        Some(&stub_lib),
+        sanitizers,
+    );
 
     roc_linker::preprocess_host(
         target,
@@ -1201,6 +1603,10 @@ pub fn check_file<'a>(
     emit_timings: bool,
     roc_cache_dir: RocCacheDir<'_>,
     threading: Threading,
+    baseline: Option<&mut Baseline>,
+    deny_dead_expects: bool,
+    max_type_errors: Option<usize>,
+    emit_types_path: Option<&Path>,
 ) -> Result<(Problems, Duration), LoadingProblem<'a>> {
     let compilation_start = Instant::now();
 
@@ -1217,10 +1623,29 @@ pub fn check_file<'a>(
         palette: DEFAULT_PALETTE,
         threading,
         exec_mode: ExecutionMode::Check,
+        platform: None,
+        max_type_errors,
+        keep_module_types: emit_types_path.is_some(),
     };
     let mut loaded =
         roc_load::load_and_typecheck(arena, roc_file_path, roc_cache_dir, load_config)?;
 
+    if deny_dead_expects {
+        for (module_id, decls) in loaded.declarations_by_id.iter() {
+            if module_id.is_builtin() {
+                continue;
+            }
+
+            let dead_expects = roc_can::lint::dead_expects(decls);
+
+            loaded
+                .can_problems
+                .entry(*module_id)
+                .or_default()
+                .extend(dead_expects);
+        }
+    }
+
     let buf = &mut String::with_capacity(1024);
 
     let mut it = loaded.timings.iter().peekable();
@@ -1264,9 +1689,21 @@ pub fn check_file<'a>(
         println!("Finished checking in {} ms\n", compilation_end.as_millis(),);
     }
 
-    Ok((report_problems_typechecked(&mut loaded), compilation_end))
+    let problems = report_problems_typechecked(&mut loaded, baseline, max_type_errors);
+
+    if let Some(path) = emit_types_path {
+        let types = roc_load::solved_types::to_json(&mut loaded);
+
+        std::fs::write(path, types).map_err(|error| LoadingProblem::FileProblem {
+            filename: path.to_path_buf(),
+            error: error.kind(),
+        })?;
+    }
+
+    Ok((problems, compilation_end))
 }
 
+#[cfg(feature = "llvm")]
 pub fn build_str_test<'a>(
     arena: &'a Bump,
     app_module_path: &Path,
@@ -1279,6 +1716,12 @@ pub fn build_str_test<'a>(
         backend: CodeGenBackend::Llvm(LlvmBackendMode::Binary),
         opt_level: OptLevel::Normal,
         emit_debug_info: false,
+        trace_effects: false,
+        hardening: true,
+        sanitizers: Sanitizers::default(),
+        dump_specializations: false,
+        dump_layouts: false,
+        list_growth_percent: None,
     };
 
     let emit_timings = false;
@@ -1,10 +1,12 @@
+#[cfg(feature = "llvm")]
 use inkwell::{
     targets::{CodeModel, InitializationConfig, RelocMode, Target, TargetMachine, TargetTriple},
     OptimizationLevel,
 };
 use roc_error_macros::internal_error;
+#[cfg(feature = "llvm")]
 use roc_mono::ir::OptLevel;
-use target_lexicon::{Architecture, Environment, OperatingSystem, Triple};
+use target_lexicon::{Architecture, ArmArchitecture, Environment, OperatingSystem, Triple};
 
 pub fn target_triple_str(target: &Triple) -> &'static str {
     // Best guide I've found on how to determine these magic strings:
@@ -30,6 +32,11 @@ pub fn target_triple_str(target: &Triple) -> &'static str {
             operating_system: OperatingSystem::Linux,
             ..
         } => "aarch64-unknown-linux-gnu",
+        Triple {
+            architecture: Architecture::Riscv64(_),
+            operating_system: OperatingSystem::Linux,
+            ..
+        } => "riscv64-unknown-linux-gnu",
         Triple {
             architecture: Architecture::Aarch64(_),
             operating_system: OperatingSystem::Darwin,
@@ -45,6 +52,11 @@ pub fn target_triple_str(target: &Triple) -> &'static str {
             operating_system: OperatingSystem::Windows,
             ..
         } => "x86_64-pc-windows-gnu",
+        Triple {
+            architecture: Architecture::Arm(ArmArchitecture::Thumbv7em),
+            operating_system: OperatingSystem::None_,
+            ..
+        } => "thumbv7em-none-eabihf",
         _ => internal_error!("TODO gracefully handle unsupported target: {:?}", target),
     }
 }
@@ -83,6 +95,17 @@ pub fn target_zig_str(target: &Triple) -> &'static str {
             operating_system: OperatingSystem::Linux,
             ..
         } => "aarch64-linux-gnu",
+        Triple {
+            architecture: Architecture::Riscv64(_),
+            operating_system: OperatingSystem::Linux,
+            environment: Environment::Musl,
+            ..
+        } => "riscv64-linux-musl",
+        Triple {
+            architecture: Architecture::Riscv64(_),
+            operating_system: OperatingSystem::Linux,
+            ..
+        } => "riscv64-linux-gnu",
         Triple {
             architecture: Architecture::X86_64,
             operating_system: OperatingSystem::Darwin,
@@ -93,10 +116,16 @@ pub fn target_zig_str(target: &Triple) -> &'static str {
             operating_system: OperatingSystem::Darwin,
             ..
         } => "aarch64-apple-darwin",
+        Triple {
+            architecture: Architecture::Arm(ArmArchitecture::Thumbv7em),
+            operating_system: OperatingSystem::None_,
+            ..
+        } => "thumb-freestanding-eabihf",
         _ => internal_error!("TODO gracefully handle unsupported target: {:?}", target),
     }
 }
 
+#[cfg(feature = "llvm")]
 pub fn init_arch(target: &Triple) {
     match target.architecture {
         Architecture::X86_64 | Architecture::X86_32(_)
@@ -110,6 +139,9 @@ pub fn init_arch(target: &Triple) {
         Architecture::Arm(_) if cfg!(feature = "target-arm") => {
             Target::initialize_arm(&InitializationConfig::default());
         }
+        Architecture::Riscv64(_) if cfg!(feature = "target-riscv64") => {
+            Target::initialize_riscv(&InitializationConfig::default());
+        }
         Architecture::Wasm32 if cfg!(feature = "target-wasm32") => {
             Target::initialize_webassembly(&InitializationConfig::default());
         }
@@ -131,8 +163,11 @@ pub fn arch_str(target: &Triple) -> &'static str {
         Architecture::X86_64 if cfg!(feature = "target-x86_64") => "x86-64",
         Architecture::X86_32(_) if cfg!(feature = "target-x86") => "x86",
         Architecture::Aarch64(_) if cfg!(feature = "target-aarch64") => "aarch64",
+        // LLVM registers Thumb as its own target, next to ARM
+        Architecture::Arm(arm) if cfg!(feature = "target-arm") && arm.is_thumb() => "thumb",
         Architecture::Arm(_) if cfg!(feature = "target-arm") => "arm",
-        Architecture::Wasm32 if cfg!(feature = "target-webassembly") => "wasm32",
+        Architecture::Riscv64(_) if cfg!(feature = "target-riscv64") => "riscv64",
+        Architecture::Wasm32 if cfg!(feature = "target-wasm32") => "wasm32",
         _ => internal_error!(
             "TODO gracefully handle unsupported target architecture: {:?}",
             target.architecture
@@ -140,6 +175,7 @@ pub fn arch_str(target: &Triple) -> &'static str {
     }
 }
 
+#[cfg(feature = "llvm")]
 pub fn target_machine(
     target: &Triple,
     opt: OptimizationLevel,
@@ -156,19 +192,38 @@ pub fn target_machine(
         //   llvm/lib/Target/AArch64/AArch64Subtarget.cpp (GoT tables)
         // Revisit when upgrading to LLVM 13.
         Architecture::Aarch64(..) => CodeModel::Large,
+        // medany, like gcc and clang use for Linux; the default (medlow) only reaches the
+        // lowest 2GiB of the address space, which a position-independent executable isn't in
+        Architecture::Riscv64(..) => CodeModel::Medium,
         _ => CodeModel::Default,
     };
 
+    let cpu = match target.architecture {
+        // the thumbv7em cores with an FPU, which the eabihf builtins are compiled for
+        Architecture::Arm(ArmArchitecture::Thumbv7em) => "cortex-m4",
+        Architecture::Riscv64(_) => "generic-rv64",
+        _ => "generic",
+    };
+
+    let features = match target.architecture {
+        // "generic-rv64" is only the base integer ISA; riscv64gc (what Linux distros target)
+        // adds multiply, atomics, floats, doubles, and compressed instructions
+        Architecture::Riscv64(_) => "+m,+a,+f,+d,+c",
+        // TODO: this probably should be TargetMachine::get_host_cpu_features() to enable all features.
+        _ => "",
+    };
+
     Target::from_name(arch).unwrap().create_target_machine(
         &TargetTriple::create(target_triple_str(target)),
-        "generic",
-        "", // TODO: this probably should be TargetMachine::get_host_cpu_features() to enable all features.
+        cpu,
+        features,
         opt,
         reloc,
         code_model,
     )
 }
 
+#[cfg(feature = "llvm")]
 pub fn convert_opt_level(level: OptLevel) -> OptimizationLevel {
     match level {
         OptLevel::Development | OptLevel::Normal => OptimizationLevel::None,
@@ -35,11 +35,13 @@ fn main() {
 
     generate_bc_file(&bitcode_path, "ir-i386", "builtins-i386");
     generate_bc_file(&bitcode_path, "ir-x86_64", "builtins-x86_64");
+    generate_bc_file(&bitcode_path, "ir-riscv64", "builtins-riscv64");
     generate_bc_file(
         &bitcode_path,
         "ir-windows-x86_64",
         "builtins-windows-x86_64",
     );
+    generate_bc_file(&bitcode_path, "ir-thumbv7em", "builtins-thumbv7em");
 
     get_zig_files(bitcode_path.as_path(), &|path| {
         let path: &Path = path;
@@ -30,15 +30,19 @@ pub fn build(b: *Builder) void {
     });
     const linux32_target = makeLinux32Target();
     const linux64_target = makeLinux64Target();
+    const linux_riscv64_target = makeLinuxRiscv64Target();
     const windows64_target = makeWindows64Target();
     const wasm32_target = makeWasm32Target();
+    const thumbv7em_target = makeThumbv7emTarget();
 
     // LLVM IR
     generateLlvmIrFile(b, mode, host_target, main_path, "ir", "builtins-host");
     generateLlvmIrFile(b, mode, linux32_target, main_path, "ir-i386", "builtins-i386");
     generateLlvmIrFile(b, mode, linux64_target, main_path, "ir-x86_64", "builtins-x86_64");
+    generateLlvmIrFile(b, mode, linux_riscv64_target, main_path, "ir-riscv64", "builtins-riscv64");
     generateLlvmIrFile(b, mode, windows64_target, main_path, "ir-windows-x86_64", "builtins-windows-x86_64");
     generateLlvmIrFile(b, mode, wasm32_target, main_path, "ir-wasm32", "builtins-wasm32");
+    generateLlvmIrFile(b, mode, thumbv7em_target, main_path, "ir-thumbv7em", "builtins-thumbv7em");
 
     // Generate Object Files
     generateObjectFile(b, mode, host_target, main_path, "object", "builtins-host");
@@ -113,6 +117,18 @@ fn makeLinux64Target() CrossTarget {
     return target;
 }
 
+fn makeLinuxRiscv64Target() CrossTarget {
+    var target = CrossTarget.parse(.{}) catch unreachable;
+
+    // riscv64gc with the hard-float lp64d ABI, like Linux distros use
+    target.cpu_arch = std.Target.Cpu.Arch.riscv64;
+    target.cpu_model = .{ .explicit = &std.Target.riscv.cpu.baseline_rv64 };
+    target.os_tag = std.Target.Os.Tag.linux;
+    target.abi = std.Target.Abi.musl;
+
+    return target;
+}
+
 fn makeWindows64Target() CrossTarget {
     var target = CrossTarget.parse(.{}) catch unreachable;
 
@@ -134,6 +150,18 @@ fn makeWasm32Target() CrossTarget {
     return target;
 }
 
+fn makeThumbv7emTarget() CrossTarget {
+    var target = CrossTarget.parse(.{}) catch unreachable;
+
+    // bare-metal Cortex-M4F/M7, with a hardware FPU for f32
+    target.cpu_arch = std.Target.Cpu.Arch.thumb;
+    target.cpu_model = .{ .explicit = &std.Target.arm.cpu.cortex_m4 };
+    target.os_tag = std.Target.Os.Tag.freestanding;
+    target.abi = std.Target.Abi.eabihf;
+
+    return target;
+}
+
 fn removeInstallSteps(b: *Builder) void {
     for (b.top_level_steps.items) |top_level_step, i| {
         const name = top_level_step.step.name;
@@ -1,5 +1,7 @@
 const std = @import("std");
 const builtin = @import("builtin");
+const RocStr = @import("str.zig").RocStr;
+const panic_help = @import("panic.zig").panic_help;
 
 const Atomic = std.atomic.Atomic;
 
@@ -9,6 +11,18 @@ const O_CREAT: c_int = 64;
 pub const PROT_WRITE: c_int = 2;
 pub const MAP_SHARED: c_int = 0x0001;
 
+// How big the parent lets the shared memory object get. We map this much address space up front,
+// so the buffer never moves when the parent grows the object behind it.
+// Must match ExpectMemory::SHM_MAX_SIZE in repl_expect.
+const SHARED_BUFFER_MAX_LENGTH: usize = 1 << 30;
+
+// Indices of u32s in the header, after the frame count and next free offset (both usize).
+// CAPACITY is the current size of the shared memory object; a frame must not be written past it.
+const CAPACITY_INDEX: usize = 4;
+const MESSAGE_INDEX: usize = 5;
+
+const MESSAGE_BUFFER_FULL: u32 = 6;
+
 // IMPORTANT: shared memory object names must begin with / and contain no other slashes!
 var SHARED_BUFFER: []u8 = undefined;
 
@@ -19,11 +33,46 @@ pub fn setSharedBuffer(ptr: [*]u8, length: usize) callconv(.C) usize {
     return 0;
 }
 
+pub const GrowSharedBuffer = fn (fd: c_int, shared_buffer: [*]u8, end: usize) callconv(.C) void;
+
+// Set (together with setSharedBuffer) when the parent runs in our own process, like `roc test`
+// does for pure expects. Then there is no parent to notify, so we ask it to grow the buffer directly.
+var GROW_SHARED_BUFFER: ?GrowSharedBuffer = null;
+var SHARED_BUFFER_FD: c_int = -1;
+
+pub fn setSharedBufferGrow(grow: GrowSharedBuffer, fd: c_int) callconv(.C) usize {
+    GROW_SHARED_BUFFER = grow;
+    SHARED_BUFFER_FD = fd;
+
+    // the rust side expects that a pointer is returned
+    return 0;
+}
+
+pub const FrameWritten = fn (shared_buffer: [*]u8, tag: u32) callconv(.C) void;
+
+// Also set when the parent runs in our own process. Then nobody waits for the notifications about
+// expect and dbg frames, so instead it is called right after each frame was written.
+var FRAME_WRITTEN: ?FrameWritten = null;
+
+pub fn setSharedBufferFrameWritten(frame_written: FrameWritten) callconv(.C) usize {
+    FRAME_WRITTEN = frame_written;
+
+    // the rust side expects that a pointer is returned
+    return 0;
+}
+
 pub fn expectFailedStartSharedBuffer() callconv(.C) [*]u8 {
     return SHARED_BUFFER.ptr;
 }
 
+// Unlike the buffer, the shared memory object can grow, so map it only once
+var SHARED_FILE: ?[*]u8 = null;
+
 pub fn expectFailedStartSharedFile() callconv(.C) [*]u8 {
+    if (SHARED_FILE) |ptr| {
+        return ptr;
+    }
+
     // IMPORTANT: shared memory object names must begin with / and contain no other slashes!
     var name: [100]u8 = undefined;
     _ = std.fmt.bufPrint(name[0..100], "/roc_expect_buffer_{}\x00", .{roc_getppid()}) catch unreachable;
@@ -31,11 +80,9 @@ pub fn expectFailedStartSharedFile() callconv(.C) [*]u8 {
     if (builtin.os.tag == .macos or builtin.os.tag == .linux) {
         const shared_fd = roc_shm_open(@ptrCast(*const i8, &name), O_RDWR | O_CREAT, 0o666);
 
-        const length = 4096;
-
         const shared_ptr = roc_mmap(
             null,
-            length,
+            SHARED_BUFFER_MAX_LENGTH,
             PROT_WRITE,
             MAP_SHARED,
             shared_fd,
@@ -43,6 +90,7 @@ pub fn expectFailedStartSharedFile() callconv(.C) [*]u8 {
         );
 
         const ptr = @ptrCast([*]u8, shared_ptr);
+        SHARED_FILE = ptr;
 
         return ptr;
     } else {
@@ -50,6 +98,28 @@ pub fn expectFailedStartSharedFile() callconv(.C) [*]u8 {
     }
 }
 
+/// Makes sure the frame being written can use the shared buffer up to `end`. If it can't, the
+/// parent grows the shared memory object first; writing past its end would crash (or be lost).
+pub fn expectEnsureCapacity(shared_buffer: [*]u8, end: usize) callconv(.C) void {
+    const header = @ptrCast([*]u32, @alignCast(@alignOf(u32), shared_buffer));
+
+    if (end <= header[CAPACITY_INDEX]) {
+        return;
+    }
+
+    if (GROW_SHARED_BUFFER) |grow| {
+        grow(SHARED_BUFFER_FD, shared_buffer, end);
+    } else {
+        // the parent reads how much room we need from the capacity, and replaces it with the new size
+        header[CAPACITY_INDEX] = @intCast(u32, std.math.min(end, std.math.maxInt(u32)));
+        notifyParent(shared_buffer, MESSAGE_BUFFER_FULL);
+    }
+
+    if (end > header[CAPACITY_INDEX]) {
+        panic_help("The values in this expect are too big to show.", 0);
+    }
+}
+
 extern fn roc_shm_open(name: *const i8, oflag: c_int, mode: c_uint) c_int;
 extern fn roc_mmap(addr: ?*anyopaque, length: c_uint, prot: c_int, flags: c_int, fd: c_int, offset: c_uint) *anyopaque;
 extern fn roc_getppid() c_int;
@@ -62,7 +132,7 @@ pub fn readSharedBufferEnv() callconv(.C) void {
         _ = std.fmt.bufPrint(name[0..100], "/roc_expect_buffer_{}\x00", .{roc_getppid()}) catch unreachable;
 
         const shared_fd = roc_shm_open(@ptrCast(*const i8, &name), O_RDWR | O_CREAT, 0o666);
-        const length = 4096;
+        const length = SHARED_BUFFER_MAX_LENGTH;
 
         const shared_ptr = roc_mmap(
             null,
@@ -82,7 +152,7 @@ pub fn readSharedBufferEnv() callconv(.C) void {
 pub fn notifyParent(shared_buffer: [*]u8, tag: u32) callconv(.C) void {
     if (builtin.os.tag == .macos or builtin.os.tag == .linux) {
         const usize_ptr = @ptrCast([*]u32, @alignCast(@alignOf(usize), shared_buffer));
-        const atomic_ptr = @ptrCast(*Atomic(u32), &usize_ptr[5]);
+        const atomic_ptr = @ptrCast(*Atomic(u32), &usize_ptr[MESSAGE_INDEX]);
         atomic_ptr.storeUnchecked(tag);
 
         // wait till the parent is done before proceeding
@@ -94,9 +164,119 @@ pub fn notifyParent(shared_buffer: [*]u8, tag: u32) callconv(.C) void {
 }
 
 pub fn notifyParentExpect(shared_buffer: [*]u8) callconv(.C) void {
-    notifyParent(shared_buffer, 1);
+    notifyFrameWritten(shared_buffer, 1);
 }
 
 pub fn notifyParentDbg(shared_buffer: [*]u8) callconv(.C) void {
-    notifyParent(shared_buffer, 2);
+    notifyFrameWritten(shared_buffer, 2);
+}
+
+fn notifyFrameWritten(shared_buffer: [*]u8, tag: u32) void {
+    if (FRAME_WRITTEN) |frame_written| {
+        frame_written(shared_buffer, tag);
+    } else {
+        notifyParent(shared_buffer, tag);
+    }
+}
+
+// Effect tracing, for `roc dev --trace-effects`.
+//
+// Before a traced effect runs, its name and arguments are written to the shared buffer (after the
+// header the parent uses for expects), and the parent is notified. The parent is notified again
+// once the effect returns, so it can measure how long the effect took.
+const TRACE_START: usize = 24;
+// must not exceed the size the parent creates the shared memory object with
+const TRACE_BUFFER_LENGTH: usize = 1024;
+// longer strings are cut off, so one big argument can't crowd out the others
+const TRACE_TEXT_LIMIT: usize = 64;
+
+const TRACE_ARG_STR: u8 = 0;
+const TRACE_ARG_INT: u8 = 1;
+const TRACE_ARG_UINT: u8 = 2;
+const TRACE_ARG_FLOAT: u8 = 3;
+const TRACE_ARG_OTHER: u8 = 4;
+const TRACE_END: u8 = 0xFF;
+
+var TRACE_BUFFER: ?[*]u8 = null;
+var TRACE_CURSOR: usize = TRACE_START;
+
+/// Writes all of `bytes`, or nothing if they don't fit. There is always room left for the end marker.
+fn traceWrite(bytes: []const u8) void {
+    const buffer = TRACE_BUFFER orelse return;
+
+    if (TRACE_CURSOR + bytes.len >= TRACE_BUFFER_LENGTH) {
+        return;
+    }
+
+    @memcpy(buffer + TRACE_CURSOR, bytes.ptr, bytes.len);
+    TRACE_CURSOR += bytes.len;
+}
+
+fn traceText(kind: u8, bytes: []const u8) void {
+    const shown = std.math.min(bytes.len, TRACE_TEXT_LIMIT);
+
+    var record: [9 + TRACE_TEXT_LIMIT]u8 = undefined;
+    record[0] = kind;
+    std.mem.writeIntNative(u32, record[1..5], @truncate(u32, bytes.len));
+    std.mem.writeIntNative(u32, record[5..9], @intCast(u32, shown));
+    std.mem.copy(u8, record[9..], bytes[0..shown]);
+
+    traceWrite(record[0 .. 9 + shown]);
+}
+
+fn traceNumber(kind: u8, bits: u64) void {
+    var record: [9]u8 = undefined;
+    record[0] = kind;
+    std.mem.writeIntNative(u64, record[1..9], bits);
+
+    traceWrite(&record);
+}
+
+pub fn traceEffectBegin(name: [*]const u8, name_length: usize) callconv(.C) void {
+    if (builtin.os.tag == .macos or builtin.os.tag == .linux) {
+        TRACE_BUFFER = expectFailedStartSharedFile();
+
+        TRACE_CURSOR = TRACE_START;
+
+        var length: [4]u8 = undefined;
+        std.mem.writeIntNative(u32, &length, @intCast(u32, name_length));
+
+        traceWrite(&length);
+        traceWrite(name[0..name_length]);
+    }
+}
+
+pub fn traceEffectArgStr(arg: *const RocStr) callconv(.C) void {
+    traceText(TRACE_ARG_STR, arg.asSlice());
+}
+
+pub fn traceEffectArgInt(arg: i64) callconv(.C) void {
+    traceNumber(TRACE_ARG_INT, @bitCast(u64, arg));
+}
+
+pub fn traceEffectArgUint(arg: u64) callconv(.C) void {
+    traceNumber(TRACE_ARG_UINT, arg);
+}
+
+pub fn traceEffectArgFloat(arg: f64) callconv(.C) void {
+    traceNumber(TRACE_ARG_FLOAT, @bitCast(u64, arg));
+}
+
+/// For arguments we can't show the value of, like lists and records.
+pub fn traceEffectArgOther(description: [*]const u8, length: usize) callconv(.C) void {
+    traceText(TRACE_ARG_OTHER, description[0..length]);
+}
+
+pub fn traceEffectCall() callconv(.C) void {
+    const buffer = TRACE_BUFFER orelse return;
+
+    buffer[TRACE_CURSOR] = TRACE_END;
+
+    notifyParent(buffer, 4);
+}
+
+pub fn traceEffectReturn() callconv(.C) void {
+    const buffer = TRACE_BUFFER orelse return;
+
+    notifyParent(buffer, 5);
 }
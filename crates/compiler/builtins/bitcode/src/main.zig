@@ -201,19 +201,42 @@ comptime {
     exportUtilsFn(utils.decrefCheckNullC, "decref_check_null");
     exportUtilsFn(utils.allocateWithRefcountC, "allocate_with_refcount");
     exportUtilsFn(utils.dictPseudoSeed, "dict_pseudo_seed");
+    @export(utils.atomic_refcounts, .{ .name = "roc_builtins.utils." ++ "atomic_refcounts", .linkage = .Strong });
+    @export(utils.list_growth_percent, .{ .name = "roc_builtins.utils." ++ "list_growth_percent", .linkage = .Strong });
 
     @export(panic_utils.panic, .{ .name = "roc_builtins.utils." ++ "panic", .linkage = .Weak });
+    // not a roc_builtins function, because the host calls it
+    @export(panic_utils.setPanicHook, .{ .name = "roc_set_panic_hook", .linkage = .Strong });
 
-    if (builtin.target.cpu.arch != .wasm32) {
+    // expects report to a parent process through shared memory, which needs an OS
+    if (builtin.target.cpu.arch != .wasm32 and builtin.os.tag != .freestanding) {
         exportUtilsFn(expect.expectFailedStartSharedBuffer, "expect_failed_start_shared_buffer");
         exportUtilsFn(expect.expectFailedStartSharedFile, "expect_failed_start_shared_file");
         exportUtilsFn(expect.notifyParentExpect, "notify_parent_expect");
         exportUtilsFn(expect.notifyParentDbg, "notify_parent_dbg");
+        exportUtilsFn(expect.expectEnsureCapacity, "expect_ensure_capacity");
+        exportUtilsFn(expect.traceEffectBegin, "trace_effect_begin");
+        exportUtilsFn(expect.traceEffectArgStr, "trace_effect_arg_str");
+        exportUtilsFn(expect.traceEffectArgInt, "trace_effect_arg_int");
+        exportUtilsFn(expect.traceEffectArgUint, "trace_effect_arg_uint");
+        exportUtilsFn(expect.traceEffectArgFloat, "trace_effect_arg_float");
+        exportUtilsFn(expect.traceEffectArgOther, "trace_effect_arg_other");
+        exportUtilsFn(expect.traceEffectCall, "trace_effect_call");
+        exportUtilsFn(expect.traceEffectReturn, "trace_effect_return");
 
         // sets the buffer used for expect failures
         @export(expect.setSharedBuffer, .{ .name = "set_shared_buffer", .linkage = .Weak });
+        @export(expect.setSharedBufferGrow, .{ .name = "set_shared_buffer_grow", .linkage = .Weak });
+        @export(expect.setSharedBufferFrameWritten, .{ .name = "set_shared_buffer_frame_written", .linkage = .Weak });
 
         exportUtilsFn(expect.readSharedBufferEnv, "read_env_shared_buffer");
+    } else if (builtin.target.cpu.arch == .wasm32) {
+        // `roc test --target wasm32` runs the expects in an interpreter, which gives us a buffer
+        // in linear memory. The frames go to it with an import, see notify_runner_expect in gen_llvm.
+        exportUtilsFn(expect.expectFailedStartSharedBuffer, "expect_failed_start_shared_buffer");
+        exportUtilsFn(expect.expectEnsureCapacity, "expect_ensure_capacity");
+
+        @export(expect.setSharedBuffer, .{ .name = "set_shared_buffer", .linkage = .Weak });
     }
 
     if (builtin.target.cpu.arch == .aarch64) {
@@ -270,6 +293,10 @@ fn exportUtilsFn(comptime func: anytype, comptime func_name: []const u8) void {
 pub fn panic(message: []const u8, stacktrace: ?*std.builtin.StackTrace) noreturn {
     if (builtin.is_test) {
         std.debug.print("{s}: {?}", .{ message, stacktrace });
+    } else if (builtin.os.tag == .freestanding and builtin.target.cpu.arch != .wasm32) {
+        // On bare metal nothing would catch the trap, so let the host report it through roc_panic.
+        _ = stacktrace;
+        panic_utils.panic_help(message, 0);
     } else {
         _ = message;
         _ = stacktrace;
@@ -5,12 +5,32 @@ const always_inline = std.builtin.CallOptions.Modifier.always_inline;
 // Signals to the host that the program has panicked
 extern fn roc_panic(msg: *const RocStr, tag_id: u32) callconv(.C) void;
 
+pub const PanicHook = fn (msg: *const RocStr, tag_id: u32) callconv(.C) void;
+
+// Set by the host through roc_set_panic_hook. Hosts that never call it get the old behavior,
+// where roc_panic alone decides how the panic is reported.
+var panic_hook: ?PanicHook = null;
+
+// Lets a platform report panics its own way (e.g. as structured logs, or to a crash reporter)
+// without having to change roc_panic, which must still unwind or abort afterwards.
+pub fn setPanicHook(hook: ?PanicHook) callconv(.C) void {
+    panic_hook = hook;
+}
+
+fn panicWithHook(msg: *const RocStr, tag_id: u32) void {
+    if (panic_hook) |hook| {
+        hook(msg, tag_id);
+    }
+
+    roc_panic(msg, tag_id);
+}
+
 pub fn panic_help(msg: []const u8, tag_id: u32) void {
     var str = RocStr.init(msg.ptr, msg.len);
-    roc_panic(&str, tag_id);
+    panicWithHook(&str, tag_id);
 }
 
 // must export this explicitly because right now it is not used from zig code
 pub fn panic(msg: *const RocStr, alignment: u32) callconv(.C) void {
-    return @call(.{ .modifier = always_inline }, roc_panic, .{ msg, alignment });
+    return @call(.{ .modifier = always_inline }, panicWithHook, .{ msg, alignment });
 }
@@ -2,6 +2,7 @@ const utils = @import("utils.zig");
 const RocList = @import("list.zig").RocList;
 const grapheme = @import("helpers/grapheme.zig");
 const UpdateMode = utils.UpdateMode;
+const expectEnsureCapacity = @import("expect.zig").expectEnsureCapacity;
 const std = @import("std");
 const mem = std.mem;
 const always_inline = std.builtin.CallOptions.Modifier.always_inline;
@@ -2892,14 +2893,21 @@ pub fn strCloneTo(
     } else {
         const slice = string.asSlice();
 
-        var relative = string;
-        relative.str_bytes = @intToPtr(?[*]u8, extra_offset); // i.e. just after the string struct
+        // The frame can be read by another process, which maps the buffer at a different address,
+        // so it may contain offsets into the buffer but no addresses of ours. A seamless slice
+        // keeps the address of the string it was sliced from, so write a regular string instead.
+        const relative = RocStr{
+            .str_bytes = @intToPtr(?[*]u8, extra_offset), // i.e. just after the string struct
+            .str_len = slice.len,
+            .str_capacity = slice.len,
+        };
 
         // write the string struct
         const array = relative.asArray();
         @memcpy(ptr + offset, &array, WIDTH);
 
         // write the string bytes just after the struct
+        expectEnsureCapacity(ptr, extra_offset + slice.len);
         @memcpy(ptr + extra_offset, slice.ptr, slice.len);
 
         return extra_offset + slice.len;
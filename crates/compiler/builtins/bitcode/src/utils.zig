@@ -132,10 +132,17 @@ const Refcount = enum {
     atomic,
 };
 
-const RC_TYPE = Refcount.normal;
+/// Whether refcounts are updated atomically, so values can be shared between threads.
+/// The LLVM backend sets this to match its `ThreadSafety` mode and makes it a constant,
+/// so the check below gets folded away.
+pub var atomic_refcounts: bool = false;
+
+inline fn rcType() Refcount {
+    return if (atomic_refcounts) Refcount.atomic else Refcount.normal;
+}
 
 pub fn increfRcPtrC(ptr_to_refcount: *isize, amount: isize) callconv(.C) void {
-    if (RC_TYPE == Refcount.none) return;
+    if (rcType() == Refcount.none) return;
 
     if (DEBUG_INCDEC and builtin.target.cpu.arch != .wasm32) {
         std.debug.print("| increment {*}: ", .{ptr_to_refcount});
@@ -145,7 +152,7 @@ pub fn increfRcPtrC(ptr_to_refcount: *isize, amount: isize) callconv(.C) void {
     if (ptr_to_refcount.* != REFCOUNT_MAX_ISIZE) {
         // Note: we assume that a refcount will never overflow.
         // As such, we do not need to cap incrementing.
-        switch (RC_TYPE) {
+        switch (rcType()) {
             Refcount.normal => {
                 if (DEBUG_INCDEC and builtin.target.cpu.arch != .wasm32) {
                     const old = @bitCast(usize, ptr_to_refcount.*);
@@ -240,7 +247,7 @@ inline fn decref_ptr_to_refcount(
     refcount_ptr: [*]isize,
     alignment: u32,
 ) void {
-    if (RC_TYPE == Refcount.none) return;
+    if (rcType() == Refcount.none) return;
     const extra_bytes = std.math.max(alignment, @sizeOf(usize));
 
     if (DEBUG_INCDEC and builtin.target.cpu.arch != .wasm32) {
@@ -250,7 +257,7 @@ inline fn decref_ptr_to_refcount(
     // Ensure that the refcount is not whole program lifetime.
     const refcount: isize = refcount_ptr[0];
     if (refcount != REFCOUNT_MAX_ISIZE) {
-        switch (RC_TYPE) {
+        switch (rcType()) {
             Refcount.normal => {
                 const old = @bitCast(usize, refcount);
                 refcount_ptr[0] = refcount -% 1;
@@ -314,6 +321,11 @@ pub fn isUnique(
 // In our case, we exposed allocate and reallocate, which will use a smart growth stategy.
 // We also expose allocateExact and reallocateExact for case where a specific number of elements is requested.
 
+/// How much a full list grows, as a percentage of its old capacity (e.g. 150 grows it by half).
+/// 0 means the fbvector-like policy below. The LLVM backend sets this from `--list-growth`
+/// and makes it a constant, so the policy that is not used gets folded away.
+pub var list_growth_percent: u32 = 0;
+
 // calculateCapacity should only be called in cases the list will be growing.
 // requested_length should always be greater than old_capacity.
 pub inline fn calculateCapacity(
@@ -335,6 +347,9 @@ pub inline fn calculateCapacity(
         return requested_length;
     } else if (old_capacity == 0) {
         new_capacity = 64 / element_width;
+    } else if (list_growth_percent != 0) {
+        const growth = std.math.max(1, (old_capacity *| @as(usize, list_growth_percent - 100)) / 100);
+        new_capacity = old_capacity +| growth;
     } else if (old_capacity < 4096 / element_width) {
         new_capacity = old_capacity * 2;
     } else if (old_capacity > 4096 * 32 / element_width) {
@@ -365,7 +380,7 @@ pub fn allocateWithRefcount(
 
     const data_ptr = new_bytes + alignment;
     const refcount_ptr = @ptrCast([*]usize, @alignCast(ptr_width, data_ptr) - ptr_width);
-    refcount_ptr[0] = if (RC_TYPE == Refcount.none) REFCOUNT_MAX_ISIZE else REFCOUNT_ONE;
+    refcount_ptr[0] = if (rcType() == Refcount.none) REFCOUNT_MAX_ISIZE else REFCOUNT_ONE;
 
     return data_ptr;
 }
@@ -425,6 +440,17 @@ test "increfC, static data" {
     try std.testing.expectEqual(mock_rc, REFCOUNT_MAX_ISIZE);
 }
 
+test "calculateCapacity, configured growth" {
+    defer list_growth_percent = 0;
+
+    list_growth_percent = 150;
+    try std.testing.expectEqual(calculateCapacity(1000, 1001, 8), 1500);
+    // small lists still grow by at least one element
+    try std.testing.expectEqual(calculateCapacity(1, 2, 8), 2);
+    // a bigger request than the growth wins
+    try std.testing.expectEqual(calculateCapacity(1000, 5000, 8), 5000);
+}
+
 // This returns a compilation dependent pseudo random seed for dictionaries.
 // The seed is the address of this function.
 // This avoids all roc Dicts using a known seed and being trivial to DOS.
@@ -21,11 +21,12 @@ interface Hash
         hashStrBytes,
         hashList,
         hashUnordered,
+        packInt,
     ] imports [
         Bool.{ Bool, isEq },
         List,
         Str,
-        Num.{ U8, U16, U32, U64, U128, I8, I16, I32, I64, I128, Nat, Dec },
+        Num.{ Int, U8, U16, U32, U64, U128, I8, I16, I32, I64, I128, Nat, Dec },
     ]
 
 ## A value that can hashed.
@@ -143,3 +144,22 @@ hashUnordered = \hasher, container, walk ->
                 nextAccum
         )
     |> \accum -> addU64 hasher accum
+
+## Packs the low `bits` bits of an integer into `word`, starting at bit `offset`.
+##
+## Derived [Hash] implementations use this to add records of small integers,
+## like `{ x : U16, y : U16 }`, to a hasher a whole word at a time.
+packInt : U64, Int a, U8, U8 -> U64
+packInt = \word, n, bits, offset ->
+    # `Num.toU64` sign-extends signed integers, and those extra bits must not
+    # end up in the bits of the fields that come after this one.
+    mask =
+        if bits >= 64 then
+            Num.maxU64
+        else
+            Num.shiftLeftBy 1 bits |> Num.subWrap 1
+
+    Num.toU64 n
+    |> Num.bitwiseAnd mask
+    |> Num.shiftLeftBy offset
+    |> Num.bitwiseOr word
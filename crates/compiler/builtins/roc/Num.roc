@@ -37,8 +37,10 @@ interface Num
         pi,
         tau,
         abs,
+        absChecked,
         absDiff,
         neg,
+        negChecked,
         add,
         sub,
         mul,
@@ -730,6 +732,38 @@ absDiff = \a, b ->
 ## (It will never crash when given a [Frac], however, because of how floating point numbers represent positive and negative numbers.)
 neg : Num a -> Num a
 
+## Returns the absolute value of the number, or `Err Overflow` when that value
+## can't be represented.
+##
+## This is the same as [Num.abs] except that calling it on the lowest value of a
+## signed integer (such as [Num.minI64] or [Num.minI32]) returns `Err Overflow`
+## instead of panicking.
+## ```
+## Num.absChecked -5
+##
+## Num.absChecked Num.minI8
+## ```
+absChecked : Num a -> Result (Num a) [Overflow]
+absChecked = \x ->
+    if x < 0 then
+        subChecked 0 x
+    else
+        Ok x
+
+## Negates the number, or returns `Err Overflow` when the negated value can't be
+## represented.
+##
+## This is the same as [Num.neg] except that calling it on the lowest value of a
+## signed integer, or on any unsigned integer other than zero, returns
+## `Err Overflow` instead of panicking.
+## ```
+## Num.negChecked 5
+##
+## Num.negChecked Num.minI8
+## ```
+negChecked : Num a -> Result (Num a) [Overflow]
+negChecked = \x -> subChecked 0 x
+
 ## Adds two numbers of the same type.
 ##
 ## (To add an [Int] and a [Frac], first convert one so that they both have the same type. There are functions in this module that can convert both [Int] to [Frac] and the other way around.)
@@ -48,7 +48,7 @@ impl FloatWidth {
         match self {
             F32 => 4,
             F64 => match target_info.architecture {
-                X86_64 | Aarch64 | Wasm32 => 8,
+                X86_64 | Aarch64 | Riscv64 | Wasm32 => 8,
                 X86_32 | Aarch32 => 4,
             },
         }
@@ -115,6 +115,7 @@ impl IntWidth {
                 Architecture::X86_64
                 | Architecture::Aarch64
                 | Architecture::Aarch32
+                | Architecture::Riscv64
                 | Architecture::Wasm32 => 8,
                 Architecture::X86_32 => 4,
             },
@@ -390,6 +391,7 @@ pub const DEC_MUL_OR_PANIC: &str = "roc_builtins.dec.mul_or_panic";
 pub const DEC_MUL_SATURATED: &str = "roc_builtins.dec.mul_saturated";
 
 pub const UTILS_TEST_PANIC: &str = "roc_builtins.utils.test_panic";
+pub const UTILS_PANIC: &str = "roc_builtins.utils.panic";
 pub const UTILS_ALLOCATE_WITH_REFCOUNT: &str = "roc_builtins.utils.allocate_with_refcount";
 pub const UTILS_INCREF_RC_PTR: &str = "roc_builtins.utils.incref_rc_ptr";
 pub const UTILS_DECREF_RC_PTR: &str = "roc_builtins.utils.decref_rc_ptr";
@@ -398,14 +400,25 @@ pub const UTILS_DECREF_DATA_PTR: &str = "roc_builtins.utils.decref_data_ptr";
 pub const UTILS_IS_UNIQUE: &str = "roc_builtins.utils.is_unique";
 pub const UTILS_DECREF_CHECK_NULL: &str = "roc_builtins.utils.decref_check_null";
 pub const UTILS_DICT_PSEUDO_SEED: &str = "roc_builtins.utils.dict_pseudo_seed";
+pub const UTILS_ATOMIC_REFCOUNTS: &str = "roc_builtins.utils.atomic_refcounts";
+pub const UTILS_LIST_GROWTH_PERCENT: &str = "roc_builtins.utils.list_growth_percent";
 
 pub const UTILS_EXPECT_FAILED_START_SHARED_BUFFER: &str =
     "roc_builtins.utils.expect_failed_start_shared_buffer";
 pub const UTILS_EXPECT_FAILED_START_SHARED_FILE: &str =
     "roc_builtins.utils.expect_failed_start_shared_file";
 pub const UTILS_EXPECT_READ_ENV_SHARED_BUFFER: &str = "roc_builtins.utils.read_env_shared_buffer";
+pub const UTILS_EXPECT_ENSURE_CAPACITY: &str = "roc_builtins.utils.expect_ensure_capacity";
 pub const NOTIFY_PARENT_EXPECT: &str = "roc_builtins.utils.notify_parent_expect";
 pub const NOTIFY_PARENT_DBG: &str = "roc_builtins.utils.notify_parent_dbg";
+pub const UTILS_TRACE_EFFECT_BEGIN: &str = "roc_builtins.utils.trace_effect_begin";
+pub const UTILS_TRACE_EFFECT_ARG_STR: &str = "roc_builtins.utils.trace_effect_arg_str";
+pub const UTILS_TRACE_EFFECT_ARG_INT: &str = "roc_builtins.utils.trace_effect_arg_int";
+pub const UTILS_TRACE_EFFECT_ARG_UINT: &str = "roc_builtins.utils.trace_effect_arg_uint";
+pub const UTILS_TRACE_EFFECT_ARG_FLOAT: &str = "roc_builtins.utils.trace_effect_arg_float";
+pub const UTILS_TRACE_EFFECT_ARG_OTHER: &str = "roc_builtins.utils.trace_effect_arg_other";
+pub const UTILS_TRACE_EFFECT_CALL: &str = "roc_builtins.utils.trace_effect_call";
+pub const UTILS_TRACE_EFFECT_RETURN: &str = "roc_builtins.utils.trace_effect_return";
 
 pub const UTILS_LONGJMP: &str = "longjmp";
 pub const UTILS_SETJMP: &str = "setjmp";
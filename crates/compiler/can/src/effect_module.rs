@@ -18,6 +18,7 @@ pub(crate) struct HostedGeneratedFunctions {
     pub(crate) always: bool,
     pub(crate) loop_: bool,
     pub(crate) forever: bool,
+    pub(crate) all: bool,
 }
 
 /// the Effects alias & associated functions
@@ -81,6 +82,12 @@ pub(crate) fn build_effect_builtins(
         declarations.push_def(def);
     }
 
+    // Effect.all : List (Effect a) -> Effect (List a)
+    if generated_functions.all {
+        let def = helper!(build_effect_all);
+        declarations.push_def(def);
+    }
+
     // Useful when working on functions in this module. By default symbols that we named do now
     // show up with their name. We have to register them like below to make the names show up in
     // debug prints
@@ -1334,6 +1341,176 @@ fn build_effect_loop_inner_body(
     )
 }
 
+fn build_effect_all(
+    scope: &mut Scope,
+    effect_symbol: Symbol,
+    var_store: &mut VarStore,
+) -> (Symbol, Def) {
+    // Effect.all = \effects -> @Effect \{} -> List.map effects \@Effect thunk -> thunk {}
+    //
+    // The effects run one after the other, in the order of the list. A platform that wants
+    // them to run concurrently can expose its own `all` instead.
+
+    let effects_symbol = new_symbol!(scope, "effect_all_effects");
+    let effect_arg_symbol = new_symbol!(scope, "effect_all_effect");
+    let thunk_symbol = new_symbol!(scope, "effect_all_thunk");
+    let force_closure_symbol = new_symbol!(scope, "effect_all_force");
+    let inner_closure_symbol = new_symbol!(scope, "effect_all_inner");
+    let all_symbol = new_symbol!(scope, "all");
+
+    // \@Effect thunk -> thunk {}
+    let force_closure = {
+        let body = force_effect(
+            Expr::Var(effect_arg_symbol, var_store.fresh()),
+            effect_symbol,
+            thunk_symbol,
+            var_store,
+        );
+
+        let arguments = vec![(
+            var_store.fresh(),
+            AnnotatedMark::new(var_store),
+            Loc::at_zero(Pattern::Identifier(effect_arg_symbol)),
+        )];
+
+        Expr::Closure(ClosureData {
+            function_type: var_store.fresh(),
+            closure_type: var_store.fresh(),
+            return_type: var_store.fresh(),
+            name: force_closure_symbol,
+            captured_symbols: Vec::new(),
+            recursive: Recursive::NotRecursive,
+            arguments,
+            loc_body: Box::new(Loc::at_zero(body)),
+        })
+    };
+
+    // `List.map effects \@Effect thunk -> thunk {}`
+    let map_call = {
+        let map_var = var_store.fresh();
+        let boxed = (
+            map_var,
+            Loc::at_zero(Expr::Var(Symbol::LIST_MAP, map_var)),
+            var_store.fresh(),
+            var_store.fresh(),
+        );
+
+        let arguments = vec![
+            (
+                var_store.fresh(),
+                Loc::at_zero(Expr::Var(effects_symbol, var_store.fresh())),
+            ),
+            (var_store.fresh(), Loc::at_zero(force_closure)),
+        ];
+        Expr::Call(Box::new(boxed), arguments, CalledVia::Space)
+    };
+
+    // `@Effect \{} -> List.map effects \@Effect thunk -> thunk {}`
+    let body = wrap_in_effect_thunk(
+        map_call,
+        effect_symbol,
+        inner_closure_symbol,
+        vec![effects_symbol],
+        var_store,
+    );
+
+    let arguments = vec![(
+        var_store.fresh(),
+        AnnotatedMark::new(var_store),
+        Loc::at_zero(Pattern::Identifier(effects_symbol)),
+    )];
+
+    let function_var = var_store.fresh();
+    let all_closure = Expr::Closure(ClosureData {
+        function_type: function_var,
+        closure_type: var_store.fresh(),
+        return_type: var_store.fresh(),
+        name: all_symbol,
+        captured_symbols: Vec::new(),
+        recursive: Recursive::NotRecursive,
+        arguments,
+        loc_body: Box::new(Loc::at_zero(body)),
+    });
+
+    let mut introduced_variables = IntroducedVariables::default();
+
+    let signature = {
+        // Effect.all : List (Effect a) -> Effect (List a)
+        let var_a = var_store.fresh();
+        introduced_variables.insert_named("a".into(), Loc::at_zero(var_a));
+
+        let effect_a = build_effect_opaque(
+            effect_symbol,
+            var_a,
+            Type::Variable(var_a),
+            var_store,
+            &mut introduced_variables,
+        );
+
+        let list_a = Type::Apply(
+            Symbol::LIST_LIST,
+            vec![Loc::at_zero(Type::Variable(var_a))],
+            Region::zero(),
+        );
+        let effect_list_a = {
+            let closure_var = var_store.fresh();
+            introduced_variables.insert_lambda_set(closure_var);
+
+            let actual = Type::Function(
+                vec![Type::EmptyRec],
+                Box::new(Type::Variable(closure_var)),
+                Box::new(list_a.clone()),
+            );
+
+            Type::Alias {
+                symbol: effect_symbol,
+                type_arguments: vec![OptAbleType::unbound(list_a)],
+                lambda_set_variables: vec![roc_types::types::LambdaSet(Type::Variable(
+                    closure_var,
+                ))],
+                infer_ext_in_output_types: vec![],
+                actual: Box::new(actual),
+                kind: AliasKind::Opaque,
+            }
+        };
+
+        let list_effect_a = Type::Apply(
+            Symbol::LIST_LIST,
+            vec![Loc::at_zero(effect_a)],
+            Region::zero(),
+        );
+
+        let closure_var = var_store.fresh();
+        introduced_variables.insert_wildcard(Loc::at_zero(closure_var));
+
+        Type::Function(
+            vec![list_effect_a],
+            Box::new(Type::Variable(closure_var)),
+            Box::new(effect_list_a),
+        )
+    };
+
+    let def_annotation = crate::def::Annotation {
+        signature,
+        introduced_variables,
+        aliases: VecMap::default(),
+        region: Region::zero(),
+    };
+
+    let pattern = Pattern::Identifier(all_symbol);
+    let mut pattern_vars = SendMap::default();
+    pattern_vars.insert(all_symbol, function_var);
+    let def = Def {
+        loc_pattern: Loc::at_zero(pattern),
+        loc_expr: Loc::at_zero(all_closure),
+        expr_var: function_var,
+        pattern_vars,
+        annotation: Some(def_annotation),
+    };
+
+    (all_symbol, def)
+}
+
 pub fn build_host_exposed_def(
     scope: &mut Scope,
     symbol: Symbol,
@@ -1,19 +1,19 @@
 use crate::expr::{self, IntValue, WhenBranch};
 use crate::pattern::DestructType;
-use roc_collections::all::HumanIndex;
+use roc_collections::all::{HumanIndex, MutSet};
 use roc_collections::VecMap;
 use roc_error_macros::internal_error;
 use roc_exhaustive::{
     is_useful, Ctor, CtorName, Error, Guard, ListArity, Literal, Pattern, RenderAs, TagId, Union,
 };
 use roc_module::ident::{Lowercase, TagIdIntType, TagName};
-use roc_module::symbol::Symbol;
+use roc_module::symbol::{ModuleId, Symbol};
 use roc_region::all::{Loc, Region};
 use roc_types::subs::{
     Content, FlatType, GetSubsSlice, RedundantMark, SortedTagsIterator, Subs, SubsFmtContent,
     Variable,
 };
-use roc_types::types::{gather_tags_unsorted_iter, AliasKind};
+use roc_types::types::{gather_tags_unsorted_iter, AliasKind, RecordField};
 
 pub use roc_exhaustive::Context as ExhaustiveContext;
 
@@ -49,10 +49,20 @@ pub fn check(
     } = sketched_rows.reify_to_non_redundant(subs, real_var)?;
     all_errors.extend(errors);
 
+    let literals = UsedLiterals::of_rows(&non_redundant_rows);
+
     let exhaustive = match roc_exhaustive::check(overall_region, context, non_redundant_rows) {
         Ok(()) => true,
         Err(errors) => {
-            all_errors.extend(errors);
+            all_errors.extend(errors.into_iter().map(|error| match error {
+                Error::Incomplete(region, context, missing, _) => {
+                    let example = (missing.first())
+                        .and_then(|pattern| example_value(subs, real_var, pattern, &literals));
+
+                    Error::Incomplete(region, context, missing, example)
+                }
+                error => error,
+            }));
             false
         }
     };
@@ -650,6 +660,288 @@ fn is_inhabited_pattern(pat: &Pattern) -> bool {
     true
 }
 
+/// EXAMPLES
+
+/// Examples of nested values stop at this depth, so recursive types don't go on forever.
+const MAX_EXAMPLE_DEPTH: usize = 8;
+
+/// The number and string literals that appear anywhere in the patterns being checked.
+/// Examples avoid all of them, so they can't be handled by a branch that matches a literal.
+#[derive(Default)]
+struct UsedLiterals {
+    numbers: MutSet<i128>,
+    strings: MutSet<Box<str>>,
+}
+
+impl UsedLiterals {
+    fn of_rows(rows: &[Vec<Pattern>]) -> Self {
+        let mut literals = Self::default();
+        let mut stack: Vec<&Pattern> = rows.iter().flatten().collect();
+
+        while let Some(pattern) = stack.pop() {
+            match pattern {
+                Pattern::Anything => {}
+                Pattern::Literal(literal) => literals.add(literal),
+                Pattern::Ctor(_, _, args) | Pattern::List(_, args) => stack.extend(args),
+            }
+        }
+
+        literals
+    }
+
+    fn add(&mut self, literal: &Literal) {
+        match literal {
+            Literal::Int(bytes) => {
+                self.numbers.insert(i128::from_ne_bytes(*bytes));
+            }
+            Literal::U128(bytes) => {
+                if let Ok(n) = i128::try_from(u128::from_ne_bytes(*bytes)) {
+                    self.numbers.insert(n);
+                }
+            }
+            Literal::Byte(byte) => {
+                self.numbers.insert(*byte as i128);
+            }
+            Literal::Float(bits) => {
+                let float = f64::from_bits(*bits);
+
+                if float.fract() == 0.0 {
+                    self.numbers.insert(float as i128);
+                }
+            }
+            Literal::Str(string) => {
+                self.strings.insert(string.clone());
+            }
+            Literal::Bit(_) | Literal::Decimal(_) => {}
+        }
+    }
+
+    fn unused_number(&self) -> Pattern {
+        let n = (0..).find(|n| !self.numbers.contains(n)).unwrap();
+
+        Pattern::Literal(Literal::Int(n.to_ne_bytes()))
+    }
+
+    fn unused_string(&self) -> Pattern {
+        let string = (0..)
+            .map(|len| "a".repeat(len).into_boxed_str())
+            .find(|string| !self.strings.contains(string))
+            .unwrap();
+
+        Pattern::Literal(Literal::Str(string))
+    }
+}
+
+/// Makes a value that isn't handled, by filling in the wildcards of a pattern that isn't covered,
+/// e.g. `Ok { name: _, age: Just _ }` becomes `Ok { name: "", age: Just 0 }`.
+///
+/// Only gives an example if the pattern has wildcards nested inside of it, since otherwise the
+/// pattern says it all, and only if every wildcard could be filled in.
+fn example_value(
+    subs: &Subs,
+    var: Variable,
+    missing: &Pattern,
+    literals: &UsedLiterals,
+) -> Option<Pattern> {
+    let has_nested_wildcard =
+        matches!(missing, Pattern::Ctor(..) | Pattern::List(..)) && !is_wildcard_free(missing);
+
+    if !has_nested_wildcard {
+        return None;
+    }
+
+    fill_wildcards(subs, var, missing, literals, 0)
+}
+
+fn is_wildcard_free(pattern: &Pattern) -> bool {
+    match pattern {
+        Pattern::Anything => false,
+        Pattern::Literal(_) => true,
+        Pattern::Ctor(_, _, args) | Pattern::List(_, args) => args.iter().all(is_wildcard_free),
+    }
+}
+
+fn fill_wildcards(
+    subs: &Subs,
+    var: Variable,
+    pattern: &Pattern,
+    literals: &UsedLiterals,
+    depth: usize,
+) -> Option<Pattern> {
+    match pattern {
+        Pattern::Anything => example_of_type(subs, var, literals, depth),
+        Pattern::Literal(literal) => Some(Pattern::Literal(literal.clone())),
+        Pattern::Ctor(union, tag_id, args) => {
+            let is_open_ctor = union.alternatives.iter().any(|ctor| {
+                ctor.tag_id == *tag_id && ctor.name.is_tag(&TagName(NONEXHAUSIVE_CTOR.into()))
+            });
+
+            // Guards have no value to show, and there's no way to know which tags an open
+            // union doesn't mention.
+            if matches!(union.render_as, RenderAs::Guard) || is_open_ctor {
+                return None;
+            }
+
+            let index_ctor = IndexCtor::of_union(union, *tag_id);
+            let arg_vars = index_var(subs, var, index_ctor, &union.render_as).ok()?;
+
+            let args = (args.iter())
+                .zip(arg_vars)
+                .map(|(arg, var)| fill_wildcards(subs, var, arg, literals, depth + 1))
+                .collect::<Option<Vec<_>>>()?;
+
+            Some(Pattern::Ctor(union.clone(), *tag_id, args))
+        }
+        Pattern::List(_, elems) => {
+            let elem_var = index_var(subs, var, IndexCtor::List, &RenderAs::Tag).ok()?[0];
+
+            // Whatever the `..` stands for can be left out
+            let elems = (elems.iter())
+                .map(|elem| fill_wildcards(subs, elem_var, elem, literals, depth + 1))
+                .collect::<Option<Vec<_>>>()?;
+
+            Some(Pattern::List(ListArity::Exact(elems.len()), elems))
+        }
+    }
+}
+
+/// The simplest value of a type, as a pattern without wildcards.
+fn example_of_type(
+    subs: &Subs,
+    var: Variable,
+    literals: &UsedLiterals,
+    depth: usize,
+) -> Option<Pattern> {
+    if depth > MAX_EXAMPLE_DEPTH || var == Variable::NULL {
+        return None;
+    }
+
+    let single_ctor = |render_as: RenderAs, name: CtorName, args: Vec<Pattern>| {
+        let tag_id = TagId(0);
+        let union = Union {
+            alternatives: vec![Ctor {
+                name,
+                tag_id,
+                arity: args.len(),
+            }],
+            render_as,
+        };
+
+        Pattern::Ctor(union, tag_id, args)
+    };
+
+    match subs.get_content_without_compacting(var) {
+        Content::FlexVar(_)
+        | Content::RigidVar(_)
+        | Content::FlexAbleVar(_, _)
+        | Content::RigidAbleVar(_, _)
+        | Content::LambdaSet(_)
+        | Content::Error => None,
+        Content::RangedNumber(..) => Some(literals.unused_number()),
+        Content::RecursionVar { structure, .. } => {
+            example_of_type(subs, *structure, literals, depth)
+        }
+        Content::Alias(Symbol::BOOL_BOOL, ..) => Some(Pattern::Literal(Literal::Bit(false))),
+        Content::Alias(symbol, ..) if symbol.module_id() == ModuleId::NUM => {
+            Some(literals.unused_number())
+        }
+        Content::Alias(symbol, _, real_var, AliasKind::Opaque) => {
+            let arg = example_of_type(subs, *real_var, literals, depth + 1)?;
+
+            Some(single_ctor(
+                RenderAs::Opaque,
+                CtorName::Opaque(*symbol),
+                vec![arg],
+            ))
+        }
+        Content::Alias(_, _, real_var, AliasKind::Structural) => {
+            example_of_type(subs, *real_var, literals, depth)
+        }
+        Content::Structure(flat_type) => match flat_type {
+            FlatType::Apply(Symbol::STR_STR, _) => Some(literals.unused_string()),
+            FlatType::Apply(Symbol::LIST_LIST, _) => {
+                Some(Pattern::List(ListArity::Exact(0), vec![]))
+            }
+            FlatType::Apply(..) | FlatType::Func(..) | FlatType::EmptyTagUnion => None,
+            FlatType::Record(fields, ext) => {
+                let mut names = Vec::with_capacity(fields.len());
+                let mut args = Vec::with_capacity(fields.len());
+
+                for (name, field) in fields.sorted_iterator(subs, *ext) {
+                    if matches!(
+                        field,
+                        RecordField::Optional(_) | RecordField::RigidOptional(_)
+                    ) {
+                        continue;
+                    }
+
+                    names.push(name);
+                    args.push(example_of_type(
+                        subs,
+                        field.into_inner(),
+                        literals,
+                        depth + 1,
+                    )?);
+                }
+
+                Some(single_ctor(
+                    RenderAs::Record(names),
+                    CtorName::Tag(TagName("#Record".into())),
+                    args,
+                ))
+            }
+            FlatType::EmptyRecord => Some(single_ctor(
+                RenderAs::Record(vec![]),
+                CtorName::Tag(TagName("#Record".into())),
+                vec![],
+            )),
+            FlatType::Tuple(elems, ext) => {
+                let args = (elems.sorted_iterator(subs, *ext))
+                    .map(|(_, elem)| example_of_type(subs, elem, literals, depth + 1))
+                    .collect::<Option<Vec<_>>>()?;
+
+                Some(single_ctor(
+                    RenderAs::Tuple,
+                    CtorName::Tag(TagName("#Record".into())),
+                    args,
+                ))
+            }
+            FlatType::EmptyTuple => Some(single_ctor(
+                RenderAs::Tuple,
+                CtorName::Tag(TagName("#Record".into())),
+                vec![],
+            )),
+            FlatType::TagUnion(tags, ext) | FlatType::RecursiveTagUnion(_, tags, ext) => {
+                // Tags with fewer arguments make for shorter examples, and are less likely to
+                // recurse.
+                let mut tags: Vec<_> = tags.sorted_iterator_and_ext(subs, *ext).0.collect();
+                tags.sort_by_key(|(_, args)| args.len());
+
+                tags.into_iter().find_map(|(tag_name, arg_vars)| {
+                    let (union, tag_id) = convert_tag(subs, var, &tag_name);
+
+                    if !union.alternatives.iter().any(|ctor| ctor.tag_id == tag_id) {
+                        // This tag can never be constructed
+                        return None;
+                    }
+
+                    let args = (arg_vars.iter())
+                        .map(|arg_var| example_of_type(subs, *arg_var, literals, depth + 1))
+                        .collect::<Option<Vec<_>>>()?;
+
+                    Some(Pattern::Ctor(union, tag_id, args))
+                })
+            }
+            FlatType::FunctionOrTagUnion(tags, _, _) => {
+                let tag_name = subs.get_subs_slice(*tags).first()?;
+                let (union, tag_id) = convert_tag(subs, var, tag_name);
+
+                Some(Pattern::Ctor(union, tag_id, vec![]))
+            }
+        },
+    }
+}
+
 fn convert_tag(subs: &Subs, whole_var: Variable, this_tag: &TagName) -> (Union, TagId) {
     let content = subs.get_content_without_compacting(whole_var);
 
@@ -14,7 +14,7 @@ use crate::traverse::{walk_expr, Visitor};
 use roc_collections::soa::Index;
 use roc_collections::{SendMap, VecMap, VecSet};
 use roc_error_macros::internal_error;
-use roc_module::called_via::CalledVia;
+use roc_module::called_via::{BinOp, CalledVia};
 use roc_module::ident::{ForeignSymbol, Lowercase, TagName};
 use roc_module::low_level::LowLevel;
 use roc_module::symbol::Symbol;
@@ -289,6 +289,18 @@ pub struct ExpectLookup {
     pub ability_info: Option<SpecializationId>,
 }
 
+/// What a failing `expect` reports.
+#[derive(Clone, Debug, Default)]
+pub struct ExpectLookups {
+    pub lookups: Vec<ExpectLookup>,
+    /// The record fields the condition reads off a lookup, e.g. `age` for `user.age`.
+    /// When a lookup is a big record, only these fields are shown.
+    pub accessed_fields: Vec<(Symbol, Lowercase)>,
+    /// The lookups on both sides of a condition like `actual == expected`, so a failure can
+    /// show where their values differ.
+    pub compared: Option<(Symbol, Symbol)>,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct DbgLookup {
     pub symbol: Symbol,
@@ -2853,6 +2865,7 @@ impl Declarations {
         let mut collector = ExpectCollector {
             expects: VecMap::default(),
             dbgs: VecMap::default(),
+            closure_captures: VecMap::default(),
         };
 
         let var = Variable::EMPTY_RECORD;
@@ -2934,11 +2947,17 @@ pub struct DestructureDef {
     pub pattern_vars: VecMap<Symbol, Variable>,
 }
 
+/// The symbols an `expect` condition looks up, so their values can be shown when it fails.
+///
+/// Symbols bound inside a closure or a branch of the condition are left out: they are not in
+/// scope where the condition's result is checked. Symbols the closure captures are kept.
 pub(crate) fn get_lookup_symbols(expr: &Expr) -> Vec<ExpectLookup> {
-    let mut stack: Vec<&Expr> = vec![expr];
+    // Each expression comes with whether it is inside a closure or branch of the condition.
+    let mut stack: Vec<(&Expr, bool)> = vec![(expr, false)];
     let mut lookups: Vec<ExpectLookup> = Vec::new();
+    let mut nested_bindings: Vec<Symbol> = Vec::new();
 
-    while let Some(expr) = stack.pop() {
+    while let Some((expr, nested)) = stack.pop() {
         match expr {
             Expr::Var(symbol, var)
             | Expr::RecordUpdate {
@@ -2965,20 +2984,26 @@ pub(crate) fn get_lookup_symbols(expr: &Expr) -> Vec<ExpectLookup> {
                 }
             }
             Expr::List { loc_elems, .. } => {
-                stack.extend(loc_elems.iter().map(|loc_elem| &loc_elem.value));
+                stack.extend(loc_elems.iter().map(|loc_elem| (&loc_elem.value, nested)));
             }
             Expr::When {
                 loc_cond, branches, ..
             } => {
-                stack.push(&loc_cond.value);
+                stack.push((&loc_cond.value, nested));
 
                 stack.reserve(branches.len());
 
                 for branch in branches {
-                    stack.push(&branch.value.value);
+                    stack.push((&branch.value.value, true));
 
                     if let Some(guard) = &branch.guard {
-                        stack.push(&guard.value);
+                        stack.push((&guard.value, true));
+                    }
+
+                    for pattern in &branch.patterns {
+                        nested_bindings.extend(
+                            BindingsFromPattern::new(&pattern.pattern).map(|(symbol, _)| symbol),
+                        );
                     }
                 }
             }
@@ -2989,61 +3014,78 @@ pub(crate) fn get_lookup_symbols(expr: &Expr) -> Vec<ExpectLookup> {
             } => {
                 stack.reserve(1 + branches.len() * 2);
 
-                for (loc_cond, loc_body) in branches {
-                    stack.push(&loc_cond.value);
-                    stack.push(&loc_body.value);
+                // Only the first condition is always evaluated; the rest is inside a branch.
+                for (index, (loc_cond, loc_body)) in branches.iter().enumerate() {
+                    stack.push((&loc_cond.value, nested || index > 0));
+                    stack.push((&loc_body.value, true));
                 }
 
-                stack.push(&final_else.value);
+                stack.push((&final_else.value, true));
             }
             Expr::LetRec(defs, expr, _illegal_cycle_mark) => {
                 for def in defs {
-                    stack.push(&def.loc_expr.value);
+                    if nested {
+                        nested_bindings.extend(def.pattern_vars.keys().copied());
+                    }
+
+                    stack.push((&def.loc_expr.value, nested));
                 }
-                stack.push(&expr.value);
+                stack.push((&expr.value, nested));
             }
             Expr::LetNonRec(def, expr) => {
-                stack.push(&def.loc_expr.value);
-                stack.push(&expr.value);
+                if nested {
+                    nested_bindings.extend(def.pattern_vars.keys().copied());
+                }
+
+                stack.push((&def.loc_expr.value, nested));
+                stack.push((&expr.value, nested));
             }
             Expr::Call(boxed_expr, args, _called_via) => {
                 stack.reserve(1 + args.len());
 
                 match &boxed_expr.1.value {
-                    Expr::Var(_, _) => {
-                        // do nothing
+                    Expr::Var(_, _) | Expr::AbilityMember(..) => {
+                        // a function called by name has nothing to show
                     }
                     function_expr => {
                         // add the expr being called
-                        stack.push(function_expr);
+                        stack.push((function_expr, nested));
                     }
                 }
 
                 for (_var, loc_arg) in args {
-                    stack.push(&loc_arg.value);
+                    stack.push((&loc_arg.value, nested));
                 }
             }
             Expr::Tag { arguments, .. } => {
-                stack.extend(arguments.iter().map(|(_var, loc_expr)| &loc_expr.value));
+                stack.extend((arguments.iter()).map(|(_var, loc_expr)| (&loc_expr.value, nested)));
             }
             Expr::RunLowLevel { args, .. } | Expr::ForeignCall { args, .. } => {
-                stack.extend(args.iter().map(|(_var, arg)| arg));
+                stack.extend(args.iter().map(|(_var, arg)| (arg, nested)));
             }
             Expr::OpaqueRef { argument, .. } => {
-                stack.push(&argument.1.value);
+                stack.push((&argument.1.value, nested));
+            }
+            Expr::RecordAccess { loc_expr, .. } | Expr::TupleAccess { loc_expr, .. } => {
+                stack.push((&loc_expr.value, nested));
             }
-            Expr::RecordAccess { loc_expr, .. }
-            | Expr::TupleAccess { loc_expr, .. }
-            | Expr::Closure(ClosureData {
-                loc_body: loc_expr, ..
+            Expr::Closure(ClosureData {
+                loc_body,
+                arguments,
+                ..
             }) => {
-                stack.push(&loc_expr.value);
+                nested_bindings.extend(
+                    BindingsFromPattern::new_many(arguments.iter().map(|(_, _, pattern)| pattern))
+                        .map(|(symbol, _)| symbol),
+                );
+
+                stack.push((&loc_body.value, true));
             }
             Expr::Record { fields, .. } => {
-                stack.extend(fields.iter().map(|(_, field)| &field.loc_expr.value));
+                stack.extend((fields.iter()).map(|(_, field)| (&field.loc_expr.value, nested)));
             }
             Expr::Tuple { elems, .. } => {
-                stack.extend(elems.iter().map(|(_, elem)| &elem.value));
+                stack.extend(elems.iter().map(|(_, elem)| (&elem.value, nested)));
             }
             Expr::Expect {
                 loc_continuation, ..
@@ -3054,12 +3096,12 @@ pub(crate) fn get_lookup_symbols(expr: &Expr) -> Vec<ExpectLookup> {
             | Expr::Dbg {
                 loc_continuation, ..
             } => {
-                stack.push(&loc_continuation.value);
+                stack.push((&loc_continuation.value, nested));
 
                 // Intentionally ignore the lookups in the nested `expect` condition itself,
                 // because they couldn't possibly influence the outcome of this `expect`!
             }
-            Expr::Crash { msg, .. } => stack.push(&msg.value),
+            Expr::Crash { msg, .. } => stack.push((&msg.value, nested)),
             Expr::Num(_, _, _, _)
             | Expr::Float(_, _, _, _, _)
             | Expr::Int(_, _, _, _, _)
@@ -3075,6 +3117,8 @@ pub(crate) fn get_lookup_symbols(expr: &Expr) -> Vec<ExpectLookup> {
         }
     }
 
+    lookups.retain(|lookup| !nested_bindings.contains(&lookup.symbol));
+
     lookups
 }
 
@@ -3180,8 +3224,11 @@ fn toplevel_expect_to_inline_expect_help(mut loc_expr: Loc<Expr>, has_effects: b
 }
 
 pub struct ExpectCollector {
-    pub expects: VecMap<Region, Vec<ExpectLookup>>,
+    pub expects: VecMap<Region, ExpectLookups>,
     pub dbgs: VecMap<Symbol, DbgLookup>,
+    /// The symbols each closure captures, sorted like its lambda set sorts them, so a failing
+    /// expect can show the captured values of a function by name.
+    pub closure_captures: VecMap<Symbol, Vec<Symbol>>,
 }
 
 impl crate::traverse::Visitor for ExpectCollector {
@@ -3197,8 +3244,16 @@ impl crate::traverse::Visitor for ExpectCollector {
                 loc_condition,
                 ..
             } => {
-                self.expects
-                    .insert(loc_condition.region, lookups_in_cond.to_vec());
+                let mut accessed_fields = AccessedFields::default();
+                accessed_fields.visit_expr(&loc_condition.value, loc_condition.region, var);
+
+                let lookups = ExpectLookups {
+                    lookups: lookups_in_cond.to_vec(),
+                    accessed_fields: accessed_fields.0,
+                    compared: compared_lookups(&loc_condition.value),
+                };
+
+                self.expects.insert(loc_condition.region, lookups);
             }
             Expr::Dbg {
                 loc_condition,
@@ -3215,9 +3270,59 @@ impl crate::traverse::Visitor for ExpectCollector {
 
                 self.dbgs.insert(*symbol, lookup);
             }
+            Expr::Closure(ClosureData {
+                name,
+                captured_symbols,
+                ..
+            }) if !captured_symbols.is_empty() => {
+                let mut captures: Vec<Symbol> =
+                    captured_symbols.iter().map(|(symbol, _)| *symbol).collect();
+                captures.sort();
+
+                self.closure_captures.insert(*name, captures);
+            }
             _ => (),
         }
 
         walk_expr(self, expr, var)
     }
 }
+
+/// The two lookups that a condition like `actual == expected` compares.
+fn compared_lookups(condition: &Expr) -> Option<(Symbol, Symbol)> {
+    let args = match condition {
+        Expr::Call(_, args, CalledVia::BinOp(BinOp::Equals)) => args,
+        _ => return None,
+    };
+
+    match args.as_slice() {
+        [(_, left), (_, right)] => match (&left.value, &right.value) {
+            (Expr::Var(left, _), Expr::Var(right, _)) => Some((*left, *right)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The fields read directly off a variable, like `age` in `user.age`.
+#[derive(Default)]
+struct AccessedFields(Vec<(Symbol, Lowercase)>);
+
+impl crate::traverse::Visitor for AccessedFields {
+    fn visit_expr(&mut self, expr: &Expr, _region: Region, var: Variable) {
+        if let Expr::RecordAccess {
+            loc_expr, field, ..
+        } = expr
+        {
+            if let Expr::Var(symbol, _) = loc_expr.value {
+                let access = (symbol, field.clone());
+
+                if !self.0.contains(&access) {
+                    self.0.push(access);
+                }
+            }
+        }
+
+        walk_expr(self, expr, var)
+    }
+}
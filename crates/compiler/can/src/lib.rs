@@ -18,6 +18,7 @@ pub mod env;
 pub mod exhaustive;
 pub mod expected;
 pub mod expr;
+pub mod lint;
 pub mod module;
 pub mod num;
 pub mod operator;
@@ -0,0 +1,155 @@
+//! Lints over the can ast that are only run when asked for, like `roc check --deny dead-expect`.
+
+use roc_module::symbol::Symbol;
+use roc_problem::can::Problem;
+use roc_region::all::{Loc, Region};
+use roc_types::subs::Variable;
+
+use crate::{
+    expr::{Declarations, Expr, IntValue},
+    traverse::{walk_decl, walk_expr, DeclarationInfo, Visitor},
+};
+
+/// Finds every `expect` whose condition can be decided without running the program,
+/// like `expect Bool.true` or `expect x == x`.
+pub fn dead_expects(decls: &Declarations) -> Vec<Problem> {
+    let mut visitor = DeadExpects::default();
+    visitor.visit_decls(decls);
+
+    visitor.problems
+}
+
+#[derive(Default)]
+struct DeadExpects {
+    problems: Vec<Problem>,
+}
+
+impl DeadExpects {
+    fn check(&mut self, loc_condition: &Loc<Expr>) {
+        if let Some(Constant::Bool(always_passes)) = fold(&loc_condition.value) {
+            self.problems.push(Problem::DeadExpect {
+                region: loc_condition.region,
+                always_passes,
+            });
+        }
+    }
+}
+
+impl Visitor for DeadExpects {
+    fn visit_decl(&mut self, decl: DeclarationInfo<'_>) {
+        if let DeclarationInfo::Expectation { loc_condition } = decl {
+            self.check(loc_condition);
+        }
+
+        walk_decl(self, decl);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr, _region: Region, var: Variable) {
+        if let Expr::Expect { loc_condition, .. } | Expr::ExpectFx { loc_condition, .. } = expr {
+            self.check(loc_condition);
+        }
+
+        walk_expr(self, expr, var);
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Constant<'a> {
+    Bool(bool),
+    Int(i128),
+    Str(&'a str),
+}
+
+/// Evaluates an expression built only from literals, `Bool` operations, integer arithmetic and
+/// comparisons. Returns `None` as soon as anything depends on a value only known at runtime.
+fn fold(expr: &Expr) -> Option<Constant<'_>> {
+    match expr {
+        Expr::Var(Symbol::BOOL_TRUE, _) => Some(Constant::Bool(true)),
+        Expr::Var(Symbol::BOOL_FALSE, _) => Some(Constant::Bool(false)),
+        Expr::Num(_, _, value, _) | Expr::Int(_, _, _, value, _) => match *value {
+            IntValue::I128(bytes) => Some(Constant::Int(i128::from_ne_bytes(bytes))),
+            IntValue::U128(bytes) => i128::try_from(u128::from_ne_bytes(bytes))
+                .ok()
+                .map(Constant::Int),
+        },
+        Expr::Str(string) => Some(Constant::Str(string)),
+        // The defs in an `expect` block only matter through the final condition.
+        Expr::LetNonRec(_, body) | Expr::LetRec(_, body, _) => fold(&body.value),
+        Expr::Call(boxed, args, _) => {
+            let symbol = match boxed.1.value {
+                Expr::Var(symbol, _) | Expr::AbilityMember(symbol, _, _) => symbol,
+                _ => return None,
+            };
+
+            match args.as_slice() {
+                [(_, arg)] => fold_unary(symbol, &arg.value),
+                [(_, left), (_, right)] => fold_binary(symbol, &left.value, &right.value),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn fold_unary(symbol: Symbol, arg: &Expr) -> Option<Constant<'_>> {
+    match (symbol, fold(arg)?) {
+        (Symbol::BOOL_NOT, Constant::Bool(b)) => Some(Constant::Bool(!b)),
+        (Symbol::NUM_NEG, Constant::Int(n)) => n.checked_neg().map(Constant::Int),
+        _ => None,
+    }
+}
+
+fn fold_binary<'a>(symbol: Symbol, left: &'a Expr, right: &'a Expr) -> Option<Constant<'a>> {
+    // `x == x` holds whatever `x` is, so there is no need to know its value.
+    if same_variable(left, right) {
+        match symbol {
+            Symbol::BOOL_IS_EQ | Symbol::BOOL_STRUCTURAL_EQ => return Some(Constant::Bool(true)),
+            Symbol::BOOL_NEQ | Symbol::BOOL_STRUCTURAL_NOT_EQ => {
+                return Some(Constant::Bool(false))
+            }
+            // `x <= x` is false for NaN, but `x < x` never holds.
+            Symbol::NUM_LT | Symbol::NUM_GT => return Some(Constant::Bool(false)),
+            _ => {}
+        }
+    }
+
+    // `&&` and `||` are decided by one known side, even if the other one isn't.
+    match (symbol, fold(left), fold(right)) {
+        (Symbol::BOOL_AND, Some(Constant::Bool(false)), _)
+        | (Symbol::BOOL_AND, _, Some(Constant::Bool(false))) => Some(Constant::Bool(false)),
+        (Symbol::BOOL_OR, Some(Constant::Bool(true)), _)
+        | (Symbol::BOOL_OR, _, Some(Constant::Bool(true))) => Some(Constant::Bool(true)),
+        (Symbol::BOOL_AND | Symbol::BOOL_OR, Some(Constant::Bool(_)), Some(Constant::Bool(b))) => {
+            Some(Constant::Bool(b))
+        }
+        (_, Some(left), Some(right)) => fold_known_binary(symbol, left, right),
+        _ => None,
+    }
+}
+
+fn fold_known_binary<'a>(
+    symbol: Symbol,
+    left: Constant<'a>,
+    right: Constant<'a>,
+) -> Option<Constant<'a>> {
+    use Constant::*;
+
+    match (symbol, left, right) {
+        (Symbol::BOOL_IS_EQ | Symbol::BOOL_STRUCTURAL_EQ, left, right) => Some(Bool(left == right)),
+        (Symbol::BOOL_NEQ | Symbol::BOOL_STRUCTURAL_NOT_EQ, left, right) => {
+            Some(Bool(left != right))
+        }
+        (Symbol::NUM_LT, Int(a), Int(b)) => Some(Bool(a < b)),
+        (Symbol::NUM_LTE, Int(a), Int(b)) => Some(Bool(a <= b)),
+        (Symbol::NUM_GT, Int(a), Int(b)) => Some(Bool(a > b)),
+        (Symbol::NUM_GTE, Int(a), Int(b)) => Some(Bool(a >= b)),
+        (Symbol::NUM_ADD, Int(a), Int(b)) => a.checked_add(b).map(Int),
+        (Symbol::NUM_SUB, Int(a), Int(b)) => a.checked_sub(b).map(Int),
+        (Symbol::NUM_MUL, Int(a), Int(b)) => a.checked_mul(b).map(Int),
+        _ => None,
+    }
+}
+
+fn same_variable(left: &Expr, right: &Expr) -> bool {
+    matches!((left, right), (Expr::Var(a, _), Expr::Var(b, _)) if a == b)
+}
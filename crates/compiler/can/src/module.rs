@@ -4,7 +4,7 @@ use crate::def::{canonicalize_defs, Def};
 use crate::effect_module::HostedGeneratedFunctions;
 use crate::env::Env;
 use crate::expr::{
-    ClosureData, DbgLookup, Declarations, ExpectLookup, Expr, Output, PendingDerives,
+    ClosureData, DbgLookup, Declarations, ExpectLookups, Expr, Output, PendingDerives,
 };
 use crate::pattern::{BindingsFromPattern, Pattern};
 use crate::scope::Scope;
@@ -132,8 +132,9 @@ pub struct Module {
     pub aliases: MutMap<Symbol, (bool, Alias)>,
     pub rigid_variables: RigidVariables,
     pub abilities_store: PendingAbilitiesStore,
-    pub loc_expects: VecMap<Region, Vec<ExpectLookup>>,
+    pub loc_expects: VecMap<Region, ExpectLookups>,
     pub loc_dbgs: VecMap<Symbol, DbgLookup>,
+    pub closure_captures: VecMap<Symbol, Vec<Symbol>>,
 }
 
 #[derive(Debug, Default)]
@@ -156,8 +157,9 @@ pub struct ModuleOutput {
     pub symbols_from_requires: Vec<(Loc<Symbol>, Loc<Type>)>,
     pub pending_derives: PendingDerives,
     pub scope: Scope,
-    pub loc_expects: VecMap<Region, Vec<ExpectLookup>>,
+    pub loc_expects: VecMap<Region, ExpectLookups>,
     pub loc_dbgs: VecMap<Symbol, DbgLookup>,
+    pub closure_captures: VecMap<Symbol, Vec<Symbol>>,
 }
 
 fn validate_generate_with<'a>(
@@ -173,6 +175,7 @@ fn validate_generate_with<'a>(
             "always" => functions.always = true,
             "loop" => functions.loop_ = true,
             "forever" => functions.forever = true,
+            "all" => functions.all = true,
             other => {
                 // we don't know how to generate this function
                 let ident = Ident::from(other);
@@ -809,6 +812,7 @@ pub fn canonicalize_module_defs<'a>(
         pending_derives,
         loc_expects: collected.expects,
         loc_dbgs: collected.dbgs,
+        closure_captures: collected.closure_captures,
         exposed_symbols,
     }
 }
@@ -30,6 +30,7 @@ use crate::{synth_var, util::Env, DerivedBody};
 pub(crate) fn derive_hash(env: &mut Env<'_>, key: FlatHashKey, def_symbol: Symbol) -> DerivedBody {
     let (body_type, body) = match key {
         FlatHashKey::Record(fields) => hash_record(env, def_symbol, fields),
+        FlatHashKey::IntRecord(fields) => hash_int_record(env, def_symbol, fields),
         FlatHashKey::Tuple(arity) => hash_tuple(env, def_symbol, arity),
         FlatHashKey::TagUnion(tags) => {
             if tags.len() == 1 {
@@ -123,6 +124,136 @@ fn hash_record(env: &mut Env<'_>, fn_name: Symbol, fields: Vec<Lowercase>) -> (V
     )
 }
 
+fn hash_int_record(
+    env: &mut Env<'_>,
+    fn_name: Symbol,
+    fields: Vec<(Lowercase, IntLitWidth)>,
+) -> (Variable, Expr) {
+    // Suppose rcd = { f1: U32, f2: U8, f3: I16 }.
+    // Unlike other records, the field types are part of the derived impl here, because they
+    // decide where each field is packed.
+    let record_var = {
+        let int_fields = (fields.iter())
+            .map(|(name, width)| {
+                (
+                    name.clone(),
+                    RecordField::Required(int_lit_width_to_variable(*width)),
+                )
+            })
+            .collect::<Vec<(Lowercase, _)>>();
+        let fields = RecordFields::insert_into_subs(env.subs, int_fields);
+
+        synth_var(
+            env.subs,
+            Content::Structure(FlatType::Record(fields, Variable::EMPTY_RECORD)),
+        )
+    };
+
+    // Now, a hasher for this record is
+    //
+    // hash_rcd : hasher, { f1: U32, f2: U8, f3: I16 } -> hasher | hasher has Hasher
+    // hash_rcd = \hasher, rcd ->
+    //   Hash.addU64
+    //     hasher
+    //     (Hash.packInt (Hash.packInt (Hash.packInt 0 rcd.f1 32 0) rcd.f2 8 32) rcd.f3 16 40)
+    //
+    // Fields are packed in order, starting a new word whenever one doesn't fit in the current
+    // word, and each word is added with a single `Hash.addU64`. Since the fields are packed by
+    // value, the hash never depends on the padding between them in memory.
+    let rcd_sym = env.new_symbol("rcd");
+
+    let hasher_sym = env.new_symbol("hasher");
+    let hasher_var = synth_var(env.subs, Content::FlexAbleVar(None, Subs::AB_HASHER));
+
+    let mut words: Vec<Vec<(Lowercase, IntLitWidth, u8)>> = vec![];
+    let mut offset = 64;
+    for (field_name, width) in fields {
+        let bits = int_bits(width);
+
+        if offset + bits > 64 {
+            words.push(vec![]);
+            offset = 0;
+        }
+
+        words.last_mut().unwrap().push((field_name, width, offset));
+        offset += bits;
+    }
+
+    let (body_var, body) = words.into_iter().fold(
+        (hasher_var, Expr::Var(hasher_sym, hasher_var)),
+        |total_hasher, word_fields| {
+            let word = word_fields.into_iter().fold(
+                int_literal(IntLitWidth::U64, 0),
+                |word, (field_name, width, offset)| {
+                    let field_var = int_lit_width_to_variable(width);
+                    let field_access = Expr::RecordAccess {
+                        record_var,
+                        field_var,
+                        ext_var: env.subs.fresh_unnamed_flex_var(),
+                        loc_expr: Box::new(Loc::at_zero(Expr::Var(
+                            rcd_sym,
+                            env.subs.fresh_unnamed_flex_var(),
+                        ))),
+                        field: field_name,
+                    };
+
+                    call_builtin(
+                        env,
+                        Symbol::HASH_PACK_INT,
+                        vec![
+                            word,
+                            (field_var, field_access),
+                            int_literal(IntLitWidth::U8, int_bits(width) as _),
+                            int_literal(IntLitWidth::U8, offset as _),
+                        ],
+                    )
+                },
+            );
+
+            call_hash_ability_member(env, Symbol::HASH_ADD_U64, total_hasher, word)
+        },
+    );
+
+    // Finally, build the closure
+    // \hasher, rcd -> body
+    build_outer_derived_closure(
+        env,
+        fn_name,
+        (hasher_var, hasher_sym),
+        (record_var, Pattern::Identifier(rcd_sym)),
+        (body_var, body),
+    )
+}
+
+fn int_bits(width: IntLitWidth) -> u8 {
+    match width {
+        IntLitWidth::U8 | IntLitWidth::I8 => 8,
+        IntLitWidth::U16 | IntLitWidth::I16 => 16,
+        IntLitWidth::U32 | IntLitWidth::I32 => 32,
+        IntLitWidth::U64 | IntLitWidth::I64 => 64,
+        _ => internal_error!("{:?} fields are never packed", width),
+    }
+}
+
+fn int_literal(width: IntLitWidth, value: u64) -> (Variable, Expr) {
+    let (num_var, precision_var) = match width {
+        IntLitWidth::U8 => (Variable::U8, Variable::UNSIGNED8),
+        IntLitWidth::U64 => (Variable::U64, Variable::UNSIGNED64),
+        _ => internal_error!("no {:?} literals are needed", width),
+    };
+
+    (
+        num_var,
+        Expr::Int(
+            num_var,
+            precision_var,
+            format!("{}", value).into_boxed_str(),
+            IntValue::I128((value as i128).to_ne_bytes()),
+            IntBound::Exact(width),
+        ),
+    )
+}
+
 fn hash_tuple(env: &mut Env<'_>, fn_name: Symbol, arity: u32) -> (Variable, Expr) {
     // Suppose tup = (v1, ..., vn).
     // Build a generalized type t_tup = (t1, ..., tn), with fresh t1, ..., tn,
@@ -501,6 +632,47 @@ fn call_hash_ability_member(
     (this_out_hasher_var, call_hash)
 }
 
+/// Builds a call to a builtin function that is not an ability member, like `Hash.packInt`.
+fn call_builtin(
+    env: &mut Env<'_>,
+    function: Symbol,
+    arguments: Vec<(Variable, Expr)>,
+) -> (Variable, Expr) {
+    // typeof function
+    let exposed_fn_var = env.import_builtin_symbol_var(function);
+
+    // (typeof arg1), ..., (typeof argn) -[clos]-> ret
+    let this_arguments_slice =
+        VariableSubsSlice::insert_into_subs(env.subs, arguments.iter().map(|(var, _)| *var));
+    let this_clos_var = env.subs.fresh_unnamed_flex_var();
+    let this_ret_var = env.subs.fresh_unnamed_flex_var();
+    let this_fn_var = synth_var(
+        env.subs,
+        Content::Structure(FlatType::Func(
+            this_arguments_slice,
+            this_clos_var,
+            this_ret_var,
+        )),
+    );
+
+    env.unify(exposed_fn_var, this_fn_var);
+
+    let fn_data = Box::new((
+        this_fn_var,
+        Loc::at_zero(Expr::Var(function, this_fn_var)),
+        this_clos_var,
+        this_ret_var,
+    ));
+    let arguments = (arguments.into_iter())
+        .map(|(var, expr)| (var, Loc::at_zero(expr)))
+        .collect();
+
+    (
+        this_ret_var,
+        Expr::Call(fn_data, arguments, CalledVia::Space),
+    )
+}
+
 fn build_outer_derived_closure(
     env: &mut Env<'_>,
     fn_name: Symbol,
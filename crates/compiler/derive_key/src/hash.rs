@@ -2,7 +2,11 @@ use roc_module::{
     ident::{Lowercase, TagName},
     symbol::Symbol,
 };
-use roc_types::subs::{Content, FlatType, GetSubsSlice, Subs, Variable};
+use roc_types::{
+    num::IntLitWidth,
+    subs::{Content, FlatType, GetSubsSlice, Subs, Variable},
+    types::AliasKind,
+};
 
 use crate::{
     util::{check_derivable_ext_var, debug_name_record, debug_name_tag, debug_name_tuple},
@@ -21,6 +25,10 @@ pub enum FlatHash {
 pub enum FlatHashKey {
     // Unfortunate that we must allocate here, c'est la vie
     Record(Vec<Lowercase>),
+    /// A record of two or more fields that are all integers of at most 64 bits, like
+    /// `{ x : U16, y : U16 }`. Its fields are packed into words before they're hashed,
+    /// rather than added to the hasher one at a time.
+    IntRecord(Vec<(Lowercase, IntLitWidth)>),
     Tuple(u32),
     TagUnion(Vec<(TagName, u16)>),
 }
@@ -29,6 +37,14 @@ impl FlatHashKey {
     pub(crate) fn debug_name(&self) -> String {
         match self {
             FlatHashKey::Record(fields) => debug_name_record(fields),
+            FlatHashKey::IntRecord(fields) => {
+                let fields: Vec<_> = fields
+                    .iter()
+                    .map(|(name, width)| format!("{}:{}", name, width.type_str()).into())
+                    .collect();
+
+                debug_name_record(&fields)
+            }
             FlatHashKey::Tuple(arity) => debug_name_tuple(*arity),
             FlatHashKey::TagUnion(tags) => debug_name_tag(tags),
         }
@@ -54,6 +70,7 @@ impl FlatHash {
                     })?;
 
                     let mut field_names = Vec::with_capacity(fields.len());
+                    let mut int_widths = Vec::with_capacity(fields.len());
                     for (field_name, record_field) in fields_iter {
                         if record_field.is_optional() {
                             // Can't derive a concrete decoder for optional fields, since those are
@@ -61,6 +78,17 @@ impl FlatHash {
                             return Err(Underivable);
                         }
                         field_names.push(field_name.clone());
+                        int_widths.push(packable_int_width(subs, *record_field.as_inner()));
+                    }
+
+                    if field_names.len() >= 2 && int_widths.iter().all(Option::is_some) {
+                        let mut fields: Vec<_> = (field_names.into_iter())
+                            .zip(int_widths.into_iter().flatten())
+                            .collect();
+
+                        fields.sort_by(|(f1, _), (f2, _)| f1.cmp(f2));
+
+                        return Ok(Key(FlatHashKey::IntRecord(fields)));
                     }
 
                     field_names.sort();
@@ -157,6 +185,27 @@ impl FlatHash {
     }
 }
 
+/// The width of `var` if it is a fixed-width integer of at most 64 bits, which can be packed
+/// into a word together with other fields. Opaque types are never packed, since they may
+/// hash themselves differently.
+fn packable_int_width(subs: &Subs, var: Variable) -> Option<IntLitWidth> {
+    match *subs.get_content_without_compacting(var) {
+        Content::Alias(sym, _, real_var, kind) => match sym {
+            Symbol::NUM_U8 | Symbol::NUM_UNSIGNED8 => Some(IntLitWidth::U8),
+            Symbol::NUM_U16 | Symbol::NUM_UNSIGNED16 => Some(IntLitWidth::U16),
+            Symbol::NUM_U32 | Symbol::NUM_UNSIGNED32 => Some(IntLitWidth::U32),
+            Symbol::NUM_U64 | Symbol::NUM_UNSIGNED64 => Some(IntLitWidth::U64),
+            Symbol::NUM_I8 | Symbol::NUM_SIGNED8 => Some(IntLitWidth::I8),
+            Symbol::NUM_I16 | Symbol::NUM_SIGNED16 => Some(IntLitWidth::I16),
+            Symbol::NUM_I32 | Symbol::NUM_SIGNED32 => Some(IntLitWidth::I32),
+            Symbol::NUM_I64 | Symbol::NUM_SIGNED64 => Some(IntLitWidth::I64),
+            _ if kind == AliasKind::Structural => packable_int_width(subs, real_var),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 const fn builtin_symbol_to_hash_lambda(symbol: Symbol) -> Option<FlatHash> {
     use FlatHash::*;
     match symbol {
@@ -138,7 +138,9 @@ pub enum Literal {
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Error {
-    Incomplete(Region, Context, Vec<Pattern>),
+    /// The patterns that aren't covered, and a value that none of the branches handle, if one
+    /// could be made from the types of the uncovered patterns' wildcards.
+    Incomplete(Region, Context, Vec<Pattern>, Option<Pattern>),
     Redundant {
         overall_region: Region,
         branch_region: Region,
@@ -189,7 +191,7 @@ pub fn check(
         // if this debug_assert! ever fails, the theory is disproven
         debug_assert!(bad_patterns.iter().map(|v| v.len()).sum::<usize>() == bad_patterns.len());
         let heads = bad_patterns.into_iter().map(|mut v| v.remove(0)).collect();
-        errors.push(Error::Incomplete(region, context, heads));
+        errors.push(Error::Incomplete(region, context, heads, None));
         return Err(errors);
     }
     Ok(())
@@ -24,6 +24,12 @@ impl<'a> Formattable for Defs<'a> {
     ) {
         let mut prev_spaces = true;
 
+        let columns = if buf.options().align_annotations {
+            annotation_columns(self)
+        } else {
+            vec![None; self.tags.len()]
+        };
+
         for (index, def) in self.defs().enumerate() {
             let spaces_before = &self.spaces[self.space_before[index].indices()];
             let spaces_after = &self.spaces[self.space_after[index].indices()];
@@ -34,9 +40,19 @@ impl<'a> Formattable for Defs<'a> {
                 fmt_default_newline(buf, spaces_before, indent);
             }
 
-            match def {
-                Ok(type_def) => type_def.format(buf, indent),
-                Err(value_def) => value_def.format(buf, indent),
+            match (def, columns[index]) {
+                (
+                    Err(ValueDef::Annotation(
+                        Loc {
+                            value: Pattern::Identifier(name),
+                            ..
+                        },
+                        loc_annotation,
+                    )),
+                    Some(column),
+                ) => fmt_aligned_annotation(buf, name, &loc_annotation.value, column, indent),
+                (Ok(type_def), _) => type_def.format(buf, indent),
+                (Err(value_def), _) => value_def.format(buf, indent),
             }
 
             fmt_spaces(buf, spaces_after.iter(), indent);
@@ -46,6 +62,81 @@ impl<'a> Formattable for Defs<'a> {
     }
 }
 
+/// The name of an annotation that can be lined up with its neighbours: a plain name with a
+/// single-line type.
+fn alignable_annotation<'a>(def: Result<&TypeDef<'a>, &ValueDef<'a>>) -> Option<&'a str> {
+    match def {
+        Err(ValueDef::Annotation(loc_pattern, loc_annotation))
+            if !loc_annotation.is_multiline() =>
+        {
+            match loc_pattern.value {
+                Pattern::Identifier(name) => Some(name),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// For each def, the column its `:` should be pushed out to, if it's an annotation in a run of
+/// two or more on consecutive lines.
+fn annotation_columns(defs: &Defs) -> std::vec::Vec<Option<usize>> {
+    let names: std::vec::Vec<_> = defs.defs().map(alignable_annotation).collect();
+    let mut columns = vec![None; names.len()];
+
+    let mut start = 0;
+
+    while start < names.len() {
+        let mut end = start + 1;
+
+        if names[start].is_some() {
+            while end < names.len() && names[end].is_some() && on_next_line(defs, end - 1) {
+                end += 1;
+            }
+
+            if end - start > 1 {
+                let column = names[start..end]
+                    .iter()
+                    .flatten()
+                    .map(|name| name.chars().count())
+                    .max();
+
+                columns[start..end].fill(column);
+            }
+        }
+
+        start = end;
+    }
+
+    columns
+}
+
+/// Whether the def after this one starts on the very next line, with no comments in between.
+fn on_next_line(defs: &Defs, index: usize) -> bool {
+    let spaces_after = &defs.spaces[defs.space_after[index].indices()];
+    let spaces_before = &defs.spaces[defs.space_before[index + 1].indices()];
+
+    let mut between = spaces_after.iter().chain(spaces_before.iter());
+
+    between.clone().all(|space| space.is_newline()) && between.nth(1).is_none()
+}
+
+fn fmt_aligned_annotation(
+    buf: &mut Buf,
+    name: &str,
+    annotation: &TypeAnnotation,
+    column: usize,
+    indent: u16,
+) {
+    buf.indent(indent);
+    buf.push_str(name);
+    buf.spaces(column - name.chars().count() + 1);
+    buf.push_str(":");
+    buf.spaces(1);
+
+    annotation.format_with_options(buf, Parens::NotNeeded, Newlines::No, indent);
+}
+
 impl<'a> Formattable for TypeDef<'a> {
     fn is_multiline(&self) -> bool {
         use roc_parse::ast::TypeDef::*;
@@ -0,0 +1,203 @@
+//! Formats only the top-level defs an edit touched, so that formatting a large file on save
+//! takes time proportional to the edit rather than to the file.
+//!
+//! Top-level defs are found by looking at the lines around each edit, without parsing the rest
+//! of the file: a def starts on a line whose first character is a letter or `_`, along with any
+//! comment lines directly above it. Everything outside of the touched defs is left exactly as it
+//! was, so this assumes the previous source was already formatted.
+use std::ops::Range;
+
+use bumpalo::Bump;
+use roc_parse::{
+    module::{self, module_defs},
+    parser::Parser,
+    state::State,
+};
+
+use crate::{def::fmt_defs, Buf};
+
+/// One change that turned the previous source into the new source: the bytes at `old` in the
+/// previous source were replaced by the bytes at `new` in the new source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub old: Range<usize>,
+    pub new: Range<usize>,
+}
+
+/// Replacing the bytes at `range` in the new source with `text` formats the defs that were
+/// there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replacement<'a> {
+    pub range: Range<usize>,
+    pub text: &'a str,
+}
+
+/// Returns the replacements that format every top-level def touched by `edits`, which must be
+/// sorted and must not overlap. The replacements are sorted, and only cover changed defs.
+///
+/// Returns `None` if the edits don't describe how `previous` became `new`, if they touch the
+/// module header, or if a touched def doesn't parse (or doesn't parse once formatted). In those
+/// cases, the whole file should be formatted instead.
+pub fn format_changed_defs<'a>(
+    arena: &'a Bump,
+    previous: &str,
+    new: &'a str,
+    edits: &[Edit],
+) -> Option<Vec<Replacement<'a>>> {
+    if !edits_are_consistent(previous, new, edits) {
+        return None;
+    }
+
+    let (_, state) = module::parse_header(arena, State::new(new.as_bytes())).ok()?;
+    let header_end = state.pos().offset as usize;
+
+    let mut chunks: Vec<Range<usize>> = Vec::with_capacity(edits.len());
+
+    for edit in edits {
+        if edit.new.start < header_end {
+            return None;
+        }
+
+        let start = def_start_at_or_before(new, edit.new.start, header_end)?;
+        let end = def_start_after(new, start, edit.new.end).unwrap_or(new.len());
+
+        match chunks.last_mut() {
+            Some(last) if start < last.end => last.end = last.end.max(end),
+            _ => chunks.push(start..end),
+        }
+    }
+
+    chunks
+        .into_iter()
+        .map(|range| {
+            let text = format_chunk(arena, &new[range.clone()], range.end < new.len())?;
+
+            Some(Replacement { range, text })
+        })
+        .filter(|replacement| match replacement {
+            Some(Replacement { range, text }) => &new[range.clone()] != *text,
+            None => true,
+        })
+        .collect()
+}
+
+/// The bytes between edits are the same in both sources, so the gaps between them must be
+/// the same length, and every edit must fit in its source.
+fn edits_are_consistent(previous: &str, new: &str, edits: &[Edit]) -> bool {
+    let mut old_end = 0;
+    let mut new_end = 0;
+
+    for edit in edits {
+        if edit.old.start < old_end
+            || edit.new.start < new_end
+            || edit.old.start > edit.old.end
+            || edit.new.start > edit.new.end
+            || edit.old.start - old_end != edit.new.start - new_end
+        {
+            return false;
+        }
+
+        old_end = edit.old.end;
+        new_end = edit.new.end;
+    }
+
+    old_end <= previous.len()
+        && new_end <= new.len()
+        && previous.len() - old_end == new.len() - new_end
+        && new.is_char_boundary(new_end)
+        && edits
+            .iter()
+            .all(|edit| new.is_char_boundary(edit.new.start))
+}
+
+fn format_chunk<'a>(arena: &'a Bump, chunk: &'a str, followed_by_def: bool) -> Option<&'a str> {
+    let defs = parse_defs(arena, chunk)?;
+    let mut buf = Buf::new_in(arena);
+
+    fmt_defs(&mut buf, &defs, 0);
+    buf.fmt_end_of_file();
+
+    let mut formatted = buf.into_bump_str();
+
+    // The chunk is formatted as if it were the end of the file, which leaves no blank line
+    // after its last def. If there was one before the next def, formatting keeps it.
+    let ends_with_blank_line = chunk
+        .trim_end_matches(|c| c == ' ' || c == '\t' || c == '\r')
+        .ends_with("\n\n");
+
+    if followed_by_def && ends_with_blank_line && !formatted.ends_with("\n\n") {
+        formatted = bumpalo::format!(in arena, "{}\n", formatted).into_bump_str();
+    }
+
+    parse_defs(arena, formatted)?;
+
+    Some(formatted)
+}
+
+fn parse_defs<'a>(arena: &'a Bump, src: &'a str) -> Option<roc_parse::ast::Defs<'a>> {
+    module_defs()
+        .parse(arena, State::new(src.as_bytes()), 0)
+        .ok()
+        .map(|(_, defs, _)| defs)
+}
+
+fn line_start(src: &str, pos: usize) -> usize {
+    src[..pos].rfind('\n').map_or(0, |newline| newline + 1)
+}
+
+fn is_def_line(src: &str, line_start: usize) -> bool {
+    matches!(src.as_bytes().get(line_start), Some(b) if b.is_ascii_alphabetic() || *b == b'_')
+}
+
+fn is_comment_line(src: &str, line_start: usize) -> bool {
+    src.as_bytes().get(line_start) == Some(&b'#')
+}
+
+/// Moves `line` up over the comment lines directly above it, but never to before `limit`.
+fn include_comments_above(src: &str, mut line: usize, limit: usize) -> usize {
+    while line > limit {
+        let above = line_start(src, line - 1);
+
+        if above < limit || !is_comment_line(src, above) {
+            break;
+        }
+
+        line = above;
+    }
+
+    line
+}
+
+/// The start of the def that `pos` is in, or `None` if `pos` comes before the first def.
+fn def_start_at_or_before(src: &str, pos: usize, header_end: usize) -> Option<usize> {
+    let mut line = line_start(src, pos);
+
+    while !is_def_line(src, line) {
+        if line == 0 {
+            return None;
+        }
+
+        line = line_start(src, line - 1);
+    }
+
+    if line < header_end {
+        return None;
+    }
+
+    Some(include_comments_above(src, line, header_end))
+}
+
+/// The start of the first def after `start` that begins at or after `pos`, if there is one.
+fn def_start_after(src: &str, start: usize, pos: usize) -> Option<usize> {
+    let mut line = if pos > start && src.as_bytes()[pos - 1] == b'\n' {
+        pos
+    } else {
+        pos + src[pos..].find('\n')? + 1
+    };
+
+    while !is_def_line(src, line) {
+        line += src[line..].find('\n')? + 1;
+    }
+
+    Some(include_comments_above(src, line, pos.max(start + 1)))
+}
@@ -6,6 +6,7 @@ pub mod annotation;
 pub mod collection;
 pub mod def;
 pub mod expr;
+pub mod incremental;
 pub mod module;
 pub mod pattern;
 pub mod spaces;
@@ -19,24 +20,48 @@ pub struct Ast<'a> {
     pub defs: roc_parse::ast::Defs<'a>,
 }
 
+/// Opt-in formatting rules. They are all off by default, so code formatted without them
+/// looks the same everywhere; teams can turn them on for their own projects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Sort the `exposes` and `imports` lists of module headers, and the names exposed by each
+    /// import, alphabetically.
+    pub sort_imports: bool,
+    /// Put imports from packages (ordered by shorthand) before imports of local modules, and
+    /// ingested files last.
+    pub group_imports: bool,
+    /// Line up the `:` of consecutive single-line type annotations.
+    pub align_annotations: bool,
+}
+
 #[derive(Debug)]
 pub struct Buf<'a> {
     text: String<'a>,
     spaces_to_flush: usize,
     newlines_to_flush: usize,
     beginning_of_line: bool,
+    options: FormatOptions,
 }
 
 impl<'a> Buf<'a> {
     pub fn new_in(arena: &'a Bump) -> Buf<'a> {
+        Self::new_with_options_in(arena, FormatOptions::default())
+    }
+
+    pub fn new_with_options_in(arena: &'a Bump, options: FormatOptions) -> Buf<'a> {
         Buf {
             text: String::new_in(arena),
             spaces_to_flush: 0,
             newlines_to_flush: 0,
             beginning_of_line: true,
+            options,
         }
     }
 
+    pub fn options(&self) -> FormatOptions {
+        self.options
+    }
+
     pub fn as_str(&'a self) -> &'a str {
         self.text.as_str()
     }
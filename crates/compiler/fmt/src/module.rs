@@ -3,14 +3,16 @@ use crate::collection::{fmt_collection, Braces};
 use crate::expr::fmt_str_literal;
 use crate::spaces::RemoveSpaces;
 use crate::spaces::{fmt_comments_only, fmt_default_spaces, fmt_spaces, NewlineAt, INDENT};
-use crate::Buf;
+use crate::{Buf, FormatOptions};
+use bumpalo::collections::Vec;
 use bumpalo::Bump;
-use roc_parse::ast::{Collection, Header, Module, Spaced, Spaces};
+use roc_parse::ast::{Collection, ExtractSpaces, Header, Module, Spaced, Spaces};
 use roc_parse::header::{
     AppHeader, ExposedName, ExposesKeyword, GeneratesKeyword, HostedHeader, ImportsEntry,
     ImportsKeyword, InterfaceHeader, Keyword, KeywordItem, ModuleName, PackageEntry, PackageHeader,
     PackageKeyword, PackageName, PackagesKeyword, PlatformHeader, PlatformRequires,
-    ProvidesKeyword, ProvidesTo, RequiresKeyword, To, ToKeyword, TypedIdent, WithKeyword,
+    PlatformThreading, ProvidesKeyword, ProvidesTo, RequiresKeyword, ThreadingKeyword, To,
+    ToKeyword, TypedIdent, WithKeyword,
 };
 use roc_parse::ident::UppercaseIdent;
 use roc_region::all::Loc;
@@ -36,6 +38,133 @@ pub fn fmt_module<'a>(buf: &mut Buf<'_>, module: &'a Module<'a>) {
     }
 }
 
+/// Reorders the `exposes` and `imports` lists of a module header, as asked for by
+/// `options.sort_imports` and `options.group_imports`.
+///
+/// Unlike the rest of formatting, this changes the AST, so callers that check the formatted
+/// output reparses to the same AST should compare it against the reordered header.
+pub fn reorder_header<'a>(
+    arena: &'a Bump,
+    module: &Module<'a>,
+    options: FormatOptions,
+) -> Module<'a> {
+    let mut module = module.clone();
+
+    if !options.sort_imports && !options.group_imports {
+        return module;
+    }
+
+    match &mut module.header {
+        Header::Interface(header) => {
+            header.exposes.item = sort_exposes(arena, header.exposes.item, options);
+            header.imports.item = reorder_imports(arena, header.imports.item, options);
+        }
+        Header::Hosted(header) => {
+            header.exposes.item = sort_exposes(arena, header.exposes.item, options);
+            header.imports.item = reorder_imports(arena, header.imports.item, options);
+        }
+        Header::App(header) => {
+            if let Some(imports) = &mut header.imports {
+                imports.item = reorder_imports(arena, imports.item, options);
+            }
+        }
+        Header::Package(header) => {
+            header.exposes.item = sort_exposes(arena, header.exposes.item, options);
+        }
+        Header::Platform(header) => {
+            header.exposes.item = sort_exposes(arena, header.exposes.item, options);
+            header.imports.item = reorder_imports(arena, header.imports.item, options);
+        }
+    }
+
+    module
+}
+
+fn sort_exposes<'a, N: Copy + Into<&'a str>>(
+    arena: &'a Bump,
+    exposes: Collection<'a, Loc<Spaced<'a, N>>>,
+    options: FormatOptions,
+) -> Collection<'a, Loc<Spaced<'a, N>>> {
+    if options.sort_imports {
+        sort_items(arena, exposes, |name| name.into())
+    } else {
+        exposes
+    }
+}
+
+/// Package imports come first when grouping, ordered by shorthand; then imports of local
+/// modules; then ingested files. Sorting orders by shorthand, then module name. Ingested files
+/// keep the order they were written in.
+fn reorder_imports<'a>(
+    arena: &'a Bump,
+    imports: Collection<'a, Loc<Spaced<'a, ImportsEntry<'a>>>>,
+    options: FormatOptions,
+) -> Collection<'a, Loc<Spaced<'a, ImportsEntry<'a>>>> {
+    let imports = if options.sort_imports {
+        let entries = imports.items.iter().map(|entry| {
+            let value = map_spaced(arena, &entry.value, &|entry| match entry {
+                ImportsEntry::Module(name, exposed) => {
+                    ImportsEntry::Module(name, sort_exposes(arena, exposed, options))
+                }
+                ImportsEntry::Package(shorthand, name, exposed) => {
+                    ImportsEntry::Package(shorthand, name, sort_exposes(arena, exposed, options))
+                }
+                ImportsEntry::IngestedFile(..) => entry,
+            });
+
+            Loc::at(entry.region, value)
+        });
+
+        imports.replace_items(Vec::from_iter_in(entries, arena).into_bump_slice())
+    } else {
+        imports
+    };
+
+    sort_items(arena, imports, |entry| {
+        let (group, shorthand, name) = match entry {
+            ImportsEntry::Package(shorthand, name, _) => (0, shorthand, name.into()),
+            ImportsEntry::Module(name, _) => (1, "", name.into()),
+            ImportsEntry::IngestedFile(..) => (2, "", ""),
+        };
+
+        match (options.group_imports, options.sort_imports) {
+            (true, true) => (group, shorthand, name),
+            (true, false) => (group, shorthand, ""),
+            (false, _) => (0, shorthand, name),
+        }
+    })
+}
+
+/// Stably sorts the items of a collection by a key of their names. Comments before or after an
+/// item move along with it.
+fn sort_items<'a, T: Copy, K: Ord>(
+    arena: &'a Bump,
+    collection: Collection<'a, Loc<Spaced<'a, T>>>,
+    key: impl Fn(T) -> K,
+) -> Collection<'a, Loc<Spaced<'a, T>>> {
+    let mut items = Vec::from_iter_in(collection.items.iter().copied(), arena);
+
+    items.sort_by_key(|item| key(item.value.extract_spaces().item));
+
+    collection.replace_items(items.into_bump_slice())
+}
+
+fn map_spaced<'a, T: Copy>(
+    arena: &'a Bump,
+    spaced: &Spaced<'a, T>,
+    f: &impl Fn(T) -> T,
+) -> Spaced<'a, T> {
+    match spaced {
+        Spaced::Item(item) => Spaced::Item(f(*item)),
+        Spaced::SpaceBefore(inner, spaces) => {
+            Spaced::SpaceBefore(arena.alloc(map_spaced(arena, inner, f)), spaces)
+        }
+        Spaced::SpaceAfter(inner, spaces) => {
+            Spaced::SpaceAfter(arena.alloc(map_spaced(arena, inner, f)), spaces)
+        }
+    }
+}
+
 macro_rules! keywords {
     ($($name:ident),* $(,)?) => {
         $(
@@ -75,6 +204,7 @@ keywords! {
     RequiresKeyword,
     ProvidesKeyword,
     ToKeyword,
+    ThreadingKeyword,
 }
 
 impl<V: Formattable> Formattable for Option<V> {
@@ -141,6 +271,23 @@ impl<'a> Formattable for PlatformRequires<'a> {
     }
 }
 
+impl Formattable for PlatformThreading {
+    fn is_multiline(&self) -> bool {
+        false
+    }
+
+    fn format_with_options(
+        &self,
+        buf: &mut Buf,
+        _parens: crate::annotation::Parens,
+        _newlines: Newlines,
+        indent: u16,
+    ) {
+        buf.indent(indent);
+        buf.push_str(self.as_str());
+    }
+}
+
 impl<'a, V: Formattable> Formattable for Spaces<'a, V> {
     fn is_multiline(&self) -> bool {
         !self.before.is_empty() || !self.after.is_empty() || self.item.is_multiline()
@@ -254,6 +401,7 @@ pub fn fmt_platform_header<'a>(buf: &mut Buf, header: &'a PlatformHeader<'a>) {
     fmt_imports(buf, header.imports.item, indent);
     header.provides.keyword.format(buf, indent);
     fmt_provides(buf, header.provides.item, None, indent);
+    header.threading.format(buf, indent);
 }
 
 fn fmt_requires(buf: &mut Buf, requires: &PlatformRequires, indent: u16) {
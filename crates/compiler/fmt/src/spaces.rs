@@ -11,7 +11,7 @@ use roc_parse::{
     header::{
         AppHeader, ExposedName, HostedHeader, ImportsEntry, InterfaceHeader, KeywordItem,
         ModuleName, PackageEntry, PackageHeader, PackageName, PlatformHeader, PlatformRequires,
-        ProvidesTo, To, TypedIdent,
+        PlatformThreading, ProvidesTo, To, TypedIdent,
     },
     ident::{BadIdent, UppercaseIdent},
 };
@@ -307,6 +307,7 @@ impl<'a> RemoveSpaces<'a> for Module<'a> {
                 packages: header.packages.remove_spaces(arena),
                 imports: header.imports.remove_spaces(arena),
                 provides: header.provides.remove_spaces(arena),
+                threading: header.threading.remove_spaces(arena),
             }),
             Header::Hosted(header) => Header::Hosted(HostedHeader {
                 before_name: &[],
@@ -392,6 +393,12 @@ impl<'a> RemoveSpaces<'a> for PlatformRequires<'a> {
     }
 }
 
+impl<'a> RemoveSpaces<'a> for PlatformThreading {
+    fn remove_spaces(&self, _arena: &'a Bump) -> Self {
+        *self
+    }
+}
+
 impl<'a> RemoveSpaces<'a> for UppercaseIdent<'a> {
     fn remove_spaces(&self, _arena: &'a Bump) -> Self {
         *self
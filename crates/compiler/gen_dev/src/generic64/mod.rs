@@ -1,6 +1,6 @@
 use crate::{
     pointer_layouts, single_register_floats, single_register_int_builtins,
-    single_register_integers, Backend, Env, Relocation,
+    single_register_integers, Backend, Env, LiveRanges, Relocation,
 };
 use bumpalo::collections::{CollectIn, Vec};
 use roc_builtins::bitcode::{self, FloatWidth, IntWidth};
@@ -48,6 +48,54 @@ impl RegisterWidth {
             _ => None,
         }
     }
+
+    fn bytes(self) -> i32 {
+        match self {
+            RegisterWidth::W8 => 1,
+            RegisterWidth::W16 => 2,
+            RegisterWidth::W32 => 4,
+            RegisterWidth::W64 => 8,
+        }
+    }
+}
+
+/// Values larger than this are copied with a call to `memcpy`,
+/// instead of a sequence of moves through a register.
+const MEMCPY_THRESHOLD_BYTES: u32 = 128;
+
+/// Splits a copy of `size` bytes into the widest moves that `alignment` allows.
+/// Yields the offset of each move relative to the start of the value, in order.
+///
+/// On targets other than x86, misaligned loads and stores might be slow or even fault,
+/// so we never use a move that is wider than the alignment of the value.
+pub(crate) fn copy_chunks(size: u32, alignment: u32) -> impl Iterator<Item = (i32, RegisterWidth)> {
+    let size = size as i32;
+    let alignment = alignment.clamp(1, 8) as i32;
+    let mut copied = 0;
+
+    std::iter::from_fn(move || {
+        let width = [
+            RegisterWidth::W64,
+            RegisterWidth::W32,
+            RegisterWidth::W16,
+            RegisterWidth::W8,
+        ]
+        .into_iter()
+        .find(|width| width.bytes() <= alignment && width.bytes() <= size - copied)?;
+
+        let offset = copied;
+        copied += width.bytes();
+
+        Some((offset, width))
+    })
+}
+
+/// The alignment that all of the given offsets are guaranteed to have, up to 8 bytes.
+pub(crate) fn offsets_alignment(offsets: &[i32]) -> u32 {
+    1 << offsets
+        .iter()
+        .fold(8, |acc, offset| acc | offset)
+        .trailing_zeros()
 }
 
 pub trait CallConv<GeneralReg: RegTrait, FloatReg: RegTrait, ASM: Assembler<GeneralReg, FloatReg>>:
@@ -762,6 +810,14 @@ impl<
         &mut self.last_seen_map
     }
 
+    fn set_live_ranges(&mut self, live_ranges: LiveRanges<'a>) {
+        self.storage_manager.set_live_ranges(live_ranges);
+    }
+
+    fn set_current_stmt(&mut self, stmt: &Stmt<'a>) {
+        self.storage_manager.set_current_stmt(stmt);
+    }
+
     fn layout_map(&mut self) -> &mut MutMap<Symbol, InLayout<'a>> {
         &mut self.layout_map
     }
@@ -2868,6 +2924,21 @@ impl<
 
         let layout = self.layout_interner.get_repr(element_layout);
 
+        if element_width > MEMCPY_THRESHOLD_BYTES as u64
+            && matches!(
+                layout,
+                LayoutRepr::Struct { .. } | LayoutRepr::Union(UnionLayout::NonRecursive(_))
+            )
+        {
+            self.build_memcpy_from_stack(sym, ptr, value, element_width as u32);
+
+            if value == Symbol::DEV_TMP {
+                self.free_symbol(&value);
+            }
+
+            return;
+        }
+
         Self::ptr_write(
             &mut self.buf,
             &mut self.storage_manager,
@@ -3850,6 +3921,35 @@ impl<
         CC: CallConv<GeneralReg, FloatReg, ASM>,
     > Backend64Bit<'a, 'r, GeneralReg, FloatReg, ASM, CC>
 {
+    /// Copies a value from the stack to where `ptr` points by calling `memcpy`.
+    /// Used for large values, where a sequence of moves would bloat the code.
+    /// `memcpy` returns its destination, so `dst` ends up holding `ptr`.
+    fn build_memcpy_from_stack(&mut self, dst: Symbol, ptr: Symbol, value: Symbol, size: u32) {
+        let (from_offset, stack_size) = self.storage_manager.stack_offset_and_size(&value);
+        debug_assert_eq!(stack_size, size);
+
+        // Load address of the value into register.
+        let src_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP4);
+        ASM::add_reg64_reg64_imm32(&mut self.buf, src_reg, CC::BASE_PTR_REG, from_offset);
+
+        self.load_literal_i64(&Symbol::DEV_TMP5, size as i64);
+
+        let u64_layout = Layout::U64;
+
+        self.build_fn_call(
+            &dst,
+            "memcpy".to_string(),
+            &[ptr, Symbol::DEV_TMP4, Symbol::DEV_TMP5],
+            &[u64_layout, u64_layout, u64_layout],
+            &u64_layout,
+        );
+
+        self.free_symbol(&Symbol::DEV_TMP4);
+        self.free_symbol(&Symbol::DEV_TMP5);
+    }
+
     fn compare(
         &mut self,
         op: CompareOperation,
@@ -3997,58 +4097,43 @@ impl<
         ASM::mov_base32_reg64(buf, base_offset + 16, tmp_reg);
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn unbox_to_stack(
         buf: &mut Vec<'a, u8>,
         storage_manager: &mut StorageManager<'a, 'r, GeneralReg, FloatReg, ASM, CC>,
         dst: Symbol,
         stack_size: u32,
+        alignment: u32,
         ptr_reg: GeneralReg,
         tmp_reg: GeneralReg,
         read_offset: i32,
     ) {
-        let mut copied = 0;
-        let size = stack_size as i32;
-
-        if size == 0 {
+        if stack_size == 0 {
             storage_manager.no_data(&dst);
             return;
         }
 
         let base_offset = storage_manager.claim_stack_area(&dst, stack_size);
+        let alignment = alignment.min(offsets_alignment(&[base_offset, read_offset]));
 
-        if size - copied >= 8 {
-            for _ in (0..(size - copied)).step_by(8) {
-                ASM::mov_reg64_mem64_offset32(buf, tmp_reg, ptr_reg, read_offset + copied);
-                ASM::mov_base32_reg64(buf, base_offset + copied, tmp_reg);
-
-                copied += 8;
-            }
-        }
-
-        if size - copied >= 4 {
-            for _ in (0..(size - copied)).step_by(4) {
-                ASM::mov_reg32_mem32_offset32(buf, tmp_reg, ptr_reg, read_offset + copied);
-                ASM::mov_base32_reg32(buf, base_offset + copied, tmp_reg);
-
-                copied += 4;
-            }
-        }
-
-        if size - copied >= 2 {
-            for _ in (0..(size - copied)).step_by(2) {
-                ASM::mov_reg16_mem16_offset32(buf, tmp_reg, ptr_reg, read_offset + copied);
-                ASM::mov_base32_reg16(buf, base_offset + copied, tmp_reg);
-
-                copied += 2;
-            }
-        }
-
-        if size - copied >= 1 {
-            for _ in (0..(size - copied)).step_by(1) {
-                ASM::mov_reg8_mem8_offset32(buf, tmp_reg, ptr_reg, read_offset + copied);
-                ASM::mov_base32_reg8(buf, base_offset + copied, tmp_reg);
-
-                copied += 1;
+        for (copied, width) in copy_chunks(stack_size, alignment) {
+            match width {
+                RegisterWidth::W64 => {
+                    ASM::mov_reg64_mem64_offset32(buf, tmp_reg, ptr_reg, read_offset + copied);
+                    ASM::mov_base32_reg64(buf, base_offset + copied, tmp_reg);
+                }
+                RegisterWidth::W32 => {
+                    ASM::mov_reg32_mem32_offset32(buf, tmp_reg, ptr_reg, read_offset + copied);
+                    ASM::mov_base32_reg32(buf, base_offset + copied, tmp_reg);
+                }
+                RegisterWidth::W16 => {
+                    ASM::mov_reg16_mem16_offset32(buf, tmp_reg, ptr_reg, read_offset + copied);
+                    ASM::mov_base32_reg16(buf, base_offset + copied, tmp_reg);
+                }
+                RegisterWidth::W8 => {
+                    ASM::mov_reg8_mem8_offset32(buf, tmp_reg, ptr_reg, read_offset + copied);
+                    ASM::mov_base32_reg8(buf, base_offset + copied, tmp_reg);
+                }
             }
         }
     }
@@ -4128,7 +4213,8 @@ impl<
 
             LayoutRepr::Struct { .. } => {
                 // put it on the stack
-                let stack_size = layout_interner.stack_size(element_in_layout);
+                let (stack_size, alignment) =
+                    layout_interner.stack_size_and_alignment(element_in_layout);
 
                 storage_manager.with_tmp_general_reg(buf, |storage_manager, buf, tmp_reg| {
                     Self::unbox_to_stack(
@@ -4136,6 +4222,7 @@ impl<
                         storage_manager,
                         dst,
                         stack_size,
+                        alignment,
                         ptr_reg,
                         tmp_reg,
                         offset,
@@ -4145,7 +4232,8 @@ impl<
 
             LayoutRepr::Union(UnionLayout::NonRecursive(_)) => {
                 // put it on the stack
-                let stack_size = layout_interner.stack_size(element_in_layout);
+                let (stack_size, alignment) =
+                    layout_interner.stack_size_and_alignment(element_in_layout);
 
                 storage_manager.with_tmp_general_reg(buf, |storage_manager, buf, tmp_reg| {
                     Self::unbox_to_stack(
@@ -4153,6 +4241,7 @@ impl<
                         storage_manager,
                         dst,
                         stack_size,
+                        alignment,
                         ptr_reg,
                         tmp_reg,
                         offset,
@@ -4233,63 +4322,31 @@ impl<
                 let (from_offset, stack_size) = storage_manager.stack_offset_and_size(&value);
                 debug_assert!(from_offset % 8 == 0);
 
-                storage_manager.with_tmp_general_reg(buf, |_storage_manager, buf, tmp_reg| {
-                    let mut copied = 0;
-                    let size = stack_size as i32;
-
-                    if size - copied >= 8 {
-                        for _ in (0..(size - copied)).step_by(8) {
-                            ASM::mov_reg64_base32(buf, tmp_reg, from_offset + copied);
-                            ASM::mov_mem64_offset32_reg64(
-                                buf,
-                                ptr_reg,
-                                element_offset + copied,
-                                tmp_reg,
-                            );
-
-                            copied += 8;
-                        }
-                    }
-
-                    if size - copied >= 4 {
-                        for _ in (0..(size - copied)).step_by(4) {
-                            ASM::mov_reg32_base32(buf, tmp_reg, from_offset + copied);
-                            ASM::mov_mem32_offset32_reg32(
-                                buf,
-                                ptr_reg,
-                                element_offset + copied,
-                                tmp_reg,
-                            );
-
-                            copied += 4;
-                        }
-                    }
-
-                    if size - copied >= 2 {
-                        for _ in (0..(size - copied)).step_by(2) {
-                            ASM::mov_reg16_base32(buf, tmp_reg, from_offset + copied);
-                            ASM::mov_mem16_offset32_reg16(
-                                buf,
-                                ptr_reg,
-                                element_offset + copied,
-                                tmp_reg,
-                            );
-
-                            copied += 2;
-                        }
-                    }
-
-                    if size - copied >= 1 {
-                        for _ in (0..(size - copied)).step_by(1) {
-                            ASM::mov_reg8_base32(buf, tmp_reg, from_offset + copied);
-                            ASM::mov_mem8_offset32_reg8(
-                                buf,
-                                ptr_reg,
-                                element_offset + copied,
-                                tmp_reg,
-                            );
+                let alignment = element_layout
+                    .alignment_bytes(layout_interner)
+                    .min(offsets_alignment(&[from_offset, element_offset]));
 
-                            copied += 1;
+                storage_manager.with_tmp_general_reg(buf, |_storage_manager, buf, tmp_reg| {
+                    for (copied, width) in copy_chunks(stack_size, alignment) {
+                        let dst_offset = element_offset + copied;
+
+                        match width {
+                            RegisterWidth::W64 => {
+                                ASM::mov_reg64_base32(buf, tmp_reg, from_offset + copied);
+                                ASM::mov_mem64_offset32_reg64(buf, ptr_reg, dst_offset, tmp_reg);
+                            }
+                            RegisterWidth::W32 => {
+                                ASM::mov_reg32_base32(buf, tmp_reg, from_offset + copied);
+                                ASM::mov_mem32_offset32_reg32(buf, ptr_reg, dst_offset, tmp_reg);
+                            }
+                            RegisterWidth::W16 => {
+                                ASM::mov_reg16_base32(buf, tmp_reg, from_offset + copied);
+                                ASM::mov_mem16_offset32_reg16(buf, ptr_reg, dst_offset, tmp_reg);
+                            }
+                            RegisterWidth::W8 => {
+                                ASM::mov_reg8_base32(buf, tmp_reg, from_offset + copied);
+                                ASM::mov_mem8_offset32_reg8(buf, ptr_reg, dst_offset, tmp_reg);
+                            }
                         }
                     }
                 });
@@ -2,6 +2,7 @@ use crate::{
     generic64::{Assembler, CallConv, RegTrait},
     pointer_layouts, sign_extended_int_builtins, single_register_floats,
     single_register_int_builtins, single_register_integers, single_register_layouts, Env,
+    LiveRanges,
 };
 use bumpalo::collections::Vec;
 use roc_builtins::bitcode::{FloatWidth, IntWidth};
@@ -9,7 +10,7 @@ use roc_collections::all::{MutMap, MutSet};
 use roc_error_macros::internal_error;
 use roc_module::symbol::Symbol;
 use roc_mono::{
-    ir::{JoinPointId, Param},
+    ir::{JoinPointId, Param, Stmt},
     layout::{
         Builtin, InLayout, Layout, LayoutInterner, LayoutRepr, STLayoutInterner, UnionLayout,
     },
@@ -23,7 +24,7 @@ use RegStorage::*;
 use StackStorage::*;
 use Storage::*;
 
-use super::RegisterWidth;
+use super::{copy_chunks, offsets_alignment, RegisterWidth};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum RegStorage<GeneralReg: RegTrait, FloatReg: RegTrait> {
@@ -123,6 +124,15 @@ pub struct StorageManager<
     general_used_callee_saved_regs: MutSet<GeneralReg>,
     float_used_callee_saved_regs: MutSet<FloatReg>,
 
+    // With linear scan register allocation, the live ranges of the current procedure.
+    // Without them, registers are handed out in order and the oldest one is spilled.
+    live_ranges: Option<Rc<LiveRanges<'a>>>,
+    // The position of the statement being built within the live ranges.
+    position: u32,
+    // Symbols that got a register while building the current statement.
+    // They are about to be used, so we avoid spilling them to make room for others.
+    pinned_symbols: Vec<'a, Symbol>,
+
     free_stack_chunks: Vec<'a, (i32, u32)>,
     stack_size: u32,
 
@@ -155,6 +165,9 @@ pub fn new_storage_manager<
         float_free_regs: bumpalo::vec![in env.arena],
         float_used_regs: bumpalo::vec![in env.arena],
         float_used_callee_saved_regs: MutSet::default(),
+        live_ranges: None,
+        position: 0,
+        pinned_symbols: bumpalo::vec![in env.arena],
         free_stack_chunks: bumpalo::vec![in env.arena],
         stack_size: 0,
         fn_call_stack_size: 0,
@@ -184,6 +197,9 @@ impl<
         self.float_used_regs.clear();
         self.float_free_regs
             .extend_from_slice(CC::FLOAT_DEFAULT_FREE_REGS);
+        self.live_ranges = None;
+        self.position = 0;
+        self.pinned_symbols.clear();
         self.free_stack_chunks.clear();
         self.stack_size = 0;
         self.fn_call_stack_size = 0;
@@ -217,17 +233,97 @@ impl<
         )
     }
 
+    pub fn set_live_ranges(&mut self, live_ranges: LiveRanges<'a>) {
+        self.live_ranges = Some(Rc::new(live_ranges));
+    }
+
+    pub fn set_current_stmt(&mut self, stmt: &Stmt<'a>) {
+        // Statements generated while building, like expanded refcounting, keep the position of
+        // the statement they came from.
+        if let Some(position) = self
+            .live_ranges
+            .as_ref()
+            .and_then(|ranges| ranges.position(stmt))
+        {
+            self.position = position;
+        }
+        self.pinned_symbols.clear();
+    }
+
+    /// Whether the symbol is better off in a callee saved register, because it lives across a call.
+    /// Temporaries (no symbol) always go in caller saved registers.
+    fn wants_callee_saved_reg(&self, sym: Option<&Symbol>) -> bool {
+        match (&self.live_ranges, sym) {
+            (Some(live_ranges), Some(sym)) => live_ranges.crosses_call(sym, self.position),
+            _ => false,
+        }
+    }
+
+    /// Whether the symbol is no longer used on the path being built.
+    /// Its register can be reused without storing the value first.
+    fn is_dead(&self, sym: &Symbol) -> bool {
+        match &self.live_ranges {
+            Some(live_ranges) => live_ranges.end(sym) < self.position,
+            None => false,
+        }
+    }
+
+    fn pin_symbol(&mut self, sym: &Symbol) {
+        if self.live_ranges.is_some() && !self.pinned_symbols.contains(sym) {
+            self.pinned_symbols.push(*sym);
+        }
+    }
+
+    /// Picks which used register to spill when all of them are taken.
+    /// With live ranges, this is the one holding the symbol that lives the longest.
+    /// Symbols that are dead on this path, because their last use was in another branch, go first.
+    fn spill_index<Reg>(&self, used_regs: &[(Reg, Symbol)]) -> usize {
+        let live_ranges = match &self.live_ranges {
+            Some(live_ranges) => live_ranges,
+            None => return 0,
+        };
+
+        used_regs
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, (_, sym))| !self.pinned_symbols.contains(sym))
+            .max_by_key(|(_, (_, sym))| {
+                if self.is_dead(sym) {
+                    u32::MAX
+                } else {
+                    live_ranges.end(sym)
+                }
+            })
+            .map(|(index, _)| index)
+            .unwrap_or(0)
+    }
+
     /// Get a general register from the free list.
     /// Will free data to the stack if necessary to get the register.
-    fn get_general_reg(&mut self, buf: &mut Vec<'a, u8>) -> GeneralReg {
-        if let Some(reg) = self.general_free_regs.pop() {
+    fn get_general_reg(&mut self, buf: &mut Vec<'a, u8>, sym: Option<&Symbol>) -> GeneralReg {
+        let callee_saved = self.wants_callee_saved_reg(sym);
+        let preferred = match self.live_ranges {
+            Some(_) => self
+                .general_free_regs
+                .iter()
+                .rposition(|reg| CC::general_callee_saved(reg) == callee_saved),
+            None => None,
+        };
+        let free_reg = match preferred {
+            Some(index) => Some(self.general_free_regs.remove(index)),
+            None => self.general_free_regs.pop(),
+        };
+
+        if let Some(reg) = free_reg {
             if CC::general_callee_saved(&reg) {
                 self.general_used_callee_saved_regs.insert(reg);
             }
             reg
         } else if !self.general_used_regs.is_empty() {
-            let (reg, sym) = self.general_used_regs.remove(0);
-            self.free_to_stack(buf, &sym, General(reg));
+            let index = self.spill_index(&self.general_used_regs);
+            let (reg, sym) = self.general_used_regs.remove(index);
+            self.spill(buf, &sym, General(reg));
             reg
         } else {
             internal_error!("completely out of general purpose registers");
@@ -236,15 +332,29 @@ impl<
 
     /// Get a float register from the free list.
     /// Will free data to the stack if necessary to get the register.
-    fn get_float_reg(&mut self, buf: &mut Vec<'a, u8>) -> FloatReg {
-        if let Some(reg) = self.float_free_regs.pop() {
+    fn get_float_reg(&mut self, buf: &mut Vec<'a, u8>, sym: Option<&Symbol>) -> FloatReg {
+        let callee_saved = self.wants_callee_saved_reg(sym);
+        let preferred = match self.live_ranges {
+            Some(_) => self
+                .float_free_regs
+                .iter()
+                .rposition(|reg| CC::float_callee_saved(reg) == callee_saved),
+            None => None,
+        };
+        let free_reg = match preferred {
+            Some(index) => Some(self.float_free_regs.remove(index)),
+            None => self.float_free_regs.pop(),
+        };
+
+        if let Some(reg) = free_reg {
             if CC::float_callee_saved(&reg) {
                 self.float_used_callee_saved_regs.insert(reg);
             }
             reg
         } else if !self.float_used_regs.is_empty() {
-            let (reg, sym) = self.float_used_regs.remove(0);
-            self.free_to_stack(buf, &sym, Float(reg));
+            let index = self.spill_index(&self.float_used_regs);
+            let (reg, sym) = self.float_used_regs.remove(index);
+            self.spill(buf, &sym, Float(reg));
             reg
         } else {
             internal_error!("completely out of general purpose registers");
@@ -259,9 +369,10 @@ impl<
             None,
             "Symbol {sym:?} is already in the storage map!"
         );
-        let reg = self.get_general_reg(buf);
+        let reg = self.get_general_reg(buf, Some(sym));
         self.general_used_regs.push((reg, *sym));
         self.symbol_storage_map.insert(*sym, Reg(General(reg)));
+        self.pin_symbol(sym);
         reg
     }
 
@@ -269,9 +380,10 @@ impl<
     /// They symbol should not already have storage.
     pub fn claim_float_reg(&mut self, buf: &mut Vec<'a, u8>, sym: &Symbol) -> FloatReg {
         debug_assert_eq!(self.symbol_storage_map.get(sym), None);
-        let reg = self.get_float_reg(buf);
+        let reg = self.get_float_reg(buf, Some(sym));
         self.float_used_regs.push((reg, *sym));
         self.symbol_storage_map.insert(*sym, Reg(Float(reg)));
+        self.pin_symbol(sym);
         reg
     }
 
@@ -282,7 +394,7 @@ impl<
         buf: &mut Vec<'a, u8>,
         callback: F,
     ) {
-        let reg = self.get_general_reg(buf);
+        let reg = self.get_general_reg(buf, None);
         callback(self, buf, reg);
         self.general_free_regs.push(reg);
     }
@@ -295,7 +407,7 @@ impl<
         buf: &mut Vec<'a, u8>,
         callback: F,
     ) {
-        let reg = self.get_float_reg(buf);
+        let reg = self.get_float_reg(buf, None);
         callback(self, buf, reg);
         self.float_free_regs.push(reg);
     }
@@ -305,6 +417,7 @@ impl<
     /// Will fail on values stored in float regs.
     /// Will fail for values that don't fit in a single register.
     pub fn load_to_general_reg(&mut self, buf: &mut Vec<'a, u8>, sym: &Symbol) -> GeneralReg {
+        self.pin_symbol(sym);
         let storage = self.remove_storage_for_sym(sym);
         match storage {
             Reg(General(reg))
@@ -327,7 +440,7 @@ impl<
                 base_offset,
             }) => {
                 debug_assert_eq!(base_offset % 8, 0);
-                let reg = self.get_general_reg(buf);
+                let reg = self.get_general_reg(buf, Some(sym));
                 ASM::mov_reg64_base32(buf, reg, base_offset);
                 self.general_used_regs.push((reg, *sym));
                 self.symbol_storage_map.insert(
@@ -344,7 +457,7 @@ impl<
                 size,
                 sign_extend,
             }) => {
-                let reg = self.get_general_reg(buf);
+                let reg = self.get_general_reg(buf, Some(sym));
 
                 let register_width = match size {
                     8 => RegisterWidth::W64,
@@ -380,6 +493,7 @@ impl<
     /// Will fail on values stored in general regs.
     /// Will fail for values that don't fit in a single register.
     pub fn load_to_float_reg(&mut self, buf: &mut Vec<'a, u8>, sym: &Symbol) -> FloatReg {
+        self.pin_symbol(sym);
         let storage = self.remove_storage_for_sym(sym);
         match storage {
             Reg(Float(reg))
@@ -402,7 +516,7 @@ impl<
                 base_offset,
             }) => {
                 debug_assert_eq!(base_offset % 8, 0);
-                let reg = self.get_float_reg(buf);
+                let reg = self.get_float_reg(buf, Some(sym));
                 ASM::mov_freg64_base32(buf, reg, base_offset);
                 self.float_used_regs.push((reg, *sym));
                 self.symbol_storage_map.insert(
@@ -418,7 +532,7 @@ impl<
                 base_offset, size, ..
             }) if base_offset % 8 == 0 && size == 8 => {
                 // The primitive is aligned and the data is exactly 8 bytes, treat it like regular stack.
-                let reg = self.get_float_reg(buf);
+                let reg = self.get_float_reg(buf, Some(sym));
                 ASM::mov_freg64_base32(buf, reg, base_offset);
                 self.float_used_regs.push((reg, *sym));
                 self.symbol_storage_map.insert(*sym, Reg(Float(reg)));
@@ -835,6 +949,8 @@ impl<
         }
     }
 
+    /// Copies `size` bytes between two stack offsets, using the widest moves
+    /// that the alignment of both offsets allows.
     pub fn copy_to_stack_offset(
         &mut self,
         buf: &mut Vec<'a, u8>,
@@ -842,51 +958,27 @@ impl<
         from_offset: i32,
         to_offset: i32,
     ) {
-        let mut copied = 0;
-        let size = size as i32;
+        let alignment = offsets_alignment(&[from_offset, to_offset]);
 
         self.with_tmp_general_reg(buf, |_storage_manager, buf, reg| {
-            // on targets beside x86, misaligned copies might be a problem
-            for _ in 0..size % 8 {
-                ASM::mov_reg8_base32(buf, reg, from_offset + copied);
-                ASM::mov_base32_reg8(buf, to_offset + copied, reg);
-
-                copied += 1;
-            }
-
-            if size - copied >= 8 {
-                for _ in (0..(size - copied)).step_by(8) {
-                    ASM::mov_reg64_base32(buf, reg, from_offset + copied);
-                    ASM::mov_base32_reg64(buf, to_offset + copied, reg);
-
-                    copied += 8;
-                }
-            }
-
-            if size - copied >= 4 {
-                for _ in (0..(size - copied)).step_by(4) {
-                    ASM::mov_reg32_base32(buf, reg, from_offset + copied);
-                    ASM::mov_base32_reg32(buf, to_offset + copied, reg);
-
-                    copied += 4;
-                }
-            }
-
-            if size - copied >= 2 {
-                for _ in (0..(size - copied)).step_by(2) {
-                    ASM::mov_reg16_base32(buf, reg, from_offset + copied);
-                    ASM::mov_base32_reg16(buf, to_offset + copied, reg);
-
-                    copied += 2;
-                }
-            }
-
-            if size - copied >= 1 {
-                for _ in (0..(size - copied)).step_by(1) {
-                    ASM::mov_reg8_base32(buf, reg, from_offset + copied);
-                    ASM::mov_base32_reg8(buf, to_offset + copied, reg);
-
-                    copied += 1;
+            for (copied, width) in copy_chunks(size, alignment) {
+                match width {
+                    RegisterWidth::W64 => {
+                        ASM::mov_reg64_base32(buf, reg, from_offset + copied);
+                        ASM::mov_base32_reg64(buf, to_offset + copied, reg);
+                    }
+                    RegisterWidth::W32 => {
+                        ASM::mov_reg32_base32(buf, reg, from_offset + copied);
+                        ASM::mov_base32_reg32(buf, to_offset + copied, reg);
+                    }
+                    RegisterWidth::W16 => {
+                        ASM::mov_reg16_base32(buf, reg, from_offset + copied);
+                        ASM::mov_base32_reg16(buf, to_offset + copied, reg);
+                    }
+                    RegisterWidth::W8 => {
+                        ASM::mov_reg8_base32(buf, reg, from_offset + copied);
+                        ASM::mov_base32_reg8(buf, to_offset + copied, reg);
+                    }
                 }
             }
         });
@@ -993,6 +1085,43 @@ impl<
         }
     }
 
+    /// Frees `wanted_reg` which is currently owned by `sym`.
+    /// The value goes to the stack, unless it is dead on this path and can simply be dropped.
+    /// Note, used and free regs are expected to be updated outside of this function.
+    fn spill(
+        &mut self,
+        buf: &mut Vec<'a, u8>,
+        sym: &Symbol,
+        wanted_reg: RegStorage<GeneralReg, FloatReg>,
+    ) {
+        if !self.is_dead(sym) {
+            self.free_to_stack(buf, sym, wanted_reg);
+            return;
+        }
+
+        let storage = match self.remove_storage_for_sym(sym) {
+            Reg(reg_storage) => {
+                debug_assert_eq!(reg_storage, wanted_reg);
+                NoData
+            }
+            Stack(Primitive {
+                reg: Some(reg_storage),
+                base_offset,
+            }) => {
+                debug_assert_eq!(reg_storage, wanted_reg);
+                Stack(Primitive {
+                    base_offset,
+                    reg: None,
+                })
+            }
+            NoData
+            | Stack(Complex { .. } | Primitive { reg: None, .. } | ReferencedPrimitive { .. }) => {
+                internal_error!("Cannot free reg from symbol without a reg: {}", sym)
+            }
+        };
+        self.symbol_storage_map.insert(*sym, storage);
+    }
+
     /// Frees `wanted_reg` which is currently owned by `sym` by making sure the value is loaded on the stack.
     /// Note, used and free regs are expected to be updated outside of this function.
     fn free_to_stack(
@@ -1445,6 +1574,9 @@ impl<
         }
     }
 
+    /// Frees all caller saved registers before a call.
+    /// With live ranges, values that are still needed after the call move to a free callee saved
+    /// register, splitting their live range, and only the rest go to the stack.
     pub fn push_used_caller_saved_regs_to_stack(&mut self, buf: &mut Vec<'a, u8>) {
         let old_general_used_regs = std::mem::replace(
             &mut self.general_used_regs,
@@ -1452,8 +1584,15 @@ impl<
         );
         for (reg, saved_sym) in old_general_used_regs.into_iter() {
             if CC::general_caller_saved(&reg) {
+                match self.free_callee_saved_general_reg(&saved_sym) {
+                    Some(new_reg) => {
+                        ASM::mov_reg64_reg64(buf, new_reg, reg);
+                        self.move_to_reg(&saved_sym, General(reg), General(new_reg));
+                        self.general_used_regs.push((new_reg, saved_sym));
+                    }
+                    None => self.spill(buf, &saved_sym, General(reg)),
+                }
                 self.general_free_regs.push(reg);
-                self.free_to_stack(buf, &saved_sym, General(reg));
             } else {
                 self.general_used_regs.push((reg, saved_sym));
             }
@@ -1462,14 +1601,86 @@ impl<
             std::mem::replace(&mut self.float_used_regs, bumpalo::vec![in self.env.arena]);
         for (reg, saved_sym) in old_float_used_regs.into_iter() {
             if CC::float_caller_saved(&reg) {
+                match self.free_callee_saved_float_reg(&saved_sym) {
+                    Some(new_reg) => {
+                        ASM::mov_freg64_freg64(buf, new_reg, reg);
+                        self.move_to_reg(&saved_sym, Float(reg), Float(new_reg));
+                        self.float_used_regs.push((new_reg, saved_sym));
+                    }
+                    None => self.spill(buf, &saved_sym, Float(reg)),
+                }
                 self.float_free_regs.push(reg);
-                self.free_to_stack(buf, &saved_sym, Float(reg));
             } else {
                 self.float_used_regs.push((reg, saved_sym));
             }
         }
     }
 
+    /// Takes a free callee saved register for a symbol that is still needed after the current statement.
+    fn free_callee_saved_general_reg(&mut self, sym: &Symbol) -> Option<GeneralReg> {
+        let live_ranges = self.live_ranges.as_ref()?;
+        if live_ranges.end(sym) <= self.position {
+            return None;
+        }
+
+        let index = self
+            .general_free_regs
+            .iter()
+            .rposition(|reg| CC::general_callee_saved(reg))?;
+        let reg = self.general_free_regs.remove(index);
+        self.general_used_callee_saved_regs.insert(reg);
+
+        Some(reg)
+    }
+
+    /// Takes a free callee saved register for a symbol that is still needed after the current statement.
+    fn free_callee_saved_float_reg(&mut self, sym: &Symbol) -> Option<FloatReg> {
+        let live_ranges = self.live_ranges.as_ref()?;
+        if live_ranges.end(sym) <= self.position {
+            return None;
+        }
+
+        let index = self
+            .float_free_regs
+            .iter()
+            .rposition(|reg| CC::float_callee_saved(reg))?;
+        let reg = self.float_free_regs.remove(index);
+        self.float_used_callee_saved_regs.insert(reg);
+
+        Some(reg)
+    }
+
+    /// Updates the storage of a symbol whose value was copied from `old_reg` to `new_reg`.
+    /// Note, used and free regs are expected to be updated outside of this function.
+    fn move_to_reg(
+        &mut self,
+        sym: &Symbol,
+        old_reg: RegStorage<GeneralReg, FloatReg>,
+        new_reg: RegStorage<GeneralReg, FloatReg>,
+    ) {
+        let storage = match self.remove_storage_for_sym(sym) {
+            Reg(reg_storage) => {
+                debug_assert_eq!(reg_storage, old_reg);
+                Reg(new_reg)
+            }
+            Stack(Primitive {
+                reg: Some(reg_storage),
+                base_offset,
+            }) => {
+                debug_assert_eq!(reg_storage, old_reg);
+                Stack(Primitive {
+                    base_offset,
+                    reg: Some(new_reg),
+                })
+            }
+            NoData
+            | Stack(Complex { .. } | Primitive { reg: None, .. } | ReferencedPrimitive { .. }) => {
+                internal_error!("Cannot move reg from symbol without a reg: {}", sym)
+            }
+        };
+        self.symbol_storage_map.insert(*sym, storage);
+    }
+
     #[allow(dead_code)]
     /// Gets the allocated area for a symbol. The index symbol must be defined.
     fn get_allocation_for_sym(&self, sym: &Symbol) -> &Rc<(i32, u32)> {
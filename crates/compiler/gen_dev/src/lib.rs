@@ -26,7 +26,9 @@ use roc_mono::layout::{
 use roc_mono::list_element_layout;
 
 mod generic64;
+mod live_ranges;
 mod object_builder;
+use live_ranges::LiveRanges;
 pub use object_builder::build_module;
 mod run_roc;
 
@@ -47,12 +49,26 @@ impl AssemblyBackendMode {
     }
 }
 
+/// How symbols are assigned to registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterAllocation {
+    /// Hands out registers in a fixed order and spills the oldest one when they run out.
+    /// Kept around to compare against.
+    Naive,
+    /// Uses the live range of every symbol to pick registers: values that live across a call
+    /// prefer callee saved registers, the value used furthest in the future is spilled first,
+    /// and at a call, values in caller saved registers move into free callee saved ones rather
+    /// than going to the stack.
+    LinearScan,
+}
+
 pub struct Env<'a> {
     pub arena: &'a Bump,
     pub module_id: ModuleId,
     pub exposed_to_host: MutSet<Symbol>,
     pub lazy_literals: bool,
     pub mode: AssemblyBackendMode,
+    pub register_allocation: RegisterAllocation,
 }
 
 // These relocations likely will need a length.
@@ -510,6 +526,8 @@ trait Backend<'a> {
         stmt: &Stmt<'a>,
         ret_layout: &InLayout<'a>,
     ) {
+        self.set_current_stmt(stmt);
+
         match stmt {
             Stmt::Let(sym, expr, layout, following) => {
                 self.build_expr(sym, expr, layout);
@@ -612,9 +630,10 @@ trait Backend<'a> {
         // Now that the arguments are needed, load them if they are literals.
         let arguments = &[msg, error_message];
         self.load_literal_symbols(arguments);
+        // Go through the builtins, so the panic hook the host may have set gets called.
         self.build_fn_call(
             &Symbol::DEV_TMP2,
-            String::from("roc_panic"),
+            bitcode::UTILS_PANIC.to_string(),
             arguments,
             &[Layout::STR, Layout::U32],
             &Layout::UNIT,
@@ -2387,5 +2406,16 @@ trait Backend<'a> {
     /// This must iterate through the ast in the same way that build_stmt does. i.e. then before else.
     fn scan_ast(&mut self, stmt: &'a Stmt<'a>) {
         *self.last_seen_map() = LastSeenMap::scan_ast(stmt);
+
+        if self.env().register_allocation == RegisterAllocation::LinearScan {
+            let live_ranges = LiveRanges::new(stmt, self.last_seen_map());
+            self.set_live_ranges(live_ranges);
+        }
     }
+
+    /// set_live_ranges gives the register allocator the live ranges of the current procedure.
+    fn set_live_ranges(&mut self, live_ranges: LiveRanges<'a>);
+
+    /// set_current_stmt lets the register allocator know which statement is being built.
+    fn set_current_stmt(&mut self, stmt: &Stmt<'a>);
 }
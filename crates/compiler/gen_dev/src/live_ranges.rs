@@ -0,0 +1,119 @@
+use roc_collections::all::MutMap;
+use roc_module::symbol::Symbol;
+use roc_mono::ir::{Expr, Stmt};
+
+/// The live range of every symbol in a procedure, for linear scan register allocation.
+///
+/// Statements are numbered in the order that build_stmt visits them, i.e. then before else and
+/// the remainder of a join point before its body. A symbol is live from the statement that
+/// defines it up to and including its last use. Since later statements on any path through
+/// the procedure get higher positions, a symbol whose range ended before the current position
+/// is dead on this path, even if it is only freed once some switch is done.
+#[derive(Debug, Default)]
+pub struct LiveRanges<'a> {
+    positions: MutMap<*const Stmt<'a>, u32>,
+    // Symbols used inside a join point live until everything inside it has been built.
+    // A switch only uses its condition up front, so it doesn't need this.
+    join_ends: MutMap<*const Stmt<'a>, u32>,
+    starts: MutMap<Symbol, u32>,
+    ends: MutMap<Symbol, u32>,
+    // Positions of statements that may call a function, in increasing order.
+    calls: std::vec::Vec<u32>,
+    next: u32,
+}
+
+impl<'a> LiveRanges<'a> {
+    pub fn new(root: &'a Stmt<'a>, last_seen: &MutMap<Symbol, *const Stmt<'a>>) -> Self {
+        let mut this: Self = Default::default();
+
+        this.number(root);
+
+        for (symbol, stmt) in last_seen {
+            let end = this
+                .join_ends
+                .get(stmt)
+                .or_else(|| this.positions.get(stmt))
+                .copied();
+
+            if let Some(end) = end {
+                this.ends.insert(*symbol, end);
+            }
+        }
+
+        this
+    }
+
+    fn number(&mut self, stmt: &'a Stmt<'a>) {
+        let position = self.next;
+        self.next += 1;
+        self.positions.insert(stmt, position);
+
+        match stmt {
+            Stmt::Let(sym, expr, _, following) => {
+                self.starts.insert(*sym, position);
+                if let Expr::Call(_) = expr {
+                    // Most low levels are inlined, but plenty of them call into the builtins.
+                    self.calls.push(position);
+                }
+                self.number(following);
+            }
+            Stmt::Refcounting(_, following) => {
+                self.calls.push(position);
+                self.number(following);
+            }
+            Stmt::Switch {
+                branches,
+                default_branch,
+                ..
+            } => {
+                for (_, _, branch) in *branches {
+                    self.number(branch);
+                }
+                self.number(default_branch.1);
+            }
+            Stmt::Join {
+                parameters,
+                body,
+                remainder,
+                ..
+            } => {
+                for param in *parameters {
+                    self.starts.insert(param.symbol, position);
+                }
+                self.number(remainder);
+                self.number(body);
+                self.join_ends.insert(stmt, self.next - 1);
+            }
+            Stmt::Dbg { remainder, .. }
+            | Stmt::Expect { remainder, .. }
+            | Stmt::ExpectFx { remainder, .. } => self.number(remainder),
+            Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::Crash(_, _) => {}
+        }
+    }
+
+    /// The position of a statement, if it was part of the procedure that was scanned.
+    /// Statements generated while building (like expanded refcounting) have no position.
+    pub fn position(&self, stmt: &Stmt<'a>) -> Option<u32> {
+        self.positions.get(&(stmt as *const Stmt<'a>)).copied()
+    }
+
+    /// The last position at which the symbol is live.
+    /// Symbols we know nothing about, like the return pointer, live for the whole procedure.
+    pub fn end(&self, symbol: &Symbol) -> u32 {
+        self.ends.get(symbol).copied().unwrap_or(u32::MAX - 1)
+    }
+
+    /// Whether a value of the symbol held in a register at `position` has to survive a call.
+    pub fn crosses_call(&self, symbol: &Symbol, position: u32) -> bool {
+        // A call in the defining statement happens before the symbol gets its value.
+        let from = match self.starts.get(symbol) {
+            Some(start) => position.max(start + 1),
+            None => position,
+        };
+        let end = self.end(symbol);
+
+        let first_call = self.calls.partition_point(|call| *call < from);
+
+        matches!(self.calls.get(first_call), Some(call) if *call < end)
+    }
+}
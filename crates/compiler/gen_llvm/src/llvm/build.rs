@@ -1,4 +1,4 @@
-use crate::llvm::bitcode::call_bitcode_fn;
+use crate::llvm::bitcode::{build_dec_wrapper, build_inc_wrapper, call_bitcode_fn};
 use crate::llvm::build_list::{self, allocate_list, empty_polymorphic_list};
 use crate::llvm::convert::{
     argument_type_from_layout, basic_type_from_builtin, basic_type_from_layout, zig_str_type,
@@ -18,15 +18,15 @@ use inkwell::debug_info::{
     AsDIScope, DICompileUnit, DIFlagsConstants, DISubprogram, DebugInfoBuilder,
 };
 use inkwell::memory_buffer::MemoryBuffer;
-use inkwell::module::{Linkage, Module};
+use inkwell::module::{FlagBehavior, Linkage, Module};
 use inkwell::passes::{PassManager, PassManagerBuilder};
 use inkwell::types::{
     AnyType, BasicMetadataTypeEnum, BasicType, BasicTypeEnum, FunctionType, IntType, StructType,
 };
 use inkwell::values::BasicValueEnum::{self};
 use inkwell::values::{
-    BasicMetadataValueEnum, CallSiteValue, FunctionValue, InstructionValue, IntValue, PointerValue,
-    StructValue,
+    BasicMetadataValueEnum, CallSiteValue, FunctionValue, GlobalValue, InstructionValue, IntValue,
+    PointerValue, StructValue,
 };
 use inkwell::OptimizationLevel;
 use inkwell::{AddressSpace, IntPredicate};
@@ -51,7 +51,7 @@ use roc_std::RocDec;
 use roc_target::{PtrWidth, TargetInfo};
 use std::convert::TryInto;
 use std::path::Path;
-use target_lexicon::{Architecture, OperatingSystem, Triple};
+use target_lexicon::{Architecture, ArmArchitecture, OperatingSystem, Triple};
 
 use super::convert::{struct_type_from_union_layout, RocUnion};
 use super::intrinsics::{
@@ -211,6 +211,30 @@ impl LlvmBackendMode {
     }
 }
 
+/// Whether the host may call into Roc from several threads at the same time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadSafety {
+    /// Refcounts are updated atomically, and the globals the generated code writes to
+    /// (like the panic message) are thread-local, so any thread can call any entry point.
+    ThreadSafe,
+    /// Refcounts are updated non-atomically, which is faster. The host must only call into
+    /// Roc from one thread; the Rust glue checks this.
+    SingleThreaded,
+}
+
+impl ThreadSafety {
+    /// Hosts are the default; dev tools that call into Roc themselves use a single thread.
+    pub fn for_mode(mode: LlvmBackendMode) -> Self {
+        match mode {
+            LlvmBackendMode::Binary | LlvmBackendMode::BinaryDev => ThreadSafety::ThreadSafe,
+            LlvmBackendMode::BinaryGlue
+            | LlvmBackendMode::GenTest
+            | LlvmBackendMode::WasmGenTest
+            | LlvmBackendMode::CliTest => ThreadSafety::SingleThreaded,
+        }
+    }
+}
+
 pub struct Env<'a, 'ctx, 'env> {
     pub arena: &'a Bump,
     pub context: &'ctx Context,
@@ -222,6 +246,17 @@ pub struct Env<'a, 'ctx, 'env> {
     pub target_info: TargetInfo,
     pub mode: LlvmBackendMode,
     pub exposed_to_host: MutSet<Symbol>,
+    /// Report every effect call to the parent process (`roc dev --trace-effects`).
+    /// Only has an effect in [LlvmBackendMode::BinaryDev], where that parent exists.
+    pub trace_effects: bool,
+    pub thread_safety: ThreadSafety,
+    /// How much a full list grows, as a percentage of its capacity (`--list-growth`).
+    /// `None` keeps the builtins' default policy.
+    pub list_growth_percent: Option<u32>,
+    /// The calls to keep track of while expects run, by the symbol that each call's result is
+    /// bound to, so a failed expect can show where it was called from.
+    /// See [crate::llvm::expect::push_call_site].
+    pub expect_call_sites: MutMap<Symbol, roc_region::all::Region>,
 }
 
 impl<'a, 'ctx, 'env> Env<'a, 'ctx, 'env> {
@@ -251,6 +286,13 @@ impl<'a, 'ctx, 'env> Env<'a, 'ctx, 'env> {
         }
     }
 
+    /// Whether `expect` and `dbg` report to a parent process. That needs shared memory, which
+    /// there is no OS to provide on bare metal.
+    pub(crate) fn runs_expects(&self) -> bool {
+        self.mode.runs_expects()
+            && self.target_info.operating_system != roc_target::OperatingSystem::Freestanding
+    }
+
     pub fn small_str_bytes(&self) -> u32 {
         self.target_info.ptr_width() as u32 * 3
     }
@@ -379,7 +421,9 @@ impl<'a, 'ctx, 'env> Env<'a, 'ctx, 'env> {
         message: BasicValueEnum<'ctx>,
         tag: CrashTag,
     ) {
-        let function = self.module.get_function("roc_panic").unwrap();
+        // Go through the builtins rather than calling roc_panic directly, so that crashes
+        // also reach the panic hook a host may have set with roc_set_panic_hook.
+        let function = self.module.get_function(bitcode::UTILS_PANIC).unwrap();
         let tag_id = self.context.i32_type().const_int(tag as u32 as u64, false);
 
         let msg = match env.target_info.ptr_width() {
@@ -491,6 +535,13 @@ pub fn module_from_builtins<'ctx>(
             } => {
                 include_bytes!("../../../builtins/bitcode/builtins-x86_64.bc")
             }
+            Triple {
+                architecture: Architecture::Riscv64(_),
+                operating_system: OperatingSystem::Linux,
+                ..
+            } => {
+                include_bytes!("../../../builtins/bitcode/builtins-riscv64.bc")
+            }
             Triple {
                 architecture: Architecture::X86_64,
                 operating_system: OperatingSystem::Windows,
@@ -498,6 +549,13 @@ pub fn module_from_builtins<'ctx>(
             } => {
                 include_bytes!("../../../builtins/bitcode/builtins-windows-x86_64.bc")
             }
+            Triple {
+                architecture: Architecture::Arm(ArmArchitecture::Thumbv7em),
+                operating_system: OperatingSystem::None_,
+                ..
+            } => {
+                include_bytes!("../../../builtins/bitcode/builtins-thumbv7em.bc")
+            }
             _ => panic!(
                 "The zig builtins are not currently built for this target: {:?}",
                 target
@@ -513,6 +571,18 @@ pub fn module_from_builtins<'ctx>(
     // Add LLVM intrinsics.
     add_intrinsics(ctx, &module);
 
+    // The RISC-V backend picks the ABI from this flag, and otherwise passes floats in integer
+    // registers (lp64), which doesn't match the hosts or libraries on riscv64gc Linux.
+    if let Architecture::Riscv64(_) = target.architecture {
+        if module.get_flag("target-abi").is_none() {
+            module.add_metadata_flag(
+                "target-abi",
+                FlagBehavior::Error,
+                ctx.metadata_string("lp64d"),
+            );
+        }
+    }
+
     module
 }
 
@@ -2201,10 +2271,9 @@ fn list_literal<'a, 'ctx>(
     let list_length = elems.len();
     let list_length_intval = env.ptr_int().const_int(list_length as _, false);
 
-    // TODO re-enable, currently causes morphic segfaults because it tries to update
-    // constants in-place...
-    // if element_type.is_int_type() {
-    if false {
+    // Alias analysis models a list literal whose elements are all literals as a static list,
+    // so morphic never lets us update it in place, and it can live in the constants section.
+    if element_type.is_int_type() {
         let element_type = element_type.into_int_type();
         let element_width = layout_interner.stack_size(element_layout);
         let size = list_length * element_width as usize;
@@ -2257,7 +2326,8 @@ fn list_literal<'a, 'ctx>(
                         // if all elements are const for LLVM, we could make the array a constant.
                         // BUT morphic does not know about this, and could allow us to modify that
                         // array in-place. That would cause a segfault. So, we'll have to find
-                        // constants ourselves and cannot lean on LLVM here.
+                        // constants ourselves (see `roc_mono::const_eval`) and cannot lean on
+                        // LLVM here.
 
                         is_all_constant = false;
 
@@ -2480,13 +2550,29 @@ pub(crate) fn build_exp_stmt<'a, 'ctx>(
             }
 
             let mut stack = Vec::with_capacity_in(queue.len(), env.arena);
+            let last = queue.len() - 1;
 
-            for (symbol, expr, layout) in queue {
+            for (index, (symbol, expr, layout)) in queue.into_iter().enumerate() {
                 debug_assert!(!matches!(
                     layout_interner.get_repr(*layout),
                     LayoutRepr::RecursivePointer(_)
                 ));
 
+                // a call in tail position isn't tracked, so it can stay a tail call
+                let call_site = match expr {
+                    roc_mono::ir::Expr::Call(roc_mono::ir::Call {
+                        call_type: CallType::ByName { .. },
+                        ..
+                    }) if !(index == last && matches!(cont, Ret(s) if s == symbol)) => {
+                        env.expect_call_sites.get(symbol)
+                    }
+                    _ => None,
+                };
+
+                if let Some(region) = call_site {
+                    crate::llvm::expect::push_call_site(env, *symbol, *region);
+                }
+
                 let val = build_exp_expr(
                     env,
                     layout_interner,
@@ -2498,6 +2584,10 @@ pub(crate) fn build_exp_stmt<'a, 'ctx>(
                     expr,
                 );
 
+                if call_site.is_some() {
+                    crate::llvm::expect::pop_call_site(env);
+                }
+
                 // Make a new scope which includes the binding we just encountered.
                 // This should be done *after* compiling the bound expr, since any
                 // recursive (in the LetRec sense) bindings should already have
@@ -2785,7 +2875,10 @@ pub(crate) fn build_exp_stmt<'a, 'ctx>(
             variable: specialized_var,
             remainder,
         } => {
-            if env.mode.runs_expects() {
+            // the wasm32 test runner only gets the frames of failed expects, not of dbg
+            if env.runs_expects()
+                && env.target_info.architecture != roc_target::Architecture::Wasm32
+            {
                 let shared_memory = crate::llvm::expect::SharedMemoryPointer::get(env);
                 let region = unsafe { std::mem::transmute::<_, roc_region::all::Region>(*symbol) };
 
@@ -2839,7 +2932,7 @@ pub(crate) fn build_exp_stmt<'a, 'ctx>(
 
             bd.build_conditional_branch(condition, then_block, throw_block);
 
-            if env.mode.runs_expects() {
+            if env.runs_expects() {
                 bd.position_at_end(throw_block);
 
                 match env.target_info.ptr_width() {
@@ -2858,9 +2951,31 @@ pub(crate) fn build_exp_stmt<'a, 'ctx>(
                             variables,
                         );
 
-                        if let LlvmBackendMode::BinaryDev = env.mode {
-                            crate::llvm::expect::notify_parent_expect(env, &shared_memory);
-                        }
+                        // `roc test` runs pure expects in its own process, and keeps track of
+                        // where each frame ends, so it can tell them apart from dbg frames
+                        crate::llvm::expect::notify_parent_expect(env, &shared_memory);
+
+                        bd.build_unconditional_branch(then_block);
+                    }
+                    roc_target::PtrWidth::Bytes4
+                        if env.target_info.architecture == roc_target::Architecture::Wasm32 =>
+                    {
+                        let shared_memory = SharedMemoryPointer::get(env);
+
+                        clone_to_shared_memory(
+                            env,
+                            layout_interner,
+                            scope,
+                            layout_ids,
+                            &shared_memory,
+                            *cond_symbol,
+                            *region,
+                            lookups,
+                            variables,
+                        );
+
+                        // wasm has no memory to share, so the runner gets each frame right away
+                        crate::llvm::expect::notify_runner_expect(env, &shared_memory);
 
                         bd.build_unconditional_branch(then_block);
                     }
@@ -2911,7 +3026,7 @@ pub(crate) fn build_exp_stmt<'a, 'ctx>(
 
             bd.build_conditional_branch(condition, then_block, throw_block);
 
-            if env.mode.runs_expects() {
+            if env.runs_expects() {
                 bd.position_at_end(throw_block);
 
                 match env.target_info.ptr_width() {
@@ -3837,7 +3952,28 @@ fn expose_function_to_host_help_c_abi_gen_test<'a, 'ctx>(
 
     let arguments_for_call = &arguments_for_call.into_bump_slice();
 
-    let (call_result, call_result_layout) = {
+    let output_arg_index = args_length - 1;
+
+    let output_arg = c_function
+        .get_nth_param(output_arg_index as u32)
+        .unwrap()
+        .into_pointer_value();
+
+    if env.target_info.architecture == roc_target::Architecture::Wasm32 {
+        // wasm has no setjmp/longjmp to catch a crash with. A crash traps instead, and whoever
+        // runs the wasm module reports it.
+        let roc_value = call_roc_function(
+            env,
+            layout_interner,
+            roc_function,
+            layout_interner.get_repr(return_layout),
+            arguments_for_call,
+        );
+
+        let call_result = make_good_roc_result(env, layout_interner, return_layout, roc_value);
+
+        builder.build_store(output_arg, call_result);
+    } else {
         let last_block = builder.get_insert_block().unwrap();
 
         let roc_wrapper_function =
@@ -3847,7 +3983,7 @@ fn expose_function_to_host_help_c_abi_gen_test<'a, 'ctx>(
 
         let wrapper_result = roc_call_result_layout(env.arena, return_layout);
 
-        let roc_value = call_roc_function(
+        let call_result = call_roc_function(
             env,
             layout_interner,
             roc_wrapper_function,
@@ -3855,23 +3991,15 @@ fn expose_function_to_host_help_c_abi_gen_test<'a, 'ctx>(
             arguments_for_call,
         );
 
-        (roc_value, wrapper_result)
-    };
-
-    let output_arg_index = args_length - 1;
-
-    let output_arg = c_function
-        .get_nth_param(output_arg_index as u32)
-        .unwrap()
-        .into_pointer_value();
+        store_roc_value(
+            env,
+            layout_interner,
+            wrapper_result,
+            output_arg,
+            call_result,
+        );
+    }
 
-    store_roc_value(
-        env,
-        layout_interner,
-        call_result_layout,
-        output_arg,
-        call_result,
-    );
     builder.build_return(None);
 
     // STEP 3: build a {} -> u64 function that gives the size of the return type
@@ -4260,7 +4388,11 @@ pub fn get_sjlj_buffer<'ctx>(env: &Env<'_, 'ctx, '_>) -> PointerValue<'ctx> {
 
     let global = match env.module.get_global("roc_sjlj_buffer") {
         Some(global) => global,
-        None => env.module.add_global(type_, None, "roc_sjlj_buffer"),
+        None => {
+            let global = env.module.add_global(type_, None, "roc_sjlj_buffer");
+            set_thread_local_if_needed(env, global);
+            global
+        }
     };
 
     global.set_initializer(&type_.const_zero());
@@ -4338,6 +4470,48 @@ pub fn build_setjmp_call<'ctx>(env: &Env<'_, 'ctx, '_>) -> BasicValueEnum<'ctx>
     }
 }
 
+/// Gives every thread its own copy of a global that the generated code writes to, so
+/// concurrent calls from the host don't overwrite each other's state.
+fn set_thread_local_if_needed<'ctx>(env: &Env<'_, 'ctx, '_>, global: GlobalValue<'ctx>) {
+    // wasm32 only has thread-local storage with the atomics proposal, and hosts there don't
+    // call into Roc from several threads anyway. On bare metal, there are no threads to
+    // keep apart, and no runtime to set up the thread pointer.
+    if env.thread_safety == ThreadSafety::ThreadSafe
+        && env.target_info.architecture != roc_target::Architecture::Wasm32
+        && env.target_info.operating_system != roc_target::OperatingSystem::Freestanding
+    {
+        global.set_thread_local(true);
+    }
+}
+
+/// Makes the builtins update refcounts the way `env.thread_safety` needs. The flag becomes a
+/// constant, so LLVM removes the check of it from every refcount operation.
+fn set_refcount_atomicity(env: &Env<'_, '_, '_>) {
+    if let Some(global) = env.module.get_global(bitcode::UTILS_ATOMIC_REFCOUNTS) {
+        if let Some(BasicValueEnum::IntValue(initial)) = global.get_initializer() {
+            let atomic = env.thread_safety == ThreadSafety::ThreadSafe;
+
+            global.set_initializer(&initial.get_type().const_int(atomic as u64, false));
+            global.set_constant(true);
+            global.set_linkage(Linkage::Internal);
+        }
+    }
+}
+
+/// Makes the builtins grow lists by `env.list_growth_percent`. Like the refcount flag, the
+/// setting becomes a constant, so LLVM drops the growth policy that is not used.
+fn set_list_growth(env: &Env<'_, '_, '_>) {
+    if let Some(global) = env.module.get_global(bitcode::UTILS_LIST_GROWTH_PERCENT) {
+        if let Some(BasicValueEnum::IntValue(initial)) = global.get_initializer() {
+            let percent = env.list_growth_percent.unwrap_or(0);
+
+            global.set_initializer(&initial.get_type().const_int(percent as u64, false));
+            global.set_constant(true);
+            global.set_linkage(Linkage::Internal);
+        }
+    }
+}
+
 /// Pointer to RocStr which is the panic message.
 pub fn get_panic_msg_ptr<'ctx>(env: &Env<'_, 'ctx, '_>) -> PointerValue<'ctx> {
     let str_typ = zig_str_type(env);
@@ -4346,6 +4520,7 @@ pub fn get_panic_msg_ptr<'ctx>(env: &Env<'_, 'ctx, '_>) -> PointerValue<'ctx> {
     let global = env.module.get_global(global_name).unwrap_or_else(|| {
         let global = env.module.add_global(str_typ, None, global_name);
         global.set_initializer(&str_typ.const_zero());
+        set_thread_local_if_needed(env, global);
         global
     });
 
@@ -4361,6 +4536,7 @@ pub fn get_panic_tag_ptr<'ctx>(env: &Env<'_, 'ctx, '_>) -> PointerValue<'ctx> {
     let global = env.module.get_global(global_name).unwrap_or_else(|| {
         let global = env.module.add_global(i64_typ, None, global_name);
         global.set_initializer(&i64_typ.const_zero());
+        set_thread_local_if_needed(env, global);
         global
     });
 
@@ -4892,6 +5068,9 @@ fn build_procedures_help<'a>(
     let mut layout_ids = roc_mono::layout::LayoutIds::default();
     let mut scope = Scope::default();
 
+    set_refcount_atomicity(env);
+    set_list_growth(env);
+
     let it = procedures.iter().map(|x| x.1);
 
     let solutions = match roc_alias_analysis::spec_program(
@@ -4995,9 +5174,7 @@ fn func_spec_name<'a>(
 
     let mut buf = bumpalo::collections::String::with_capacity_in(1, arena);
 
-    let ident_string = symbol.as_str(interns);
-    let module_string = interns.module_ids.get_name(symbol.module_id()).unwrap();
-    write!(buf, "{}_{}_", module_string, ident_string).unwrap();
+    write_proc_name_prefix(&mut buf, interns, symbol);
 
     for byte in func_spec.0.iter() {
         write!(buf, "{:x?}", byte).unwrap();
@@ -5006,6 +5183,18 @@ fn func_spec_name<'a>(
     buf
 }
 
+/// Writes what the names of all specializations of `symbol` start with; the rest of such a
+/// name is made of hex digits.
+pub(crate) fn write_proc_name_prefix(
+    buf: &mut impl std::fmt::Write,
+    interns: &Interns,
+    symbol: Symbol,
+) {
+    let ident_string = symbol.as_str(interns);
+    let module_string = interns.module_ids.get_name(symbol.module_id()).unwrap();
+    write!(buf, "{}_{}_", module_string, ident_string).unwrap();
+}
+
 fn build_proc_header<'a, 'ctx>(
     env: &Env<'a, 'ctx, '_>,
     layout_interner: &STLayoutInterner<'a>,
@@ -5082,6 +5271,7 @@ fn build_proc_header<'a, 'ctx>(
 fn expose_alias_to_host<'a>(
     env: &Env<'a, '_, '_>,
     layout_interner: &STLayoutInterner<'a>,
+    layout_ids: &mut LayoutIds<'a>,
     mod_solutions: &'a ModSolutions,
     fn_name: &str,
     alias_symbol: Symbol,
@@ -5132,6 +5322,7 @@ fn expose_alias_to_host<'a>(
             build_closure_caller(
                 env,
                 layout_interner,
+                layout_ids,
                 fn_name,
                 evaluator,
                 alias_symbol,
@@ -5164,6 +5355,7 @@ fn expose_alias_to_host<'a>(
 fn build_closure_caller<'a, 'ctx>(
     env: &Env<'a, 'ctx, '_>,
     layout_interner: &STLayoutInterner<'a>,
+    layout_ids: &mut LayoutIds<'a>,
     def_name: &str,
     evaluator: FunctionValue<'ctx>,
     alias_symbol: Symbol,
@@ -5286,6 +5478,68 @@ fn build_closure_caller<'a, 'ctx>(
         alias_symbol,
         lambda_set.runtime_representation(),
     );
+
+    // STEP 5: build functions that increment and decrement the refcounts in the closure data
+    build_host_exposed_closure_refcount(
+        env,
+        layout_interner,
+        layout_ids,
+        def_name,
+        lambda_set.runtime_representation(),
+    );
+}
+
+/// The closure caller consumes the closure data, like any other Roc function consumes its
+/// arguments. A host that holds on to a closure, e.g. to call it whenever an event happens, calls
+///
+/// * `roc__mainForHost_0_inc(closure_data)` before each call it makes, so the data outlives it
+/// * `roc__mainForHost_0_dec(closure_data)` once it no longer needs the closure
+fn build_host_exposed_closure_refcount<'a, 'ctx>(
+    env: &Env<'a, 'ctx, '_>,
+    layout_interner: &STLayoutInterner<'a>,
+    layout_ids: &mut LayoutIds<'a>,
+    def_name: &str,
+    closure_layout: InLayout<'a>,
+) {
+    let builder = env.builder;
+    let context = env.context;
+
+    let opaque_ptr_type = context.i8_type().ptr_type(AddressSpace::default());
+
+    for label in ["inc", "dec"] {
+        let function_name = format!("roc__{}_{}", def_name, label);
+
+        let function_spec =
+            FunctionSpec::cconv(env, CCReturn::Void, None, &[opaque_ptr_type.into()]);
+
+        let function_value = add_func(
+            env.context,
+            env.module,
+            function_name.as_str(),
+            function_spec,
+            Linkage::External,
+        );
+
+        let subprogram = env.new_subprogram(&function_name);
+        function_value.set_subprogram(subprogram);
+
+        let entry = context.append_basic_block(function_value, "entry");
+        builder.position_at_end(entry);
+
+        debug_info_init!(env, function_value);
+
+        let rc_wrapper = if label == "inc" {
+            build_inc_wrapper(env, layout_interner, layout_ids, closure_layout)
+        } else {
+            build_dec_wrapper(env, layout_interner, layout_ids, closure_layout)
+        };
+
+        let closure_data = function_value.get_nth_param(0).unwrap();
+        let call = builder.build_call(rc_wrapper, &[closure_data.into()], "modify_closure_data");
+        call.set_call_convention(C_CALL_CONV);
+
+        builder.build_return(None);
+    }
 }
 
 fn build_host_exposed_alias_size<'a, 'r>(
@@ -5367,6 +5621,7 @@ fn build_proc<'a, 'ctx>(
                         expose_alias_to_host(
                             env,
                             layout_interner,
+                            layout_ids,
                             mod_solutions,
                             &fn_name,
                             *alias_name,
@@ -5890,6 +6145,14 @@ pub fn to_cc_return<'a>(
         }
         roc_target::OperatingSystem::Unix => return_size > 2 * env.target_info.ptr_width() as u32,
         roc_target::OperatingSystem::Wasi => return_size > 2 * env.target_info.ptr_width() as u32,
+        // the bare-metal ARM ABI (AAPCS) returns numbers of up to two words in registers,
+        // but any struct bigger than one word through memory
+        roc_target::OperatingSystem::Freestanding => match layout_interner.get_repr(layout) {
+            LayoutRepr::Builtin(Builtin::Bool | Builtin::Int(_) | Builtin::Float(_)) => {
+                return_size > 2 * env.target_info.ptr_width() as u32
+            }
+            _ => return_size > env.target_info.ptr_width() as u32,
+        },
     };
 
     if return_size == 0 {
@@ -5954,10 +6217,12 @@ fn build_foreign_symbol<'a, 'ctx>(
             let mut fastcc_argument_types =
                 Vec::with_capacity_in(argument_symbols.len(), env.arena);
             let mut arguments = Vec::with_capacity_in(argument_symbols.len(), env.arena);
+            let mut argument_layouts = Vec::with_capacity_in(argument_symbols.len(), env.arena);
 
             for symbol in argument_symbols {
                 let (value, layout) = scope.load_symbol_and_layout(symbol);
 
+                argument_layouts.push(layout);
                 cc_argument_types.push(to_cc_type(env, layout_interner, layout));
 
                 let basic_type = argument_type_from_layout(
@@ -6004,6 +6269,25 @@ fn build_foreign_symbol<'a, 'ctx>(
                     cc_arguments.push(return_pointer.into());
                 }
 
+                let trace = env.trace_effects
+                    && env.runs_expects()
+                    && matches!(env.mode, LlvmBackendMode::BinaryDev);
+
+                if trace {
+                    let it = fastcc_parameters
+                        .iter()
+                        .copied()
+                        .zip(argument_layouts.iter().copied());
+                    let traced = Vec::from_iter_in(it, env.arena);
+
+                    crate::llvm::expect::trace_effect_call(
+                        env,
+                        layout_interner,
+                        foreign.as_str(),
+                        &traced,
+                    );
+                }
+
                 let it = fastcc_parameters.into_iter().zip(cc_argument_types.iter());
                 for (param, cc_type) in it {
                     if param.get_type() == *cc_type {
@@ -6043,6 +6327,10 @@ fn build_foreign_symbol<'a, 'ctx>(
                 let call = env.builder.build_call(cc_function, &cc_arguments, "tmp");
                 call.set_call_convention(C_CALL_CONV);
 
+                if trace {
+                    crate::llvm::expect::trace_effect_return(env);
+                }
+
                 match roc_return {
                     RocReturn::Return => {
                         let return_value = match cc_return {
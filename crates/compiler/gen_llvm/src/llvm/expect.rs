@@ -1,14 +1,14 @@
 use crate::debug_info_init;
-use crate::llvm::bitcode::call_str_bitcode_fn;
+use crate::llvm::bitcode::{call_str_bitcode_fn, call_void_bitcode_fn};
 use crate::llvm::build::{get_tag_id, store_roc_value, tag_pointer_clear_tag_id, Env};
 use crate::llvm::build_list::{self, incrementing_elem_loop};
-use crate::llvm::convert::{basic_type_from_layout, RocUnion};
+use crate::llvm::convert::{basic_type_from_layout, zig_str_type, RocUnion};
 use inkwell::builder::Builder;
 use inkwell::module::Linkage;
 use inkwell::types::{BasicMetadataTypeEnum, BasicType, BasicTypeEnum};
-use inkwell::values::{BasicValueEnum, FunctionValue, IntValue, PointerValue};
+use inkwell::values::{BasicValueEnum, FunctionValue, GlobalValue, IntValue, PointerValue};
 use inkwell::AddressSpace;
-use roc_builtins::bitcode;
+use roc_builtins::bitcode::{self, FloatWidth};
 use roc_error_macros::internal_error;
 use roc_module::symbol::Symbol;
 use roc_mono::ir::LookupType;
@@ -20,6 +20,7 @@ use roc_region::all::Region;
 use super::build::BuilderExt;
 use super::build::{add_func, load_roc_value, FunctionSpec, LlvmBackendMode};
 use super::convert::struct_type_from_union_layout;
+use super::externs::{EXPECT_CALL_SITES_GLOBAL, EXPECT_FAILED_IMPORT};
 use super::scope::Scope;
 use super::struct_::RocStruct;
 
@@ -64,32 +65,176 @@ fn pointer_at_offset<'ctx>(
     unsafe { bd.new_build_in_bounds_gep(element_type, ptr, &[offset], "offset_ptr") }
 }
 
+/// The region and module of an expect or dbg, as they appear in the frame header:
+/// region start, region end and module id, each a u32.
+const HEADER_SIZE: u32 = 3 * std::mem::size_of::<u32>() as u32;
+
+/// How many of the innermost calls that led to an expect or dbg its frame keeps.
+const CALL_SITES_CAPACITY: u32 = 4;
+
+/// The calls that led to an expect or dbg, as they appear in the frame after its header:
+/// how many calls there were (a u32), followed by a ring of [CALL_SITES_CAPACITY] entries
+/// that have the same shape as a header. The call at depth `d` is in entry `d % capacity`.
+const CALL_SITES_SIZE: u32 = (1 + 3 * CALL_SITES_CAPACITY) * std::mem::size_of::<u32>() as u32;
+
+/// Gets the read-only header entry for this region, adding it to the module if needed.
+///
+/// Every expect and dbg site copies its header out of this table of constants, instead
+/// of materializing the region and module id as immediates. That keeps the code small
+/// for modules with many inline expects, and entries for the same region are shared.
+fn header_entry<'ctx>(
+    env: &Env<'_, 'ctx, '_>,
+    condition: Symbol,
+    region: Region,
+) -> GlobalValue<'ctx> {
+    let module_id: u32 = unsafe { std::mem::transmute(condition.module_id()) };
+    let region_start = region.start().offset;
+    let region_end = region.end().offset;
+
+    let name = format!("roc__expect_header_{module_id}_{region_start}_{region_end}");
+
+    if let Some(global) = env.module.get_global(&name) {
+        return global;
+    }
+
+    let i32_type = env.context.i32_type();
+    let header_type = env.context.struct_type(&[i32_type.into(); 3], false);
+    let header = header_type.const_named_struct(&[
+        i32_type.const_int(region_start as _, false).into(),
+        i32_type.const_int(region_end as _, false).into(),
+        i32_type.const_int(module_id as _, false).into(),
+    ]);
+
+    let global = env.module.add_global(header_type, None, &name);
+
+    global.set_constant(true);
+    global.set_unnamed_addr(true);
+    global.set_linkage(Linkage::Private);
+    global.set_initializer(&header);
+
+    global
+}
+
 /// Writes the module and region into the buffer
 fn write_header<'ctx>(
     env: &Env<'_, 'ctx, '_>,
     ptr: PointerValue<'ctx>,
-    mut offset: IntValue<'ctx>,
+    offset: IntValue<'ctx>,
     condition: Symbol,
     region: Region,
 ) -> IntValue<'ctx> {
-    let region_start = env
-        .context
-        .i32_type()
-        .const_int(region.start().offset as _, false);
+    let entry = header_entry(env, condition, region);
+
+    let destination = pointer_at_offset(env.builder, env.context.i8_type(), ptr, offset);
+    let header_size = env.ptr_int().const_int(HEADER_SIZE as _, false);
+
+    // the shared buffer gives no alignment guarantees for the header
+    env.builder
+        .build_memcpy(destination, 1, entry.as_pointer_value(), 4, header_size)
+        .unwrap();
+
+    offset_add(env.builder, offset, HEADER_SIZE)
+}
 
-    let region_end = env
+/// Gets the [EXPECT_CALL_SITES_GLOBAL] that [push_call_site] and [pop_call_site] keep up to date,
+/// adding it to the module if needed. It has the shape of the call sites in a frame.
+fn call_sites_global<'ctx>(env: &Env<'_, 'ctx, '_>) -> GlobalValue<'ctx> {
+    if let Some(global) = env.module.get_global(EXPECT_CALL_SITES_GLOBAL) {
+        return global;
+    }
+
+    let array_type = env
         .context
         .i32_type()
-        .const_int(region.end().offset as _, false);
+        .array_type(CALL_SITES_SIZE / std::mem::size_of::<u32>() as u32);
 
-    let module_id: u32 = unsafe { std::mem::transmute(condition.module_id()) };
-    let module_id = env.context.i32_type().const_int(module_id as _, false);
+    let global = env
+        .module
+        .add_global(array_type, None, EXPECT_CALL_SITES_GLOBAL);
+
+    // `roc test` resets it before every expect, because an expect that crashes skips the pops
+    let linkage = match env.mode {
+        LlvmBackendMode::CliTest => Linkage::External,
+        _ => Linkage::Internal,
+    };
 
-    offset = build_copy(env, ptr, offset, region_start.into());
-    offset = build_copy(env, ptr, offset, region_end.into());
-    offset = build_copy(env, ptr, offset, module_id.into());
+    global.set_linkage(linkage);
+    global.set_initializer(&array_type.const_zero());
+
+    global
+}
 
-    offset
+fn call_sites_depth_ptr<'ctx>(env: &Env<'_, 'ctx, '_>) -> PointerValue<'ctx> {
+    env.builder.build_pointer_cast(
+        call_sites_global(env).as_pointer_value(),
+        env.context.i32_type().ptr_type(AddressSpace::default()),
+        "call_sites_depth",
+    )
+}
+
+/// Remembers that the call whose result is bound to `call` is running, until the matching
+/// [pop_call_site]. The frames of the expects and dbgs that fail in the meantime say that
+/// they were called from its `region`.
+pub(crate) fn push_call_site(env: &Env<'_, '_, '_>, call: Symbol, region: Region) {
+    let builder = env.builder;
+    let i32_type = env.context.i32_type();
+
+    let depth_ptr = call_sites_depth_ptr(env);
+    let depth = builder
+        .new_build_load(i32_type, depth_ptr, "depth")
+        .into_int_value();
+
+    // once the ring is full, a call replaces the outermost one it has
+    let slot = builder.build_int_unsigned_rem(
+        depth,
+        i32_type.const_int(CALL_SITES_CAPACITY as _, false),
+        "slot",
+    );
+    let slot_start = builder.build_int_mul(slot, i32_type.const_int(3, false), "slot_start");
+    let entry_index = builder.build_int_add(slot_start, i32_type.const_int(1, false), "entry");
+
+    let destination = pointer_at_offset(builder, i32_type, depth_ptr, entry_index);
+    let entry = header_entry(env, call, region);
+    let header_size = env.ptr_int().const_int(HEADER_SIZE as _, false);
+
+    builder
+        .build_memcpy(destination, 4, entry.as_pointer_value(), 4, header_size)
+        .unwrap();
+
+    let depth = builder.build_int_add(depth, i32_type.const_int(1, false), "push");
+    builder.build_store(depth_ptr, depth);
+}
+
+/// Forgets the call of the latest [push_call_site], which has returned.
+pub(crate) fn pop_call_site(env: &Env<'_, '_, '_>) {
+    let builder = env.builder;
+    let i32_type = env.context.i32_type();
+
+    let depth_ptr = call_sites_depth_ptr(env);
+    let depth = builder
+        .new_build_load(i32_type, depth_ptr, "depth")
+        .into_int_value();
+
+    let depth = builder.build_int_sub(depth, i32_type.const_int(1, false), "pop");
+    builder.build_store(depth_ptr, depth);
+}
+
+/// Copies the calls that are running into the buffer, right after the header
+fn write_call_sites<'ctx>(
+    env: &Env<'_, 'ctx, '_>,
+    ptr: PointerValue<'ctx>,
+    offset: IntValue<'ctx>,
+) -> IntValue<'ctx> {
+    let call_sites = call_sites_global(env);
+
+    let destination = pointer_at_offset(env.builder, env.context.i8_type(), ptr, offset);
+    let size = env.ptr_int().const_int(CALL_SITES_SIZE as _, false);
+
+    env.builder
+        .build_memcpy(destination, 1, call_sites.as_pointer_value(), 4, size)
+        .unwrap();
+
+    offset_add(env.builder, offset, CALL_SITES_SIZE)
 }
 
 /// Read the first two 32-bit values from the shared memory,
@@ -137,6 +282,16 @@ fn offset_add<'ctx>(
     builder.build_int_add(current, intval, "offset_add")
 }
 
+/// Makes sure the shared memory is big enough to write up to `end`, asking the parent to grow it
+/// if it isn't. Called whenever part of the buffer is claimed for the frame, before writing to it.
+fn ensure_capacity<'ctx>(env: &Env<'_, 'ctx, '_>, ptr: PointerValue<'ctx>, end: IntValue<'ctx>) {
+    call_void_bitcode_fn(
+        env,
+        &[ptr.into(), end.into()],
+        bitcode::UTILS_EXPECT_ENSURE_CAPACITY,
+    );
+}
+
 pub(crate) fn notify_parent_expect(env: &Env, shared_memory: &SharedMemoryPointer) {
     let func = env
         .module
@@ -150,6 +305,32 @@ pub(crate) fn notify_parent_expect(env: &Env, shared_memory: &SharedMemoryPointe
     );
 }
 
+/// Hands the frame that was just written to whoever runs the wasm32 module, with the
+/// `roc_expect_failed(buffer, length)` import. The runner resets the buffer for the next frame.
+pub(crate) fn notify_runner_expect(env: &Env, shared_memory: &SharedMemoryPointer) {
+    let buffer = shared_memory.0;
+    let (_count, length) = read_state(env, buffer);
+
+    let func = match env.module.get_function(EXPECT_FAILED_IMPORT) {
+        Some(func) => func,
+        None => {
+            let fn_type = env
+                .context
+                .void_type()
+                .fn_type(&[buffer.get_type().into(), env.ptr_int().into()], false);
+
+            env.module
+                .add_function(EXPECT_FAILED_IMPORT, fn_type, Some(Linkage::External))
+        }
+    };
+
+    env.builder.build_call(
+        func,
+        &[buffer.into(), length.into()],
+        "call_roc_expect_failed",
+    );
+}
+
 pub(crate) fn notify_parent_dbg(env: &Env, shared_memory: &SharedMemoryPointer) {
     let func = env.module.get_function(bitcode::NOTIFY_PARENT_DBG).unwrap();
 
@@ -160,10 +341,167 @@ pub(crate) fn notify_parent_dbg(env: &Env, shared_memory: &SharedMemoryPointer)
     );
 }
 
+/// Tells the parent process which effect is about to run, and with which arguments,
+/// for `roc dev --trace-effects`. The effect's name is the host function without its `roc_fx_` prefix.
+pub(crate) fn trace_effect_call<'a, 'ctx>(
+    env: &Env<'a, 'ctx, '_>,
+    layout_interner: &STLayoutInterner<'a>,
+    host_function: &str,
+    arguments: &[(BasicValueEnum<'ctx>, InLayout<'a>)],
+) {
+    let name = host_function.trim_start_matches("roc_fx_");
+    let (name_ptr, name_length) = trace_text(env, name);
+
+    call_void_bitcode_fn(
+        env,
+        &[name_ptr.into(), name_length.into()],
+        bitcode::UTILS_TRACE_EFFECT_BEGIN,
+    );
+
+    for (value, layout) in arguments {
+        trace_effect_argument(env, layout_interner, *value, *layout);
+    }
+
+    call_void_bitcode_fn(env, &[], bitcode::UTILS_TRACE_EFFECT_CALL);
+}
+
+/// Tells the parent process that the effect from the last [trace_effect_call] has returned.
+pub(crate) fn trace_effect_return(env: &Env) {
+    call_void_bitcode_fn(env, &[], bitcode::UTILS_TRACE_EFFECT_RETURN);
+}
+
+fn trace_effect_argument<'a, 'ctx>(
+    env: &Env<'a, 'ctx, '_>,
+    layout_interner: &STLayoutInterner<'a>,
+    value: BasicValueEnum<'ctx>,
+    layout: InLayout<'a>,
+) {
+    let builder = env.builder;
+
+    // Only show the values we can read without knowing the argument's type; lists, records etc.
+    // would need the type checker's help, like `dbg` has.
+    let placeholder = match layout_interner.get_repr(layout) {
+        LayoutRepr::Builtin(Builtin::Str) => {
+            let str_ptr = if value.is_pointer_value() {
+                value.into_pointer_value()
+            } else {
+                let alloca = builder.build_alloca(value.get_type(), "trace_str");
+                builder.build_store(alloca, value);
+
+                alloca
+            };
+
+            let zig_str_ptr = builder.build_pointer_cast(
+                str_ptr,
+                zig_str_type(env).ptr_type(AddressSpace::default()),
+                "to_zig_str",
+            );
+
+            call_void_bitcode_fn(
+                env,
+                &[zig_str_ptr.into()],
+                bitcode::UTILS_TRACE_EFFECT_ARG_STR,
+            );
+
+            return;
+        }
+        LayoutRepr::Builtin(Builtin::Int(int_width)) if int_width.stack_size() <= 8 => {
+            let signed = int_width.is_signed();
+            let as_64_bits = builder.build_int_cast_sign_flag(
+                value.into_int_value(),
+                env.context.i64_type(),
+                signed,
+                "trace_int",
+            );
+
+            let function = if signed {
+                bitcode::UTILS_TRACE_EFFECT_ARG_INT
+            } else {
+                bitcode::UTILS_TRACE_EFFECT_ARG_UINT
+            };
+
+            call_void_bitcode_fn(env, &[as_64_bits.into()], function);
+
+            return;
+        }
+        LayoutRepr::Builtin(Builtin::Float(float_width)) => {
+            let value = value.into_float_value();
+            let as_f64 = match float_width {
+                FloatWidth::F32 => {
+                    builder.build_float_ext(value, env.context.f64_type(), "trace_float")
+                }
+                FloatWidth::F64 => value,
+            };
+
+            call_void_bitcode_fn(env, &[as_f64.into()], bitcode::UTILS_TRACE_EFFECT_ARG_FLOAT);
+
+            return;
+        }
+        LayoutRepr::Builtin(Builtin::Bool) => {
+            let (true_ptr, true_length) = trace_text(env, "Bool.true");
+            let (false_ptr, false_length) = trace_text(env, "Bool.false");
+            let is_true = value.into_int_value();
+
+            let ptr = builder.build_select(is_true, true_ptr, false_ptr, "trace_bool_ptr");
+            let length = builder.build_select(is_true, true_length, false_length, "trace_bool_len");
+
+            call_void_bitcode_fn(env, &[ptr, length], bitcode::UTILS_TRACE_EFFECT_ARG_OTHER);
+
+            return;
+        }
+        LayoutRepr::Builtin(Builtin::Int(int_width)) => format!("<{:?}>", int_width),
+        LayoutRepr::Builtin(Builtin::Decimal) => "<Dec>".to_string(),
+        LayoutRepr::Builtin(Builtin::List(_)) => "<List>".to_string(),
+        LayoutRepr::Struct([]) => "{}".to_string(),
+        LayoutRepr::Struct(_) => "<record>".to_string(),
+        LayoutRepr::Boxed(_) => "<Box>".to_string(),
+        LayoutRepr::Union(_) | LayoutRepr::RecursivePointer(_) => "<tag union>".to_string(),
+        LayoutRepr::LambdaSet(_) => "<function>".to_string(),
+    };
+
+    let (ptr, length) = trace_text(env, &placeholder);
+
+    call_void_bitcode_fn(
+        env,
+        &[ptr.into(), length.into()],
+        bitcode::UTILS_TRACE_EFFECT_ARG_OTHER,
+    );
+}
+
+/// A pointer to a read-only copy of `text`, and its length.
+fn trace_text<'ctx>(env: &Env<'_, 'ctx, '_>, text: &str) -> (PointerValue<'ctx>, IntValue<'ctx>) {
+    let name = format!("roc__trace_text_{text}");
+    let length = env.ptr_int().const_int(text.len() as _, false);
+
+    let global = match env.module.get_global(&name) {
+        Some(global) => global,
+        None => {
+            let bytes = env.context.const_string(text.as_bytes(), false);
+            let global = env.module.add_global(bytes.get_type(), None, &name);
+
+            global.set_constant(true);
+            global.set_unnamed_addr(true);
+            global.set_linkage(Linkage::Private);
+            global.set_initializer(&bytes);
+
+            global
+        }
+    };
+
+    let ptr = env.builder.build_pointer_cast(
+        global.as_pointer_value(),
+        env.context.i8_type().ptr_type(AddressSpace::default()),
+        "trace_text_ptr",
+    );
+
+    (ptr, length)
+}
+
 // Shape of expect frame:
 //
 //     ===
 //     Fixed-size header
+//     Fixed-size call sites
 //     ===
 // /-- ptr_lookup_1  (ptr_size)
 // |   var_lookup_1  (u32)
@@ -174,6 +512,10 @@ pub(crate) fn notify_parent_dbg(env: &Env, shared_memory: &SharedMemoryPointer)
 //     ..
 //     lookup_val_n  (varsize)
 //
+// A value that points at another one (a string, list, box, or recursive tag) holds the offset of
+// that value from the start of the buffer instead of its address, so the frame can be read by any
+// process that maps the buffer, wherever it maps it.
+//
 pub(crate) fn clone_to_shared_memory<'a, 'ctx>(
     env: &Env<'a, 'ctx, '_>,
     layout_interner: &STLayoutInterner<'a>,
@@ -189,16 +531,27 @@ pub(crate) fn clone_to_shared_memory<'a, 'ctx>(
 
     let (count, mut offset) = read_state(env, original_ptr);
 
-    offset = write_header(env, original_ptr, offset, condition, region);
-
-    let after_header = offset;
-
     let space_for_offsets = env.ptr_int().const_int(
         (lookups.len() * env.target_info.ptr_size() + lookups.len() * std::mem::size_of::<u32>())
             as _,
         false,
     );
 
+    ensure_capacity(
+        env,
+        original_ptr,
+        env.builder.build_int_add(
+            offset_add(env.builder, offset, HEADER_SIZE + CALL_SITES_SIZE),
+            space_for_offsets,
+            "after_offsets",
+        ),
+    );
+
+    offset = write_header(env, original_ptr, offset, condition, region);
+    offset = write_call_sites(env, original_ptr, offset);
+
+    let after_header = offset;
+
     let mut lookup_starts = bumpalo::collections::Vec::with_capacity_in(lookups.len(), env.arena);
 
     offset = env
@@ -216,6 +569,8 @@ pub(crate) fn clone_to_shared_memory<'a, 'ctx>(
 
         let mut extra_offset = env.builder.build_int_add(offset, stack_size, "offset");
 
+        ensure_capacity(env, original_ptr, extra_offset);
+
         let cursors = Cursors {
             offset,
             extra_offset,
@@ -317,9 +672,16 @@ fn build_clone<'a, 'ctx>(
             field_layouts,
         ),
 
-        // Since we will never actually display functions (and hence lambda sets)
-        // we just write nothing to the buffer
-        LayoutRepr::LambdaSet(_) => cursors.extra_offset,
+        // A function is written as its closure data, so the captured values can be shown
+        LayoutRepr::LambdaSet(lambda_set) => build_clone(
+            env,
+            layout_interner,
+            layout_ids,
+            ptr,
+            cursors,
+            value,
+            layout_interner.get_repr(lambda_set.runtime_representation()),
+        ),
 
         LayoutRepr::Union(union_layout) => {
             if layout.safe_to_memcpy(layout_interner) {
@@ -374,6 +736,13 @@ fn build_clone<'a, 'ctx>(
                 .builder
                 .build_int_add(cursors.offset, inner_width, "new_extra");
 
+            ensure_capacity(
+                env,
+                ptr,
+                env.builder
+                    .build_int_add(cursors.extra_offset, inner_width, "inner_end"),
+            );
+
             let cursors = Cursors {
                 offset: cursors.extra_offset,
                 extra_offset: new_extra,
@@ -766,6 +1135,8 @@ fn build_clone_tag_help<'a, 'ctx>(
                     ),
                 };
 
+                ensure_capacity(env, ptr, cursors.extra_offset);
+
                 let answer =
                     build_clone(env, layout_interner, layout_ids, ptr, cursors, data, layout);
 
@@ -807,6 +1178,8 @@ fn build_clone_tag_help<'a, 'ctx>(
 
             let data = load_tag_data(env, layout_interner, union_layout, tag_value, basic_type);
 
+            ensure_capacity(env, ptr, cursors.extra_offset);
+
             let answer = build_clone(env, layout_interner, layout_ids, ptr, cursors, data, layout);
 
             env.builder.build_return(Some(&answer));
@@ -865,6 +1238,8 @@ fn build_clone_tag_help<'a, 'ctx>(
                     let data =
                         load_tag_data(env, layout_interner, union_layout, tag_value, basic_type);
 
+                    ensure_capacity(env, ptr, cursors.extra_offset);
+
                     let answer =
                         build_clone(env, layout_interner, layout_ids, ptr, cursors, data, layout);
 
@@ -942,6 +1317,8 @@ fn build_clone_tag_help<'a, 'ctx>(
                     basic_type,
                 );
 
+                ensure_capacity(env, ptr, cursors.extra_offset);
+
                 let answer =
                     build_clone(env, layout_interner, layout_ids, ptr, cursors, data, layout);
 
@@ -1057,6 +1434,12 @@ fn build_clone_builtin<'a, 'ctx>(
 
             let elements_width = bd.build_int_mul(element_width, len, "elements_width");
 
+            ensure_capacity(
+                env,
+                ptr,
+                bd.build_int_add(cursors.extra_offset, elements_width, "elements_end"),
+            );
+
             // We clone the elements into the extra_offset address.
             let _ = offset;
             let elements_start_offset = cursors.extra_offset;
@@ -1,11 +1,19 @@
+use crate::debug_info_init;
 use crate::llvm::bitcode::call_void_bitcode_fn;
-use crate::llvm::build::{add_func, get_panic_msg_ptr, get_panic_tag_ptr, BuilderExt, C_CALL_CONV};
+use crate::llvm::build::{
+    add_func, get_panic_msg_ptr, get_panic_tag_ptr, throw_internal_exception,
+    write_proc_name_prefix, BuilderExt, C_CALL_CONV,
+};
 use crate::llvm::build::{CCReturn, Env, FunctionSpec};
 use crate::llvm::convert::zig_str_type;
 use inkwell::module::Linkage;
 use inkwell::types::BasicType;
-use inkwell::AddressSpace;
+use inkwell::values::BasicValueEnum;
+use inkwell::{AddressSpace, IntPredicate};
 use roc_builtins::bitcode;
+use roc_collections::all::MutMap;
+use roc_error_macros::internal_error;
+use roc_module::symbol::Symbol;
 
 use super::build::get_sjlj_buffer;
 use super::intrinsics::LLVM_LONGJMP;
@@ -20,17 +28,19 @@ pub fn add_default_roc_externs(env: &Env<'_, '_, '_>) {
     let usize_type = env.ptr_int();
     let i8_ptr_type = ctx.i8_type().ptr_type(AddressSpace::default());
 
-    match env.mode {
-        super::build::LlvmBackendMode::CliTest => {
-            // expose this function
-            if let Some(fn_val) = module.get_function("set_shared_buffer") {
-                fn_val.set_linkage(Linkage::External);
+    for name in ["set_shared_buffer", "set_shared_buffer_grow"] {
+        match env.mode {
+            super::build::LlvmBackendMode::CliTest => {
+                // expose this function
+                if let Some(fn_val) = module.get_function(name) {
+                    fn_val.set_linkage(Linkage::External);
+                }
             }
-        }
-        _ => {
-            // remove this function from the module
-            if let Some(fn_val) = module.get_function("set_shared_buffer") {
-                unsafe { fn_val.delete() };
+            _ => {
+                // remove this function from the module
+                if let Some(fn_val) = module.get_function(name) {
+                    unsafe { fn_val.delete() };
+                }
             }
         }
     }
@@ -160,6 +170,12 @@ pub fn add_default_roc_externs(env: &Env<'_, '_, '_>) {
             }
         }
 
+        // wasm has no processes, and no setjmp/longjmp. So the builtins that talk to a parent
+        // process aren't there, and roc_panic stays an import, for whoever runs the module.
+        if env.target_info.architecture == roc_target::Architecture::Wasm32 {
+            return;
+        }
+
         unreachable_function(env, "roc_getppid");
         unreachable_function(env, "roc_mmap");
         unreachable_function(env, "roc_shm_open");
@@ -168,6 +184,384 @@ pub fn add_default_roc_externs(env: &Env<'_, '_, '_>) {
     }
 }
 
+/// The number of times roc_alloc was called, see [add_allocation_counters]
+pub const ALLOC_COUNT_GLOBAL: &str = "roc_alloc_count";
+
+/// The total number of bytes requested from roc_alloc, see [add_allocation_counters]
+pub const ALLOC_BYTES_GLOBAL: &str = "roc_alloc_bytes";
+
+/// Make the roc_alloc defined by [add_default_roc_externs] count its calls and the bytes it
+/// was asked for, in two exported pointer-sized globals. The repl reads these to report
+/// allocations when benchmarking an expression.
+pub fn add_allocation_counters(env: &Env<'_, '_, '_>) {
+    let builder = env.builder;
+    let usize_type = env.ptr_int();
+
+    let fn_val = env.module.get_function("roc_alloc").unwrap();
+    let size_arg = fn_val.get_nth_param(0).unwrap().into_int_value();
+
+    // Count before anything else happens in roc_alloc
+    let entry = fn_val.get_first_basic_block().unwrap();
+    builder.position_before(&entry.get_first_instruction().unwrap());
+
+    for (name, amount) in [
+        (ALLOC_COUNT_GLOBAL, usize_type.const_int(1, false)),
+        (ALLOC_BYTES_GLOBAL, size_arg),
+    ] {
+        let global = env.module.add_global(usize_type, None, name);
+        global.set_linkage(Linkage::External);
+        global.set_initializer(&usize_type.const_zero());
+
+        let ptr = global.as_pointer_value();
+        let old = builder
+            .new_build_load(usize_type, ptr, "old_count")
+            .into_int_value();
+        let new = builder.build_int_add(old, amount, "new_count");
+
+        builder.build_store(ptr, new);
+    }
+
+    if cfg!(debug_assertions) {
+        crate::llvm::build::verify_fn(fn_val);
+    }
+}
+
+/// The number of bytes allocated and not freed yet, see [add_memory_limit]
+pub const MEMORY_IN_USE_GLOBAL: &str = "roc_memory_in_use";
+
+/// The most bytes that were allocated at once without going over the limit, see
+/// [add_memory_limit]
+pub const MEMORY_HIGH_WATER_GLOBAL: &str = "roc_memory_high_water";
+
+/// The limit given to [add_memory_limit], so whoever runs the program can report it
+pub const MEMORY_LIMIT_GLOBAL: &str = "roc_memory_limit";
+
+/// What a program crashes with when an allocation takes it over its memory limit
+pub const MEMORY_LIMIT_MESSAGE: &str = "This program went over its memory limit.";
+
+/// Make the roc_alloc, roc_realloc, and roc_dealloc defined by [add_default_roc_externs] keep
+/// track of how many bytes are allocated, and crash the program like `crash` does when an
+/// allocation takes that over `max_bytes`. This needs the `roc_panic` defined by
+/// [add_sjlj_roc_panic].
+///
+/// The size of an allocation comes from libc (e.g. `malloc_usable_size`), because
+/// roc_dealloc isn't told the size of what it frees. So the counts include the padding libc
+/// adds, and only work when the module runs on the machine it was built for.
+///
+/// The counts are kept in exported pointer-sized globals, which whoever runs the program can
+/// reset, e.g. before each expect, and read after a crash to report them.
+pub fn add_memory_limit<'ctx>(env: &Env<'_, 'ctx, '_>, max_bytes: u64) {
+    let ctx = env.context;
+    let module = env.module;
+    let builder = env.builder;
+
+    let usize_type = env.ptr_int();
+    let i8_ptr_type = ctx.i8_type().ptr_type(AddressSpace::default());
+
+    let usable_size_name = match env.target_info.operating_system {
+        roc_target::OperatingSystem::Windows => "_msize",
+        roc_target::OperatingSystem::Unix if cfg!(target_os = "macos") => "malloc_size",
+        roc_target::OperatingSystem::Unix => "malloc_usable_size",
+        roc_target::OperatingSystem::Wasi | roc_target::OperatingSystem::Freestanding => {
+            internal_error!("there is no libc to get the size of an allocation from")
+        }
+    };
+
+    let usable_size = {
+        let fn_spec = FunctionSpec::cconv(
+            env,
+            CCReturn::Return,
+            Some(usize_type.as_basic_type_enum()),
+            &[i8_ptr_type.into()],
+        );
+
+        add_func(ctx, module, usable_size_name, fn_spec, Linkage::External)
+    };
+
+    let add_global = |name, initial_value| {
+        let global = module.add_global(usize_type, None, name);
+        global.set_linkage(Linkage::External);
+        global.set_initializer(&usize_type.const_int(initial_value, false));
+
+        global.as_pointer_value()
+    };
+
+    let in_use = add_global(MEMORY_IN_USE_GLOBAL, 0);
+    let high_water = add_global(MEMORY_HIGH_WATER_GLOBAL, 0);
+    let limit = add_global(MEMORY_LIMIT_GLOBAL, max_bytes);
+
+    let counted = |name: &str| {
+        let fn_type = ctx.void_type().fn_type(&[usize_type.into()], false);
+        let fn_val = module.add_function(name, fn_type, Some(Linkage::Internal));
+        fn_val.set_call_conventions(C_CALL_CONV);
+
+        fn_val
+    };
+
+    let count_alloc = counted("roc_memory_count_alloc");
+    let count_free = counted("roc_memory_count_free");
+
+    let usable_size_of = |ptr: BasicValueEnum<'ctx>| {
+        let call = builder.build_call(usable_size, &[ptr.into()], "usable_size");
+        call.set_call_convention(C_CALL_CONV);
+
+        call.try_as_basic_value().left().unwrap()
+    };
+
+    let call_counted = |fn_val, size: BasicValueEnum<'ctx>| {
+        builder
+            .build_call(fn_val, &[size.into()], "count")
+            .set_call_convention(C_CALL_CONV);
+    };
+
+    // roc_alloc counts what malloc gave it
+    {
+        let fn_val = module.get_function("roc_alloc").unwrap();
+        let entry = fn_val.get_first_basic_block().unwrap();
+        let ret = entry.get_terminator().unwrap();
+
+        builder.position_before(&ret);
+
+        let ptr = ret.get_operand(0).unwrap().left().unwrap();
+        let size = usable_size_of(ptr);
+        call_counted(count_alloc, size);
+    }
+
+    // roc_realloc stops counting the old allocation, and counts the new one
+    {
+        let fn_val = module.get_function("roc_realloc").unwrap();
+        let entry = fn_val.get_first_basic_block().unwrap();
+
+        builder.position_before(&entry.get_first_instruction().unwrap());
+
+        let old_size = usable_size_of(fn_val.get_nth_param(0).unwrap());
+        call_counted(count_free, old_size);
+
+        let ret = entry.get_terminator().unwrap();
+        builder.position_before(&ret);
+
+        let ptr = ret.get_operand(0).unwrap().left().unwrap();
+        let new_size = usable_size_of(ptr);
+        call_counted(count_alloc, new_size);
+    }
+
+    // roc_dealloc stops counting what it frees
+    {
+        let fn_val = module.get_function("roc_dealloc").unwrap();
+        let entry = fn_val.get_first_basic_block().unwrap();
+
+        builder.position_before(&entry.get_first_instruction().unwrap());
+
+        let size = usable_size_of(fn_val.get_nth_param(0).unwrap());
+        call_counted(count_free, size);
+    }
+
+    for fn_val in [count_alloc, count_free] {
+        let name = fn_val.get_name().to_str().unwrap();
+        let subprogram = env.new_subprogram(name);
+        fn_val.set_subprogram(subprogram);
+    }
+
+    // roc_memory_count_alloc
+    {
+        let fn_val = count_alloc;
+        let size = fn_val.get_nth_param(0).unwrap().into_int_value();
+
+        let entry = ctx.append_basic_block(fn_val, "entry");
+        let over_limit = ctx.append_basic_block(fn_val, "over_limit");
+        let within_limit = ctx.append_basic_block(fn_val, "within_limit");
+
+        builder.position_at_end(entry);
+        debug_info_init!(env, fn_val);
+
+        let old = builder
+            .new_build_load(usize_type, in_use, "old_in_use")
+            .into_int_value();
+        let new = builder.build_int_add(old, size, "new_in_use");
+        builder.build_store(in_use, new);
+
+        let max = builder
+            .new_build_load(usize_type, limit, "limit")
+            .into_int_value();
+        let is_over = builder.build_int_compare(IntPredicate::UGT, new, max, "is_over_limit");
+        builder.build_conditional_branch(is_over, over_limit, within_limit);
+
+        builder.position_at_end(over_limit);
+        throw_internal_exception(env, fn_val, MEMORY_LIMIT_MESSAGE);
+
+        builder.position_at_end(within_limit);
+
+        let old_high = builder
+            .new_build_load(usize_type, high_water, "old_high_water")
+            .into_int_value();
+        let is_higher = builder.build_int_compare(IntPredicate::UGT, new, old_high, "is_higher");
+        let new_high = builder.build_select(is_higher, new, old_high, "new_high_water");
+        builder.build_store(high_water, new_high);
+
+        builder.build_return(None);
+
+        if cfg!(debug_assertions) {
+            crate::llvm::build::verify_fn(fn_val);
+        }
+    }
+
+    // roc_memory_count_free
+    {
+        let fn_val = count_free;
+        let size = fn_val.get_nth_param(0).unwrap().into_int_value();
+
+        let entry = ctx.append_basic_block(fn_val, "entry");
+
+        builder.position_at_end(entry);
+        debug_info_init!(env, fn_val);
+
+        // Whoever runs the program may have reset the count since this was allocated
+        let old = builder
+            .new_build_load(usize_type, in_use, "old_in_use")
+            .into_int_value();
+        let is_counted = builder.build_int_compare(IntPredicate::UGE, old, size, "is_counted");
+        let decreased = builder.build_int_sub(old, size, "decreased");
+        let new =
+            builder.build_select(is_counted, decreased, usize_type.const_zero(), "new_in_use");
+        builder.build_store(in_use, new);
+
+        builder.build_return(None);
+
+        if cfg!(debug_assertions) {
+            crate::llvm::build::verify_fn(fn_val);
+        }
+    }
+}
+
+/// The function a wasm32 module built for `roc test` imports, to hand the frame of a failed
+/// expect to whoever runs it.
+pub const EXPECT_FAILED_IMPORT: &str = "roc_expect_failed";
+
+/// The global that has the calls that are running while expects run, which the frame of an
+/// expect or dbg includes. `roc test` sets its first u32 (how many calls there are) to zero
+/// before every expect.
+pub const EXPECT_CALL_SITES_GLOBAL: &str = "roc_expect_call_sites";
+
+/// The name of the function [add_exported_crash] defines.
+pub const EXPORTED_CRASH_FN: &str = "roc_expect_crash";
+
+/// Export a function that crashes the running Roc program like `roc_panic` does, with the same
+/// arguments: it jumps back to where the program was called, which then returns the crash.
+/// `roc test` calls this from a signal handler, to stop an expect that runs for too long.
+/// This needs the `roc_panic` defined by [add_sjlj_roc_panic].
+pub fn add_exported_crash(env: &Env<'_, '_, '_>) {
+    let roc_panic = env.module.get_function("roc_panic").unwrap();
+
+    let fn_val = env.module.add_function(
+        EXPORTED_CRASH_FN,
+        roc_panic.get_type(),
+        Some(Linkage::External),
+    );
+    fn_val.set_call_conventions(C_CALL_CONV);
+
+    let subprogram = env.new_subprogram(EXPORTED_CRASH_FN);
+    fn_val.set_subprogram(subprogram);
+
+    let entry = env.context.append_basic_block(fn_val, "entry");
+    env.builder.position_at_end(entry);
+
+    debug_info_init!(env, fn_val);
+
+    let args: Vec<_> = fn_val.get_param_iter().map(|arg| arg.into()).collect();
+    let call = env.builder.build_call(roc_panic, &args, "call_roc_panic");
+    call.set_call_convention(C_CALL_CONV);
+
+    env.builder.build_unreachable();
+
+    if cfg!(debug_assertions) {
+        crate::llvm::build::verify_fn(fn_val);
+    }
+}
+
+/// The exported global that points at the hit counters of [add_coverage_counters].
+pub const COVERAGE_COUNTERS_GLOBAL: &str = "roc_coverage_counters";
+
+/// Make each specialization of `definitions[i]` add one to the `i`th of an array of u64
+/// counters whenever it's called, so `roc test --coverage` can tell which definitions no
+/// expect used. The procedures must be built already.
+///
+/// The exported [COVERAGE_COUNTERS_GLOBAL] points at the counters. It starts out pointing at
+/// counters of the module's own, and whoever runs the program can point it elsewhere, e.g. at
+/// memory that it shares with the processes that run effectful expects.
+pub fn add_coverage_counters(env: &Env<'_, '_, '_>, definitions: &[Symbol]) {
+    let ctx = env.context;
+    let module = env.module;
+    let builder = env.builder;
+
+    let u64_type = ctx.i64_type();
+    let counters_type = u64_type.ptr_type(AddressSpace::default());
+    let array_type = u64_type.array_type(definitions.len() as u32);
+
+    let own_counters = module.add_global(array_type, None, "roc_coverage_own_counters");
+    own_counters.set_linkage(Linkage::Internal);
+    own_counters.set_initializer(&array_type.const_zero());
+
+    let counters = module.add_global(counters_type, None, COVERAGE_COUNTERS_GLOBAL);
+    counters.set_linkage(Linkage::External);
+    counters.set_initializer(&own_counters.as_pointer_value().const_cast(counters_type));
+
+    let mut index_by_prefix = MutMap::default();
+
+    for (index, symbol) in definitions.iter().enumerate() {
+        let mut prefix = String::new();
+        write_proc_name_prefix(&mut prefix, &env.interns, *symbol);
+
+        index_by_prefix.insert(prefix, index);
+    }
+
+    // the counting code belongs to no line of Roc code
+    builder.unset_current_debug_location();
+
+    for fn_val in module.get_functions() {
+        let name = fn_val.get_name().to_string_lossy();
+
+        // the specialization's hex digits follow the last underscore
+        let index = match name.rfind('_') {
+            Some(end) if name[end + 1..].bytes().all(|b| b.is_ascii_hexdigit()) => {
+                index_by_prefix.get(&name[..=end])
+            }
+            _ => None,
+        };
+
+        let (index, entry) = match (index, fn_val.get_first_basic_block()) {
+            (Some(index), Some(entry)) => (*index, entry),
+            _ => continue,
+        };
+
+        match entry.get_first_instruction() {
+            Some(first) => builder.position_before(&first),
+            None => builder.position_at_end(entry),
+        }
+
+        let counters_ptr = builder
+            .new_build_load(
+                counters_type,
+                counters.as_pointer_value(),
+                "coverage_counters",
+            )
+            .into_pointer_value();
+        let counter = unsafe {
+            builder.new_build_in_bounds_gep(
+                u64_type,
+                counters_ptr,
+                &[env.ptr_int().const_int(index as u64, false)],
+                "coverage_counter",
+            )
+        };
+        let hits = builder
+            .new_build_load(u64_type, counter, "hits")
+            .into_int_value();
+        let hits = builder.build_int_add(hits, u64_type.const_int(1, false), "hits");
+
+        builder.build_store(counter, hits);
+    }
+}
+
 fn unreachable_function(env: &Env, name: &str) {
     // The type of this function (but not the implementation) should have
     // already been defined by the builtins, which rely on it.
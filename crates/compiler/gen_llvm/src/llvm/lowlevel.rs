@@ -520,7 +520,7 @@ pub(crate) fn run_low_level<'a, 'ctx>(
                     env.builder
                         .new_build_load(return_type, cast_result, "load_result")
                 }
-                Unix => {
+                Unix | Freestanding => {
                     let result = call_str_bitcode_fn(
                         env,
                         &[string],
@@ -1848,7 +1848,7 @@ fn dec_to_str<'ctx>(env: &Env<'_, 'ctx, '_>, dec: BasicValueEnum<'ctx>) -> Basic
                 bitcode::DEC_TO_STR,
             )
         }
-        Unix => {
+        Unix | Freestanding => {
             let (low, high) = dec_split_into_words(env, dec);
 
             call_str_bitcode_fn(
@@ -1873,7 +1873,7 @@ fn dec_to_i128<'ctx>(env: &Env<'_, 'ctx, '_>, dec: BasicValueEnum<'ctx>) -> Basi
             //
             call_bitcode_fn(env, &[dec_alloca(env, dec).into()], bitcode::DEC_TO_I128)
         }
-        Unix => {
+        Unix | Freestanding => {
             let (low, high) = dec_split_into_words(env, dec);
 
             call_bitcode_fn(env, &[low.into(), high.into()], bitcode::DEC_TO_I128)
@@ -1908,7 +1908,7 @@ fn dec_binop_with_overflow<'ctx>(
                 fn_name,
             );
         }
-        Unix => {
+        Unix | Freestanding => {
             let (lhs_low, lhs_high) = dec_split_into_words(env, lhs);
             let (rhs_low, rhs_high) = dec_split_into_words(env, rhs);
 
@@ -1952,7 +1952,7 @@ pub(crate) fn dec_binop_with_unchecked<'ctx>(
                 fn_name,
             )
         }
-        Unix => {
+        Unix | Freestanding => {
             let (lhs_low, lhs_high) = dec_split_into_words(env, lhs);
             let (rhs_low, rhs_high) = dec_split_into_words(env, rhs);
 
@@ -187,3 +187,31 @@ macro_rules! run_jit_function_dynamic_type {
         }
     }};
 }
+
+/// Like [run_jit_function_dynamic_type], but returns the crash message and tag instead of
+/// panicking when the Roc code crashes.
+#[macro_export]
+macro_rules! try_run_jit_function_dynamic_type {
+    ($lib: expr, $main_fn_name: expr, $bytes:expr, $transform:expr) => {{
+        use roc_gen_llvm::run_roc::RocCallResult;
+
+        unsafe {
+            let main: libloading::Symbol<unsafe extern "C" fn(*const u8)> = $lib
+                .get($main_fn_name.as_bytes())
+                .ok()
+                .ok_or(format!("Unable to JIT compile `{}`", $main_fn_name))
+                .expect("errored");
+
+            let header_size = std::mem::size_of::<RocCallResult<()>>();
+            let size = header_size + $bytes;
+            let layout = std::alloc::Layout::array::<u8>(size).unwrap();
+            let result = std::alloc::alloc(layout);
+            main(result);
+
+            let header: Result<(), (String, _)> =
+                std::ptr::read(result as *const RocCallResult<()>).into();
+
+            header.map(|()| $transform(result.add(header_size) as usize))
+        }
+    }};
+}
@@ -1191,7 +1191,7 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
             self.code_builder.i32_store(Align::Bytes4, offset + 8);
         } else {
             let bytes = string.as_bytes();
-            let elements_addr = self.store_bytes_in_data_section(bytes);
+            let elements_addr = self.store_bytes_in_data_section(bytes, 1);
 
             // ptr
             self.code_builder.get_local(local_id);
@@ -1210,13 +1210,14 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
         };
     }
 
-    /// Create a string constant in the module data section
+    /// Create a string or list constant in the module data section
     /// Return the data we need for code gen: linker symbol index and memory address
-    fn store_bytes_in_data_section(&mut self, bytes: &[u8]) -> u32 {
-        // Place the segment at a 4-byte aligned offset
-        let segment_addr = round_up_to_alignment!(self.module.data.end_addr, PTR_SIZE);
-        let elements_addr = segment_addr + PTR_SIZE;
-        let length_with_refcount = 4 + bytes.len();
+    fn store_bytes_in_data_section(&mut self, bytes: &[u8], alignment: u32) -> u32 {
+        // The elements must be aligned, and the refcount goes right before them
+        let alignment = alignment.max(PTR_SIZE);
+        let segment_addr = round_up_to_alignment!(self.module.data.end_addr, alignment);
+        let elements_addr = segment_addr + alignment;
+        let length_with_refcount = alignment as usize + bytes.len();
         self.module.data.end_addr = segment_addr + length_with_refcount as u32;
 
         let mut segment = DataSegment {
@@ -1224,7 +1225,8 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
             init: Vec::with_capacity_in(length_with_refcount, self.env.arena),
         };
 
-        // Prefix the string bytes with "infinite" refcount
+        // Prefix the bytes with "infinite" refcount
+        segment.init.resize((alignment - PTR_SIZE) as usize, 0);
         let refcount_max_bytes: [u8; 4] = (REFCOUNT_MAX as i32).to_le_bytes();
         segment.init.extend_from_slice(&refcount_max_bytes);
         segment.init.extend_from_slice(bytes);
@@ -1535,11 +1537,21 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
     ) {
         if let StoredValue::StackMemory { location, .. } = storage {
             let size = self.layout_interner.stack_size(elem_layout) * (elems.len() as u32);
-
-            // Allocate heap space and store its address in a local variable
             let heap_local_id = self.storage.create_anonymous_local(PTR_TYPE);
             let heap_alignment = self.layout_interner.alignment_bytes(elem_layout);
-            self.allocate_with_refcount(Some(size), heap_alignment, 1);
+            let constant_bytes = self.constant_list_bytes(elem_layout, elems);
+
+            match &constant_bytes {
+                // All elements are known at compile time, so the list can live in the data section
+                Some(bytes) => {
+                    let elements_addr = self.store_bytes_in_data_section(bytes, heap_alignment);
+                    self.code_builder.i32_const(elements_addr as i32);
+                }
+                // Allocate heap space
+                None => self.allocate_with_refcount(Some(size), heap_alignment, 1),
+            }
+
+            // Store the elements' address in a local variable
             self.code_builder.set_local(heap_local_id);
 
             let (stack_local_id, stack_offset) =
@@ -1562,6 +1574,10 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
             self.code_builder
                 .i32_store(Align::Bytes4, stack_offset + 4 * Builtin::WRAPPER_CAPACITY);
 
+            if constant_bytes.is_some() {
+                return;
+            }
+
             let mut elem_offset = 0;
 
             for (i, elem) in elems.iter().enumerate() {
@@ -1598,6 +1614,35 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
         }
     }
 
+    /// The bytes of a list literal's elements, if they are all number literals
+    fn constant_list_bytes(
+        &self,
+        elem_layout: InLayout<'a>,
+        elems: &[ListLiteralElement<'a>],
+    ) -> Option<Vec<'a, u8>> {
+        let elem_size = self.layout_interner.stack_size(elem_layout) as usize;
+        let mut bytes = Vec::with_capacity_in(elem_size * elems.len(), self.env.arena);
+
+        for elem in elems {
+            match elem {
+                ListLiteralElement::Literal(
+                    Literal::Int(x) | Literal::U128(x) | Literal::Decimal(x),
+                ) => bytes.extend_from_slice(&x[..elem_size]),
+                ListLiteralElement::Literal(Literal::Float(x)) => match elem_size {
+                    4 => bytes.extend_from_slice(&(*x as f32).to_le_bytes()),
+                    _ => bytes.extend_from_slice(&x.to_le_bytes()),
+                },
+                ListLiteralElement::Literal(Literal::Bool(x)) => bytes.push(*x as u8),
+                ListLiteralElement::Literal(Literal::Byte(x)) => bytes.push(*x),
+                ListLiteralElement::Literal(Literal::Str(_)) | ListLiteralElement::Symbol(_) => {
+                    return None
+                }
+            }
+        }
+
+        Some(bytes)
+    }
+
     fn expr_empty_array(&mut self, sym: Symbol, storage: &StoredValue) {
         if let StoredValue::StackMemory { location, .. } = storage {
             let (local_id, offset) = location.local_and_offset(self.storage.stack_frame_pointer);
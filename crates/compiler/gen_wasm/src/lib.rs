@@ -64,18 +64,24 @@ pub fn parse_host<'a>(arena: &'a Bump, host_bytes: &[u8]) -> Result<WasmModule<'
 ///   interns        names of functions and variables (as memory-efficient interned strings)
 ///   host_module    parsed module from a Wasm object file containing all of the non-Roc code
 ///   procedures     Roc code in monomorphized intermediate representation
+///   optimize       shrink the function bodies after dead code elimination (`--optimize`)
 pub fn build_app_binary<'a, 'r>(
     env: &'r Env<'a>,
     layout_interner: &'r mut STLayoutInterner<'a>,
     interns: &'r mut Interns,
     host_module: WasmModule<'a>,
     procedures: MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    optimize: bool,
 ) -> std::vec::Vec<u8> {
     let (mut wasm_module, called_fns, _) =
         build_app_module(env, layout_interner, interns, host_module, procedures);
 
     wasm_module.eliminate_dead_code(env.arena, called_fns);
 
+    if optimize {
+        wasm_module.optimize_code(env.arena);
+    }
+
     let mut buffer = std::vec::Vec::with_capacity(wasm_module.size());
     wasm_module.serialize(&mut buffer);
     buffer
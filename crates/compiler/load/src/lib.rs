@@ -18,9 +18,11 @@ const SKIP_SUBS_CACHE: bool = {
 
 pub use roc_load_internal::docs;
 pub use roc_load_internal::file::{
-    EntryPoint, ExecutionMode, ExpectMetadata, Expectations, ExposedToHost, LoadConfig, LoadResult,
-    LoadStart, LoadedModule, LoadingProblem, MonomorphizedModule, Phase, Threading,
+    EntryPoint, ExecutionMode, ExpectMetadata, Expectations, ExposedToHost, ImportGraph,
+    LoadConfig, LoadResult, LoadStart, LoadedModule, LoadingProblem, MonomorphizedModule, Phase,
+    Threading,
 };
+pub use roc_load_internal::{api_diff, solved_types};
 
 #[allow(clippy::too_many_arguments)]
 fn load<'a>(
@@ -65,6 +67,8 @@ pub fn load_single_threaded<'a>(
         render,
         palette,
         exec_mode,
+        None,
+        false,
         roc_cache_dir,
     )
 }
@@ -126,6 +130,7 @@ pub fn load_and_monomorphize<'a>(
         load_config.render,
         roc_cache_dir,
         load_config.palette,
+        load_config.platform.as_deref(),
     )?;
 
     let exposed_types = ExposedByModule::default();
@@ -150,6 +155,7 @@ pub fn load_and_typecheck<'a>(
         load_config.render,
         roc_cache_dir,
         load_config.palette,
+        load_config.platform.as_deref(),
     )?;
 
     let exposed_types = ExposedByModule::default();
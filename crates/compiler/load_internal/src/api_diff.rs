@@ -0,0 +1,313 @@
+//! Compares the exposed values of two versions of a package, for `roc api-diff`.
+//!
+//! Both versions are described with [`solved_types::modules_json`](crate::solved_types), and a
+//! value whose type changed is compatible when the new type is more general than the old one,
+//! like `a -> a` instead of `Str -> Str`, or an open record `{ name : Str }*` instead of a closed
+//! one. Roc has no subtyping, so any other change, like a function taking an extra tag, can
+//! break code that uses the value.
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeKind {
+    Removed,
+    Breaking,
+    Compatible,
+    Added,
+}
+
+impl ChangeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ChangeKind::Removed => "removed",
+            ChangeKind::Breaking => "breaking",
+            ChangeKind::Compatible => "compatible",
+            ChangeKind::Added => "added",
+        }
+    }
+}
+
+/// A change to one exposed value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub module: String,
+    pub name: String,
+    pub kind: ChangeKind,
+    /// The type in the old version, unless the value was added.
+    pub old_type: Option<String>,
+    /// The type in the new version, unless the value was removed.
+    pub new_type: Option<String>,
+}
+
+/// Which part of a `major.minor.patch` version the changes call for increasing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VersionBump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl VersionBump {
+    pub fn of(changes: &[Change]) -> Self {
+        let bump = |change: &Change| match change.kind {
+            ChangeKind::Removed | ChangeKind::Breaking => VersionBump::Major,
+            ChangeKind::Compatible | ChangeKind::Added => VersionBump::Minor,
+        };
+
+        changes.iter().map(bump).max().unwrap_or(VersionBump::Patch)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            VersionBump::Patch => "patch",
+            VersionBump::Minor => "minor",
+            VersionBump::Major => "major",
+        }
+    }
+}
+
+/// The changes to exposed values between the `old` and `new` module descriptions, sorted by
+/// module and name. Values whose type stayed the same are left out.
+pub fn diff(old: &[Value], new: &[Value]) -> Vec<Change> {
+    let old = exposed_defs(old);
+    let new = exposed_defs(new);
+    let mut changes = Vec::new();
+
+    for ((module, name), old_def) in old.iter() {
+        let change = |kind, new_type: Option<&Value>| Change {
+            module: module.to_string(),
+            name: name.to_string(),
+            kind,
+            old_type: old_def["type"].as_str().map(String::from),
+            new_type: new_type.and_then(|t| t.as_str()).map(String::from),
+        };
+
+        match new.get(&(*module, *name)) {
+            None => changes.push(change(ChangeKind::Removed, None)),
+            Some(new_def) if new_def["type"] == old_def["type"] => {}
+            Some(new_def) => {
+                let kind = if can_replace(new_def, old_def) {
+                    ChangeKind::Compatible
+                } else {
+                    ChangeKind::Breaking
+                };
+
+                changes.push(change(kind, Some(&new_def["type"])));
+            }
+        }
+    }
+
+    for ((module, name), new_def) in new.iter() {
+        if !old.contains_key(&(*module, *name)) {
+            changes.push(Change {
+                module: module.to_string(),
+                name: name.to_string(),
+                kind: ChangeKind::Added,
+                old_type: None,
+                new_type: new_def["type"].as_str().map(String::from),
+            });
+        }
+    }
+
+    changes.sort_by(|a, b| (&a.module, &a.name).cmp(&(&b.module, &b.name)));
+
+    changes
+}
+
+/// The changes, and the version bump they call for, as JSON.
+pub fn to_json(changes: &[Change]) -> Value {
+    let bump = VersionBump::of(changes);
+    let changes: Vec<Value> = changes
+        .iter()
+        .map(|change| {
+            json!({
+                "module": change.module,
+                "name": change.name,
+                "change": change.kind.as_str(),
+                "old_type": change.old_type,
+                "new_type": change.new_type,
+            })
+        })
+        .collect();
+
+    json!({
+        "bump": bump.as_str(),
+        "changes": changes,
+    })
+}
+
+fn exposed_defs(modules: &[Value]) -> BTreeMap<(&str, &str), &Value> {
+    let mut defs = BTreeMap::new();
+
+    for module in modules {
+        let module_name = module["name"].as_str().unwrap_or_default();
+
+        for def in module["defs"].as_array().into_iter().flatten() {
+            if def["exposed"] == Value::Bool(true) {
+                let name = def["name"].as_str().unwrap_or_default();
+
+                defs.insert((module_name, name), def);
+            }
+        }
+    }
+
+    defs
+}
+
+/// Whether code that used the `old` def still type-checks when given the `new` one, which is the
+/// case when the new type is at least as general as the old one.
+fn can_replace(new: &Value, old: &Value) -> bool {
+    let mut matcher = Matcher {
+        new_constraints: elements(&new["constraints"]),
+        old_constraints: elements(&old["constraints"]),
+        bindings: BTreeMap::new(),
+    };
+
+    matcher.generalizes(&new["structure"], &old["structure"])
+}
+
+/// Matches a new type against an old one, binding each type variable of the new type to the part
+/// of the old type it stands for.
+struct Matcher<'a> {
+    new_constraints: &'a [Value],
+    old_constraints: &'a [Value],
+    bindings: BTreeMap<String, Value>,
+}
+
+impl<'a> Matcher<'a> {
+    /// Whether `old` is `new` with some of the type variables of `new` filled in.
+    fn generalizes(&mut self, new: &Value, old: &Value) -> bool {
+        match (kind(new), kind(old)) {
+            ("var", _) => self.bind(new, old),
+            // Structural aliases are just names for their actual type, which may have changed.
+            ("alias", _) => self.generalizes(&new["actual"], old),
+            (_, "alias") => self.generalizes(new, &old["actual"]),
+            ("function", "function") => {
+                self.all_generalize(&new["args"], &old["args"])
+                    && self.generalizes(&new["ret"], &old["ret"])
+            }
+            ("record", "record") => self.generalizes_row(new, old, "fields"),
+            ("tag_union", "tag_union") => {
+                new["recursion_var"] == old["recursion_var"]
+                    && self.generalizes_row(new, old, "tags")
+            }
+            ("tuple", "tuple") => {
+                self.all_generalize(&new["elems"], &old["elems"])
+                    && self.generalizes_extension(&new["extension"], &old["extension"])
+            }
+            ("apply", "apply") | ("opaque", "opaque") => {
+                new["name"] == old["name"] && self.all_generalize(&new["args"], &old["args"])
+            }
+            _ => new == old,
+        }
+    }
+
+    fn all_generalize(&mut self, new: &Value, old: &Value) -> bool {
+        let new = elements(new);
+        let old = elements(old);
+
+        new.len() == old.len() && new.iter().zip(old).all(|(n, o)| self.generalizes(n, o))
+    }
+
+    /// Matches the fields of records, or the tags of tag unions. An open new row may have fewer
+    /// entries than the old one, and then its extension stands for the rest.
+    fn generalizes_row(&mut self, new: &Value, old: &Value, entries: &str) -> bool {
+        let old_entries = elements(&old[entries]);
+        let mut rest: Vec<Value> = Vec::new();
+
+        for old_entry in old_entries {
+            let new_entry = elements(&new[entries])
+                .iter()
+                .find(|e| e["name"] == old_entry["name"]);
+
+            match new_entry {
+                Some(new_entry) => {
+                    let matches = new_entry["requirement"] == old_entry["requirement"]
+                        && match entries {
+                            "fields" => self.generalizes(&new_entry["type"], &old_entry["type"]),
+                            _ => self.all_generalize(&new_entry["args"], &old_entry["args"]),
+                        };
+
+                    if !matches {
+                        return false;
+                    }
+                }
+                None => rest.push(old_entry.clone()),
+            }
+        }
+
+        if elements(&new[entries]).len() + rest.len() != old_entries.len() {
+            // The new type has an entry the old one doesn't.
+            return false;
+        }
+
+        if rest.is_empty() {
+            self.generalizes_extension(&new["extension"], &old["extension"])
+        } else {
+            let mut old_rest = serde_json::Map::new();
+            old_rest.insert("kind".into(), old["kind"].clone());
+            old_rest.insert(entries.into(), Value::Array(rest));
+            old_rest.insert("extension".into(), old["extension"].clone());
+
+            kind(&new["extension"]) == "var"
+                && self.bind(&new["extension"], &Value::Object(old_rest))
+        }
+    }
+
+    /// Extensions are `null` when closed.
+    fn generalizes_extension(&mut self, new: &Value, old: &Value) -> bool {
+        match (new, old) {
+            (Value::Null, Value::Null) => true,
+            (Value::Null, _) => false,
+            (_, Value::Null) => kind(new) == "var" && self.bind(new, old),
+            _ => self.generalizes(new, old),
+        }
+    }
+
+    fn bind(&mut self, var: &Value, old: &Value) -> bool {
+        let name = var["name"].as_str().unwrap_or_default();
+
+        // Wildcards only occur once, so there is nothing to stay consistent with.
+        if name == "*" {
+            return true;
+        }
+
+        if let Some(bound) = self.bindings.get(name) {
+            return bound == old;
+        }
+
+        // Abilities the variable has to implement now must already have been required of what it
+        // stands for. We don't know which abilities concrete types implement, so those can't
+        // gain any.
+        let abilities = constraint(self.new_constraints, var);
+        let old_abilities = match kind(old) {
+            "var" => constraint(self.old_constraints, old),
+            _ => &[],
+        };
+
+        if !abilities.iter().all(|a| old_abilities.contains(a)) {
+            return false;
+        }
+
+        self.bindings.insert(name.to_string(), old.clone());
+
+        true
+    }
+}
+
+/// The abilities the type variable `var` has to implement.
+fn constraint<'a>(constraints: &'a [Value], var: &Value) -> &'a [Value] {
+    constraints
+        .iter()
+        .find(|c| c["var"] == var["name"])
+        .map(|c| elements(&c["abilities"]))
+        .unwrap_or_default()
+}
+
+fn kind(value: &Value) -> &str {
+    value["kind"].as_str().unwrap_or_default()
+}
+
+fn elements(value: &Value) -> &[Value] {
+    value.as_array().map(Vec::as_slice).unwrap_or_default()
+}
@@ -1,6 +1,7 @@
 #![allow(clippy::too_many_arguments)]
 
 use crate::docs::ModuleDocumentation;
+use crate::solved_types::{self, ModuleTypes};
 use bumpalo::{collections::CollectIn, Bump};
 use crossbeam::channel::{bounded, Sender};
 use crossbeam::deque::{Injector, Stealer, Worker};
@@ -9,7 +10,7 @@ use parking_lot::Mutex;
 use roc_builtins::roc::module_source;
 use roc_can::abilities::{AbilitiesStore, PendingAbilitiesStore, ResolvedImpl};
 use roc_can::constraint::{Constraint as ConstraintSoa, Constraints, TypeOrVar};
-use roc_can::expr::{DbgLookup, Declarations, ExpectLookup, PendingDerives};
+use roc_can::expr::{DbgLookup, Declarations, ExpectLookups, PendingDerives};
 use roc_can::module::{
     canonicalize_module_defs, ExposedByModule, ExposedForModule, ExposedModuleTypes, Module,
     ResolvedImplementations, TypeState,
@@ -39,14 +40,15 @@ use roc_mono::layout::{
     GlobalLayoutInterner, LambdaName, Layout, LayoutCache, LayoutProblem, Niche, STLayoutInterner,
 };
 use roc_mono::reset_reuse;
-use roc_mono::{drop_specialization, inc_dec};
+use roc_mono::{bounds_check, const_eval, drop_specialization, inc_dec};
 use roc_packaging::cache::RocCacheDir;
 use roc_parse::ast::{
     self, CommentOrNewline, Defs, Expr, ExtractSpaces, Pattern, Spaced, StrLiteral, TypeAnnotation,
     ValueDef,
 };
 use roc_parse::header::{
-    ExposedName, ImportsEntry, PackageEntry, PackageHeader, PlatformHeader, To, TypedIdent,
+    ExposedName, ImportsEntry, PackageEntry, PackageHeader, PlatformHeader, PlatformThreading, To,
+    TypedIdent,
 };
 use roc_parse::header::{HeaderType, PackageName};
 use roc_parse::module::module_defs;
@@ -94,19 +96,31 @@ const ROC_FILE_EXTENSION: &str = "roc";
 /// The . in between module names like Foo.Bar.Baz
 const MODULE_SEPARATOR: char = '.';
 
+/// In [ExecutionMode::Test], the modules in this directory (next to the root module) whose
+/// names end in [TEST_MODULE_SUFFIX] are loaded too, even though nothing imports them.
+const TESTS_DIR: &str = "Tests";
+const TEST_MODULE_SUFFIX: &str = "Test";
+
 const EXPANDED_STACK_SIZE: usize = 8 * 1024 * 1024;
 
 macro_rules! log {
     ($($arg:tt)*) => (dbg_do!(ROC_PRINT_LOAD_LOG, println!($($arg)*)))
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LoadConfig {
     pub target_info: TargetInfo,
     pub render: RenderTarget,
     pub palette: Palette,
     pub threading: Threading,
     pub exec_mode: ExecutionMode,
+    /// A platform (a URL or a path) to use instead of the one named in the app header.
+    pub platform: Option<String>,
+    /// Stop solving a module once it has this many type errors, not counting follow-on errors.
+    pub max_type_errors: Option<usize>,
+    /// Keep the solved types of every module in the root package, not just the root module,
+    /// e.g. for `roc check --emit-types`.
+    pub keep_module_types: bool,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -418,6 +432,18 @@ fn start_phase<'a>(
 
                 let derived_module = SharedDerivedModule::clone(&state.derived_module);
 
+                // Only worth doing if this module will be specialized.
+                let drop_unused_captures = (state.goal_phase() > Phase::SolveTypes
+                    || state.exec_mode.build_if_checks())
+                    && !module_id.is_builtin();
+
+                let compile_expects = drop_unused_captures && {
+                    let modules = state.arc_modules.lock();
+                    modules
+                        .package_eq(module_id, state.root_id)
+                        .expect("root or this module is not yet known - that's a bug!")
+                };
+
                 BuildTask::solve_module(
                     module,
                     ident_ids,
@@ -433,6 +459,9 @@ fn start_phase<'a>(
                     declarations,
                     state.cached_types.clone(),
                     derived_module,
+                    state.max_type_errors,
+                    drop_unused_captures,
+                    compile_expects,
                 )
             }
             Phase::FindSpecializations => {
@@ -642,6 +671,22 @@ pub struct LoadedModule {
     pub timings: MutMap<ModuleId, ModuleTiming>,
     pub docs_by_module: VecMap<ModuleId, ModuleDocumentation>,
     pub abilities_store: AbilitiesStore,
+    pub import_graph: ImportGraph,
+    /// Whether the platform's host may call into the program from several threads at once.
+    pub platform_threading: PlatformThreading,
+    /// The solved types of the root package's other modules, if
+    /// [`LoadConfig::keep_module_types`] was set.
+    pub module_types: MutMap<ModuleId, ModuleTypes>,
+}
+
+/// Which modules import which, e.g. for `roc graph`.
+#[derive(Debug, Default, Clone)]
+pub struct ImportGraph {
+    /// The modules each module imports, including the builtins every module imports implicitly.
+    pub imports: MutMap<ModuleId, MutSet<ModuleId>>,
+    /// The shorthand of the package each module came from, like `pf`. Modules in the root
+    /// module's own package, and builtins, have no entry.
+    pub packages: MutMap<ModuleId, String>,
 }
 
 impl LoadedModule {
@@ -769,10 +814,23 @@ pub struct MonomorphizedModule<'a> {
     pub entry_point: EntryPoint<'a>,
     pub exposed_to_host: ExposedToHost,
     pub sources: MutMap<ModuleId, (PathBuf, Box<str>)>,
+    /// The modules each module imports, e.g. to find the modules that depend on a changed one.
+    pub imports: MutMap<ModuleId, MutSet<ModuleId>>,
     pub timings: MutMap<ModuleId, ModuleTiming>,
     pub expectations: VecMap<ModuleId, Expectations>,
     pub uses_prebuilt_platform: bool,
+    /// Whether the platform's host may call into the program from several threads at once.
+    pub platform_threading: PlatformThreading,
     pub glue_layouts: GlueLayouts<'a>,
+    /// How many of the loaded modules had their types restored from the builtins cache,
+    /// rather than being constrained and solved from scratch.
+    pub cached_module_count: usize,
+    /// The values that each module of the root package exposes, e.g. to find the ones
+    /// that no expect uses.
+    pub root_package_exposes: MutMap<ModuleId, VecSet<Symbol>>,
+    /// Where the calls of all modules with expectations are, by the symbol that each call's
+    /// result is bound to.
+    pub expect_call_sites: MutMap<Symbol, Region>,
 }
 
 /// Values used to render expect output
@@ -795,8 +853,12 @@ pub enum EntryPoint<'a> {
 pub struct Expectations {
     pub subs: roc_types::subs::Subs,
     pub path: PathBuf,
-    pub expectations: VecMap<Region, Vec<ExpectLookup>>,
+    pub expectations: VecMap<Region, ExpectLookups>,
     pub dbgs: VecMap<Symbol, DbgLookup>,
+    /// The symbols each closure in the module captures, see [roc_can::expr::ExpectCollector].
+    pub closure_captures: VecMap<Symbol, Vec<Symbol>>,
+    /// Where the calls in the module are, see [roc_mono::ir::Env::expect_call_sites].
+    pub call_sites: MutMap<Symbol, Region>,
     pub ident_ids: IdentIds,
 }
 
@@ -843,8 +905,9 @@ struct ParsedModule<'a> {
     header_comments: &'a [CommentOrNewline<'a>],
 }
 
-type LocExpects = VecMap<Region, Vec<ExpectLookup>>;
+type LocExpects = VecMap<Region, ExpectLookups>;
 type LocDbgs = VecMap<Symbol, DbgLookup>;
+type ClosureCaptures = VecMap<Symbol, Vec<Symbol>>;
 
 /// A message sent out _from_ a worker thread,
 /// representing a result of work done, or a request for further work
@@ -865,6 +928,7 @@ enum Msg<'a> {
         abilities_store: AbilitiesStore,
         loc_expects: LocExpects,
         loc_dbgs: LocDbgs,
+        closure_captures: ClosureCaptures,
     },
     FinishedAllTypeChecking {
         solved_subs: Solved<Subs>,
@@ -979,7 +1043,11 @@ struct State<'a> {
     pub exposed_types: ExposedByModule,
     pub output_path: Option<&'a str>,
     pub platform_path: PlatformPath<'a>,
+    /// Declared in the platform's header; see [PlatformThreading].
+    pub platform_threading: PlatformThreading,
     pub target_info: TargetInfo,
+    /// Modules from the [TESTS_DIR], which are loaded as roots of their own.
+    pub test_modules: Vec<ModuleId>,
 
     /// Note: only packages and platforms actually expose any modules;
     /// for all others, this will be empty.
@@ -1018,6 +1086,9 @@ struct State<'a> {
     pub render: RenderTarget,
     pub palette: Palette,
     pub exec_mode: ExecutionMode,
+    pub max_type_errors: Option<usize>,
+    pub keep_module_types: bool,
+    pub module_types: MutMap<ModuleId, ModuleTypes>,
 
     /// All abilities across all modules.
     pub world_abilities: WorldAbilities,
@@ -1049,6 +1120,8 @@ impl<'a> State<'a> {
         palette: Palette,
         number_of_workers: usize,
         exec_mode: ExecutionMode,
+        max_type_errors: Option<usize>,
+        keep_module_types: bool,
     ) -> Self {
         let arc_shorthands = Arc::new(Mutex::new(MutMap::default()));
         let cache_dir = roc_packaging::cache::roc_cache_dir();
@@ -1063,6 +1136,8 @@ impl<'a> State<'a> {
             platform_data: None,
             output_path: None,
             platform_path: PlatformPath::NotSpecified,
+            platform_threading: PlatformThreading::MultiThreaded,
+            test_modules: Vec::new(),
             module_cache: ModuleCache::default(),
             dependencies,
             procedures: MutMap::default(),
@@ -1083,11 +1158,68 @@ impl<'a> State<'a> {
             render,
             palette,
             exec_mode,
+            max_type_errors,
+            keep_module_types,
+            module_types: MutMap::default(),
             make_specializations_pass: MakeSpecializationsPass::Pass(1),
             world_abilities: Default::default(),
             layout_interner: GlobalLayoutInterner::with_capacity(128, target_info),
         }
     }
+
+    /// When running tests, also load the modules in the [TESTS_DIR], so that tests don't need
+    /// to live in the modules they test.
+    fn add_test_modules(&mut self, src_dir: &Path) {
+        if !matches!(self.exec_mode, ExecutionMode::Test) {
+            return;
+        }
+
+        let mut module_ids = self.arc_modules.lock();
+
+        for module_name in find_test_modules(src_dir) {
+            let module_name = PQModuleName::Unqualified(module_name);
+            let module_id = module_ids.get_or_insert(&module_name);
+
+            // e.g. `roc test Tests/FooTest.roc`
+            if module_id != self.root_id {
+                self.module_cache
+                    .module_names
+                    .insert(module_id, module_name);
+                self.test_modules.push(module_id);
+            }
+        }
+    }
+}
+
+/// The names of the test modules in `src_dir`'s [TESTS_DIR], e.g. `Tests.ParserTest` for
+/// `Tests/ParserTest.roc`, in a stable order.
+fn find_test_modules(src_dir: &Path) -> Vec<ModuleName> {
+    let entries = match std::fs::read_dir(src_dir.join(TESTS_DIR)) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+
+            if path.extension()? != ROC_FILE_EXTENSION {
+                return None;
+            }
+
+            let stem = path.file_stem()?.to_str()?;
+
+            if stem.ends_with(TEST_MODULE_SUFFIX) && stem.len() > TEST_MODULE_SUFFIX.len() {
+                Some(format!("{}{}{}", TESTS_DIR, MODULE_SEPARATOR, stem))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    names.sort();
+
+    names.into_iter().map(ModuleName::from).collect()
 }
 
 #[derive(Debug)]
@@ -1242,6 +1374,9 @@ enum BuildTask<'a> {
         dep_idents: IdentIdsByModule,
         cached_subs: CachedTypeState,
         derived_module: SharedDerivedModule,
+        max_type_errors: Option<usize>,
+        drop_unused_captures: bool,
+        compile_expects: bool,
     },
     BuildPendingSpecializations {
         module_timing: ModuleTiming,
@@ -1359,6 +1494,9 @@ pub fn load_and_typecheck_str<'a>(
         palette,
         threading,
         exec_mode: ExecutionMode::Check,
+        platform: None,
+        max_type_errors: None,
+        keep_module_types: false,
     };
 
     match load(
@@ -1396,11 +1534,13 @@ impl<'a> LoadStart<'a> {
         render: RenderTarget,
         roc_cache_dir: RocCacheDir<'_>,
         palette: Palette,
+        opt_platform: Option<&str>,
     ) -> Result<Self, LoadingProblem<'a>> {
         let arc_modules = Arc::new(Mutex::new(PackageModuleIds::default()));
         let root_exposed_ident_ids = IdentIds::exposed_builtins(0);
         let ident_ids_by_module = Arc::new(Mutex::new(root_exposed_ident_ids));
         let mut src_dir = filename.parent().unwrap().to_path_buf();
+        let opt_platform = opt_platform.map(|platform| &*arena.alloc_str(platform));
 
         // Load the root module synchronously; we can't proceed until we have its id.
         let header_output = {
@@ -1416,6 +1556,7 @@ impl<'a> LoadStart<'a> {
                 Arc::clone(&ident_ids_by_module),
                 roc_cache_dir,
                 root_start_time,
+                opt_platform,
             );
 
             match res_loaded {
@@ -1618,6 +1759,8 @@ pub fn load<'a>(
             load_config.render,
             load_config.palette,
             load_config.exec_mode,
+            load_config.max_type_errors,
+            load_config.keep_module_types,
             roc_cache_dir,
         ),
         Threads::Many(threads) => load_multi_threaded(
@@ -1630,6 +1773,8 @@ pub fn load<'a>(
             load_config.palette,
             threads,
             load_config.exec_mode,
+            load_config.max_type_errors,
+            load_config.keep_module_types,
             roc_cache_dir,
         ),
     }
@@ -1645,6 +1790,8 @@ pub fn load_single_threaded<'a>(
     render: RenderTarget,
     palette: Palette,
     exec_mode: ExecutionMode,
+    max_type_errors: Option<usize>,
+    keep_module_types: bool,
     roc_cache_dir: RocCacheDir<'_>,
 ) -> Result<LoadResult<'a>, LoadingProblem<'a>> {
     let LoadStart {
@@ -1676,8 +1823,12 @@ pub fn load_single_threaded<'a>(
         palette,
         number_of_workers,
         exec_mode,
+        max_type_errors,
+        keep_module_types,
     );
 
+    state.add_test_modules(&src_dir);
+
     // We'll add tasks to this, and then worker threads will take tasks from it.
     let injector = Injector::new();
 
@@ -1960,6 +2111,8 @@ fn load_multi_threaded<'a>(
     palette: Palette,
     available_threads: usize,
     exec_mode: ExecutionMode,
+    max_type_errors: Option<usize>,
+    keep_module_types: bool,
     roc_cache_dir: RocCacheDir<'_>,
 ) -> Result<LoadResult<'a>, LoadingProblem<'a>> {
     let LoadStart {
@@ -2006,8 +2159,12 @@ fn load_multi_threaded<'a>(
         palette,
         num_workers,
         exec_mode,
+        max_type_errors,
+        keep_module_types,
     );
 
+    state.add_test_modules(&src_dir);
+
     // an arena for every worker, stored in an arena-allocated bumpalo vec to make the lifetimes work
     let arenas = std::iter::repeat_with(Bump::new).take(num_workers);
     let worker_arenas = arena.alloc(bumpalo::collections::Vec::from_iter_in(arenas, arena));
@@ -2428,6 +2585,16 @@ fn update<'a>(
 
             let mut work = MutSet::default();
 
+            // Nothing imports the test modules, so start loading them along with the root
+            if header.is_root_module {
+                work.extend(
+                    state
+                        .test_modules
+                        .iter()
+                        .map(|module_id| (*module_id, Phase::LoadHeader)),
+                );
+            }
+
             // Register the package's path under its shorthand
             // (e.g. for { pf: "blah" }, register that "pf" should resolve to "blah")
             {
@@ -2520,10 +2687,17 @@ fn update<'a>(
                         config_shorthand,
                         provides,
                         exposes_ids,
+                        threading,
                         ..
                     } => {
                         work.extend(state.dependencies.notify_package(config_shorthand));
 
+                        if header.is_root_module
+                            || state.opt_platform_shorthand == Some(config_shorthand)
+                        {
+                            state.platform_threading = threading;
+                        }
+
                         let is_prebuilt = if header.is_root_module {
                             debug_assert!(matches!(
                                 state.platform_path,
@@ -2743,6 +2917,7 @@ fn update<'a>(
             abilities_store,
             loc_expects,
             loc_dbgs,
+            closure_captures,
         } => {
             log!("solved types for {:?}", module_id);
             module_timing.end_time = Instant::now();
@@ -2765,6 +2940,8 @@ fn update<'a>(
                 Some(Expectations {
                     expectations: loc_expects,
                     dbgs: loc_dbgs,
+                    closure_captures,
+                    call_sites: MutMap::default(),
                     subs: solved_subs.clone().into_inner(),
                     path: path.to_owned(),
                     ident_ids: ident_ids.clone(),
@@ -2782,6 +2959,14 @@ fn update<'a>(
                 Some(ref platform_data) => module_id == platform_data.module_id,
             };
 
+            // Usually every other module is checked before the host-exposed one, because it
+            // depends on all of them; but nothing depends on the test modules.
+            let checked_all_modules = if state.test_modules.is_empty() {
+                is_host_exposed
+            } else {
+                state.dependencies.solved_all()
+            };
+
             let add_to_host_exposed = is_host_exposed &&
                 // During testing, we don't need to expose anything to the host.
                 !matches!(state.exec_mode, ExecutionMode::Test);
@@ -2806,7 +2991,7 @@ fn update<'a>(
                     .extend(solved_module.aliases.keys().copied());
             }
 
-            let finish_type_checking = is_host_exposed &&
+            let finish_type_checking = checked_all_modules &&
                 (state.goal_phase() == Phase::SolveTypes)
                 // If we're running in check-and-then-build mode, only exit now there are errors.
                 && (!state.exec_mode.build_if_checks() || state.module_cache.has_errors());
@@ -2892,11 +3077,30 @@ fn update<'a>(
                         .typechecked
                         .insert(module_id, typechecked);
                 } else {
+                    let keep_types = state.keep_module_types && !module_id.is_builtin() && {
+                        let modules = state.arc_modules.lock();
+                        modules
+                            .package_eq(module_id, state.root_id)
+                            .expect("root or this module is not yet known - that's a bug!")
+                    };
+
+                    if keep_types {
+                        let module_types = ModuleTypes {
+                            subs: solved_subs.into_inner(),
+                            defs: solved_types::top_level_defs(&decls, &abilities_store),
+                            exposed: (solved_module.exposed_vars_by_symbol.iter())
+                                .map(|(symbol, _)| *symbol)
+                                .collect(),
+                        };
+
+                        state.module_types.insert(module_id, module_types);
+                    }
+
                     state.constrained_ident_ids.insert(module_id, ident_ids);
                     state.timings.insert(module_id, module_timing);
                 }
 
-                let work = if is_host_exposed && state.exec_mode.build_if_checks() {
+                let work = if checked_all_modules && state.exec_mode.build_if_checks() {
                     debug_assert!(
                         work.is_empty(),
                         "work left over after host exposed is checked"
@@ -3104,6 +3308,16 @@ fn update<'a>(
 
                     let ident_ids = state.constrained_ident_ids.get_mut(&module_id).unwrap();
 
+                    const_eval::fold_constants(arena, &mut state.procedures);
+
+                    bounds_check::eliminate_bounds_checks(
+                        arena,
+                        &layout_interner,
+                        module_id,
+                        ident_ids,
+                        &mut state.procedures,
+                    );
+
                     inc_dec::insert_inc_dec_operations(
                         arena,
                         &layout_interner,
@@ -3301,6 +3515,20 @@ fn finish_specialization<'a>(
             roc_types::types::get_type_clone_count()
         );
     }
+    let root_package_exposes = {
+        let modules = state.arc_modules.lock();
+
+        state
+            .exposed_symbols_by_module
+            .iter()
+            .filter(|(module_id, _)| {
+                modules.package_eq(**module_id, state.root_id) == Some(true)
+                    && !module_id.is_builtin()
+            })
+            .map(|(module_id, exposed)| (*module_id, exposed.clone()))
+            .collect()
+    };
+
     let module_ids = Arc::try_unwrap(state.arc_modules)
         .unwrap_or_else(|_| panic!("There were still outstanding Arc references to module_ids"))
         .into_inner()
@@ -3394,6 +3622,7 @@ fn finish_specialization<'a>(
         module_cache,
         output_path,
         platform_data,
+        platform_threading,
         ..
     } = state;
 
@@ -3401,6 +3630,7 @@ fn finish_specialization<'a>(
         type_problems,
         can_problems,
         sources,
+        imports,
         ..
     } = module_cache;
 
@@ -3470,6 +3700,22 @@ fn finish_specialization<'a>(
         None => false,
     };
 
+    let expect_call_sites = module_expectations
+        .values()
+        .flat_map(|expectations| expectations.call_sites.iter())
+        .map(|(symbol, region)| (*symbol, *region))
+        .collect();
+
+    let cached_module_count = {
+        let cached_types = state.cached_types.lock();
+
+        state
+            .timings
+            .keys()
+            .filter(|module_id| cached_types.contains_key(module_id))
+            .count()
+    };
+
     Ok(MonomorphizedModule {
         can_problems,
         type_problems,
@@ -3483,12 +3729,17 @@ fn finish_specialization<'a>(
         procedures,
         entry_point,
         sources,
+        imports,
+        cached_module_count,
         timings: state.timings,
         toplevel_expects,
         glue_layouts: GlueLayouts {
             getters: glue_getters,
         },
         uses_prebuilt_platform,
+        platform_threading,
+        root_package_exposes,
+        expect_call_sites,
     })
 }
 
@@ -3522,10 +3773,22 @@ fn finish(
     documentation: VecMap<ModuleId, ModuleDocumentation>,
     abilities_store: AbilitiesStore,
 ) -> LoadedModule {
-    let module_ids = Arc::try_unwrap(state.arc_modules)
+    let package_module_ids = Arc::try_unwrap(state.arc_modules)
         .unwrap_or_else(|_| panic!("There were still outstanding Arc references to module_ids"))
-        .into_inner()
-        .into_module_ids();
+        .into_inner();
+
+    let imports = state.module_cache.imports;
+    let packages = (imports.keys())
+        .filter_map(|module_id| match package_module_ids.get_name(*module_id) {
+            Some(PackageQualified::Qualified(shorthand, _)) => {
+                Some((*module_id, shorthand.to_string()))
+            }
+            _ => None,
+        })
+        .collect();
+    let import_graph = ImportGraph { imports, packages };
+
+    let module_ids = package_module_ids.into_module_ids();
 
     // Associate the ident IDs from the derived synth module
     let (_, derived_synth_ident_ids) = Arc::try_unwrap(state.derived_module)
@@ -3569,6 +3832,9 @@ fn finish(
         timings: state.timings,
         docs_by_module: documentation,
         abilities_store,
+        import_graph,
+        platform_threading: state.platform_threading,
+        module_types: state.module_types,
     }
 }
 
@@ -3861,6 +4127,7 @@ fn load_module<'a>(
         ident_ids_by_module,
         roc_cache_dir,
         module_start_time,
+        None,
     )
 }
 
@@ -4026,6 +4293,7 @@ fn parse_header<'a>(
     src_bytes: &'a [u8],
     roc_cache_dir: RocCacheDir<'_>,
     start_time: Instant,
+    opt_platform: Option<&'a str>,
 ) -> Result<HeaderOutput<'a>, LoadingProblem<'a>> {
     let parse_start = Instant::now();
     let parse_state = roc_parse::state::State::new(src_bytes);
@@ -4148,6 +4416,13 @@ fn parse_header<'a>(
                 &[]
             };
 
+            let (packages, to_platform) = match opt_platform {
+                Some(platform) => {
+                    override_platform(arena, packages, header.provides.to.value, platform)
+                }
+                None => (packages, header.provides.to.value),
+            };
+
             let mut provides = bumpalo::collections::Vec::new_in(arena);
 
             provides.extend(unspace(arena, header.provides.entries.items));
@@ -4174,7 +4449,7 @@ fn parse_header<'a>(
                 header_type: HeaderType::App {
                     provides: provides.into_bump_slice(),
                     output_name: header.name.value,
-                    to_platform: header.provides.to.value,
+                    to_platform,
                 },
                 module_comments: comments,
             };
@@ -4205,7 +4480,7 @@ fn parse_header<'a>(
             );
 
             // Look at the app module's `to` keyword to determine which package was the platform.
-            match header.provides.to.value {
+            match to_platform {
                 To::ExistingPackage(shorthand) => {
                     if !packages
                         .iter()
@@ -4295,6 +4570,32 @@ fn parse_header<'a>(
     }
 }
 
+/// Points an app at `platform` instead of the platform its header names,
+/// e.g. for `roc run --platform ../test-platform/main.roc`.
+fn override_platform<'a>(
+    arena: &'a Bump,
+    packages: &'a [Loc<PackageEntry<'a>>],
+    to_platform: To<'a>,
+    platform: &'a str,
+) -> (&'a [Loc<PackageEntry<'a>>], To<'a>) {
+    match to_platform {
+        To::ExistingPackage(shorthand) => {
+            let packages = packages.iter().map(|loc_entry| {
+                let mut entry = loc_entry.value;
+
+                if entry.shorthand == shorthand {
+                    entry.package_name.value = PackageName::from(platform);
+                }
+
+                Loc::at(loc_entry.region, entry)
+            });
+
+            (arena.alloc_slice_fill_iter(packages), to_platform)
+        }
+        To::NewPackage(_) => (packages, To::NewPackage(PackageName::from(platform))),
+    }
+}
+
 fn load_packages<'a>(
     packages: &[Loc<PackageEntry<'a>>],
     load_messages: &mut Vec<Msg<'a>>,
@@ -4386,6 +4687,7 @@ fn load_filename<'a>(
     ident_ids_by_module: SharedIdentIdsByModule,
     roc_cache_dir: RocCacheDir<'_>,
     module_start_time: Instant,
+    opt_platform: Option<&'a str>,
 ) -> Result<HeaderOutput<'a>, LoadingProblem<'a>> {
     let file_io_start = Instant::now();
     let file = fs::read(&filename);
@@ -4404,6 +4706,7 @@ fn load_filename<'a>(
             arena.alloc(bytes),
             roc_cache_dir,
             module_start_time,
+            opt_platform,
         ),
         Err(err) => Err(LoadingProblem::FileProblem {
             filename,
@@ -4438,6 +4741,7 @@ fn load_from_str<'a>(
         src.as_bytes(),
         roc_cache_dir,
         module_start_time,
+        None,
     )
 }
 
@@ -4791,6 +5095,9 @@ impl<'a> BuildTask<'a> {
         declarations: Declarations,
         cached_subs: CachedTypeState,
         derived_module: SharedDerivedModule,
+        max_type_errors: Option<usize>,
+        drop_unused_captures: bool,
+        compile_expects: bool,
     ) -> Self {
         let exposed_by_module = exposed_types.retain_modules(imported_modules.keys());
 
@@ -4812,6 +5119,9 @@ impl<'a> BuildTask<'a> {
             module_timing,
             cached_subs,
             derived_module,
+            max_type_errors,
+            drop_unused_captures,
+            compile_expects,
         }
     }
 }
@@ -5079,6 +5389,7 @@ fn run_solve_solve(
     var_store: VarStore,
     module: Module,
     derived_module: SharedDerivedModule,
+    max_errors: Option<usize>,
 ) -> SolveResult {
     let Module {
         exposed_symbols,
@@ -5129,6 +5440,7 @@ fn run_solve_solve(
             pending_derives,
             exposed_by_module: &exposed_for_module.exposed_by_module,
             derived_module,
+            max_errors,
         };
 
         let solve_output = roc_solve::module::run_solve(
@@ -5192,10 +5504,13 @@ fn run_solve<'a>(
     constraint: ConstraintSoa,
     pending_derives: PendingDerives,
     var_store: VarStore,
-    decls: Declarations,
+    mut decls: Declarations,
     dep_idents: IdentIdsByModule,
     cached_types: CachedTypeState,
     derived_module: SharedDerivedModule,
+    max_type_errors: Option<usize>,
+    drop_unused_captures: bool,
+    compile_expects: bool,
 ) -> Msg<'a> {
     let solve_start = Instant::now();
 
@@ -5207,6 +5522,7 @@ fn run_solve<'a>(
     let mut module = module;
     let loc_expects = std::mem::take(&mut module.loc_expects);
     let loc_dbgs = std::mem::take(&mut module.loc_dbgs);
+    let closure_captures = std::mem::take(&mut module.closure_captures);
     let module = module;
 
     let solve_result = {
@@ -5221,6 +5537,7 @@ fn run_solve<'a>(
                     var_store,
                     module,
                     derived_module,
+                    None,
                 ),
                 Some(TypeState {
                     subs,
@@ -5245,6 +5562,7 @@ fn run_solve<'a>(
                 var_store,
                 module,
                 derived_module,
+                max_type_errors,
             )
         }
     };
@@ -5253,10 +5571,27 @@ fn run_solve<'a>(
         solved: mut solved_subs,
         solved_implementations,
         exposed_vars_by_symbol,
-        problems,
+        mut problems,
         abilities_store,
     } = solve_result;
 
+    if !module_id.is_builtin() {
+        problems.extend(roc_solve::literals::lossy_f32_literals(
+            &decls,
+            solved_subs.inner(),
+        ));
+    }
+
+    if drop_unused_captures {
+        // Must happen before the exposed types are copied out of the module's subs, so that
+        // other modules see the same captures for this module's closures.
+        roc_mono::unused_captures::drop_unused_captures(
+            solved_subs.inner_mut(),
+            &mut decls,
+            compile_expects,
+        );
+    }
+
     let exposed_types = roc_solve::module::exposed_types_storage_subs(
         module_id,
         &mut solved_subs,
@@ -5289,6 +5624,7 @@ fn run_solve<'a>(
         abilities_store,
         loc_expects,
         loc_dbgs,
+        closure_captures,
     }
 }
 
@@ -5396,6 +5732,10 @@ fn build_platform_header<'a>(
         exposes: exposes.into_bump_slice(),
         requires,
         requires_types,
+        threading: (header.threading.as_ref())
+            .map_or(PlatformThreading::MultiThreaded, |threading| {
+                threading.item.value
+            }),
     };
 
     let info = HeaderInfo {
@@ -5587,6 +5927,7 @@ fn canonicalize_and_constrain<'a>(
         abilities_store: module_output.scope.abilities_store,
         loc_expects: module_output.loc_expects,
         loc_dbgs: module_output.loc_dbgs,
+        closure_captures: module_output.closure_captures,
     };
 
     let constrained_module = ConstrainedModule {
@@ -5789,11 +6130,16 @@ fn make_specializations<'a>(
 ) -> Msg<'a> {
     let make_specializations_start = Instant::now();
     let mut update_mode_ids = UpdateModeIds::new();
+    let (expectation_subs, expect_call_sites) = match expectations.as_mut() {
+        Some(e) => (Some(&mut e.subs), Some(&mut e.call_sites)),
+        None => (None, None),
+    };
     // do the thing
     let mut mono_env = roc_mono::ir::Env {
         arena,
         subs: &mut subs,
-        expectation_subs: expectations.as_mut().map(|e| &mut e.subs),
+        expectation_subs,
+        expect_call_sites,
         home,
         ident_ids: &mut ident_ids,
         target_info,
@@ -5888,10 +6234,15 @@ fn build_pending_specializations<'a>(
 
     let mut update_mode_ids = UpdateModeIds::new();
     let mut subs = solved_subs.into_inner();
+    let (expectation_subs, expect_call_sites) = match expectations.as_mut() {
+        Some(e) => (Some(&mut e.subs), Some(&mut e.call_sites)),
+        None => (None, None),
+    };
     let mut mono_env = roc_mono::ir::Env {
         arena,
         subs: &mut subs,
-        expectation_subs: expectations.as_mut().map(|e| &mut e.subs),
+        expectation_subs,
+        expect_call_sites,
         home,
         ident_ids: &mut ident_ids,
         target_info,
@@ -6374,6 +6725,7 @@ fn load_derived_partial_procs<'a>(
             subs,
             // There are no derived expectations.
             expectation_subs: None,
+            expect_call_sites: None,
             home,
             ident_ids,
             target_info,
@@ -6509,6 +6861,9 @@ fn run_task<'a>(
             dep_idents,
             cached_subs,
             derived_module,
+            max_type_errors,
+            drop_unused_captures,
+            compile_expects,
         } => Ok(run_solve(
             module,
             ident_ids,
@@ -6523,6 +6878,9 @@ fn run_task<'a>(
             dep_idents,
             cached_subs,
             derived_module,
+            max_type_errors,
+            drop_unused_captures,
+            compile_expects,
         )),
         BuildPendingSpecializations {
             module_id,
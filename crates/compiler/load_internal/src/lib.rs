@@ -4,8 +4,10 @@
 #![allow(clippy::large_enum_variant)]
 
 use roc_module::symbol::ModuleId;
+pub mod api_diff;
 pub mod docs;
 pub mod file;
+pub mod solved_types;
 mod work;
 
 #[cfg(target_family = "wasm")]
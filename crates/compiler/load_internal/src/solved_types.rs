@@ -0,0 +1,398 @@
+//! A stable, machine-readable description of the solved types of a package's top-level defs,
+//! for `roc check --emit-types`. Tools can diff it between versions of a package to notice
+//! when its public types change.
+//!
+//! Modules and defs are sorted by name, type variables are named the way `roc check` would
+//! print them, and lambda sets are left out, so the output only changes when a type does.
+use crate::file::LoadedModule;
+use roc_can::abilities::AbilitiesStore;
+use roc_can::expr::{DeclarationTag, Declarations};
+use roc_collections::{MutMap, VecSet};
+use roc_module::symbol::{Interns, ModuleId, Symbol};
+use roc_types::pretty_print::{name_and_print_var, DebugPrint};
+use roc_types::subs::{Content, FlatType, GetSubsSlice, Subs, Variable};
+use roc_types::types::{AliasKind, RecordField};
+use serde_json::{json, Map, Value};
+
+/// Bumped whenever the shape of the output changes in a way that could break its readers.
+const FORMAT_VERSION: u32 = 1;
+
+/// The solved types of a module other than the root one, kept around when
+/// [`LoadConfig::keep_module_types`](crate::file::LoadConfig::keep_module_types) is set.
+#[derive(Debug)]
+pub struct ModuleTypes {
+    pub subs: Subs,
+    pub defs: Vec<(Symbol, Variable)>,
+    pub exposed: VecSet<Symbol>,
+}
+
+/// The top-level defs of a module and their types, leaving out ability implementations, whose
+/// names aren't unique within a module.
+pub fn top_level_defs(
+    declarations: &Declarations,
+    abilities_store: &AbilitiesStore,
+) -> Vec<(Symbol, Variable)> {
+    use DeclarationTag::*;
+
+    let mut defs = Vec::new();
+
+    for (index, tag) in declarations.declarations.iter().enumerate() {
+        match tag {
+            Value | Function(_) | Recursive(_) | TailRecursive(_) => {
+                defs.push((
+                    declarations.symbols[index].value,
+                    declarations.variables[index],
+                ));
+            }
+            Destructure(destructure_index) => {
+                let destructure = &declarations.destructs[destructure_index.index()];
+
+                defs.extend(destructure.pattern_vars.iter().map(|(s, v)| (*s, *v)));
+            }
+            MutualRecursion { .. } | Expectation | ExpectationFx => {}
+        }
+    }
+
+    defs.retain(|(symbol, _)| !abilities_store.is_specialization_name(*symbol));
+
+    defs
+}
+
+/// Describes the type of every top-level def in the root module, and in the other modules whose
+/// types were kept, as pretty-printed JSON.
+pub fn to_json(loaded: &mut LoadedModule) -> String {
+    let output = json!({
+        "format_version": FORMAT_VERSION,
+        "modules": modules_json(loaded),
+    });
+
+    let mut content = serde_json::to_string_pretty(&output).unwrap();
+    content.push('\n');
+
+    content
+}
+
+/// The description of each module in [to_json], sorted by name.
+pub fn modules_json(loaded: &mut LoadedModule) -> Vec<Value> {
+    let root_id = loaded.module_id;
+    let mut modules = Vec::with_capacity(loaded.module_types.len() + 1);
+
+    if let Some(declarations) = loaded.declarations_by_id.get(&root_id) {
+        let defs = top_level_defs(declarations, &loaded.abilities_store);
+        let exposed = loaded.exposed_values.iter().copied().collect();
+
+        modules.push(module_json(
+            root_id,
+            loaded.solved.inner_mut(),
+            &defs,
+            &exposed,
+            &loaded.interns,
+            &loaded.sources,
+        ));
+    }
+
+    for (module_id, module_types) in loaded.module_types.iter_mut() {
+        modules.push(module_json(
+            *module_id,
+            &mut module_types.subs,
+            &module_types.defs,
+            &module_types.exposed,
+            &loaded.interns,
+            &loaded.sources,
+        ));
+    }
+
+    modules.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+
+    modules
+}
+
+fn module_json(
+    home: ModuleId,
+    subs: &mut Subs,
+    defs: &[(Symbol, Variable)],
+    exposed: &VecSet<Symbol>,
+    interns: &Interns,
+    sources: &MutMap<ModuleId, (std::path::PathBuf, Box<str>)>,
+) -> Value {
+    let mut name = interns.module_name(home).to_string();
+
+    if name.is_empty() {
+        // Apps have no module name, so use the name of their file instead.
+        name = sources
+            .get(&home)
+            .and_then(|(path, _)| path.file_stem())
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+    }
+
+    let mut defs: Vec<Value> = defs
+        .iter()
+        .map(|(symbol, var)| {
+            // This also names the type variables, which the structure below reuses.
+            let printed = name_and_print_var(*var, subs, home, interns, DebugPrint::NOTHING);
+
+            let mut describer = Describer {
+                subs,
+                interns,
+                constraints: Vec::new(),
+                seen: Vec::new(),
+            };
+            let structure = describer.describe(*var);
+            let constraints: Vec<Value> = describer
+                .constraints
+                .into_iter()
+                .map(|(var_name, abilities)| json!({ "var": var_name, "abilities": abilities }))
+                .collect();
+
+            json!({
+                "name": symbol.as_str(interns),
+                "exposed": exposed.contains(symbol),
+                "type": printed,
+                "structure": structure,
+                "constraints": constraints,
+            })
+        })
+        .collect();
+
+    defs.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+
+    json!({
+        "name": name,
+        "defs": defs,
+    })
+}
+
+struct Describer<'a> {
+    subs: &'a Subs,
+    interns: &'a Interns,
+    /// The abilities each type variable has to implement, in the order they were first seen.
+    constraints: Vec<(String, Vec<String>)>,
+    /// The variables being described, to stop at recursion.
+    seen: Vec<Variable>,
+}
+
+impl<'a> Describer<'a> {
+    fn describe(&mut self, var: Variable) -> Value {
+        let var = self.subs.get_root_key_without_compacting(var);
+
+        if self.seen.contains(&var) {
+            return json!({ "kind": "recursion" });
+        }
+
+        self.seen.push(var);
+        let value = self.describe_content(var);
+        self.seen.pop();
+
+        value
+    }
+
+    fn describe_content(&mut self, var: Variable) -> Value {
+        use Content::*;
+
+        match *self.subs.get_content_without_compacting(var) {
+            FlexVar(opt_name) => self.type_var(opt_name.map(|i| self.subs[i].as_str()), &[]),
+            RigidVar(name) => self.type_var(Some(self.subs[name].as_str()), &[]),
+            FlexAbleVar(opt_name, abilities) => {
+                let abilities = self.subs.get_subs_slice(abilities);
+
+                self.type_var(opt_name.map(|i| self.subs[i].as_str()), abilities)
+            }
+            RigidAbleVar(name, abilities) => {
+                let abilities = self.subs.get_subs_slice(abilities);
+
+                self.type_var(Some(self.subs[name].as_str()), abilities)
+            }
+            RecursionVar { opt_name, .. } => json!({
+                "kind": "recursion",
+                "name": opt_name.map(|i| self.subs[i].as_str()),
+            }),
+            Structure(flat_type) => self.describe_flat_type(flat_type),
+            Alias(symbol, args, real_var, kind) => {
+                let args: Vec<Value> = self
+                    .subs
+                    .get_subs_slice(args.type_variables())
+                    .iter()
+                    .map(|arg| self.describe(*arg))
+                    .collect();
+
+                match kind {
+                    AliasKind::Structural => json!({
+                        "kind": "alias",
+                        "name": self.symbol_name(symbol),
+                        "args": args,
+                        "actual": self.describe(real_var),
+                    }),
+                    // What an opaque type wraps isn't part of its interface.
+                    AliasKind::Opaque => json!({
+                        "kind": "opaque",
+                        "name": self.symbol_name(symbol),
+                        "args": args,
+                    }),
+                }
+            }
+            RangedNumber(_) => json!({ "kind": "number_literal" }),
+            LambdaSet(_) => json!({ "kind": "lambda_set" }),
+            Error => json!({ "kind": "error" }),
+        }
+    }
+
+    fn describe_flat_type(&mut self, flat_type: FlatType) -> Value {
+        use FlatType::*;
+
+        match flat_type {
+            Apply(symbol, args) => {
+                let args: Vec<Value> = self
+                    .subs
+                    .get_subs_slice(args)
+                    .iter()
+                    .map(|arg| self.describe(*arg))
+                    .collect();
+
+                json!({
+                    "kind": "apply",
+                    "name": self.symbol_name(symbol),
+                    "args": args,
+                })
+            }
+            Func(args, _lambda_set, ret) => {
+                let args: Vec<Value> = self
+                    .subs
+                    .get_subs_slice(args)
+                    .iter()
+                    .map(|arg| self.describe(*arg))
+                    .collect();
+
+                json!({
+                    "kind": "function",
+                    "args": args,
+                    "ret": self.describe(ret),
+                })
+            }
+            Record(fields, ext) => {
+                let (fields, ext) = fields.sorted_iterator_and_ext(self.subs, ext);
+                let fields: Vec<(_, _)> = fields.collect();
+                let fields: Vec<Value> = fields
+                    .into_iter()
+                    .map(|(name, field)| {
+                        let (requirement, var) = match field {
+                            RecordField::Demanded(var) | RecordField::Required(var) => {
+                                ("required", var)
+                            }
+                            RecordField::RigidRequired(var) => ("required", var),
+                            RecordField::Optional(var) | RecordField::RigidOptional(var) => {
+                                ("optional", var)
+                            }
+                        };
+
+                        json!({
+                            "name": name.as_str(),
+                            "requirement": requirement,
+                            "type": self.describe(var),
+                        })
+                    })
+                    .collect();
+
+                json!({
+                    "kind": "record",
+                    "fields": fields,
+                    "extension": self.extension(ext),
+                })
+            }
+            Tuple(elems, ext) => {
+                let (elems, ext) = elems.sorted_iterator_and_ext(self.subs, ext);
+                let elems: Vec<(_, _)> = elems.collect();
+                let elems: Vec<Value> = elems
+                    .into_iter()
+                    .map(|(_, var)| self.describe(var))
+                    .collect();
+
+                json!({
+                    "kind": "tuple",
+                    "elems": elems,
+                    "extension": self.extension(ext),
+                })
+            }
+            TagUnion(tags, ext) | RecursiveTagUnion(_, tags, ext) => {
+                let (tags, ext) = tags.sorted_iterator_and_ext(self.subs, ext);
+                let tags: Vec<(_, Vec<Variable>)> =
+                    tags.map(|(name, args)| (name, args.to_vec())).collect();
+                let tags: Vec<Value> = tags
+                    .into_iter()
+                    .map(|(name, args)| {
+                        let args: Vec<Value> = args.iter().map(|arg| self.describe(*arg)).collect();
+
+                        json!({ "name": name.0.as_str(), "args": args })
+                    })
+                    .collect();
+
+                let mut union = Map::new();
+                union.insert("kind".into(), json!("tag_union"));
+                union.insert("tags".into(), json!(tags));
+                union.insert("extension".into(), self.extension(ext.var()));
+
+                if let RecursiveTagUnion(rec_var, ..) = flat_type {
+                    let rec_name = match self.subs.get_content_without_compacting(rec_var) {
+                        Content::RecursionVar {
+                            opt_name: Some(name),
+                            ..
+                        } => Some(self.subs[*name].as_str()),
+                        _ => None,
+                    };
+
+                    union.insert("recursion_var".into(), json!(rec_name));
+                }
+
+                Value::Object(union)
+            }
+            FunctionOrTagUnion(tag_names, _, ext) => {
+                let tags: Vec<Value> = self
+                    .subs
+                    .get_subs_slice(tag_names)
+                    .iter()
+                    .map(|name| json!({ "name": name.0.as_str(), "args": [] }))
+                    .collect();
+
+                json!({
+                    "kind": "tag_union",
+                    "tags": tags,
+                    "extension": self.extension(ext.var()),
+                })
+            }
+            EmptyRecord => json!({ "kind": "record", "fields": [], "extension": null }),
+            EmptyTuple => json!({ "kind": "tuple", "elems": [], "extension": null }),
+            EmptyTagUnion => json!({ "kind": "tag_union", "tags": [], "extension": null }),
+        }
+    }
+
+    /// `null` for a closed record, tuple, or tag union.
+    fn extension(&mut self, ext: Variable) -> Value {
+        match self.subs.get_content_without_compacting(ext) {
+            Content::Structure(FlatType::EmptyRecord)
+            | Content::Structure(FlatType::EmptyTuple)
+            | Content::Structure(FlatType::EmptyTagUnion) => Value::Null,
+            _ => self.describe(ext),
+        }
+    }
+
+    fn type_var(&mut self, name: Option<&str>, abilities: &[Symbol]) -> Value {
+        // Unnamed variables only occur once, and print as a wildcard.
+        let name = name.unwrap_or("*").to_string();
+
+        if !abilities.is_empty() && !self.constraints.iter().any(|(n, _)| *n == name) {
+            let abilities = abilities.iter().map(|a| self.symbol_name(*a)).collect();
+
+            self.constraints.push((name.clone(), abilities));
+        }
+
+        json!({ "kind": "var", "name": name })
+    }
+
+    fn symbol_name(&self, symbol: Symbol) -> String {
+        format!(
+            "{}.{}",
+            symbol.module_string(self.interns),
+            symbol.as_str(self.interns)
+        )
+    }
+}
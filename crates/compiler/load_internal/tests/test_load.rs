@@ -22,6 +22,7 @@ use roc_load_internal::file::{LoadResult, LoadStart, LoadedModule, LoadingProble
 use roc_module::ident::ModuleName;
 use roc_module::symbol::{Interns, ModuleId};
 use roc_packaging::cache::RocCacheDir;
+use roc_parse::header::PlatformThreading;
 use roc_problem::can::Problem;
 use roc_region::all::LineInfo;
 use roc_reporting::report::RenderTarget;
@@ -31,7 +32,7 @@ use roc_target::TargetInfo;
 use roc_types::pretty_print::name_and_print_var;
 use roc_types::pretty_print::DebugPrint;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 fn load_and_typecheck(
     arena: &Bump,
@@ -47,6 +48,7 @@ fn load_and_typecheck(
         RenderTarget::Generic,
         RocCacheDir::Disallowed,
         DEFAULT_PALETTE,
+        None,
     )?;
     let load_config = LoadConfig {
         target_info,
@@ -54,6 +56,9 @@ fn load_and_typecheck(
         palette: DEFAULT_PALETTE,
         threading: Threading::Single,
         exec_mode: ExecutionMode::Check,
+        platform: None,
+        max_type_errors: None,
+        keep_module_types: false,
     };
 
     match roc_load_internal::file::load(
@@ -966,6 +971,48 @@ fn import_builtin_in_platform_and_check_app() {
     assert!(result.is_ok(), "should check");
 }
 
+#[test]
+fn platform_declares_threading() {
+    let modules = vec![
+        (
+            "platform/main.roc",
+            indoc!(
+                r#"
+                    platform "testplatform"
+                        requires {} { main : Str }
+                        exposes []
+                        packages {}
+                        imports []
+                        provides [mainForHost]
+                        threading singleThreaded
+
+                    mainForHost : Str
+                    mainForHost = main
+                    "#
+            ),
+        ),
+        (
+            "Main",
+            indoc!(
+                r#"
+                    app "test"
+                        packages { pf: "platform/main.roc" }
+                        provides [main] to pf
+
+                    main = ""
+                    "#
+            ),
+        ),
+    ];
+
+    let loaded_module = multiple_modules("platform_declares_threading", modules).unwrap();
+
+    assert_eq!(
+        loaded_module.platform_threading,
+        PlatformThreading::SingleThreaded
+    );
+}
+
 #[test]
 fn module_doesnt_match_file_path() {
     let modules = vec![(
@@ -1126,3 +1173,465 @@ fn nested_module_has_incorrect_name() {
         err
     );
 }
+
+#[test]
+fn test_modules_are_loaded_as_roots() {
+    use std::fs;
+
+    let dir = roc_test_utils::TmpDir::new("tmp/test_modules_are_loaded_as_roots");
+
+    fs::create_dir_all(dir.path().join("Tests")).unwrap();
+    fs::write(
+        dir.path().join("Greeting.roc"),
+        indoc!(
+            r#"
+            interface Greeting exposes [greet] imports []
+
+            greet = \name -> "Hello, \(name)!"
+
+            expect greet "Roc" == "Hello, Roc!"
+            "#
+        ),
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("Tests/GreetingTest.roc"),
+        indoc!(
+            r#"
+            interface Tests.GreetingTest exposes [] imports [Greeting]
+
+            expect Greeting.greet "" == "Hello, !"
+            "#
+        ),
+    )
+    .unwrap();
+    // not a test module, so it's not loaded
+    fs::write(
+        dir.path().join("Tests/Helpers.roc"),
+        "interface Tests.Helpers exposes [] imports []\n\nexpect 1 == 2\n",
+    )
+    .unwrap();
+
+    let arena = Bump::new();
+    let load_start = LoadStart::from_path(
+        &arena,
+        dir.path().join("Greeting.roc"),
+        RenderTarget::Generic,
+        RocCacheDir::Disallowed,
+        DEFAULT_PALETTE,
+        None,
+    )
+    .unwrap();
+    let load_config = LoadConfig {
+        target_info: TARGET_INFO,
+        render: RenderTarget::Generic,
+        palette: DEFAULT_PALETTE,
+        threading: Threading::Single,
+        exec_mode: ExecutionMode::Test,
+        platform: None,
+        max_type_errors: None,
+        keep_module_types: false,
+    };
+
+    let loaded = match roc_load_internal::file::load(
+        &arena,
+        load_start,
+        Default::default(),
+        Default::default(),
+        RocCacheDir::Disallowed,
+        load_config,
+    ) {
+        Ok(LoadResult::Monomorphized(module)) => module,
+        Ok(LoadResult::TypeChecked(_)) => panic!("the modules should have no errors"),
+        Err(problem) => panic!("{:?}", problem),
+    };
+
+    let mut modules_with_expects: Vec<&str> = loaded
+        .expectations
+        .keys()
+        .map(|module_id| loaded.interns.module_name(*module_id).as_str())
+        .collect();
+    modules_with_expects.sort_unstable();
+
+    assert_eq!(modules_with_expects, ["Greeting", "Tests.GreetingTest"]);
+    assert_eq!(loaded.toplevel_expects.pure.len(), 2);
+}
+
+#[test]
+fn platform_override_replaces_app_platform() {
+    use std::fs;
+
+    let dir = roc_test_utils::TmpDir::new("tmp/platform_override_replaces_app_platform");
+    let platform = |threading: &str| {
+        format!(
+            indoc!(
+                r#"
+                platform "testplatform"
+                    requires {{}} {{ main : Str }}
+                    exposes []
+                    packages {{}}
+                    imports []
+                    provides [mainForHost]
+                    threading {}
+
+                mainForHost : Str
+                mainForHost = main
+                "#
+            ),
+            threading
+        )
+    };
+
+    fs::create_dir_all(dir.path().join("platform")).unwrap();
+    fs::create_dir_all(dir.path().join("test-platform")).unwrap();
+    fs::write(
+        dir.path().join("platform/main.roc"),
+        platform("multiThreaded"),
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("test-platform/main.roc"),
+        platform("singleThreaded"),
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("Main.roc"),
+        indoc!(
+            r#"
+            app "test"
+                packages { pf: "platform/main.roc" }
+                provides [main] to pf
+
+            main = ""
+            "#
+        ),
+    )
+    .unwrap();
+
+    // Like `roc run --platform`, which makes the path absolute before loading.
+    let test_platform = fs::canonicalize(dir.path().join("test-platform/main.roc")).unwrap();
+    let arena = Bump::new();
+    let load_start = LoadStart::from_path(
+        &arena,
+        dir.path().join("Main.roc"),
+        RenderTarget::Generic,
+        RocCacheDir::Disallowed,
+        DEFAULT_PALETTE,
+        test_platform.to_str(),
+    )
+    .unwrap();
+    let load_config = LoadConfig {
+        target_info: TARGET_INFO,
+        render: RenderTarget::Generic,
+        palette: DEFAULT_PALETTE,
+        threading: Threading::Single,
+        exec_mode: ExecutionMode::Check,
+        platform: None,
+        max_type_errors: None,
+        keep_module_types: false,
+    };
+
+    let loaded = match roc_load_internal::file::load(
+        &arena,
+        load_start,
+        Default::default(),
+        Default::default(),
+        RocCacheDir::Disallowed,
+        load_config,
+    ) {
+        Ok(LoadResult::TypeChecked(module)) => module,
+        Ok(LoadResult::Monomorphized(_)) => unreachable!(),
+        Err(problem) => panic!("{:?}", problem),
+    };
+
+    assert_eq!(loaded.platform_threading, PlatformThreading::SingleThreaded);
+}
+
+#[test]
+fn max_type_errors_stops_solving_early() {
+    use std::fs;
+
+    let dir = roc_test_utils::TmpDir::new("tmp/max_type_errors_stops_solving_early");
+    fs::write(
+        dir.path().join("Main.roc"),
+        indoc!(
+            r#"
+            interface Main exposes [a, b, c, d] imports []
+
+            a = [1, ""]
+
+            # follows from the error in `a`, so it doesn't count
+            b : Str
+            b = a
+
+            c : Str
+            c = 3
+
+            d : Str
+            d = 4
+            "#
+        ),
+    )
+    .unwrap();
+
+    let type_problems = |max_type_errors| {
+        let arena = Bump::new();
+        let load_start = LoadStart::from_path(
+            &arena,
+            dir.path().join("Main.roc"),
+            RenderTarget::Generic,
+            RocCacheDir::Disallowed,
+            DEFAULT_PALETTE,
+            None,
+        )
+        .unwrap();
+        let load_config = LoadConfig {
+            target_info: TARGET_INFO,
+            render: RenderTarget::Generic,
+            palette: DEFAULT_PALETTE,
+            threading: Threading::Single,
+            exec_mode: ExecutionMode::Check,
+            platform: None,
+            max_type_errors,
+            keep_module_types: false,
+        };
+
+        let mut loaded = match roc_load_internal::file::load(
+            &arena,
+            load_start,
+            Default::default(),
+            Default::default(),
+            RocCacheDir::Disallowed,
+            load_config,
+        ) {
+            Ok(LoadResult::TypeChecked(module)) => module,
+            Ok(LoadResult::Monomorphized(_)) => unreachable!(),
+            Err(problem) => panic!("{:?}", problem),
+        };
+
+        loaded
+            .type_problems
+            .remove(&loaded.module_id)
+            .unwrap_or_default()
+            .len()
+    };
+
+    assert_eq!(type_problems(None), 4);
+    assert_eq!(type_problems(Some(2)), 3);
+    assert_eq!(type_problems(Some(1)), 1);
+}
+
+#[test]
+fn emit_types_of_every_module_in_the_package() {
+    use std::fs;
+
+    let dir = roc_test_utils::TmpDir::new("tmp/emit_types_of_every_module_in_the_package");
+    fs::write(
+        dir.path().join("Dep.roc"),
+        indoc!(
+            r#"
+            interface Dep exposes [Token, token] imports []
+
+            Token := U64
+
+            token : U64 -> Token
+            token = \n -> @Token n
+
+            helper = 1
+            "#
+        ),
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("Main.roc"),
+        indoc!(
+            r#"
+            interface Main exposes [same, name] imports [Dep]
+
+            same : a, a -> Bool | a has Eq
+            same = \x, y -> x == y
+
+            name : { name : Str, token : Dep.Token } -> Str
+            name = \r -> r.name
+
+            internal = Dep.token 1
+            "#
+        ),
+    )
+    .unwrap();
+
+    let arena = Bump::new();
+    let load_start = LoadStart::from_path(
+        &arena,
+        dir.path().join("Main.roc"),
+        RenderTarget::Generic,
+        RocCacheDir::Disallowed,
+        DEFAULT_PALETTE,
+        None,
+    )
+    .unwrap();
+    let load_config = LoadConfig {
+        target_info: TARGET_INFO,
+        render: RenderTarget::Generic,
+        palette: DEFAULT_PALETTE,
+        threading: Threading::Single,
+        exec_mode: ExecutionMode::Check,
+        platform: None,
+        max_type_errors: None,
+        keep_module_types: true,
+    };
+
+    let mut loaded = match roc_load_internal::file::load(
+        &arena,
+        load_start,
+        Default::default(),
+        Default::default(),
+        RocCacheDir::Disallowed,
+        load_config,
+    ) {
+        Ok(LoadResult::TypeChecked(module)) => module,
+        Ok(LoadResult::Monomorphized(_)) => unreachable!(),
+        Err(problem) => panic!("{:?}", problem),
+    };
+
+    let json = roc_load_internal::solved_types::to_json(&mut loaded);
+    let types: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let def = |module: usize, def: usize| &types["modules"][module]["defs"][def];
+
+    assert_eq!(types["modules"][0]["name"], "Dep");
+    assert_eq!(types["modules"][1]["name"], "Main");
+
+    assert_eq!(def(0, 0)["name"], "helper");
+    assert_eq!(def(0, 0)["exposed"], false);
+    assert_eq!(def(0, 1)["name"], "token");
+    assert_eq!(def(0, 1)["exposed"], true);
+    assert_eq!(def(0, 1)["type"], "U64 -> Token");
+    assert_eq!(
+        def(0, 1)["structure"]["ret"],
+        serde_json::json!({ "kind": "opaque", "name": "Dep.Token", "args": [] })
+    );
+    assert_eq!(def(0, 1)["structure"]["args"][0]["kind"], "alias");
+    assert_eq!(def(0, 1)["structure"]["args"][0]["name"], "Num.U64");
+
+    assert_eq!(def(1, 0)["name"], "internal");
+    assert_eq!(def(1, 0)["exposed"], false);
+    assert_eq!(def(1, 1)["name"], "name");
+    assert_eq!(
+        def(1, 1)["structure"]["args"][0]["fields"][1]["type"]["name"],
+        "Dep.Token"
+    );
+    assert_eq!(def(1, 2)["name"], "same");
+    assert_eq!(
+        def(1, 2)["constraints"],
+        serde_json::json!([{ "var": "a", "abilities": ["Bool.Eq"] }])
+    );
+}
+
+#[test]
+fn api_diff_between_two_versions() {
+    use roc_load_internal::api_diff::{self, ChangeKind, VersionBump};
+    use std::fs;
+
+    fn load_types(dir: &Path, src: &str) -> Vec<serde_json::Value> {
+        fs::write(dir.join("Main.roc"), src).unwrap();
+
+        let arena = Bump::new();
+        let load_start = LoadStart::from_path(
+            &arena,
+            dir.join("Main.roc"),
+            RenderTarget::Generic,
+            RocCacheDir::Disallowed,
+            DEFAULT_PALETTE,
+            None,
+        )
+        .unwrap();
+        let load_config = LoadConfig {
+            target_info: TARGET_INFO,
+            render: RenderTarget::Generic,
+            palette: DEFAULT_PALETTE,
+            threading: Threading::Single,
+            exec_mode: ExecutionMode::Check,
+            platform: None,
+            max_type_errors: None,
+            keep_module_types: true,
+        };
+
+        let mut loaded = match roc_load_internal::file::load(
+            &arena,
+            load_start,
+            Default::default(),
+            Default::default(),
+            RocCacheDir::Disallowed,
+            load_config,
+        ) {
+            Ok(LoadResult::TypeChecked(module)) => module,
+            Ok(LoadResult::Monomorphized(_)) => unreachable!(),
+            Err(problem) => panic!("{:?}", problem),
+        };
+
+        roc_load_internal::solved_types::modules_json(&mut loaded)
+    }
+
+    let old_dir = roc_test_utils::TmpDir::new("tmp/api_diff_between_two_versions_old");
+    let old = load_types(
+        old_dir.path(),
+        indoc!(
+            r#"
+            interface Main exposes [greet, first, label, gone] imports []
+
+            greet : Str -> Str
+            greet = \name -> "Hi \(name)"
+
+            first : List Str -> Result Str [ListWasEmpty]
+            first = \list -> List.first list
+
+            label : { name : Str } -> Str
+            label = \r -> r.name
+
+            gone = 1
+            "#
+        ),
+    );
+
+    let new_dir = roc_test_utils::TmpDir::new("tmp/api_diff_between_two_versions_new");
+    let new = load_types(
+        new_dir.path(),
+        indoc!(
+            r#"
+            interface Main exposes [greet, first, label, added] imports []
+
+            greet : a -> a
+            greet = \x -> x
+
+            first : List Str -> Result Str [ListWasEmpty, Other]
+            first = \list -> List.first list
+
+            label : { name : Str }* -> Str
+            label = \r -> r.name
+
+            added = "new"
+            "#
+        ),
+    );
+
+    let changes = api_diff::diff(&old, &new);
+    let kinds: Vec<(&str, ChangeKind)> = changes
+        .iter()
+        .map(|change| (change.name.as_str(), change.kind))
+        .collect();
+
+    assert_eq!(
+        kinds,
+        [
+            ("added", ChangeKind::Added),
+            ("first", ChangeKind::Breaking),
+            ("gone", ChangeKind::Removed),
+            ("greet", ChangeKind::Compatible),
+            ("label", ChangeKind::Compatible),
+        ]
+    );
+    assert_eq!(changes[3].old_type.as_deref(), Some("Str -> Str"));
+    assert_eq!(changes[3].new_type.as_deref(), Some("a -> a"));
+    assert_eq!(VersionBump::of(&changes), VersionBump::Major);
+    assert_eq!(VersionBump::of(&changes[3..]), VersionBump::Minor);
+}
@@ -1268,6 +1268,8 @@ define_builtins! {
         162 NUM_PI: "pi"
         163 NUM_TAU: "tau"
         164 NUM_BITWISE_NOT: "bitwiseNot"
+        165 NUM_ABS_CHECKED: "absChecked"
+        166 NUM_NEG_CHECKED: "negChecked"
     }
     4 BOOL: "Bool" => {
         0 BOOL_BOOL: "Bool" exposed_type=true // the Bool.Bool type alias
@@ -1579,6 +1581,7 @@ define_builtins! {
         19 HASH_HASH_STR_BYTES: "hashStrBytes"
         20 HASH_HASH_LIST: "hashList"
         21 HASH_HASH_UNORDERED: "hashUnordered"
+        22 HASH_PACK_INT: "packInt"
     }
     14 JSON: "TotallyNotJson" => {
         0 JSON_JSON: "TotallyNotJson"
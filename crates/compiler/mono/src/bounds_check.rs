@@ -0,0 +1,335 @@
+//! Removes bounds checks that an earlier check in the same procedure already made.
+//!
+//! In the branch where `index < List.len list` is true, or where `List.get list index` returned
+//! `Ok`, the index is known to be in bounds for the rest of that branch: symbols are immutable,
+//! and lists can't change length behind a symbol. There, another `index < List.len list`
+//! becomes `Bool.true`, and `List.get list index` becomes `Ok` of an unchecked element access.
+//!
+//! This runs before refcounting operations are inserted, so the element access needs no extra
+//! care; the refcounting pass handles the low-level call like any other.
+
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use roc_collections::MutMap;
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::{IdentIds, ModuleId, Symbol};
+
+use crate::ir::{Call, CallType, Expr, Literal, Proc, ProcLayout, Stmt, UpdateModeId};
+use crate::layout::{
+    InLayout, LayoutInterner, LayoutRepr, STLayoutInterner, TagIdIntType, UnionLayout,
+};
+
+/// The tag id of `Ok` in a `Result`: tags are sorted by name, so `Err` comes first.
+const OK_TAG_ID: TagIdIntType = 1;
+
+pub fn eliminate_bounds_checks<'a>(
+    arena: &'a Bump,
+    layout_interner: &STLayoutInterner<'a>,
+    home: ModuleId,
+    ident_ids: &mut IdentIds,
+    procedures: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) {
+    for proc in procedures.values_mut() {
+        let mut env = Env {
+            arena,
+            layout_interner,
+            home,
+            ident_ids,
+            lengths: MutMap::default(),
+            comparisons: MutMap::default(),
+            gets: MutMap::default(),
+            tag_ids: MutMap::default(),
+            in_bounds: std::vec::Vec::new(),
+            changed: false,
+        };
+
+        let body = env.eliminate_stmt(&proc.body);
+
+        if env.changed {
+            proc.body = body;
+        }
+    }
+}
+
+/// A check that `index` is in bounds for `list`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct BoundsCheck {
+    index: Symbol,
+    list: Symbol,
+}
+
+struct Env<'a, 'i> {
+    arena: &'a Bump,
+    layout_interner: &'i STLayoutInterner<'a>,
+    home: ModuleId,
+    ident_ids: &'i mut IdentIds,
+    /// Symbols bound to `List.len list`, with their list.
+    lengths: MutMap<Symbol, Symbol>,
+    /// Symbols bound to `index < List.len list`.
+    comparisons: MutMap<Symbol, BoundsCheck>,
+    /// Symbols bound to `List.get list index`.
+    gets: MutMap<Symbol, BoundsCheck>,
+    /// Symbols bound to the tag id of a `List.get list index`.
+    tag_ids: MutMap<Symbol, BoundsCheck>,
+    /// The checks known to have passed at the current statement.
+    in_bounds: std::vec::Vec<BoundsCheck>,
+    changed: bool,
+}
+
+impl<'a, 'i> Env<'a, 'i> {
+    fn eliminate_stmt(&mut self, stmt: &Stmt<'a>) -> Stmt<'a> {
+        match stmt {
+            Stmt::Let(symbol, expr, layout, continuation) => {
+                if let Some(check) = self.list_get(expr) {
+                    if self.in_bounds.contains(&check) {
+                        if let Some(stmt) =
+                            self.unchecked_get(*symbol, check, *layout, continuation)
+                        {
+                            self.changed = true;
+
+                            return stmt;
+                        }
+                    }
+
+                    self.gets.insert(*symbol, check);
+                }
+
+                let expr = self.eliminate_expr(*symbol, expr);
+                let continuation = self.eliminate_stmt(continuation);
+
+                Stmt::Let(*symbol, expr, *layout, self.arena.alloc(continuation))
+            }
+            Stmt::Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout,
+            } => {
+                let check = (self.comparisons.get(cond_symbol))
+                    .or_else(|| self.tag_ids.get(cond_symbol))
+                    .copied();
+
+                // A comparison or a `Result` tag id can only be 0 or 1, so when the only other
+                // branch is for 0, the default branch is the one where the check passed.
+                let passes_by_default = matches!(branches, [(0, _, _)]);
+
+                let mut new_branches = Vec::with_capacity_in(branches.len(), self.arena);
+
+                for (id, info, branch) in branches.iter() {
+                    let check = check.filter(|_| *id == OK_TAG_ID as u64);
+                    let branch = self.eliminate_branch(check, branch);
+
+                    new_branches.push((*id, info.clone(), branch));
+                }
+
+                let (default_info, default_stmt) = default_branch;
+                let check = check.filter(|_| passes_by_default);
+                let default_stmt = self.eliminate_branch(check, default_stmt);
+
+                Stmt::Switch {
+                    cond_symbol: *cond_symbol,
+                    cond_layout: *cond_layout,
+                    branches: new_branches.into_bump_slice(),
+                    default_branch: (default_info.clone(), self.arena.alloc(default_stmt)),
+                    ret_layout: *ret_layout,
+                }
+            }
+            Stmt::Refcounting(modify_rc, continuation) => {
+                let continuation = self.eliminate_stmt(continuation);
+
+                Stmt::Refcounting(*modify_rc, self.arena.alloc(continuation))
+            }
+            Stmt::Expect {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => {
+                let remainder = self.eliminate_stmt(remainder);
+
+                Stmt::Expect {
+                    condition: *condition,
+                    region: *region,
+                    lookups: *lookups,
+                    variables: *variables,
+                    remainder: self.arena.alloc(remainder),
+                }
+            }
+            Stmt::ExpectFx {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => {
+                let remainder = self.eliminate_stmt(remainder);
+
+                Stmt::ExpectFx {
+                    condition: *condition,
+                    region: *region,
+                    lookups: *lookups,
+                    variables: *variables,
+                    remainder: self.arena.alloc(remainder),
+                }
+            }
+            Stmt::Dbg {
+                symbol,
+                variable,
+                remainder,
+            } => {
+                let remainder = self.eliminate_stmt(remainder);
+
+                Stmt::Dbg {
+                    symbol: *symbol,
+                    variable: *variable,
+                    remainder: self.arena.alloc(remainder),
+                }
+            }
+            Stmt::Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => {
+                // Every jump to the join point comes from the remainder, where the checks that
+                // passed before the join point still hold.
+                let body = self.eliminate_stmt(body);
+                let remainder = self.eliminate_stmt(remainder);
+
+                Stmt::Join {
+                    id: *id,
+                    parameters: *parameters,
+                    body: self.arena.alloc(body),
+                    remainder: self.arena.alloc(remainder),
+                }
+            }
+            Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::Crash(_, _) => stmt.clone(),
+        }
+    }
+
+    fn eliminate_branch(&mut self, check: Option<BoundsCheck>, branch: &Stmt<'a>) -> Stmt<'a> {
+        let known = self.in_bounds.len();
+
+        self.in_bounds.extend(check);
+        let branch = self.eliminate_stmt(branch);
+        self.in_bounds.truncate(known);
+
+        branch
+    }
+
+    fn eliminate_expr(&mut self, symbol: Symbol, expr: &Expr<'a>) -> Expr<'a> {
+        let check = match expr {
+            Expr::Call(call) => match (lowlevel_op(&call.call_type), call.arguments) {
+                (Some(LowLevel::ListLen), [list]) => {
+                    self.lengths.insert(symbol, *list);
+
+                    None
+                }
+                (Some(LowLevel::NumLt), [index, length])
+                | (Some(LowLevel::NumGt), [length, index]) => {
+                    (self.lengths.get(length)).map(|list| BoundsCheck {
+                        index: *index,
+                        list: *list,
+                    })
+                }
+                _ => None,
+            },
+            Expr::GetTagId { structure, .. } => {
+                if let Some(check) = self.gets.get(structure) {
+                    self.tag_ids.insert(symbol, *check);
+                }
+
+                None
+            }
+            _ => None,
+        };
+
+        match check {
+            Some(check) if self.in_bounds.contains(&check) => {
+                self.changed = true;
+
+                Expr::Literal(Literal::Bool(true))
+            }
+            Some(check) => {
+                self.comparisons.insert(symbol, check);
+
+                expr.clone()
+            }
+            None => expr.clone(),
+        }
+    }
+
+    /// The bounds check made by a call to `List.get`.
+    fn list_get(&self, expr: &Expr<'a>) -> Option<BoundsCheck> {
+        match expr {
+            Expr::Call(Call {
+                call_type: CallType::ByName { name, .. },
+                arguments: [list, index],
+            }) if name.name() == Symbol::LIST_GET => Some(BoundsCheck {
+                index: *index,
+                list: *list,
+            }),
+            _ => None,
+        }
+    }
+
+    /// `List.get` with an index known to be in bounds, as `Ok` of an unchecked access.
+    fn unchecked_get(
+        &mut self,
+        symbol: Symbol,
+        check: BoundsCheck,
+        result_layout: InLayout<'a>,
+        continuation: &Stmt<'a>,
+    ) -> Option<Stmt<'a>> {
+        let (union_layout, elem_layout) = match self.layout_interner.get_repr(result_layout) {
+            LayoutRepr::Union(UnionLayout::NonRecursive(tags))
+                if tags.len() == 2 && tags[OK_TAG_ID as usize].len() == 1 =>
+            {
+                (UnionLayout::NonRecursive(tags), tags[OK_TAG_ID as usize][0])
+            }
+            _ => return None,
+        };
+
+        let elem = Symbol::new(self.home, self.ident_ids.gen_unique());
+
+        let get_unsafe = Expr::Call(Call {
+            call_type: CallType::LowLevel {
+                op: LowLevel::ListGetUnsafe,
+                update_mode: UpdateModeId::BACKEND_DUMMY,
+            },
+            arguments: self.arena.alloc([check.list, check.index]),
+        });
+
+        let ok = Expr::Tag {
+            tag_layout: union_layout,
+            tag_id: OK_TAG_ID,
+            arguments: self.arena.alloc([elem]),
+        };
+
+        let continuation = self.eliminate_stmt(continuation);
+        let continuation = Stmt::Let(symbol, ok, result_layout, self.arena.alloc(continuation));
+
+        Some(Stmt::Let(
+            elem,
+            get_unsafe,
+            elem_layout,
+            self.arena.alloc(continuation),
+        ))
+    }
+}
+
+/// The low-level operation a call performs, whether directly or through its builtin wrapper.
+fn lowlevel_op(call_type: &CallType) -> Option<LowLevel> {
+    match call_type {
+        CallType::LowLevel { op, .. } => Some(*op),
+        CallType::ByName { name, .. } => match name.name() {
+            Symbol::LIST_LEN => Some(LowLevel::ListLen),
+            Symbol::NUM_LT => Some(LowLevel::NumLt),
+            Symbol::NUM_GT => Some(LowLevel::NumGt),
+            _ => None,
+        },
+        _ => None,
+    }
+}
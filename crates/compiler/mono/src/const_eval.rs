@@ -0,0 +1,242 @@
+//! Evaluates top-level constants at compile time, when they are built entirely from literals.
+//!
+//! A top-level def like `answer = 42` becomes a procedure without arguments, and every use of
+//! `answer` calls it. When such a procedure just returns a number (or a tag that compiles to
+//! `Bool` or `Byte`), its calls are replaced by that literal. List literals whose elements are
+//! such constants then have only literal elements, which the backends put in the read-only data
+//! section instead of building the list at runtime.
+//!
+//! Strings are left alone: they are refcounted, and string literals already live in the data
+//! section.
+
+use bumpalo::collections::{CollectIn, Vec};
+use bumpalo::Bump;
+use roc_collections::MutMap;
+use roc_module::symbol::Symbol;
+
+use crate::ir::{Call, CallType, Expr, ListLiteralElement, Literal, Proc, ProcLayout, Stmt};
+use crate::layout::{InLayout, LambdaName};
+
+pub fn fold_constants<'a>(
+    arena: &'a Bump,
+    procedures: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) {
+    // A constant can be defined in terms of other constants, like `b = a` where `a = 1`.
+    // Folding the call to `a` makes `b` a constant, so keep going until nothing changes.
+    loop {
+        let constants: MutMap<(LambdaName<'a>, InLayout<'a>), Literal<'a>> = procedures
+            .values()
+            .filter_map(|proc| Some(((proc.name, proc.ret_layout), constant_value(proc)?)))
+            .collect();
+
+        let mut changed = false;
+
+        for proc in procedures.values_mut() {
+            let mut env = Env {
+                arena,
+                constants: &constants,
+                literals: MutMap::default(),
+                changed: false,
+            };
+
+            let body = env.fold_stmt(&proc.body);
+
+            if env.changed {
+                proc.body = body;
+                changed = true;
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+}
+
+/// The literal a procedure always returns, if it takes no arguments and does nothing else.
+fn constant_value<'a>(proc: &Proc<'a>) -> Option<Literal<'a>> {
+    if !proc.args.is_empty() || proc.closure_data_layout.is_some() {
+        return None;
+    }
+
+    match &proc.body {
+        Stmt::Let(symbol, Expr::Literal(literal), _, Stmt::Ret(returned))
+            if symbol == returned && is_foldable(literal) =>
+        {
+            Some(*literal)
+        }
+        _ => None,
+    }
+}
+
+fn is_foldable(literal: &Literal) -> bool {
+    !matches!(literal, Literal::Str(_))
+}
+
+struct Env<'a, 'c> {
+    arena: &'a Bump,
+    constants: &'c MutMap<(LambdaName<'a>, InLayout<'a>), Literal<'a>>,
+    /// The symbols in the current procedure that are bound to a literal.
+    literals: MutMap<Symbol, Literal<'a>>,
+    changed: bool,
+}
+
+impl<'a, 'c> Env<'a, 'c> {
+    fn fold_stmt(&mut self, stmt: &Stmt<'a>) -> Stmt<'a> {
+        match stmt {
+            Stmt::Let(symbol, expr, layout, continuation) => {
+                let expr = self.fold_expr(expr);
+
+                if let Expr::Literal(literal) = &expr {
+                    if is_foldable(literal) {
+                        self.literals.insert(*symbol, *literal);
+                    }
+                }
+
+                let continuation = self.fold_stmt(continuation);
+
+                Stmt::Let(*symbol, expr, *layout, self.arena.alloc(continuation))
+            }
+            Stmt::Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout,
+            } => {
+                let mut new_branches = Vec::with_capacity_in(branches.len(), self.arena);
+
+                for (id, info, branch) in branches.iter() {
+                    new_branches.push((*id, info.clone(), self.fold_stmt(branch)));
+                }
+
+                let (default_info, default_stmt) = default_branch;
+                let default_stmt = self.fold_stmt(default_stmt);
+
+                Stmt::Switch {
+                    cond_symbol: *cond_symbol,
+                    cond_layout: *cond_layout,
+                    branches: new_branches.into_bump_slice(),
+                    default_branch: (default_info.clone(), self.arena.alloc(default_stmt)),
+                    ret_layout: *ret_layout,
+                }
+            }
+            Stmt::Refcounting(modify_rc, continuation) => {
+                let continuation = self.fold_stmt(continuation);
+
+                Stmt::Refcounting(*modify_rc, self.arena.alloc(continuation))
+            }
+            Stmt::Expect {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => {
+                let remainder = self.fold_stmt(remainder);
+
+                Stmt::Expect {
+                    condition: *condition,
+                    region: *region,
+                    lookups: *lookups,
+                    variables: *variables,
+                    remainder: self.arena.alloc(remainder),
+                }
+            }
+            Stmt::ExpectFx {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => {
+                let remainder = self.fold_stmt(remainder);
+
+                Stmt::ExpectFx {
+                    condition: *condition,
+                    region: *region,
+                    lookups: *lookups,
+                    variables: *variables,
+                    remainder: self.arena.alloc(remainder),
+                }
+            }
+            Stmt::Dbg {
+                symbol,
+                variable,
+                remainder,
+            } => {
+                let remainder = self.fold_stmt(remainder);
+
+                Stmt::Dbg {
+                    symbol: *symbol,
+                    variable: *variable,
+                    remainder: self.arena.alloc(remainder),
+                }
+            }
+            Stmt::Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => {
+                let body = self.fold_stmt(body);
+                let remainder = self.fold_stmt(remainder);
+
+                Stmt::Join {
+                    id: *id,
+                    parameters: *parameters,
+                    body: self.arena.alloc(body),
+                    remainder: self.arena.alloc(remainder),
+                }
+            }
+            Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::Crash(_, _) => stmt.clone(),
+        }
+    }
+
+    fn fold_expr(&mut self, expr: &Expr<'a>) -> Expr<'a> {
+        match expr {
+            Expr::Call(Call {
+                call_type:
+                    CallType::ByName {
+                        name, ret_layout, ..
+                    },
+                arguments,
+            }) if arguments.is_empty() => match self.constants.get(&(*name, *ret_layout)) {
+                Some(literal) => {
+                    self.changed = true;
+
+                    Expr::Literal(*literal)
+                }
+                None => expr.clone(),
+            },
+            Expr::Array { elem_layout, elems }
+                if elems
+                    .iter()
+                    .any(|elem| self.literal_element(elem).is_some()) =>
+            {
+                self.changed = true;
+
+                let elems = (elems.iter())
+                    .map(|elem| self.literal_element(elem).unwrap_or(*elem))
+                    .collect_in::<Vec<_>>(self.arena);
+
+                Expr::Array {
+                    elem_layout: *elem_layout,
+                    elems: elems.into_bump_slice(),
+                }
+            }
+            _ => expr.clone(),
+        }
+    }
+
+    /// The literal a symbol element of a list literal is bound to.
+    fn literal_element(&self, elem: &ListLiteralElement<'a>) -> Option<ListLiteralElement<'a>> {
+        match elem {
+            ListLiteralElement::Symbol(symbol) => self
+                .literals
+                .get(symbol)
+                .map(|literal| ListLiteralElement::Literal(*literal)),
+            ListLiteralElement::Literal(_) => None,
+        }
+    }
+}
@@ -1377,6 +1377,10 @@ pub struct Env<'a, 'i> {
     /// [Subs] to write specialized variables of lookups in expects.
     /// [None] if this module doesn't produce any expects.
     pub expectation_subs: Option<&'i mut Subs>,
+    /// Where the calls of this module are, by the symbol that each call's result is bound to,
+    /// so a failed expect can show the calls that led to it.
+    /// [None] if this module doesn't produce any expects.
+    pub expect_call_sites: Option<&'i mut MutMap<Symbol, Region>>,
     pub home: ModuleId,
     pub ident_ids: &'i mut IdentIds,
     pub target_info: TargetInfo,
@@ -2118,6 +2122,41 @@ impl<'a> Stmt<'a> {
         from_can(env, var, can_expr, procs, layout_cache)
     }
 
+    /// Number of statements, including the ones in branches and join points.
+    /// A rough estimate of how much code this will generate.
+    pub fn count_statements(&self) -> usize {
+        let mut count = 0;
+        let mut stack = vec![self];
+
+        while let Some(stmt) = stack.pop() {
+            count += 1;
+
+            match stmt {
+                Stmt::Let(_, _, _, cont) | Stmt::Refcounting(_, cont) => stack.push(cont),
+                Stmt::Expect { remainder, .. }
+                | Stmt::ExpectFx { remainder, .. }
+                | Stmt::Dbg { remainder, .. } => stack.push(remainder),
+                Stmt::Switch {
+                    branches,
+                    default_branch,
+                    ..
+                } => {
+                    stack.extend(branches.iter().map(|(_, _, branch)| branch));
+                    stack.push(default_branch.1);
+                }
+                Stmt::Join {
+                    body, remainder, ..
+                } => {
+                    stack.push(body);
+                    stack.push(remainder);
+                }
+                Stmt::Ret(_) | Stmt::Jump(..) | Stmt::Crash(..) => {}
+            }
+        }
+
+        count
+    }
+
     pub fn to_doc<'b, D, A, I>(
         &'b self,
         alloc: &'b D,
@@ -5191,6 +5230,15 @@ pub fn with_hole<'a>(
         Call(boxed, loc_args, _) => {
             let (fn_var, loc_expr, _lambda_set_var, _ret_var) = *boxed;
 
+            if let Some(call_sites) = env.expect_call_sites.as_mut() {
+                let region = Region::across_all(
+                    std::iter::once(&loc_expr.region)
+                        .chain(loc_args.iter().map(|(_, a)| &a.region)),
+                );
+
+                call_sites.insert(assigned, region);
+            }
+
             // even if a call looks like it's by name, it may in fact be by-pointer.
             // E.g. in `(\f, x -> f x)` the call is in fact by pointer.
             // So we check the function name against the list of partial procedures,
@@ -6754,11 +6802,18 @@ pub fn from_can<'a>(
             lookups_in_cond,
         } => {
             let rest = from_can(env, variable, loc_continuation.value, procs, layout_cache);
+
+            if env.expectation_subs.is_none() {
+                // Only expects in the root package are checked; this one came from a dependency.
+                return rest;
+            }
+
             let cond_symbol = env.unique_symbol();
 
             let mut lookups = Vec::with_capacity_in(lookups_in_cond.len(), env.arena);
             let mut lookup_variables = Vec::with_capacity_in(lookups_in_cond.len(), env.arena);
             let mut specialized_variables = Vec::with_capacity_in(lookups_in_cond.len(), env.arena);
+            let mut forced_lookups = std::vec::Vec::new();
 
             for ExpectLookup {
                 symbol,
@@ -6782,12 +6837,18 @@ pub fn from_can<'a>(
                     .expect("if expects are compiled, their subs should be available");
                 let spec_var = expectation_subs.fresh_unnamed_flex_var();
 
-                if !env.subs.is_function(var) {
-                    // Exclude functions from lookups
-                    lookups.push(symbol);
-                    lookup_variables.push(var);
-                    specialized_variables.push(spec_var);
-                }
+                let symbol = expect_lookup_symbol(
+                    env,
+                    procs,
+                    layout_cache,
+                    &mut forced_lookups,
+                    symbol,
+                    var,
+                );
+
+                lookups.push(symbol);
+                lookup_variables.push(var);
+                specialized_variables.push(spec_var);
             }
 
             let specialized_variables = specialized_variables.into_bump_slice();
@@ -6800,6 +6861,8 @@ pub fn from_can<'a>(
                 remainder: env.arena.alloc(rest),
             };
 
+            stmt = force_expect_lookups(env, procs, layout_cache, forced_lookups, stmt);
+
             stmt = with_hole(
                 env,
                 loc_condition.value,
@@ -6823,11 +6886,18 @@ pub fn from_can<'a>(
             lookups_in_cond,
         } => {
             let rest = from_can(env, variable, loc_continuation.value, procs, layout_cache);
+
+            if env.expectation_subs.is_none() {
+                // Only expects in the root package are checked; this one came from a dependency.
+                return rest;
+            }
+
             let cond_symbol = env.unique_symbol();
 
             let mut lookups = Vec::with_capacity_in(lookups_in_cond.len(), env.arena);
             let mut lookup_variables = Vec::with_capacity_in(lookups_in_cond.len(), env.arena);
             let mut specialized_variables = Vec::with_capacity_in(lookups_in_cond.len(), env.arena);
+            let mut forced_lookups = std::vec::Vec::new();
 
             for ExpectLookup {
                 symbol,
@@ -6851,12 +6921,18 @@ pub fn from_can<'a>(
                     .expect("if expects are compiled, their subs should be available");
                 let spec_var = expectation_subs.fresh_unnamed_flex_var();
 
-                if !env.subs.is_function(var) {
-                    // Exclude functions from lookups
-                    lookups.push(symbol);
-                    lookup_variables.push(var);
-                    specialized_variables.push(spec_var);
-                }
+                let symbol = expect_lookup_symbol(
+                    env,
+                    procs,
+                    layout_cache,
+                    &mut forced_lookups,
+                    symbol,
+                    var,
+                );
+
+                lookups.push(symbol);
+                lookup_variables.push(var);
+                specialized_variables.push(spec_var);
             }
 
             let specialized_variables = specialized_variables.into_bump_slice();
@@ -6869,6 +6945,8 @@ pub fn from_can<'a>(
                 remainder: env.arena.alloc(rest),
             };
 
+            stmt = force_expect_lookups(env, procs, layout_cache, forced_lookups, stmt);
+
             stmt = with_hole(
                 env,
                 loc_condition.value,
@@ -6938,6 +7016,54 @@ pub fn from_can<'a>(
     }
 }
 
+/// The symbol an `expect` stores for a lookup.
+///
+/// Top-level and imported values are thunks: their symbol is never bound to a value, so they
+/// get a fresh symbol that [force_expect_lookups] binds to the forced value. The same goes for
+/// functions, which are only bound to their closure data where they are used as a value.
+fn expect_lookup_symbol<'a>(
+    env: &mut Env<'a, '_>,
+    procs: &mut Procs<'a>,
+    layout_cache: &mut LayoutCache<'a>,
+    forced_lookups: &mut std::vec::Vec<(Symbol, Variable, Symbol)>,
+    symbol: Symbol,
+    var: Variable,
+) -> Symbol {
+    if procs.is_module_thunk(symbol) || env.is_imported_symbol(symbol) || env.subs.is_function(var)
+    {
+        let forced = env.unique_symbol();
+        forced_lookups.push((symbol, var, forced));
+
+        forced
+    } else {
+        // A polymorphic value has a symbol for each of its specializations.
+        procs.get_or_insert_symbol_specialization(env, layout_cache, symbol, var)
+    }
+}
+
+/// Binds the fresh symbols made by [expect_lookup_symbol] right before the `expect` reads them.
+fn force_expect_lookups<'a>(
+    env: &mut Env<'a, '_>,
+    procs: &mut Procs<'a>,
+    layout_cache: &mut LayoutCache<'a>,
+    forced_lookups: std::vec::Vec<(Symbol, Variable, Symbol)>,
+    mut stmt: Stmt<'a>,
+) -> Stmt<'a> {
+    for (symbol, var, forced) in forced_lookups.into_iter().rev() {
+        stmt = specialize_naked_symbol(
+            env,
+            var,
+            procs,
+            layout_cache,
+            forced,
+            env.arena.alloc(stmt),
+            symbol,
+        );
+    }
+
+    stmt
+}
+
 fn store_specialized_expectation_lookups(
     env: &mut Env,
     lookup_variables: impl IntoIterator<Item = Variable>,
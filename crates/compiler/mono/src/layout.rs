@@ -29,7 +29,8 @@ use ven_pretty::{DocAllocator, DocBuilder};
 mod intern;
 mod semantic;
 pub use intern::{
-    GlobalLayoutInterner, InLayout, LayoutInterner, STLayoutInterner, TLLayoutInterner,
+    GlobalLayoutInterner, InLayout, LayoutInterner, LayoutInternerStats, STLayoutInterner,
+    TLLayoutInterner,
 };
 pub use semantic::SemanticRepr;
 
@@ -8,7 +8,7 @@ use std::{
 use bumpalo::Bump;
 use parking_lot::{Mutex, RwLock};
 use roc_builtins::bitcode::{FloatWidth, IntWidth};
-use roc_collections::{default_hasher, BumpMap};
+use roc_collections::{default_hasher, BumpMap, MutMap};
 use roc_module::symbol::Symbol;
 use roc_target::TargetInfo;
 
@@ -953,6 +953,71 @@ impl<'a> STLayoutInterner<'a> {
     pub fn is_empty(&self) -> bool {
         self.vec.is_empty()
     }
+
+    pub fn len(&self) -> usize {
+        self.vec.len()
+    }
+
+    /// Collects statistics on the interned layouts, to help diagnose pathological memory use.
+    /// Reports the `biggest_count` layouts with the largest stack size.
+    pub fn stats(&self, biggest_count: usize) -> LayoutInternerStats<'a> {
+        // Equivalent layouts always print the same way, so only compare layouts within a bucket
+        let mut buckets: MutMap<(String, SemanticRepr<'a>), Vec<Vec<InLayout<'a>>>> =
+            MutMap::default();
+        let mut biggest = Vec::with_capacity(self.vec.len());
+
+        for index in 0..self.vec.len() {
+            // SAFETY: the index is in bounds
+            let layout = unsafe { InLayout::from_index(index) };
+            biggest.push((layout, self.stack_size(layout)));
+
+            // Recursion pointers are references to other layouts, so they're never duplicates
+            if let LayoutRepr::RecursivePointer(_) = self.get_repr(layout) {
+                continue;
+            }
+
+            let key = (self.dbg(layout), self.get_semantic(layout));
+            let groups = buckets.entry(key).or_default();
+            match groups.iter_mut().find(|group| self.equiv(group[0], layout)) {
+                Some(group) => group.push(layout),
+                None => groups.push(vec![layout]),
+            }
+        }
+
+        let mut duplicates: Vec<Vec<InLayout<'a>>> = buckets
+            .into_values()
+            .flatten()
+            .filter(|group| group.len() > 1)
+            .collect();
+        duplicates.sort_by(|g1, g2| g2.len().cmp(&g1.len()).then_with(|| g1[0].cmp(&g2[0])));
+
+        biggest.sort_by(|(l1, s1), (l2, s2)| s2.cmp(s1).then_with(|| l1.cmp(l2)));
+        biggest.truncate(biggest_count);
+
+        LayoutInternerStats {
+            layouts: self.vec.len(),
+            map_entries: self.map.len(),
+            lambda_sets: self.normalized_lambda_set_map.len(),
+            duplicates,
+            biggest,
+        }
+    }
+}
+
+/// Statistics about the layouts in a [STLayoutInterner]; see [STLayoutInterner::stats].
+#[derive(Debug)]
+pub struct LayoutInternerStats<'a> {
+    /// Number of interned layouts, including the reserved ones
+    pub layouts: usize,
+    /// Number of entries in the deduplication map. Recursive layouts have two.
+    pub map_entries: usize,
+    /// Number of distinct lambda sets
+    pub lambda_sets: usize,
+    /// Groups of layouts that are equivalent and have the same semantics, but were interned
+    /// separately. Biggest group first.
+    pub duplicates: Vec<Vec<InLayout<'a>>>,
+    /// The layouts with the largest stack size, largest first
+    pub biggest: Vec<(InLayout<'a>, u32)>,
 }
 
 macro_rules! st_impl {
@@ -1998,3 +2063,28 @@ mod insert_recursive_layout {
         assert_eq!(in1, in2);
     }
 }
+
+#[cfg(test)]
+mod stats {
+    use roc_target::TargetInfo;
+
+    use crate::layout::{Layout, SemanticRepr};
+
+    use super::{LayoutInterner, STLayoutInterner};
+
+    const TARGET_INFO: TargetInfo = TargetInfo::default_x86_64();
+
+    #[test]
+    fn reports_equivalent_layouts_interned_twice() {
+        let mut interner = STLayoutInterner::with_capacity(4, TARGET_INFO);
+        let newtype = interner.insert(Layout {
+            repr: Layout::U64.newtype(),
+            semantic: SemanticRepr::NONE,
+        });
+
+        let stats = interner.stats(1);
+        assert_eq!(stats.layouts, interner.len());
+        assert_eq!(stats.duplicates, vec![vec![Layout::U64, newtype]]);
+        assert_eq!(stats.biggest.len(), 1);
+    }
+}
@@ -10,7 +10,9 @@
 #![allow(clippy::too_many_arguments)]
 
 pub mod borrow;
+pub mod bounds_check;
 pub mod code_gen_help;
+pub mod const_eval;
 pub mod drop_specialization;
 pub mod inc_dec;
 pub mod ir;
@@ -19,5 +21,6 @@ pub mod layout_soa;
 pub mod low_level;
 pub mod reset_reuse;
 pub mod tail_recursion;
+pub mod unused_captures;
 
 pub mod debug;
@@ -0,0 +1,383 @@
+//! Drops captures that a closure can never use once its module is specialized.
+//!
+//! A closure captures every symbol its body refers to, but specialization doesn't compile all
+//! of that body: `when` branches found redundant by exhaustiveness checking are skipped, and so
+//! are inline `expect`s outside of the root package. A capture that is only referenced in such
+//! code is stored in the closure for nothing, and makes its lambda set layout bigger.
+//!
+//! This runs right after a module is solved, before any layout involving its closures exists,
+//! and before its types are exposed to other modules. A dropped capture is removed both from the
+//! closure's `captured_symbols` and from every lambda set in the module's `Subs` that mentions
+//! the closure, so closure construction, capture unpacking, and lambda set layouts all keep
+//! agreeing on what the closure captures.
+
+use roc_can::def::Def;
+use roc_can::expr::{ClosureData, Declarations, Expr};
+use roc_can::pattern::{DestructType, Pattern};
+use roc_collections::{VecMap, VecSet};
+use roc_module::symbol::Symbol;
+use roc_types::subs::{Content, LambdaSet, Subs, UnionLambdas, Variable};
+
+/// Removes the captures that can't be used after specialization from the closures in
+/// `declarations`, and from the lambda sets in `subs`.
+///
+/// `compile_expects` says whether inline `expect`s in this module are compiled, which is only the
+/// case in the root package.
+pub fn drop_unused_captures(
+    subs: &mut Subs,
+    declarations: &mut Declarations,
+    compile_expects: bool,
+) {
+    let mut env = Env {
+        subs,
+        compile_expects,
+        closure_captures: VecMap::default(),
+        kept_captures: VecMap::default(),
+    };
+
+    for function_def in declarations.function_bodies.iter_mut() {
+        for (_, _, loc_pattern) in function_def.value.arguments.iter_mut() {
+            env.pattern(&mut loc_pattern.value, &mut VecSet::default());
+        }
+    }
+
+    for destructure_def in declarations.destructs.iter_mut() {
+        env.pattern(
+            &mut destructure_def.loc_pattern.value,
+            &mut VecSet::default(),
+        );
+    }
+
+    for loc_expr in declarations.expressions.iter_mut() {
+        env.expr(&mut loc_expr.value, &mut VecSet::default());
+    }
+
+    let kept_captures = env.kept_captures;
+
+    if !kept_captures.is_empty() {
+        drop_from_lambda_sets(subs, &kept_captures);
+    }
+}
+
+struct Env<'a> {
+    subs: &'a Subs,
+    compile_expects: bool,
+    /// The remaining captures of each closure seen so far. Referring to a capturing closure by
+    /// name means constructing it there, which needs all of its captures.
+    closure_captures: VecMap<Symbol, Vec<Symbol>>,
+    /// For every closure that lost a capture, which of its original captures are kept.
+    kept_captures: VecMap<Symbol, Vec<bool>>,
+}
+
+impl<'a> Env<'a> {
+    fn use_symbol(&self, symbol: Symbol, used: &mut VecSet<Symbol>) {
+        used.insert(symbol);
+
+        if let Some(captures) = self.closure_captures.get(&symbol) {
+            used.extend(captures.iter().copied());
+        }
+    }
+
+    /// Walks the parts of `expr` that specialization compiles, recording in `used` every symbol
+    /// they need.
+    fn expr(&mut self, expr: &mut Expr, used: &mut VecSet<Symbol>) {
+        use Expr::*;
+
+        match expr {
+            Var(symbol, _) => self.use_symbol(*symbol, used),
+            RecordUpdate {
+                symbol, updates, ..
+            } => {
+                self.use_symbol(*symbol, used);
+
+                for (_, field) in updates.iter_mut() {
+                    self.expr(&mut field.loc_expr.value, used);
+                }
+            }
+            Closure(closure_data) => self.closure(closure_data, true, used),
+            LetNonRec(def, loc_body) => {
+                self.def(def, true, used);
+                self.expr(&mut loc_body.value, used);
+            }
+            LetRec(defs, loc_body, _) => {
+                // Mutually recursive closures share one capture set, which is left alone.
+                let prune = defs.len() == 1;
+
+                if !prune {
+                    for def in defs.iter() {
+                        if let Closure(ClosureData {
+                            name,
+                            captured_symbols,
+                            ..
+                        }) = &def.loc_expr.value
+                        {
+                            let captures = captured_symbols.iter().map(|(s, _)| *s).collect();
+                            self.closure_captures.insert(*name, captures);
+                        }
+                    }
+                }
+
+                for def in defs.iter_mut() {
+                    self.def(def, prune, used);
+                }
+
+                self.expr(&mut loc_body.value, used);
+            }
+            When {
+                loc_cond, branches, ..
+            } => {
+                self.expr(&mut loc_cond.value, used);
+
+                for branch in branches.iter_mut() {
+                    if branch.redundant.is_redundant(self.subs) {
+                        // Never compiled, see `from_can_when`.
+                        continue;
+                    }
+
+                    for branch_pattern in branch.patterns.iter_mut() {
+                        self.pattern(&mut branch_pattern.pattern.value, used);
+                    }
+
+                    if let Some(guard) = &mut branch.guard {
+                        self.expr(&mut guard.value, used);
+                    }
+
+                    self.expr(&mut branch.value.value, used);
+                }
+            }
+            If {
+                branches,
+                final_else,
+                ..
+            } => {
+                for (loc_cond, loc_then) in branches.iter_mut() {
+                    self.expr(&mut loc_cond.value, used);
+                    self.expr(&mut loc_then.value, used);
+                }
+
+                self.expr(&mut final_else.value, used);
+            }
+            Expect {
+                loc_condition,
+                loc_continuation,
+                ..
+            }
+            | ExpectFx {
+                loc_condition,
+                loc_continuation,
+                ..
+            } => {
+                if self.compile_expects {
+                    self.expr(&mut loc_condition.value, used);
+                }
+
+                self.expr(&mut loc_continuation.value, used);
+            }
+            Dbg {
+                loc_condition,
+                loc_continuation,
+                ..
+            } => {
+                self.expr(&mut loc_condition.value, used);
+                self.expr(&mut loc_continuation.value, used);
+            }
+            Call(boxed, args, _) => {
+                self.expr(&mut boxed.1.value, used);
+
+                for (_, loc_arg) in args.iter_mut() {
+                    self.expr(&mut loc_arg.value, used);
+                }
+            }
+            RunLowLevel { args, .. } | ForeignCall { args, .. } => {
+                for (_, arg) in args.iter_mut() {
+                    self.expr(arg, used);
+                }
+            }
+            List { loc_elems, .. } => {
+                for loc_elem in loc_elems.iter_mut() {
+                    self.expr(&mut loc_elem.value, used);
+                }
+            }
+            Record { fields, .. } => {
+                for (_, field) in fields.iter_mut() {
+                    self.expr(&mut field.loc_expr.value, used);
+                }
+            }
+            Tuple { elems, .. } => {
+                for (_, loc_elem) in elems.iter_mut() {
+                    self.expr(&mut loc_elem.value, used);
+                }
+            }
+            Tag { arguments, .. } => {
+                for (_, loc_arg) in arguments.iter_mut() {
+                    self.expr(&mut loc_arg.value, used);
+                }
+            }
+            OpaqueRef { argument, .. } => self.expr(&mut argument.1.value, used),
+            RecordAccess { loc_expr, .. } | TupleAccess { loc_expr, .. } => {
+                self.expr(&mut loc_expr.value, used)
+            }
+            Crash { msg, .. } => self.expr(&mut msg.value, used),
+
+            Num(..)
+            | Int(..)
+            | Float(..)
+            | Str(_)
+            | SingleQuote(..)
+            | IngestedFile(..)
+            | AbilityMember(..)
+            | EmptyRecord
+            | ZeroArgumentTag { .. }
+            | RecordAccessor(_)
+            | OpaqueWrapFunction(_)
+            | TypedHole(_)
+            | RuntimeError(_) => {}
+        }
+    }
+
+    fn def(&mut self, def: &mut Def, prune: bool, used: &mut VecSet<Symbol>) {
+        self.pattern(&mut def.loc_pattern.value, used);
+
+        match &mut def.loc_expr.value {
+            Expr::Closure(closure_data) => self.closure(closure_data, prune, used),
+            other => self.expr(other, used),
+        }
+    }
+
+    fn closure(&mut self, closure_data: &mut ClosureData, prune: bool, used: &mut VecSet<Symbol>) {
+        let ClosureData {
+            name,
+            captured_symbols,
+            arguments,
+            loc_body,
+            ..
+        } = closure_data;
+
+        let mut used_in_body = VecSet::default();
+
+        for (_, _, loc_pattern) in arguments.iter_mut() {
+            self.pattern(&mut loc_pattern.value, &mut used_in_body);
+        }
+
+        self.expr(&mut loc_body.value, &mut used_in_body);
+
+        if prune {
+            let kept: Vec<bool> = captured_symbols
+                .iter()
+                .map(|(symbol, _)| used_in_body.contains(symbol))
+                .collect();
+
+            if kept.contains(&false) {
+                let mut kept_iter = kept.iter();
+                captured_symbols.retain(|_| *kept_iter.next().unwrap());
+
+                self.kept_captures.insert(*name, kept);
+            }
+        }
+
+        let captures: Vec<Symbol> = captured_symbols.iter().map(|(s, _)| *s).collect();
+
+        used.extend(captures.iter().copied());
+        self.closure_captures.insert(*name, captures);
+    }
+
+    /// Patterns only need walking for the default values of optional record fields.
+    fn pattern(&mut self, pattern: &mut Pattern, used: &mut VecSet<Symbol>) {
+        use Pattern::*;
+
+        match pattern {
+            AppliedTag { arguments, .. } => {
+                for (_, loc_arg) in arguments.iter_mut() {
+                    self.pattern(&mut loc_arg.value, used);
+                }
+            }
+            UnwrappedOpaque { argument, .. } => self.pattern(&mut argument.1.value, used),
+            RecordDestructure { destructs, .. } => {
+                for loc_destruct in destructs.iter_mut() {
+                    match &mut loc_destruct.value.typ {
+                        DestructType::Required => {}
+                        DestructType::Optional(_, loc_expr) => self.expr(&mut loc_expr.value, used),
+                        DestructType::Guard(_, loc_pattern) => {
+                            self.pattern(&mut loc_pattern.value, used)
+                        }
+                    }
+                }
+            }
+            TupleDestructure { destructs, .. } => {
+                for loc_destruct in destructs.iter_mut() {
+                    self.pattern(&mut loc_destruct.value.typ.1.value, used);
+                }
+            }
+            List { patterns, .. } => {
+                for loc_pattern in patterns.patterns.iter_mut() {
+                    self.pattern(&mut loc_pattern.value, used);
+                }
+            }
+            As(subpattern, _) => self.pattern(&mut subpattern.value, used),
+
+            Identifier(_)
+            | NumLiteral(..)
+            | IntLiteral(..)
+            | FloatLiteral(..)
+            | StrLiteral(_)
+            | SingleQuote(..)
+            | Underscore
+            | Shadowed(..)
+            | MalformedPattern(..)
+            | UnsupportedPattern(_)
+            | OpaqueNotInScope(..)
+            | AbilityMemberSpecialization { .. } => {}
+        }
+    }
+}
+
+/// Rewrites every lambda set that mentions a closure in `kept_captures` to only have the kept
+/// captures. The captures of a closure are in the same order in its lambda set as in its
+/// `captured_symbols`.
+fn drop_from_lambda_sets(subs: &mut Subs, kept_captures: &VecMap<Symbol, Vec<bool>>) {
+    for index in 0..subs.len() {
+        // Safety: every index below `subs.len()` is a variable in `subs`.
+        let var = unsafe { Variable::from_index(index as u32) };
+
+        if subs.get_root_key_without_compacting(var) != var {
+            continue;
+        }
+
+        let lambda_set = match subs.get_content_without_compacting(var) {
+            Content::LambdaSet(lambda_set) => *lambda_set,
+            _ => continue,
+        };
+
+        let mentions_pruned = lambda_set
+            .solved
+            .iter_from_subs(subs)
+            .any(|(symbol, _)| kept_captures.contains_key(symbol));
+
+        if !mentions_pruned {
+            continue;
+        }
+
+        let labels: Vec<(Symbol, Vec<Variable>)> = lambda_set
+            .solved
+            .iter_from_subs(subs)
+            .map(|(symbol, captures)| match kept_captures.get(symbol) {
+                Some(kept) if kept.len() == captures.len() => {
+                    let captures = captures.iter().zip(kept).filter(|(_, kept)| **kept);
+
+                    (*symbol, captures.map(|(var, _)| *var).collect())
+                }
+                _ => (*symbol, captures.to_vec()),
+            })
+            .collect();
+
+        let solved = UnionLambdas::insert_into_subs(subs, labels);
+
+        subs.set_content(
+            var,
+            Content::LambdaSet(LambdaSet {
+                solved,
+                ..lambda_set
+            }),
+        );
+    }
+}
@@ -61,6 +61,7 @@ pub enum HeaderType<'a> {
 
         /// usually `pf`
         config_shorthand: &'a str,
+        threading: PlatformThreading,
     },
     Interface {
         name: ModuleName<'a>,
@@ -175,6 +176,7 @@ keywords! {
     RequiresKeyword => "requires",
     ProvidesKeyword => "provides",
     ToKeyword => "to",
+    ThreadingKeyword => "threading",
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -261,6 +263,27 @@ pub struct PlatformHeader<'a> {
     pub imports: KeywordItem<'a, ImportsKeyword, Collection<'a, Loc<Spaced<'a, ImportsEntry<'a>>>>>,
     pub provides:
         KeywordItem<'a, ProvidesKeyword, Collection<'a, Loc<Spaced<'a, ExposedName<'a>>>>>,
+    /// e.g. `threading singleThreaded`. Without it, the host may call from any thread.
+    pub threading: Option<KeywordItem<'a, ThreadingKeyword, Loc<PlatformThreading>>>,
+}
+
+/// Whether a platform's host calls into the Roc program from more than one thread.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PlatformThreading {
+    /// `singleThreaded`: the host only ever calls from one thread, so refcounts don't need
+    /// to be updated atomically.
+    SingleThreaded,
+    /// `multiThreaded`: the host may call from several threads at once. This is the default.
+    MultiThreaded,
+}
+
+impl PlatformThreading {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PlatformThreading::SingleThreaded => "singleThreaded",
+            PlatformThreading::MultiThreaded => "multiThreaded",
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
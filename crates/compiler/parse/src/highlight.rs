@@ -365,7 +365,7 @@ fn fast_forward_to(
     tokens.push(Loc::at(Region::between(start, state.pos()), Token::Error));
 }
 
-pub const HEADER_KEYWORDS: [&str; 14] = [
+pub const HEADER_KEYWORDS: [&str; 15] = [
     "interface",
     "app",
     "package",
@@ -380,6 +380,7 @@ pub const HEADER_KEYWORDS: [&str; 14] = [
     "requires",
     "provides",
     "to",
+    "threading",
 ];
 
 #[cfg(test)]
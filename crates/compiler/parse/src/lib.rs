@@ -17,6 +17,8 @@ pub mod module;
 pub mod number_literal;
 pub mod pattern;
 pub mod problems;
+pub mod ranges;
+pub mod recovery;
 pub mod state;
 pub mod string_literal;
 pub mod test_helpers;
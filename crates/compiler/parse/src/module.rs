@@ -4,14 +4,15 @@ use crate::header::{
     package_entry, package_name, AppHeader, ExposedName, ExposesKeyword, GeneratesKeyword,
     HostedHeader, ImportsEntry, ImportsKeyword, InterfaceHeader, Keyword, KeywordItem, ModuleName,
     PackageEntry, PackageHeader, PackagesKeyword, PlatformHeader, PlatformRequires,
-    ProvidesKeyword, ProvidesTo, RequiresKeyword, To, ToKeyword, TypedIdent, WithKeyword,
+    PlatformThreading, ProvidesKeyword, ProvidesTo, RequiresKeyword, ThreadingKeyword, To,
+    ToKeyword, TypedIdent, WithKeyword,
 };
 use crate::ident::{self, lowercase_ident, unqualified_ident, uppercase, UppercaseIdent};
 use crate::parser::Progress::{self, *};
 use crate::parser::{
     backtrackable, increment_min_indent, optional, reset_min_indent, specialize, word1, word2,
     EExposes, EGenerates, EGeneratesWith, EHeader, EImports, EPackages, EProvides, ERequires,
-    ETypedIdent, Parser, SourceError, SpaceProblem, SyntaxError,
+    EThreading, ETypedIdent, Parser, SourceError, SpaceProblem, SyntaxError,
 };
 use crate::state::State;
 use crate::string_literal::{self, parse_str_literal};
@@ -211,6 +212,7 @@ fn platform_header<'a>() -> impl Parser<'a, PlatformHeader<'a>, EHeader<'a>> {
         packages: specialize(EHeader::Packages, packages()),
         imports: specialize(EHeader::Imports, imports()),
         provides: specialize(EHeader::Provides, provides_exposed()),
+        threading: optional(specialize(EHeader::Threading, threading())),
     })
     .trace("platform_header")
 }
@@ -504,6 +506,29 @@ fn generates<'a>(
     })
 }
 
+#[inline(always)]
+fn threading<'a>(
+) -> impl Parser<'a, KeywordItem<'a, ThreadingKeyword, Loc<PlatformThreading>>, EThreading> {
+    use crate::parser::keyword_e;
+
+    record!(KeywordItem {
+        keyword: spaces_around_keyword(
+            ThreadingKeyword,
+            EThreading::Threading,
+            EThreading::IndentThreading,
+            EThreading::IndentMode
+        ),
+        item: loc!(one_of![
+            map!(keyword_e("singleThreaded", EThreading::Mode), |_| {
+                PlatformThreading::SingleThreaded
+            }),
+            map!(keyword_e("multiThreaded", EThreading::Mode), |_| {
+                PlatformThreading::MultiThreaded
+            }),
+        ])
+    })
+}
+
 #[inline(always)]
 fn generates_with<'a>() -> impl Parser<
     'a,
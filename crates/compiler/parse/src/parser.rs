@@ -87,6 +87,7 @@ impl_space_problem! {
     EGenerates,
     EGeneratesWith,
     EHeader<'a>,
+    EThreading,
     EIf<'a>,
     EImports,
     EInParens<'a>,
@@ -121,6 +122,7 @@ pub enum EHeader<'a> {
     Packages(EPackages<'a>, Position),
     Generates(EGenerates, Position),
     GeneratesWith(EGeneratesWith, Position),
+    Threading(EThreading, Position),
 
     Space(BadInputError, Position),
     Start(Position),
@@ -248,6 +250,15 @@ pub enum EGenerates {
     IndentTypeEnd(Position),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EThreading {
+    Threading(Position),
+    IndentThreading(Position),
+    IndentMode(Position),
+    Mode(Position),
+    Space(BadInputError, Position),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EGeneratesWith {
     Open(Position),
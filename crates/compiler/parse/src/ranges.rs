@@ -0,0 +1,454 @@
+//! The structure of a parsed module as editors use it: the nested regions that "expand
+//! selection" steps through, and the regions that can be folded away.
+//!
+//! Both only need the AST, so they work on modules that don't type check, and (through
+//! [crate::recovery]) on modules that don't even parse completely.
+use roc_region::all::{LineInfo, Loc, Position, Region};
+
+use crate::ast::{
+    AssignedField, Defs, Expr, Pattern, RecordBuilderField, TypeAnnotation, ValueDef, WhenBranch,
+};
+
+/// What a folding range folds away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoldKind {
+    /// A def, top-level or in an expression
+    Def,
+    /// The patterns, guard and body of a `when` branch
+    WhenBranch,
+    /// A list, record, tuple or record builder
+    Collection,
+    /// Consecutive lines of comments
+    Comment,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fold {
+    pub kind: FoldKind,
+    pub region: Region,
+}
+
+/// The regions of `defs` that span several lines of `src`, and so can be folded, ordered by
+/// where they start. `src` is the whole module, including its header.
+pub fn folding_ranges(src: &str, defs: &Defs<'_>) -> Vec<Fold> {
+    let line_info = LineInfo::new(src);
+    let mut folds = Vec::new();
+
+    walk_defs(defs, &mut |region, node| {
+        let kind = match node {
+            Node::Def => FoldKind::Def,
+            Node::WhenBranch => FoldKind::WhenBranch,
+            Node::Collection => FoldKind::Collection,
+            Node::Expr | Node::Leaf => return,
+        };
+
+        folds.push(Fold { kind, region });
+    });
+
+    folds.extend(comment_blocks(src).map(|region| Fold {
+        kind: FoldKind::Comment,
+        region,
+    }));
+
+    folds.retain(|fold| {
+        let lines = line_info.convert_region(fold.region);
+
+        lines.start.line < lines.end.line
+    });
+    folds.sort_by_key(|fold| fold.region.start().offset);
+
+    folds
+}
+
+/// The regions of `defs` around `position`, from the innermost to the outermost. Each one
+/// contains the one before it.
+pub fn selection_ranges(defs: &Defs<'_>, position: Position) -> Vec<Region> {
+    let mut around = Vec::new();
+
+    walk_defs(defs, &mut |region, _| {
+        if region.start() <= position && position <= region.end() {
+            around.push(region);
+        }
+    });
+
+    around.sort_by_key(|region| region.end().offset - region.start().offset);
+
+    let mut nested: Vec<Region> = Vec::with_capacity(around.len());
+
+    for region in around {
+        // nodes that only touch at `position` aren't nested
+        match nested.last() {
+            Some(inner) if *inner == region || !region.contains(inner) => {}
+            _ => nested.push(region),
+        }
+    }
+
+    nested
+}
+
+/// Runs of lines that only have a comment on them.
+fn comment_blocks(src: &str) -> impl Iterator<Item = Region> + '_ {
+    let mut blocks = Vec::new();
+    let mut current: Option<Region> = None;
+    let mut line_start = 0;
+
+    for line in src.split_inclusive('\n') {
+        let text = line.trim_end();
+        let indent = text.len() - text.trim_start().len();
+        let start = Position::new((line_start + indent) as u32);
+        let end = Position::new((line_start + text.len()) as u32);
+
+        if text.trim_start().starts_with('#') {
+            current = Some(match current {
+                Some(block) => Region::new(block.start(), end),
+                None => Region::new(start, end),
+            });
+        } else if let Some(block) = current.take() {
+            blocks.push(block);
+        }
+
+        line_start += line.len();
+    }
+
+    blocks.extend(current);
+
+    blocks.into_iter()
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Node {
+    Def,
+    WhenBranch,
+    Collection,
+    Expr,
+    /// Patterns, type annotations and field names, which we don't look inside of
+    Leaf,
+}
+
+fn walk_defs(defs: &Defs<'_>, visit: &mut impl FnMut(Region, Node)) {
+    for (def, region) in defs.defs().zip(defs.regions.iter()) {
+        visit(*region, Node::Def);
+
+        let value_def = match def {
+            Ok(_type_def) => continue,
+            Err(value_def) => value_def,
+        };
+
+        match value_def {
+            ValueDef::Annotation(pattern, annotation) => {
+                walk_pattern(pattern, visit);
+                walk_annotation(annotation, visit);
+            }
+            ValueDef::Body(pattern, body) => {
+                walk_pattern(pattern, visit);
+                walk_expr(body, visit);
+            }
+            ValueDef::AnnotatedBody {
+                ann_pattern,
+                ann_type,
+                body_pattern,
+                body_expr,
+                ..
+            } => {
+                walk_pattern(ann_pattern, visit);
+                walk_annotation(ann_type, visit);
+                walk_pattern(body_pattern, visit);
+                walk_expr(body_expr, visit);
+            }
+            ValueDef::Dbg { condition, .. }
+            | ValueDef::Expect { condition, .. }
+            | ValueDef::ExpectFx { condition, .. } => walk_expr(condition, visit),
+        }
+    }
+}
+
+fn walk_pattern(pattern: &Loc<Pattern<'_>>, visit: &mut impl FnMut(Region, Node)) {
+    visit(pattern.region, Node::Leaf);
+}
+
+fn walk_annotation(annotation: &Loc<TypeAnnotation<'_>>, visit: &mut impl FnMut(Region, Node)) {
+    visit(annotation.region, Node::Leaf);
+}
+
+fn walk_expr(expr: &Loc<Expr<'_>>, visit: &mut impl FnMut(Region, Node)) {
+    let mut value = &expr.value;
+
+    while let Expr::SpaceBefore(inner, _) | Expr::SpaceAfter(inner, _) = value {
+        value = inner;
+    }
+
+    let node = match value {
+        Expr::List(_)
+        | Expr::Record(_)
+        | Expr::RecordUpdate { .. }
+        | Expr::Tuple(_)
+        | Expr::RecordBuilder(_) => Node::Collection,
+        _ => Node::Expr,
+    };
+
+    visit(expr.region, node);
+    walk_expr_children(&expr.value, visit);
+}
+
+fn walk_expr_children(expr: &Expr<'_>, visit: &mut impl FnMut(Region, Node)) {
+    match expr {
+        Expr::RecordAccess(inner, _)
+        | Expr::TupleAccess(inner, _)
+        | Expr::SpaceBefore(inner, _)
+        | Expr::SpaceAfter(inner, _)
+        | Expr::ParensAround(inner) => walk_expr_children(inner, visit),
+        Expr::List(items) | Expr::Tuple(items) => {
+            for item in items.iter() {
+                walk_expr(item, visit);
+            }
+        }
+        Expr::RecordUpdate { update, fields } => {
+            walk_expr(update, visit);
+
+            for field in fields.iter() {
+                visit(field.region, Node::Expr);
+                walk_field(&field.value, visit);
+            }
+        }
+        Expr::Record(fields) => {
+            for field in fields.iter() {
+                visit(field.region, Node::Expr);
+                walk_field(&field.value, visit);
+            }
+        }
+        Expr::RecordBuilder(fields) => {
+            for field in fields.iter() {
+                visit(field.region, Node::Expr);
+                walk_builder_field(&field.value, visit);
+            }
+        }
+        Expr::IngestedFile(_, annotation) => walk_annotation(annotation, visit),
+        Expr::Closure(arguments, body) => {
+            for argument in arguments.iter() {
+                walk_pattern(argument, visit);
+            }
+
+            walk_expr(body, visit);
+        }
+        Expr::Defs(defs, final_expr) => {
+            walk_defs(defs, visit);
+            walk_expr(final_expr, visit);
+        }
+        Expr::Backpassing(patterns, call, continuation) => {
+            for pattern in patterns.iter() {
+                walk_pattern(pattern, visit);
+            }
+
+            walk_expr(call, visit);
+            walk_expr(continuation, visit);
+        }
+        Expr::Expect(condition, continuation) | Expr::Dbg(condition, continuation) => {
+            walk_expr(condition, visit);
+            walk_expr(continuation, visit);
+        }
+        Expr::Apply(function, arguments, _) => {
+            walk_expr(function, visit);
+
+            for argument in arguments.iter() {
+                walk_expr(argument, visit);
+            }
+        }
+        Expr::BinOps(operands, last) => {
+            for (operand, _) in operands.iter() {
+                walk_expr(operand, visit);
+            }
+
+            walk_expr(last, visit);
+        }
+        Expr::UnaryOp(operand, _) => walk_expr(operand, visit),
+        Expr::If(branches, final_else) => {
+            for (condition, then) in branches.iter() {
+                walk_expr(condition, visit);
+                walk_expr(then, visit);
+            }
+
+            walk_expr(final_else, visit);
+        }
+        Expr::When(condition, branches) => {
+            walk_expr(condition, visit);
+
+            for branch in branches.iter() {
+                walk_when_branch(branch, visit);
+            }
+        }
+        Expr::PrecedenceConflict(conflict) => walk_expr(conflict.expr, visit),
+        Expr::MultipleRecordBuilders(inner) | Expr::UnappliedRecordBuilder(inner) => {
+            walk_expr(inner, visit)
+        }
+        Expr::Float(_)
+        | Expr::Num(_)
+        | Expr::NonBase10Int { .. }
+        | Expr::Str(_)
+        | Expr::SingleQuote(_)
+        | Expr::AccessorFunction(_)
+        | Expr::Var { .. }
+        | Expr::Underscore(_)
+        | Expr::Crash
+        | Expr::Tag(_)
+        | Expr::OpaqueRef(_)
+        | Expr::MalformedIdent(_, _)
+        | Expr::MalformedClosure => {}
+    }
+}
+
+fn walk_when_branch(branch: &WhenBranch<'_>, visit: &mut impl FnMut(Region, Node)) {
+    let region = Region::across_all(
+        (branch.patterns.iter().map(|pattern| &pattern.region))
+            .chain(branch.guard.iter().map(|guard| &guard.region))
+            .chain(std::iter::once(&branch.value.region)),
+    );
+
+    visit(region, Node::WhenBranch);
+
+    for pattern in branch.patterns.iter() {
+        walk_pattern(pattern, visit);
+    }
+
+    if let Some(guard) = &branch.guard {
+        walk_expr(guard, visit);
+    }
+
+    walk_expr(&branch.value, visit);
+}
+
+fn walk_field(field: &AssignedField<'_, Expr<'_>>, visit: &mut impl FnMut(Region, Node)) {
+    match field {
+        AssignedField::RequiredValue(name, _, value)
+        | AssignedField::OptionalValue(name, _, value) => {
+            visit(name.region, Node::Leaf);
+            walk_expr(value, visit);
+        }
+        AssignedField::LabelOnly(name) => visit(name.region, Node::Leaf),
+        AssignedField::SpaceBefore(inner, _) | AssignedField::SpaceAfter(inner, _) => {
+            walk_field(inner, visit)
+        }
+        AssignedField::Malformed(_) => {}
+    }
+}
+
+fn walk_builder_field(field: &RecordBuilderField<'_>, visit: &mut impl FnMut(Region, Node)) {
+    match field {
+        RecordBuilderField::Value(name, _, value)
+        | RecordBuilderField::ApplyValue(name, _, _, value) => {
+            visit(name.region, Node::Leaf);
+            walk_expr(value, visit);
+        }
+        RecordBuilderField::LabelOnly(name) => visit(name.region, Node::Leaf),
+        RecordBuilderField::SpaceBefore(inner, _) | RecordBuilderField::SpaceAfter(inner, _) => {
+            walk_builder_field(inner, visit)
+        }
+        RecordBuilderField::Malformed(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module::parse_header;
+    use crate::recovery::module_defs_recovering;
+    use crate::state::State;
+    use bumpalo::Bump;
+
+    fn parse<'a>(arena: &'a Bump, src: &'a str) -> Defs<'a> {
+        let (_, state) = parse_header(arena, State::new(src.as_bytes())).unwrap();
+
+        module_defs_recovering(arena, state).defs
+    }
+
+    fn text(src: &str, region: Region) -> &str {
+        &src[region.start().offset as usize..region.end().offset as usize]
+    }
+
+    #[test]
+    fn folds_multiline_defs_collections_and_comments() {
+        let arena = Bump::new();
+        let src = indoc::indoc!(
+            r#"
+            interface Foo exposes [] imports []
+
+            # one
+            # two
+            short = 1
+
+            long =
+                [
+                    1,
+                    2,
+                ]
+            "#
+        );
+        let defs = parse(&arena, src);
+
+        let folds: Vec<(FoldKind, &str)> = folding_ranges(src, &defs)
+            .into_iter()
+            .map(|fold| (fold.kind, text(src, fold.region)))
+            .collect();
+
+        assert_eq!(
+            folds,
+            vec![
+                (FoldKind::Comment, "# one\n# two"),
+                (
+                    FoldKind::Def,
+                    "long =\n    [\n        1,\n        2,\n    ]"
+                ),
+                (FoldKind::Collection, "[\n        1,\n        2,\n    ]"),
+            ]
+        );
+    }
+
+    #[test]
+    fn folds_when_branches() {
+        let arena = Bump::new();
+        let src = indoc::indoc!(
+            r#"
+            interface Foo exposes [] imports []
+
+            f = \x ->
+                when x is
+                    A ->
+                        1
+
+                    B -> 2
+            "#
+        );
+        let defs = parse(&arena, src);
+
+        let branches: Vec<&str> = folding_ranges(src, &defs)
+            .into_iter()
+            .filter(|fold| fold.kind == FoldKind::WhenBranch)
+            .map(|fold| text(src, fold.region))
+            .collect();
+
+        assert_eq!(branches, vec!["A ->\n            1"]);
+    }
+
+    #[test]
+    fn selection_grows_from_the_innermost_node() {
+        let arena = Bump::new();
+        let src = "interface Foo exposes [] imports []\n\nx = f [1, 2 + 3]\n";
+        let defs = parse(&arena, src);
+        let position = Position::new(src.find('3').unwrap() as u32);
+
+        let ranges: Vec<&str> = selection_ranges(&defs, position)
+            .into_iter()
+            .map(|region| text(src, region))
+            .collect();
+
+        assert_eq!(
+            ranges,
+            vec![
+                "3",
+                "2 + 3",
+                "[1, 2 + 3]",
+                "f [1, 2 + 3]",
+                "x = f [1, 2 + 3]"
+            ]
+        );
+    }
+}
@@ -0,0 +1,334 @@
+//! Parsing that keeps going after syntax errors, for tools like the language server and the
+//! formatter, which are most useful on exactly the files that don't parse yet.
+//!
+//! Recovery happens at two levels. A top-level def that doesn't parse is left out of the
+//! result; but if leaving out a single element of one of its collections (a list, record,
+//! tuple...) is enough to make the def parse, only that element is left out. Either way, the
+//! part that was left out becomes an error node, so tools know where the file is broken.
+//!
+//! Parts are left out by overwriting them with spaces (keeping newlines), so the regions of
+//! everything else in the AST still point into the original source.
+use std::ops::Range;
+
+use bumpalo::Bump;
+use roc_region::all::{Position, Region};
+
+use crate::{
+    ast::Defs,
+    module::module_defs,
+    parser::{Parser, SyntaxError},
+    state::State,
+};
+
+/// A part of the source that didn't parse, and was left out of the AST.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorNode<'a> {
+    pub region: Region,
+    pub error: SyntaxError<'a>,
+}
+
+/// The top-level defs that parsed, along with error nodes for the parts that didn't, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialDefs<'a> {
+    pub defs: Defs<'a>,
+    pub errors: Vec<ErrorNode<'a>>,
+}
+
+/// Like [module_defs], but never fails. `state` is the state after the module header.
+pub fn module_defs_recovering<'a>(arena: &'a Bump, state: State<'a>) -> PartialDefs<'a> {
+    if let Ok((_, defs, _)) = module_defs().parse(arena, state.clone(), 0) {
+        return PartialDefs {
+            defs,
+            errors: Vec::new(),
+        };
+    }
+
+    let src = state.original_bytes();
+    let start = state.pos().offset as usize;
+    let mut working = src.to_vec();
+    let mut errors = Vec::new();
+
+    for chunk in top_level_chunks(src, start) {
+        if parses(arena, &src[chunk.clone()]) {
+            continue;
+        }
+
+        let error = chunk_error(arena, &working, chunk.clone());
+        let left_out = match recoverable_element(arena, &src[chunk.clone()]) {
+            Some(element) => chunk.start + element.start..chunk.start + element.end,
+            None => chunk,
+        };
+
+        blank(&mut working[left_out.clone()]);
+        errors.push(ErrorNode {
+            region: trimmed_region(src, left_out),
+            error,
+        });
+    }
+
+    // The header is already parsed; blanking it keeps the positions of everything after it.
+    let working = arena.alloc_slice_copy(&working);
+    blank(&mut working[..start]);
+
+    match module_defs().parse(arena, State::new(working), 0) {
+        Ok((_, defs, _)) => PartialDefs { defs, errors },
+        Err((_, error)) => {
+            // Leaving out the broken defs wasn't enough, so give up on the whole file.
+            errors.clear();
+            errors.push(ErrorNode {
+                region: trimmed_region(src, start..src.len()),
+                error,
+            });
+
+            PartialDefs {
+                defs: Defs::default(),
+                errors,
+            }
+        }
+    }
+}
+
+fn parses(arena: &Bump, src: &[u8]) -> bool {
+    let src = arena.alloc_slice_copy(src);
+
+    module_defs().parse(arena, State::new(src), 0).is_ok()
+}
+
+/// Parses the chunk where it is in the file, so that the positions in the error point into
+/// the file, rather than into the chunk.
+fn chunk_error<'a>(arena: &'a Bump, working: &[u8], chunk: Range<usize>) -> SyntaxError<'a> {
+    let src = arena.alloc_slice_copy(&working[..chunk.end]);
+    blank(&mut src[..chunk.start]);
+
+    match module_defs().parse(arena, State::new(src), 0) {
+        Err((_, error)) => error,
+        // The chunk only fails to parse together with the ones before it.
+        Ok(_) => SyntaxError::Unexpected(Region::new(
+            Position::new(chunk.start as u32),
+            Position::new(chunk.end as u32),
+        )),
+    }
+}
+
+/// Splits everything after the header into top-level defs, by looking for lines that start
+/// with a letter or `_`. Comment lines right above such a line belong to the def below them.
+fn top_level_chunks(src: &[u8], start: usize) -> Vec<Range<usize>> {
+    let mut def_starts = vec![start];
+    let mut comments_start = None;
+    let mut line = start;
+
+    while line < src.len() {
+        if line == 0 || src[line - 1] == b'\n' {
+            match src[line] {
+                b'#' => {
+                    comments_start.get_or_insert(line);
+                }
+                b if b.is_ascii_alphabetic() || b == b'_' => {
+                    def_starts.push(comments_start.take().unwrap_or(line));
+                }
+                _ => comments_start = None,
+            }
+        }
+
+        line = match src[line..].iter().position(|b| *b == b'\n') {
+            Some(newline) => line + newline + 1,
+            None => src.len(),
+        };
+    }
+
+    def_starts.dedup();
+    def_starts.push(src.len());
+
+    def_starts
+        .windows(2)
+        .map(|bounds| bounds[0]..bounds[1])
+        .filter(|chunk| !chunk.is_empty())
+        .collect()
+}
+
+/// The smallest collection element whose removal makes `chunk` parse, as a range that also
+/// covers one of the commas next to the element.
+fn recoverable_element(arena: &Bump, chunk: &[u8]) -> Option<Range<usize>> {
+    let mut elements = collection_elements(chunk);
+    elements.sort_by_key(|element| element.len());
+
+    elements.into_iter().find(|element| {
+        let mut candidate = chunk.to_vec();
+        blank(&mut candidate[element.clone()]);
+
+        parses(arena, &candidate)
+    })
+}
+
+/// The elements of every collection in `src`. Each includes the comma after it, or if it's
+/// the last element, the comma before it, so that removing it leaves a valid collection.
+fn collection_elements(src: &[u8]) -> Vec<Range<usize>> {
+    struct Collection {
+        open: usize,
+        last_comma: Option<usize>,
+    }
+
+    let mut open: Vec<Collection> = Vec::new();
+    let mut elements = Vec::new();
+    let mut i = 0;
+
+    while i < src.len() {
+        match src[i] {
+            b'"' if src[i..].starts_with(b"\"\"\"") => {
+                i = find_from(src, i + 3, b"\"\"\"").map_or(src.len(), |end| end + 3);
+                continue;
+            }
+            b'"' => {
+                i += 1;
+                while i < src.len() && src[i] != b'"' && src[i] != b'\n' {
+                    i += if src[i] == b'\\' { 2 } else { 1 };
+                }
+            }
+            b'\'' => {
+                i += 1;
+                while i < src.len() && src[i] != b'\'' && src[i] != b'\n' {
+                    i += if src[i] == b'\\' { 2 } else { 1 };
+                }
+            }
+            b'#' => {
+                i = find_from(src, i, b"\n").unwrap_or(src.len());
+                continue;
+            }
+            b'[' | b'{' | b'(' => open.push(Collection {
+                open: i,
+                last_comma: None,
+            }),
+            b',' => {
+                if let Some(collection) = open.last_mut() {
+                    let element_start = collection.last_comma.unwrap_or(collection.open) + 1;
+
+                    elements.push(element_start..i + 1);
+                    collection.last_comma = Some(i);
+                }
+            }
+            b']' | b'}' | b')' => {
+                if let Some(collection) = open.pop() {
+                    let element = match collection.last_comma {
+                        Some(comma) => Some(comma..i),
+                        // Parens without commas group an expression, rather than being a tuple.
+                        None if src[collection.open] == b'(' => None,
+                        None => Some(collection.open + 1..i),
+                    };
+                    let element = element.filter(|element| {
+                        src[element.clone()]
+                            .iter()
+                            .any(|b| !b.is_ascii_whitespace() && *b != b',')
+                    });
+
+                    elements.extend(element);
+                }
+            }
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    elements
+}
+
+fn find_from(src: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    src.get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|index| from + index)
+}
+
+/// Overwrites everything but line breaks with spaces.
+fn blank(bytes: &mut [u8]) {
+    for byte in bytes {
+        if *byte != b'\n' && *byte != b'\r' {
+            *byte = b' ';
+        }
+    }
+}
+
+/// The region of `range`, without the whitespace at either end.
+fn trimmed_region(src: &[u8], range: Range<usize>) -> Region {
+    let text = &src[range.clone()];
+    let leading = text.iter().take_while(|b| b.is_ascii_whitespace()).count();
+    let trailing = (text[leading..].iter().rev())
+        .take_while(|b| b.is_ascii_whitespace())
+        .count();
+
+    Region::new(
+        Position::new((range.start + leading) as u32),
+        Position::new((range.end - trailing) as u32),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module::parse_header;
+
+    fn recover<'a>(arena: &'a Bump, src: &'a str) -> PartialDefs<'a> {
+        let (_, state) = parse_header(arena, State::new(src.as_bytes())).unwrap();
+
+        module_defs_recovering(arena, state)
+    }
+
+    fn error_texts<'a>(src: &'a str, partial: &PartialDefs) -> Vec<&'a str> {
+        (partial.errors.iter())
+            .map(|node| {
+                &src[node.region.start().offset as usize..node.region.end().offset as usize]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn valid_module_has_no_errors() {
+        let arena = Bump::new();
+        let src = "interface Foo exposes [] imports []\n\na = 1\n\nb = 2\n";
+        let partial = recover(&arena, src);
+
+        assert_eq!(partial.defs.len(), 2);
+        assert!(partial.errors.is_empty());
+    }
+
+    #[test]
+    fn broken_def_is_left_out() {
+        let arena = Bump::new();
+        let src = "interface Foo exposes [] imports []\n\na = 1\n\nb = \\ ->\n\nc = 3\n";
+        let partial = recover(&arena, src);
+
+        assert_eq!(partial.defs.len(), 2);
+        assert_eq!(error_texts(src, &partial), vec!["b = \\ ->"]);
+
+        // the regions of the defs after the broken one still point into the source
+        let c_region = partial.defs.regions[1];
+        assert_eq!(&src[c_region.start().offset as usize..], "c = 3\n");
+    }
+
+    #[test]
+    fn broken_list_element_is_left_out() {
+        let arena = Bump::new();
+        let src = "interface Foo exposes [] imports []\n\na = [1, (2 +), 3]\n\nb = 2\n";
+        let partial = recover(&arena, src);
+
+        assert_eq!(partial.defs.len(), 2);
+        assert_eq!(error_texts(src, &partial), vec!["(2 +),"]);
+    }
+
+    #[test]
+    fn broken_last_record_field_is_left_out() {
+        let arena = Bump::new();
+        let src = "interface Foo exposes [] imports []\n\na = { x: 1, y: }\n";
+        let partial = recover(&arena, src);
+
+        assert_eq!(partial.defs.len(), 1);
+        assert_eq!(error_texts(src, &partial), vec![", y:"]);
+    }
+
+    #[test]
+    fn collection_elements_skip_strings_and_comments() {
+        let src = b"[\"a,b\", # c, d\n x]";
+
+        assert_eq!(collection_elements(src), vec![1..7, 6..17]);
+    }
+}
@@ -207,6 +207,12 @@ pub enum Problem {
     OverAppliedCrash {
         region: Region,
     },
+    /// An `expect` whose condition is known at compile time, reported when
+    /// `--deny dead-expect` is passed.
+    DeadExpect {
+        region: Region,
+        always_passes: bool,
+    },
     FileProblem {
         filename: PathBuf,
         error: io::ErrorKind,
@@ -275,6 +281,7 @@ impl Problem {
             // injecting a crash message
             Problem::UnappliedCrash { .. } => RuntimeError,
             Problem::OverAppliedCrash { .. } => RuntimeError,
+            Problem::DeadExpect { .. } => RuntimeError,
             Problem::DefsOnlyUsedInRecursion(_, _) => Warning,
             Problem::FileProblem { .. } => Fatal,
         }
@@ -416,6 +423,7 @@ impl Problem {
             | Problem::UnnecessaryOutputWildcard { region }
             | Problem::OverAppliedCrash { region }
             | Problem::UnappliedCrash { region }
+            | Problem::DeadExpect { region, .. }
             | Problem::DefsOnlyUsedInRecursion(_, region) => Some(*region),
             Problem::RuntimeError(RuntimeError::CircularDef(cycle_entries))
             | Problem::BadRecursion(cycle_entries) => {
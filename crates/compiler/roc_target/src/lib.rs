@@ -10,6 +10,9 @@ pub enum OperatingSystem {
     Windows,
     Unix,
     Wasi,
+    /// Bare metal, e.g. a microcontroller. The host provides everything the builtins need,
+    /// and there is no OS to link against.
+    Freestanding,
 }
 
 impl OperatingSystem {
@@ -21,6 +24,7 @@ impl OperatingSystem {
             target_lexicon::OperatingSystem::MacOSX { .. } => Some(OperatingSystem::Unix),
             target_lexicon::OperatingSystem::Darwin => Some(OperatingSystem::Unix),
             target_lexicon::OperatingSystem::Unknown => Some(OperatingSystem::Unix),
+            target_lexicon::OperatingSystem::None_ => Some(OperatingSystem::Freestanding),
             _ => None,
         }
     }
@@ -30,6 +34,7 @@ impl OperatingSystem {
             OperatingSystem::Windows => "obj",
             OperatingSystem::Unix => "o",
             OperatingSystem::Wasi => "wasm",
+            OperatingSystem::Freestanding => "o",
         }
     }
 
@@ -38,6 +43,7 @@ impl OperatingSystem {
             OperatingSystem::Windows => Some("exe"),
             OperatingSystem::Unix => None,
             OperatingSystem::Wasi => Some("wasm"),
+            OperatingSystem::Freestanding => None,
         }
     }
 }
@@ -126,6 +132,7 @@ pub enum PtrWidth {
 pub enum Architecture {
     Aarch32,
     Aarch64,
+    Riscv64,
     Wasm32,
     X86_32,
     X86_64,
@@ -136,7 +143,7 @@ impl Architecture {
         use Architecture::*;
 
         match self {
-            X86_64 | Aarch64 => PtrWidth::Bytes8,
+            X86_64 | Aarch64 | Riscv64 => PtrWidth::Bytes8,
             X86_32 | Aarch32 | Wasm32 => PtrWidth::Bytes4,
         }
     }
@@ -153,6 +160,7 @@ impl From<target_lexicon::Architecture> for Architecture {
             target_lexicon::Architecture::X86_32(_) => Architecture::X86_32,
             target_lexicon::Architecture::Aarch64(_) => Architecture::Aarch64,
             target_lexicon::Architecture::Arm(_) => Architecture::Aarch32,
+            target_lexicon::Architecture::Riscv64(_) => Architecture::Riscv64,
             target_lexicon::Architecture::Wasm32 => Architecture::Wasm32,
             _ => unreachable!("unsupported architecture"),
         }
@@ -162,11 +170,13 @@ impl From<target_lexicon::Architecture> for Architecture {
 pub const WASM_TARGET_STR: &str = "wasm32";
 pub const LINUX_X86_64_TARGET_STR: &str = "linux-x86_64";
 pub const LINUX_ARM64_TARGET_STR: &str = "linux-arm64";
+pub const LINUX_RISCV64_TARGET_STR: &str = "linux-riscv64";
 pub const MACOS_ARM64_TARGET_STR: &str = "macos-arm64";
 pub const MACOS_X86_64_TARGET_STR: &str = "macos-x86_64";
 pub const WINDOWS_X86_64_TARGET_STR: &str = "windows-x86_64";
 pub const WINDOWS_X86_32_TARGET_STR: &str = "windows-x86_32";
 pub const WIDNOWS_ARM64_TARGET_STR: &str = "windows-arm64";
+pub const THUMBV7EM_TARGET_STR: &str = "thumbv7em";
 
 pub fn get_target_triple_str(target: &target_lexicon::Triple) -> Option<&'static str> {
     match target {
@@ -184,6 +194,11 @@ pub fn get_target_triple_str(target: &target_lexicon::Triple) -> Option<&'static
             architecture: target_lexicon::Architecture::Aarch64(_),
             ..
         } => Some(LINUX_ARM64_TARGET_STR),
+        target_lexicon::Triple {
+            operating_system: target_lexicon::OperatingSystem::Linux,
+            architecture: target_lexicon::Architecture::Riscv64(_),
+            ..
+        } => Some(LINUX_RISCV64_TARGET_STR),
         target_lexicon::Triple {
             operating_system: target_lexicon::OperatingSystem::Darwin,
             architecture: target_lexicon::Architecture::Aarch64(_),
@@ -209,6 +224,12 @@ pub fn get_target_triple_str(target: &target_lexicon::Triple) -> Option<&'static
             architecture: target_lexicon::Architecture::Aarch64(_),
             ..
         } => Some(WIDNOWS_ARM64_TARGET_STR),
+        target_lexicon::Triple {
+            operating_system: target_lexicon::OperatingSystem::None_,
+            architecture:
+                target_lexicon::Architecture::Arm(target_lexicon::ArmArchitecture::Thumbv7em),
+            ..
+        } => Some(THUMBV7EM_TARGET_STR),
         _ => None,
     }
 }
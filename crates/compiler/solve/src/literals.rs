@@ -0,0 +1,97 @@
+//! Checks for number literals that change their value when they're stored in the type
+//! that type inference picked for them. Integer literals that don't fit their type are
+//! already type mismatches, but F32 can store any number in its range, just not exactly.
+
+use roc_can::expr::{Declarations, Expr, IntValue};
+use roc_can::pattern::Pattern;
+use roc_can::traverse::{walk_expr, walk_pattern, Visitor};
+use roc_module::symbol::Symbol;
+use roc_region::all::Region;
+use roc_solve_problem::TypeError;
+use roc_types::subs::{Content, Subs, Variable};
+
+/// Finds the literals that became F32s, but can't be stored as one without overflowing or
+/// losing digits, like `3.14159265358979` or `16777217` (which becomes `16777216`).
+pub fn lossy_f32_literals(decls: &Declarations, subs: &Subs) -> Vec<TypeError> {
+    let mut visitor = LossyF32Literals {
+        subs,
+        problems: Vec::new(),
+    };
+
+    visitor.visit_decls(decls);
+
+    visitor.problems
+}
+
+struct LossyF32Literals<'a> {
+    subs: &'a Subs,
+    problems: Vec<TypeError>,
+}
+
+impl LossyF32Literals<'_> {
+    fn check(&mut self, var: Variable, literal: &str, value: f64, region: Region) {
+        if !is_f32(self.subs, var) {
+            return;
+        }
+
+        let stored_as = value as f32;
+
+        // F32 prints the shortest number that would be stored the same way, so if that's
+        // a different number than the literal, some of the literal's digits got lost.
+        let round_trips = stored_as.to_string().parse::<f64>() == Ok(value);
+
+        if !round_trips && !value.is_nan() {
+            self.problems.push(TypeError::LossyF32Literal {
+                region,
+                literal: literal.into(),
+                stored_as,
+            });
+        }
+    }
+}
+
+impl Visitor for LossyF32Literals<'_> {
+    fn visit_expr(&mut self, expr: &Expr, region: Region, var: Variable) {
+        match expr {
+            Expr::Num(num_var, literal, value, _) => {
+                self.check(*num_var, literal, int_to_f64(value), region)
+            }
+            Expr::Float(num_var, _, literal, value, _) => {
+                self.check(*num_var, literal, *value, region)
+            }
+            _ => {}
+        }
+
+        walk_expr(self, expr, var);
+    }
+
+    fn visit_pattern(&mut self, pattern: &Pattern, region: Region, _opt_var: Option<Variable>) {
+        match pattern {
+            Pattern::NumLiteral(num_var, literal, value, _) => {
+                self.check(*num_var, literal, int_to_f64(value), region)
+            }
+            Pattern::FloatLiteral(num_var, _, literal, value, _) => {
+                self.check(*num_var, literal, *value, region)
+            }
+            _ => {}
+        }
+
+        walk_pattern(self, pattern);
+    }
+}
+
+fn int_to_f64(value: &IntValue) -> f64 {
+    match value {
+        IntValue::I128(bytes) => i128::from_ne_bytes(*bytes) as f64,
+        IntValue::U128(bytes) => u128::from_ne_bytes(*bytes) as f64,
+    }
+}
+
+/// Whether this is `F32`, or some other alias of `Num (FloatingPoint Binary32)`.
+fn is_f32(subs: &Subs, var: Variable) -> bool {
+    match subs.get_content_without_compacting(var) {
+        Content::Alias(Symbol::NUM_BINARY32, ..) => true,
+        Content::Alias(_, _, real, _) => is_f32(subs, *real),
+        _ => false,
+    }
+}
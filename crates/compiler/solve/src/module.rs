@@ -72,6 +72,9 @@ pub struct SolveConfig<'a> {
     /// Needed during solving to resolve lambda sets from derived implementations that escape into
     /// the user module.
     pub derived_module: SharedDerivedModule,
+    /// Stop solving once this many errors have been found, not counting follow-on errors
+    /// (see [TypeError::is_follow_on]). The rest of the module is then left unchecked.
+    pub max_errors: Option<usize>,
 }
 
 pub struct SolveOutput {
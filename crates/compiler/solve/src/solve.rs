@@ -22,6 +22,7 @@ use roc_debug_flags::ROC_VERIFY_RIGID_LET_GENERALIZED;
 use roc_error_macros::internal_error;
 use roc_module::symbol::Symbol;
 use roc_problem::can::CycleEntry;
+use roc_problem::Severity;
 use roc_region::all::Loc;
 use roc_solve_problem::TypeError;
 use roc_types::subs::{
@@ -122,6 +123,7 @@ fn run_in_place(
         pending_derives,
         exposed_by_module,
         derived_module,
+        max_errors,
     } = config;
 
     let mut pools = Pools::default();
@@ -175,6 +177,7 @@ fn run_in_place(
         abilities_store,
         &mut obligation_cache,
         &mut awaiting_specializations,
+        max_errors,
     );
 
     state.scope
@@ -228,6 +231,7 @@ fn solve(
     abilities_store: &mut AbilitiesStore,
     obligation_cache: &mut ObligationCache,
     awaiting_specializations: &mut AwaitingSpecializations,
+    max_errors: Option<usize>,
 ) -> State {
     let initial = Work::Constraint {
         scope: &Scope::default(),
@@ -237,7 +241,26 @@ fn solve(
 
     let mut stack = vec![initial];
 
+    // How many of the problems found so far have been looked at, and how many of those were not
+    // follow-on errors; only the latter count against `max_errors`.
+    let mut problems_seen = 0;
+    let mut root_errors = 0;
+
     while let Some(work_item) = stack.pop() {
+        if let Some(max_errors) = max_errors {
+            root_errors += problems[problems_seen..]
+                .iter()
+                .filter(|problem| {
+                    problem.severity() != Severity::Warning && !problem.is_follow_on()
+                })
+                .count();
+            problems_seen = problems.len();
+
+            if root_errors >= max_errors {
+                break;
+            }
+        }
+
         let (scope, rank, constraint) = match work_item {
             Work::Constraint {
                 scope,
@@ -33,6 +33,13 @@ pub enum TypeError {
     },
     IngestedFileBadUtf8(Box<PathBuf>, Utf8Error),
     IngestedFileUnsupportedType(Box<PathBuf>, ErrorType),
+    /// A number literal whose type is F32, but whose value changes when it's stored as one.
+    LossyF32Literal {
+        region: Region,
+        literal: Box<str>,
+        /// What the literal actually becomes; infinite if it's out of range.
+        stored_as: f32,
+    },
 }
 
 impl TypeError {
@@ -54,6 +61,73 @@ impl TypeError {
             TypeError::WrongSpecialization { .. } => RuntimeError,
             TypeError::IngestedFileBadUtf8(..) => Fatal,
             TypeError::IngestedFileUnsupportedType(..) => Fatal,
+            // Overflowing turns the number into infinity, but rounding it to fewer digits
+            // is often what was intended.
+            TypeError::LossyF32Literal { stored_as, .. } => {
+                if stored_as.is_infinite() {
+                    RuntimeError
+                } else {
+                    Warning
+                }
+            }
+        }
+    }
+
+    /// Returns the Region this problem is about, if it has one.
+    /// For problems involving several regions, this is the one an editor should highlight.
+    pub fn region(&self) -> Option<Region> {
+        match self {
+            TypeError::BadExpr(region, ..)
+            | TypeError::BadPattern(region, ..)
+            | TypeError::CircularType(region, ..)
+            | TypeError::BadExprMissingAbility(region, ..)
+            | TypeError::BadPatternMissingAbility(region, ..)
+            | TypeError::StructuralSpecialization { region, .. }
+            | TypeError::WrongSpecialization { region, .. }
+            | TypeError::LossyF32Literal { region, .. } => Some(*region),
+            TypeError::CircularDef(entries) => entries.first().map(|entry| entry.symbol_region),
+            TypeError::UnfulfilledAbility(Unfulfilled::OpaqueUnderivable {
+                derive_region, ..
+            }) => Some(*derive_region),
+            TypeError::Exhaustive(roc_exhaustive::Error::Incomplete(region, ..))
+            | TypeError::Exhaustive(roc_exhaustive::Error::Redundant {
+                branch_region: region,
+                ..
+            })
+            | TypeError::Exhaustive(roc_exhaustive::Error::Unmatchable {
+                branch_region: region,
+                ..
+            }) => Some(*region),
+            TypeError::UnexposedLookup(_)
+            | TypeError::UnfulfilledAbility(_)
+            | TypeError::IngestedFileBadUtf8(..)
+            | TypeError::IngestedFileUnsupportedType(..) => None,
+        }
+    }
+
+    /// Whether this problem is a follow-on of an earlier one: a mismatch where one of the types
+    /// already contains an error, because a variable in it failed to unify before.
+    /// Fixing the earlier problem usually makes this one go away, or at least changes it.
+    pub fn is_follow_on(&self) -> bool {
+        match self {
+            TypeError::BadExpr(_, _, found, expected) => {
+                found.contains_error() || expected.get_type_ref().contains_error()
+            }
+            TypeError::BadPattern(_, _, found, expected) => {
+                found.contains_error() || expected.get_type_ref().contains_error()
+            }
+            TypeError::BadExprMissingAbility(_, _, typ, _)
+            | TypeError::BadPatternMissingAbility(_, _, typ, _) => typ.contains_error(),
+            TypeError::CircularType(..)
+            | TypeError::CircularDef(_)
+            | TypeError::UnexposedLookup(_)
+            | TypeError::UnfulfilledAbility(_)
+            | TypeError::Exhaustive(_)
+            | TypeError::StructuralSpecialization { .. }
+            | TypeError::WrongSpecialization { .. }
+            | TypeError::IngestedFileBadUtf8(..)
+            | TypeError::IngestedFileUnsupportedType(..)
+            | TypeError::LossyF32Literal { .. } => false,
         }
     }
 }
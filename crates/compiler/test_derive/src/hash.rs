@@ -11,7 +11,7 @@ use crate::{
 };
 use insta::assert_snapshot;
 use roc_module::symbol::Symbol;
-use roc_types::subs::Variable;
+use roc_types::{num::IntLitWidth, subs::Variable};
 
 use roc_derive_key::{hash::FlatHashKey, DeriveBuiltin::Hash, DeriveError, DeriveKey};
 
@@ -27,6 +27,8 @@ test_key_eq! {
         v!({ c: v!(U8), a: v!(U8), b: v!(U8), })
     explicit_empty_record_and_implicit_empty_record:
         v!(EMPTY_RECORD), v!({})
+    same_int_record_any_order:
+        v!({ a: v!(U8), b: v!(I16), }), v!({ b: v!(I16), a: v!(U8), })
 
     same_tuple:
         v!((v!(U8), v!(U16),)), v!((v!(U8), v!(U16),))
@@ -55,6 +57,10 @@ test_key_neq! {
         v!({ a: v!(U8), }), v!({ b: v!(U8), })
     record_empty_vs_nonempty:
         v!(EMPTY_RECORD), v!({ a: v!(U8), })
+    int_record_vs_record:
+        v!({ a: v!(U8), b: v!(U8), }), v!({ a: v!(U8), b: v!(STR), })
+    int_records_diff_types:
+        v!({ a: v!(U8), b: v!(U8), }), v!({ a: v!(U8), b: v!(I8), })
 
     different_tuple_arities:
         v!((v!(U8), v!(U16),)), v!((v!(U8), v!(U16), v!(U32),))
@@ -116,6 +122,32 @@ fn derivable_record_with_record_ext() {
     );
 }
 
+#[test]
+fn derivable_int_record() {
+    check_derivable(
+        Hash,
+        v!({ b: v!(I16), a: v!(U32), }),
+        DeriveKey::Hash(FlatHashKey::IntRecord(vec![
+            ("a".into(), IntLitWidth::U32),
+            ("b".into(), IntLitWidth::I16),
+        ])),
+    );
+}
+
+#[test]
+fn record_of_wide_or_opaque_ints_is_not_packed() {
+    check_derivable(
+        Hash,
+        v!({ a: v!(U8), b: v!(U128), }),
+        DeriveKey::Hash(FlatHashKey::Record(vec!["a".into(), "b".into()])),
+    );
+    check_derivable(
+        Hash,
+        v!({ a: v!(U8), b: v!(@Symbol::ATTR_ATTR => v!(U8)), }),
+        DeriveKey::Hash(FlatHashKey::Record(vec!["a".into(), "b".into()])),
+    );
+}
+
 #[test]
 fn derivable_tag_ext_flex_var() {
     check_derivable(
@@ -0,0 +1,84 @@
+#[path = "../src/helpers/mod.rs"]
+mod helpers;
+
+// defines roc_alloc and friends
+pub use helpers::platform_functions::*;
+
+use bumpalo::Bump;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use roc_gen_dev::RegisterAllocation;
+
+// Run with `cargo bench -p test_gen --no-default-features --features gen-dev --bench dev_register_allocation`
+//
+// The naive allocator spills the oldest register whenever it runs out, and spills every
+// caller saved register at each call. Linear scan keeps values that live across calls in callee
+// saved registers, so `body` only goes to the stack once those run out.
+
+type Main = unsafe extern "C" fn() -> i64;
+
+const REGISTER_PRESSURE: &str = indoc::indoc!(
+    r#"
+    app "bench" provides [main] to "./platform"
+
+    mix : I64, I64 -> I64
+    mix = \a, b -> Num.bitwiseXor (Num.mulWrap a 31) b
+
+    body : I64 -> I64
+    body = \n ->
+        a = mix n 1
+        b = mix a 2
+        c = mix b 3
+        d = mix c 4
+        e = mix d 5
+        f = mix e 6
+        g = mix f 7
+        h = mix g 8
+
+        Num.addWrap (Num.addWrap (Num.addWrap a b) (Num.addWrap c d)) (Num.addWrap (Num.addWrap e f) (Num.addWrap g h))
+
+    run : I64 -> I64
+    run = \n ->
+        if n == 0 then
+            0
+        else
+            Num.bitwiseXor (body n) (run (n - 1))
+
+    main : I64
+    main = run 1000
+    "#
+);
+
+fn roc_function<'a>(
+    arena: &'a Bump,
+    source: &str,
+    register_allocation: RegisterAllocation,
+) -> libloading::Symbol<'a, Main> {
+    let (main_fn_name, errors, lib) =
+        helpers::dev::helper(arena, source, false, true, register_allocation);
+
+    assert!(errors.is_empty(), "Encountered errors:\n{:?}", errors);
+
+    let lib = arena.alloc(lib);
+
+    unsafe { lib.get(main_fn_name.as_bytes()) }.expect("main was not exposed")
+}
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let arena = Bump::new();
+
+    let naive_main = roc_function(&arena, REGISTER_PRESSURE, RegisterAllocation::Naive);
+    let linear_scan_main = roc_function(&arena, REGISTER_PRESSURE, RegisterAllocation::LinearScan);
+
+    assert_eq!(unsafe { naive_main() }, unsafe { linear_scan_main() });
+
+    c.bench_function("dev naive register allocation", |b| {
+        b.iter(|| black_box(unsafe { naive_main() }))
+    });
+
+    c.bench_function("dev linear scan register allocation", |b| {
+        b.iter(|| black_box(unsafe { linear_scan_main() }))
+    });
+}
+
+criterion_group!(dev_register_allocation_benches, criterion_benchmark);
+criterion_main!(dev_register_allocation_benches);
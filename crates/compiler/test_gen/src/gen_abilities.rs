@@ -2014,10 +2014,66 @@ mod hash {
                     ),
                     TEST_HASHER,
                 ),
-                RocList::from_slice(&[15, 27, 31]),
+                // the fields are packed into a single U64
+                RocList::from_slice(&[15, 27, 31, 0, 0, 0, 0, 0]),
                 RocList<u8>
             )
         }
+
+        #[test]
+        fn record_of_small_ints_is_packed() {
+            assert_evals_to!(
+                &build_test(r#"{ a: 1u32, b: 2u8, c: -1i16 }"#),
+                // the sign bits of `c` stay out of the unused top byte
+                RocList::from_slice(&[1, 0, 0, 0, 2, 255, 255, 0]),
+                RocList<u8>
+            )
+        }
+
+        #[test]
+        fn record_of_small_ints_spanning_words() {
+            assert_evals_to!(
+                &build_test(r#"{ a: 1u16, b: -2i64, c: 3u8 }"#),
+                RocList::from_slice(&[
+                    1, 0, 0, 0, 0, 0, 0, 0, //
+                    254, 255, 255, 255, 255, 255, 255, 255, //
+                    3, 0, 0, 0, 0, 0, 0, 0,
+                ]),
+                RocList<u8>
+            )
+        }
+
+        #[test]
+        fn packed_records_with_different_values_hash_differently() {
+            assert_evals_to!(
+                &format!(
+                    indoc!(
+                        r#"
+                        app "test" provides [main] to "./platform"
+
+                        {}
+
+                        hashOf = \rcd -> @THasher [] |> Hash.hash rcd |> tRead
+
+                        main =
+                            a : {{ x : I8, y : U8 }}
+                            a = {{ x: -1, y: 0 }}
+
+                            b : {{ x : I8, y : U8 }}
+                            b = {{ x: 0, y: 255 }}
+
+                            c : {{ x : I8, y : U8 }}
+                            c = {{ x: -1, y: 0 }}
+
+                            hashOf a != hashOf b && hashOf a == hashOf c
+                        "#
+                    ),
+                    TEST_HASHER,
+                ),
+                true,
+                bool
+            )
+        }
     }
 }
 
@@ -2067,6 +2067,36 @@ fn get_int_list_ok() {
     );
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn get_int_list_after_bounds_check() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            list = [12, 9, 6]
+
+            f = \index ->
+                if index < List.len list then
+                    when List.get list index is
+                        Ok elem ->
+                            when List.get list index is
+                                Ok again -> elem + again
+                                Err OutOfBounds -> -1
+
+                        Err OutOfBounds -> -2
+                else
+                    when List.get list index is
+                        Ok _ -> -3
+                        Err OutOfBounds -> 0
+
+            [f 0, f 2, f 3]
+            "#
+        ),
+        RocList::from_slice(&[24, 12, 0]),
+        RocList<i64>
+    );
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn get_int_list_oob() {
@@ -2638,6 +2668,53 @@ fn list_literal_increment_decrement() {
     );
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn list_literal_of_toplevel_constants() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            low : U16
+            low = 10
+
+            high : U16
+            high = 200
+
+            table : List U16
+            table = [low, 100, high]
+
+            main = List.sum table + List.sum table
+            "#
+        ),
+        620,
+        u16
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn list_set_on_constant_list() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            table : List I64
+            table = [1, 2, 3]
+
+            main =
+                changed = List.set table 0 42
+
+                List.concat changed table
+            "#
+        ),
+        RocList::from_slice(&[42, 2, 3, 1, 2, 3]),
+        RocList<i64>
+    );
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn list_pass_to_function() {
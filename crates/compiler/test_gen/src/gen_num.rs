@@ -3939,6 +3939,25 @@ fn add_checked_dec() {
     );
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn abs_checked() {
+    assert_evals_to!(r#"Num.absChecked -5i8"#, RocResult::ok(5), RocResult<i8, ()>);
+    assert_evals_to!(r#"Num.absChecked 5i8"#, RocResult::ok(5), RocResult<i8, ()>);
+    assert_evals_to!(r#"Num.absChecked Num.minI8"#, RocResult::err(()), RocResult<i8, ()>);
+    assert_evals_to!(r#"Num.absChecked 7u8"#, RocResult::ok(7), RocResult<u8, ()>);
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn neg_checked() {
+    assert_evals_to!(r#"Num.negChecked 5i8"#, RocResult::ok(-5), RocResult<i8, ()>);
+    assert_evals_to!(r#"Num.negChecked Num.maxI8"#, RocResult::ok(-127), RocResult<i8, ()>);
+    assert_evals_to!(r#"Num.negChecked Num.minI8"#, RocResult::err(()), RocResult<i8, ()>);
+    assert_evals_to!(r#"Num.negChecked 0u8"#, RocResult::ok(0), RocResult<u8, ()>);
+    assert_evals_to!(r#"Num.negChecked 1u8"#, RocResult::err(()), RocResult<u8, ()>);
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
 fn sub_checked_dec() {
@@ -3380,6 +3380,42 @@ fn box_and_unbox_record() {
     )
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn box_and_unbox_record_odd_size() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            Box.unbox (Box.box { a: 1u8, b: 2u8, c: 3u8, d: 4u8, e: 5u8, f: 6u8, g: 7u8, h: 8u8, i: 9u8, j: 10u8, k: 11u8 })
+            "#
+        ),
+        [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+        [u8; 11]
+    )
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn box_and_unbox_big_record() {
+    // big enough to be copied with a call to memcpy by the dev backend
+    assert_evals_to!(
+        indoc!(
+            r#"
+            record = {
+                a: 1u64, b: 2u64, c: 3u64, d: 4u64, e: 5u64,
+                f: 6u64, g: 7u64, h: 8u64, i: 9u64, j: 10u64,
+                k: 11u64, l: 12u64, m: 13u64, n: 14u64, o: 15u64,
+                p: 16u64, q: 17u64, r: 18u64, s: 19u64, t: 20u64,
+            }
+
+            Box.unbox (Box.box record)
+            "#
+        ),
+        [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20],
+        [u64; 20]
+    )
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn box_and_unbox_tag_union() {
@@ -4499,3 +4535,57 @@ fn pass_lambda_set_to_function() {
         i64
     );
 }
+
+#[test]
+#[cfg(feature = "gen-dev")]
+fn register_allocation_across_calls() {
+    // More values stay live across the calls to `mix` than there are callee saved registers.
+    let src = indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        mix : I64, I64 -> I64
+        mix = \a, b -> Num.bitwiseXor (Num.mulWrap a 31) b
+
+        loop : I64, I64, I64, I64, I64, I64, I64 -> I64
+        loop = \i, a, b, c, d, e, acc ->
+            if i == 0 then
+                Num.addWrap acc (Num.addWrap (Num.addWrap a b) (Num.addWrap c (Num.addWrap d e)))
+            else
+                f = mix a b
+                g = mix b c
+                h = mix c d
+                k = mix d e
+                l = mix e a
+                sum = Num.addWrap (Num.addWrap f g) (Num.addWrap h (Num.addWrap k l))
+
+                loop (i - 1) f g h k l (Num.bitwiseXor acc sum)
+
+        main : I64
+        main = loop 1000 1 2 3 4 5 0
+        "#
+    );
+
+    let expected = {
+        let mix = |a: i64, b: i64| a.wrapping_mul(31) ^ b;
+        let (mut a, mut b, mut c, mut d, mut e, mut acc) = (1i64, 2i64, 3i64, 4i64, 5i64, 0i64);
+        for _ in 0..1000 {
+            let (f, g, h, k, l) = (mix(a, b), mix(b, c), mix(c, d), mix(d, e), mix(e, a));
+            let sum = f
+                .wrapping_add(g)
+                .wrapping_add(h.wrapping_add(k.wrapping_add(l)));
+            (a, b, c, d, e, acc) = (f, g, h, k, l, acc ^ sum);
+        }
+        acc.wrapping_add(
+            a.wrapping_add(b)
+                .wrapping_add(c.wrapping_add(d.wrapping_add(e))),
+        )
+    };
+
+    for register_allocation in [
+        roc_gen_dev::RegisterAllocation::Naive,
+        roc_gen_dev::RegisterAllocation::LinearScan,
+    ] {
+        assert_evals_to!(src, expected, i64, |x| x, true, true, register_allocation);
+    }
+}
@@ -1,5 +1,5 @@
 use libloading::Library;
-use roc_build::link::{link, LinkType};
+use roc_build::link::{link, LinkType, Sanitizers};
 use roc_builtins::bitcode;
 use roc_load::{EntryPoint, ExecutionMode, LoadConfig, Threading};
 use roc_mono::ir::SingleEntryPoint;
@@ -33,6 +33,7 @@ pub fn helper(
     src: &str,
     _leak: bool,
     lazy_literals: bool,
+    register_allocation: roc_gen_dev::RegisterAllocation,
 ) -> (String, Vec<roc_problem::can::Problem>, Library) {
     use std::path::PathBuf;
 
@@ -58,6 +59,9 @@ pub fn helper(
         palette: roc_reporting::report::DEFAULT_PALETTE,
         threading: Threading::Single,
         exec_mode: ExecutionMode::Executable,
+        platform: None,
+        max_type_errors: None,
+        keep_module_types: false,
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
@@ -191,6 +195,7 @@ pub fn helper(
         exposed_to_host: exposed_to_host.top_level_values.keys().copied().collect(),
         lazy_literals,
         mode: roc_gen_dev::AssemblyBackendMode::Test,
+        register_allocation,
     };
 
     let target = target_lexicon::Triple::host();
@@ -224,6 +229,8 @@ pub fn helper(
             builtins_host_tempfile.path().to_str().unwrap(),
         ],
         LinkType::Dylib,
+        false,
+        Sanitizers::default(),
     )
     .expect("failed to link dynamic library");
 
@@ -264,12 +271,23 @@ macro_rules! assert_evals_to {
         }
     };
     ($src:expr, $expected:expr, $ty:ty, $transform:expr, $leak:expr, $lazy_literals:expr) => {
+        assert_evals_to!(
+            $src,
+            $expected,
+            $ty,
+            $transform,
+            $leak,
+            $lazy_literals,
+            roc_gen_dev::RegisterAllocation::LinearScan
+        );
+    };
+    ($src:expr, $expected:expr, $ty:ty, $transform:expr, $leak:expr, $lazy_literals:expr, $register_allocation:expr) => {
         use bumpalo::Bump;
         use roc_gen_dev::run_jit_function_raw;
 
         let arena = Bump::new();
         let (main_fn_name, errors, lib) =
-            $crate::helpers::dev::helper(&arena, $src, $leak, $lazy_literals);
+            $crate::helpers::dev::helper(&arena, $src, $leak, $lazy_literals, $register_allocation);
 
         let transform = |success| {
             let expected = $expected;
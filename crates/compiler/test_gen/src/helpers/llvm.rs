@@ -4,7 +4,7 @@ use std::path::PathBuf;
 use inkwell::module::Module;
 use libloading::Library;
 use roc_build::link::llvm_module_to_dylib;
-use roc_collections::all::MutSet;
+use roc_collections::all::{MutMap, MutSet};
 use roc_command_utils::zig;
 use roc_gen_llvm::llvm::externs::add_default_roc_externs;
 use roc_gen_llvm::{llvm::build::LlvmBackendMode, run_roc::RocCallResult};
@@ -74,6 +74,9 @@ fn create_llvm_module<'a>(
         palette: DEFAULT_PALETTE,
         threading: Threading::Single,
         exec_mode: ExecutionMode::Executable,
+        platform: None,
+        max_type_errors: None,
+        keep_module_types: false,
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
@@ -226,6 +229,10 @@ fn create_llvm_module<'a>(
         mode: config.mode,
         // important! we don't want any procedures to get the C calling convention
         exposed_to_host: MutSet::default(),
+        trace_effects: false,
+        thread_safety: roc_gen_llvm::llvm::build::ThreadSafety::SingleThreaded,
+        list_growth_percent: None,
+        expect_call_sites: MutMap::default(),
     };
 
     // strip Zig debug stuff
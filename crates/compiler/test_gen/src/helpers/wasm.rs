@@ -92,6 +92,9 @@ fn compile_roc_to_wasm_bytes<'a, T: Wasm32Result>(
         palette: DEFAULT_PALETTE_HTML,
         threading: Threading::Single,
         exec_mode: ExecutionMode::Executable,
+        platform: None,
+        max_type_errors: None,
+        keep_module_types: false,
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
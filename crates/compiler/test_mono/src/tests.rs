@@ -108,6 +108,9 @@ fn compiles_to_ir(test_name: &str, src: &str, mode: &str, allow_type_errors: boo
         render: roc_reporting::report::RenderTarget::Generic,
         palette: roc_reporting::report::DEFAULT_PALETTE,
         exec_mode,
+        platform: None,
+        max_type_errors: None,
+        keep_module_types: false,
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
@@ -3146,3 +3149,23 @@ fn dbg_str_followed_by_number() {
         "#
     )
 }
+
+#[mono_test(allow_type_errors = "true")]
+fn capture_only_used_in_redundant_branch_is_dropped() {
+    indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        main =
+            unused = "only used in a redundant branch"
+            used = 1u8
+
+            f = \n ->
+                when n is
+                    _ -> used
+                    0 -> Num.toU8 (Str.countUtf8Bytes unused)
+
+            f 0u8
+        "#
+    )
+}
@@ -0,0 +1,7 @@
+platform "cli"
+    requires {} { main : {} }
+    exposes []
+    packages {}
+    imports []
+    provides [mainForHost]
+    threading singleThreaded
@@ -5,8 +5,9 @@ extern crate indoc;
 mod test_fmt {
     use bumpalo::Bump;
     use roc_fmt::def::fmt_defs;
-    use roc_fmt::module::fmt_module;
-    use roc_fmt::Buf;
+    use roc_fmt::incremental::{format_changed_defs, Edit};
+    use roc_fmt::module::{fmt_module, reorder_header};
+    use roc_fmt::{Buf, FormatOptions};
     use roc_parse::ast::Module;
     use roc_parse::module::{self, module_defs};
     use roc_parse::parser::Parser;
@@ -50,7 +51,7 @@ mod test_fmt {
     }
 
     // Not intended to be used directly in tests; please use module_formats_to or module_formats_same
-    fn expect_format_module_helper(src: &str, expected: &str) {
+    fn expect_format_module_helper(src: &str, expected: &str, options: FormatOptions) {
         let arena = Bump::new();
         let src = src.trim();
         let expected = expected.trim();
@@ -59,7 +60,8 @@ mod test_fmt {
             Ok((actual, state)) => {
                 use roc_fmt::spaces::RemoveSpaces;
 
-                let mut buf = Buf::new_in(&arena);
+                let actual = reorder_header(&arena, &actual, options);
+                let mut buf = Buf::new_with_options_in(&arena, options);
 
                 fmt_module_and_defs(&arena, src, &actual, state, &mut buf);
 
@@ -91,7 +93,7 @@ mod test_fmt {
                 }
 
                 // Now verify that the resultant formatting is _stable_ - i.e. that it doesn't change again if re-formatted
-                let mut reformatted_buf = Buf::new_in(&arena);
+                let mut reformatted_buf = Buf::new_with_options_in(&arena, options);
 
                 fmt_module_and_defs(&arena, output, &reparsed_ast, state, &mut reformatted_buf);
 
@@ -116,12 +118,16 @@ mod test_fmt {
     }
 
     fn module_formats_to(input: &str, expected: &str) {
+        module_formats_to_with_options(input, expected, FormatOptions::default())
+    }
+
+    fn module_formats_to_with_options(input: &str, expected: &str, options: FormatOptions) {
         // First check that input formats to the expected version
-        expect_format_module_helper(input, expected);
+        expect_format_module_helper(input, expected, options);
 
         // Parse the expected result format it, asserting that it doesn't change
         // It's important that formatting be stable / idempotent
-        expect_format_module_helper(expected, expected);
+        expect_format_module_helper(expected, expected, options);
     }
 
     fn module_formats_same(input: &str) {
@@ -4958,6 +4964,118 @@ mod test_fmt {
         ));
     }
 
+    #[test]
+    fn sort_imports() {
+        module_formats_to_with_options(
+            indoc!(
+                r#"
+                    interface Foo
+                        exposes [b, A, a]
+                        imports [
+                            Things.{ z, y },
+                            # the basics
+                            Blah,
+                            pf.Task.{ await, Task },
+                        ]"#
+            ),
+            indoc!(
+                r#"
+                    interface Foo
+                        exposes [A, a, b]
+                        imports [
+                            # the basics
+                            Blah,
+                            Things.{ y, z },
+                            pf.Task.{ Task, await },
+                        ]"#
+            ),
+            FormatOptions {
+                sort_imports: true,
+                ..FormatOptions::default()
+            },
+        );
+    }
+
+    #[test]
+    fn group_imports() {
+        module_formats_to_with_options(
+            indoc!(
+                r#"
+                    app "test"
+                        packages { pf: "platform/main.roc", json: "json/main.roc" }
+                        imports [Things, pf.Task, "data.txt" as data : Str, json.Core, Blah]
+                        provides [main] to pf"#
+            ),
+            indoc!(
+                r#"
+                    app "test"
+                        packages { pf: "platform/main.roc", json: "json/main.roc" }
+                        imports [json.Core, pf.Task, Things, Blah, "data.txt" as data : Str]
+                        provides [main] to pf"#
+            ),
+            FormatOptions {
+                group_imports: true,
+                ..FormatOptions::default()
+            },
+        );
+    }
+
+    #[test]
+    fn imports_stay_in_order_by_default() {
+        module_formats_same(indoc!(
+            r#"
+                interface Foo
+                    exposes [b, a]
+                    imports [Things.{ z, y }, pf.Task, Blah]"#
+        ));
+    }
+
+    #[test]
+    fn align_annotations() {
+        module_formats_to_with_options(
+            indoc!(
+                r#"
+                    interface Foo
+                        exposes []
+                        imports []
+
+                    id : U64
+                    name   : Str
+                    isAdmin : Bool
+
+                    # on its own
+                    role : Str
+                    config : { verbose : Bool }
+                    config = { verbose: Bool.false }
+                    x : I64
+                    xs : List I64
+                "#
+            ),
+            indoc!(
+                r#"
+                    interface Foo
+                        exposes []
+                        imports []
+
+                    id      : U64
+                    name    : Str
+                    isAdmin : Bool
+
+                    # on its own
+                    role : Str
+                    config : { verbose : Bool }
+                    config = { verbose: Bool.false }
+                    x  : I64
+                    xs : List I64
+                "#
+            ),
+            FormatOptions {
+                align_annotations: true,
+                ..FormatOptions::default()
+            },
+        );
+    }
+
     /// Annotations and aliases
 
     #[test]
@@ -6007,6 +6125,130 @@ mod test_fmt {
         );
     }
 
+    /// The single edit that turns `previous` into `new`.
+    fn edit_between(previous: &str, new: &str) -> Edit {
+        let prefix = previous
+            .bytes()
+            .zip(new.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let suffix = previous[prefix..]
+            .bytes()
+            .rev()
+            .zip(new[prefix..].bytes().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        Edit {
+            old: prefix..previous.len() - suffix,
+            new: prefix..new.len() - suffix,
+        }
+    }
+
+    fn format_changed(previous: &str, new: &str) -> Option<String> {
+        let arena = Bump::new();
+        let edit = edit_between(previous, new);
+        let replacements = format_changed_defs(&arena, previous, new, &[edit])?;
+
+        let mut formatted = new.to_string();
+        for replacement in replacements.iter().rev() {
+            formatted.replace_range(replacement.range.clone(), replacement.text);
+        }
+
+        Some(formatted)
+    }
+
+    #[test]
+    fn incremental_format_only_changes_edited_def() {
+        let previous = indoc!(
+            r#"
+            interface Foo exposes [] imports []
+
+            a  =  1
+
+            b = 2
+            "#
+        );
+        let new = previous.replace("b = 2", "b = 2 +  3");
+
+        assert_multiline_str_eq!(
+            format_changed(previous, &new).unwrap().as_str(),
+            indoc!(
+                r#"
+                interface Foo exposes [] imports []
+
+                a  =  1
+
+                b = 2 + 3
+                "#
+            )
+        );
+    }
+
+    #[test]
+    fn incremental_format_keeps_blank_line_before_next_def() {
+        let previous = indoc!(
+            r#"
+            interface Foo exposes [] imports []
+
+            a  =  1
+
+            # b is two
+            b = 2
+            c = 3
+
+            d  =  4
+            "#
+        );
+        let new = previous.replace("b = 2", "b =  [2,3]");
+
+        assert_multiline_str_eq!(
+            format_changed(previous, &new).unwrap().as_str(),
+            indoc!(
+                r#"
+                interface Foo exposes [] imports []
+
+                a  =  1
+
+                # b is two
+                b = [2, 3]
+                c = 3
+
+                d  =  4
+                "#
+            )
+        );
+    }
+
+    #[test]
+    fn incremental_format_gives_up_on_header_edits() {
+        let previous = "interface Foo exposes [] imports []\n\na = 1\n";
+        let new = "interface Foo exposes [a] imports []\n\na = 1\n";
+
+        assert_eq!(format_changed(previous, new), None);
+    }
+
+    #[test]
+    fn incremental_format_gives_up_on_syntax_errors() {
+        let previous = "interface Foo exposes [] imports []\n\na = 1\n";
+        let new = "interface Foo exposes [] imports []\n\na = (1\n";
+
+        assert_eq!(format_changed(previous, new), None);
+    }
+
+    #[test]
+    fn incremental_format_rejects_edits_that_dont_match_the_sources() {
+        let arena = Bump::new();
+        let previous = "interface Foo exposes [] imports []\n\na = 1\n";
+        let new = "interface Foo exposes [] imports []\n\na = 12\n";
+        let edit = Edit {
+            old: 41..42,
+            new: 41..42,
+        };
+
+        assert_eq!(format_changed_defs(&arena, previous, new, &[edit]), None);
+    }
+
     // this is a parse error atm
     //    #[test]
     //    fn multiline_apply() {
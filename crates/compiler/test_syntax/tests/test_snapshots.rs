@@ -413,6 +413,7 @@ mod test_snapshots {
         pass/pattern_as_list_rest.expr,
         pass/pattern_as_spaces.expr,
         pass/pattern_with_space_in_parens.expr, // https://github.com/roc-lang/roc/issues/929
+        pass/platform_threading.header,
         pass/plus_if.expr,
         pass/plus_when.expr,
         pass/pos_inf_float.expr,
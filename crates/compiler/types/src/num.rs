@@ -159,7 +159,7 @@ enum IntSignedness {
     Signed,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum IntLitWidth {
     U8,
     U16,
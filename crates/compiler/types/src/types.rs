@@ -3864,6 +3864,34 @@ impl ErrorType {
             Error => {}
         }
     }
+
+    /// Whether an [ErrorType::Error] appears anywhere in the type, meaning that part of it was
+    /// already involved in an earlier type error.
+    pub fn contains_error(&self) -> bool {
+        use ErrorType::*;
+        match self {
+            Infinite | FlexVar(_) | RigidVar(_) | FlexAbleVar(..) | RigidAbleVar(..) => false,
+            Type(_, ts) | Range(ts) => ts.iter().any(|t| t.contains_error()),
+            Record(fields, _) => fields.iter().any(|(_, t)| t.as_inner().contains_error()),
+            Tuple(elems, _) => elems.iter().any(|(_, t)| t.contains_error()),
+            TagUnion(tags, _, _) => tags
+                .iter()
+                .any(|(_, ts)| ts.iter().any(|t| t.contains_error())),
+            RecursiveTagUnion(t, tags, _, _) => {
+                t.contains_error()
+                    || tags
+                        .iter()
+                        .any(|(_, ts)| ts.iter().any(|t| t.contains_error()))
+            }
+            Function(args, capt, ret) => {
+                args.iter().any(|t| t.contains_error())
+                    || capt.contains_error()
+                    || ret.contains_error()
+            }
+            Alias(_, ts, t, _) => ts.iter().any(|t| t.contains_error()) || t.contains_error(),
+            Error => true,
+        }
+    }
 }
 
 pub fn write_error_type(interns: &Interns, error_type: ErrorType) -> String {
@@ -45,6 +45,9 @@ pub fn write_compiled_ir<'a>(
         render: roc_reporting::report::RenderTarget::Generic,
         palette: roc_reporting::report::DEFAULT_PALETTE,
         exec_mode,
+        platform: None,
+        max_type_errors: None,
+        keep_module_types: false,
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
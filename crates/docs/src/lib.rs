@@ -15,12 +15,59 @@ use roc_parse::state::State;
 use roc_region::all::Region;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 const BUILD_DIR: &str = "./generated-docs";
 
 const LINK_SVG: &str = include_str!("./static/link.svg");
 
-pub fn generate_docs_html(root_file: PathBuf) {
+/// Which color scheme the generated docs use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Theme {
+    /// Follow the reader's system preference (`prefers-color-scheme`).
+    #[default]
+    Auto,
+    Light,
+    Dark,
+}
+
+impl Theme {
+    pub const NAMES: [&'static str; 3] = ["auto", "light", "dark"];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Theme::Auto => "auto",
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "auto" => Ok(Theme::Auto),
+            "light" => Ok(Theme::Light),
+            "dark" => Ok(Theme::Dark),
+            _ => Err(format!(
+                "{string:?} is not a docs theme; expected one of {}",
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DocsOptions {
+    pub theme: Theme,
+    /// Only emit the documentation body (no sidebar, header, or footer),
+    /// so it can be included in an existing static site.
+    pub embed: bool,
+}
+
+pub fn generate_docs_html(root_file: PathBuf, options: DocsOptions) {
     let build_dir = Path::new(BUILD_DIR);
     let loaded_module = load_module_for_docs(root_file);
 
@@ -44,6 +91,7 @@ pub fn generate_docs_html(root_file: PathBuf) {
         styles_css: S,
         favicon_svg: S,
         raw_template_html: S,
+        raw_embed_html: S,
     }
 
     #[cfg(not(debug_assertions))]
@@ -52,12 +100,14 @@ pub fn generate_docs_html(root_file: PathBuf) {
         let styles_css = include_str!("./static/styles.css");
         let favicon_svg = include_str!("./static/favicon.svg");
         let raw_template_html = include_str!("./static/index.html");
+        let raw_embed_html = include_str!("./static/embed.html");
 
         Assets {
             search_js,
             styles_css,
             favicon_svg,
             raw_template_html,
+            raw_embed_html,
         }
     };
 
@@ -72,22 +122,31 @@ pub fn generate_docs_html(root_file: PathBuf) {
         let styles_css = fs::read_to_string(static_dir.join("styles.css")).unwrap();
         let favicon_svg = fs::read_to_string(static_dir.join("favicon.svg")).unwrap();
         let raw_template_html = fs::read_to_string(static_dir.join("index.html")).unwrap();
+        let raw_embed_html = fs::read_to_string(static_dir.join("embed.html")).unwrap();
 
         Assets {
             search_js,
             styles_css,
             favicon_svg,
             raw_template_html,
+            raw_embed_html,
         }
     };
 
     // Write CSS, JS, and favicon
     // (The HTML requires more work!)
-    for (file, contents) in [
-        ("search.js", assets.search_js),
-        ("styles.css", assets.styles_css),
-        ("favicon.svg", assets.favicon_svg),
-    ] {
+    // Embedded docs are styled by the embedding site, which only needs our CSS.
+    let static_files = if options.embed {
+        vec![("styles.css", assets.styles_css)]
+    } else {
+        vec![
+            ("search.js", assets.search_js),
+            ("styles.css", assets.styles_css),
+            ("favicon.svg", assets.favicon_svg),
+        ]
+    };
+
+    for (file, contents) in static_files {
         let dir = build_dir.join(file);
         fs::write(&dir, contents).unwrap_or_else(|error| {
             panic!(
@@ -98,9 +157,15 @@ pub fn generate_docs_html(root_file: PathBuf) {
         })
     }
 
-    // Insert asset urls & sidebar links
-    let template_html = assets
-        .raw_template_html
+    let raw_template_html: &str = if options.embed {
+        assets.raw_embed_html.as_ref()
+    } else {
+        assets.raw_template_html.as_ref()
+    };
+
+    // Insert theme, asset urls & sidebar links
+    let template_html = raw_template_html
+        .replace("<!-- theme -->", options.theme.as_str())
         .replace("<!-- search.js -->", "/search.js")
         .replace("<!-- styles.css -->", "/styles.css")
         .replace("<!-- favicon.svg -->", "/favicon.svg")
@@ -461,6 +526,9 @@ pub fn load_module_for_docs(filename: PathBuf) -> LoadedModule {
         palette: roc_reporting::report::DEFAULT_PALETTE,
         threading: Threading::AllAvailable,
         exec_mode: ExecutionMode::Check,
+        platform: None,
+        max_type_errors: None,
+        keep_module_types: false,
     };
     match roc_load::load_and_typecheck(
         &arena,
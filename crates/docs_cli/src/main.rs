@@ -1,10 +1,13 @@
 //! Provides a binary that is only used for static build servers.
-use clap::{value_parser, Arg, Command};
-use roc_docs::generate_docs_html;
+use clap::{value_parser, Arg, ArgAction, Command};
+use roc_docs::{generate_docs_html, DocsOptions, Theme};
 use std::io;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 pub const ROC_FILE: &str = "ROC_FILE";
+pub const FLAG_THEME: &str = "theme";
+pub const FLAG_EMBED: &str = "embed";
 const DEFAULT_ROC_FILENAME: &str = "main.roc";
 
 fn main() -> io::Result<()> {
@@ -17,10 +20,33 @@ fn main() -> io::Result<()> {
                 .value_parser(value_parser!(PathBuf))
                 .default_value(DEFAULT_ROC_FILENAME),
         )
+        .arg(
+            Arg::new(FLAG_THEME)
+                .long(FLAG_THEME)
+                .help("Choose the color scheme of the generated docs")
+                .value_parser(Theme::NAMES)
+                .default_value(Theme::Auto.as_str()),
+        )
+        .arg(
+            Arg::new(FLAG_EMBED)
+                .long(FLAG_EMBED)
+                .help(
+                    "Only generate the documentation body, without the sidebar, header, and footer",
+                )
+                .action(ArgAction::SetTrue),
+        )
         .get_matches();
 
+    let options = DocsOptions {
+        theme: Theme::from_str(matches.get_one::<String>(FLAG_THEME).unwrap()).unwrap(),
+        embed: matches.get_flag(FLAG_EMBED),
+    };
+
     // Populate roc_files
-    generate_docs_html(matches.get_one::<PathBuf>(ROC_FILE).unwrap().to_owned());
+    generate_docs_html(
+        matches.get_one::<PathBuf>(ROC_FILE).unwrap().to_owned(),
+        options,
+    );
 
     Ok(())
 }
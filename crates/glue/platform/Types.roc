@@ -1,5 +1,5 @@
 interface Types
-    exposes [Types, shape, size, alignment, target, walkShapes, entryPoints]
+    exposes [Types, shape, size, alignment, target, threadSafe, walkShapes, entryPoints]
     imports [Shape.{ Shape }, TypeId.{ TypeId }, Target.{ Target }, InternalTypeId]
 
 # TODO: switch AssocList uses to Dict once roc_std is updated.
@@ -23,11 +23,17 @@ Types := {
     ## Names and types of the entry points of the program (e.g. mainForHost)
     entrypoints : List Tuple1,
     target : Target,
+
+    ## Whether the host may call the entry points from several threads at once
+    threadSafe : Bool,
 }
 
 target : Types -> Target
 target = \@Types types -> types.target
 
+threadSafe : Types -> Bool
+threadSafe = \@Types types -> types.threadSafe
+
 entryPoints : Types -> List Tuple1
 entryPoints = \@Types { entrypoints } -> entrypoints
 
@@ -5,28 +5,63 @@ app "rust-glue"
 
 makeGlue : List Types -> Result (List File) Str
 makeGlue = \typesByArch ->
+    includeOs = hasMultipleOperatingSystems typesByArch
     modFileContent =
         List.walk typesByArch "" \content, types ->
-            arch = (Types.target types).architecture
-            archStr = archName arch
+            target = Types.target types
+            modName = targetModName target includeOs
+            cfg = targetCfg target includeOs
 
             Str.concat
                 content
                 """
-                #[cfg(target_arch = "\(archStr)")]
-                mod \(archStr);
-                #[cfg(target_arch = "\(archStr)")]
-                pub use \(archStr)::*;
+                #[cfg(\(cfg))]
+                mod \(modName);
+                #[cfg(\(cfg))]
+                pub use \(modName)::*;
 
                 """
 
     typesByArch
-    |> List.map convertTypesToFile
+    |> List.map \types -> convertTypesToFile types includeOs
     |> List.append { name: "mod.rs", content: modFileContent }
     |> Ok
 
-convertTypesToFile : Types -> File
-convertTypesToFile = \types ->
+## When glue is generated for several operating systems (e.g. `roc glue --targets x86_64-linux,x86_64-windows`),
+## the same architecture can appear more than once, so the operating system has to be part of the module name and cfg.
+hasMultipleOperatingSystems : List Types -> Bool
+hasMultipleOperatingSystems = \typesByArch ->
+    when List.first typesByArch is
+        Ok first ->
+            firstOs = (Types.target first).operatingSystem
+
+            List.any typesByArch \types -> (Types.target types).operatingSystem != firstOs
+
+        Err _ ->
+            Bool.false
+
+targetModName = \target, includeOs ->
+    archStr = archName target.architecture
+
+    if includeOs then
+        osStr = osName target.operatingSystem
+
+        "\(archStr)_\(osStr)"
+    else
+        archStr
+
+targetCfg = \target, includeOs ->
+    archStr = archName target.architecture
+
+    if includeOs then
+        osStr = osCfg target.operatingSystem
+
+        "all(target_arch = \"\(archStr)\", \(osStr))"
+    else
+        "target_arch = \"\(archStr)\""
+
+convertTypesToFile : Types, Bool -> File
+convertTypesToFile = \types, includeOs ->
     content =
         Types.walkShapes types fileHeader \buf, type, id ->
             when type is
@@ -90,17 +125,58 @@ convertTypesToFile = \types ->
                     # TODO: Eventually we want to generate roc_std. So these types will need to be emitted.
                     buf
 
-    arch = (Types.target types).architecture
-    archStr = archName arch
+    modName = targetModName (Types.target types) includeOs
 
     {
-        name: "\(archStr).rs",
+        name: "\(modName).rs",
         content: content |> generateEntryPoints types,
     }
 
 generateEntryPoints : Str, Types -> Str
 generateEntryPoints = \buf, types ->
-    List.walk (Types.entryPoints types) buf \accum, T name id -> generateEntryPoint accum types name id
+    withCheck =
+        if Types.threadSafe types then
+            buf
+        else
+            generateSingleThreadCheck buf
+
+    List.walk (Types.entryPoints types) withCheck \accum, T name id -> generateEntryPoint accum types name id
+
+# The program doesn't update refcounts atomically, so calling it from a second thread would corrupt them.
+generateSingleThreadCheck : Str -> Str
+generateSingleThreadCheck = \buf ->
+    """
+    \(buf)
+
+    /// Panics unless this is the thread that first called into Roc.
+    fn assert_single_thread() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static OWNER: AtomicUsize = AtomicUsize::new(0);
+
+        thread_local! {
+            static MARKER: u8 = 0;
+        }
+
+        // every thread has its own MARKER, so its address identifies the thread
+        let this_thread = MARKER.with(|marker| marker as *const u8 as usize);
+
+        if let Err(owner) = OWNER.compare_exchange(0, this_thread, Ordering::Relaxed, Ordering::Relaxed) {
+            assert_eq!(
+                owner, this_thread,
+                "The platform declares `threading singleThreaded`, so Roc can only be called from one thread."
+            );
+        }
+    }
+    """
+
+# A call to the check above, for platforms that declare `threading singleThreaded`.
+singleThreadCheck : Types -> Str
+singleThreadCheck = \types ->
+    if Types.threadSafe types then
+        ""
+    else
+        "assert_single_thread();\n\n"
 
 generateEntryPoint : Str, Types, Str, TypeId -> Str
 generateEntryPoint = \buf, types, name, id ->
@@ -152,6 +228,8 @@ generateEntryPoint = \buf, types, name, id ->
             _ ->
                 ""
 
+    threadCheck = singleThreadCheck types
+
     """
     \(buf)
 
@@ -160,7 +238,7 @@ generateEntryPoint = \buf, types, name, id ->
             fn roc__\(name)_1_exposed_generic\(externSignature);
         }
 
-        let mut ret = std::mem::MaybeUninit::uninit();
+        \(threadCheck)let mut ret = std::mem::MaybeUninit::uninit();
 
         unsafe { roc__\(name)_1_exposed_generic(ret.as_mut_ptr(), \(externArguments)) };
 
@@ -200,34 +278,115 @@ generateFunction = \buf, types, rocFn ->
 
     externComma = if Str.isEmpty publicArguments then "" else ", "
 
+    forgetArguments =
+        rocFn.args
+        |> List.mapWithIndex \_, i ->
+            c = Num.toStr i
+            "core::mem::forget(arg\(c));"
+        |> Str.joinWith " "
+
+    callArguments =
+        rocFn.args
+        |> List.mapWithIndex \_, i ->
+            c = Num.toStr i
+            "arg\(c)"
+        |> Str.joinWith ", "
+
+    # e.g. `roc__mainForHost_0_caller` comes with `roc__mainForHost_0_inc` and `roc__mainForHost_0_dec`,
+    # which increment and decrement the refcounts in the closure data
+    hookName = \hook ->
+        when Str.replaceLast externName "_caller" "_\(hook)" is
+            Ok replaced -> replaced
+            Err NotFound -> crash "the extern name of a function always ends in _caller"
+
+    incName = hookName "inc"
+    decName = hookName "dec"
+
     ret = typeName types rocFn.ret
 
+    # Refcounted values in the closure data can only be shared between threads when the
+    # program updates refcounts atomically. Otherwise the raw pointer keeps the struct on one thread.
+    threadSafe = Types.threadSafe types
+    threadCheck = singleThreadCheck types
+
+    threadMarkerField =
+        if threadSafe then
+            ""
+        else
+            "\n    _single_threaded: core::marker::PhantomData<*const ()>,"
+
+    threadMarkerClone =
+        if threadSafe then
+            ""
+        else
+            "\n            _single_threaded: core::marker::PhantomData,"
+
+    threadImpls =
+        if threadSafe then
+            "\n\n// the Roc program updates refcounts atomically, so any thread can call or drop this\nunsafe impl Send for \(name) {}\nunsafe impl Sync for \(name) {}"
+        else
+            ""
+
     """
     \(buf)
 
     #[repr(C)]
-    #[derive(Debug, Clone)]
+    #[derive(Debug)]
     pub struct \(name) {
-        closure_data: \(lambdaSet),
+        closure_data: core::mem::ManuallyDrop<\(lambdaSet)>,\(threadMarkerField)
     }
 
     impl \(name) {
-        pub fn force_thunk(mut self, \(publicArguments)) -> \(ret) {
+        /// Calls the function, giving it back to Roc. Like Roc functions do, this consumes the arguments.
+        pub fn force_thunk(self, \(publicArguments)) -> \(ret) {
             extern "C" {
                 fn \(externName)(\(externDefArguments)\(externComma) closure_data: *mut u8, output: *mut \(ret));
             }
 
+            \(threadCheck)// ownership of the closure is transferred back to roc
+            let mut this = core::mem::ManuallyDrop::new(self);
+
             let mut output = std::mem::MaybeUninit::uninit();
-            let ptr = &mut self.closure_data as *mut _ as *mut u8;
+            let ptr = &mut this.closure_data as *mut _ as *mut u8;
 
             unsafe { \(externName)(\(externCallArguments)\(externComma) ptr, output.as_mut_ptr(), ) };
 
-            // ownership of the closure is transferred back to roc
-            core::mem::forget(self.closure_data);
+            // ownership of the arguments is transferred to roc too
+            \(forgetArguments)
 
             unsafe { output.assume_init() }
         }
+
+        /// Calls the function, and keeps it to be called again, e.g. by an event handler or a timer.
+        pub fn call(&self, \(publicArguments)) -> \(ret) {
+            self.clone().force_thunk(\(callArguments))
+        }
+    }
+
+    impl Clone for \(name) {
+        fn clone(&self) -> Self {
+            extern "C" {
+                fn \(incName)(closure_data: *const u8);
+            }
+
+            unsafe { \(incName)(&self.closure_data as *const _ as *const u8) };
+
+            // the closure data now has one more owner, so it can be shared by copying it
+            Self {
+                closure_data: unsafe { core::ptr::read(&self.closure_data) },\(threadMarkerClone)
+            }
+        }
     }
+
+    impl Drop for \(name) {
+        fn drop(&mut self) {
+            extern "C" {
+                fn \(decName)(closure_data: *mut u8);
+            }
+
+            unsafe { \(decName)(&mut self.closure_data as *mut _ as *mut u8) };
+        }
+    }\(threadImpls)
     """
 
 generateStruct : Str, Types, TypeId, _, _, _ -> Str
@@ -1923,6 +2082,9 @@ archName = \arch ->
         Aarch64 ->
             "aarch64"
 
+        Riscv64 ->
+            "riscv64"
+
         Wasm32 ->
             "wasm32"
 
@@ -1932,6 +2094,28 @@ archName = \arch ->
         X86x64 ->
             "x86_64"
 
+osName = \os ->
+    when os is
+        Windows ->
+            "windows"
+
+        Unix ->
+            "unix"
+
+        Wasi ->
+            "wasi"
+
+osCfg = \os ->
+    when os is
+        Windows ->
+            "windows"
+
+        Unix ->
+            "unix"
+
+        Wasi ->
+            "target_os = \"wasi\""
+
 fileHeader =
     """
     // ⚠️ GENERATED CODE ⚠️ - this entire file was generated by the `roc glue` CLI command
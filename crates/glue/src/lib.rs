@@ -4,6 +4,7 @@
 //! the plan is to support any language via a plugin model.
 pub mod enums;
 pub mod load;
+pub mod manifest;
 pub mod roc_type;
 pub mod rust_glue;
 pub mod structs;
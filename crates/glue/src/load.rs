@@ -3,19 +3,20 @@ use crate::types::Types;
 use bumpalo::Bump;
 use libloading::Library;
 use roc_build::{
-    link::{LinkType, LinkingStrategy},
+    link::{LinkType, LinkingStrategy, Sanitizers},
     program::{
         build_file, handle_error_module, handle_loading_problem, standard_load_config,
-        BuildFileError, BuildOrdering, BuiltFile, CodeGenBackend, CodeGenOptions,
+        thread_safety, BuildFileError, BuildOrdering, BuiltFile, CodeGenBackend, CodeGenOptions,
     },
 };
 use roc_collections::MutMap;
+use roc_gen_llvm::llvm::build::LlvmBackendMode;
 use roc_load::{ExecutionMode, LoadConfig, LoadedModule, LoadingProblem, Threading};
 use roc_mono::ir::{generate_glue_procs, GlueProc, OptLevel};
 use roc_mono::layout::{GlobalLayoutInterner, LayoutCache, LayoutInterner};
 use roc_packaging::cache::{self, RocCacheDir};
 use roc_reporting::report::{RenderTarget, DEFAULT_PALETTE};
-use roc_target::{Architecture, TargetInfo};
+use roc_target::{Architecture, OperatingSystem, TargetInfo};
 use roc_types::subs::{Subs, Variable};
 use std::fs::File;
 use std::io::{self, ErrorKind, Write};
@@ -33,17 +34,75 @@ impl IgnoreErrors {
     const NONE: Self = IgnoreErrors { can: false };
 }
 
+/// Every architecture we support, with the host's operating system.
+/// This is what glue generates code for when no `--targets` are given.
+pub fn host_os_targets() -> Vec<TargetInfo> {
+    let operating_system = TargetInfo::from(&Triple::host()).operating_system;
+
+    Architecture::iter()
+        .map(|architecture| TargetInfo {
+            architecture,
+            operating_system,
+        })
+        .collect()
+}
+
+/// Parse one of the comma-separated `--targets` of `roc glue`, e.g. `x86_64-linux`,
+/// `aarch64-macos`, or `wasm32`. If the operating system is left out, it defaults
+/// to WASI for wasm32 and to the host's operating system otherwise.
+pub fn parse_target(target: &str) -> Result<TargetInfo, String> {
+    let (arch_str, os_str) = match target.split_once('-') {
+        Some((arch_str, os_str)) => (arch_str, Some(os_str)),
+        None => (target, None),
+    };
+
+    let architecture = match arch_str {
+        "x86_64" => Architecture::X86_64,
+        "x86_32" | "x86" => Architecture::X86_32,
+        "aarch64" | "arm64" => Architecture::Aarch64,
+        "aarch32" | "arm" => Architecture::Aarch32,
+        "riscv64" => Architecture::Riscv64,
+        "wasm32" => Architecture::Wasm32,
+        _ => {
+            return Err(format!(
+                "Unknown architecture {arch_str:?} in glue target {target:?}. Valid architectures are x86_64, x86_32, aarch64, aarch32, riscv64, and wasm32."
+            ))
+        }
+    };
+
+    let operating_system = match os_str {
+        Some("linux" | "macos" | "darwin" | "unix") => OperatingSystem::Unix,
+        Some("windows") => OperatingSystem::Windows,
+        Some("wasi") => OperatingSystem::Wasi,
+        Some(os_str) => {
+            return Err(format!(
+                "Unknown operating system {os_str:?} in glue target {target:?}. Valid operating systems are linux, macos, windows, and wasi."
+            ))
+        }
+        None if architecture == Architecture::Wasm32 => OperatingSystem::Wasi,
+        None => TargetInfo::from(&Triple::host()).operating_system,
+    };
+
+    Ok(TargetInfo {
+        architecture,
+        operating_system,
+    })
+}
+
 pub fn generate(
     input_path: &Path,
     output_path: &Path,
     spec_path: &Path,
     backend: CodeGenBackend,
+    targets: &[TargetInfo],
+    emit_manifest: bool,
 ) -> io::Result<i32> {
     // TODO: Add verification around the paths. Make sure they heav the correct file extension and what not.
     match load_types(
         input_path.to_path_buf(),
         Threading::AllAvailable,
         IgnoreErrors::NONE,
+        targets,
     ) {
         Ok(types) => {
             // TODO: we should to modify the app file first before loading it.
@@ -54,6 +113,12 @@ pub fn generate(
                 backend,
                 opt_level: OptLevel::Development,
                 emit_debug_info: false,
+                trace_effects: false,
+                hardening: true,
+                sanitizers: Sanitizers::default(),
+                dump_specializations: false,
+                dump_layouts: false,
+                list_growth_percent: None,
             };
 
             let load_config = standard_load_config(
@@ -87,9 +152,11 @@ pub fn generate(
             match res_binary_path {
                 Ok(BuiltFile {
                     binary_path,
+                    host_path: _,
                     problems,
                     total_time,
                     expect_metadata: _,
+                    stats: _,
                 }) => {
                     // TODO: Should binary_path be update to deal with extensions?
                     use target_lexicon::OperatingSystem;
@@ -142,8 +209,18 @@ pub fn generate(
 
                         process::exit(1);
                     });
-                    for roc_type::File { name, content } in &files {
-                        let valid_name = PathBuf::from(name.as_str())
+                    let manifest = emit_manifest.then(|| crate::manifest::emit(&types));
+                    let files = files
+                        .iter()
+                        .map(|roc_type::File { name, content }| (name.as_str(), content.as_str()))
+                        .chain(
+                            manifest
+                                .iter()
+                                .map(|file| (file.name.as_str(), file.content.as_str())),
+                        );
+
+                    for (name, content) in files {
+                        let valid_name = PathBuf::from(name)
                             .components()
                             .all(|comp| matches!(comp, Component::CurDir | Component::Normal(_)));
                         if !valid_name {
@@ -151,7 +228,7 @@ pub fn generate(
 
                             process::exit(1);
                         }
-                        let full_path = output_path.join(name.as_str());
+                        let full_path = output_path.join(name);
                         if let Some(dir_path) = full_path.parent() {
                             std::fs::create_dir_all(dir_path).unwrap_or_else(|err| {
                                 eprintln!(
@@ -332,6 +409,7 @@ pub fn load_types(
     full_file_path: PathBuf,
     threading: Threading,
     ignore_errors: IgnoreErrors,
+    targets: &[TargetInfo],
 ) -> Result<Vec<Types>, io::Error> {
     let target_info = (&Triple::host()).into();
     let arena = &Bump::new();
@@ -343,6 +421,7 @@ pub fn load_types(
         mut solved,
         interns,
         exposed_to_host,
+        platform_threading,
         ..
     } = roc_load::load_and_typecheck(
         arena,
@@ -354,6 +433,9 @@ pub fn load_types(
             palette: DEFAULT_PALETTE,
             threading,
             exec_mode: ExecutionMode::Check,
+            platform: None,
+            max_type_errors: None,
+            keep_module_types: false,
         },
     )
     .unwrap_or_else(|problem| match problem {
@@ -387,16 +469,15 @@ pub fn load_types(
         exposed_to_host.get(&symbol).copied()
     });
 
-    let operating_system = target_info.operating_system;
-    let architectures = Architecture::iter();
-    let mut arch_types = Vec::with_capacity(architectures.len());
+    // The same target may be given more than once, but layouts only need to be computed once per target.
+    let mut unique_targets = targets.to_vec();
+    unique_targets.sort();
+    unique_targets.dedup();
 
-    for architecture in architectures {
+    let mut target_types = Vec::with_capacity(unique_targets.len());
+
+    for target_info in unique_targets {
         let mut interns = interns.clone(); // TODO there may be a way to avoid this.
-        let target_info = TargetInfo {
-            architecture,
-            operating_system,
-        };
         let layout_interner = GlobalLayoutInterner::with_capacity(128, target_info);
         let mut layout_cache = LayoutCache::new(layout_interner.fork(), target_info);
         let mut glue_procs_by_layout = MutMap::default();
@@ -460,11 +541,13 @@ pub fn load_types(
             glue_procs_by_layout,
             layout_cache,
             target_info,
+            // The mode the platform's host gets its code built in.
+            thread_safety(LlvmBackendMode::Binary, platform_threading),
             exposed_to_host.clone(),
         );
 
-        arch_types.push(types);
+        target_types.push(types);
     }
 
-    Ok(arch_types)
+    Ok(target_types)
 }
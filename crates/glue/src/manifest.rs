@@ -0,0 +1,251 @@
+//! A JSON description of a platform's entry points, for hosts and FFI generators that
+//! aren't written in Rust. It says which symbol to call for each entry point, how its
+//! arguments and return value are laid out on each target, and who has to manage their refcounts.
+use crate::types::{
+    File, RocFn, RocNum, RocSingleTagPayload, RocStructFields, RocTagUnion, RocType, TypeId, Types,
+};
+use roc_target::{Architecture, OperatingSystem};
+use serde_json::{json, Value};
+
+pub const MANIFEST_FILE_NAME: &str = "glue-manifest.json";
+
+/// Bumped whenever the shape of the manifest changes in a way that could break its readers.
+const FORMAT_VERSION: u32 = 1;
+
+pub fn emit(types: &[Types]) -> File {
+    let targets: Vec<Value> = types.iter().map(target_manifest).collect();
+    let manifest = json!({
+        "format_version": FORMAT_VERSION,
+        "targets": targets,
+    });
+
+    let mut content = serde_json::to_string_pretty(&manifest).unwrap();
+    content.push('\n');
+
+    File {
+        name: MANIFEST_FILE_NAME.to_string(),
+        content,
+    }
+}
+
+fn target_manifest(types: &Types) -> Value {
+    let target = types.target();
+    let entry_points: Vec<Value> = types
+        .entry_points()
+        .iter()
+        .map(|(name, id)| entry_point_manifest(name, *id, types))
+        .collect();
+
+    json!({
+        "architecture": architecture_name(target.architecture),
+        "operating_system": operating_system_name(target.operating_system),
+        "thread_safe": types.thread_safe(),
+        "entry_points": entry_points,
+    })
+}
+
+/// Entry points are called through `roc__<name>_1_exposed_generic`, which takes a pointer to write
+/// the return value into, followed by the arguments. Roc takes ownership of the arguments,
+/// and the host owns the return value, so it has to decrement its refcounts once it's done with it.
+fn entry_point_manifest(name: &str, id: TypeId, types: &Types) -> Value {
+    let (args, ret) = match types.get_type(id) {
+        RocType::Function(RocFn { args, ret, .. }) => (args.as_slice(), *ret),
+        _ => (&[][..], id),
+    };
+
+    let arguments: Vec<Value> = args
+        .iter()
+        .map(|arg| {
+            let mut layout = layout_manifest(*arg, types);
+            let ownership = if is_refcounted(*arg, types) {
+                "consumed"
+            } else {
+                "none"
+            };
+
+            layout["ownership"] = json!(ownership);
+            layout
+        })
+        .collect();
+
+    let mut ret_layout = layout_manifest(ret, types);
+    let ownership = if is_refcounted(ret, types) {
+        "owned"
+    } else {
+        "none"
+    };
+
+    ret_layout["ownership"] = json!(ownership);
+
+    json!({
+        "name": name,
+        "symbol": format!("roc__{name}_1_exposed_generic"),
+        "returns_through_pointer": true,
+        "arguments": arguments,
+        "return": ret_layout,
+    })
+}
+
+fn layout_manifest(id: TypeId, types: &Types) -> Value {
+    json!({
+        "type": type_name(id, types),
+        "size": types.size_rounded_to_alignment(id),
+        "align": types.align(id),
+        "abi_class": abi_class(id, types),
+        "refcounted": is_refcounted(id, types),
+    })
+}
+
+/// How a value of this type is passed in registers under the C calling convention.
+/// Aggregates are split up or passed in memory depending on the target's C ABI,
+/// just like a C struct of the same size and alignment would be.
+fn abi_class(id: TypeId, types: &Types) -> &'static str {
+    if types.size_ignoring_alignment(id) == 0 {
+        return "void";
+    }
+
+    match types.get_type(id) {
+        RocType::Unit | RocType::EmptyTagUnion => "void",
+        RocType::Num(RocNum::F32 | RocNum::F64) => "float",
+        RocType::Num(RocNum::I128 | RocNum::U128 | RocNum::Dec) => "aggregate",
+        RocType::Bool | RocType::Num(_) | RocType::TagUnion(RocTagUnion::Enumeration { .. }) => {
+            "integer"
+        }
+        RocType::RocBox(_)
+        | RocType::RecursivePointer(_)
+        | RocType::TagUnion(
+            RocTagUnion::Recursive { .. }
+            | RocTagUnion::NullableWrapped { .. }
+            | RocTagUnion::NullableUnwrapped { .. }
+            | RocTagUnion::NonNullableUnwrapped { .. },
+        ) => "pointer",
+        RocType::RocStr
+        | RocType::RocList(_)
+        | RocType::RocDict(_, _)
+        | RocType::RocSet(_)
+        | RocType::RocResult(_, _)
+        | RocType::Struct { .. }
+        | RocType::TagUnionPayload { .. }
+        | RocType::TagUnion(
+            RocTagUnion::NonRecursive { .. } | RocTagUnion::SingleTagStruct { .. },
+        )
+        | RocType::Function(_)
+        | RocType::Unsized => "aggregate",
+    }
+}
+
+/// Whether a value of this type can point to refcounted memory, which whoever owns the value
+/// has to decrement when they're done with it.
+fn is_refcounted(id: TypeId, types: &Types) -> bool {
+    match types.get_type(id) {
+        RocType::Unit
+        | RocType::EmptyTagUnion
+        | RocType::Bool
+        | RocType::Num(_)
+        | RocType::TagUnion(RocTagUnion::Enumeration { .. }) => false,
+        RocType::RocStr
+        | RocType::RocList(_)
+        | RocType::RocDict(_, _)
+        | RocType::RocSet(_)
+        | RocType::RocBox(_)
+        | RocType::RecursivePointer(_)
+        | RocType::TagUnion(
+            RocTagUnion::Recursive { .. }
+            | RocTagUnion::NullableWrapped { .. }
+            | RocTagUnion::NullableUnwrapped { .. }
+            | RocTagUnion::NonNullableUnwrapped { .. },
+        ) => true,
+        // The captures of a closure can be anything; the host has to assume they need refcounting.
+        RocType::Unsized => true,
+        RocType::Function(RocFn { lambda_set, .. }) => is_refcounted(*lambda_set, types),
+        RocType::RocResult(ok, err) => is_refcounted(*ok, types) || is_refcounted(*err, types),
+        RocType::TagUnion(RocTagUnion::NonRecursive { tags, .. }) => tags
+            .iter()
+            .any(|(_, payload)| payload.map_or(false, |id| is_refcounted(id, types))),
+        RocType::TagUnion(RocTagUnion::SingleTagStruct { payload, .. }) => match payload {
+            RocSingleTagPayload::HasNoClosure { payload_fields } => {
+                payload_fields.iter().any(|id| is_refcounted(*id, types))
+            }
+            RocSingleTagPayload::HasClosure { payload_getters } => payload_getters
+                .iter()
+                .any(|(id, _)| is_refcounted(*id, types)),
+        },
+        RocType::Struct { fields, .. } | RocType::TagUnionPayload { fields, .. } => match fields {
+            RocStructFields::HasNoClosure { fields } => {
+                fields.iter().any(|(_, id)| is_refcounted(*id, types))
+            }
+            RocStructFields::HasClosure { fields } => {
+                fields.iter().any(|(_, id, _)| is_refcounted(*id, types))
+            }
+        },
+    }
+}
+
+/// The name of the type as it's written in Roc, e.g. `List Str` or `Person`.
+fn type_name(id: TypeId, types: &Types) -> String {
+    match types.get_type(id) {
+        RocType::Unit => "{}".to_string(),
+        RocType::EmptyTagUnion => "[]".to_string(),
+        RocType::RocStr => "Str".to_string(),
+        RocType::Bool => "Bool".to_string(),
+        RocType::Num(num) => format!("{num:?}"),
+        RocType::RocList(elem) => format!("List {}", type_arg_name(*elem, types)),
+        RocType::RocSet(elem) => format!("Set {}", type_arg_name(*elem, types)),
+        RocType::RocBox(elem) => format!("Box {}", type_arg_name(*elem, types)),
+        RocType::RocDict(key, value) => format!(
+            "Dict {} {}",
+            type_arg_name(*key, types),
+            type_arg_name(*value, types)
+        ),
+        RocType::RocResult(ok, err) => format!(
+            "Result {} {}",
+            type_arg_name(*ok, types),
+            type_arg_name(*err, types)
+        ),
+        RocType::Unsized => "Unsized".to_string(),
+        RocType::RecursivePointer(content) => type_name(*content, types),
+        RocType::Function(RocFn { function_name, .. }) => function_name.clone(),
+        RocType::Struct { name, .. }
+        | RocType::TagUnionPayload { name, .. }
+        | RocType::TagUnion(
+            RocTagUnion::Enumeration { name, .. }
+            | RocTagUnion::NonRecursive { name, .. }
+            | RocTagUnion::Recursive { name, .. }
+            | RocTagUnion::NonNullableUnwrapped { name, .. }
+            | RocTagUnion::SingleTagStruct { name, .. }
+            | RocTagUnion::NullableWrapped { name, .. }
+            | RocTagUnion::NullableUnwrapped { name, .. },
+        ) => name.clone(),
+    }
+}
+
+/// Like [type_name], but wrapped in parens if it's an application, e.g. `List (List Str)`.
+fn type_arg_name(id: TypeId, types: &Types) -> String {
+    let name = type_name(id, types);
+
+    if name.contains(' ') {
+        format!("({name})")
+    } else {
+        name
+    }
+}
+
+fn architecture_name(architecture: Architecture) -> &'static str {
+    match architecture {
+        Architecture::Aarch32 => "aarch32",
+        Architecture::Aarch64 => "aarch64",
+        Architecture::Riscv64 => "riscv64",
+        Architecture::Wasm32 => "wasm32",
+        Architecture::X86_32 => "x86_32",
+        Architecture::X86_64 => "x86_64",
+    }
+}
+
+fn operating_system_name(operating_system: OperatingSystem) -> &'static str {
+    match operating_system {
+        OperatingSystem::Windows => "windows",
+        OperatingSystem::Unix => "unix",
+        OperatingSystem::Wasi => "wasi",
+        OperatingSystem::Freestanding => "freestanding",
+    }
+}
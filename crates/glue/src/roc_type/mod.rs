@@ -19,6 +19,7 @@
 #[cfg(any(
     target_arch = "arm",
     target_arch = "aarch64",
+    target_arch = "riscv64",
     target_arch = "wasm32",
     target_arch = "x86",
     target_arch = "x86_64"
@@ -33,6 +34,7 @@ pub struct File {
 #[cfg(any(
     target_arch = "arm",
     target_arch = "aarch64",
+    target_arch = "riscv64",
     target_arch = "wasm32",
     target_arch = "x86",
     target_arch = "x86_64"
@@ -47,6 +49,7 @@ pub struct Types {
     pub types: roc_std::RocList<RocType>,
     pub typesByName: roc_std::RocList<Tuple1>,
     pub target: Target,
+    pub threadSafe: bool,
 }
 
 #[cfg(any(target_arch = "arm", target_arch = "wasm32", target_arch = "x86"))]
@@ -60,6 +63,7 @@ pub struct Tuple1 {
 #[cfg(any(
     target_arch = "arm",
     target_arch = "aarch64",
+    target_arch = "riscv64",
     target_arch = "wasm32",
     target_arch = "x86",
     target_arch = "x86_64"
@@ -135,6 +139,7 @@ pub struct R16 {
 #[cfg(any(
     target_arch = "arm",
     target_arch = "aarch64",
+    target_arch = "riscv64",
     target_arch = "wasm32",
     target_arch = "x86",
     target_arch = "x86_64"
@@ -174,6 +179,7 @@ pub struct Tuple2 {
 #[cfg(any(
     target_arch = "arm",
     target_arch = "aarch64",
+    target_arch = "riscv64",
     target_arch = "wasm32",
     target_arch = "x86",
     target_arch = "x86_64"
@@ -188,6 +194,7 @@ pub struct Target {
 #[cfg(any(
     target_arch = "arm",
     target_arch = "aarch64",
+    target_arch = "riscv64",
     target_arch = "wasm32",
     target_arch = "x86",
     target_arch = "x86_64"
@@ -213,6 +220,7 @@ impl core::fmt::Debug for OperatingSystem {
 #[cfg(any(
     target_arch = "arm",
     target_arch = "aarch64",
+    target_arch = "riscv64",
     target_arch = "wasm32",
     target_arch = "x86",
     target_arch = "x86_64"
@@ -222,9 +230,10 @@ impl core::fmt::Debug for OperatingSystem {
 pub enum Architecture {
     Aarch32 = 0,
     Aarch64 = 1,
-    Wasm32 = 2,
-    X86x32 = 3,
-    X86x64 = 4,
+    Riscv64 = 2,
+    Wasm32 = 3,
+    X86x32 = 4,
+    X86x64 = 5,
 }
 
 impl core::fmt::Debug for Architecture {
@@ -232,6 +241,7 @@ impl core::fmt::Debug for Architecture {
         match self {
             Self::Aarch32 => f.write_str("Architecture::Aarch32"),
             Self::Aarch64 => f.write_str("Architecture::Aarch64"),
+            Self::Riscv64 => f.write_str("Architecture::Riscv64"),
             Self::Wasm32 => f.write_str("Architecture::Wasm32"),
             Self::X86x32 => f.write_str("Architecture::X86x32"),
             Self::X86x64 => f.write_str("Architecture::X86x64"),
@@ -242,6 +252,7 @@ impl core::fmt::Debug for Architecture {
 #[cfg(any(
     target_arch = "arm",
     target_arch = "aarch64",
+    target_arch = "riscv64",
     target_arch = "wasm32",
     target_arch = "x86",
     target_arch = "x86_64"
@@ -290,6 +301,7 @@ pub union RocTagUnion {
 #[cfg(any(
     target_arch = "arm",
     target_arch = "aarch64",
+    target_arch = "riscv64",
     target_arch = "wasm32",
     target_arch = "x86",
     target_arch = "x86_64"
@@ -305,6 +317,7 @@ pub struct R14 {
 #[cfg(any(
     target_arch = "arm",
     target_arch = "aarch64",
+    target_arch = "riscv64",
     target_arch = "wasm32",
     target_arch = "x86",
     target_arch = "x86_64"
@@ -328,6 +341,7 @@ impl core::fmt::Debug for discriminant_RocSingleTagPayload {
 #[cfg(any(
     target_arch = "arm",
     target_arch = "aarch64",
+    target_arch = "riscv64",
     target_arch = "wasm32",
     target_arch = "x86",
     target_arch = "x86_64"
@@ -364,6 +378,7 @@ pub struct R9 {
 #[cfg(any(
     target_arch = "arm",
     target_arch = "aarch64",
+    target_arch = "riscv64",
     target_arch = "wasm32",
     target_arch = "x86",
     target_arch = "x86_64"
@@ -397,6 +412,7 @@ pub struct R7 {
 #[cfg(any(
     target_arch = "arm",
     target_arch = "aarch64",
+    target_arch = "riscv64",
     target_arch = "wasm32",
     target_arch = "x86",
     target_arch = "x86_64"
@@ -445,6 +461,7 @@ pub struct R5 {
 #[cfg(any(
     target_arch = "arm",
     target_arch = "aarch64",
+    target_arch = "riscv64",
     target_arch = "wasm32",
     target_arch = "x86",
     target_arch = "x86_64"
@@ -459,6 +476,7 @@ pub struct R1 {
 #[cfg(any(
     target_arch = "arm",
     target_arch = "aarch64",
+    target_arch = "riscv64",
     target_arch = "wasm32",
     target_arch = "x86",
     target_arch = "x86_64"
@@ -482,6 +500,7 @@ impl core::fmt::Debug for discriminant_RocStructFields {
 #[cfg(any(
     target_arch = "arm",
     target_arch = "aarch64",
+    target_arch = "riscv64",
     target_arch = "wasm32",
     target_arch = "x86",
     target_arch = "x86_64"
@@ -496,6 +515,7 @@ pub union RocStructFields {
 #[cfg(any(
     target_arch = "arm",
     target_arch = "aarch64",
+    target_arch = "riscv64",
     target_arch = "wasm32",
     target_arch = "x86",
     target_arch = "x86_64"
@@ -517,6 +537,7 @@ struct RocType_RocDict {
 #[cfg(any(
     target_arch = "arm",
     target_arch = "aarch64",
+    target_arch = "riscv64",
     target_arch = "wasm32",
     target_arch = "x86",
     target_arch = "x86_64"
@@ -571,7 +592,11 @@ pub struct RocFn {
     pub is_toplevel: bool,
 }
 
-#[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+#[cfg(any(
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "x86_64"
+))]
 #[repr(C)]
 #[derive(Clone, Default, Eq, Ord, Hash, PartialEq, PartialOrd)]
 pub struct Tuple1 {
@@ -579,7 +604,11 @@ pub struct Tuple1 {
     f1: u64,
 }
 
-#[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+#[cfg(any(
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "x86_64"
+))]
 #[repr(C)]
 pub union RocType {
     Function: core::mem::ManuallyDrop<RocFn>,
@@ -596,14 +625,22 @@ pub union RocType {
     _sizer: [u8; 104],
 }
 
-#[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+#[cfg(any(
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "x86_64"
+))]
 #[derive(Clone, Copy, Debug, Default, Eq, Ord, Hash, PartialEq, PartialOrd)]
 #[repr(transparent)]
 pub struct R16 {
     pub id: u64,
 }
 
-#[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+#[cfg(any(
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "x86_64"
+))]
 #[derive(Clone, Debug, Default, Eq, Ord, Hash, PartialEq, PartialOrd)]
 #[repr(C)]
 pub struct R4 {
@@ -611,7 +648,11 @@ pub struct R4 {
     pub name: roc_std::RocStr,
 }
 
-#[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+#[cfg(any(
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "x86_64"
+))]
 #[derive(Clone, Debug, Default, Eq, Ord, Hash, PartialEq, PartialOrd)]
 #[repr(C)]
 pub struct R2 {
@@ -620,7 +661,11 @@ pub struct R2 {
     pub name: roc_std::RocStr,
 }
 
-#[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+#[cfg(any(
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "x86_64"
+))]
 #[repr(C)]
 #[derive(Clone, Default, Eq, Ord, Hash, PartialEq, PartialOrd)]
 pub struct Tuple2 {
@@ -628,7 +673,11 @@ pub struct Tuple2 {
     f1: roc_std::RocList<u64>,
 }
 
-#[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+#[cfg(any(
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "x86_64"
+))]
 #[repr(C)]
 pub union RocTagUnion {
     Enumeration: core::mem::ManuallyDrop<R5>,
@@ -641,7 +690,11 @@ pub union RocTagUnion {
     _sizer: [u8; 96],
 }
 
-#[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+#[cfg(any(
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "x86_64"
+))]
 #[derive(Clone, Debug, Eq, Ord, Hash, PartialEq, PartialOrd)]
 #[repr(C)]
 pub struct R10 {
@@ -652,7 +705,11 @@ pub struct R10 {
     pub indexOfNullTag: u16,
 }
 
-#[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+#[cfg(any(
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "x86_64"
+))]
 #[derive(Clone, Debug, Eq, Ord, Hash, PartialEq, PartialOrd)]
 #[repr(C)]
 pub struct R9 {
@@ -663,7 +720,11 @@ pub struct R9 {
     pub whichTagIsNull: U2,
 }
 
-#[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+#[cfg(any(
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "x86_64"
+))]
 #[derive(Clone, Debug, Eq, Ord, Hash, PartialEq, PartialOrd)]
 #[repr(C)]
 pub struct R7 {
@@ -673,14 +734,22 @@ pub struct R7 {
     pub discriminantSize: u32,
 }
 
-#[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+#[cfg(any(
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "x86_64"
+))]
 #[repr(C)]
 pub union U1 {
     Some: u64,
     _sizer: [u8; 16],
 }
 
-#[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+#[cfg(any(
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "x86_64"
+))]
 #[derive(Clone, Debug, Default, Eq, Ord, Hash, PartialEq, PartialOrd)]
 #[repr(C)]
 pub struct R6 {
@@ -689,7 +758,11 @@ pub struct R6 {
     pub tagName: roc_std::RocStr,
 }
 
-#[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+#[cfg(any(
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "x86_64"
+))]
 #[derive(Clone, Debug, Default, Eq, Ord, Hash, PartialEq, PartialOrd)]
 #[repr(C)]
 pub struct R5 {
@@ -698,7 +771,11 @@ pub struct R5 {
     pub size: u32,
 }
 
-#[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+#[cfg(any(
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "x86_64"
+))]
 #[derive(Clone, Copy, Debug, Default, Eq, Ord, Hash, PartialEq, PartialOrd)]
 #[repr(C)]
 struct RocType_RocDict {
@@ -706,7 +783,11 @@ struct RocType_RocDict {
     pub f1: u64,
 }
 
-#[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+#[cfg(any(
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "x86_64"
+))]
 #[derive(Clone, Debug, Default, Eq, Ord, Hash, PartialEq, PartialOrd)]
 #[repr(C)]
 pub struct RocFn {
@@ -739,20 +820,32 @@ impl Tuple1 {
         (&self.f0, &self.f1)
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// A tag named T, with the given payload.
     pub fn T(f0: roc_std::RocStr, f1: u64) -> Self {
         Self { f0, f1 }
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Since `T` only has one tag (namely, `T`),
     /// convert it to `T`'s payload.
     pub fn into_T(self) -> (roc_std::RocStr, u64) {
         (self.f0, self.f1)
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Since `T` only has one tag (namely, `T`),
     /// convert it to `T`'s payload.
     pub fn as_T(&self) -> (&roc_std::RocStr, &u64) {
@@ -764,6 +857,7 @@ impl core::fmt::Debug for Tuple1 {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -810,6 +904,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -823,6 +918,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -846,6 +942,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -859,6 +956,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -872,6 +970,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -890,6 +989,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -919,6 +1019,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -936,6 +1037,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -952,6 +1054,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -969,6 +1072,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1194,6 +1298,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1207,6 +1312,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1220,6 +1326,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1238,6 +1345,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1267,6 +1375,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1284,6 +1393,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1302,6 +1412,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1331,6 +1442,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1348,6 +1460,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1366,6 +1479,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1395,6 +1509,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1422,6 +1537,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1435,6 +1551,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1458,6 +1575,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1471,6 +1589,7 @@ impl RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1481,7 +1600,11 @@ impl RocType {
         ()
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Returns which variant this tag union holds. Note that this never includes a payload!
     pub fn discriminant(&self) -> discriminant_RocType {
         unsafe {
@@ -1491,7 +1614,11 @@ impl RocType {
         }
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Internal helper
     fn set_discriminant(&mut self, discriminant: discriminant_RocType) {
         let discriminant_ptr: *mut discriminant_RocType = (self as *mut RocType).cast();
@@ -1501,7 +1628,11 @@ impl RocType {
         }
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// A tag named Bool, which has no payload.
     pub const Bool: Self = unsafe {
         let mut bytes = [0; core::mem::size_of::<RocType>()];
@@ -1511,7 +1642,11 @@ impl RocType {
         core::mem::transmute::<[u8; core::mem::size_of::<RocType>()], RocType>(bytes)
     };
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// A tag named EmptyTagUnion, which has no payload.
     pub const EmptyTagUnion: Self = unsafe {
         let mut bytes = [0; core::mem::size_of::<RocType>()];
@@ -1521,7 +1656,11 @@ impl RocType {
         core::mem::transmute::<[u8; core::mem::size_of::<RocType>()], RocType>(bytes)
     };
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Construct a tag named `RecursivePointer`, with the appropriate payload
     pub fn RecursivePointer(arg: u64) -> Self {
         let mut answer = Self {
@@ -1533,7 +1672,11 @@ impl RocType {
         answer
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Unsafely assume the given `RocType` has a `.discriminant()` of `RecursivePointer` and convert it to `RecursivePointer`'s payload.
     /// (Always examine `.discriminant()` first to make sure this is the correct variant!)
     /// Panics in debug builds if the `.discriminant()` doesn't return `RecursivePointer`.
@@ -1544,7 +1687,11 @@ impl RocType {
         payload
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Unsafely assume the given `RocType` has a `.discriminant()` of `RecursivePointer` and return its payload.
     /// (Always examine `.discriminant()` first to make sure this is the correct variant!)
     /// Panics in debug builds if the `.discriminant()` doesn't return `RecursivePointer`.
@@ -1555,7 +1702,11 @@ impl RocType {
         &payload
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Construct a tag named `RocBox`, with the appropriate payload
     pub fn RocBox(arg: u64) -> Self {
         let mut answer = Self { RocBox: arg };
@@ -1565,7 +1716,11 @@ impl RocType {
         answer
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Unsafely assume the given `RocType` has a `.discriminant()` of `RocBox` and convert it to `RocBox`'s payload.
     /// (Always examine `.discriminant()` first to make sure this is the correct variant!)
     /// Panics in debug builds if the `.discriminant()` doesn't return `RocBox`.
@@ -1576,7 +1731,11 @@ impl RocType {
         payload
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Unsafely assume the given `RocType` has a `.discriminant()` of `RocBox` and return its payload.
     /// (Always examine `.discriminant()` first to make sure this is the correct variant!)
     /// Panics in debug builds if the `.discriminant()` doesn't return `RocBox`.
@@ -1587,7 +1746,11 @@ impl RocType {
         &payload
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Construct a tag named `RocDict`, with the appropriate payload
     pub fn RocDict(arg0: u64, arg1: u64) -> Self {
         let mut answer = Self {
@@ -1599,7 +1762,11 @@ impl RocType {
         answer
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Unsafely assume the given `RocType` has a `.discriminant()` of `RocDict` and convert it to `RocDict`'s payload.
     /// (Always examine `.discriminant()` first to make sure this is the correct variant!)
     /// Panics in debug builds if the `.discriminant()` doesn't return `RocDict`.
@@ -1610,7 +1777,11 @@ impl RocType {
         (payload.f0, payload.f1)
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Unsafely assume the given `RocType` has a `.discriminant()` of `RocDict` and return its payload.
     /// (Always examine `.discriminant()` first to make sure this is the correct variant!)
     /// Panics in debug builds if the `.discriminant()` doesn't return `RocDict`.
@@ -1621,7 +1792,11 @@ impl RocType {
         (&payload.f0, &payload.f1)
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Construct a tag named `RocList`, with the appropriate payload
     pub fn RocList(arg: u64) -> Self {
         let mut answer = Self { RocList: arg };
@@ -1631,7 +1806,11 @@ impl RocType {
         answer
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Unsafely assume the given `RocType` has a `.discriminant()` of `RocList` and convert it to `RocList`'s payload.
     /// (Always examine `.discriminant()` first to make sure this is the correct variant!)
     /// Panics in debug builds if the `.discriminant()` doesn't return `RocList`.
@@ -1642,7 +1821,11 @@ impl RocType {
         payload
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Unsafely assume the given `RocType` has a `.discriminant()` of `RocList` and return its payload.
     /// (Always examine `.discriminant()` first to make sure this is the correct variant!)
     /// Panics in debug builds if the `.discriminant()` doesn't return `RocList`.
@@ -1653,7 +1836,11 @@ impl RocType {
         &payload
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Construct a tag named `RocResult`, with the appropriate payload
     pub fn RocResult(arg0: u64, arg1: u64) -> Self {
         let mut answer = Self {
@@ -1665,7 +1852,11 @@ impl RocType {
         answer
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Unsafely assume the given `RocType` has a `.discriminant()` of `RocResult` and convert it to `RocResult`'s payload.
     /// (Always examine `.discriminant()` first to make sure this is the correct variant!)
     /// Panics in debug builds if the `.discriminant()` doesn't return `RocResult`.
@@ -1676,7 +1867,11 @@ impl RocType {
         (payload.f0, payload.f1)
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Unsafely assume the given `RocType` has a `.discriminant()` of `RocResult` and return its payload.
     /// (Always examine `.discriminant()` first to make sure this is the correct variant!)
     /// Panics in debug builds if the `.discriminant()` doesn't return `RocResult`.
@@ -1687,7 +1882,11 @@ impl RocType {
         (&payload.f0, &payload.f1)
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Construct a tag named `RocSet`, with the appropriate payload
     pub fn RocSet(arg: u64) -> Self {
         let mut answer = Self { RocSet: arg };
@@ -1697,7 +1896,11 @@ impl RocType {
         answer
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Unsafely assume the given `RocType` has a `.discriminant()` of `RocSet` and convert it to `RocSet`'s payload.
     /// (Always examine `.discriminant()` first to make sure this is the correct variant!)
     /// Panics in debug builds if the `.discriminant()` doesn't return `RocSet`.
@@ -1708,7 +1911,11 @@ impl RocType {
         payload
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Unsafely assume the given `RocType` has a `.discriminant()` of `RocSet` and return its payload.
     /// (Always examine `.discriminant()` first to make sure this is the correct variant!)
     /// Panics in debug builds if the `.discriminant()` doesn't return `RocSet`.
@@ -1719,7 +1926,11 @@ impl RocType {
         &payload
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// A tag named RocStr, which has no payload.
     pub const RocStr: Self = unsafe {
         let mut bytes = [0; core::mem::size_of::<RocType>()];
@@ -1729,7 +1940,11 @@ impl RocType {
         core::mem::transmute::<[u8; core::mem::size_of::<RocType>()], RocType>(bytes)
     };
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// A tag named Unit, which has no payload.
     pub const Unit: Self = unsafe {
         let mut bytes = [0; core::mem::size_of::<RocType>()];
@@ -1739,7 +1954,11 @@ impl RocType {
         core::mem::transmute::<[u8; core::mem::size_of::<RocType>()], RocType>(bytes)
     };
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// A tag named Unsized, which has no payload.
     pub const Unsized: Self = unsafe {
         let mut bytes = [0; core::mem::size_of::<RocType>()];
@@ -1754,6 +1973,7 @@ impl Drop for RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1795,6 +2015,7 @@ impl PartialEq for RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1835,6 +2056,7 @@ impl PartialOrd for RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1876,6 +2098,7 @@ impl Ord for RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1917,6 +2140,7 @@ impl Clone for RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -1991,6 +2215,7 @@ impl core::hash::Hash for RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2054,6 +2279,7 @@ impl core::fmt::Debug for RocType {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2131,20 +2357,32 @@ impl Tuple2 {
         (&self.f0, &self.f1)
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// A tag named T, with the given payload.
     pub fn T(f0: u64, f1: roc_std::RocList<u64>) -> Self {
         Self { f0, f1 }
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Since `T` only has one tag (namely, `T`),
     /// convert it to `T`'s payload.
     pub fn into_T(self) -> (u64, roc_std::RocList<u64>) {
         (self.f0, self.f1)
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Since `T` only has one tag (namely, `T`),
     /// convert it to `T`'s payload.
     pub fn as_T(&self) -> (&u64, &roc_std::RocList<u64>) {
@@ -2156,6 +2394,7 @@ impl core::fmt::Debug for Tuple2 {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2192,6 +2431,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2210,6 +2450,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2239,6 +2480,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2256,6 +2498,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2274,6 +2517,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2306,6 +2550,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2326,6 +2571,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2344,6 +2590,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2373,6 +2620,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2390,6 +2638,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2408,6 +2657,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2440,6 +2690,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2460,6 +2711,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2478,6 +2730,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2510,6 +2763,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2530,6 +2784,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2548,6 +2803,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2577,6 +2833,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2594,6 +2851,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2612,6 +2870,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2644,6 +2903,7 @@ impl RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2661,7 +2921,11 @@ impl RocTagUnion {
         payload
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Returns which variant this tag union holds. Note that this never includes a payload!
     pub fn discriminant(&self) -> discriminant_RocTagUnion {
         unsafe {
@@ -2671,7 +2935,11 @@ impl RocTagUnion {
         }
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Internal helper
     fn set_discriminant(&mut self, discriminant: discriminant_RocTagUnion) {
         let discriminant_ptr: *mut discriminant_RocTagUnion = (self as *mut RocTagUnion).cast();
@@ -2686,6 +2954,7 @@ impl Drop for RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2724,6 +2993,7 @@ impl PartialEq for RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2759,6 +3029,7 @@ impl PartialOrd for RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2799,6 +3070,7 @@ impl Ord for RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2837,6 +3109,7 @@ impl Clone for RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2878,6 +3151,7 @@ impl core::hash::Hash for RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2920,6 +3194,7 @@ impl core::fmt::Debug for RocTagUnion {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -2986,6 +3261,7 @@ impl RocSingleTagPayload {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3004,6 +3280,7 @@ impl RocSingleTagPayload {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3036,6 +3313,7 @@ impl RocSingleTagPayload {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3056,6 +3334,7 @@ impl RocSingleTagPayload {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3074,6 +3353,7 @@ impl RocSingleTagPayload {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3106,6 +3386,7 @@ impl RocSingleTagPayload {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3123,7 +3404,11 @@ impl RocSingleTagPayload {
         &payload
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Returns which variant this tag union holds. Note that this never includes a payload!
     pub fn discriminant(&self) -> discriminant_RocSingleTagPayload {
         unsafe {
@@ -3133,7 +3418,11 @@ impl RocSingleTagPayload {
         }
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Internal helper
     fn set_discriminant(&mut self, discriminant: discriminant_RocSingleTagPayload) {
         let discriminant_ptr: *mut discriminant_RocSingleTagPayload =
@@ -3149,6 +3438,7 @@ impl Drop for RocSingleTagPayload {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3172,6 +3462,7 @@ impl PartialEq for RocSingleTagPayload {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3196,6 +3487,7 @@ impl PartialOrd for RocSingleTagPayload {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3223,6 +3515,7 @@ impl Ord for RocSingleTagPayload {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3250,6 +3543,7 @@ impl Clone for RocSingleTagPayload {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3276,6 +3570,7 @@ impl core::hash::Hash for RocSingleTagPayload {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3298,6 +3593,7 @@ impl core::fmt::Debug for RocSingleTagPayload {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3354,6 +3650,7 @@ impl U1 {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3367,6 +3664,7 @@ impl U1 {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3409,7 +3707,11 @@ impl U1 {
         &payload
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Returns which variant this tag union holds. Note that this never includes a payload!
     pub fn discriminant(&self) -> discriminant_U1 {
         unsafe {
@@ -3419,7 +3721,11 @@ impl U1 {
         }
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Internal helper
     fn set_discriminant(&mut self, discriminant: discriminant_U1) {
         let discriminant_ptr: *mut discriminant_U1 = (self as *mut U1).cast();
@@ -3429,7 +3735,11 @@ impl U1 {
         }
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// A tag named None, which has no payload.
     pub const None: Self = unsafe {
         let mut bytes = [0; core::mem::size_of::<U1>()];
@@ -3439,7 +3749,11 @@ impl U1 {
         core::mem::transmute::<[u8; core::mem::size_of::<U1>()], U1>(bytes)
     };
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Construct a tag named `Some`, with the appropriate payload
     pub fn Some(arg: u64) -> Self {
         let mut answer = Self { Some: arg };
@@ -3449,7 +3763,11 @@ impl U1 {
         answer
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Unsafely assume the given `U1` has a `.discriminant()` of `Some` and convert it to `Some`'s payload.
     /// (Always examine `.discriminant()` first to make sure this is the correct variant!)
     /// Panics in debug builds if the `.discriminant()` doesn't return `Some`.
@@ -3460,7 +3778,11 @@ impl U1 {
         payload
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Unsafely assume the given `U1` has a `.discriminant()` of `Some` and return its payload.
     /// (Always examine `.discriminant()` first to make sure this is the correct variant!)
     /// Panics in debug builds if the `.discriminant()` doesn't return `Some`.
@@ -3478,6 +3800,7 @@ impl PartialEq for U1 {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3500,6 +3823,7 @@ impl PartialOrd for U1 {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3523,6 +3847,7 @@ impl Ord for U1 {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3548,6 +3873,7 @@ impl Clone for U1 {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3574,6 +3900,7 @@ impl core::hash::Hash for U1 {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3593,6 +3920,7 @@ impl core::fmt::Debug for U1 {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3634,6 +3962,7 @@ impl RocStructFields {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3652,6 +3981,7 @@ impl RocStructFields {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3684,6 +4014,7 @@ impl RocStructFields {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3704,6 +4035,7 @@ impl RocStructFields {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3722,6 +4054,7 @@ impl RocStructFields {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3754,6 +4087,7 @@ impl RocStructFields {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3771,7 +4105,11 @@ impl RocStructFields {
         &payload
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Returns which variant this tag union holds. Note that this never includes a payload!
     pub fn discriminant(&self) -> discriminant_RocStructFields {
         unsafe {
@@ -3781,7 +4119,11 @@ impl RocStructFields {
         }
     }
 
-    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "x86_64"
+    ))]
     /// Internal helper
     fn set_discriminant(&mut self, discriminant: discriminant_RocStructFields) {
         let discriminant_ptr: *mut discriminant_RocStructFields =
@@ -3797,6 +4139,7 @@ impl Drop for RocStructFields {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3820,6 +4163,7 @@ impl PartialEq for RocStructFields {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3844,6 +4188,7 @@ impl PartialOrd for RocStructFields {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3871,6 +4216,7 @@ impl Ord for RocStructFields {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3896,6 +4242,7 @@ impl Clone for RocStructFields {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3922,6 +4269,7 @@ impl core::hash::Hash for RocStructFields {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
@@ -3944,6 +4292,7 @@ impl core::fmt::Debug for RocStructFields {
     #[cfg(any(
         target_arch = "arm",
         target_arch = "aarch64",
+        target_arch = "riscv64",
         target_arch = "wasm32",
         target_arch = "x86",
         target_arch = "x86_64"
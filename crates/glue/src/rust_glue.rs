@@ -3,7 +3,7 @@ use crate::types::{
     TypeId, Types,
 };
 use indexmap::IndexMap;
-use roc_target::{Architecture, TargetInfo};
+use roc_target::{Architecture, OperatingSystem, TargetInfo};
 use std::fmt::{Display, Write};
 
 pub static HEADER: &[u8] = include_bytes!("../templates/header.rs");
@@ -64,6 +64,13 @@ pub fn emit(types: &[Types]) -> Vec<File> {
     let mut buf = std::str::from_utf8(HEADER).unwrap().to_string();
     let mut impls: Impls = IndexMap::default();
 
+    // Only gate on the operating system when layouts were computed for more than one,
+    // so that single-OS glue keeps its plain `target_arch` gates.
+    let include_os = types
+        .iter()
+        .skip(1)
+        .any(|t| t.target().operating_system != types[0].target().operating_system);
+
     for types in types {
         for id in types.sorted_ids() {
             add_type(types.target(), id, types, &mut impls);
@@ -93,9 +100,9 @@ pub fn emit(types: &[Types]) -> Vec<File> {
 
             match targets.len() {
                 1 => {
-                    let arch = arch_to_str(targets.get(0).unwrap().architecture);
+                    let predicate = target_cfg(*targets.get(0).unwrap(), include_os);
 
-                    write!(buf, "#[cfg(target_arch = \"{arch}\")]").unwrap();
+                    write!(buf, "#[cfg({predicate})]").unwrap();
                 }
                 _ => {
                     // We should never have a decl recorded with 0 targets!
@@ -108,8 +115,8 @@ pub fn emit(types: &[Types]) -> Vec<File> {
                     while let Some(target_info) = it.next() {
                         write!(
                             buf,
-                            "{indent}{INDENT}target_arch = \"{}\"",
-                            arch_to_str(target_info.architecture)
+                            "{indent}{INDENT}{}",
+                            target_cfg(*target_info, include_os)
                         )
                         .unwrap();
 
@@ -2572,11 +2579,34 @@ fn arch_to_str(architecture: Architecture) -> &'static str {
         Architecture::X86_64 => "x86_64",
         Architecture::X86_32 => "x86",
         Architecture::Aarch64 => "aarch64",
+        Architecture::Riscv64 => "riscv64",
         Architecture::Aarch32 => "arm",
         Architecture::Wasm32 => "wasm32",
     }
 }
 
+fn os_to_cfg(operating_system: OperatingSystem) -> &'static str {
+    match operating_system {
+        OperatingSystem::Windows => "windows",
+        OperatingSystem::Unix => "unix",
+        OperatingSystem::Wasi => "target_os = \"wasi\"",
+        OperatingSystem::Freestanding => "target_os = \"none\"",
+    }
+}
+
+/// The `#[cfg(...)]` predicate which is true exactly when compiling for the given target.
+fn target_cfg(target_info: TargetInfo, include_os: bool) -> String {
+    let arch = arch_to_str(target_info.architecture);
+
+    if include_os {
+        let os = os_to_cfg(target_info.operating_system);
+
+        format!("all(target_arch = \"{arch}\", {os})")
+    } else {
+        format!("target_arch = \"{arch}\"")
+    }
+}
+
 fn write_indents(indentations: usize, buf: &mut String) {
     for _ in 0..indentations {
         buf.push_str(INDENT);
@@ -2586,7 +2616,7 @@ fn write_indents(indentations: usize, buf: &mut String) {
 fn max_pointer_tagged_variants(architecture: Architecture) -> usize {
     match architecture {
         // On a 64-bit system, pointers have 3 bits that are unused, so return 2^3 = 8
-        Architecture::X86_64 | Architecture::Aarch64 => 8,
+        Architecture::X86_64 | Architecture::Aarch64 | Architecture::Riscv64 => 8,
         // On a 32-bit system, pointers have 2 bits that are unused, so return 2^4 = 4
         Architecture::X86_32 | Architecture::Aarch32 | Architecture::Wasm32 => 4,
     }
@@ -2596,7 +2626,7 @@ fn max_pointer_tagged_variants(architecture: Architecture) -> usize {
 fn tagged_pointer_bitmask(architecture: Architecture) -> u8 {
     match architecture {
         // On a 64-bit system, pointers have 3 bits that are unused
-        Architecture::X86_64 | Architecture::Aarch64 => 0b0000_0111,
+        Architecture::X86_64 | Architecture::Aarch64 | Architecture::Riscv64 => 0b0000_0111,
         // On a 32-bit system, pointers have 2 bits that are unused
         Architecture::X86_32 | Architecture::Aarch32 | Architecture::Wasm32 => 0b0000_0011,
     }
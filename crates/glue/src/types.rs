@@ -8,6 +8,7 @@ use roc_builtins::bitcode::{
     IntWidth::{self, *},
 };
 use roc_collections::{MutMap, VecMap};
+use roc_gen_llvm::llvm::build::ThreadSafety;
 use roc_module::{
     ident::TagName,
     symbol::{Interns, Symbol},
@@ -65,6 +66,8 @@ pub struct Types {
     /// type declaration earlier in the file than where it gets referenced by another type.
     deps: VecMap<TypeId, Vec<TypeId>>,
     target: TargetInfo,
+    /// Whether the host may call the entry points from several threads at once.
+    thread_safe: bool,
 }
 
 impl Types {
@@ -87,6 +90,7 @@ impl Types {
             types_by_name: FnvHashMap::with_capacity_and_hasher(10, Default::default()),
             entry_points: Vec::new(),
             deps: VecMap::with_capacity(cap),
+            thread_safe: true,
         }
     }
 
@@ -98,9 +102,11 @@ impl Types {
         glue_procs_by_layout: MutMap<Layout<'a>, &'a [String]>,
         layout_cache: LayoutCache<'a>,
         target: TargetInfo,
+        thread_safety: ThreadSafety,
         mut entry_points: MutMap<Symbol, Variable>,
     ) -> Self {
         let mut types = Self::with_capacity(entry_points.len(), target);
+        types.thread_safe = thread_safety == ThreadSafety::ThreadSafe;
         let mut env = Env::new(
             arena,
             subs,
@@ -643,6 +649,10 @@ impl Types {
     pub fn target(&self) -> TargetInfo {
         self.target
     }
+
+    pub fn thread_safe(&self) -> bool {
+        self.thread_safe
+    }
 }
 
 impl From<&Types> for roc_type::Types {
@@ -672,6 +682,7 @@ impl From<&Types> for roc_type::Types {
             types: types.types.iter().map(roc_type::RocType::from).collect(),
             typesByName: types_by_name,
             target: types.target.into(),
+            threadSafe: types.thread_safe,
         }
     }
 }
@@ -920,6 +931,7 @@ impl From<Architecture> for roc_type::Architecture {
         match arch {
             Architecture::Aarch32 => roc_type::Architecture::Aarch32,
             Architecture::Aarch64 => roc_type::Architecture::Aarch64,
+            Architecture::Riscv64 => roc_type::Architecture::Riscv64,
             Architecture::Wasm32 => roc_type::Architecture::Wasm32,
             Architecture::X86_32 => roc_type::Architecture::X86x32,
             Architecture::X86_64 => roc_type::Architecture::X86x64,
@@ -933,6 +945,7 @@ impl From<OperatingSystem> for roc_type::OperatingSystem {
             OperatingSystem::Windows => roc_type::OperatingSystem::Windows,
             OperatingSystem::Unix => roc_type::OperatingSystem::Unix,
             OperatingSystem::Wasi => roc_type::OperatingSystem::Wasi,
+            OperatingSystem::Freestanding => unreachable!("glue targets are never bare metal"),
         }
     }
 }
@@ -3,10 +3,16 @@ mod test_glue;
 #[no_mangle]
 pub extern "C" fn rust_main() -> i32 {
     let record = test_glue::mainForHost();
-    let answer1 = record.f.force_thunk(42i64, 1);
-    let answer2 = record.g.force_thunk(42i64, 1);
 
-    println!("Answer was: {:?} {:?}", answer1, answer2);
+    // `call` keeps the function, so it can be called again later
+    let answer1 = record.f.call(42i64, 1);
+    let answer2 = record.f.call(answer1, 1);
+
+    // the program is thread-safe, so its functions can be called from other threads
+    let g = record.g.clone();
+    let answer3 = std::thread::spawn(move || g.force_thunk(42i64, 1)).join().unwrap();
+
+    println!("Answer was: {:?} {:?} {:?}", answer1, answer2, answer3);
 
     // Exit code
     0
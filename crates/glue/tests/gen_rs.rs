@@ -2,7 +2,7 @@ mod helpers;
 
 #[cfg(test)]
 mod test_gen_rs {
-    use crate::helpers::generate_bindings;
+    use crate::helpers::{generate_bindings, generate_bindings_for_targets};
     use indoc::indoc;
     use pretty_assertions::assert_eq;
     use roc_glue::rust_glue::HEADER;
@@ -30,6 +30,7 @@ mod test_gen_rs {
                     #[cfg(any(
                         target_arch = "arm",
                         target_arch = "aarch64",
+                        target_arch = "riscv64",
                         target_arch = "wasm32",
                         target_arch = "x86",
                         target_arch = "x86_64"
@@ -46,6 +47,44 @@ mod test_gen_rs {
         );
     }
 
+    #[test]
+    fn record_for_multiple_operating_systems() {
+        let module = indoc!(
+            r#"
+            MyRcd : { a : U64, b : U8 }
+
+            main : MyRcd
+            main = { a: 1u64, b: 2u8 }
+            "#
+        );
+
+        let targets = ["x86_64-linux", "x86_64-windows"]
+            .map(|target| roc_glue::load::parse_target(target).unwrap());
+
+        let full_header = std::str::from_utf8(HEADER).unwrap().to_string() + "\n";
+        assert_eq!(
+            generate_bindings_for_targets(module, &targets),
+            vec![File {
+                name: "mod.rs".to_string(),
+                content: full_header
+                    + indoc!(
+                        r#"
+                    #[cfg(any(
+                        all(target_arch = "x86_64", windows),
+                        all(target_arch = "x86_64", unix)
+                    ))]
+                    #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+                    #[repr(C)]
+                    pub struct MyRcd {
+                        pub a: u64,
+                        pub b: u8,
+                    }
+                    "#
+                    )
+            }]
+        );
+    }
+
     #[test]
     fn nested_record_aliased() {
         let module = indoc!(
@@ -83,6 +122,7 @@ mod test_gen_rs {
                     #[cfg(any(
                         target_arch = "arm",
                         target_arch = "aarch64",
+                        target_arch = "riscv64",
                         target_arch = "wasm32",
                         target_arch = "x86",
                         target_arch = "x86_64"
@@ -96,6 +136,7 @@ mod test_gen_rs {
 
                     #[cfg(any(
                         target_arch = "aarch64",
+                        target_arch = "riscv64",
                         target_arch = "x86_64"
                     ))]
                     #[derive(Clone, Debug, Default, PartialEq, PartialOrd)]
@@ -126,6 +167,7 @@ mod test_gen_rs {
                     #[cfg(any(
                         target_arch = "arm",
                         target_arch = "aarch64",
+                        target_arch = "riscv64",
                         target_arch = "wasm32",
                         target_arch = "x86",
                         target_arch = "x86_64"
@@ -170,6 +212,7 @@ mod test_gen_rs {
                     #[cfg(any(
                         target_arch = "arm",
                         target_arch = "aarch64",
+                        target_arch = "riscv64",
                         target_arch = "wasm32",
                         target_arch = "x86",
                         target_arch = "x86_64"
@@ -183,6 +226,7 @@ mod test_gen_rs {
 
                     #[cfg(any(
                         target_arch = "aarch64",
+                        target_arch = "riscv64",
                         target_arch = "x86_64"
                     ))]
                     #[derive(Clone, Debug, Default, PartialEq, PartialOrd)]
@@ -220,6 +264,7 @@ mod test_gen_rs {
                     #[cfg(any(
                         target_arch = "arm",
                         target_arch = "aarch64",
+                        target_arch = "riscv64",
                         target_arch = "wasm32",
                         target_arch = "x86",
                         target_arch = "x86_64"
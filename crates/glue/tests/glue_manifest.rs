@@ -0,0 +1,81 @@
+mod helpers;
+
+#[cfg(test)]
+mod test_glue_manifest {
+    use crate::helpers::load_types_for_targets;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+    use roc_glue::manifest::{self, MANIFEST_FILE_NAME};
+    use serde_json::{json, Value};
+
+    #[test]
+    fn entry_point_layouts_per_target() {
+        let module = indoc!(
+            r#"
+            main : Str, U8 -> List F64
+            main = \_, _ -> []
+            "#
+        );
+
+        let targets =
+            ["x86_64-linux", "wasm32"].map(|target| roc_glue::load::parse_target(target).unwrap());
+        let file = manifest::emit(&load_types_for_targets(module, &targets));
+
+        assert_eq!(file.name, MANIFEST_FILE_NAME);
+
+        let manifest: Value = serde_json::from_str(&file.content).unwrap();
+        let entry_points = |architecture: &str| {
+            manifest["targets"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|target| target["architecture"] == architecture)
+                .unwrap()["entry_points"]
+                .clone()
+        };
+
+        assert_eq!(
+            entry_points("x86_64"),
+            json!([{
+                "name": "main",
+                "symbol": "roc__main_1_exposed_generic",
+                "returns_through_pointer": true,
+                "arguments": [
+                    {
+                        "type": "Str",
+                        "size": 24,
+                        "align": 8,
+                        "abi_class": "aggregate",
+                        "refcounted": true,
+                        "ownership": "consumed",
+                    },
+                    {
+                        "type": "U8",
+                        "size": 1,
+                        "align": 1,
+                        "abi_class": "integer",
+                        "refcounted": false,
+                        "ownership": "none",
+                    },
+                ],
+                "return": {
+                    "type": "List F64",
+                    "size": 24,
+                    "align": 8,
+                    "abi_class": "aggregate",
+                    "refcounted": true,
+                    "ownership": "owned",
+                },
+            }])
+        );
+
+        let wasm_sizes: Vec<&Value> = entry_points("wasm32")[0]["arguments"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|argument| &argument["size"])
+            .collect();
+
+        assert_eq!(wasm_sizes, [&json!(12), &json!(1)]);
+    }
+}
@@ -1,7 +1,9 @@
 use indoc::indoc;
-use roc_glue::load::{load_types, IgnoreErrors};
+use roc_glue::load::{host_os_targets, load_types, IgnoreErrors};
 use roc_glue::rust_glue;
+use roc_glue::types::Types;
 use roc_load::Threading;
+use roc_target::TargetInfo;
 use std::env;
 use std::fs::File;
 use std::io::Write;
@@ -9,6 +11,19 @@ use std::path::PathBuf;
 
 #[allow(dead_code)]
 pub fn generate_bindings(decl_src: &str) -> Vec<roc_glue::types::File> {
+    generate_bindings_for_targets(decl_src, &host_os_targets())
+}
+
+#[allow(dead_code)]
+pub fn generate_bindings_for_targets(
+    decl_src: &str,
+    targets: &[TargetInfo],
+) -> Vec<roc_glue::types::File> {
+    rust_glue::emit(&load_types_for_targets(decl_src, targets))
+}
+
+#[allow(dead_code)]
+pub fn load_types_for_targets(decl_src: &str, targets: &[TargetInfo]) -> Vec<Types> {
     use tempfile::tempdir;
 
     let mut src = indoc!(
@@ -26,27 +41,24 @@ pub fn generate_bindings(decl_src: &str) -> Vec<roc_glue::types::File> {
 
     src.push_str(decl_src);
 
-    let types = {
-        let dir = tempdir().expect("Unable to create tempdir");
-        let filename = PathBuf::from("platform.roc");
-        let file_path = dir.path().join(filename);
-        let full_file_path = file_path.clone();
-        let mut file = File::create(file_path).unwrap();
-        writeln!(file, "{}", &src).unwrap();
-
-        let result = load_types(
-            full_file_path,
-            Threading::Single,
-            // required `nothing` is unused; that error is okay
-            IgnoreErrors { can: true },
-        );
+    let dir = tempdir().expect("Unable to create tempdir");
+    let filename = PathBuf::from("platform.roc");
+    let file_path = dir.path().join(filename);
+    let full_file_path = file_path.clone();
+    let mut file = File::create(file_path).unwrap();
+    writeln!(file, "{}", &src).unwrap();
 
-        dir.close().expect("Unable to close tempdir");
+    let result = load_types(
+        full_file_path,
+        Threading::Single,
+        // required `nothing` is unused; that error is okay
+        IgnoreErrors { can: true },
+        targets,
+    );
 
-        result.expect("had problems loading")
-    };
+    dir.close().expect("Unable to close tempdir");
 
-    rust_glue::emit(&types)
+    result.expect("had problems loading")
 }
 
 #[allow(dead_code)]
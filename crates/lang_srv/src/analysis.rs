@@ -0,0 +1,274 @@
+//! Answers the questions an editor asks about a document, using the same compiler
+//! stages as `roc check` and `roc format`.
+use bumpalo::Bump;
+use roc_fmt::def::fmt_defs;
+use roc_fmt::module::fmt_module;
+use roc_fmt::Buf;
+use roc_load::{LoadedModule, LoadingProblem};
+use roc_packaging::cache::{self, RocCacheDir};
+use roc_parse::{
+    ast::Defs,
+    module::{self, module_defs},
+    parser::Parser,
+    ranges::{self, FoldKind},
+    recovery::module_defs_recovering,
+    state::State,
+};
+use roc_problem::Severity;
+use roc_region::all::{LineColumn, LineColumnRegion, LineInfo, Position, Region};
+use roc_reporting::report::{
+    can_problem, type_problem, CiWrite, RenderTarget, Report, RocDocAllocator, DEFAULT_PALETTE,
+};
+use serde_json::{json, Value};
+use std::path::Path;
+
+// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#diagnosticSeverity
+pub(crate) const DIAGNOSTIC_ERROR: u8 = 1;
+const DIAGNOSTIC_WARNING: u8 = 2;
+
+/// Type-checks `source` as the contents of the module at `path`, in the project rooted in
+/// `src_dir`, and returns its problems as LSP `Diagnostic`s. Imported modules are read from disk.
+pub fn diagnostics(path: &Path, source: &str, src_dir: &Path) -> Vec<Value> {
+    let arena = Bump::new();
+    let target_info = roc_target::TargetInfo::from(&target_lexicon::Triple::host());
+
+    // This is the same package cache the CLI uses, so packages that `roc check` or `roc build`
+    // already downloaded don't get downloaded again, and vice versa.
+    let cache_dir = cache::roc_cache_dir();
+
+    let loaded = roc_load::load_and_typecheck_str(
+        &arena,
+        path.to_path_buf(),
+        arena.alloc_str(source),
+        src_dir.to_path_buf(),
+        target_info,
+        RenderTarget::Generic,
+        RocCacheDir::Persistent(cache_dir.as_path()),
+        DEFAULT_PALETTE,
+    );
+
+    let src_lines: Vec<&str> = source.split('\n').collect();
+
+    match loaded {
+        Ok(loaded) => module_diagnostics(loaded),
+        // Syntax errors and missing imports end up here, already rendered.
+        Err(LoadingProblem::FormattedReport(report)) => vec![diagnostic(
+            &src_lines,
+            LineColumnRegion::zero(),
+            DIAGNOSTIC_ERROR,
+            report,
+        )],
+        Err(other) => vec![diagnostic(
+            &src_lines,
+            LineColumnRegion::zero(),
+            DIAGNOSTIC_ERROR,
+            format!("{:?}", other),
+        )],
+    }
+}
+
+pub(crate) fn module_diagnostics(mut loaded: LoadedModule) -> Vec<Value> {
+    let home = loaded.module_id;
+    let (module_path, src) = match loaded.sources.get(&home) {
+        Some(source) => source,
+        None => return Vec::new(),
+    };
+
+    let src_lines: Vec<&str> = src.split('\n').collect();
+    let lines = LineInfo::new(src);
+    let alloc = RocDocAllocator::new(&src_lines, home, &loaded.interns);
+    let mut diagnostics = Vec::new();
+
+    for problem in loaded.can_problems.remove(&home).unwrap_or_default() {
+        let region = problem.region();
+        let report = can_problem(&alloc, &lines, module_path.clone(), problem);
+
+        diagnostics.push(report_diagnostic(&src_lines, &lines, region, report));
+    }
+
+    for problem in loaded.type_problems.remove(&home).unwrap_or_default() {
+        let region = problem.region();
+
+        if let Some(report) = type_problem(&alloc, &lines, module_path.clone(), problem) {
+            diagnostics.push(report_diagnostic(&src_lines, &lines, region, report));
+        }
+    }
+
+    diagnostics
+}
+
+fn report_diagnostic(
+    src_lines: &[&str],
+    lines: &LineInfo,
+    region: Option<Region>,
+    report: Report,
+) -> Value {
+    let severity = match report.severity {
+        Severity::Warning => DIAGNOSTIC_WARNING,
+        Severity::RuntimeError | Severity::Fatal => DIAGNOSTIC_ERROR,
+    };
+    let range = region
+        .map(|region| lines.convert_region(region))
+        .unwrap_or_else(LineColumnRegion::zero);
+
+    // The editor already shows which file the problem is in, so instead of the header
+    // `roc check` prints (which includes the path), start with just the title.
+    let mut message = report.title.clone();
+    message.push_str("\n\n");
+    report
+        .doc
+        .1
+        .render_raw(70, &mut CiWrite::new(&mut message))
+        .expect("<buffer is not a utf-8 encoded string>");
+
+    diagnostic(src_lines, range, severity, message)
+}
+
+pub(crate) fn diagnostic(
+    src_lines: &[&str],
+    range: LineColumnRegion,
+    severity: u8,
+    message: String,
+) -> Value {
+    json!({
+        "range": {
+            "start": position(src_lines, range.start),
+            "end": position(src_lines, range.end),
+        },
+        "severity": severity,
+        "source": "roc",
+        "message": message,
+    })
+}
+
+/// LSP positions count UTF-16 code units, whereas our columns count bytes.
+pub(crate) fn position(src_lines: &[&str], line_column: LineColumn) -> Value {
+    let column = line_column.column as usize;
+    let character = match src_lines.get(line_column.line as usize) {
+        Some(line) => match line.get(..column) {
+            Some(before) => before.encode_utf16().count(),
+            None => column,
+        },
+        None => column,
+    };
+
+    json!({ "line": line_column.line, "character": character })
+}
+
+/// Formats `source` the way `roc format` would. Returns `None` if it doesn't parse,
+/// or if the formatted code doesn't parse, so a formatter bug can't break the document.
+pub fn format(source: &str) -> Option<String> {
+    let arena = Bump::new();
+    let formatted = format_in(&arena, source)?;
+
+    format_in(&arena, formatted)?;
+
+    Some(formatted.to_owned())
+}
+
+fn format_in<'a>(arena: &'a Bump, source: &'a str) -> Option<&'a str> {
+    let (header, state) = module::parse_header(arena, State::new(source.as_bytes())).ok()?;
+    let (_, defs, _) = module_defs().parse(arena, state, 0).ok()?;
+    let mut buf = Buf::new_in(arena);
+
+    fmt_module(&mut buf, &header);
+    fmt_defs(&mut buf, &defs, 0);
+    buf.fmt_end_of_file();
+
+    Some(buf.into_bump_str())
+}
+
+/// The parts of `source` an editor can fold, as LSP `FoldingRange`s. The defs that don't parse
+/// are left out, and so is everything if the header doesn't parse.
+pub fn folding_ranges(source: &str) -> Vec<Value> {
+    let arena = Bump::new();
+    let defs = match parse_recovering(&arena, source) {
+        Some(defs) => defs,
+        None => return Vec::new(),
+    };
+    let lines = LineInfo::new(source);
+
+    ranges::folding_ranges(source, &defs)
+        .into_iter()
+        .map(|fold| {
+            let region = lines.convert_region(fold.region);
+            let mut range = json!({ "startLine": region.start.line, "endLine": region.end.line });
+
+            if fold.kind == FoldKind::Comment {
+                range["kind"] = json!("comment");
+            }
+
+            range
+        })
+        .collect()
+}
+
+/// For each of the LSP `positions`, the nested regions of `source` around it, as an LSP
+/// `SelectionRange` whose parents are the ever bigger regions around it.
+pub fn selection_ranges(source: &str, positions: &[Value]) -> Vec<Value> {
+    let arena = Bump::new();
+    let defs = parse_recovering(&arena, source).unwrap_or_default();
+    let src_lines: Vec<&str> = source.split('\n').collect();
+    let lines = LineInfo::new(source);
+
+    positions
+        .iter()
+        .map(|position| {
+            let offset = offset(&src_lines, position);
+            let mut selection = json!({ "range": { "start": position, "end": position } });
+            let mut parent: Option<Value> = None;
+
+            // build the chain from the outermost region in
+            for region in ranges::selection_ranges(&defs, offset).into_iter().rev() {
+                let region = lines.convert_region(region);
+
+                selection = json!({
+                    "range": {
+                        "start": self::position(&src_lines, region.start),
+                        "end": self::position(&src_lines, region.end),
+                    },
+                });
+
+                if let Some(parent) = parent.take() {
+                    selection["parent"] = parent;
+                }
+
+                parent = Some(selection.clone());
+            }
+
+            selection
+        })
+        .collect()
+}
+
+fn parse_recovering<'a>(arena: &'a Bump, source: &'a str) -> Option<Defs<'a>> {
+    let (_, state) = module::parse_header(arena, State::new(source.as_bytes())).ok()?;
+
+    Some(module_defs_recovering(arena, state).defs)
+}
+
+/// The inverse of [position]: where an LSP position is in the source.
+fn offset(src_lines: &[&str], position: &Value) -> Position {
+    let line = position["line"].as_u64().unwrap_or_default() as usize;
+    let character = position["character"].as_u64().unwrap_or_default() as usize;
+
+    // the lines before this one, and the newlines after them
+    let mut offset: usize = src_lines.iter().take(line).map(|line| line.len() + 1).sum();
+
+    if let Some(line) = src_lines.get(line) {
+        let mut units = 0;
+
+        for (index, c) in line.char_indices() {
+            if units >= character {
+                offset += index;
+                return Position::new(offset as u32);
+            }
+
+            units += c.len_utf16();
+        }
+
+        offset += line.len();
+    }
+
+    Position::new(offset as u32)
+}
@@ -0,0 +1,531 @@
+//! A language server for Roc, which editors talk to over stdin and stdout.
+//!
+//! It ships inside the `roc` binary (as `roc lsp`), so editors don't need a separately built
+//! server, and it always understands the same version of Roc as the compiler next to it.
+mod analysis;
+mod lsif;
+mod rpc;
+mod workspace;
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use workspace::Workspace;
+
+const VERSION: &str = include_str!("../../../version.txt");
+
+// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#errorCodes
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// `TextDocumentSyncKind.Full`: the editor sends the whole document whenever it changes.
+const SYNC_FULL: u8 = 1;
+
+pub fn main() -> i32 {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+
+    match Server::default().run(&mut stdin.lock(), &mut stdout.lock()) {
+        Ok(exit_code) => exit_code,
+        Err(error) => {
+            eprintln!("roc lsp stopped because of an I/O error: {}", error);
+
+            1
+        }
+    }
+}
+
+/// Writes an LSIF dump of every module under `root` to `output`, for `roc index --lsif`.
+pub fn index(root: &Path, output: &Path) -> i32 {
+    let result = File::create(output).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        let indexed = lsif::export(root, &mut writer)?;
+
+        writer.flush()?;
+
+        Ok(indexed)
+    });
+
+    match result {
+        Ok(indexed) => {
+            println!(
+                "Indexed {} {} into {}.",
+                indexed,
+                if indexed == 1 { "module" } else { "modules" },
+                output.display()
+            );
+
+            0
+        }
+        Err(error) => {
+            eprintln!("roc index failed because of an I/O error: {}", error);
+
+            1
+        }
+    }
+}
+
+#[derive(Default)]
+struct Server {
+    /// The latest text of each open document, by URI. Editors own these
+    /// documents while they're open, so we never read them from disk.
+    documents: HashMap<String, String>,
+    workspace: Workspace,
+    shutdown_requested: bool,
+}
+
+impl Server {
+    fn run<R: BufRead, W: Write>(&mut self, reader: &mut R, writer: &mut W) -> io::Result<i32> {
+        while let Some(message) = rpc::read_message(reader)? {
+            let method = message["method"].as_str().unwrap_or_default();
+            let params = &message["params"];
+
+            match message.get("id") {
+                Some(id) => {
+                    let response = match self.handle_request(method, params) {
+                        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                        Err((code, error)) => json!({
+                            "jsonrpc": "2.0",
+                            "id": id,
+                            "error": { "code": code, "message": error },
+                        }),
+                    };
+
+                    rpc::write_message(writer, &response)?;
+                }
+                None if method == "exit" => {
+                    // The spec asks for exit code 1 if the client never sent `shutdown`.
+                    return Ok(if self.shutdown_requested { 0 } else { 1 });
+                }
+                None => {
+                    for notification in self.handle_notification(method, params) {
+                        rpc::write_message(writer, &notification)?;
+                    }
+                }
+            }
+        }
+
+        // The client went away without sending `exit`.
+        Ok(1)
+    }
+
+    fn handle_request(&mut self, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+        match method {
+            "initialize" => {
+                self.workspace = Workspace::from_initialize(params);
+
+                Ok(json!({
+                    "capabilities": {
+                        "textDocumentSync": { "openClose": true, "change": SYNC_FULL, "save": true },
+                        "documentFormattingProvider": true,
+                        "foldingRangeProvider": true,
+                        "selectionRangeProvider": true,
+                        "workspace": {
+                            "workspaceFolders": { "supported": true, "changeNotifications": true },
+                        },
+                    },
+                    "serverInfo": { "name": "roc", "version": VERSION.trim() },
+                }))
+            }
+            "shutdown" => {
+                self.shutdown_requested = true;
+
+                Ok(Value::Null)
+            }
+            "textDocument/formatting" => {
+                let text = self.open_document(params)?;
+
+                // Leave documents that don't parse (or are already formatted) alone.
+                let edits = match analysis::format(text) {
+                    Some(formatted) if &formatted != text => vec![json!({
+                        "range": { "start": { "line": 0, "character": 0 }, "end": end_of(text) },
+                        "newText": formatted,
+                    })],
+                    _ => Vec::new(),
+                };
+
+                Ok(Value::Array(edits))
+            }
+            "textDocument/foldingRange" => {
+                let text = self.open_document(params)?;
+
+                Ok(Value::Array(analysis::folding_ranges(text)))
+            }
+            "textDocument/selectionRange" => {
+                let text = self.open_document(params)?;
+                let positions = params["positions"]
+                    .as_array()
+                    .ok_or_else(|| (INVALID_PARAMS, "there are no positions".to_string()))?;
+
+                Ok(Value::Array(analysis::selection_ranges(text, positions)))
+            }
+            _ => Err((
+                METHOD_NOT_FOUND,
+                format!("roc lsp does not support `{}`", method),
+            )),
+        }
+    }
+
+    /// The text of the document a request is about, which must be open.
+    fn open_document(&self, params: &Value) -> Result<&String, (i64, String)> {
+        document_uri(params)
+            .and_then(|uri| self.documents.get(uri))
+            .ok_or_else(|| (INVALID_PARAMS, "that document is not open".to_string()))
+    }
+
+    /// Returns the notifications to send back, which are always diagnostics.
+    fn handle_notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        if method == "workspace/didChangeWorkspaceFolders" {
+            self.workspace.change(&params["event"]);
+
+            // Documents may now belong to other projects
+            let mut uris: Vec<&String> = self.documents.keys().collect();
+
+            uris.sort();
+
+            return uris.into_iter().map(|uri| self.check(uri)).collect();
+        }
+
+        let uri = match document_uri(params) {
+            Some(uri) => uri.to_string(),
+            None => return Vec::new(),
+        };
+
+        match method {
+            "textDocument/didOpen" => {
+                if let Some(text) = params["textDocument"]["text"].as_str() {
+                    self.documents.insert(uri.clone(), text.to_string());
+                }
+            }
+            "textDocument/didChange" => {
+                // With full sync, the last change is the whole document.
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+
+                if let Some(text) = text {
+                    self.documents.insert(uri.clone(), text.to_string());
+                }
+            }
+            // Other modules are read from disk, so saving can change the diagnostics of the
+            // documents that import the saved one. Those can only be in the same project.
+            "textDocument/didSave" => {
+                let root = match uri_to_path(&uri) {
+                    Some(path) => self.workspace.project_root(&path),
+                    None => return vec![self.check(&uri)],
+                };
+                let mut uris: Vec<&String> = self
+                    .documents
+                    .keys()
+                    .filter(|other| {
+                        uri_to_path(other)
+                            .map_or(false, |path| self.workspace.project_root(&path) == root)
+                    })
+                    .collect();
+
+                uris.sort();
+
+                return uris.into_iter().map(|uri| self.check(uri)).collect();
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+
+                return vec![publish_diagnostics(&uri, Vec::new())];
+            }
+            _ => return Vec::new(),
+        }
+
+        vec![self.check(&uri)]
+    }
+
+    /// The diagnostics of an open document, checked as part of the project it's in.
+    fn check(&self, uri: &str) -> Value {
+        let diagnostics = match (uri_to_path(uri), self.documents.get(uri)) {
+            (Some(path), Some(text)) => {
+                let src_dir = self.workspace.project_root(&path);
+
+                analysis::diagnostics(&path, text, &src_dir)
+            }
+            _ => Vec::new(),
+        };
+
+        publish_diagnostics(uri, diagnostics)
+    }
+}
+
+fn document_uri(params: &Value) -> Option<&str> {
+    params["textDocument"]["uri"].as_str()
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+/// The LSP position just past the last character of `text`.
+fn end_of(text: &str) -> Value {
+    let line = text.matches('\n').count();
+    let last_line = text.rsplit('\n').next().unwrap_or_default();
+
+    json!({ "line": line, "character": last_line.encode_utf16().count() })
+}
+
+/// Only `file:` URIs are supported, since the compiler needs to find imported modules on disk.
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let mut rest = uri.strip_prefix("file://")?.as_bytes();
+    let mut bytes = Vec::with_capacity(rest.len());
+
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = match tail {
+            [high, low, ..] if byte == b'%' => std::str::from_utf8(&[*high, *low])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+
+        match escaped {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+
+    String::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+/// The inverse of [uri_to_path], for an absolute path.
+fn path_to_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'.' | b'_' | b'~' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(messages: &[Value]) -> (i32, Vec<Value>) {
+        let mut input = Vec::new();
+
+        for message in messages {
+            rpc::write_message(&mut input, message).unwrap();
+        }
+
+        let mut output = Vec::new();
+        let exit_code = Server::default()
+            .run(&mut input.as_slice(), &mut output)
+            .unwrap();
+
+        let mut reader = output.as_slice();
+        let mut responses = Vec::new();
+
+        while let Some(response) = rpc::read_message(&mut reader).unwrap() {
+            responses.push(response);
+        }
+
+        (exit_code, responses)
+    }
+
+    #[test]
+    fn uri_to_path_decodes_escapes() {
+        assert_eq!(
+            uri_to_path("file:///home/me/my%20app/main.roc"),
+            Some(PathBuf::from("/home/me/my app/main.roc"))
+        );
+        assert_eq!(uri_to_path("untitled:Untitled-1"), None);
+    }
+
+    #[test]
+    fn path_to_uri_escapes() {
+        let path = Path::new("/home/me/my app/main.roc");
+
+        assert_eq!(path_to_uri(path), "file:///home/me/my%20app/main.roc");
+        assert_eq!(uri_to_path(&path_to_uri(path)).as_deref(), Some(path));
+    }
+
+    #[test]
+    fn exit_code_depends_on_shutdown() {
+        let (exit_code, responses) = session(&[
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "shutdown" }),
+            json!({ "jsonrpc": "2.0", "method": "exit" }),
+        ]);
+
+        assert_eq!(exit_code, 0);
+        assert_eq!(responses.len(), 2);
+        assert_eq!(
+            responses[0]["result"]["capabilities"]["documentFormattingProvider"],
+            json!(true)
+        );
+
+        let (exit_code, _) = session(&[json!({ "jsonrpc": "2.0", "method": "exit" })]);
+
+        assert_eq!(exit_code, 1);
+    }
+
+    #[test]
+    fn documents_are_checked_in_their_own_project() {
+        let dir = std::env::temp_dir().join(format!("roc_lsp_workspace_{}", std::process::id()));
+        let hello = dir.join("hello");
+        let goodbye = dir.join("goodbye");
+
+        for (app, exposed) in [(&hello, "greeting"), (&goodbye, "farewell")] {
+            std::fs::create_dir_all(app.join("Sub")).unwrap();
+            std::fs::write(app.join("main.roc"), "").unwrap();
+            std::fs::write(
+                app.join("Text.roc"),
+                format!("interface Text exposes [{exposed}] imports []\n\n{exposed} = \"Hi\"\n"),
+            )
+            .unwrap();
+        }
+
+        // The same module, which only type-checks in the `hello` project
+        let source =
+            "interface Sub.Greet exposes [greet] imports [Text]\n\ngreet = Text.greeting\n";
+        let hello_uri = path_to_uri(&hello.join("Sub").join("Greet.roc"));
+        let goodbye_uri = path_to_uri(&goodbye.join("Sub").join("Greet.roc"));
+        let did_open = |uri: &str| {
+            json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/didOpen",
+                "params": { "textDocument": { "uri": uri, "text": source } },
+            })
+        };
+
+        let (_, responses) = session(&[
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": { "workspaceFolders": [{ "uri": path_to_uri(&hello), "name": "hello" }] },
+            }),
+            json!({
+                "jsonrpc": "2.0",
+                "method": "workspace/didChangeWorkspaceFolders",
+                "params": {
+                    "event": {
+                        "added": [{ "uri": path_to_uri(&goodbye), "name": "goodbye" }],
+                        "removed": [],
+                    },
+                },
+            }),
+            did_open(&hello_uri),
+            did_open(&goodbye_uri),
+        ]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            responses[0]["result"]["capabilities"]["workspace"]["workspaceFolders"]["supported"],
+            json!(true)
+        );
+        assert_eq!(responses[1]["params"]["uri"], json!(hello_uri));
+        assert_eq!(responses[1]["params"]["diagnostics"], json!([]));
+        assert_eq!(responses[2]["params"]["uri"], json!(goodbye_uri));
+        assert_ne!(responses[2]["params"]["diagnostics"], json!([]));
+    }
+
+    #[test]
+    fn formatting_replaces_the_whole_document() {
+        let uri = "untitled:Untitled-1";
+        let (_, responses) = session(&[
+            json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/didOpen",
+                "params": { "textDocument": { "uri": uri, "text": "interface Foo exposes [] imports []\n\nx  =  1" } },
+            }),
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "textDocument/formatting",
+                "params": { "textDocument": { "uri": uri } },
+            }),
+        ]);
+
+        // The document isn't a file, so it can't be checked and its diagnostics are empty.
+        assert_eq!(responses[0]["params"]["diagnostics"], json!([]));
+        assert_eq!(
+            responses[1]["result"],
+            json!([{
+                "range": {
+                    "start": { "line": 0, "character": 0 },
+                    "end": { "line": 2, "character": 7 },
+                },
+                "newText": "interface Foo exposes [] imports []\n\nx = 1\n",
+            }])
+        );
+    }
+
+    #[test]
+    fn folds_and_selects_the_structure_of_a_document() {
+        let uri = "untitled:Untitled-1";
+        let (_, responses) = session(&[
+            json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/didOpen",
+                "params": { "textDocument": { "uri": uri, "text": "interface Foo exposes [] imports []\n\nx =\n    [\n        1,\n    ]\n" } },
+            }),
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "textDocument/foldingRange",
+                "params": { "textDocument": { "uri": uri } },
+            }),
+            json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "textDocument/selectionRange",
+                "params": {
+                    "textDocument": { "uri": uri },
+                    "positions": [{ "line": 4, "character": 8 }],
+                },
+            }),
+        ]);
+
+        assert_eq!(
+            responses[1]["result"],
+            json!([{ "startLine": 2, "endLine": 5 }, { "startLine": 3, "endLine": 5 }])
+        );
+        assert_eq!(
+            responses[2]["result"],
+            json!([{
+                "range": {
+                    "start": { "line": 4, "character": 8 },
+                    "end": { "line": 4, "character": 9 },
+                },
+                "parent": {
+                    "range": {
+                        "start": { "line": 3, "character": 4 },
+                        "end": { "line": 5, "character": 5 },
+                    },
+                    "parent": {
+                        "range": {
+                            "start": { "line": 2, "character": 0 },
+                            "end": { "line": 5, "character": 5 },
+                        },
+                    },
+                },
+            }])
+        );
+    }
+}
@@ -0,0 +1,580 @@
+//! Writes what `roc lsp` knows about every module in a workspace to an
+//! [LSIF](https://microsoft.github.io/language-server-protocol/specifications/lsif/0.4.0/specification/)
+//! dump, so code browsers can show definitions, references, hovers and diagnostics
+//! without running a language server.
+use crate::analysis::{self, DIAGNOSTIC_ERROR};
+use crate::path_to_uri;
+use bumpalo::Bump;
+use roc_can::expr::Expr;
+use roc_can::pattern::{DestructType, Pattern, RecordDestruct};
+use roc_can::traverse::{walk_expr, walk_pattern, walk_record_destruct, Visitor};
+use roc_load::{ExecutionMode, LoadConfig, LoadedModule, LoadingProblem, Threading};
+use roc_module::symbol::{Interns, ModuleId, Symbol};
+use roc_packaging::cache::{self, RocCacheDir};
+use roc_region::all::{LineColumnRegion, LineInfo, Region};
+use roc_reporting::report::{RenderTarget, DEFAULT_PALETTE};
+use roc_types::pretty_print::{name_and_print_var, DebugPrint};
+use roc_types::subs::Variable;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const LSIF_VERSION: &str = "0.4.3";
+
+/// Indexes every .roc file under `root`, and writes the dump to `out`, one JSON value per line.
+/// Returns how many modules were indexed.
+pub fn export<W: Write>(root: &Path, out: &mut W) -> io::Result<usize> {
+    let root = fs::canonicalize(root)?;
+    let mut paths = Vec::new();
+
+    roc_files(&root, &mut paths)?;
+    paths.sort();
+
+    let documents: Vec<Document> = paths
+        .iter()
+        .enumerate()
+        .map(|(index, path)| index_module(index, path))
+        .collect();
+
+    Dump::default().write(&root, &documents, out)?;
+
+    Ok(documents.len())
+}
+
+/// Finds .roc files recursively, skipping hidden directories like `.git`.
+fn roc_files(dir: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map_or(false, |name| name.starts_with('.'));
+
+        if path.is_dir() && !hidden {
+            roc_files(&path, paths)?;
+        } else if path.extension().and_then(|ext| ext.to_str()) == Some("roc") {
+            paths.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+struct Document {
+    path: PathBuf,
+    occurrences: Vec<Occurrence>,
+    diagnostics: Vec<Value>,
+}
+
+struct Occurrence {
+    start: Value,
+    end: Value,
+    symbol: SymbolKey,
+    is_definition: bool,
+    /// The symbol's type, if it's known here.
+    hover: Option<String>,
+}
+
+/// Identifies a symbol across modules. Each module is loaded separately (as the root, so that
+/// its types are available), which means `ModuleId`s can't be compared between modules.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum SymbolKey {
+    /// A top-level value, by its module's path (or name, for builtins) and its name.
+    TopLevel(String, String),
+    /// A value only visible inside one module, by the index of that module's document.
+    Local(usize, Symbol),
+}
+
+fn index_module(index: usize, path: &Path) -> Document {
+    let arena = Bump::new();
+    let cache_dir = cache::roc_cache_dir();
+    let load_config = LoadConfig {
+        target_info: roc_target::TargetInfo::from(&target_lexicon::Triple::host()),
+        render: RenderTarget::Generic,
+        palette: DEFAULT_PALETTE,
+        threading: Threading::AllAvailable,
+        exec_mode: ExecutionMode::Check,
+        platform: None,
+        max_type_errors: None,
+        keep_module_types: false,
+    };
+    let loaded = roc_load::load_and_typecheck(
+        &arena,
+        path.to_path_buf(),
+        RocCacheDir::Persistent(cache_dir.as_path()),
+        load_config,
+    );
+
+    match loaded {
+        Ok(mut loaded) => Document {
+            path: path.to_path_buf(),
+            occurrences: occurrences(index, &mut loaded),
+            diagnostics: analysis::module_diagnostics(loaded),
+        },
+        Err(problem) => {
+            let message = match problem {
+                LoadingProblem::FormattedReport(report) => report,
+                other => format!("{:?}", other),
+            };
+            let source = fs::read_to_string(path).unwrap_or_default();
+            let src_lines: Vec<&str> = source.split('\n').collect();
+
+            Document {
+                path: path.to_path_buf(),
+                occurrences: Vec::new(),
+                diagnostics: vec![analysis::diagnostic(
+                    &src_lines,
+                    LineColumnRegion::zero(),
+                    DIAGNOSTIC_ERROR,
+                    message,
+                )],
+            }
+        }
+    }
+}
+
+fn occurrences(index: usize, loaded: &mut LoadedModule) -> Vec<Occurrence> {
+    let LoadedModule {
+        module_id: home,
+        interns,
+        solved,
+        declarations_by_id,
+        sources,
+        ..
+    } = loaded;
+    let home = *home;
+    let (decls, (_, src)) = match (declarations_by_id.get(&home), sources.get(&home)) {
+        (Some(decls), Some(source)) => (decls, source),
+        _ => return Vec::new(),
+    };
+
+    let mut finder = SymbolFinder::default();
+    finder.visit_decls(decls);
+
+    let src_lines: Vec<&str> = src.split('\n').collect();
+    let lines = LineInfo::new(src);
+    let subs = solved.inner_mut();
+    let mut occurrences: Vec<Occurrence> = Vec::with_capacity(finder.found.len());
+
+    for found in finder.found {
+        // Code the compiler generated has no place in the source.
+        if found.region.is_empty() {
+            continue;
+        }
+
+        let range = lines.convert_region(found.region);
+        let start = analysis::position(&src_lines, range.start);
+        let end = analysis::position(&src_lines, range.end);
+
+        // Desugaring can produce several nodes for the same source range.
+        if occurrences
+            .iter()
+            .any(|other| other.start == start && other.end == end)
+        {
+            continue;
+        }
+
+        let symbol = found.symbol;
+        let is_top_level =
+            symbol.module_id() != home || decls.symbols.iter().any(|s| s.value == symbol);
+        let key = if is_top_level {
+            SymbolKey::TopLevel(
+                module_key(
+                    sources.get(&symbol.module_id()),
+                    interns,
+                    symbol.module_id(),
+                ),
+                symbol.as_str(interns).to_string(),
+            )
+        } else {
+            SymbolKey::Local(index, symbol)
+        };
+        let hover = found.var.map(|var| {
+            let typ = name_and_print_var(var, subs, home, interns, DebugPrint::NOTHING);
+
+            format!("```roc\n{} : {}\n```", symbol.as_str(interns), typ)
+        });
+
+        occurrences.push(Occurrence {
+            start,
+            end,
+            symbol: key,
+            is_definition: found.is_definition,
+            hover,
+        });
+    }
+
+    occurrences
+}
+
+/// Modules in the workspace are identified by their path, so the same module gets the same key
+/// no matter which module's load it showed up in.
+fn module_key(
+    source: Option<&(PathBuf, Box<str>)>,
+    interns: &Interns,
+    module_id: ModuleId,
+) -> String {
+    match source {
+        Some((path, _)) if !module_id.is_builtin() => fs::canonicalize(path)
+            .unwrap_or_else(|_| path.clone())
+            .to_string_lossy()
+            .into_owned(),
+        _ => interns.module_name(module_id).to_string(),
+    }
+}
+
+struct FoundSymbol {
+    region: Region,
+    symbol: Symbol,
+    var: Option<Variable>,
+    is_definition: bool,
+}
+
+#[derive(Default)]
+struct SymbolFinder {
+    found: Vec<FoundSymbol>,
+}
+
+impl SymbolFinder {
+    fn push(&mut self, region: Region, symbol: Symbol, var: Option<Variable>, is_definition: bool) {
+        self.found.push(FoundSymbol {
+            region,
+            symbol,
+            var,
+            is_definition,
+        });
+    }
+}
+
+impl Visitor for SymbolFinder {
+    fn visit_pattern(&mut self, pattern: &Pattern, region: Region, opt_var: Option<Variable>) {
+        if let Pattern::Identifier(symbol)
+        | Pattern::AbilityMemberSpecialization { ident: symbol, .. } = pattern
+        {
+            self.push(region, *symbol, opt_var, true);
+        }
+
+        walk_pattern(self, pattern);
+    }
+
+    fn visit_record_destruct(&mut self, destruct: &RecordDestruct, region: Region) {
+        // With a guard like `{ x: Foo y }`, only the guard introduces symbols.
+        if !matches!(destruct.typ, DestructType::Guard(..)) {
+            self.push(region, destruct.symbol, Some(destruct.var), true);
+        }
+
+        walk_record_destruct(self, destruct);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr, region: Region, var: Variable) {
+        match expr {
+            Expr::Var(symbol, var) | Expr::AbilityMember(symbol, _, var) => {
+                self.push(region, *symbol, Some(*var), false);
+            }
+            _ => {}
+        }
+
+        walk_expr(self, expr, var);
+    }
+}
+
+/// The results for one symbol, which every range of that symbol links to.
+struct SymbolResults {
+    result_set: u64,
+    /// The type at the definition, or else at the first use.
+    hover: Option<String>,
+    hover_from_definition: bool,
+    /// (document, range) pairs.
+    definitions: Vec<(u64, u64)>,
+    references: Vec<(u64, u64)>,
+}
+
+#[derive(Default)]
+struct Dump {
+    last_id: u64,
+    symbols: HashMap<SymbolKey, SymbolResults>,
+    /// The order symbols were first seen in, so the dump is the same every time.
+    symbol_order: Vec<SymbolKey>,
+}
+
+impl Dump {
+    fn write<W: Write>(
+        &mut self,
+        root: &Path,
+        documents: &[Document],
+        out: &mut W,
+    ) -> io::Result<()> {
+        self.vertex(
+            out,
+            json!({
+                "label": "metaData",
+                "version": LSIF_VERSION,
+                "projectRoot": path_to_uri(root),
+                "positionEncoding": "utf-16",
+                "toolInfo": { "name": "roc", "version": crate::VERSION.trim() },
+            }),
+        )?;
+
+        let project = self.vertex(out, json!({ "label": "project", "kind": "roc" }))?;
+        let mut document_ids = Vec::with_capacity(documents.len());
+
+        for document in documents {
+            document_ids.push(self.write_document(document, out)?);
+        }
+
+        self.edge(out, "contains", project, json!({ "inVs": document_ids }))?;
+
+        for key in std::mem::take(&mut self.symbol_order) {
+            let results = self.symbols.remove(&key).unwrap();
+
+            self.write_results(results, out)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_document<W: Write>(&mut self, document: &Document, out: &mut W) -> io::Result<u64> {
+        let id = self.vertex(
+            out,
+            json!({
+                "label": "document",
+                "uri": path_to_uri(&document.path),
+                "languageId": "roc",
+            }),
+        )?;
+        let mut range_ids = Vec::with_capacity(document.occurrences.len());
+
+        for occurrence in &document.occurrences {
+            let range = self.vertex(
+                out,
+                json!({ "label": "range", "start": occurrence.start, "end": occurrence.end }),
+            )?;
+            let result_set = self.result_set(&occurrence.symbol, out)?;
+
+            self.edge(out, "next", range, json!({ "inV": result_set }))?;
+
+            let results = self.symbols.get_mut(&occurrence.symbol).unwrap();
+
+            if occurrence.is_definition {
+                results.definitions.push((id, range));
+            } else {
+                results.references.push((id, range));
+            }
+
+            if occurrence.hover.is_some()
+                && (results.hover.is_none()
+                    || (occurrence.is_definition && !results.hover_from_definition))
+            {
+                results.hover = occurrence.hover.clone();
+                results.hover_from_definition = occurrence.is_definition;
+            }
+
+            range_ids.push(range);
+        }
+
+        if !range_ids.is_empty() {
+            self.edge(out, "contains", id, json!({ "inVs": range_ids }))?;
+        }
+
+        if !document.diagnostics.is_empty() {
+            let result = self.vertex(
+                out,
+                json!({ "label": "diagnosticResult", "result": document.diagnostics }),
+            )?;
+
+            self.edge(out, "textDocument/diagnostic", id, json!({ "inV": result }))?;
+        }
+
+        Ok(id)
+    }
+
+    /// Returns the symbol's result set, writing it the first time the symbol is seen.
+    fn result_set<W: Write>(&mut self, key: &SymbolKey, out: &mut W) -> io::Result<u64> {
+        if let Some(results) = self.symbols.get(key) {
+            return Ok(results.result_set);
+        }
+
+        let result_set = self.vertex(out, json!({ "label": "resultSet" }))?;
+
+        self.symbol_order.push(key.clone());
+        self.symbols.insert(
+            key.clone(),
+            SymbolResults {
+                result_set,
+                hover: None,
+                hover_from_definition: false,
+                definitions: Vec::new(),
+                references: Vec::new(),
+            },
+        );
+
+        Ok(result_set)
+    }
+
+    fn write_results<W: Write>(&mut self, results: SymbolResults, out: &mut W) -> io::Result<()> {
+        let result_set = results.result_set;
+
+        if let Some(hover) = results.hover {
+            let hover = self.vertex(
+                out,
+                json!({
+                    "label": "hoverResult",
+                    "result": { "contents": { "kind": "markdown", "value": hover } },
+                }),
+            )?;
+
+            self.edge(
+                out,
+                "textDocument/hover",
+                result_set,
+                json!({ "inV": hover }),
+            )?;
+        }
+
+        // Builtins and modules outside the workspace have no definition here.
+        if !results.definitions.is_empty() {
+            let definition = self.vertex(out, json!({ "label": "definitionResult" }))?;
+
+            self.edge(
+                out,
+                "textDocument/definition",
+                result_set,
+                json!({ "inV": definition }),
+            )?;
+            self.items(out, definition, &results.definitions, None)?;
+        }
+
+        let references = self.vertex(out, json!({ "label": "referenceResult" }))?;
+
+        self.edge(
+            out,
+            "textDocument/references",
+            result_set,
+            json!({ "inV": references }),
+        )?;
+        self.items(out, references, &results.definitions, Some("definitions"))?;
+        self.items(out, references, &results.references, Some("references"))?;
+
+        Ok(())
+    }
+
+    /// Writes one `item` edge per document, since each edge can only point into one document.
+    fn items<W: Write>(
+        &mut self,
+        out: &mut W,
+        result: u64,
+        ranges: &[(u64, u64)],
+        property: Option<&str>,
+    ) -> io::Result<()> {
+        let mut by_document: Vec<(u64, Vec<u64>)> = Vec::new();
+
+        for &(document, range) in ranges {
+            match by_document.iter_mut().find(|(doc, _)| *doc == document) {
+                Some((_, in_vs)) => in_vs.push(range),
+                None => by_document.push((document, vec![range])),
+            }
+        }
+
+        for (document, in_vs) in by_document {
+            let mut fields = json!({ "inVs": in_vs, "document": document });
+
+            if let Some(property) = property {
+                fields["property"] = json!(property);
+            }
+
+            self.edge(out, "item", result, fields)?;
+        }
+
+        Ok(())
+    }
+
+    fn vertex<W: Write>(&mut self, out: &mut W, mut fields: Value) -> io::Result<u64> {
+        self.last_id += 1;
+        fields["id"] = json!(self.last_id);
+        fields["type"] = json!("vertex");
+
+        writeln!(out, "{}", fields)?;
+
+        Ok(self.last_id)
+    }
+
+    fn edge<W: Write>(
+        &mut self,
+        out: &mut W,
+        label: &str,
+        out_v: u64,
+        mut fields: Value,
+    ) -> io::Result<u64> {
+        self.last_id += 1;
+        fields["id"] = json!(self.last_id);
+        fields["type"] = json!("edge");
+        fields["label"] = json!(label);
+        fields["outV"] = json!(out_v);
+
+        writeln!(out, "{}", fields)?;
+
+        Ok(self.last_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_uses_to_definitions() {
+        let dir = std::env::temp_dir().join(format!("roc_lsif_{}", std::process::id()));
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("Greeting.roc"),
+            "interface Greeting exposes [greet, hello] imports []\n\ngreet = \\name -> Str.concat \"Hello, \" name\n\nhello = greet \"Roc\"\n",
+        )
+        .unwrap();
+
+        let mut out = Vec::new();
+        let indexed = export(&dir, &mut out).unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        let dump: Vec<Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let labelled = |label: &str| {
+            dump.iter()
+                .filter(|value| value["label"] == label)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(indexed, 1);
+        assert_eq!(dump[0]["label"], "metaData");
+        assert_eq!(labelled("document").len(), 1);
+        assert!(
+            labelled("hoverResult")
+                .iter()
+                .any(|hover| hover["result"]["contents"]["value"]
+                    == "```roc\ngreet : Str -> Str\n```")
+        );
+
+        // `greet` is defined at the start of line 2, and used after `hello = ` on line 4.
+        let range_at = |line: u64, character: u64| {
+            labelled("range")
+                .into_iter()
+                .find(|range| range["start"] == json!({ "line": line, "character": character }))
+                .unwrap()["id"]
+                .clone()
+        };
+        let definition = range_at(2, 0);
+        let usage = range_at(4, 8);
+        let items = labelled("item");
+
+        assert!(items.iter().any(|item| item["property"] == "definitions"
+            && item["inVs"].as_array().unwrap().contains(&definition)));
+        assert!(items.iter().any(|item| item["property"] == "references"
+            && item["inVs"].as_array().unwrap().contains(&usage)));
+    }
+}
@@ -0,0 +1,80 @@
+//! Reads and writes Language Server Protocol messages, which are JSON-RPC payloads
+//! preceded by a `Content-Length` header.
+use serde_json::Value;
+use std::io::{self, BufRead, Write};
+
+/// Returns `None` once the client has closed the stream.
+pub fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+
+    loop {
+        let mut line = String::new();
+
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let line = line.trim_end();
+
+        if line.is_empty() {
+            break;
+        }
+
+        // Other headers (only `Content-Type` is defined) don't tell us anything we need.
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            content_length = Some(value.trim().parse::<usize>().map_err(invalid_data)?);
+        }
+    }
+
+    let content_length =
+        content_length.ok_or_else(|| invalid_data("message had no Content-Length header"))?;
+    let mut body = vec![0; content_length];
+
+    reader.read_exact(&mut body)?;
+
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(invalid_data)
+}
+
+pub fn write_message<W: Write>(writer: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+
+    writer.flush()
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn round_trip() {
+        let message = json!({ "jsonrpc": "2.0", "id": 1, "method": "shutdown" });
+        let mut bytes = Vec::new();
+
+        write_message(&mut bytes, &message).unwrap();
+        write_message(&mut bytes, &message).unwrap();
+
+        let mut reader = bytes.as_slice();
+
+        assert_eq!(read_message(&mut reader).unwrap(), Some(message.clone()));
+        assert_eq!(read_message(&mut reader).unwrap(), Some(message));
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn missing_content_length() {
+        let mut reader = "Content-Type: application/vscode-jsonrpc\r\n\r\n{}".as_bytes();
+
+        let error = read_message(&mut reader).unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,144 @@
+//! The workspace folders an editor has open, and which project each document belongs to.
+//!
+//! A monorepo can hold several Roc apps, packages and platforms, in one folder or in several.
+//! Each of them is checked on its own, with the directory of its main module as the `src_dir`,
+//! so their modules never end up in one another's module graph. They do all share the package
+//! cache, which is the same one the CLI uses.
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// The files that mark the directory they're in as the root of a project.
+const PROJECT_MARKERS: [&str; 2] = ["main.roc", "roc.toml"];
+
+#[derive(Debug, Default)]
+pub(crate) struct Workspace {
+    folders: Vec<PathBuf>,
+}
+
+impl Workspace {
+    /// The folders from the params of an `initialize` request. Clients that don't know about
+    /// workspace folders send a single root instead.
+    pub(crate) fn from_initialize(params: &Value) -> Self {
+        let mut workspace = Self::default();
+
+        match params["workspaceFolders"].as_array() {
+            Some(folders) => workspace.add(folders),
+            None => {
+                let root = match params["rootUri"].as_str() {
+                    Some(uri) => crate::uri_to_path(uri),
+                    None => params["rootPath"].as_str().map(PathBuf::from),
+                };
+
+                workspace.folders.extend(root);
+            }
+        }
+
+        workspace
+    }
+
+    /// Applies the `event` of a `workspace/didChangeWorkspaceFolders` notification.
+    pub(crate) fn change(&mut self, event: &Value) {
+        if let Some(removed) = event["removed"].as_array() {
+            for path in removed.iter().filter_map(folder_path) {
+                self.folders.retain(|folder| folder != &path);
+            }
+        }
+
+        if let Some(added) = event["added"].as_array() {
+            self.add(added);
+        }
+    }
+
+    fn add(&mut self, folders: &[Value]) {
+        for path in folders.iter().filter_map(folder_path) {
+            if !self.folders.contains(&path) {
+                self.folders.push(path);
+            }
+        }
+    }
+
+    /// The directory the project that `path` belongs to is rooted in: the closest directory
+    /// around it with a `main.roc` or `roc.toml` in it, without leaving the workspace folder
+    /// it's in. If there is none, that's the workspace folder, and if the document isn't in
+    /// one, its own directory.
+    pub(crate) fn project_root(&self, path: &Path) -> PathBuf {
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+        // With nested folders, the innermost one is the one the document is in
+        let folder = self
+            .folders
+            .iter()
+            .filter(|folder| dir.starts_with(folder))
+            .max_by_key(|folder| folder.components().count());
+
+        let folder = match folder {
+            Some(folder) => folder,
+            None => return dir.to_path_buf(),
+        };
+
+        dir.ancestors()
+            .take_while(|ancestor| ancestor.starts_with(folder))
+            .find(|ancestor| {
+                PROJECT_MARKERS
+                    .iter()
+                    .any(|marker| ancestor.join(marker).is_file())
+            })
+            .unwrap_or(folder)
+            .to_path_buf()
+    }
+}
+
+/// The path of an LSP `WorkspaceFolder`, if it's on disk.
+fn folder_path(folder: &Value) -> Option<PathBuf> {
+    folder["uri"].as_str().and_then(crate::uri_to_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+
+    #[test]
+    fn documents_belong_to_the_closest_project() {
+        let dir = std::env::temp_dir().join(format!("roc_workspace_{}", std::process::id()));
+        let app = dir.join("apps").join("hello");
+
+        fs::create_dir_all(app.join("Util")).unwrap();
+        fs::create_dir_all(dir.join("scripts")).unwrap();
+        fs::write(app.join("main.roc"), "").unwrap();
+
+        let workspace = Workspace::from_initialize(&json!({
+            "workspaceFolders": [{ "uri": crate::path_to_uri(&dir), "name": "monorepo" }],
+        }));
+        let app_root = workspace.project_root(&app.join("Util").join("Text.roc"));
+        let script_root = workspace.project_root(&dir.join("scripts").join("Release.roc"));
+        let outside_root = workspace.project_root(Path::new("/elsewhere/Main.roc"));
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(app_root, app);
+        assert_eq!(script_root, dir);
+        assert_eq!(outside_root, Path::new("/elsewhere"));
+    }
+
+    #[test]
+    fn folders_can_be_added_and_removed() {
+        let mut workspace = Workspace::from_initialize(&json!({ "rootUri": "file:///repo" }));
+
+        workspace.change(&json!({
+            "added": [{ "uri": "file:///repo/apps/hello", "name": "hello" }],
+            "removed": [{ "uri": "file:///repo", "name": "repo" }],
+        }));
+
+        assert_eq!(workspace.folders, vec![PathBuf::from("/repo/apps/hello")]);
+        assert_eq!(
+            workspace.project_root(Path::new("/repo/apps/hello/Util/Text.roc")),
+            Path::new("/repo/apps/hello")
+        );
+        assert_eq!(
+            workspace.project_root(Path::new("/repo/Build.roc")),
+            Path::new("/repo")
+        );
+    }
+}
@@ -20,7 +20,8 @@ use std::{
 
 use crate::{
     align_by_constraint, align_to_offset_by_constraint, load_struct_inplace,
-    load_struct_inplace_mut, load_structs_inplace_mut, open_mmap, open_mmap_mut,
+    load_struct_inplace_mut, load_structs_inplace, load_structs_inplace_mut, open_mmap,
+    open_mmap_mut, AppSymbolDiff,
 };
 
 const MIN_SECTION_ALIGNMENT: usize = 0x40;
@@ -1183,6 +1184,8 @@ pub(crate) fn surgery_elf(
     let md = Metadata::read_from_file(metadata_path);
     let loading_metadata_duration = loading_metadata_start.elapsed();
 
+    AppSymbolDiff::from_app_object(&md.app_functions, &app_obj).exit_if_missing();
+
     let load_and_mmap_start = Instant::now();
     let max_out_len = md.exec_len + roc_app_bytes.len() as u64 + md.load_align_constraint;
     let mut exec_mmap = open_mmap_mut(executable_path, max_out_len as usize);
@@ -1749,6 +1752,71 @@ fn surgery_elf_help(
     *offset_ref = offset;
 }
 
+/// The exploit mitigations that the executable in `exec_data` is missing, out of the ones
+/// `roc build` gives Linux executables unless it's passed `--hardening=off`.
+pub(crate) fn missing_hardening(exec_data: &[u8]) -> Vec<&'static str> {
+    let file_header = load_struct_inplace::<elf::FileHeader64<LE>>(exec_data, 0);
+    let program_headers = load_structs_inplace::<elf::ProgramHeader64<LE>>(
+        exec_data,
+        file_header.e_phoff.get(LE) as usize,
+        file_header.e_phnum.get(LE) as usize,
+    );
+
+    let mut missing = Vec::new();
+
+    // A position-independent executable is a shared object, as far as ELF is concerned
+    if file_header.e_type.get(LE) != elf::ET_DYN {
+        missing.push("position-independent code (PIE)");
+    }
+
+    let has_relro = program_headers
+        .iter()
+        .any(|ph| ph.p_type.get(LE) == elf::PT_GNU_RELRO);
+
+    if !has_relro {
+        missing.push("read-only relocations (RELRO)");
+    }
+
+    let binds_now = program_headers
+        .iter()
+        .filter(|ph| ph.p_type.get(LE) == elf::PT_DYNAMIC)
+        .any(|ph| {
+            let count = ph.p_filesz.get(LE) as usize / mem::size_of::<elf::Dyn64<LE>>();
+            let dyns = load_structs_inplace::<elf::Dyn64<LE>>(
+                exec_data,
+                ph.p_offset.get(LE) as usize,
+                count,
+            );
+
+            dyns.iter().any(|d| {
+                let value = d.d_val.get(LE);
+
+                match d.d_tag.get(LE) as u32 {
+                    elf::DT_BIND_NOW => true,
+                    elf::DT_FLAGS => value & elf::DF_BIND_NOW as u64 != 0,
+                    elf::DT_FLAGS_1 => value & elf::DF_1_NOW as u64 != 0,
+                    _ => false,
+                }
+            })
+        });
+
+    // without this, the GOT stays writable so that lazy binding can fill it in
+    if !binds_now {
+        missing.push("immediate binding (full RELRO)");
+    }
+
+    // Without a PT_GNU_STACK, the loader makes the stack executable
+    let has_noexec_stack = program_headers
+        .iter()
+        .any(|ph| ph.p_type.get(LE) == elf::PT_GNU_STACK && ph.p_flags.get(LE) & elf::PF_X == 0);
+
+    if !has_noexec_stack {
+        missing.push("a non-executable stack");
+    }
+
+    missing
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1889,6 +1957,10 @@ mod tests {
                 "libapp.so",
                 "host.zig",
                 "-fPIE",
+                "-z",
+                "relro",
+                "-z",
+                "now",
                 "-lc",
                 "-target",
                 "x86_64-linux-gnu",
@@ -1956,5 +2028,44 @@ mod tests {
         let output = String::from_utf8_lossy(&output.stdout);
 
         assert_eq!("Hello foo\n", output);
+
+        // surgery must not undo the host's hardening
+        let missing = missing_hardening(&open_mmap(&dir.join("final")));
+
+        assert!(missing.is_empty(), "{:?}", missing);
+    }
+
+    #[test]
+    fn app_symbol_diff() {
+        let host_calls = [
+            "roc__mainForHost_1_exposed_generic".to_string(),
+            "roc__mainForHost_1_exposed_size".to_string(),
+            "roc__mainForHost_0_caller".to_string(),
+        ];
+        let app_defines = [
+            "roc__main_1_exposed_generic",
+            "roc__mainForHost_1_exposed_size",
+            "roc_set_panic_hook",
+            "Str_concat",
+        ];
+
+        assert_eq!(
+            AppSymbolDiff::new(&host_calls, app_defines),
+            AppSymbolDiff {
+                missing: vec![
+                    "roc__mainForHost_0_caller".to_string(),
+                    "roc__mainForHost_1_exposed_generic".to_string(),
+                ],
+                unused: vec!["roc__main_1_exposed_generic".to_string()],
+            }
+        );
+
+        assert_eq!(
+            AppSymbolDiff::new(&host_calls[1..2], app_defines),
+            AppSymbolDiff {
+                missing: vec![],
+                unused: vec!["roc__main_1_exposed_generic".to_string()],
+            }
+        );
     }
 }
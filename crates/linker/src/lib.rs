@@ -78,9 +78,24 @@ pub fn link_preprocessed_host(
     platform_path: &Path,
     roc_app_bytes: &[u8],
     binary_path: &Path,
+    hardening: bool,
 ) {
     let metadata = platform_path.with_file_name(metadata_file_name(target));
-    surgery(roc_app_bytes, &metadata, binary_path, false, false, target)
+    surgery(roc_app_bytes, &metadata, binary_path, false, false, target);
+
+    // The surgical linker keeps the host's program headers and dynamic section, so the
+    // executable is only as hardened as the host was.
+    if hardening && target.binary_format == target_lexicon::BinaryFormat::Elf {
+        let missing = crate::elf::missing_hardening(&open_mmap(binary_path));
+
+        if !missing.is_empty() {
+            eprintln!(
+                "\nWarning: {} was built without these exploit mitigations, because the platform's host was built without them:\n\n    {}\n\nThe host needs to be linked with `-pie -z relro -z now -z noexecstack`. Pass `--hardening=off` to skip this check.\n",
+                binary_path.display(),
+                missing.join("\n    "),
+            );
+        }
+    }
 }
 
 // Exposed function to load a platform file and generate a stub lib for it.
@@ -105,6 +120,9 @@ pub fn generate_stub_lib(
             palette: DEFAULT_PALETTE,
             threading: Threading::AllAvailable,
             exec_mode: ExecutionMode::Executable,
+            platform: None,
+            max_type_errors: None,
+            keep_module_types: false,
         },
     )
     .unwrap_or_else(|problem| todo!("{:?}", problem));
@@ -165,6 +183,10 @@ pub fn generate_stub_lib_from_loaded(
     stub_lib_path
 }
 
+/// The host may call this (with a function pointer, or null to unset it) to be told about
+/// every panic before `roc_panic` handles it. It's always defined, so hosts can rely on it.
+const PANIC_HOOK_SETTER: &str = "roc_set_panic_hook";
+
 pub struct ExposedSymbols {
     // usually just `mainForhost`
     pub top_level_values: Vec<String>,
@@ -196,6 +218,8 @@ impl ExposedSymbols {
                     format!("roc__{}_{i}_caller", sym),
                     format!("roc__{}_{i}_size", sym),
                     format!("roc__{}_{i}_result_size", sym),
+                    format!("roc__{}_{i}_inc", sym),
+                    format!("roc__{}_{i}_dec", sym),
                 ]);
             }
         }
@@ -212,9 +236,16 @@ impl ExposedSymbols {
         for (top_level_value, lambda_set_id) in &exposed_to_host.lambda_sets {
             let sym = top_level_value.as_str(interns);
             let id = lambda_set_id.0;
-            custom_names.extend([format!("roc__{sym}_{id}_caller")]);
+            custom_names.extend([
+                format!("roc__{sym}_{id}_caller"),
+                format!("roc__{sym}_{id}_inc"),
+                format!("roc__{sym}_{id}_dec"),
+            ]);
         }
 
+        // defined by the builtins in every app, for hosts that want to observe panics
+        custom_names.push(PANIC_HOOK_SETTER.to_string());
+
         // on windows (PE) binary search is used on the symbols,
         // so they must be in alphabetical order
         custom_names.sort_unstable();
@@ -237,10 +268,15 @@ impl ExposedSymbols {
                     format!("roc__{}_1_{}_caller", sym, closure_type),
                     format!("roc__{}_1_{}_size", sym, closure_type),
                     format!("roc__{}_1_{}_result_size", sym, closure_type),
+                    format!("roc__{}_1_{}_inc", sym, closure_type),
+                    format!("roc__{}_1_{}_dec", sym, closure_type),
                 ]);
             }
         }
 
+        // defined by the builtins in every app, for hosts that want to observe panics
+        custom_names.push(PANIC_HOOK_SETTER.to_string());
+
         // on windows (PE) binary search is used on the symbols,
         // so they must be in alphabetical order
         custom_names.sort_unstable();
@@ -351,6 +387,7 @@ fn object_matches_target<'a>(target: &Triple, object: &object::File<'a, &'a [u8]
             object.format() == target_format
         }
         TLA::Aarch64(_) => object.architecture() == object::Architecture::Aarch64,
+        TLA::Riscv64(_) => object.architecture() == object::Architecture::Riscv64,
         _ => todo!("surgical linker does not support target {:?}", target),
     }
 }
@@ -528,6 +565,90 @@ fn surgery(
     }
 }
 
+/// The functions a host calls into the app for, compared to the ones an app defines.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct AppSymbolDiff {
+    /// The host calls these, but the app doesn't define them.
+    pub missing: Vec<String>,
+    /// The app defines these for a host, but this host doesn't call them.
+    pub unused: Vec<String>,
+}
+
+impl AppSymbolDiff {
+    pub(crate) fn new<'a>(
+        host_calls: &[String],
+        app_defines: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        let app_defines: std::collections::BTreeSet<&str> = app_defines.into_iter().collect();
+
+        let mut missing: Vec<String> = host_calls
+            .iter()
+            .filter(|name| !app_defines.contains(name.as_str()))
+            .cloned()
+            .collect();
+        missing.sort_unstable();
+        missing.dedup();
+
+        // only the functions exposed to the host (which start with `roc__`),
+        // not the app's own functions or builtins that happen to be global
+        let unused = app_defines
+            .into_iter()
+            .filter(|name| name.trim_start_matches('_').starts_with("roc__"))
+            .filter(|name| !host_calls.iter().any(|called| called == name))
+            .map(str::to_string)
+            .collect();
+
+        Self { missing, unused }
+    }
+
+    /// The functions `app_obj` defines, compared to the ones the host calls.
+    pub(crate) fn from_app_object(host_calls: &[String], app_obj: &object::File) -> Self {
+        use object::ObjectSymbol;
+
+        let app_defines = app_obj
+            .symbols()
+            .filter(|sym| sym.is_definition() && sym.is_global())
+            .filter_map(|sym| sym.name().ok());
+
+        Self::new(host_calls, app_defines)
+    }
+
+    /// Reports every function the host calls that the app doesn't define at once, instead of
+    /// failing on the first one during surgery, and exits.
+    pub(crate) fn exit_if_missing(&self) {
+        if self.missing.is_empty() {
+            return;
+        }
+
+        eprintln!("Error:");
+        eprintln!(
+            "\n\tThe platform's host calls these functions, which the app does not define:\n"
+        );
+        for name in &self.missing {
+            eprintln!("\t\t{name}");
+        }
+
+        if !self.unused.is_empty() {
+            eprintln!("\n\tThe app defines these functions for its host, which this host does not call:\n");
+            for name in &self.unused {
+                eprintln!("\t\t{name}");
+            }
+        }
+
+        eprintln!("\nPotential causes:");
+        eprintln!("\n\t- because the platform was built with a non-compatible version of roc compared to the one you are running.");
+        eprintln!("\n\t\tsolutions:");
+        eprintln!(
+            "\t\t\t+ Downgrade your roc version to the one that was used to build the platform."
+        );
+        eprintln!("\t\t\t+ Or ask the platform author to release a new version of the platform using a current roc release.");
+        eprintln!("\n\t- because the app's `main` (or whatever the platform requires) does not have the type the platform expects.");
+        eprintln!("\n\t- This can also occur due to a bug in the compiler. In that case, file an issue here: https://github.com/roc-lang/roc/issues/new/choose");
+
+        std::process::exit(1);
+    }
+}
+
 pub(crate) fn align_by_constraint(offset: usize, constraint: usize) -> usize {
     if offset % constraint == 0 {
         offset
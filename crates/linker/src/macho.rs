@@ -22,7 +22,7 @@ use target_lexicon::Triple;
 use crate::{
     align_by_constraint, align_to_offset_by_constraint, load_struct_inplace,
     load_struct_inplace_mut, load_structs_inplace, load_structs_inplace_mut, open_mmap,
-    open_mmap_mut,
+    open_mmap_mut, AppSymbolDiff,
 };
 
 const MIN_SECTION_ALIGNMENT: usize = 0x40;
@@ -1151,6 +1151,8 @@ pub(crate) fn surgery_macho(
     let md = Metadata::read_from_file(metadata_path);
     let loading_metadata_duration = loading_metadata_start.elapsed();
 
+    AppSymbolDiff::from_app_object(&md.app_functions, &app_obj).exit_if_missing();
+
     let load_and_mmap_start = Instant::now();
     let max_out_len = md.exec_len + roc_app_bytes.len() as u64 + md.load_align_constraint;
     let mut exec_mmap = open_mmap_mut(executable_path, max_out_len as usize);
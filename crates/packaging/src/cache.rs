@@ -168,31 +168,48 @@ const ROC_CACHE_DIR_NAME: &str = "Roc";
 // e.g. the "roc" in ~/.cache/roc
 const ROC_CACHE_DIR_NAME: &str = "roc";
 
+/// Set this environment variable to keep Roc's cache somewhere other than the default location.
+pub const ROC_CACHE_DIR_VAR: &str = "ROC_CACHE_DIR";
+
+/// Downloaded packages are in this directory inside of the Roc cache dir.
+/// Anything else in there was compiled by Roc.
+#[cfg(not(target_family = "wasm"))]
+const PACKAGES_DIR_NAME: &str = "packages";
+
+/// This looks up environment variables, so it should ideally be called once and then cached!
+///
+/// Returns the directory that downloaded packages go in, which is
+/// roc_cache_root().join("packages")
+#[cfg(not(target_family = "wasm"))]
+pub fn roc_cache_dir() -> PathBuf {
+    roc_cache_root().join(PACKAGES_DIR_NAME)
+}
+
 /// This looks up environment variables, so it should ideally be called once and then cached!
 ///
-/// Returns a path of the form cache_dir_path.join(ROC_CACHE_DIR_NAME).join("packages")
-/// where cache_dir_path is:
-/// - The XDG_CACHE_HOME environment varaible, if it's set.
-/// - Otherwise, ~/.cache on UNIX and %APPDATA% on Windows.
+/// Returns the directory Roc keeps its cache in, which is:
+/// - The ROC_CACHE_DIR environment variable, if it's set.
+/// - Otherwise, cache_dir_path.join(ROC_CACHE_DIR_NAME), where cache_dir_path is
+///   the XDG_CACHE_HOME environment varaible if it's set, or else
+///   ~/.cache on UNIX and %APPDATA% on Windows.
 ///
 /// ROC_CACHE_DIR_NAME is "roc" on UNIX and "Roc" on Windows.
 ///
 /// So ~/.cache/roc will be typical on UNIX, and %APPDATA%\\Roc will be typical on Windows.
 ///
-/// Returns None if XDG_CACHE_HOME is not set, and also we can't determine the home directory
-/// (or if %APPDATA% is missing on Windows) on this system.
+/// Exits if none of these are set (or if %APPDATA% is missing on Windows) on this system.
 #[cfg(not(target_family = "wasm"))]
-pub fn roc_cache_dir() -> PathBuf {
+pub fn roc_cache_root() -> PathBuf {
     use std::{env, process};
 
-    const PACKAGES_DIR_NAME: &str = "packages";
+    if let Some(roc_cache_dir) = env::var_os(ROC_CACHE_DIR_VAR) {
+        return PathBuf::from(roc_cache_dir);
+    }
 
     // Respect XDG, if the system appears to be using it.
     // https://specifications.freedesktop.org/basedir-spec/basedir-spec-latest.html
     match env::var_os("XDG_CACHE_HOME") {
-        Some(xdg_cache_home) => Path::new(&xdg_cache_home)
-            .join(ROC_CACHE_DIR_NAME)
-            .join(PACKAGES_DIR_NAME),
+        Some(xdg_cache_home) => Path::new(&xdg_cache_home).join(ROC_CACHE_DIR_NAME),
         None => {
             #[cfg(windows)]
             {
@@ -202,9 +219,7 @@ pub fn roc_cache_dir() -> PathBuf {
                     // https://learn.microsoft.com/en-us/windows/deployment/usmt/usmt-recognized-environment-variables
                     env::var_os("APPDATA").or_else(|| env::var_os("CSIDL_APPDATA"))
                 {
-                    Path::new(&appdata)
-                        .join(ROC_CACHE_DIR_NAME)
-                        .join(PACKAGES_DIR_NAME)
+                    Path::new(&appdata).join(ROC_CACHE_DIR_NAME)
                 } else {
                     eprintln!("roc needs either the %APPDATA% or else the %XDG_CACHE_HOME% environment variables set. Please set one of these environment variables and re-run roc!");
                     process::exit(1);
@@ -215,10 +230,7 @@ pub fn roc_cache_dir() -> PathBuf {
             {
                 // e.g. $HOME/.cache/roc
                 if let Some(home) = env::var_os("HOME") {
-                    Path::new(&home)
-                        .join(".cache")
-                        .join(ROC_CACHE_DIR_NAME)
-                        .join(PACKAGES_DIR_NAME)
+                    Path::new(&home).join(".cache").join(ROC_CACHE_DIR_NAME)
                 } else {
                     eprintln!("roc needs either the $HOME or else the $XDG_CACHE_HOME environment variables set. Please set one of these environment variables and re-run roc!");
                     process::exit(1);
@@ -228,6 +240,60 @@ pub fn roc_cache_dir() -> PathBuf {
     }
 }
 
+/// What's in the Roc cache dir
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CacheContents {
+    /// Packages downloaded from URLs
+    Packages,
+    /// Everything that isn't a downloaded package
+    Builds,
+    All,
+}
+
+/// The files and directories directly inside of the given Roc cache dir that have these contents.
+/// Returns nothing if the cache dir doesn't exist yet.
+#[cfg(not(target_family = "wasm"))]
+pub fn cache_entries(root: &Path, contents: CacheContents) -> std::io::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    let mut paths = Vec::new();
+
+    for entry in entries {
+        let path = entry?.path();
+        let is_packages = path.file_name() == Some(PACKAGES_DIR_NAME.as_ref());
+
+        let wanted = match contents {
+            CacheContents::Packages => is_packages,
+            CacheContents::Builds => !is_packages,
+            CacheContents::All => true,
+        };
+
+        if wanted {
+            paths.push(path);
+        }
+    }
+
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// The total size in bytes of the files at this path, including in its subdirectories.
+#[cfg(not(target_family = "wasm"))]
+pub fn disk_usage(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
 /// WASI doesn't have a home directory, so just make the cache dir in the current directory
 /// https://github.com/WebAssembly/wasi-filesystem/issues/59
 #[cfg(target_family = "wasm")]
@@ -2,43 +2,117 @@ use bumpalo::Bump;
 use inkwell::context::Context;
 use libloading::Library;
 use roc_build::link::llvm_module_to_dylib;
-use roc_collections::all::MutSet;
+use roc_collections::all::{MutMap, MutSet};
 use roc_gen_llvm::llvm::build::LlvmBackendMode;
-use roc_gen_llvm::llvm::externs::add_default_roc_externs;
-use roc_gen_llvm::{run_jit_function, run_jit_function_dynamic_type};
+use roc_gen_llvm::llvm::externs::{
+    add_allocation_counters, add_default_roc_externs, add_exported_crash, add_memory_limit,
+    ALLOC_BYTES_GLOBAL, ALLOC_COUNT_GLOBAL,
+};
+use roc_gen_llvm::run_roc::RocCallResult;
 use roc_load::{EntryPoint, MonomorphizedModule};
-use roc_mono::ir::OptLevel;
-use roc_mono::layout::STLayoutInterner;
+use roc_mono::ir::{CrashTag, OptLevel};
+use roc_mono::layout::{LayoutInterner, STLayoutInterner};
 use roc_parse::ast::Expr;
 use roc_repl_eval::eval::jit_to_ast;
-use roc_repl_eval::gen::{compile_to_mono, format_answer, Problems, ReplOutput};
+use roc_repl_eval::gen::{
+    compile_to_mono, crash_report, format_answer, stopped_report, Problems, ReplDeps, ReplOutput,
+};
 use roc_repl_eval::{ReplApp, ReplAppMemory};
 use roc_reporting::report::DEFAULT_PALETTE;
 use roc_std::RocStr;
 use roc_target::TargetInfo;
 use roc_types::pretty_print::{name_and_print_var, DebugPrint};
 use roc_types::subs::Subs;
+use roc_value_render::{Inspector, Json};
+use std::time::{Duration, Instant};
 use target_lexicon::Triple;
 
+use crate::limits::{EvalLimits, Watch};
+use crate::repl_state::OutputFormat;
+
 pub fn gen_and_eval_llvm<'a, I: Iterator<Item = &'a str>>(
     defs: I,
     src: &str,
+    deps: &ReplDeps,
     target: Triple,
     opt_level: OptLevel,
+    output_format: OutputFormat,
+    limits: EvalLimits,
 ) -> (Option<ReplOutput>, Problems) {
+    // Every expression gets timed; only :time and :bench look at it
+    let (output, problems, _) = gen_and_measure_llvm(
+        defs,
+        src,
+        deps,
+        target,
+        opt_level,
+        output_format,
+        limits,
+        Measure::Time,
+    );
+
+    (output, problems)
+}
+
+/// How `:time` and `:bench` run an expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Measure {
+    /// Run it once, like any other expression
+    Time,
+    /// Run it over and over again, after warming up
+    Bench,
+}
+
+/// How long a single run of an expression took, and how much it allocated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Measurement {
+    pub elapsed: Duration,
+    pub allocations: usize,
+    pub allocated_bytes: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Measurements {
+    /// Runs that happened before measuring, so that e.g. caches are warm
+    pub warmup_runs: usize,
+    pub runs: Vec<Measurement>,
+}
+
+/// `:bench` warms up for at least this many runs...
+const BENCH_MIN_WARMUP_RUNS: usize = 3;
+/// ...and for at least this long
+const BENCH_WARMUP_TIME: Duration = Duration::from_millis(100);
+/// Then it measures for at least this many runs...
+const BENCH_MIN_RUNS: usize = 10;
+/// ...and for about this long, unless it hits the maximum number of runs first
+const BENCH_TIME: Duration = Duration::from_secs(1);
+const BENCH_MAX_RUNS: usize = 10_000;
+
+/// Like [gen_and_eval_llvm], but also measures the expression, for `:time` and `:bench`.
+/// Returns no measurements if the expression didn't need to run, e.g. because it's a function.
+pub fn gen_and_measure_llvm<'a, I: Iterator<Item = &'a str>>(
+    defs: I,
+    src: &str,
+    deps: &ReplDeps,
+    target: Triple,
+    opt_level: OptLevel,
+    output_format: OutputFormat,
+    limits: EvalLimits,
+    measure: Measure,
+) -> (Option<ReplOutput>, Problems, Option<Measurements>) {
     let arena = Bump::new();
     let target_info = TargetInfo::from(&target);
 
     let mut loaded;
-    let problems;
+    let mut problems;
 
-    match compile_to_mono(&arena, defs, src, target_info, DEFAULT_PALETTE) {
+    match compile_to_mono(&arena, defs, src, deps, target_info, DEFAULT_PALETTE) {
         (Some(mono), probs) => {
             loaded = mono;
             problems = probs;
         }
         (None, probs) => {
-            return (None, probs);
+            return (None, probs, None);
         }
     };
 
@@ -67,29 +141,107 @@ pub fn gen_and_eval_llvm<'a, I: Iterator<Item = &'a str>>(
             let empty_vec: Vec<String> = Vec::new(); // rustc can't infer the type of this Vec.
             debug_assert_ne!(problems.errors, empty_vec, "Got no errors but also no valid layout for the generated main function in the repl!");
 
-            return (None, problems);
+            return (None, problems, None);
         }
     };
 
     let interns = loaded.interns.clone();
+    let module_id = loaded.module_id;
+    let module_src = loaded.sources[&module_id].1.clone();
 
     let (lib, main_fn_name, subs, layout_interner) =
-        mono_module_to_dylib(&arena, target, loaded, opt_level).expect("we produce a valid Dylib");
+        mono_module_to_dylib(&arena, target, loaded, opt_level, limits.max_memory)
+            .expect("we produce a valid Dylib");
 
-    let mut app = CliApp { lib };
+    let ret_bytes = layout_interner.stack_size(main_fn_layout.result) as usize;
+    let mut app = CliApp {
+        lib,
+        last_run: None,
+    };
 
-    let expr = jit_to_ast(
-        &arena,
-        &mut app,
-        main_fn_name,
-        main_fn_layout,
-        main_fn_var,
-        &subs,
-        &interns,
-        layout_interner.into_global().fork(),
-        target_info,
-    );
-    let expr_str = format_answer(&arena, expr).to_string();
+    let watch = Watch::start(&app.lib, limits);
+
+    // A crash unwinds out of jit_to_ast, because there is no value to convert
+    let expr = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        jit_to_ast(
+            &arena,
+            &mut app,
+            main_fn_name,
+            main_fn_layout,
+            main_fn_var,
+            &subs,
+            &interns,
+            layout_interner.into_global().fork(),
+            target_info,
+            None,
+        )
+    }));
+
+    let mut report_crash = |watch: Watch, (message, tag): (String, CrashTag)| {
+        let report = match watch.stop(Some(&message)) {
+            Some(stopped) => stopped_report(
+                module_id,
+                &interns,
+                &module_src,
+                stopped.title,
+                &stopped.message,
+                &DEFAULT_PALETTE,
+            ),
+            None => crash_report(
+                module_id,
+                &interns,
+                &module_src,
+                &message,
+                tag,
+                &DEFAULT_PALETTE,
+            ),
+        };
+
+        problems.errors.push(report);
+    };
+
+    let expr = match expr {
+        Ok(expr) => expr,
+        Err(payload) => match payload.downcast::<RocCrash>() {
+            Ok(crash) => {
+                report_crash(watch, (crash.message, crash.tag));
+
+                return (None, problems, None);
+            }
+            Err(payload) => {
+                watch.stop(None);
+
+                std::panic::resume_unwind(payload)
+            }
+        },
+    };
+
+    // jit_to_ast doesn't run functions, since they get printed as <function>
+    let measurements = match (app.last_run, measure) {
+        (None, _) => None,
+        (Some(run), Measure::Time) => Some(Measurements {
+            warmup_runs: 0,
+            runs: vec![run],
+        }),
+        // The run that jit_to_ast did counts as the first warmup run
+        (Some(_), Measure::Bench) => match app.bench(main_fn_name, ret_bytes) {
+            Ok(measurements) => Some(measurements),
+            Err(crash) => {
+                report_crash(watch, crash);
+
+                return (None, problems, None);
+            }
+        },
+    };
+
+    // It finished, so there is nothing left to stop
+    watch.stop(None);
+
+    let expr_str = match output_format {
+        OutputFormat::Roc => format_answer(&arena, expr),
+        OutputFormat::Json => Json.inspect(&arena, &expr),
+    }
+    .to_string();
 
     (
         Some(ReplOutput {
@@ -97,11 +249,121 @@ pub fn gen_and_eval_llvm<'a, I: Iterator<Item = &'a str>>(
             expr_type: expr_type_str,
         }),
         problems,
+        measurements,
     )
 }
 
 struct CliApp {
     lib: Library,
+    /// The most recent call to the main function
+    last_run: Option<Measurement>,
+}
+
+impl CliApp {
+    /// Calls the main function, which writes a [RocCallResult] into `result`,
+    /// and measures how long that took and how much it allocated.
+    fn run_main(&mut self, main_fn_name: &str, result: *mut u8) {
+        unsafe {
+            let main: libloading::Symbol<unsafe extern "C" fn(*mut u8)> = self
+                .lib
+                .get(main_fn_name.as_bytes())
+                .unwrap_or_else(|_| panic!("Unable to JIT compile `{main_fn_name}`"));
+
+            let (allocations_before, bytes_before) = self.allocation_counters();
+            let start = Instant::now();
+
+            main(result);
+
+            let elapsed = start.elapsed();
+            let (allocations_after, bytes_after) = self.allocation_counters();
+
+            self.last_run = Some(Measurement {
+                elapsed,
+                allocations: allocations_after - allocations_before,
+                allocated_bytes: bytes_after - bytes_before,
+            });
+        }
+    }
+
+    /// How many times roc_alloc has been called so far, and the total bytes it was asked for
+    fn allocation_counters(&self) -> (usize, usize) {
+        let read = |name: &str| unsafe {
+            let global: libloading::Symbol<*const usize> = self
+                .lib
+                .get(name.as_bytes())
+                .expect("the repl's roc_alloc counts allocations");
+
+            std::ptr::read_volatile(*global)
+        };
+
+        (read(ALLOC_COUNT_GLOBAL), read(ALLOC_BYTES_GLOBAL))
+    }
+
+    /// Warms up by calling the main function, then calls it over and over again,
+    /// measuring each run. Every run writes its result to the same buffer, and the values
+    /// it returns are leaked, just like the one that gets printed.
+    fn bench(
+        &mut self,
+        main_fn_name: &str,
+        ret_bytes: usize,
+    ) -> Result<Measurements, (String, CrashTag)> {
+        let size = std::mem::size_of::<RocCallResult<()>>() + ret_bytes;
+        let layout = std::alloc::Layout::from_size_align(size, 16).unwrap();
+        let result = unsafe { std::alloc::alloc(layout) };
+
+        let outcome = self.bench_runs(main_fn_name, result);
+
+        unsafe { std::alloc::dealloc(result, layout) };
+
+        outcome
+    }
+
+    fn bench_runs(
+        &mut self,
+        main_fn_name: &str,
+        result: *mut u8,
+    ) -> Result<Measurements, (String, CrashTag)> {
+        let mut run = || {
+            self.run_main(main_fn_name, result);
+
+            let header: Result<(), (String, CrashTag)> =
+                unsafe { std::ptr::read(result as *const RocCallResult<()>) }.into();
+
+            header.map(|()| self.last_run.unwrap())
+        };
+
+        let warmup_start = Instant::now();
+        let mut warmup_runs = 1;
+
+        while warmup_runs < BENCH_MIN_WARMUP_RUNS || warmup_start.elapsed() < BENCH_WARMUP_TIME {
+            run()?;
+            warmup_runs += 1;
+        }
+
+        let start = Instant::now();
+        let mut runs = Vec::new();
+
+        while runs.len() < BENCH_MIN_RUNS
+            || (start.elapsed() < BENCH_TIME && runs.len() < BENCH_MAX_RUNS)
+        {
+            runs.push(run()?);
+        }
+
+        Ok(Measurements { warmup_runs, runs })
+    }
+}
+
+/// The payload that a crash in the evaluated Roc code unwinds with
+struct RocCrash {
+    message: String,
+    tag: CrashTag,
+}
+
+impl RocCrash {
+    fn unwind((message, tag): (String, CrashTag)) -> ! {
+        // resume_unwind doesn't run the panic hook, so nothing gets printed
+        std::panic::resume_unwind(Box::new(RocCrash { message, tag }))
+    }
 }
 
 struct CliMemory;
@@ -116,7 +378,18 @@ impl<'a> ReplApp<'a> for CliApp {
         F: FnMut(&'a Self::Memory, Return) -> Expr<'a>,
         Self::Memory: 'a,
     {
-        run_jit_function!(self.lib, main_fn_name, Return, |v| transform(&CliMemory, v))
+        let layout = std::alloc::Layout::new::<RocCallResult<Return>>();
+        let result = unsafe { std::alloc::alloc(layout) };
+
+        self.run_main(main_fn_name, result);
+
+        let result: Result<Return, (String, CrashTag)> =
+            unsafe { std::ptr::read(result as *const RocCallResult<Return>) }.into();
+
+        match result {
+            Ok(value) => transform(&CliMemory, value),
+            Err(crash) => RocCrash::unwind(crash),
+        }
     }
 
     /// Run user code that returns a struct or union, whose size is provided as an argument
@@ -130,9 +403,21 @@ impl<'a> ReplApp<'a> for CliApp {
         F: FnMut(&'a Self::Memory, usize) -> T,
         Self::Memory: 'a,
     {
-        run_jit_function_dynamic_type!(self.lib, main_fn_name, ret_bytes, |v| transform(
-            &CliMemory, v
-        ))
+        // Like try_run_jit_function_dynamic_type!, but without converting the value while
+        // the call is being measured
+        let header_size = std::mem::size_of::<RocCallResult<()>>();
+        let layout = std::alloc::Layout::array::<u8>(header_size + ret_bytes).unwrap();
+        let result = unsafe { std::alloc::alloc(layout) };
+
+        self.run_main(main_fn_name, result);
+
+        let header: Result<(), (String, CrashTag)> =
+            unsafe { std::ptr::read(result as *const RocCallResult<()>) }.into();
+
+        match header {
+            Ok(()) => transform(&CliMemory, unsafe { result.add(header_size) } as usize),
+            Err(crash) => RocCrash::unwind(crash),
+        }
     }
 }
 
@@ -185,6 +470,7 @@ fn mono_module_to_dylib<'a>(
     target: Triple,
     loaded: MonomorphizedModule<'a>,
     opt_level: OptLevel,
+    max_memory: Option<u64>,
 ) -> Result<(libloading::Library, &'a str, Subs, STLayoutInterner<'a>), libloading::Error> {
     let target_info = TargetInfo::from(&target);
 
@@ -222,12 +508,26 @@ fn mono_module_to_dylib<'a>(
         mode: LlvmBackendMode::GenTest, // so roc_panic is generated
         // important! we don't want any procedures to get the C calling convention
         exposed_to_host: MutSet::default(),
+        trace_effects: false,
+        thread_safety: roc_gen_llvm::llvm::build::ThreadSafety::SingleThreaded,
+        list_growth_percent: None,
+        expect_call_sites: MutMap::default(),
     };
 
     // Add roc_alloc, roc_realloc, and roc_dealloc, since the repl has no
     // platform to provide them.
     add_default_roc_externs(&env);
 
+    // So that :time and :bench can report allocations
+    add_allocation_counters(&env);
+
+    // So that an expression can be stopped when it goes over its limits, or is cancelled
+    add_exported_crash(&env);
+
+    if let Some(max_bytes) = max_memory {
+        add_memory_limit(&env, max_bytes);
+    }
+
     let entry_point = match entry_point {
         EntryPoint::Executable {
             exposed_to_host,
@@ -1,11 +1,17 @@
 //! Command Line Interface (CLI) functionality for the Read-Evaluate-Print-Loop (REPL).
 mod cli_gen;
 mod colors;
+pub mod limits;
+pub mod platform;
 pub mod repl_state;
 
 use colors::{BLUE, END_COL, PINK};
 use const_format::concatcp;
-use repl_state::ReplState;
+use limits::EvalLimits;
+use platform::ReplPlatform;
+use repl_state::{OutputFormat, ReplState};
+use roc_repl_eval::gen::ReplDeps;
+use std::path::{Path, PathBuf};
 
 use crate::repl_state::PROMPT;
 
@@ -25,17 +31,106 @@ pub const WELCOME_MESSAGE: &str = concatcp!(
 // TODO add link to repl tutorial(does not yet exist).
 pub const SHORT_INSTRUCTIONS: &str = "Enter an expression, or :help, or :q to quit.\n\n";
 
-pub fn main() -> i32 {
+/// If `roc repl` is not given a `--prelude`, it looks for one in this environment variable.
+pub const PRELUDE_ENV_VAR: &str = "ROC_REPL_PRELUDE";
+
+/// Evaluates one expression and prints its value, for `roc eval`. Returns the exit code.
+pub fn eval(expr: &str, dep_paths: &[PathBuf], output_format: OutputFormat) -> i32 {
+    let deps = match ReplDeps::from_files(dep_paths) {
+        Ok(deps) => deps,
+        Err(message) => {
+            eprintln!("{}", message);
+
+            return 1;
+        }
+    };
+
+    let mut state = ReplState::new();
+
+    state.set_deps(deps);
+    state.set_output_format(output_format);
+
+    match state.eval_once(expr) {
+        Ok(value) => {
+            println!("{}", value);
+
+            0
+        }
+        Err(problems) => {
+            eprintln!("{}", problems);
+
+            1
+        }
+    }
+}
+
+pub fn main(
+    prelude_path: Option<&Path>,
+    platform_path: Option<&Path>,
+    output_format: OutputFormat,
+    limits: EvalLimits,
+) -> i32 {
     use rustyline::error::ReadlineError;
     use rustyline::Editor;
 
     // To debug rustyline:
     // <UNCOMMENT> env_logger::init();
     // <RUN WITH:> RUST_LOG=rustyline=debug cargo run repl 2> debug.log
-    print!("{}{}", WELCOME_MESSAGE, SHORT_INSTRUCTIONS);
+    if output_format == OutputFormat::Roc {
+        // JSON output is meant for other programs, which would trip over the banner
+        print!("{}{}", WELCOME_MESSAGE, SHORT_INSTRUCTIONS);
+    }
 
     let mut editor = Editor::<ReplState>::new();
-    let repl_helper = ReplState::new();
+    let mut repl_helper = ReplState::new();
+
+    repl_helper.set_output_format(output_format);
+    repl_helper.set_limits(limits);
+
+    if let Some(platform_path) = platform_path {
+        match ReplPlatform::load(platform_path) {
+            Ok(platform) => {
+                if !platform::confirm_effects(platform_path) {
+                    return 1;
+                }
+
+                repl_helper.set_platform(platform);
+            }
+            Err(message) => {
+                eprintln!("{}", message);
+
+                return 1;
+            }
+        }
+    }
+
+    let prelude_path = prelude_path
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::var_os(PRELUDE_ENV_VAR).map(PathBuf::from));
+
+    if let Some(prelude_path) = prelude_path {
+        match std::fs::read_to_string(&prelude_path) {
+            Ok(prelude_src) => {
+                if let Err(problems) = repl_helper.load_prelude(&prelude_src) {
+                    println!(
+                        "Some definitions in the prelude {} had problems, so they were skipped:\n{}",
+                        prelude_path.display(),
+                        problems
+                    );
+                }
+            }
+            Err(err) => {
+                eprintln!(
+                    "I could not read the prelude {}: {}",
+                    prelude_path.display(),
+                    err
+                );
+
+                return 1;
+            }
+        }
+    }
+
     editor.set_helper(Some(repl_helper));
 
     loop {
@@ -0,0 +1,182 @@
+//! Stopping an expression that runs for too long or allocates too much, for `roc repl
+//! --timeout-ms` and `--max-memory`, or because Ctrl-C was pressed, without ending the session.
+//!
+//! Expressions run on the REPL's own thread, so this works like `roc test` does for pure
+//! expects, see [roc_repl_expect::timeout] and [roc_repl_expect::memory_limit]: the expression
+//! crashes, and the crash gets reported as why it was stopped. Whatever it had allocated is
+//! leaked. Where `roc test` can't stop expects, there is no timeout, and Ctrl-C ends the REPL
+//! like it always did.
+use std::time::Duration;
+
+pub(crate) use watch::Watch;
+
+/// What an expression may use up
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EvalLimits {
+    /// How long it may run
+    pub timeout: Option<Duration>,
+    /// How many bytes it may have allocated at once
+    pub max_memory: Option<u64>,
+}
+
+/// Why an expression didn't finish, in place of its crash message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Stopped {
+    pub title: &'static str,
+    pub message: String,
+}
+
+#[cfg(not(windows))]
+mod watch {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{mpsc, Once};
+    use std::time::Duration;
+
+    use libloading::Library;
+    use roc_repl_expect::memory_limit::MemoryLimit;
+    use roc_repl_expect::timeout::{ExpectTimeout, Timer};
+    use signal_hook::consts::signal::SIGINT;
+
+    use super::{EvalLimits, Stopped};
+
+    /// How long an expression runs before the REPL says how to cancel it
+    const STILL_RUNNING_AFTER: Duration = Duration::from_secs(2);
+
+    /// Whether Ctrl-C should cancel the expression, rather than end the REPL
+    static EVALUATING: AtomicBool = AtomicBool::new(false);
+    static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+    /// Watches an expression while it runs on the current thread, to stop it when it goes over
+    /// its [EvalLimits] or Ctrl-C is pressed. The memory limit needs `lib` to be built with
+    /// [add_memory_limit](roc_gen_llvm::llvm::externs::add_memory_limit).
+    pub(crate) struct Watch {
+        timer: Option<Timer>,
+        timeout: Option<Duration>,
+        memory_limit: Option<MemoryLimit>,
+        /// Dropping this keeps the "still running" note from being printed
+        _running: Option<mpsc::Sender<()>>,
+    }
+
+    impl Watch {
+        pub(crate) fn start(lib: &Library, limits: EvalLimits) -> Self {
+            let duration = limits.timeout.unwrap_or(Duration::MAX);
+            let timer = ExpectTimeout::new(lib, duration).map(|timeout| {
+                install_sigint_handler();
+
+                CANCELLED.store(false, Ordering::SeqCst);
+                EVALUATING.store(true, Ordering::SeqCst);
+
+                timeout.start_cancellable(&CANCELLED)
+            });
+
+            // Only say Ctrl-C cancels when it does
+            let running = timer.as_ref().map(|_| {
+                let (running, finished) = mpsc::channel::<()>();
+
+                std::thread::spawn(move || {
+                    if finished.recv_timeout(STILL_RUNNING_AFTER)
+                        == Err(mpsc::RecvTimeoutError::Timeout)
+                    {
+                        eprintln!("Still running… press Ctrl-C to cancel.");
+                    }
+                });
+
+                running
+            });
+
+            let memory_limit = MemoryLimit::new(lib);
+
+            if let Some(memory_limit) = &memory_limit {
+                memory_limit.reset();
+            }
+
+            Self {
+                timer,
+                timeout: limits.timeout,
+                memory_limit,
+                _running: running,
+            }
+        }
+
+        /// Stops watching. If the expression crashed with `crash_message` because it was
+        /// stopped, returns why.
+        pub(crate) fn stop(self, crash_message: Option<&str>) -> Option<Stopped> {
+            let interrupted = match self.timer {
+                Some(timer) => {
+                    let interrupted = timer.stop();
+
+                    EVALUATING.store(false, Ordering::SeqCst);
+
+                    interrupted
+                }
+                None => false,
+            };
+
+            if interrupted {
+                let stopped = if CANCELLED.load(Ordering::SeqCst) {
+                    Stopped {
+                        title: "CANCELLED",
+                        message: "It was cancelled with Ctrl-C.".to_string(),
+                    }
+                } else {
+                    let timeout = self.timeout.unwrap_or_default();
+
+                    Stopped {
+                        title: "TIMED OUT",
+                        message: format!(
+                            "It ran for longer than the time limit of {} ms.",
+                            timeout.as_millis()
+                        ),
+                    }
+                };
+
+                return Some(stopped);
+            }
+
+            let message = self.memory_limit?.explain(crash_message?, "expression")?;
+
+            Some(Stopped {
+                title: "OUT OF MEMORY",
+                message,
+            })
+        }
+    }
+
+    /// Ctrl-C cancels the expression while one runs, and ends the REPL otherwise. Reading a line
+    /// doesn't involve the signal, since the terminal is in raw mode then.
+    fn install_sigint_handler() {
+        static INSTALL: Once = Once::new();
+
+        INSTALL.call_once(|| {
+            // If it can't be installed, Ctrl-C ends the REPL like before
+            let _ = unsafe {
+                signal_hook::low_level::register(SIGINT, || {
+                    if EVALUATING.load(Ordering::SeqCst) {
+                        CANCELLED.store(true, Ordering::SeqCst);
+                    } else {
+                        let _ = signal_hook::low_level::emulate_default_handler(SIGINT);
+                    }
+                })
+            };
+        });
+    }
+}
+
+#[cfg(windows)]
+mod watch {
+    use libloading::Library;
+
+    use super::{EvalLimits, Stopped};
+
+    pub(crate) struct Watch;
+
+    impl Watch {
+        pub(crate) fn start(_lib: &Library, _limits: EvalLimits) -> Self {
+            Watch
+        }
+
+        pub(crate) fn stop(self, _crash_message: Option<&str>) -> Option<Stopped> {
+            None
+        }
+    }
+}
@@ -0,0 +1,250 @@
+//! Lets `roc repl --platform` evaluate expressions that use the platform's effects, like
+//! `Stdout.line "hi"`.
+//!
+//! Such an expression becomes the `main` of a small app on that platform, which gets built and
+//! linked with the platform's host the same way `roc run` would do it. Running the app performs
+//! the effects, with the host's stdin and stdout connected to the REPL's terminal.
+use bumpalo::Bump;
+use roc_build::link::{LinkType, LinkingStrategy, Sanitizers};
+use roc_build::program::{
+    build_file, report_problems_typechecked, standard_load_config, BuildFileError, BuildOrdering,
+    BuiltFile, CodeGenBackend, CodeGenOptions,
+};
+use roc_gen_llvm::llvm::build::LlvmBackendMode;
+use roc_load::{LoadingProblem, Threading};
+use roc_mono::ir::OptLevel;
+use roc_packaging::cache::{self, RocCacheDir};
+use roc_parse::ast::{Header, Spaced};
+use roc_parse::module::parse_header;
+use roc_parse::state::State;
+use roc_repl_eval::gen::{Problems, ReplOutput};
+use std::path::{Path, PathBuf};
+use target_lexicon::Triple;
+
+pub struct ReplPlatform {
+    main_path: PathBuf,
+    /// The modules the platform exposes to apps, e.g. `Stdout` and `Task`.
+    exposed_modules: Vec<String>,
+    /// The name of the def the platform requires apps to provide, e.g. `main`.
+    required_name: String,
+}
+
+impl ReplPlatform {
+    /// Reads the header of the platform's main .roc file.
+    pub fn load(main_path: &Path) -> Result<Self, String> {
+        let src = std::fs::read_to_string(main_path)
+            .map_err(|err| format!("I could not read {}: {}", main_path.display(), err))?;
+        let arena = Bump::new();
+
+        let header = match parse_header(&arena, State::new(src.as_bytes())) {
+            Ok((module, _)) => module.header,
+            Err(_) => {
+                return Err(format!(
+                    "The header of {} has a syntax error.",
+                    main_path.display()
+                ))
+            }
+        };
+
+        let header = match header {
+            Header::Platform(header) => header,
+            _ => {
+                return Err(format!(
+                    "{} is not the main file of a platform; it does not start with a `platform` header.",
+                    main_path.display()
+                ))
+            }
+        };
+
+        let exposed_modules = (header.exposes.item.iter())
+            .map(|module_name| unspaced(&module_name.value).as_str().to_string())
+            .collect();
+        let required_name = unspaced(&header.requires.item.signature.value)
+            .ident
+            .value
+            .to_string();
+
+        Ok(ReplPlatform {
+            main_path: main_path
+                .canonicalize()
+                .unwrap_or_else(|_| main_path.to_owned()),
+            exposed_modules,
+            required_name,
+        })
+    }
+
+    /// Whether `src` refers to any of the modules the platform exposes.
+    pub fn is_effectful(&self, src: &str) -> bool {
+        self.exposed_modules
+            .iter()
+            .any(|module_name| refers_to_module(src, module_name))
+    }
+
+    /// Builds an app that runs `expr` as its `main`, with `defs` in scope, and then runs it.
+    /// Any problems the build has get printed as it goes, like `roc run` does.
+    pub fn build_and_run<'i, I: Iterator<Item = &'i str>>(
+        &self,
+        defs: I,
+        expr: &str,
+    ) -> (Option<ReplOutput>, Problems) {
+        let dir = match tempfile::tempdir() {
+            Ok(dir) => dir,
+            Err(err) => return error(format!("I could not create a temporary directory: {err}")),
+        };
+        let app_path = dir.path().join("repl.roc");
+
+        if let Err(err) = std::fs::write(&app_path, self.app_source(defs, expr)) {
+            return error(format!("I could not write {}: {}", app_path.display(), err));
+        }
+
+        let arena = Bump::new();
+        let triple = Triple::host();
+        let linking_strategy = if roc_linker::supported(LinkType::Executable, &triple) {
+            LinkingStrategy::Surgical
+        } else {
+            LinkingStrategy::Legacy
+        };
+        let code_gen_options = CodeGenOptions {
+            backend: CodeGenBackend::Llvm(LlvmBackendMode::Binary),
+            opt_level: OptLevel::Normal,
+            emit_debug_info: false,
+            trace_effects: false,
+            hardening: true,
+            sanitizers: Sanitizers::default(),
+            dump_specializations: false,
+            dump_layouts: false,
+            list_growth_percent: None,
+        };
+
+        let built = build_file(
+            &arena,
+            &triple,
+            app_path,
+            code_gen_options,
+            false,
+            LinkType::Executable,
+            linking_strategy,
+            false,
+            None,
+            RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
+            standard_load_config(
+                &triple,
+                BuildOrdering::BuildIfChecks,
+                Threading::AllAvailable,
+            ),
+        );
+
+        match built {
+            Ok(BuiltFile {
+                binary_path,
+                problems,
+                ..
+            }) if problems.errors == 0 => match std::process::Command::new(&binary_path).status() {
+                Ok(status) if status.success() => (
+                    Some(ReplOutput {
+                        expr: String::new(),
+                        expr_type: String::new(),
+                    }),
+                    Problems::default(),
+                ),
+                Ok(status) => error(format!("The platform's host exited with {status}.")),
+                Err(err) => error(format!("I could not run the built app: {err}")),
+            },
+            // The errors were already printed while building.
+            Ok(_) => error("The expression has errors, so it was not run.".to_string()),
+            Err(BuildFileError::ErrorModule { mut module, .. }) => {
+                report_problems_typechecked(&mut module, None, None);
+
+                error("The expression has errors, so it was not run.".to_string())
+            }
+            Err(BuildFileError::LoadingProblem(LoadingProblem::FormattedReport(report))) => {
+                error(report)
+            }
+            Err(BuildFileError::LoadingProblem(problem)) => {
+                error(format!("I could not build the expression: {problem:?}"))
+            }
+        }
+    }
+
+    fn app_source<'i, I: Iterator<Item = &'i str>>(&self, defs: I, expr: &str) -> String {
+        let imports: Vec<String> = (self.exposed_modules.iter())
+            .map(|module_name| format!("pf.{module_name}"))
+            .collect();
+
+        let mut buf = format!(
+            "app \"repl\"\n    packages {{ pf: {:?} }}\n    imports [{}]\n    provides [{}] to pf\n\n",
+            self.main_path.display().to_string(),
+            imports.join(", "),
+            self.required_name,
+        );
+
+        for def in defs {
+            buf.push_str(def);
+            buf.push_str("\n\n");
+        }
+
+        buf.push_str(&self.required_name);
+        buf.push_str(" =\n");
+
+        for line in expr.lines() {
+            buf.push_str("    ");
+            buf.push_str(line);
+            buf.push('\n');
+        }
+
+        buf
+    }
+}
+
+/// Effects can do anything the platform lets them, so the user has to agree to running them.
+pub fn confirm_effects(main_path: &Path) -> bool {
+    use std::io::Write;
+
+    print!(
+        "Expressions that use {} will be run with its host, so they can do anything the platform can (like changing files or making network requests). Continue? [y/N] ",
+        main_path.display()
+    );
+
+    let _ = std::io::stdout().flush();
+
+    let mut answer = String::new();
+
+    match std::io::stdin().read_line(&mut answer) {
+        Ok(_) => matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"),
+        Err(_) => false,
+    }
+}
+
+fn error(message: String) -> (Option<ReplOutput>, Problems) {
+    (
+        None,
+        Problems {
+            errors: vec![message],
+            warnings: Vec::new(),
+        },
+    )
+}
+
+fn unspaced<'a, 'b, T>(spaced: &'b Spaced<'a, T>) -> &'b T {
+    match spaced {
+        Spaced::Item(item) => item,
+        Spaced::SpaceBefore(inner, _) | Spaced::SpaceAfter(inner, _) => unspaced(inner),
+    }
+}
+
+/// Whether `src` contains a qualified name from `module_name`, like `Stdout.line`.
+fn refers_to_module(src: &str, module_name: &str) -> bool {
+    src.match_indices(module_name).any(|(index, _)| {
+        let before = src[..index].chars().next_back();
+        let after = src[index + module_name.len()..].chars().next();
+
+        !matches!(before, Some(c) if c.is_alphanumeric() || c == '_' || c == '.')
+            && after == Some('.')
+    })
+}
+
+/// Whether `src` contains `ident` as a whole word.
+pub(crate) fn refers_to_def(src: &str, ident: &str) -> bool {
+    src.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .any(|word| word == ident)
+}
@@ -1,5 +1,7 @@
-use crate::cli_gen::gen_and_eval_llvm;
+use crate::cli_gen::{gen_and_eval_llvm, gen_and_measure_llvm, Measure, Measurements};
 use crate::colors::{BLUE, END_COL, GREEN, PINK};
+use crate::limits::EvalLimits;
+use crate::platform::{refers_to_def, ReplPlatform};
 use bumpalo::Bump;
 use const_format::concatcp;
 use roc_collections::MutSet;
@@ -12,11 +14,12 @@ use roc_parse::parser::{EWhen, Either};
 use roc_parse::state::State;
 use roc_parse::{join_alias_to_body, join_ann_to_body};
 use roc_region::all::Loc;
-use roc_repl_eval::gen::{Problems, ReplOutput};
+use roc_repl_eval::gen::{Problems, ReplDeps, ReplOutput};
 use rustyline::highlight::{Highlighter, PromptInfo};
 use rustyline::validate::{self, ValidationContext, ValidationResult, Validator};
 use rustyline_derive::{Completer, Helper, Hinter};
 use std::borrow::Cow;
+use std::time::Duration;
 use target_lexicon::Triple;
 
 pub const PROMPT: &str = concatcp!(BLUE, "»", END_COL, " ");
@@ -59,13 +62,44 @@ pub const TIPS: &str = concatcp!(
     BLUE,
     "  - ",
     END_COL,
+    ":set output json prints values as JSON (and :set output roc goes back)\n\n",
+    BLUE,
+    "  - ",
+    END_COL,
+    ":time <expr> prints how long an expression took, and :bench <expr> runs it many times\n\n",
+    BLUE,
+    "  - ",
+    END_COL,
     ":help"
 );
 
+/// How the REPL prints the values it evaluates. Change it with `:set output json`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Roc syntax, followed by the type and the name the value can be referred to by.
+    #[default]
+    Roc,
+    /// Only the value, as JSON (see [roc_value_render::Json]), e.g. to pipe into `jq`.
+    Json,
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "roc" => Some(OutputFormat::Roc),
+            "json" => Some(OutputFormat::Json),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 struct PastDef {
     ident: String,
     src: String,
+    /// Whether this def uses the platform's effects, so it can only be used from
+    /// expressions that get run on the platform.
+    effectful: bool,
 }
 
 #[derive(Completer, Helper, Hinter)]
@@ -74,6 +108,10 @@ pub struct ReplState {
     past_defs: Vec<PastDef>,
     past_def_idents: MutSet<String>,
     last_auto_ident: u64,
+    platform: Option<ReplPlatform>,
+    output_format: OutputFormat,
+    deps: ReplDeps,
+    limits: EvalLimits,
 }
 
 impl Default for ReplState {
@@ -89,9 +127,33 @@ impl ReplState {
             past_defs: Default::default(),
             past_def_idents: Default::default(),
             last_auto_ident: 0,
+            platform: None,
+            output_format: OutputFormat::Roc,
+            deps: ReplDeps::default(),
+            limits: EvalLimits::default(),
         }
     }
 
+    /// Lets expressions use the platform's effects (e.g. the file given to `roc repl --platform`).
+    pub fn set_platform(&mut self, platform: ReplPlatform) {
+        self.platform = Some(platform);
+    }
+
+    pub fn set_output_format(&mut self, output_format: OutputFormat) {
+        self.output_format = output_format;
+    }
+
+    /// Lets expressions use these modules (e.g. the files given to `roc eval --dep`).
+    pub fn set_deps(&mut self, deps: ReplDeps) {
+        self.deps = deps;
+    }
+
+    /// Stops expressions that go over these limits (e.g. `roc repl --timeout-ms`), instead of
+    /// letting them run until they're done.
+    pub fn set_limits(&mut self, limits: EvalLimits) {
+        self.limits = limits;
+    }
+
     pub fn step(&mut self, line: &str, dimensions: Option<(usize, usize)>) -> Result<String, i32> {
         let arena = Bump::new();
 
@@ -107,10 +169,132 @@ impl ReplState {
                 Ok(TIPS.to_string())
             }
             ParseOutcome::Exit => Err(0),
+            ParseOutcome::SetOutput(Some(output_format)) => {
+                self.output_format = output_format;
+
+                Ok(String::new())
+            }
+            ParseOutcome::SetOutput(None) => Ok(format!(
+                "\nThe settings I know are {BLUE}:set output roc{END_COL} and {BLUE}:set output json{END_COL}.\n"
+            )),
+            ParseOutcome::Measure(measure, src) => {
+                Ok(self.measure_and_format(measure, src, dimensions))
+            }
+        }
+    }
+
+    /// Evaluates an expression for `:time` or `:bench`, and prints how long it took after its
+    /// value. Unlike expressions entered on their own, it doesn't get a name to refer to it by.
+    fn measure_and_format(
+        &self,
+        measure: Measure,
+        src: &str,
+        dimensions: Option<(usize, usize)>,
+    ) -> String {
+        let command = match measure {
+            Measure::Time => ":time",
+            Measure::Bench => ":bench",
+        };
+
+        if src.trim().is_empty() {
+            return format!("\nUsage: {BLUE}{command} <expr>{END_COL}\n");
+        }
+
+        if self.is_effectful(src) {
+            return format!(
+                "\n{BLUE}{command}{END_COL} can only run expressions that don't use the platform's effects.\n"
+            );
+        }
+
+        let (output, problems, opt_measurements) = gen_and_measure_llvm(
+            (self.past_defs.iter())
+                .filter(|def| !def.effectful)
+                .map(|def| def.src.as_str()),
+            src,
+            &self.deps,
+            Triple::host(),
+            OptLevel::Normal,
+            self.output_format,
+            self.limits,
+            measure,
+        );
+
+        // Only the errors get printed in that case, so don't print the time either
+        let has_errors = !problems.errors.is_empty();
+        let mut buf = match self.output_format {
+            OutputFormat::Roc => format_output(output, problems, None, dimensions),
+            OutputFormat::Json => format_json_output(output, problems),
+        };
+
+        if let (Some(measurements), false) = (opt_measurements, has_errors) {
+            buf.push_str("\n\n");
+            buf.push_str(&format_measurements(&measurements));
+        }
+
+        buf
+    }
+
+    /// Adds the contents of a prelude (e.g. the file given to `roc repl --prelude`), as if
+    /// each of its entries had been entered at the prompt before the session started.
+    /// Entries are separated by blank lines, like the defs the REPL keeps around.
+    ///
+    /// Entries that fail to compile are skipped, and their problems are returned.
+    pub fn load_prelude(&mut self, prelude_src: &str) -> Result<(), String> {
+        let mut buf = String::new();
+
+        for entry in prelude_entries(prelude_src) {
+            if let Some((_, problems, opt_var_name)) = self.eval(&entry) {
+                if !problems.errors.is_empty() {
+                    // Defs get recorded even if they have errors (so that the errors are
+                    // reported when they are used), but a broken prelude def is just noise.
+                    if let Some(var_name) = opt_var_name {
+                        self.remove_past_def(&var_name);
+                    }
+
+                    for message in problems.errors {
+                        buf.push('\n');
+                        buf.push_str(&message);
+                        buf.push('\n');
+                    }
+                }
+            }
+        }
+
+        if buf.is_empty() {
+            Ok(())
+        } else {
+            Err(buf)
         }
     }
 
     pub fn eval_and_format(&mut self, src: &str, dimensions: Option<(usize, usize)>) -> String {
+        match self.eval(src) {
+            Some((output, problems, opt_var_name)) => match self.output_format {
+                OutputFormat::Roc => format_output(output, problems, opt_var_name, dimensions),
+                OutputFormat::Json => format_json_output(output, problems),
+            },
+            None => String::new(),
+        }
+    }
+
+    /// Evaluates a single expression, e.g. for `roc eval`. Returns just the value (without its
+    /// type), or the errors that kept it from being evaluated.
+    pub fn eval_once(&mut self, src: &str) -> Result<String, String> {
+        match self.eval(src) {
+            Some((_, problems, _)) if !problems.errors.is_empty() => {
+                Err(problems.errors.join("\n\n"))
+            }
+            Some((Some(ReplOutput { expr, .. }), _, _)) if !expr.is_empty() => Ok(expr),
+            _ => Err(
+                "I can only evaluate expressions, but this is a definition or a type.".to_string(),
+            ),
+        }
+    }
+
+    /// Evaluates the given source, and records it as a past def if appropriate.
+    /// Returns `None` for things that can't be evaluated, like standalone annotations
+    /// and type definitions.
+    fn eval(&mut self, src: &str) -> Option<(Option<ReplOutput>, Problems, Option<String>)> {
         let arena = Bump::new();
         let pending_past_def;
         let mut opt_var_name;
@@ -138,7 +322,7 @@ impl ReplState {
 
                         // Return early without running eval, since standalone annotations
                         // cannnot be evaluated as expressions.
-                        return String::new();
+                        return None;
                     }
                     ValueDef::Body(
                         Loc {
@@ -218,9 +402,13 @@ impl ReplState {
 
                 // Return early without running eval, since none of these
                 // can be evaluated as expressions.
-                return String::new();
+                return None;
             }
-            ParseOutcome::Empty | ParseOutcome::Help | ParseOutcome::Exit => unreachable!(),
+            ParseOutcome::Empty
+            | ParseOutcome::Help
+            | ParseOutcome::Exit
+            | ParseOutcome::SetOutput(_)
+            | ParseOutcome::Measure(_, _) => unreachable!(),
         };
 
         // Record e.g. "val1" as a past def, unless our input was exactly the name of
@@ -231,20 +419,10 @@ impl ReplState {
                 Some(existing_ident) => {
                     opt_var_name = Some(existing_ident);
 
-                    gen_and_eval_llvm(
-                        self.past_defs.iter().map(|def| def.src.as_str()),
-                        src,
-                        Triple::host(),
-                        OptLevel::Normal,
-                    )
+                    self.gen_and_eval(src)
                 }
                 None => {
-                    let (output, problems) = gen_and_eval_llvm(
-                        self.past_defs.iter().map(|def| def.src.as_str()),
-                        src,
-                        Triple::host(),
-                        OptLevel::Normal,
-                    );
+                    let (output, problems) = self.gen_and_eval(src);
 
                     // Don't persist defs that have compile errors
                     if problems.errors.is_empty() {
@@ -266,7 +444,38 @@ impl ReplState {
             self.add_past_def(ident, src);
         }
 
-        format_output(output, problems, opt_var_name, dimensions)
+        Some((output, problems, opt_var_name))
+    }
+
+    /// Runs effectful expressions on the platform, and evaluates everything else in the JIT.
+    fn gen_and_eval(&self, src: &str) -> (Option<ReplOutput>, Problems) {
+        match &self.platform {
+            Some(platform) if self.is_effectful(src) => {
+                platform.build_and_run(self.past_defs.iter().map(|def| def.src.as_str()), src)
+            }
+            _ => gen_and_eval_llvm(
+                (self.past_defs.iter())
+                    .filter(|def| !def.effectful)
+                    .map(|def| def.src.as_str()),
+                src,
+                &self.deps,
+                Triple::host(),
+                OptLevel::Normal,
+                self.output_format,
+                self.limits,
+            ),
+        }
+    }
+
+    fn is_effectful(&self, src: &str) -> bool {
+        match &self.platform {
+            Some(platform) => {
+                platform.is_effectful(src)
+                    || (self.past_defs.iter())
+                        .any(|def| def.effectful && refers_to_def(src, &def.ident))
+            }
+            None => false,
+        }
     }
 
     fn next_auto_ident(&mut self) -> u64 {
@@ -279,10 +488,56 @@ impl ReplState {
 
         existing_idents.insert(ident.clone());
 
-        self.past_defs.push(PastDef { ident, src });
+        let effectful = self.is_effectful(&src);
+
+        self.past_defs.push(PastDef {
+            ident,
+            src,
+            effectful,
+        });
+    }
+
+    fn remove_past_def(&mut self, ident: &str) {
+        self.past_def_idents.remove(ident);
+        self.past_defs.retain(|def| def.ident != ident);
     }
 }
 
+/// Splits a prelude into its entries. A new entry starts at every unindented line
+/// that follows a blank line, so that blank lines inside of a def body are fine.
+fn prelude_entries(prelude_src: &str) -> Vec<String> {
+    let mut entries = Vec::new();
+    let mut current = String::new();
+    let mut after_blank_line = false;
+
+    for line in prelude_src.lines() {
+        if line.trim().is_empty() {
+            after_blank_line = true;
+        } else {
+            let starts_entry = !line.starts_with(char::is_whitespace);
+
+            if after_blank_line && starts_entry && !current.trim().is_empty() {
+                entries.push(std::mem::take(&mut current));
+            } else if after_blank_line {
+                current.push('\n');
+            }
+
+            if !current.is_empty() {
+                current.push('\n');
+            }
+
+            current.push_str(line);
+            after_blank_line = false;
+        }
+    }
+
+    if !current.trim().is_empty() {
+        entries.push(current);
+    }
+
+    entries
+}
+
 #[derive(Debug, PartialEq)]
 enum ParseOutcome<'a> {
     ValueDef(ValueDef<'a>),
@@ -293,6 +548,10 @@ enum ParseOutcome<'a> {
     Empty,
     Help,
     Exit,
+    /// `:set output <format>`, or `None` for a setting we don't know
+    SetOutput(Option<OutputFormat>),
+    /// `:time <expr>` or `:bench <expr>`
+    Measure(Measure, &'a str),
 }
 
 fn parse_src<'a>(arena: &'a Bump, line: &'a str) -> ParseOutcome<'a> {
@@ -300,6 +559,18 @@ fn parse_src<'a>(arena: &'a Bump, line: &'a str) -> ParseOutcome<'a> {
         "" => ParseOutcome::Empty,
         ":help" => ParseOutcome::Help,
         ":exit" | ":quit" | ":q" => ParseOutcome::Exit,
+        command if command == ":set" || command.starts_with(":set ") => {
+            match command.split_whitespace().collect::<Vec<_>>().as_slice() {
+                [_, "output", format] => ParseOutcome::SetOutput(OutputFormat::from_name(format)),
+                _ => ParseOutcome::SetOutput(None),
+            }
+        }
+        command if is_command(command, ":time") => {
+            ParseOutcome::Measure(Measure::Time, &line.trim_start()[":time".len()..])
+        }
+        command if is_command(command, ":bench") => {
+            ParseOutcome::Measure(Measure::Bench, &line.trim_start()[":bench".len()..])
+        }
         _ => {
             let src_bytes = line.as_bytes();
 
@@ -457,6 +728,14 @@ fn parse_src<'a>(arena: &'a Bump, line: &'a str) -> ParseOutcome<'a> {
     }
 }
 
+/// Whether the (lowercased) line is the given command, either on its own or followed by arguments
+fn is_command(line: &str, command: &str) -> bool {
+    match line.strip_prefix(command) {
+        Some(rest) => rest.is_empty() || rest.starts_with(char::is_whitespace),
+        None => false,
+    }
+}
+
 struct InputValidator {}
 
 impl InputValidator {
@@ -491,9 +770,12 @@ pub fn is_incomplete(input: &str) -> bool {
             // them until they enter a blank line!
             !input.ends_with('\n')
         }
+        // The expression being measured can span multiple lines, just like one entered on its own
+        ParseOutcome::Measure(_, src) => !src.trim().is_empty() && is_incomplete(src.trim_start()),
         ParseOutcome::Empty
         | ParseOutcome::Help
         | ParseOutcome::Exit
+        | ParseOutcome::SetOutput(_)
         | ParseOutcome::ValueDef(_)
         | ParseOutcome::TypeDef(_)
         | ParseOutcome::SyntaxErr
@@ -614,3 +896,108 @@ fn format_output(
 
     buf
 }
+
+/// Like [format_output], but prints only the value (already rendered as JSON), so the output
+/// of piping expressions into the REPL can be fed to tools like `jq`.
+fn format_json_output(opt_output: Option<ReplOutput>, problems: Problems) -> String {
+    let mut buf = String::new();
+
+    for message in problems.errors.iter().chain(problems.warnings.iter()) {
+        buf.push('\n');
+        buf.push_str(message);
+        buf.push('\n');
+    }
+
+    if let Some(ReplOutput { expr, .. }) = opt_output {
+        if !expr.is_empty() && problems.errors.is_empty() {
+            buf.push_str(&expr);
+        }
+    }
+
+    buf
+}
+
+/// Prints what `:time` or `:bench` measured, e.g. `Ran in 1.23ms, with 2 allocations (48 bytes)`
+fn format_measurements(measurements: &Measurements) -> String {
+    let runs = &measurements.runs;
+    let mut buf = String::new();
+
+    let allocations = |allocations: usize, bytes: usize| match allocations {
+        0 => "no allocations".to_string(),
+        1 => format!("1 allocation ({})", format_bytes(bytes)),
+        n => format!("{n} allocations ({})", format_bytes(bytes)),
+    };
+
+    if let [run] = runs.as_slice() {
+        buf.push_str(&format!(
+            "Ran in {}, with {}\n",
+            format_duration(run.elapsed),
+            allocations(run.allocations, run.allocated_bytes)
+        ));
+
+        return buf;
+    }
+
+    let mut elapsed: Vec<Duration> = runs.iter().map(|run| run.elapsed).collect();
+    elapsed.sort();
+
+    let total: Duration = elapsed.iter().sum();
+    let mean = total / runs.len() as u32;
+    let median = elapsed[elapsed.len() / 2];
+
+    buf.push_str(&format!(
+        "Ran {} times in {}, after {} warmup runs\n\n",
+        runs.len(),
+        format_duration(total),
+        measurements.warmup_runs
+    ));
+
+    for (label, duration) in [
+        ("min", elapsed[0]),
+        ("median", median),
+        ("mean", mean),
+        ("max", elapsed[elapsed.len() - 1]),
+    ] {
+        buf.push_str(&format!("  {label:<8}{}\n", format_duration(duration)));
+    }
+
+    // Pure code allocates the same amount every time, but print a range just in case
+    let min = runs.iter().map(|run| run.allocations).min().unwrap();
+    let max = runs.iter().map(|run| run.allocations).max().unwrap();
+    let bytes = runs.iter().map(|run| run.allocated_bytes).max().unwrap();
+
+    if min == max {
+        buf.push_str(&format!("\nEach run had {}\n", allocations(min, bytes)));
+    } else {
+        buf.push_str(&format!(
+            "\nEach run had between {min} and {max} allocations (up to {})\n",
+            format_bytes(bytes)
+        ));
+    }
+
+    buf
+}
+
+fn format_duration(duration: Duration) -> String {
+    let nanos = duration.as_nanos();
+
+    if nanos < 1_000 {
+        format!("{nanos}ns")
+    } else if nanos < 1_000_000 {
+        format!("{:.2}µs", nanos as f64 / 1e3)
+    } else if nanos < 1_000_000_000 {
+        format!("{:.2}ms", nanos as f64 / 1e6)
+    } else {
+        format!("{:.2}s", nanos as f64 / 1e9)
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{bytes} bytes")
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
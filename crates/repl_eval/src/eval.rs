@@ -10,8 +10,9 @@ use roc_module::ident::TagName;
 use roc_module::symbol::{Interns, ModuleId, Symbol};
 use roc_mono::ir::ProcLayout;
 use roc_mono::layout::{
-    self, cmp_fields, union_sorted_tags_pub, Builtin, InLayout, Layout, LayoutCache,
-    LayoutInterner, LayoutRepr, TLLayoutInterner, UnionLayout, UnionVariant, WrappedVariant,
+    self, cmp_fields, union_sorted_tags_pub, Builtin, ClosureRepresentation, InLayout, LambdaSet,
+    Layout, LayoutCache, LayoutInterner, LayoutRepr, TLLayoutInterner, UnionLayout, UnionVariant,
+    WrappedVariant,
 };
 use roc_parse::ast::{AssignedField, Collection, Expr, Pattern, StrLiteral};
 use roc_region::all::{Loc, Region};
@@ -29,6 +30,9 @@ struct Env<'a, 'env> {
     target_info: TargetInfo,
     interns: &'a Interns,
     layout_cache: LayoutCache<'a>,
+    /// When set, functions are shown with the values they captured, named by the symbols each
+    /// closure captures. Otherwise they are all shown as `<function>`.
+    closure_captures: Option<&'a MutMap<Symbol, std::vec::Vec<Symbol>>>,
 }
 
 /// JIT execute the given main function, and then wrap its results in an Expr
@@ -39,6 +43,9 @@ struct Env<'a, 'env> {
 /// By traversing the type signature while we're traversing the layout, once
 /// we get to a struct or tag, we know what the labels are and can turn them
 /// back into the appropriate user-facing literals.
+///
+/// Function values are only described, as `<function Parser.many, captures: { ... }>`, when
+/// `closure_captures` is given.
 #[allow(clippy::too_many_arguments)]
 pub fn jit_to_ast<'a, A: ReplApp<'a>>(
     arena: &'a Bump,
@@ -50,6 +57,7 @@ pub fn jit_to_ast<'a, A: ReplApp<'a>>(
     interns: &'a Interns,
     layout_interner: TLLayoutInterner<'a>,
     target_info: TargetInfo,
+    closure_captures: Option<&'a MutMap<Symbol, std::vec::Vec<Symbol>>>,
 ) -> Expr<'a> {
     let mut env = Env {
         arena,
@@ -57,6 +65,7 @@ pub fn jit_to_ast<'a, A: ReplApp<'a>>(
         target_info,
         interns,
         layout_cache: LayoutCache::new(layout_interner, target_info),
+        closure_captures,
     };
 
     match layout {
@@ -534,6 +543,15 @@ fn jit_to_ast_help<'a, A: ReplApp<'a>>(
         LayoutRepr::RecursivePointer(_) => {
             unreachable!("RecursivePointers can only be inside structures")
         }
+        LayoutRepr::LambdaSet(lambda_set) if env.closure_captures.is_some() => {
+            let size = env.layout_cache.interner.stack_size(layout);
+
+            app.call_function_dynamic_size(
+                main_fn_name,
+                size as usize,
+                |mem: &'a A::Memory, addr| function_to_ast(env, mem, addr, lambda_set, raw_var),
+            )
+        }
         LayoutRepr::LambdaSet(_) => OPAQUE_FUNCTION,
         LayoutRepr::Boxed(_) => {
             let size = env.layout_cache.interner.stack_size(layout);
@@ -554,6 +572,210 @@ fn jit_to_ast_help<'a, A: ReplApp<'a>>(
     apply_newtypes(env, newtype_containers.into_bump_slice(), expr)
 }
 
+/// Describes a function value by the function it calls and the values that function captured,
+/// like `<function Parser.many, captures: { parser: ... }>`. Which function of the lambda set
+/// it is comes from the closure data, like the tag of a tag union. When that can't be read,
+/// it lists the functions it could be, like `<one of: function Parser.many, function Parser.map>`.
+fn function_to_ast<'a, M: ReplAppMemory>(
+    env: &mut Env<'a, '_>,
+    mem: &'a M,
+    addr: usize,
+    lambda_set: LambdaSet<'a>,
+    var: Variable,
+) -> Expr<'a> {
+    let index = match lambda_set.len() {
+        0 => return OPAQUE_FUNCTION,
+        1 => 0,
+        _ => match env
+            .layout_cache
+            .get_repr(lambda_set.runtime_representation())
+        {
+            LayoutRepr::Builtin(Builtin::Bool) => mem.deref_bool(addr) as usize,
+            LayoutRepr::Builtin(Builtin::Int(IntWidth::U8)) => mem.deref_u8(addr) as usize,
+            LayoutRepr::Union(union_layout @ UnionLayout::NonRecursive(_)) => {
+                tag_id_from_data(env, mem, union_layout, addr) as usize
+            }
+            // recursive closure data lives on the heap
+            _ => return candidate_functions_to_ast(env, lambda_set),
+        },
+    };
+
+    let lambda_name = match lambda_set.iter_set().nth(index) {
+        Some(lambda_name) => lambda_name,
+        None => return OPAQUE_FUNCTION,
+    };
+
+    let capture_layouts: &[InLayout<'a>] = match lambda_set
+        .layout_for_member_with_lambda_name(&env.layout_cache.interner, lambda_name)
+    {
+        ClosureRepresentation::Union {
+            alphabetic_order_fields,
+            ..
+        } => alphabetic_order_fields,
+        ClosureRepresentation::AlphabeticOrderStruct(fields) => fields,
+        ClosureRepresentation::UnwrappedCapture(layout) => env.arena.alloc([layout]),
+        ClosureRepresentation::EnumDispatch(_) => &[],
+    };
+
+    let name = lambda_name.name();
+    let description = describe_function(env, name);
+
+    let capture_vars = match capture_layouts {
+        [] => None,
+        _ => captured_vars(env, var, name, capture_layouts),
+    };
+
+    let description = match capture_vars {
+        None => description,
+        Some(capture_vars) => {
+            let captures = captures_to_ast(env, mem, addr, name, capture_layouts, &capture_vars);
+
+            format!(
+                "{description}, captures: {}",
+                roc_value_render::render(env.arena, &captures)
+            )
+        }
+    };
+
+    Expr::Var {
+        module_name: "",
+        ident: env.arena.alloc_str(&format!("<{description}>")),
+    }
+}
+
+fn candidate_functions_to_ast<'a>(env: &Env<'a, '_>, lambda_set: LambdaSet<'a>) -> Expr<'a> {
+    let mut candidates: std::vec::Vec<String> = std::vec::Vec::new();
+
+    // a function can be in a lambda set more than once, with captures of different layouts
+    for lambda_name in lambda_set.iter_set() {
+        let description = describe_function(env, lambda_name.name());
+
+        if !candidates.contains(&description) {
+            candidates.push(description);
+        }
+    }
+
+    Expr::Var {
+        module_name: "",
+        ident: env
+            .arena
+            .alloc_str(&format!("<one of: {}>", candidates.join(", "))),
+    }
+}
+
+fn describe_function(env: &Env<'_, '_>, name: Symbol) -> String {
+    if name
+        .as_str(env.interns)
+        .starts_with(|c: char| c.is_ascii_digit())
+    {
+        format!("anonymous function in {}", name.module_string(env.interns))
+    } else {
+        format!(
+            "function {}.{}",
+            name.module_string(env.interns),
+            name.as_str(env.interns)
+        )
+    }
+}
+
+/// The variables of the values `function` captures, in the lambda set of the function type `var`.
+/// A function can be in a lambda set more than once, with captures of different layouts.
+fn captured_vars<'a>(
+    env: &mut Env<'a, '_>,
+    var: Variable,
+    function: Symbol,
+    capture_layouts: &[InLayout<'a>],
+) -> Option<std::vec::Vec<Variable>> {
+    let closure_var = match env.subs.get_content_without_compacting(var) {
+        Content::Structure(FlatType::Func(_, closure_var, _)) => *closure_var,
+        _ => return None,
+    };
+
+    let solved = match env.subs.get_content_without_compacting(closure_var) {
+        Content::LambdaSet(lambda_set) => lambda_set.solved,
+        _ => return None,
+    };
+
+    let candidates: std::vec::Vec<std::vec::Vec<Variable>> = solved
+        .iter_from_subs(env.subs)
+        .filter(|(symbol, vars)| **symbol == function && vars.len() == capture_layouts.len())
+        .map(|(_, vars)| vars.to_vec())
+        .collect();
+
+    candidates.into_iter().find(|vars| {
+        vars.iter().zip(capture_layouts).all(|(var, layout)| {
+            match env.layout_cache.from_var(env.arena, *var, env.subs) {
+                Ok(var_layout) => env.layout_cache.interner.equiv(var_layout, *layout),
+                Err(_) => false,
+            }
+        })
+    })
+}
+
+/// The values a closure captured, as a record when we know the names of the captured symbols,
+/// and as a tuple otherwise.
+fn captures_to_ast<'a, M: ReplAppMemory>(
+    env: &mut Env<'a, '_>,
+    mem: &'a M,
+    addr: usize,
+    function: Symbol,
+    capture_layouts: &[InLayout<'a>],
+    capture_vars: &[Variable],
+) -> Expr<'a> {
+    let arena = env.arena;
+
+    // The captures are sorted by symbol, but the closure data sorts them by alignment, keeping
+    // the order of captures with the same alignment.
+    let mut in_memory: std::vec::Vec<usize> = (0..capture_layouts.len()).collect();
+    in_memory.sort_by_key(|index| {
+        std::cmp::Reverse(
+            env.layout_cache
+                .get_repr(capture_layouts[*index])
+                .alignment_bytes(&env.layout_cache.interner),
+        )
+    });
+
+    let mut values = std::vec::Vec::from_iter(std::iter::repeat(FAKE_EXPR).take(in_memory.len()));
+    let mut field_addr = addr;
+
+    for index in in_memory {
+        let layout = capture_layouts[index];
+        let expr = addr_to_ast(
+            env,
+            mem,
+            field_addr,
+            env.layout_cache.get_repr(layout),
+            WhenRecursive::Unreachable,
+            capture_vars[index],
+        );
+
+        values[index] = &*arena.alloc(Loc::at_zero(expr));
+        field_addr += env.layout_cache.interner.stack_size(layout) as usize;
+    }
+
+    let names = env
+        .closure_captures
+        .and_then(|captures| captures.get(&function))
+        .filter(|names| names.len() == values.len());
+
+    match names {
+        Some(names) => {
+            let fields = names.iter().zip(values).map(|(name, value)| {
+                let label = Loc::at_zero(&*arena.alloc_str(name.as_str(env.interns)));
+
+                Loc::at_zero(AssignedField::RequiredValue(label, &[], value))
+            });
+
+            Expr::Record(Collection::with_items(
+                Vec::from_iter_in(fields, arena).into_bump_slice(),
+            ))
+        }
+        None => Expr::Tuple(Collection::with_items(
+            Vec::from_iter_in(values, arena).into_bump_slice(),
+        )),
+    }
+}
+
 fn tag_name_to_expr<'a>(env: &Env<'a, '_>, tag_name: &TagName) -> Expr<'a> {
     Expr::Tag(env.arena.alloc_str(&tag_name.as_ident_str()))
 }
@@ -586,6 +808,9 @@ fn addr_to_ast<'a, M: ReplAppMemory>(
     let raw_content = env.subs.get_content_without_compacting(raw_var);
 
     let expr = match (raw_content, layout) {
+        (_, LayoutRepr::LambdaSet(lambda_set)) if env.closure_captures.is_some() => {
+            function_to_ast(env, mem, addr, lambda_set, raw_var)
+        }
         (Content::Structure(FlatType::Func(_, _, _)), _) | (_, LayoutRepr::LambdaSet(_)) => {
             OPAQUE_FUNCTION
         }
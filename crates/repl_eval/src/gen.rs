@@ -3,14 +3,14 @@ use roc_load::{ExecutionMode, LoadConfig, LoadMonomorphizedError, Threading};
 use roc_packaging::cache::{self, RocCacheDir};
 use roc_problem::Severity;
 use roc_reporting::report::Palette;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use roc_fmt::annotation::Formattable;
-use roc_fmt::annotation::{Newlines, Parens};
 use roc_load::{LoadingProblem, MonomorphizedModule};
+use roc_module::symbol::{Interns, ModuleId};
+use roc_mono::ir::CrashTag;
 use roc_parse::ast::Expr;
-use roc_region::all::LineInfo;
-use roc_reporting::report::{can_problem, type_problem, RocDocAllocator};
+use roc_region::all::{LineInfo, Position, Region};
+use roc_reporting::report::{can_problem, type_problem, Report, RocDocAllocator};
 use roc_target::TargetInfo;
 
 #[derive(Debug)]
@@ -20,16 +20,7 @@ pub struct ReplOutput {
 }
 
 pub fn format_answer<'a>(arena: &'a Bump, answer: Expr<'_>) -> &'a str {
-    match answer {
-        Expr::Closure(_, _) | Expr::MalformedClosure => "<function>",
-        _ => {
-            let mut expr = roc_fmt::Buf::new_in(arena);
-
-            answer.format_with_options(&mut expr, Parens::NotNeeded, Newlines::Yes, 0);
-
-            expr.into_bump_str()
-        }
-    }
+    roc_value_render::render(arena, &answer)
 }
 
 #[derive(Default, Debug)]
@@ -44,16 +35,74 @@ impl Problems {
     }
 }
 
+/// Modules that the entered code can use, besides the builtins (e.g. from `roc eval --dep`).
+#[derive(Debug, Default, Clone)]
+pub struct ReplDeps {
+    /// The directory the modules are in.
+    dir: Option<PathBuf>,
+    /// The module names, e.g. `Parser` for `Parser.roc`.
+    modules: Vec<String>,
+}
+
+impl ReplDeps {
+    /// Every module is imported by its file name, so they all have to be in the same directory,
+    /// just like the modules an app imports.
+    pub fn from_files(paths: &[PathBuf]) -> Result<Self, String> {
+        let mut deps = ReplDeps::default();
+
+        for path in paths {
+            if !path.is_file() {
+                return Err(format!("I could not find the module {}", path.display()));
+            }
+
+            let module_name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) if name.starts_with(|c: char| c.is_ascii_uppercase()) => name,
+                _ => {
+                    return Err(format!(
+                        "{} is not named like a module, so I can't import it. Module names start with an uppercase letter, like `Parser.roc`.",
+                        path.display()
+                    ))
+                }
+            };
+
+            let dir = match path.parent() {
+                Some(dir) if dir.as_os_str().is_empty() => Path::new("."),
+                Some(dir) => dir,
+                None => Path::new("."),
+            };
+
+            match &deps.dir {
+                Some(existing) if existing != dir => {
+                    return Err(format!(
+                        "{} is not in the same directory as the other modules ({}), so I can't import them together.",
+                        path.display(),
+                        existing.display()
+                    ));
+                }
+                _ => deps.dir = Some(dir.to_path_buf()),
+            }
+
+            deps.modules.push(module_name.to_string());
+        }
+
+        Ok(deps)
+    }
+}
+
 pub fn compile_to_mono<'a, 'i, I: Iterator<Item = &'i str>>(
     arena: &'a Bump,
     defs: I,
     expr: &str,
+    deps: &ReplDeps,
     target_info: TargetInfo,
     palette: Palette,
 ) -> (Option<MonomorphizedModule<'a>>, Problems) {
     let filename = PathBuf::from("");
-    let src_dir = PathBuf::from("fake/test/path");
-    let (bytes_before_expr, module_src) = promote_expr_to_module(arena, defs, expr);
+    let src_dir = match &deps.dir {
+        Some(dir) => dir.clone(),
+        None => PathBuf::from("fake/test/path"),
+    };
+    let (bytes_before_expr, module_src) = promote_expr_to_module(arena, defs, expr, &deps.modules);
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
         filename,
@@ -66,6 +115,9 @@ pub fn compile_to_mono<'a, 'i, I: Iterator<Item = &'i str>>(
             palette,
             threading: Threading::Single,
             exec_mode: ExecutionMode::Executable,
+            platform: None,
+            max_type_errors: None,
+            keep_module_types: false,
         },
     );
 
@@ -92,6 +144,7 @@ pub fn compile_to_mono<'a, 'i, I: Iterator<Item = &'i str>>(
     };
 
     let MonomorphizedModule {
+        module_id: repl_module_id,
         interns,
         sources,
         can_problems,
@@ -114,9 +167,16 @@ pub fn compile_to_mono<'a, 'i, I: Iterator<Item = &'i str>>(
             continue;
         }
 
-        let line_info = LineInfo::new(module_src);
+        let line_info = LineInfo::new(src);
         let src_lines: Vec<&str> = src.split('\n').collect();
 
+        // Problems in the modules the entered code imports are all reported.
+        let bytes_before_expr = if *home == *repl_module_id {
+            bytes_before_expr
+        } else {
+            0
+        };
+
         // Report parsing and canonicalization problems
         let alloc = RocDocAllocator::new(&src_lines, *home, interns);
 
@@ -165,16 +225,158 @@ pub fn compile_to_mono<'a, 'i, I: Iterator<Item = &'i str>>(
     (Some(loaded), problems)
 }
 
+/// Renders a crash in the entered expression like `roc test` renders a crashing expect: with the
+/// expression highlighted, followed by the crash message.
+pub fn crash_report(
+    module_id: ModuleId,
+    interns: &Interns,
+    module_src: &str,
+    message: &str,
+    crash_tag: CrashTag,
+    palette: &Palette,
+) -> String {
+    let intro = match crash_tag {
+        CrashTag::User => "This expression crashed:",
+        CrashTag::Roc => "Roc crashed while evaluating this expression:",
+    };
+
+    expr_report(
+        module_id,
+        interns,
+        module_src,
+        "CRASH",
+        intro,
+        Some("The crash reported this message:"),
+        message,
+        palette,
+    )
+}
+
+/// Like [crash_report], for an expression that was stopped before it finished, e.g. because it
+/// ran for too long. `message` says why.
+pub fn stopped_report(
+    module_id: ModuleId,
+    interns: &Interns,
+    module_src: &str,
+    title: &str,
+    message: &str,
+    palette: &Palette,
+) -> String {
+    expr_report(
+        module_id,
+        interns,
+        module_src,
+        title,
+        "I stopped evaluating this expression:",
+        None,
+        message,
+        palette,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn expr_report(
+    module_id: ModuleId,
+    interns: &Interns,
+    module_src: &str,
+    title: &str,
+    intro: &str,
+    message_intro: Option<&str>,
+    message: &str,
+    palette: &Palette,
+) -> String {
+    let line_info = LineInfo::new(module_src);
+    let src_lines: Vec<&str> = module_src.split('\n').collect();
+    let alloc = RocDocAllocator::new(&src_lines, module_id, interns);
+
+    let mut parts = vec![
+        alloc.string(intro.to_string()),
+        alloc.region(line_info.convert_region(expr_region(module_src))),
+    ];
+
+    if let Some(message_intro) = message_intro {
+        parts.push(alloc.string(message_intro.to_string()));
+    }
+
+    parts.push(alloc.string(message.to_string()));
+
+    let doc = alloc.stack(parts);
+
+    let report = Report {
+        title: title.to_string(),
+        filename: PathBuf::from(""),
+        doc,
+        severity: Severity::RuntimeError,
+    };
+
+    let mut buf = String::new();
+
+    report.render_color_terminal(&mut buf, &alloc, palette);
+
+    buf
+}
+
+/// The region of the entered expression in the module generated by [promote_expr_to_module].
+/// The expression is the only thing after the main def that is indented.
+fn expr_region(module_src: &str) -> Region {
+    let main_def = format!("\n{REPL_MODULE_MAIN_DEF}");
+    let start = match module_src.rfind(&main_def) {
+        Some(index) => index + main_def.len(),
+        None => 0,
+    };
+    let expr = &module_src[start..];
+    let indent = expr.len() - expr.trim_start().len();
+    let end = module_src.trim_end().len();
+
+    Region::new(
+        Position::new((start + indent) as u32),
+        Position::new(end as u32),
+    )
+}
+
+const REPL_MODULE_MAIN_DEF: &str = "replOutput =\n";
+
+/// Builtin modules that are not imported into every module by default.
+/// The REPL imports them as soon as the entered code refers to them.
+const AUTO_IMPORTED_MODULES: &[&str] = &["TotallyNotJson"];
+
 fn promote_expr_to_module<'a, 'i, I: Iterator<Item = &'i str>>(
     arena: &'a Bump,
     defs: I,
     expr: &str,
+    dep_modules: &[String],
 ) -> (usize, &'a str) {
-    const REPL_MODULE_HEADER: &str = "app \"app\" provides [replOutput] to \"./platform\"\n\n";
-    const REPL_MODULE_MAIN_DEF: &str = "replOutput =\n";
+    const REPL_MODULE_NAME: &str = "app \"app\"";
+    const REPL_MODULE_PROVIDES: &str = " provides [replOutput] to \"./platform\"\n\n";
     const INDENT: &str = "    ";
 
-    let mut buffer = bumpalo::collections::string::String::from_str_in(REPL_MODULE_HEADER, arena);
+    let defs: Vec<&str> = defs.collect();
+
+    let mut buffer = bumpalo::collections::string::String::from_str_in(REPL_MODULE_NAME, arena);
+
+    let mut imports = AUTO_IMPORTED_MODULES
+        .iter()
+        .copied()
+        .filter(|module_name| {
+            let qualifier = format!("{module_name}.");
+
+            expr.contains(&qualifier) || defs.iter().any(|def| def.contains(&qualifier))
+        })
+        .chain(dep_modules.iter().map(String::as_str));
+
+    if let Some(first) = imports.next() {
+        buffer.push_str(" imports [");
+        buffer.push_str(first);
+
+        for module_name in imports {
+            buffer.push_str(", ");
+            buffer.push_str(module_name);
+        }
+
+        buffer.push(']');
+    }
+
+    buffer.push_str(REPL_MODULE_PROVIDES);
 
     for line in defs {
         // don't indent the defs
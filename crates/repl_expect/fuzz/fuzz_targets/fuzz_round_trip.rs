@@ -0,0 +1,9 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use roc_repl_expect::round_trip::{check_round_trip, values_from_bytes};
+
+fuzz_target!(|data: &[u8]| {
+    if let Err(message) = check_round_trip(&values_from_bytes(data)) {
+        panic!("{message}");
+    }
+});
@@ -1,16 +1,54 @@
 use roc_parse::ast::Expr;
 use roc_repl_eval::{ReplApp, ReplAppMemory};
-use roc_std::RocStr;
 use roc_target::TargetInfo;
 
+/// A buffer of frames, where values point at each other by their offset from its start.
 pub(crate) struct ExpectMemory {
     pub(crate) start: *const u8,
+    /// The target the frames were written by: on wasm32, pointers and `usize`s take 4 bytes.
+    pub(crate) target_info: TargetInfo,
+    /// Where the frames end. A malformed frame can point anywhere, but only this much of the
+    /// buffer is read.
+    end: usize,
+}
+
+impl ExpectMemory {
+    /// The frames in the buffer at `start`, which ends where its header says the next frame goes.
+    pub(crate) fn new(start: *const u8, target_info: TargetInfo) -> Self {
+        let ptr_size = target_info.ptr_size();
+        let header = Self {
+            start,
+            target_info,
+            end: 2 * ptr_size,
+        };
+
+        Self {
+            end: header.deref_usize(ptr_size),
+            ..header
+        }
+    }
+
+    fn ptr_size(&self) -> usize {
+        self.target_info.ptr_size()
+    }
+
+    /// The `length` bytes at `addr`, which must have been written.
+    fn at(&self, addr: usize, length: usize) -> *const u8 {
+        match addr.checked_add(length) {
+            Some(end) if end <= self.end => unsafe { self.start.add(addr) },
+            _ => panic!(
+                "a value in this frame is at {addr}..{}, but only {} bytes were written",
+                addr.saturating_add(length),
+                self.end
+            ),
+        }
+    }
 }
 
 macro_rules! deref_number {
     ($name: ident, $t: ty) => {
         fn $name(&self, addr: usize) -> $t {
-            let ptr = unsafe { self.start.add(addr) } as *const _;
+            let ptr = self.at(addr, std::mem::size_of::<$t>()) as *const _;
             unsafe { std::ptr::read_unaligned(ptr) }
         }
     };
@@ -18,7 +56,7 @@ macro_rules! deref_number {
 
 impl ReplAppMemory for ExpectMemory {
     fn deref_bool(&self, addr: usize) -> bool {
-        let ptr = unsafe { self.start.add(addr) } as *const u8;
+        let ptr = self.at(addr, 1);
         let value = unsafe { std::ptr::read_unaligned(ptr) };
 
         // bool values should only ever be 0 or 1
@@ -32,42 +70,53 @@ impl ReplAppMemory for ExpectMemory {
     deref_number!(deref_u32, u32);
     deref_number!(deref_u64, u64);
     deref_number!(deref_u128, u128);
-    deref_number!(deref_usize, usize);
+
+    fn deref_usize(&self, addr: usize) -> usize {
+        match self.ptr_size() {
+            4 => self.deref_u32(addr) as usize,
+            _ => self.deref_u64(addr) as usize,
+        }
+    }
 
     deref_number!(deref_i8, i8);
     deref_number!(deref_i16, i16);
     deref_number!(deref_i32, i32);
     deref_number!(deref_i64, i64);
     deref_number!(deref_i128, i128);
-    deref_number!(deref_isize, isize);
+
+    fn deref_isize(&self, addr: usize) -> isize {
+        match self.ptr_size() {
+            4 => self.deref_i32(addr) as isize,
+            _ => self.deref_i64(addr) as isize,
+        }
+    }
 
     deref_number!(deref_f32, f32);
     deref_number!(deref_f64, f64);
 
     fn deref_str(&self, addr: usize) -> &str {
-        const WIDTH: usize = 3 * std::mem::size_of::<usize>();
+        let width = 3 * self.ptr_size();
 
-        let last_byte_addr = addr + WIDTH - 1;
+        let last_byte_addr = addr + width - 1;
         let last_byte = self.deref_i8(last_byte_addr);
 
         let is_small = last_byte < 0;
 
-        if is_small {
-            let ptr = unsafe { self.start.add(addr) };
-            let roc_str: &RocStr = unsafe { &*ptr.cast() };
-
-            roc_str.as_str()
+        let (offset, length) = if is_small {
+            // the bytes are stored inline, and the last byte has the length in its low bits
+            (addr, (last_byte as u8 & 0x7F) as usize)
         } else {
-            let offset = self.deref_usize(addr);
-            let length = self.deref_usize(addr + std::mem::size_of::<usize>());
-            let _capacity = self.deref_usize(addr + 2 * std::mem::size_of::<usize>());
+            (
+                self.deref_usize(addr),
+                self.deref_usize(addr + self.ptr_size()),
+            )
+        };
 
-            unsafe {
-                let ptr = self.start.add(offset);
-                let slice = std::slice::from_raw_parts(ptr, length);
+        unsafe {
+            let ptr = self.at(offset, length);
+            let slice = std::slice::from_raw_parts(ptr, length);
 
-                std::str::from_utf8_unchecked(slice)
-            }
+            std::str::from_utf8_unchecked(slice)
         }
     }
 
@@ -98,25 +147,39 @@ impl<'a> ReplApp<'a> for ExpectReplApp<'a> {
         Self::Memory: 'a,
     {
         let result: Return = unsafe {
-            let ptr = self.memory.start.add(self.offset);
+            let ptr = self.memory.at(self.offset, std::mem::size_of::<Return>());
             let ptr: *const Return = std::mem::transmute(ptr);
-            ptr.read()
+            ptr.read_unaligned()
         };
 
         transform(self.memory, result)
     }
 
-    fn call_function_returns_roc_list<F>(&mut self, main_fn_name: &str, transform: F) -> Expr<'a>
+    fn call_function_returns_roc_list<F>(
+        &mut self,
+        main_fn_name: &str,
+        mut transform: F,
+    ) -> Expr<'a>
     where
         F: FnMut(&'a Self::Memory, (usize, usize, usize)) -> Expr<'a>,
         Self::Memory: 'a,
     {
-        self.call_function(main_fn_name, transform)
+        let width = self.memory.ptr_size();
+
+        self.call_function_dynamic_size(main_fn_name, 3 * width, |memory, addr| {
+            let list = (
+                memory.deref_usize(addr),
+                memory.deref_usize(addr + width),
+                memory.deref_usize(addr + 2 * width),
+            );
+
+            transform(memory, list)
+        })
     }
 
     fn call_function_returns_roc_str<T, F>(
         &mut self,
-        _target_info: TargetInfo,
+        target_info: TargetInfo,
         main_fn_name: &str,
         transform: F,
     ) -> T
@@ -124,7 +187,9 @@ impl<'a> ReplApp<'a> for ExpectReplApp<'a> {
         F: Fn(&'a Self::Memory, usize) -> T,
         Self::Memory: 'a,
     {
-        self.call_function_dynamic_size(main_fn_name, 24, transform)
+        let width = 3 * target_info.ptr_size();
+
+        self.call_function_dynamic_size(main_fn_name, width, transform)
     }
 
     /// Run user code that returns a struct or union, whose size is provided as an argument
@@ -0,0 +1,267 @@
+//! Why the forked child that runs an effectful expect ended, so `roc test` can tell a Roc
+//! `crash` apart from a signal or from an effect exiting the process.
+
+use std::fmt;
+use std::os::unix::io::RawFd;
+use std::time::Duration;
+
+/// How the child running an effectful expect ended.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ChildExit {
+    /// The expect ran to completion. Whether it passed depends on what the child reported.
+    Finished,
+    /// The Roc code crashed, either with `crash` or with a builtin panic like an overflow.
+    Crashed(String),
+    /// The child could not enter the sandbox, so the expect did not run.
+    SandboxFailed(String),
+    /// The child was killed by a signal, e.g. a segfault in the host or in an effect.
+    Signaled(i32),
+    /// The child exited with a non-zero code without a crash, which means an effect ended the
+    /// process.
+    Exited(i32),
+    /// The child ran for longer than `--fx-timeout`, so we killed its process group.
+    TimedOut(Duration),
+}
+
+impl ChildExit {
+    /// Classifies a status from `waitpid`. A note the child sent before exiting is more
+    /// specific than its exit code, so it wins.
+    pub(crate) fn from_status(status: libc::c_int, note: Option<Note>) -> Self {
+        match note {
+            Some(Note::Crashed(message)) => ChildExit::Crashed(message),
+            Some(Note::SandboxFailed(message)) => ChildExit::SandboxFailed(message),
+            None if libc::WIFSIGNALED(status) => ChildExit::Signaled(libc::WTERMSIG(status)),
+            None if libc::WIFEXITED(status) && libc::WEXITSTATUS(status) != 0 => {
+                ChildExit::Exited(libc::WEXITSTATUS(status))
+            }
+            None => ChildExit::Finished,
+        }
+    }
+}
+
+impl fmt::Display for ChildExit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChildExit::Finished => write!(f, "finished"),
+            ChildExit::Crashed(message) => write!(f, "crashed with the message \"{message}\""),
+            ChildExit::SandboxFailed(message) => {
+                write!(
+                    f,
+                    "could not be sandboxed: {}",
+                    message.trim_end_matches('.')
+                )
+            }
+            ChildExit::Signaled(signal) => match signal_name(*signal) {
+                Some(name) => write!(f, "was killed by signal {signal} ({name})"),
+                None => write!(f, "was killed by signal {signal}"),
+            },
+            ChildExit::Exited(code) => {
+                write!(f, "ended the process with exit code {code} from an effect")
+            }
+            ChildExit::TimedOut(timeout) => write!(
+                f,
+                "did not finish within {} seconds, so I stopped it and everything it started",
+                timeout.as_secs_f64()
+            ),
+        }
+    }
+}
+
+fn signal_name(signal: i32) -> Option<&'static str> {
+    let name = match signal {
+        libc::SIGABRT => "SIGABRT",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGFPE => "SIGFPE",
+        libc::SIGHUP => "SIGHUP",
+        libc::SIGILL => "SIGILL",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGPIPE => "SIGPIPE",
+        libc::SIGQUIT => "SIGQUIT",
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGTERM => "SIGTERM",
+        libc::SIGTRAP => "SIGTRAP",
+        _ => return None,
+    };
+
+    Some(name)
+}
+
+/// What the child tells us about why it is about to exit.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Note {
+    Crashed(String),
+    SandboxFailed(String),
+}
+
+impl Note {
+    const CRASHED: u8 = 1;
+    const SANDBOX_FAILED: u8 = 2;
+
+    /// Long crash messages are cut, so that writing one never blocks on a full pipe.
+    const MAX_MESSAGE_LENGTH: usize = 4096;
+
+    fn encode(&self) -> Vec<u8> {
+        let (kind, message) = match self {
+            Note::Crashed(message) => (Self::CRASHED, message),
+            Note::SandboxFailed(message) => (Self::SANDBOX_FAILED, message),
+        };
+
+        let mut end = message.len().min(Self::MAX_MESSAGE_LENGTH);
+        while !message.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        let mut bytes = vec![kind];
+        bytes.extend_from_slice(&message.as_bytes()[..end]);
+
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let (kind, message) = bytes.split_first()?;
+        let message = String::from_utf8_lossy(message).into_owned();
+
+        match *kind {
+            Self::CRASHED => Some(Note::Crashed(message)),
+            Self::SANDBOX_FAILED => Some(Note::SandboxFailed(message)),
+            _ => None,
+        }
+    }
+}
+
+/// A pipe the child uses to send a [Note] to its parent before it exits.
+///
+/// Both ends are closed on `exec`, so processes that effects start never hold the pipe open.
+pub(crate) struct NotePipe {
+    read: RawFd,
+    write: RawFd,
+}
+
+impl NotePipe {
+    pub(crate) fn create() -> std::io::Result<Self> {
+        let mut fds = [0; 2];
+
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        for fd in fds {
+            unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) };
+        }
+
+        // a child that was killed may have forked processes that still hold the write end
+        unsafe { libc::fcntl(fds[0], libc::F_SETFL, libc::O_NONBLOCK) };
+
+        Ok(Self {
+            read: fds[0],
+            write: fds[1],
+        })
+    }
+
+    /// Called in the child, right before it exits.
+    pub(crate) fn send(&self, note: &Note) {
+        let bytes = note.encode();
+
+        unsafe { libc::write(self.write, bytes.as_ptr().cast(), bytes.len()) };
+    }
+
+    /// Called in the parent, once the child has exited.
+    pub(crate) fn receive(&self) -> Option<Note> {
+        let mut bytes = Vec::new();
+        let mut buffer = [0u8; 1024];
+
+        loop {
+            let n = unsafe { libc::read(self.read, buffer.as_mut_ptr().cast(), buffer.len()) };
+
+            if n <= 0 {
+                break;
+            }
+
+            bytes.extend_from_slice(&buffer[..n as usize]);
+        }
+
+        Note::decode(&bytes)
+    }
+}
+
+impl Drop for NotePipe {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.read);
+            libc::close(self.write);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::Command;
+
+    fn status_of(script: &str) -> libc::c_int {
+        let status = Command::new("sh").args(["-c", script]).status().unwrap();
+
+        status.into_raw()
+    }
+
+    #[test]
+    fn classifies_exit_statuses() {
+        assert_eq!(
+            ChildExit::from_status(status_of("exit 0"), None),
+            ChildExit::Finished
+        );
+        assert_eq!(
+            ChildExit::from_status(status_of("exit 7"), None),
+            ChildExit::Exited(7)
+        );
+        assert_eq!(
+            ChildExit::from_status(status_of("kill -SEGV $$"), None),
+            ChildExit::Signaled(libc::SIGSEGV)
+        );
+    }
+
+    #[test]
+    fn note_wins_over_exit_code() {
+        let note = Note::Crashed("Integer addition overflowed!".to_string());
+
+        assert_eq!(
+            ChildExit::from_status(status_of("exit 1"), Some(note)),
+            ChildExit::Crashed("Integer addition overflowed!".to_string())
+        );
+    }
+
+    #[test]
+    fn notes_go_through_the_pipe() {
+        let pipe = NotePipe::create().unwrap();
+
+        assert_eq!(pipe.receive(), None);
+
+        pipe.send(&Note::SandboxFailed("no landlock".to_string()));
+
+        assert_eq!(
+            pipe.receive(),
+            Some(Note::SandboxFailed("no landlock".to_string()))
+        );
+    }
+
+    #[test]
+    fn long_messages_are_cut_at_a_char_boundary() {
+        let message = "é".repeat(Note::MAX_MESSAGE_LENGTH);
+        let bytes = Note::Crashed(message).encode();
+
+        let Some(Note::Crashed(decoded)) = Note::decode(&bytes) else {
+            panic!("expected a crash note");
+        };
+
+        assert_eq!(decoded.len(), Note::MAX_MESSAGE_LENGTH);
+    }
+
+    #[test]
+    fn signals_are_named() {
+        assert_eq!(
+            ChildExit::Signaled(libc::SIGSEGV).to_string(),
+            format!("was killed by signal {} (SIGSEGV)", libc::SIGSEGV)
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Finding the exposed definitions that no expect uses, for `roc test --coverage`.
+//!
+//! The dylib counts the calls of every specialization of those definitions itself, see
+//! [add_coverage_counters]. Here, its counters are pointed at memory that's shared with the
+//! processes that run effectful expects, so that their calls count too. A definition that
+//! nothing specialized has no counter, because no expect can have used it.
+//!
+//! The counters don't live in the shared buffer that the expects write their failures to:
+//! everything that reads frames from that buffer expects them right after its header.
+//!
+//! [add_coverage_counters]: roc_gen_llvm::llvm::externs::add_coverage_counters
+use roc_collections::MutSet;
+use roc_error_macros::internal_error;
+use roc_gen_llvm::llvm::externs::COVERAGE_COUNTERS_GLOBAL;
+use roc_load::MonomorphizedModule;
+use roc_module::symbol::Symbol;
+use roc_reporting::coverage::{CoverageSummary, Uncovered};
+
+#[derive(Debug)]
+pub struct Coverage {
+    /// The definitions that have counters, in the order of their counters.
+    counted: Vec<Symbol>,
+    /// How to report each of `counted`.
+    counted_reports: Vec<Uncovered>,
+    /// The exposed definitions that nothing specialized.
+    never_specialized: Vec<Uncovered>,
+    counters: *mut u64,
+}
+
+impl Coverage {
+    pub(crate) fn new(loaded: &MonomorphizedModule<'_>) -> Self {
+        let specialized: MutSet<Symbol> = loaded
+            .procedures
+            .keys()
+            .map(|(symbol, _)| *symbol)
+            .collect();
+
+        let mut counted = Vec::new();
+        let mut counted_reports = Vec::new();
+        let mut never_specialized = Vec::new();
+
+        for (module_id, exposed) in loaded.root_package_exposes.iter() {
+            let path = match loaded.sources.get(module_id) {
+                Some((path, _)) => path,
+                None => continue,
+            };
+            let module_name = loaded.interns.module_name(*module_id).to_string();
+
+            for symbol in exposed.iter() {
+                let report = Uncovered {
+                    module_name: module_name.clone(),
+                    name: symbol.as_str(&loaded.interns).to_string(),
+                    path: path.clone(),
+                };
+
+                if specialized.contains(symbol) {
+                    counted.push(*symbol);
+                    counted_reports.push(report);
+                } else {
+                    never_specialized.push(report);
+                }
+            }
+        }
+
+        // shared, so a forked process counts in the same place
+        let counters = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                Self::mapping_len(counted.len()),
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+
+        if counters == libc::MAP_FAILED {
+            internal_error!("failed to mmap the coverage counters");
+        }
+
+        Self {
+            counted,
+            counted_reports,
+            never_specialized,
+            counters: counters.cast(),
+        }
+    }
+
+    fn mapping_len(counters: usize) -> usize {
+        // mmap can't map nothing
+        counters.max(1) * std::mem::size_of::<u64>()
+    }
+
+    /// The definitions to give counters, in order.
+    pub(crate) fn counted(&self) -> &[Symbol] {
+        &self.counted
+    }
+
+    /// Makes `lib` count in our counters, starting from zero.
+    pub(crate) fn attach(&self, lib: &libloading::Library) {
+        unsafe {
+            std::ptr::write_bytes(self.counters, 0, self.counted.len());
+
+            match lib.get::<*mut *mut u64>(COVERAGE_COUNTERS_GLOBAL.as_bytes()) {
+                Ok(global) => **global = self.counters,
+                Err(_) => internal_error!("the expects were built without coverage counters"),
+            }
+        }
+    }
+
+    pub(crate) fn summary(&self) -> CoverageSummary {
+        let counts = unsafe { std::slice::from_raw_parts(self.counters, self.counted.len()) };
+
+        let mut summary = CoverageSummary {
+            covered: 0,
+            uncovered: self.never_specialized.clone(),
+        };
+
+        for (count, report) in counts.iter().zip(self.counted_reports.iter()) {
+            if *count > 0 {
+                summary.covered += 1;
+            } else {
+                summary.uncovered.push(report.clone());
+            }
+        }
+
+        summary
+    }
+}
+
+impl Drop for Coverage {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.counters.cast(), Self::mapping_len(self.counted.len())) };
+    }
+}
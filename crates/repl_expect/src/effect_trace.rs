@@ -0,0 +1,135 @@
+//! Decodes the effect calls a program reports to `roc dev --trace-effects`.
+//!
+//! The records are written by the builtins (see `expect.zig`), right before the effect runs.
+use std::fmt;
+
+const ARG_STR: u8 = 0;
+const ARG_INT: u8 = 1;
+const ARG_UINT: u8 = 2;
+const ARG_FLOAT: u8 = 3;
+const ARG_OTHER: u8 = 4;
+const END: u8 = 0xFF;
+
+/// An effect, e.g. `putLine`, and its arguments, rendered as Roc values where possible.
+#[derive(Debug, PartialEq, Eq)]
+pub struct EffectCall {
+    pub name: String,
+    pub arguments: Vec<String>,
+}
+
+impl EffectCall {
+    /// Arguments that didn't fit in the buffer are left out, without corrupting the others.
+    pub fn decode(bytes: &[u8]) -> Self {
+        let mut reader = Reader { bytes };
+
+        let name_length = reader.u32() as usize;
+        let name = String::from_utf8_lossy(reader.take(name_length)).into_owned();
+        let mut arguments = Vec::new();
+
+        loop {
+            let argument = match reader.take(1).first().copied().unwrap_or(END) {
+                ARG_STR => {
+                    let (full_length, shown) = reader.text();
+
+                    if full_length > shown.len() {
+                        format!(
+                            "{:?}… ({} bytes)",
+                            String::from_utf8_lossy(shown),
+                            full_length
+                        )
+                    } else {
+                        format!("{:?}", String::from_utf8_lossy(shown))
+                    }
+                }
+                ARG_INT => (reader.u64() as i64).to_string(),
+                ARG_UINT => reader.u64().to_string(),
+                ARG_FLOAT => f64::from_bits(reader.u64()).to_string(),
+                ARG_OTHER => String::from_utf8_lossy(reader.text().1).into_owned(),
+                _ => break,
+            };
+
+            arguments.push(argument);
+        }
+
+        Self { name, arguments }
+    }
+}
+
+impl fmt::Display for EffectCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({})", self.name, self.arguments.join(", "))
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> &'a [u8] {
+        let (taken, rest) = self.bytes.split_at(count.min(self.bytes.len()));
+        self.bytes = rest;
+
+        taken
+    }
+
+    fn u32(&mut self) -> u32 {
+        let mut bytes = [0; 4];
+        let taken = self.take(4);
+        bytes[..taken.len()].copy_from_slice(taken);
+
+        u32::from_ne_bytes(bytes)
+    }
+
+    fn u64(&mut self) -> u64 {
+        let mut bytes = [0; 8];
+        let taken = self.take(8);
+        bytes[..taken.len()].copy_from_slice(taken);
+
+        u64::from_ne_bytes(bytes)
+    }
+
+    /// The full length of the text, and as much of it as was written.
+    fn text(&mut self) -> (usize, &'a [u8]) {
+        let full_length = self.u32() as usize;
+        let shown_length = self.u32() as usize;
+
+        (full_length, self.take(shown_length))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(kind: u8, full_length: u32, shown: &str) -> Vec<u8> {
+        let mut bytes = vec![kind];
+        bytes.extend(full_length.to_ne_bytes());
+        bytes.extend((shown.len() as u32).to_ne_bytes());
+        bytes.extend(shown.as_bytes());
+
+        bytes
+    }
+
+    #[test]
+    fn decode_arguments() {
+        let mut bytes = Vec::new();
+        bytes.extend(7u32.to_ne_bytes());
+        bytes.extend(b"putLine");
+        bytes.extend(text(ARG_STR, 5, "hello"));
+        bytes.extend(text(ARG_STR, 100, "cut"));
+        bytes.push(ARG_INT);
+        bytes.extend((-3i64).to_ne_bytes());
+        bytes.push(ARG_FLOAT);
+        bytes.extend(1.5f64.to_bits().to_ne_bytes());
+        bytes.extend(text(ARG_OTHER, 6, "<List>"));
+        bytes.push(END);
+
+        let call = EffectCall::decode(&bytes);
+
+        assert_eq!(
+            call.to_string(),
+            r#"putLine("hello", "cut"… (100 bytes), -3, 1.5, <List>)"#
+        );
+    }
+}
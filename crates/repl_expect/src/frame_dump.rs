@@ -0,0 +1,87 @@
+//! Saves the shared-memory frames of failed expects to disk, for `roc test --dump-frames`.
+//!
+//! When a failure is rendered wrongly, the frame is what it takes to reproduce the bug:
+//! the exact bytes the program wrote, and what we decoded them as.
+use std::cell::Cell;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use roc_module::symbol::{Interns, ModuleId, Symbol};
+use roc_parse::ast::Expr;
+use roc_region::all::Region;
+
+pub struct FrameDump {
+    dir: PathBuf,
+    frames_written: Cell<usize>,
+}
+
+/// What we decoded from a frame, before rendering it.
+pub(crate) struct DecodedFrame<'r, 'a> {
+    pub module_id: ModuleId,
+    pub path: &'r Path,
+    pub region: Region,
+    pub offset: usize,
+    pub symbols: &'r [Symbol],
+    pub expressions: &'r [Expr<'a>],
+}
+
+impl FrameDump {
+    /// Creates `dir` if it doesn't exist. Frames from an earlier run get overwritten.
+    pub fn create(dir: &Path) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            frames_written: Cell::new(0),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn frames_written(&self) -> usize {
+        self.frames_written.get()
+    }
+
+    /// Writes the shared memory up to the end of the frame to `frame-NNNN.bin`, and what we
+    /// decoded from it (along with the failure as it was printed) to `frame-NNNN.txt`.
+    pub(crate) fn write(
+        &self,
+        interns: &Interns,
+        bytes: &[u8],
+        frame: &DecodedFrame,
+        rendered: &[u8],
+    ) -> std::io::Result<()> {
+        let index = self.frames_written.get();
+        let name = format!("frame-{:04}", index);
+
+        std::fs::write(self.dir.join(&name).with_extension("bin"), bytes)?;
+
+        // writing to a String cannot fail
+        let mut description = String::new();
+        let _ = writeln!(
+            description,
+            "module: {}",
+            interns.module_name(frame.module_id)
+        );
+        let _ = writeln!(description, "path: {}", frame.path.display());
+        let _ = writeln!(description, "region: {:?}", frame.region);
+        let _ = writeln!(description, "frame offset: {}", frame.offset);
+        let _ = writeln!(description, "length: {} bytes", bytes.len());
+
+        let _ = writeln!(description, "\nlookups:");
+        for (symbol, expr) in frame.symbols.iter().zip(frame.expressions) {
+            let _ = writeln!(description, "{} = {:?}", symbol.as_str(interns), expr);
+        }
+
+        let _ = writeln!(description, "\nrendered:");
+        description.push_str(&String::from_utf8_lossy(rendered));
+
+        std::fs::write(self.dir.join(name).with_extension("txt"), description)?;
+
+        self.frames_written.set(index + 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,275 @@
+//! Machine-readable test results for `roc test --format json`, so that CI systems and
+//! editors can annotate failing expects inline.
+//!
+//! Every record is a JSON object on a line of its own, with a `kind` saying what it describes:
+//! - `failure`: an expect whose condition was false, along with the values it looked up, and
+//!   the `calls` that led to it when it's in a function that the expect called, innermost first
+//! - `crash`: an expect that crashed, or an effectful expect whose process ended abnormally
+//! - `dbg`: a value that a failed or crashed expect printed with `dbg`, before the record of
+//!   the failure or crash it led up to
+//! - `internal-error`: a failure or `dbg` that couldn't be rendered because of a bug in the
+//!   compiler, with the `expect` it belongs to when that's known
+//! - `coverage`: with `roc test --coverage`, how many exposed definitions the expects used, and
+//!   the ones they didn't use, right before the summary
+//! - `summary`: how many expects passed, failed, and were skipped after too many failed, and the `seed` they were shuffled with
+//!   if `roc test --shuffle` was used; always the last record
+//!
+//! Lines and columns are 1-based, and `source` is the code of the expect that failed.
+use std::path::Path;
+
+use bumpalo::Bump;
+use roc_module::ident::ModuleName;
+use roc_module::symbol::{Interns, ModuleId, Symbol};
+use roc_parse::ast::Expr;
+use roc_region::all::{LineInfo, Region};
+use roc_reporting::coverage::CoverageSummary;
+use roc_reporting::error::expect::CallSite;
+use roc_types::pretty_print::{name_and_print_var, DebugPrint};
+use roc_types::subs::{Subs, Variable};
+use roc_value_render::{Inspector, Json, RocSyntax};
+use serde_json::{json, Value};
+
+/// How `roc test` reports the expects that failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FailureFormat {
+    /// Reports meant for people, like the ones for compile errors
+    #[default]
+    Human,
+    /// One JSON record per line; see [crate::json]
+    Json,
+    /// Like [FailureFormat::Human], and every expect also becomes a test case of a JUnit XML
+    /// report; see [crate::junit]
+    Junit,
+    /// A TAP stream, with a test point for every expect; see [crate::tap]
+    Tap,
+}
+
+impl FailureFormat {
+    pub const NAMES: [&'static str; 4] = ["human", "json", "junit", "tap"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "human" => Some(FailureFormat::Human),
+            "json" => Some(FailureFormat::Json),
+            "junit" => Some(FailureFormat::Junit),
+            "tap" => Some(FailureFormat::Tap),
+            _ => None,
+        }
+    }
+
+    /// Whether failures are rendered as reports meant for people.
+    pub fn is_human(self) -> bool {
+        match self {
+            FailureFormat::Human | FailureFormat::Junit | FailureFormat::Tap => true,
+            FailureFormat::Json => false,
+        }
+    }
+
+    /// Whether anything besides the failures, like warnings and notes, may be printed
+    /// alongside them.
+    pub fn allows_notes(self) -> bool {
+        match self {
+            FailureFormat::Human | FailureFormat::Junit => true,
+            FailureFormat::Json | FailureFormat::Tap => false,
+        }
+    }
+
+    /// Whether every expect that ran is kept as a [crate::junit::TestCase].
+    pub(crate) fn keeps_cases(self) -> bool {
+        match self {
+            FailureFormat::Junit | FailureFormat::Tap => true,
+            FailureFormat::Human | FailureFormat::Json => false,
+        }
+    }
+}
+
+/// Where an expect is, for the records that point at one.
+pub(crate) struct Location<'r> {
+    pub module_id: ModuleId,
+    pub path: &'r Path,
+    pub source: &'r str,
+    pub region: Region,
+}
+
+impl Location<'_> {
+    pub(crate) fn to_json(&self, interns: &Interns) -> Value {
+        let region = LineInfo::new(self.source).convert_region(self.region);
+        let code = self
+            .source
+            .get(self.region.start().offset as usize..self.region.end().offset as usize)
+            .unwrap_or_default();
+
+        json!({
+            "module": module_name(interns, self.module_id, self.path),
+            "path": self.path.display().to_string(),
+            "region": {
+                "start": { "line": region.start.line + 1, "column": region.start.column + 1 },
+                "end": { "line": region.end.line + 1, "column": region.end.column + 1 },
+            },
+            "source": code,
+        })
+    }
+}
+
+/// The name of a module, for people and tools to show. App modules all have the same name
+/// internally, so they go by the name of their file instead, like `main` for `main.roc`.
+pub(crate) fn module_name(interns: &Interns, module_id: ModuleId, path: &Path) -> String {
+    let name = interns.module_name(module_id).as_str();
+
+    match path.file_stem().and_then(|stem| stem.to_str()) {
+        Some(stem) if name == ModuleName::APP => stem.to_string(),
+        _ => name.to_string(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn write_failure(
+    writer: &mut impl std::io::Write,
+    arena: &Bump,
+    interns: &Interns,
+    subs: &mut Subs,
+    location: Location,
+    call_sites: &[CallSite],
+    symbols: &[Symbol],
+    variables: &[Variable],
+    expressions: &[Expr<'_>],
+) -> std::io::Result<()> {
+    let values: Vec<Value> = symbols
+        .iter()
+        .zip(variables)
+        .zip(expressions)
+        .map(|((symbol, variable), expr)| {
+            let typ = name_and_print_var(
+                *variable,
+                subs,
+                location.module_id,
+                interns,
+                DebugPrint::NOTHING,
+            );
+            let as_json =
+                serde_json::from_str::<Value>(Json.inspect(arena, expr)).unwrap_or(Value::Null);
+
+            json!({
+                "name": symbol.as_str(interns),
+                "type": typ,
+                "value": RocSyntax.inspect(arena, expr),
+                "json": as_json,
+            })
+        })
+        .collect();
+
+    let calls: Vec<Value> = call_sites
+        .iter()
+        .map(|call_site| {
+            json!({
+                "path": call_site.path.display().to_string(),
+                "line": call_site.region.start.line + 1,
+                "column": call_site.region.start.column + 1,
+            })
+        })
+        .collect();
+
+    let mut record = location.to_json(interns);
+    record["kind"] = json!("failure");
+    record["values"] = json!(values);
+    record["calls"] = json!(calls);
+
+    write_record(writer, &record)
+}
+
+pub(crate) fn write_crash(
+    writer: &mut impl std::io::Write,
+    interns: &Interns,
+    location: Location,
+    message: &str,
+) -> std::io::Result<()> {
+    let mut record = location.to_json(interns);
+    record["kind"] = json!("crash");
+    record["message"] = json!(message);
+
+    write_record(writer, &record)
+}
+
+pub(crate) fn write_dbg(
+    writer: &mut impl std::io::Write,
+    arena: &Bump,
+    interns: &Interns,
+    location: Location,
+    expression: &Expr<'_>,
+) -> std::io::Result<()> {
+    let mut record = location.to_json(interns);
+    record["kind"] = json!("dbg");
+    record["value"] = json!(RocSyntax.inspect(arena, expression));
+
+    write_record(writer, &record)
+}
+
+pub(crate) fn write_internal_error(
+    writer: &mut impl std::io::Write,
+    what: &str,
+    expect: Option<String>,
+    message: &str,
+) -> std::io::Result<()> {
+    let record = json!({
+        "kind": "internal-error",
+        "of": what,
+        "expect": expect,
+        "message": message,
+    });
+
+    write_record(writer, &record)
+}
+
+pub fn write_coverage(
+    writer: &mut impl std::io::Write,
+    coverage: &CoverageSummary,
+) -> std::io::Result<()> {
+    let uncovered: Vec<Value> = coverage
+        .uncovered
+        .iter()
+        .map(|def| {
+            json!({
+                "module": def.module_name,
+                "name": def.name,
+                "path": def.path.display().to_string(),
+            })
+        })
+        .collect();
+
+    let record = json!({
+        "kind": "coverage",
+        "covered": coverage.covered,
+        "uncovered": uncovered,
+    });
+
+    write_record(writer, &record)
+}
+
+pub fn write_summary(
+    writer: &mut impl std::io::Write,
+    failed: usize,
+    passed: usize,
+    skipped: usize,
+    milliseconds: u128,
+    seed: Option<u64>,
+) -> std::io::Result<()> {
+    let mut record = json!({
+        "kind": "summary",
+        "failed": failed,
+        "passed": passed,
+        "skipped": skipped,
+        "milliseconds": milliseconds as u64,
+    });
+
+    if let Some(seed) = seed {
+        record["seed"] = json!(seed);
+    }
+
+    write_record(writer, &record)
+}
+
+fn write_record(writer: &mut impl std::io::Write, record: &Value) -> std::io::Result<()> {
+    serde_json::to_writer(&mut *writer, record)?;
+
+    writeln!(writer)
+}
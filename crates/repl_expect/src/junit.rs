@@ -0,0 +1,253 @@
+//! JUnit XML reports for `roc test --format junit`, which CI systems like GitLab and Jenkins
+//! know how to show.
+//!
+//! Every top-level expect that ran becomes a `testcase`, grouped into one `testsuite` per
+//! module. A failed or crashed expect gets a `failure` holding the report that was printed
+//! for it on the terminal, without colors.
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use roc_collections::VecMap;
+use roc_load::Expectations;
+use roc_module::symbol::{Interns, ModuleId};
+use roc_region::all::{LineInfo, Region};
+
+use crate::json::FailureFormat;
+use crate::run::ToplevelExpect;
+
+/// The outcome of one top-level expect.
+#[derive(Debug, Clone)]
+pub struct TestCase {
+    /// The comment right above the expect, or else which line it's on
+    pub name: String,
+    pub module: String,
+    pub path: PathBuf,
+    /// 1-based
+    pub line: u32,
+    pub time: Duration,
+    /// What was printed about the expect failing, or `None` if it passed
+    pub failure: Option<String>,
+}
+
+impl TestCase {
+    pub(crate) fn new(
+        interns: &Interns,
+        expectations: &VecMap<ModuleId, Expectations>,
+        expect: &ToplevelExpect<'_>,
+        passed: bool,
+        time: Duration,
+        output: &[u8],
+    ) -> Self {
+        let module_id = expect.symbol.module_id();
+        let path = expectations
+            .get(&module_id)
+            .map(|data| data.path.clone())
+            .unwrap_or_default();
+        let (comment, line) = comment_and_line(&path, expect.region);
+
+        let name = match comment {
+            Some(comment) => comment,
+            None => format!("expect on line {line}"),
+        };
+
+        let failure = match passed {
+            true => None,
+            false => {
+                let plain = strip_ansi_escapes::strip(output).unwrap_or_else(|_| output.to_vec());
+
+                Some(String::from_utf8_lossy(&plain).trim().to_string())
+            }
+        };
+
+        Self {
+            name,
+            module: crate::json::module_name(interns, module_id, &path),
+            path,
+            line,
+            time,
+            failure,
+        }
+    }
+}
+
+/// The comment right above the expect in the module at `path`, if there is one, and the
+/// (1-based) line the expect starts on.
+pub(crate) fn comment_and_line(path: &Path, region: Region) -> (Option<String>, u32) {
+    let source = std::fs::read_to_string(path).unwrap_or_default();
+
+    let line = LineInfo::new(&source).convert_region(region).start.line + 1;
+
+    (comment(&source, region), line)
+}
+
+/// The comment a top-level expect at `region` in `source` starts with, since the region of a
+/// top-level expect includes the comment right above it.
+pub(crate) fn comment(source: &str, region: Region) -> Option<String> {
+    source
+        .get(region.start().offset as usize..)
+        .and_then(|code| code.lines().next())
+        .and_then(|first_line| first_line.trim().strip_prefix('#'))
+        .map(|comment| comment.trim_start_matches('#').trim())
+        .filter(|comment| !comment.is_empty())
+        .map(str::to_string)
+}
+
+/// Passes everything written to it on to `writer`, and keeps a copy when the format keeps test
+/// cases, so the report of an expect can go both to the terminal and into the JUnit file. For
+/// [FailureFormat::Tap], the report only goes into the test point, so nothing is passed on.
+pub(crate) struct Tee<'w, W> {
+    writer: Option<&'w mut W>,
+    copy: Option<Vec<u8>>,
+}
+
+impl<'w, W: Write> Tee<'w, W> {
+    pub(crate) fn new(writer: &'w mut W, format: FailureFormat) -> Self {
+        Self {
+            writer: (format != FailureFormat::Tap).then_some(writer),
+            copy: format.keeps_cases().then(Vec::new),
+        }
+    }
+
+    pub(crate) fn into_copy(self) -> Option<Vec<u8>> {
+        self.copy
+    }
+}
+
+impl<W: Write> Write for Tee<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = match self.writer.as_mut() {
+            Some(writer) => writer.write(buf)?,
+            None => buf.len(),
+        };
+
+        if let Some(copy) = self.copy.as_mut() {
+            copy.extend_from_slice(&buf[..written]);
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self.writer.as_mut() {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+pub fn write_report(
+    writer: &mut impl Write,
+    cases: &[TestCase],
+    total_time: Duration,
+) -> std::io::Result<()> {
+    let failures = |cases: &[&TestCase]| cases.iter().filter(|case| case.failure.is_some()).count();
+
+    let all: Vec<&TestCase> = cases.iter().collect();
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<testsuites name="roc test" tests="{}" failures="{}" time="{:.3}">"#,
+        all.len(),
+        failures(&all),
+        total_time.as_secs_f64()
+    )?;
+
+    // one suite per module, in the order their expects ran
+    let mut modules: Vec<&str> = Vec::new();
+
+    for case in cases {
+        if !modules.contains(&case.module.as_str()) {
+            modules.push(&case.module);
+        }
+    }
+
+    for module in modules {
+        let suite: Vec<&TestCase> = cases.iter().filter(|case| case.module == module).collect();
+        let time: Duration = suite.iter().map(|case| case.time).sum();
+
+        writeln!(
+            writer,
+            r#"  <testsuite name="{}" tests="{}" failures="{}" time="{:.3}" file="{}">"#,
+            escape(module),
+            suite.len(),
+            failures(&suite),
+            time.as_secs_f64(),
+            escape(&suite[0].path.display().to_string()),
+        )?;
+
+        for case in suite {
+            write!(
+                writer,
+                r#"    <testcase name="{}" classname="{}" file="{}" line="{}" time="{:.3}""#,
+                escape(&case.name),
+                escape(&case.module),
+                escape(&case.path.display().to_string()),
+                case.line,
+                case.time.as_secs_f64(),
+            )?;
+
+            match &case.failure {
+                None => writeln!(writer, "/>")?,
+                Some(report) => {
+                    writeln!(writer, ">")?;
+                    writeln!(
+                        writer,
+                        r#"      <failure message="{}"><![CDATA[{}]]></failure>"#,
+                        escape(&failure_message(report)),
+                        // `]]>` would end the CDATA section early
+                        without_control_chars(report).replace("]]>", "]]]]><![CDATA[>"),
+                    )?;
+                    writeln!(writer, "    </testcase>")?;
+                }
+            }
+        }
+
+        writeln!(writer, "  </testsuite>")?;
+    }
+
+    writeln!(writer, "</testsuites>")
+}
+
+/// The header of the first report, e.g. `EXPECT FAILED in ./Test.roc` for
+/// `── EXPECT FAILED ──────── ./Test.roc ─`.
+pub(crate) fn failure_message(report: &str) -> String {
+    let header = report.lines().find(|line| !line.trim().is_empty());
+
+    let parts: Vec<&str> = header
+        .unwrap_or_default()
+        .split('─')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect();
+
+    match parts.as_slice() {
+        [] => "The expectation failed.".to_string(),
+        parts => parts.join(" in "),
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in without_control_chars(text).chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// XML can't contain most control characters, not even escaped.
+fn without_control_chars(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+        .collect()
+}
@@ -1,7 +1,8 @@
 //! Supports evaluating `expect` and printing contextual information when they fail.
 #[cfg(not(windows))]
 use {
-    roc_module::symbol::Interns,
+    roc_collections::MutMap,
+    roc_module::symbol::{Interns, Symbol},
     roc_mono::{
         ir::ProcLayout,
         layout::{GlobalLayoutInterner, LayoutCache, Niche},
@@ -15,7 +16,41 @@ use {
 #[cfg(not(windows))]
 mod app;
 #[cfg(not(windows))]
+mod child_exit;
+#[cfg(not(windows))]
+pub mod coverage;
+#[cfg(not(windows))]
+pub mod effect_trace;
+#[cfg(not(windows))]
+pub mod frame_dump;
+#[cfg(not(windows))]
+pub mod json;
+#[cfg(not(windows))]
+pub mod junit;
+#[cfg(not(windows))]
+pub mod list;
+#[cfg(not(windows))]
+pub mod memory_limit;
+#[cfg(not(windows))]
+pub mod profile;
+#[cfg(not(windows))]
+pub mod round_trip;
+#[cfg(not(windows))]
 pub mod run;
+#[cfg(not(windows))]
+pub mod sandbox;
+#[cfg(not(windows))]
+pub mod session;
+#[cfg(not(windows))]
+pub mod shm;
+#[cfg(not(windows))]
+pub mod tap;
+#[cfg(not(windows))]
+pub mod timeout;
+#[cfg(not(windows))]
+pub mod times;
+#[cfg(not(windows))]
+pub mod wasm;
 
 #[cfg(not(windows))]
 use app::{ExpectMemory, ExpectReplApp};
@@ -28,6 +63,7 @@ pub fn get_values<'a>(
     subs: &Subs,
     interns: &'a Interns,
     layout_interner: &GlobalLayoutInterner<'a>,
+    closure_captures: &'a MutMap<Symbol, std::vec::Vec<Symbol>>,
     start: *const u8,
     start_offset: usize,
     number_of_lookups: usize,
@@ -35,7 +71,7 @@ pub fn get_values<'a>(
     let mut result = Vec::with_capacity(number_of_lookups);
     let mut result_vars = Vec::with_capacity(number_of_lookups);
 
-    let memory = ExpectMemory { start };
+    let memory = ExpectMemory::new(start, target_info);
 
     let app = ExpectReplApp {
         memory: arena.alloc(memory),
@@ -45,13 +81,14 @@ pub fn get_values<'a>(
     let app = arena.alloc(app);
 
     for i in 0..number_of_lookups {
-        let size_of_lookup_header = 8 /* pointer to value */ + 4 /* type variable */;
+        let pointer_size = target_info.ptr_size();
+        let size_of_lookup_header = pointer_size /* pointer to value */ + 4 /* type variable */;
 
         let start = app
             .memory
             .deref_usize(start_offset + i * size_of_lookup_header);
         let variable = app.memory.deref_u32(
-            start_offset + i * size_of_lookup_header + 8, /* skip the pointer */
+            start_offset + i * size_of_lookup_header + pointer_size, /* skip the pointer */
         );
         let variable = unsafe { Variable::from_index(variable) };
 
@@ -78,6 +115,7 @@ pub fn get_values<'a>(
                 interns,
                 layout_interner.fork(),
                 target_info,
+                Some(closure_captures),
             )
         };
 
@@ -93,17 +131,64 @@ pub fn get_values<'a>(
 mod test {
     use indoc::indoc;
     use pretty_assertions::assert_eq;
-    use roc_gen_llvm::{llvm::build::LlvmBackendMode, run_roc::RocCallResult, run_roc_dylib};
+    use roc_gen_llvm::llvm::build::LlvmBackendMode;
     use roc_load::{ExecutionMode, LoadConfig, LoadMonomorphizedError, Threading};
     use roc_packaging::cache::RocCacheDir;
     use roc_reporting::report::{RenderTarget, DEFAULT_PALETTE};
     use target_lexicon::Triple;
 
+    use crate::json::FailureFormat;
     use crate::run::expect_mono_module_to_dylib;
 
     use super::*;
 
     fn run_expect_test(source: &str, expected: &str) {
+        let writer = run_expects(source, FailureFormat::Human);
+
+        // Remove ANSI escape codes from the answer - for example:
+        //
+        //     Before: "42 \u{1b}[35m:\u{1b}[0m Num *"
+        //     After:  "42 : Num *"
+        let bytes = strip_ansi_escapes::strip(writer).unwrap();
+        let actual = String::from_utf8(bytes).unwrap();
+
+        if !actual.is_empty() {
+            // trim off the first line; it contains a path in a tempdir that
+            // changes between test runs
+            let p = actual.bytes().position(|c| c == b'\n').unwrap();
+            let (_, x) = actual.split_at(p);
+            let x = x.trim();
+            let expected = expected.trim_end();
+
+            if x != expected {
+                println!("{}", x);
+            }
+
+            assert_eq!(expected, x);
+        } else {
+            assert_eq!(expected, actual);
+        }
+    }
+
+    /// Runs the toplevel expects in `source`, and returns what they wrote.
+    fn run_expects(source: &str, format: FailureFormat) -> Vec<u8> {
+        run_expects_with_results(source, format).0
+    }
+
+    fn run_expects_with_results(
+        source: &str,
+        format: FailureFormat,
+    ) -> (Vec<u8>, crate::run::ExpectResults) {
+        run_expects_with(source, format, None, false, None)
+    }
+
+    fn run_expects_with(
+        source: &str,
+        format: FailureFormat,
+        max_memory: Option<u64>,
+        coverage: bool,
+        max_failures: Option<usize>,
+    ) -> (Vec<u8>, crate::run::ExpectResults) {
         let arena = bumpalo::Bump::new();
         let arena = &arena;
 
@@ -111,66 +196,36 @@ mod test {
         let target = &triple;
 
         let opt_level = roc_mono::ir::OptLevel::Normal;
-        let target_info = TargetInfo::from(target);
 
         // Step 1: compile the app and generate the .o file
-        let src_dir = tempfile::tempdir().unwrap();
-        let filename = src_dir.path().join("Test.roc");
-
-        std::fs::write(&filename, source).unwrap();
-
-        let load_config = LoadConfig {
-            target_info,
-            render: RenderTarget::ColorTerminal,
-            palette: DEFAULT_PALETTE,
-            threading: Threading::Single,
-            exec_mode: ExecutionMode::Test,
-        };
-        let loaded = match roc_load::load_and_monomorphize_from_str(
-            arena,
-            filename,
-            source,
-            src_dir.path().to_path_buf(),
-            RocCacheDir::Disallowed,
-            load_config,
-        ) {
-            Ok(m) => m,
-            Err(LoadMonomorphizedError::ErrorModule(m)) => {
-                panic!("{:?}", (m.can_problems, m.type_problems))
-            }
-            Err(e) => panic!("{e:?}"),
-        };
-
-        let mut loaded = loaded;
+        let (mut loaded, _src_dir) = load_test_module(arena, source);
         let mut expectations = std::mem::take(&mut loaded.expectations);
-        let loaded = loaded;
 
         let interns = loaded.interns.clone();
 
-        let (lib, expects, layout_interner) = expect_mono_module_to_dylib(
+        let (lib, mut expects, layout_interner) = expect_mono_module_to_dylib(
             arena,
             target.clone(),
             loaded,
             opt_level,
             LlvmBackendMode::CliTest,
+            false,
+            coverage,
+            max_memory,
+            &crate::run::ExpectFilter::ALL,
         )
         .unwrap();
 
+        expects.max_failures = max_failures;
+
         let arena = &bumpalo::Bump::new();
         let interns = arena.alloc(interns);
 
-        const BUFFER_SIZE: usize = 1024;
-
-        let mut shared_buffer = [0u8; BUFFER_SIZE];
-        let mut memory = crate::run::ExpectMemory::from_slice(&mut shared_buffer);
-
-        // communicate the mmapped name to zig/roc
-        let set_shared_buffer = run_roc_dylib!(lib, "set_shared_buffer", (*mut u8, usize), ());
-        let mut result = RocCallResult::default();
-        unsafe { set_shared_buffer((shared_buffer.as_mut_ptr(), BUFFER_SIZE), &mut result) };
+        // tests run in parallel, so each needs a buffer of its own
+        let mut memory = crate::run::ExpectMemory::create_unique();
 
         let mut writer = Vec::with_capacity(1024);
-        let (_failed, _passed) = crate::run::run_expects_with_memory(
+        let results = crate::run::run_expects_with_memory(
             &mut writer,
             RenderTarget::ColorTerminal,
             arena,
@@ -180,32 +235,55 @@ mod test {
             &mut expectations,
             expects,
             &mut memory,
+            crate::sandbox::Sandbox::NONE,
+            None,
+            None,
+            None,
+            format,
         )
         .unwrap();
 
-        // Remove ANSI escape codes from the answer - for example:
-        //
-        //     Before: "42 \u{1b}[35m:\u{1b}[0m Num *"
-        //     After:  "42 : Num *"
-        let bytes = strip_ansi_escapes::strip(writer).unwrap();
-        let actual = String::from_utf8(bytes).unwrap();
+        (writer, results)
+    }
 
-        if !actual.is_empty() {
-            // trim off the first line; it contains a path in a tempdir that
-            // changes between test runs
-            let p = actual.bytes().position(|c| c == b'\n').unwrap();
-            let (_, x) = actual.split_at(p);
-            let x = x.trim();
-            let expected = expected.trim_end();
+    /// Loads `source` as the `Test.roc` in the returned directory.
+    fn load_test_module<'a>(
+        arena: &'a bumpalo::Bump,
+        source: &'a str,
+    ) -> (roc_load::MonomorphizedModule<'a>, tempfile::TempDir) {
+        let target_info = TargetInfo::from(&Triple::host());
 
-            if x != expected {
-                println!("{}", x);
+        let src_dir = tempfile::tempdir().unwrap();
+        let filename = src_dir.path().join("Test.roc");
+
+        std::fs::write(&filename, source).unwrap();
+
+        let load_config = LoadConfig {
+            target_info,
+            render: RenderTarget::ColorTerminal,
+            palette: DEFAULT_PALETTE,
+            threading: Threading::Single,
+            exec_mode: ExecutionMode::Test,
+            platform: None,
+            max_type_errors: None,
+            keep_module_types: false,
+        };
+        let loaded = match roc_load::load_and_monomorphize_from_str(
+            arena,
+            filename,
+            source,
+            src_dir.path().to_path_buf(),
+            RocCacheDir::Disallowed,
+            load_config,
+        ) {
+            Ok(m) => m,
+            Err(LoadMonomorphizedError::ErrorModule(m)) => {
+                panic!("{:?}", (m.can_problems, m.type_problems))
             }
+            Err(e) => panic!("{e:?}"),
+        };
 
-            assert_eq!(expected, x);
-        } else {
-            assert_eq!(expected, actual);
-        }
+        (loaded, src_dir)
     }
 
     #[test]
@@ -255,32 +333,480 @@ mod test {
                 main = 0
 
                 expect
-                    a = 1
-                    b = 2
+                    a = 1
+                    b = 2
+
+                    a == b
+                "#
+            ),
+            indoc!(
+                r#"
+                This expectation failed:
+
+                5│>  expect
+                6│>      a = 1
+                7│>      b = 2
+                8│>
+                9│>      a == b
+
+                When it failed, these variables had these values:
+
+                a : Num *
+                a = 1
+
+                b : Num *
+                b = 2
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn json_failure() {
+        let output = run_expects(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                expect 1 == 1
+
+                expect
+                    a = 1
+                    b = [2, 3]
+
+                    a == List.len b
+                "#
+            ),
+            FailureFormat::Json,
+        );
+        let output = String::from_utf8(output).unwrap();
+
+        // only the failed expect gets a record
+        let records: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 1);
+
+        let record = &records[0];
+        assert_eq!(record["kind"], "failure");
+        assert_eq!(record["module"], "Test");
+        assert_eq!(record["region"]["start"]["line"], 7);
+        assert_eq!(record["region"]["end"]["line"], 11);
+        assert!(record["source"]
+            .as_str()
+            .unwrap()
+            .ends_with("a == List.len b"));
+
+        assert_eq!(
+            record["values"],
+            serde_json::json!([
+                { "name": "a", "type": "Nat", "value": "1", "json": 1 },
+                { "name": "b", "type": "List (Num *)", "value": "[2, 3]", "json": [2, 3] },
+            ])
+        );
+    }
+
+    #[test]
+    fn json_dbg_before_failure() {
+        let output = run_expects(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                expect
+                    a = 1
+                    dbg a
+                    a == 2
+
+                expect
+                    b = 3
+                    dbg b
+                    b == 3
+                "#
+            ),
+            FailureFormat::Json,
+        );
+        let output = String::from_utf8(output).unwrap();
+
+        // the dbg of the expect that passed is not shown
+        let records: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+
+        assert_eq!(records[0]["kind"], "dbg");
+        assert_eq!(records[0]["region"]["start"]["line"], 7);
+        assert_eq!(records[0]["value"], "1");
+
+        assert_eq!(records[1]["kind"], "failure");
+        assert_eq!(records[1]["region"]["start"]["line"], 5);
+    }
+
+    #[test]
+    fn junit_report() {
+        let (output, results) = run_expects_with_results(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                # one is one
+                expect 1 == 1
+
+                expect
+                    a = 1
+                    b = 2
+
+                    a == b
+                "#
+            ),
+            FailureFormat::Junit,
+        );
+
+        // failures are still shown like usual
+        let output = String::from_utf8(strip_ansi_escapes::strip(output).unwrap()).unwrap();
+        assert!(output.contains("This expectation failed:"));
+
+        let mut report = Vec::new();
+        crate::junit::write_report(&mut report, &results.cases, std::time::Duration::ZERO).unwrap();
+        let report = String::from_utf8(report).unwrap();
+
+        assert!(report.contains(r#"<testsuites name="roc test" tests="2" failures="1""#));
+        assert!(report.contains(r#"<testcase name="one is one" classname="Test""#));
+        assert!(report.contains(r#"<testcase name="expect on line 8" classname="Test""#));
+        assert!(report.contains(r#"<failure message="EXPECT FAILED in "#));
+        assert!(report.contains("<![CDATA[── EXPECT FAILED ─"));
+        assert!(report.contains("a = 1"));
+        assert!(!report.contains('\u{1b}'));
+    }
+
+    #[test]
+    fn tap_stream() {
+        let (output, results) = run_expects_with_results(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                # one is one
+                expect 1 == 1
+
+                expect
+                    a = 1
+                    b = 2
+
+                    a == b
+                "#
+            ),
+            FailureFormat::Tap,
+        );
+
+        let mut stream = output;
+        crate::tap::write_plan(&mut stream, &results.cases, 0, 0).unwrap();
+        let stream = String::from_utf8(stream).unwrap();
+        let lines: Vec<&str> = stream.lines().collect();
+
+        // the reports only go into the YAML block
+        assert_eq!(lines[0], "ok 1 - one is one");
+        assert_eq!(lines[1], "not ok 2 - expect on line 8");
+        assert_eq!(lines[2], "  ---");
+        assert!(lines[3].starts_with(r#"  message: "EXPECT FAILED in "#));
+        assert!(stream.contains("  report: |-\n    ── EXPECT FAILED ─"));
+        assert!(stream.contains("\n    a = 1\n"));
+        assert!(stream.ends_with("  ...\n1..2\n"));
+        assert!(!stream.contains('\u{1b}'));
+    }
+
+    #[test]
+    fn slowest_expects() {
+        let (_, results) = run_expects_with_results(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                # one is one
+                expect 1 == 1
+
+                expect
+                    List.len (List.range { start: At 0, end: Before 1_000_000 }) == 1_000_000
+                "#
+            ),
+            FailureFormat::Human,
+        );
+
+        assert_eq!(results.times.len(), 2);
+
+        let mut output = Vec::new();
+        crate::times::write_slowest(&mut output, &results.times, 1).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        // the module is gone by now, so which line the expect is on isn't known anymore
+        assert_eq!(lines[0], "The slowest expect:");
+        assert!(lines[2].contains(" ms  "), "{}", lines[2]);
+        assert!(lines[2].contains("Test.roc:"), "{}", lines[2]);
+        assert_eq!(lines.len(), 3);
+
+        let mut output = Vec::new();
+        crate::times::write_slowest(&mut output, &results.times, 10).unwrap();
+
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .starts_with("The 2 slowest expects:\n"));
+    }
+
+    #[test]
+    fn list_expects_without_running_them() {
+        let arena = bumpalo::Bump::new();
+        let (loaded, _src_dir) = load_test_module(
+            &arena,
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                double = \x ->
+                    expect x > 0
+
+                    x * 2
+
+                # doubles
+                expect double 1 == 2
+
+                expect 1 + 1 == 2
+                "#
+            ),
+        );
+
+        let listed = crate::list::list_expects(&loaded, &crate::run::ExpectFilter::ALL);
+
+        let mut human = Vec::new();
+        crate::list::write_human(&mut human, &loaded, &listed).unwrap();
+        let human = String::from_utf8(human).unwrap();
+        let lines: Vec<&str> = human.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with("Test.roc:6:12  Test  inline expect"));
+        assert!(lines[1].ends_with("Test.roc:10:1  Test  expect  doubles"));
+        assert!(lines[2].ends_with("Test.roc:13:1  Test  expect"));
+
+        let mut json = Vec::new();
+        crate::list::write_json(&mut json, &loaded, &listed).unwrap();
+        let records: Vec<serde_json::Value> = String::from_utf8(json)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(records[0]["toplevel"], false);
+        assert_eq!(records[0]["source"], "x > 0");
+        assert_eq!(records[1]["name"], "doubles");
+        assert_eq!(records[1]["effectful"], false);
+        assert_eq!(records[2]["name"], serde_json::Value::Null);
+        assert_eq!(records[2]["region"]["start"]["line"], 13);
+    }
+
+    #[test]
+    fn over_the_memory_limit() {
+        let (output, results) = run_expects_with(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                expect
+                    small = List.repeat 1u8 1000
+
+                    List.len small == 1000
+
+                expect
+                    big = List.repeat 1u8 2_000_000
+
+                    List.len big == 2_000_000
+                "#
+            ),
+            FailureFormat::Human,
+            Some(1024 * 1024),
+            false,
+            None,
+        );
+
+        let output = String::from_utf8(strip_ansi_escapes::strip(output).unwrap()).unwrap();
+
+        assert_eq!((results.passed, results.failed), (1, 1));
+        assert!(output.contains("EXPECT PANICKED"));
+        assert!(output.contains("went over the memory limit of 1.0 MiB"));
+    }
+
+    #[test]
+    fn coverage_of_exposed_definitions() {
+        let (_, results) = run_expects_with(
+            indoc!(
+                r#"
+                interface Test exposes [double, triple, quadruple, answer] imports []
+
+                double = \n -> 2 * n
+
+                triple = \n -> 3 * n
+
+                quadruple = \n -> double (double n)
+
+                answer = 42
+
+                expect quadruple 1 == 4
+                "#
+            ),
+            FailureFormat::Human,
+            None,
+            true,
+            None,
+        );
+
+        let coverage = results.coverage.unwrap();
+        let mut uncovered: Vec<_> = coverage.uncovered.iter().map(|u| u.name.as_str()).collect();
+        uncovered.sort();
+
+        assert_eq!(results.passed, 1);
+        assert_eq!(coverage.covered, 2);
+        assert_eq!(uncovered, ["answer", "triple"]);
+    }
+
+    #[test]
+    fn stops_after_the_maximum_number_of_failures() {
+        let (output, results) = run_expects_with(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                expect 1 == 2
+
+                expect 1 == 1
+
+                expect 2 == 3
+
+                expect 3 == 4
+                "#
+            ),
+            FailureFormat::Human,
+            None,
+            false,
+            Some(2),
+        );
+
+        let output = String::from_utf8(strip_ansi_escapes::strip(output).unwrap()).unwrap();
+
+        assert_eq!((results.failed, results.passed, results.skipped), (2, 1, 1));
+        assert_eq!(output.matches("EXPECT FAILED").count(), 2);
+        assert!(!output.contains("3 == 4"));
+    }
+
+    #[test]
+    fn long_lists_are_cut_short() {
+        let output = run_expects(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                expect
+                    xs = List.range { start: At 0, end: Before 1000 }
+
+                    xs == []
+                "#
+            ),
+            FailureFormat::Human,
+        );
+
+        let output = String::from_utf8(strip_ansi_escapes::strip(output).unwrap()).unwrap();
+
+        assert!(output.contains("98, 99, … 900 more elements]"));
+        assert!(output.contains("Some values were cut short."));
+    }
+
+    #[test]
+    fn session_runs_its_latest_build() {
+        let mut session = crate::session::ExpectSession::new(Triple::host());
+
+        for (source, failed) in [
+            ("expect 1 == 2", 1),
+            ("expect 1 == 1", 0),
+            ("expect 2 == 3", 1),
+        ] {
+            let source =
+                format!("app \"test\" provides [main] to \"./platform\"\n\nmain = 0\n\n{source}\n");
+            let arena = &bumpalo::Bump::new();
+            let (mut loaded, _src_dir) = load_test_module(arena, &source);
+            let mut expectations = std::mem::take(&mut loaded.expectations);
+            let interns = arena.alloc(loaded.interns.clone());
+
+            let (expects, layout_interner) = session
+                .build(
+                    arena,
+                    loaded,
+                    roc_mono::ir::OptLevel::Normal,
+                    &crate::run::ExpectFilter::ALL,
+                )
+                .unwrap();
+
+            let results = session
+                .run(
+                    &mut Vec::new(),
+                    RenderTarget::ColorTerminal,
+                    arena,
+                    interns,
+                    &layout_interner.into_global(),
+                    &mut expectations,
+                    expects,
+                    None,
+                    None,
+                    FailureFormat::Human,
+                )
+                .unwrap();
+
+            assert_eq!((results.failed, results.passed), (failed, 1 - failed));
+        }
+    }
+
+    #[test]
+    fn lookup_bigger_than_the_initial_buffer() {
+        let output = run_expects(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                expect
+                    strings = List.map (List.range { start: At 0, end: Before 500 }) \n ->
+                        Str.concat "a string too long to be a small string " (Num.toStr n)
 
-                    a == b
+                    List.isEmpty strings
                 "#
             ),
-            indoc!(
-                r#"
-                This expectation failed:
-
-                5│>  expect
-                6│>      a = 1
-                7│>      b = 2
-                8│>
-                9│>      a == b
-
-                When it failed, these variables had these values:
+            FailureFormat::Json,
+        );
+        let output = String::from_utf8(output).unwrap();
 
-                a : Num *
-                a = 1
+        let record: serde_json::Value = serde_json::from_str(output.trim_end()).unwrap();
+        let strings = record["values"][0]["json"].as_array().unwrap();
 
-                b : Num *
-                b = 2
-                "#
-            ),
-        );
+        assert_eq!(strings.len(), 500);
+        assert_eq!(strings[499], "a string too long to be a small string 499");
     }
 
     #[test]
@@ -316,6 +842,10 @@ mod test {
 
                 b : List Str
                 b = ["a string so long that it cannot be short"]
+
+                a and b differ here:
+
+                    [0]: "foo" ≠ "a string so long that it cannot be short"
                 "#
             ),
         );
@@ -354,6 +884,12 @@ mod test {
 
                 b : List (List Str)
                 b = [["a string so long that it cannot be short", "bar"]]
+
+                a and b differ here:
+
+                    [0][0]: "foo" ≠ "a string so long that it cannot be short"
+                    [0][1]: (missing) ≠ "bar"
+                    [1]: [] ≠ (missing)
                 "#
             ),
         );
@@ -443,6 +979,39 @@ mod test {
         );
     }
 
+    #[test]
+    fn lookup_seamless_slice() {
+        run_expect_test(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                expect
+                    s = Str.trim "   a string too long to be a small string"
+
+                    s == "x"
+                "#
+            ),
+            indoc!(
+                r#"
+                This expectation failed:
+
+                5│>  expect
+                6│>      s = Str.trim "   a string too long to be a small string"
+                7│>
+                8│>      s == "x"
+
+                When it failed, these variables had these values:
+
+                s : Str
+                s = "a string too long to be a small string"
+                "#
+            ),
+        );
+    }
+
     #[test]
     fn lookup_copy_record() {
         run_expect_test(
@@ -482,6 +1051,96 @@ mod test {
                     y : U8,
                 }
                 vec2 = { x: 4, y: 8 }
+
+                vec1 and vec2 differ here:
+
+                    .x: 1 ≠ 4
+                    .y: 2 ≠ 8
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn big_record_shows_accessed_fields() {
+        run_expect_test(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                expect
+                    user = { a: 0u8, b: 0u8, c: 0u8, d: 0u8, e: 0u8, f: 0u8, g: 0u8, h: 0u8, age: 17u8 }
+
+                    user.age >= 18
+                "#
+            ),
+            indoc!(
+                r#"
+                This expectation failed:
+
+                5│>  expect
+                6│>      user = { a: 0u8, b: 0u8, c: 0u8, d: 0u8, e: 0u8, f: 0u8, g: 0u8, h: 0u8, age: 17u8 }
+                7│>
+                8│>      user.age >= 18
+
+                When it failed, these variables had these values:
+
+                user : { age : U8, … }
+                user = {
+                    age: 17,
+                    # … 8 more fields
+                }
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn diff_only_shows_what_differs() {
+        run_expect_test(
+            indoc!(
+                r#"
+                interface Test exposes [] imports []
+
+                Response : { id : U8, tags : List U8, status : Result U16 Str }
+
+                expect
+                    actual : Response
+                    actual = { id: 1, tags: [1, 2, 3], status: Ok 200 }
+
+                    expected : Response
+                    expected = { id: 1, tags: [1, 5, 3], status: Ok 201 }
+
+                    actual == expected
+                "#
+            ),
+            indoc!(
+                r#"
+                This expectation failed:
+
+                 5│>  expect
+                 6│>      actual : Response
+                 7│>      actual = { id: 1, tags: [1, 2, 3], status: Ok 200 }
+                 8│>
+                 9│>      expected : Response
+                10│>      expected = { id: 1, tags: [1, 5, 3], status: Ok 201 }
+                11│>
+                12│>      actual == expected
+
+                When it failed, these variables had these values:
+
+                actual : Response
+                actual = { id: 1, status: Ok 200, tags: [1, 2, 3] }
+
+                expected : Response
+                expected = { id: 1, status: Ok 201, tags: [1, 5, 3] }
+
+                actual and expected differ here:
+
+                    .status(Ok): 200 ≠ 201
+                    .tags[1]: 2 ≠ 5
                 "#
             ),
         );
@@ -633,6 +1292,10 @@ mod test {
 
                 b : Box Str
                 b = Box.box "Profundum et fundamentum"
+
+                a and b differ here:
+
+                    (Box.box): "Astra mortemque praestare gradatim" ≠ "Profundum et fundamentum"
                 "#
             ),
         );
@@ -869,6 +1532,11 @@ mod test {
 
                 b : RoseTree Str
                 b = Tree "foo" [Tree "bar" []]
+
+                a and b differ here:
+
+                    (Tree #1): "Astra mortemque praestare gradatim" ≠ "foo"
+                    (Tree #2)[0]: (missing) ≠ Tree "bar" []
                 "#
             ),
         );
@@ -951,6 +1619,9 @@ mod test {
 
                 When it failed, these variables had these values:
 
+                forcer : Str -> U8
+                forcer = <anonymous function in Test>
+
                 case : Str
                 case = ""
                 "#
@@ -977,6 +1648,48 @@ mod test {
                 3│>  expect
                 4│>      totalCount = \{} -> 1u8
                 5│>      totalCount {} == 96u8
+
+                When it failed, these variables had these values:
+
+                totalCount : {} -> U8
+                totalCount = <function Test.totalCount>
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn lookup_closure_with_captures() {
+        run_expect_test(
+            indoc!(
+                r#"
+                interface Test exposes [] imports []
+
+                makeCounter : Str, U8 -> (U8 -> Str)
+                makeCounter = \label, step ->
+                    count = \x -> Str.concat label (Num.toStr (x + step))
+
+                    count
+
+                expect
+                    counter = makeCounter "total" 2
+
+                    counter 1 == "total 3"
+                "#
+            ),
+            indoc!(
+                r#"
+                This expectation failed:
+
+                 9│>  expect
+                10│>      counter = makeCounter "total" 2
+                11│>
+                12│>      counter 1 == "total 3"
+
+                When it failed, these variables had these values:
+
+                counter : U8 -> Str
+                counter = <function Test.count, captures: { label: "total", step: 2 }>
                 "#
             ),
         );
@@ -1041,6 +1754,18 @@ mod test {
                     x : List (Int Unsigned8),
                 }
                 expected = { body: [42, 43, 44], headers: [15, 16, 17], x: [115, 116, 117] }
+
+                actual and expected differ here:
+
+                    .body[0]: (missing) ≠ 42
+                    .body[1]: (missing) ≠ 43
+                    .body[2]: (missing) ≠ 44
+                    .headers[0]: (missing) ≠ 15
+                    .headers[1]: (missing) ≠ 16
+                    .headers[2]: (missing) ≠ 17
+                    .x[0]: (missing) ≠ 115
+                    .x[1]: (missing) ≠ 116
+                    .x[2]: (missing) ≠ 117
                 "#
             ),
         );
@@ -1101,6 +1826,10 @@ mod test {
                 expected : Request
                 expected = { fieldA: Get, fieldB: "/things?id=1" }
 
+                actual and expected differ here:
+
+                    .fieldB: "/things?id=2" ≠ "/things?id=1"
+
                 "#
             ),
         );
@@ -1250,4 +1979,191 @@ mod test {
             ),
         );
     }
+
+    #[test]
+    fn inline_expect_in_closure_with_captures() {
+        run_expect_test(
+            indoc!(
+                r#"
+                interface Test exposes [] imports []
+
+                makeCheck : U8 -> (List U8 -> Nat)
+                makeCheck = \limit ->
+                    \xs ->
+                        expect List.all xs \x -> x < limit
+
+                        List.len xs
+
+                expect
+                    check = makeCheck 2
+                    check [1, 5] == 2
+                "#
+            ),
+            indoc!(
+                r#"
+                This expectation failed:
+
+                6│          expect List.all xs \x -> x < limit
+                                   ^^^^^^^^^^^^^^^^^^^^^^^^^^^
+
+                It was called from here:
+
+                12│      check [1, 5] == 2
+                         ^^^^^^^^^^^^
+
+                When it failed, these variables had these values:
+
+                limit : U8
+                limit = 2
+
+                xs : List U8
+                xs = [1, 5]
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn inline_expect_looks_up_toplevel_value() {
+        run_expect_test(
+            indoc!(
+                r#"
+                interface Test exposes [] imports []
+
+                limit : U8
+                limit = 2
+
+                isSmall : U8 -> Bool
+                isSmall = \x ->
+                    expect x < limit
+
+                    Bool.true
+
+                expect isSmall 5
+                "#
+            ),
+            indoc!(
+                r#"
+                This expectation failed:
+
+                8│      expect x < limit
+                               ^^^^^^^^^
+
+                It was called from here:
+
+                12│  expect isSmall 5
+                            ^^^^^^^^^
+
+                When it failed, these variables had these values:
+
+                limit : U8
+                limit = 2
+
+                x : U8
+                x = 5
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn inline_expect_shows_where_it_was_called_from() {
+        run_expect_test(
+            indoc!(
+                r#"
+                interface Test exposes [] imports []
+
+                inner : U8 -> U8
+                inner = \x ->
+                    expect x < 3
+
+                    x + 1
+
+                outer : U8 -> U8
+                outer = \x ->
+                    y = inner x
+
+                    y + 1
+
+                expect outer 4 == 6
+                "#
+            ),
+            indoc!(
+                r#"
+                This expectation failed:
+
+                5│      expect x < 3
+                               ^^^^^
+
+                It was called from here:
+
+                11│      y = inner x
+                             ^^^^^^^
+
+                15│  expect outer 4 == 6
+                            ^^^^^^^
+
+                When it failed, these variables had these values:
+
+                x : U8
+                x = 4
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn a_panic_while_rendering_becomes_a_note() {
+        let mut writer = Vec::new();
+
+        let end_offset = crate::run::render_recovering(
+            &mut writer,
+            FailureFormat::Human,
+            24,
+            "expect",
+            Some("Test.roc:3".to_string()),
+            |_| panic!("region @0-0 not in list of expects"),
+        )
+        .unwrap();
+
+        let bytes = strip_ansi_escapes::strip(writer).unwrap();
+
+        assert_eq!(end_offset, 24);
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            indoc!(
+                r#"
+                Internal rendering error for this expect at Test.roc:3: region @0-0 not in list of expects
+
+                This is a bug in the compiler. The results of the other expects are still reported.
+
+                "#
+            )
+        );
+
+        let mut writer = Vec::new();
+
+        crate::run::render_recovering(&mut writer, FailureFormat::Human, 24, "expect", None, |w| {
+            w.extend_from_slice(b"rendered");
+
+            Ok(32)
+        })
+        .unwrap();
+
+        assert_eq!(writer, b"rendered");
+    }
+
+    #[test]
+    fn shared_memory_names_tell_whose_they_are() {
+        assert_eq!(crate::shm::owner("roc_expect_buffer_123"), Some(123));
+        assert_eq!(crate::shm::owner("roc_expect_123_00ab12cd"), Some(123));
+        assert_eq!(crate::shm::owner("roc_expect_buffer"), None);
+        assert_eq!(crate::shm::owner("other_123"), None);
+
+        let name = crate::shm::unique_name();
+
+        assert_eq!(crate::shm::owner(&name[1..]), Some(std::process::id() as _));
+        assert!(name.len() <= 31);
+        assert_ne!(name, crate::shm::unique_name());
+    }
 }
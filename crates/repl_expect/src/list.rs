@@ -0,0 +1,172 @@
+//! Every expect of a program, without running any of them, for `roc test --list`, which tools
+//! that run tests use to show them and to pick which ones to run.
+//!
+//! With `--format json`, every expect is a JSON object on a line of its own, like the records of
+//! [crate::json], with `kind` set to `expect`:
+//! - `module`, `path`, `region` and `source` say where it is, like they do for a failure
+//! - `toplevel` is `false` for an expect inside a definition, which runs whenever the code
+//!   around it does
+//! - `effectful` is `true` for an `expect-fx`
+//! - `name` is the comment right above a top-level expect, or `null`
+use std::io::Write;
+use std::path::PathBuf;
+
+use roc_load::MonomorphizedModule;
+use roc_module::symbol::ModuleId;
+use roc_region::all::{LineInfo, Region};
+use serde_json::json;
+
+use crate::json::{module_name, Location};
+use crate::junit::comment;
+use crate::run::ExpectFilter;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListedExpect {
+    pub module_id: ModuleId,
+    pub path: PathBuf,
+    /// For a top-level expect, this starts at the comment right above it.
+    pub region: Region,
+    pub toplevel: bool,
+    pub effectful: bool,
+    pub name: Option<String>,
+}
+
+/// The expects of `loaded` that match `filter`, both top-level ones and ones inside definitions,
+/// ordered by the path of their module and then by where they are in it.
+pub fn list_expects(loaded: &MonomorphizedModule<'_>, filter: &ExpectFilter) -> Vec<ListedExpect> {
+    let interns = &loaded.interns;
+    let sources = &loaded.sources;
+    let mut listed = Vec::new();
+
+    let toplevel = [
+        (&loaded.toplevel_expects.pure, false),
+        (&loaded.toplevel_expects.fx, true),
+    ];
+
+    for (expects, effectful) in toplevel {
+        for (symbol, region) in expects.iter() {
+            let module_id = symbol.module_id();
+
+            if let Some((path, source)) = sources.get(&module_id) {
+                if filter.matches(interns, sources, module_id, *region) {
+                    listed.push(ListedExpect {
+                        module_id,
+                        path: path.clone(),
+                        region: *region,
+                        toplevel: true,
+                        effectful,
+                        name: comment(source, *region),
+                    });
+                }
+            }
+        }
+    }
+
+    for (module_id, expectations) in loaded.expectations.iter() {
+        // The conditions of top-level expects are in here too, and only they are inside one,
+        // whether or not the top-level expect matched the filter.
+        let toplevel_regions: Vec<Region> = loaded
+            .toplevel_expects
+            .pure
+            .iter()
+            .chain(loaded.toplevel_expects.fx.iter())
+            .filter(|(symbol, _)| symbol.module_id() == *module_id)
+            .map(|(_, region)| *region)
+            .collect();
+
+        for region in expectations.expectations.keys() {
+            let in_toplevel = toplevel_regions
+                .iter()
+                .any(|toplevel| toplevel.contains(region));
+
+            if !in_toplevel && filter.matches(interns, sources, *module_id, *region) {
+                listed.push(ListedExpect {
+                    module_id: *module_id,
+                    path: expectations.path.clone(),
+                    region: *region,
+                    toplevel: false,
+                    effectful: false,
+                    name: None,
+                });
+            }
+        }
+    }
+
+    listed.sort_by(|a, b| {
+        (&a.path, a.region.start().offset).cmp(&(&b.path, b.region.start().offset))
+    });
+
+    listed
+}
+
+/// One line per expect, like `Main.roc:12:1  Main  expect-fx  writes the file`.
+pub fn write_human(
+    writer: &mut impl Write,
+    loaded: &MonomorphizedModule<'_>,
+    listed: &[ListedExpect],
+) -> std::io::Result<()> {
+    if listed.is_empty() {
+        return writeln!(writer, "No expectations were found.");
+    }
+
+    for expect in listed {
+        let start = match loaded.sources.get(&expect.module_id) {
+            Some((_, source)) => LineInfo::new(source).convert_pos(expect.region.start()),
+            None => continue,
+        };
+
+        let kind = match (expect.toplevel, expect.effectful) {
+            (_, true) => "expect-fx",
+            (true, false) => "expect",
+            (false, false) => "inline expect",
+        };
+
+        write!(
+            writer,
+            "{}:{}:{}  {}  {kind}",
+            expect.path.display(),
+            start.line + 1,
+            start.column + 1,
+            module_name(&loaded.interns, expect.module_id, &expect.path),
+        )?;
+
+        match &expect.name {
+            Some(name) => writeln!(writer, "  {name}")?,
+            None => writeln!(writer)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// One JSON object per line per expect, see the [module documentation](self).
+pub fn write_json(
+    writer: &mut impl Write,
+    loaded: &MonomorphizedModule<'_>,
+    listed: &[ListedExpect],
+) -> std::io::Result<()> {
+    for expect in listed {
+        let source = match loaded.sources.get(&expect.module_id) {
+            Some((_, source)) => source,
+            None => continue,
+        };
+
+        let location = Location {
+            module_id: expect.module_id,
+            path: &expect.path,
+            source,
+            region: expect.region,
+        };
+
+        let mut record = location.to_json(&loaded.interns);
+        record["kind"] = json!("expect");
+        record["toplevel"] = json!(expect.toplevel);
+        record["effectful"] = json!(expect.effectful);
+        record["name"] = json!(expect.name);
+
+        serde_json::to_writer(&mut *writer, &record)?;
+        writeln!(writer)?;
+    }
+
+    Ok(())
+}
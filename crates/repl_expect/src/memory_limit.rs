@@ -0,0 +1,75 @@
+//! Stopping pure expects that allocate too much, for `roc test --max-memory`.
+//!
+//! The dylib counts what it allocates itself, and crashes like a `crash` in Roc code does when
+//! that goes over the limit, see [add_memory_limit]. Here, the counts are reset before every
+//! expect, so that each one gets the whole limit, and a crash is reported with how much the
+//! expect had allocated. The REPL limits what its expressions allocate the same way.
+//!
+//! [add_memory_limit]: roc_gen_llvm::llvm::externs::add_memory_limit
+use roc_gen_llvm::llvm::externs::{
+    MEMORY_HIGH_WATER_GLOBAL, MEMORY_IN_USE_GLOBAL, MEMORY_LIMIT_GLOBAL, MEMORY_LIMIT_MESSAGE,
+};
+
+/// The allocation counts of one dylib.
+#[derive(Debug)]
+pub struct MemoryLimit {
+    in_use: *mut usize,
+    high_water: *mut usize,
+    max_bytes: usize,
+}
+
+impl MemoryLimit {
+    /// `None` if `lib` wasn't built with a memory limit.
+    pub fn new(lib: &libloading::Library) -> Option<Self> {
+        let global = |name: &str| unsafe {
+            lib.get::<*mut usize>(name.as_bytes())
+                .ok()
+                .map(|symbol| *symbol)
+        };
+
+        let max_bytes = unsafe { *global(MEMORY_LIMIT_GLOBAL)? };
+
+        Some(Self {
+            in_use: global(MEMORY_IN_USE_GLOBAL)?,
+            high_water: global(MEMORY_HIGH_WATER_GLOBAL)?,
+            max_bytes,
+        })
+    }
+
+    /// Forget what was allocated so far. Freeing it later doesn't count.
+    pub fn reset(&self) {
+        unsafe {
+            *self.in_use = 0;
+            *self.high_water = 0;
+        }
+    }
+
+    /// What to report instead of the crash message, if the crash was from going over the limit.
+    /// `what` went over it, e.g. "expect".
+    pub fn explain(&self, crash_message: &str, what: &str) -> Option<String> {
+        if crash_message != MEMORY_LIMIT_MESSAGE {
+            return None;
+        }
+
+        let (in_use, high_water) = unsafe { (*self.in_use, *self.high_water) };
+
+        Some(format!(
+            "This {what} went over the memory limit of {}, when it tried to have {} allocated at once. Before that, it had at most {} allocated at once.",
+            format_bytes(self.max_bytes),
+            format_bytes(in_use),
+            format_bytes(high_water),
+        ))
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{bytes} bytes")
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    } else if bytes < 1024 * 1024 * 1024 {
+        format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1} GiB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    }
+}
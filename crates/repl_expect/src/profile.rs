@@ -0,0 +1,338 @@
+//! A sampling profiler for `roc test --profile-expects`.
+//!
+//! While an expect runs, a `SIGPROF` timer interrupts it after every millisecond of CPU time,
+//! and the signal handler records which instruction it interrupted. Afterwards, we look up which
+//! function of the expect dylib each of those instructions is in, and print the functions the
+//! expect spent the most time in.
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::run::{expect_location, ToplevelExpect};
+use roc_collections::{MutMap, VecMap};
+use roc_load::Expectations;
+use roc_module::symbol::ModuleId;
+
+/// Whether we know how to find the interrupted instruction on this platform.
+pub const IS_SUPPORTED: bool = cfg!(any(
+    all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ),
+    all(
+        target_os = "macos",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ),
+));
+
+/// How much CPU time there is between two samples.
+const SAMPLE_INTERVAL_MICROS: i64 = 1000;
+
+/// Samples beyond this are dropped, so the signal handler never has to allocate.
+const MAX_SAMPLES: usize = 1 << 16;
+
+/// How many of the hottest functions to print for each expect.
+const HOT_FUNCTIONS_SHOWN: usize = 10;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const NO_SAMPLE: AtomicUsize = AtomicUsize::new(0);
+static SAMPLES: [AtomicUsize; MAX_SAMPLES] = [NO_SAMPLE; MAX_SAMPLES];
+static SAMPLE_COUNT: AtomicUsize = AtomicUsize::new(0);
+/// The thread running the expect. `SIGPROF` goes to whichever thread used the CPU time.
+static SAMPLED_THREAD: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug)]
+struct Function {
+    /// Where the function starts, relative to where the dylib was loaded.
+    start: u64,
+    /// Zero if the symbol table doesn't say (Mach-O doesn't).
+    size: u64,
+    name: String,
+}
+
+/// The functions of an expect dylib, to attribute samples to.
+#[derive(Debug)]
+pub struct Profiler {
+    load_address: usize,
+    /// Sorted by start address.
+    functions: Vec<Function>,
+}
+
+impl Profiler {
+    /// `bytes` are the contents of the file `lib` was loaded from, and `exported` is the name of
+    /// any symbol the dylib exports, which tells us where it was loaded.
+    pub fn new(lib: &libloading::Library, bytes: &[u8], exported: &str) -> Option<Self> {
+        use object::{Object, ObjectSymbol, SymbolKind};
+
+        let address = unsafe {
+            let symbol: libloading::Symbol<*const libc::c_void> =
+                lib.get(exported.as_bytes()).ok()?;
+
+            *symbol
+        };
+
+        let mut info: libc::Dl_info = unsafe { std::mem::zeroed() };
+        if unsafe { libc::dladdr(address, &mut info) } == 0 {
+            return None;
+        }
+
+        let file = object::File::parse(bytes).ok()?;
+        let is_mach_o = file.format() == object::BinaryFormat::MachO;
+
+        let mut functions: Vec<Function> = file
+            .symbols()
+            .filter(|symbol| symbol.kind() == SymbolKind::Text && symbol.is_definition())
+            .filter_map(|symbol| {
+                let name = symbol.name().ok()?;
+
+                // Mach-O prefixes every C symbol with an underscore
+                let name = match is_mach_o {
+                    true => name.strip_prefix('_').unwrap_or(name),
+                    false => name,
+                };
+
+                Some(Function {
+                    start: symbol.address(),
+                    size: symbol.size(),
+                    name: display_name(name),
+                })
+            })
+            .collect();
+
+        functions.sort_by_key(|function| function.start);
+
+        Some(Self {
+            load_address: info.dli_fbase as usize,
+            functions,
+        })
+    }
+
+    /// Start sampling the current thread, until [Sampling::finish] is called.
+    pub(crate) fn start(&self) -> Sampling {
+        SAMPLE_COUNT.store(0, Ordering::SeqCst);
+        SAMPLED_THREAD.store(unsafe { libc::pthread_self() } as usize, Ordering::SeqCst);
+
+        let mut previous_action: libc::sigaction = unsafe { std::mem::zeroed() };
+
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = on_sigprof as usize;
+            action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+
+            libc::sigaction(libc::SIGPROF, &action, &mut previous_action);
+            set_timer(SAMPLE_INTERVAL_MICROS);
+        }
+
+        Sampling { previous_action }
+    }
+
+    /// Print the functions the expect spent the most time in. Expects that were too fast
+    /// to get sampled are skipped.
+    pub(crate) fn report<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        expectations: &VecMap<ModuleId, Expectations>,
+        expect: &ToplevelExpect<'_>,
+        samples: &[usize],
+    ) -> std::io::Result<()> {
+        if samples.is_empty() {
+            return Ok(());
+        }
+
+        let mut counts: MutMap<&str, usize> = MutMap::default();
+
+        for pc in samples {
+            let name = self
+                .function_at(*pc)
+                .unwrap_or("(outside of the Roc program)");
+
+            *counts.entry(name).or_insert(0) += 1;
+        }
+
+        let mut counts: Vec<(&str, usize)> = counts.into_iter().collect();
+        counts.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+
+        writeln!(
+            writer,
+            "\x1B[36mProfile of the expect at {} ({} samples, one per {} ms of CPU time):\x1B[39m\n",
+            expect_location(expectations, expect),
+            samples.len(),
+            SAMPLE_INTERVAL_MICROS / 1000,
+        )?;
+
+        for (name, count) in counts.iter().take(HOT_FUNCTIONS_SHOWN) {
+            let percentage = 100.0 * *count as f64 / samples.len() as f64;
+
+            writeln!(writer, "    {:>5.1}%  {}", percentage, name)?;
+        }
+
+        if counts.len() > HOT_FUNCTIONS_SHOWN {
+            writeln!(
+                writer,
+                "    ... and {} more functions",
+                counts.len() - HOT_FUNCTIONS_SHOWN
+            )?;
+        }
+
+        writeln!(writer)
+    }
+
+    fn function_at(&self, pc: usize) -> Option<&str> {
+        let offset = pc.checked_sub(self.load_address)? as u64;
+
+        let index = self
+            .functions
+            .partition_point(|function| function.start <= offset);
+        let function = &self.functions[index.checked_sub(1)?];
+
+        if function.size == 0 || offset < function.start + function.size {
+            Some(&function.name)
+        } else {
+            None
+        }
+    }
+}
+
+/// Sampling in progress; samples are taken until [Sampling::finish] is called.
+pub(crate) struct Sampling {
+    previous_action: libc::sigaction,
+}
+
+impl Sampling {
+    /// Stop sampling, and return the address of each interrupted instruction.
+    pub(crate) fn finish(self) -> Vec<usize> {
+        unsafe {
+            set_timer(0);
+            libc::sigaction(libc::SIGPROF, &self.previous_action, std::ptr::null_mut());
+        }
+
+        let count = SAMPLE_COUNT.load(Ordering::SeqCst).min(MAX_SAMPLES);
+
+        SAMPLES[..count]
+            .iter()
+            .map(|sample| sample.load(Ordering::Relaxed))
+            .filter(|pc| *pc != 0)
+            .collect()
+    }
+}
+
+/// A timer that sends `SIGPROF` every `interval_micros` of CPU time. Zero disables it.
+unsafe fn set_timer(interval_micros: i64) {
+    let interval = libc::timeval {
+        tv_sec: 0,
+        tv_usec: interval_micros as _,
+    };
+    let timer = libc::itimerval {
+        it_interval: interval,
+        it_value: interval,
+    };
+
+    libc::setitimer(libc::ITIMER_PROF, &timer, std::ptr::null_mut());
+}
+
+extern "C" fn on_sigprof(
+    _signal: libc::c_int,
+    _info: *mut libc::siginfo_t,
+    context: *mut libc::c_void,
+) {
+    // only atomics in here: we may have interrupted anything, including the allocator
+    if unsafe { libc::pthread_self() } as usize != SAMPLED_THREAD.load(Ordering::Relaxed) {
+        return;
+    }
+
+    if let Some(pc) = unsafe { interrupted_pc(context) } {
+        let index = SAMPLE_COUNT.fetch_add(1, Ordering::Relaxed);
+
+        if index < MAX_SAMPLES {
+            SAMPLES[index].store(pc, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+pub(crate) unsafe fn interrupted_pc(context: *mut libc::c_void) -> Option<usize> {
+    let context = &*(context as *const libc::ucontext_t);
+
+    Some(context.uc_mcontext.gregs[libc::REG_RIP as usize] as usize)
+}
+
+#[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+pub(crate) unsafe fn interrupted_pc(context: *mut libc::c_void) -> Option<usize> {
+    let context = &*(context as *const libc::ucontext_t);
+
+    Some(context.uc_mcontext.pc as usize)
+}
+
+#[cfg(all(target_os = "macos", target_arch = "x86_64"))]
+pub(crate) unsafe fn interrupted_pc(context: *mut libc::c_void) -> Option<usize> {
+    let context = &*(context as *const libc::ucontext_t);
+
+    Some((*context.uc_mcontext).__ss.__rip as usize)
+}
+
+#[cfg(all(target_os = "macos", target_arch = "aarch64"))]
+pub(crate) unsafe fn interrupted_pc(context: *mut libc::c_void) -> Option<usize> {
+    let context = &*(context as *const libc::ucontext_t);
+
+    Some((*context.uc_mcontext).__ss.__pc as usize)
+}
+
+#[cfg(not(any(
+    all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ),
+    all(
+        target_os = "macos",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ),
+)))]
+pub(crate) unsafe fn interrupted_pc(_context: *mut libc::c_void) -> Option<usize> {
+    None
+}
+
+/// Roc procedures are named `Module_ident_<specialization>`; show them as `Module.ident`,
+/// so the samples of every specialization of a function add up.
+fn display_name(symbol_name: &str) -> String {
+    let without_specialization = match symbol_name.rsplit_once('_') {
+        Some((rest, specialization))
+            if specialization.len() >= 32
+                && specialization.chars().all(|c| c.is_ascii_hexdigit()) =>
+        {
+            rest
+        }
+        _ => return symbol_name.to_string(),
+    };
+
+    match without_specialization.split_once('_') {
+        Some((module, ident)) => format!("{}.{}", module, ident),
+        None => without_specialization.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::display_name;
+
+    #[test]
+    fn display_name_merges_specializations() {
+        let specialization = "1a".repeat(32);
+
+        assert_eq!(
+            display_name(&format!("UserApp_fib_{}", specialization)),
+            "UserApp.fib"
+        );
+        assert_eq!(
+            display_name(&format!("List_walk_{}", specialization)),
+            "List.walk"
+        );
+    }
+
+    #[test]
+    fn display_name_keeps_other_symbols() {
+        assert_eq!(
+            display_name("roc_builtins.list.sort_with"),
+            "roc_builtins.list.sort_with"
+        );
+        assert_eq!(display_name("memcpy"), "memcpy");
+    }
+}
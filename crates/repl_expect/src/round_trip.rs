@@ -0,0 +1,613 @@
+//! Round trips values through the shared memory buffer that failed expects report the values
+//! they looked up in, to catch mistakes in how those values are written and read back, like
+//! wrong padding or alignment, or a recursive tag union that's followed the wrong way.
+//!
+//! A compiled expect writes the values (see `clone_to_shared_memory` in roc_gen_llvm), and
+//! [crate::get_values] reads them back. [round_trip] puts values in an expect that always fails,
+//! runs it, and returns how the values it read back render. [values_from_bytes] turns any bytes
+//! into values of all kinds of types, so that a fuzzer or proptest can come up with them; the
+//! fuzz target is in `fuzz/`.
+use std::fmt::Write;
+
+use bumpalo::Bump;
+use roc_gen_llvm::llvm::build::LlvmBackendMode;
+use roc_load::{ExecutionMode, LoadConfig, LoadMonomorphizedError, Threading};
+use roc_mono::ir::OptLevel;
+use roc_packaging::cache::RocCacheDir;
+use roc_parse::test_helpers::parse_expr_with;
+use roc_reporting::report::{RenderTarget, DEFAULT_PALETTE};
+use roc_target::TargetInfo;
+use serde_json::Value as Json;
+use target_lexicon::Triple;
+
+use crate::json::FailureFormat;
+use crate::run::{
+    expect_mono_module_to_dylib, run_expects_with_memory, ExpectFilter, ExpectMemory,
+};
+use crate::sandbox::Sandbox;
+use crate::shm;
+
+/// The integer types values can have, with how many bytes they take up.
+const INTS: [(&str, usize); 5] = [("U8", 1), ("I16", 2), ("U32", 4), ("I64", 8), ("I128", 16)];
+
+const FIELD_NAMES: [&str; 6] = ["a", "b", "c", "d", "e", "f"];
+const TAG_NAMES: [&str; 3] = ["Alpha", "Beta", "Gamma"];
+const STR_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz ";
+
+/// How many levels of lists, records, tuples and tags a shape can have.
+const MAX_SHAPE_DEPTH: usize = 3;
+/// How many levels a value of a recursive tag union can have.
+const MAX_RECURSION_DEPTH: usize = 3;
+
+/// The type of a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Shape {
+    /// One of [INTS], by its name
+    Int(&'static str),
+    F64,
+    Str,
+    List(Box<Shape>),
+    /// The fields are sorted by name.
+    Record(Vec<(&'static str, Shape)>),
+    Tuple(Vec<Shape>),
+    /// A tag union that isn't recursive; the tags are sorted by name.
+    Tags(Vec<(&'static str, Vec<Shape>)>),
+    /// `ConsList a : [Cons a (ConsList a), Nil]`, where only one tag has a payload
+    ConsList(Box<Shape>),
+    /// `Tree a : [Leaf, Node (Tree a) a (Tree a)]`
+    Tree(Box<Shape>),
+    /// `Rose a : [Branch a (List (Rose a))]`, which has a single tag
+    Rose(Box<Shape>),
+    /// `Arith : [Add Arith Arith, Lit I64, Neg Arith]`, where several tags have a payload
+    Arith,
+}
+
+/// A value of a [Shape].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i128),
+    F64(f64),
+    Str(String),
+    List(Vec<Value>),
+    Record(Vec<(&'static str, Value)>),
+    Tuple(Vec<Value>),
+    Tag(&'static str, Vec<Value>),
+}
+
+/// The recursive tag unions, which have to be declared before they can be used.
+const RECURSIVE_ALIASES: [(&str, &str); 4] = [
+    ("ConsList ", "ConsList a : [Cons a (ConsList a), Nil]"),
+    ("Tree ", "Tree a : [Leaf, Node (Tree a) a (Tree a)]"),
+    ("Rose ", "Rose a : [Branch a (List (Rose a))]"),
+    ("Arith", "Arith : [Add Arith Arith, Lit I64, Neg Arith]"),
+];
+
+impl Shape {
+    /// The Roc type, in parentheses if it's an argument of another type and needs them.
+    pub fn roc_type(&self, is_argument: bool) -> String {
+        let applied = |name: &str, argument: &Shape| {
+            let applied = format!("{name} {}", argument.roc_type(true));
+
+            match is_argument {
+                true => format!("({applied})"),
+                false => applied,
+            }
+        };
+
+        match self {
+            Shape::Int(name) => name.to_string(),
+            Shape::F64 => "F64".to_string(),
+            Shape::Str => "Str".to_string(),
+            Shape::List(element) => applied("List", element),
+            Shape::Record(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, shape)| format!("{name} : {}", shape.roc_type(false)))
+                    .collect();
+
+                format!("{{ {} }}", fields.join(", "))
+            }
+            Shape::Tuple(elements) => {
+                let elements: Vec<String> = elements
+                    .iter()
+                    .map(|element| element.roc_type(false))
+                    .collect();
+
+                format!("({})", elements.join(", "))
+            }
+            Shape::Tags(tags) => {
+                let tags: Vec<String> = tags
+                    .iter()
+                    .map(|(name, payload)| {
+                        let mut tag = name.to_string();
+
+                        for shape in payload {
+                            write!(tag, " {}", shape.roc_type(true)).unwrap();
+                        }
+
+                        tag
+                    })
+                    .collect();
+
+                format!("[{}]", tags.join(", "))
+            }
+            Shape::ConsList(element) => applied("ConsList", element),
+            Shape::Tree(element) => applied("Tree", element),
+            Shape::Rose(element) => applied("Rose", element),
+            Shape::Arith => "Arith".to_string(),
+        }
+    }
+}
+
+impl Value {
+    /// The Roc literal, in parentheses if it's the argument of a tag and needs them.
+    pub fn roc_literal(&self, is_argument: bool) -> String {
+        let join = |values: &[Value]| {
+            values
+                .iter()
+                .map(|value| value.roc_literal(false))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        match self {
+            Value::Int(int) => int.to_string(),
+            Value::F64(float) => float.to_string(),
+            Value::Str(string) => format!("\"{string}\""),
+            Value::List(elements) => format!("[{}]", join(elements)),
+            Value::Record(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, value)| format!("{name}: {}", value.roc_literal(false)))
+                    .collect();
+
+                format!("{{ {} }}", fields.join(", "))
+            }
+            Value::Tuple(elements) => format!("({})", join(elements)),
+            Value::Tag(name, payload) if payload.is_empty() => name.to_string(),
+            Value::Tag(name, payload) => {
+                let mut tag = name.to_string();
+
+                for value in payload {
+                    write!(tag, " {}", value.roc_literal(true)).unwrap();
+                }
+
+                match is_argument {
+                    true => format!("({tag})"),
+                    false => tag,
+                }
+            }
+        }
+    }
+
+    /// How the value renders when it's read back correctly, which is how its literal renders.
+    pub fn expected_rendering(&self) -> String {
+        let arena = Bump::new();
+        let literal = arena.alloc_str(&self.roc_literal(false));
+
+        match parse_expr_with(&arena, literal) {
+            Ok(expr) => roc_value_render::render(&arena, &expr).to_string(),
+            Err(_) => literal.to_string(),
+        }
+    }
+}
+
+/// Reads the bytes one at a time, and then zeroes once they run out, so that any bytes make
+/// values, and fewer bytes make smaller ones.
+struct Bytes<'b> {
+    rest: &'b [u8],
+}
+
+impl Bytes<'_> {
+    fn next(&mut self) -> u8 {
+        match self.rest.split_first() {
+            Some((byte, rest)) => {
+                self.rest = rest;
+                *byte
+            }
+            None => 0,
+        }
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        self.next() as usize % bound
+    }
+
+    fn int(&mut self, size: usize) -> i128 {
+        let mut bytes = [0; 16];
+
+        for byte in bytes.iter_mut().take(size) {
+            *byte = self.next();
+        }
+
+        let unsigned = u128::from_le_bytes(bytes);
+
+        // the sizes of INTS, the odd ones of which are unsigned
+        match size {
+            1 => unsigned as u8 as i128,
+            2 => unsigned as u16 as i16 as i128,
+            4 => unsigned as u32 as i128,
+            8 => unsigned as u64 as i64 as i128,
+            _ => unsigned as i128,
+        }
+    }
+
+    fn shape(&mut self, depth: usize) -> Shape {
+        let choices = if depth < MAX_SHAPE_DEPTH { 11 } else { 3 };
+
+        match self.below(choices) {
+            0 => Shape::Int(INTS[self.below(INTS.len())].0),
+            1 => Shape::F64,
+            2 => Shape::Str,
+            3 => Shape::List(Box::new(self.shape(depth + 1))),
+            4 => {
+                let mut names: Vec<&str> = FIELD_NAMES
+                    .into_iter()
+                    .filter(|_| self.next() % 2 == 0)
+                    .collect();
+
+                if names.is_empty() {
+                    names.push(FIELD_NAMES[0]);
+                }
+
+                Shape::Record(
+                    names
+                        .into_iter()
+                        .map(|name| (name, self.shape(depth + 1)))
+                        .collect(),
+                )
+            }
+            5 => Shape::Tuple(
+                (0..2 + self.below(2))
+                    .map(|_| self.shape(depth + 1))
+                    .collect(),
+            ),
+            6 => {
+                let tags = TAG_NAMES[..2 + self.below(2)]
+                    .iter()
+                    .map(|name| {
+                        let payload = (0..self.below(3)).map(|_| self.shape(depth + 1)).collect();
+
+                        (*name, payload)
+                    })
+                    .collect();
+
+                Shape::Tags(tags)
+            }
+            7 => Shape::ConsList(Box::new(self.shape(depth + 1))),
+            8 => Shape::Tree(Box::new(self.shape(depth + 1))),
+            9 => Shape::Rose(Box::new(self.shape(depth + 1))),
+            _ => Shape::Arith,
+        }
+    }
+
+    fn value(&mut self, shape: &Shape, depth: usize) -> Value {
+        let recurse = depth < MAX_RECURSION_DEPTH;
+
+        match shape {
+            Shape::Int(name) => {
+                let size = INTS
+                    .iter()
+                    .find(|(int, _)| int == name)
+                    .map_or(8, |int| int.1);
+
+                Value::Int(self.int(size))
+            }
+            // halves, so they're written the same way in Roc as in Rust
+            Shape::F64 => Value::F64(self.int(2) as f64 + 0.5),
+            Shape::Str => {
+                let length = self.below(40);
+                let string = (0..length)
+                    .map(|_| STR_CHARS[self.below(STR_CHARS.len())] as char)
+                    .collect();
+
+                Value::Str(string)
+            }
+            Shape::List(element) => {
+                Value::List((0..self.below(5)).map(|_| self.value(element, 0)).collect())
+            }
+            Shape::Record(fields) => Value::Record(
+                fields
+                    .iter()
+                    .map(|(name, shape)| (*name, self.value(shape, 0)))
+                    .collect(),
+            ),
+            Shape::Tuple(elements) => {
+                Value::Tuple(elements.iter().map(|shape| self.value(shape, 0)).collect())
+            }
+            Shape::Tags(tags) => {
+                let (name, payload) = &tags[self.below(tags.len())];
+
+                Value::Tag(
+                    name,
+                    payload.iter().map(|shape| self.value(shape, 0)).collect(),
+                )
+            }
+            Shape::ConsList(element) => match recurse && self.next() % 4 != 0 {
+                true => Value::Tag(
+                    "Cons",
+                    vec![self.value(element, 0), self.value(shape, depth + 1)],
+                ),
+                false => Value::Tag("Nil", Vec::new()),
+            },
+            Shape::Tree(element) => match recurse && self.next() % 2 == 0 {
+                true => Value::Tag(
+                    "Node",
+                    vec![
+                        self.value(shape, depth + 1),
+                        self.value(element, 0),
+                        self.value(shape, depth + 1),
+                    ],
+                ),
+                false => Value::Tag("Leaf", Vec::new()),
+            },
+            Shape::Rose(element) => {
+                let children = match recurse {
+                    true => (0..self.below(3))
+                        .map(|_| self.value(shape, depth + 1))
+                        .collect(),
+                    false => Vec::new(),
+                };
+
+                Value::Tag(
+                    "Branch",
+                    vec![self.value(element, 0), Value::List(children)],
+                )
+            }
+            Shape::Arith => match (recurse, self.below(3)) {
+                (true, 1) => Value::Tag("Neg", vec![self.value(shape, depth + 1)]),
+                (true, 2) => Value::Tag(
+                    "Add",
+                    vec![self.value(shape, depth + 1), self.value(shape, depth + 1)],
+                ),
+                _ => Value::Tag("Lit", vec![Value::Int(self.int(8))]),
+            },
+        }
+    }
+}
+
+/// Between one and four values, made from `bytes`.
+pub fn values_from_bytes(bytes: &[u8]) -> Vec<(Shape, Value)> {
+    let mut bytes = Bytes { rest: bytes };
+
+    (0..1 + bytes.below(4))
+        .map(|_| {
+            let shape = bytes.shape(0);
+            let value = bytes.value(&shape, 0);
+
+            (shape, value)
+        })
+        .collect()
+}
+
+/// An app with an expect that looks up `values` as `v0`, `v1` and so on, and always fails.
+pub fn expect_source(values: &[(Shape, Value)]) -> String {
+    let mut source = String::from("app \"roundtrip\" provides [main] to \"./platform\"\n\n");
+    let mut defs = String::new();
+
+    for (index, (shape, value)) in values.iter().enumerate() {
+        writeln!(defs, "    v{index} : {}", shape.roc_type(false)).unwrap();
+        writeln!(defs, "    v{index} = {}\n", value.roc_literal(false)).unwrap();
+    }
+
+    // unused aliases would be warned about
+    for (used_as, alias) in RECURSIVE_ALIASES {
+        if defs.contains(used_as) {
+            writeln!(source, "{alias}\n").unwrap();
+        }
+    }
+
+    let arguments: Vec<String> = (0..values.len()).map(|index| format!("v{index}")).collect();
+    let ignored = vec!["_"; values.len()].join(", ");
+
+    writeln!(source, "main = 0\n\nexpect").unwrap();
+    source.push_str(&defs);
+    writeln!(
+        source,
+        "    (\\{ignored} -> Bool.false) {}",
+        arguments.join(" ")
+    )
+    .unwrap();
+
+    source
+}
+
+/// Compiles and runs [expect_source] for `values`, and returns how each value that the failed
+/// expect reported renders, in order. If the expect doesn't compile or crashes, returns why.
+pub fn round_trip(values: &[(Shape, Value)]) -> Result<Vec<String>, String> {
+    let dir = std::env::temp_dir().join(shm::unique_name().trim_start_matches('/'));
+
+    std::fs::create_dir_all(&dir).map_err(|error| error.to_string())?;
+
+    let result = round_trip_in(&dir, values);
+
+    let _ = std::fs::remove_dir_all(&dir);
+
+    result
+}
+
+fn round_trip_in(dir: &std::path::Path, values: &[(Shape, Value)]) -> Result<Vec<String>, String> {
+    let arena = &Bump::new();
+    let source = arena.alloc_str(&expect_source(values));
+    let triple = Triple::host();
+
+    let load_config = LoadConfig {
+        target_info: TargetInfo::from(&triple),
+        render: RenderTarget::Generic,
+        palette: DEFAULT_PALETTE,
+        threading: Threading::Single,
+        exec_mode: ExecutionMode::Test,
+        platform: None,
+        max_type_errors: None,
+        keep_module_types: false,
+    };
+
+    let mut loaded = match roc_load::load_and_monomorphize_from_str(
+        arena,
+        dir.join("RoundTrip.roc"),
+        source,
+        dir.to_path_buf(),
+        RocCacheDir::Disallowed,
+        load_config,
+    ) {
+        Ok(loaded) => loaded,
+        Err(LoadMonomorphizedError::ErrorModule(module)) => {
+            return Err(format!(
+                "This doesn't compile:\n\n{source}\n{:?}",
+                (module.can_problems, module.type_problems)
+            ))
+        }
+        Err(LoadMonomorphizedError::LoadingProblem(problem)) => {
+            return Err(format!("This doesn't load:\n\n{source}\n{problem:?}"))
+        }
+    };
+
+    let mut expectations = std::mem::take(&mut loaded.expectations);
+    let interns = loaded.interns.clone();
+
+    let (lib, expects, layout_interner) = expect_mono_module_to_dylib(
+        arena,
+        triple,
+        loaded,
+        OptLevel::Normal,
+        LlvmBackendMode::CliTest,
+        false,
+        false,
+        None,
+        &ExpectFilter::ALL,
+    )
+    .map_err(|error| error.to_string())?;
+
+    let run_arena = &Bump::new();
+    let interns = run_arena.alloc(interns);
+    let mut memory = ExpectMemory::create_unique();
+    let mut output = Vec::new();
+
+    run_expects_with_memory(
+        &mut output,
+        RenderTarget::Generic,
+        run_arena,
+        interns,
+        &layout_interner.into_global(),
+        &lib,
+        &mut expectations,
+        expects,
+        &mut memory,
+        Sandbox::NONE,
+        None,
+        None,
+        None,
+        FailureFormat::Json,
+    )
+    .map_err(|error| error.to_string())?;
+
+    let output = String::from_utf8_lossy(&output);
+    let records: Vec<Json> = output
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+
+    let failure = records
+        .iter()
+        .find(|record| record["kind"] == "failure")
+        .ok_or_else(|| format!("The expect didn't report a failure, but:\n\n{output}"))?;
+
+    (0..values.len())
+        .map(|index| {
+            let name = format!("v{index}");
+
+            failure["values"]
+                .as_array()
+                .and_then(|reported| reported.iter().find(|value| value["name"] == name))
+                .and_then(|value| value["value"].as_str())
+                .map(str::to_string)
+                .ok_or_else(|| format!("The expect didn't report {name}:\n\n{output}"))
+        })
+        .collect()
+}
+
+/// Checks that every one of `values` renders the same after the [round_trip] as its literal
+/// does, and says which one didn't otherwise.
+pub fn check_round_trip(values: &[(Shape, Value)]) -> Result<(), String> {
+    let rendered = round_trip(values)?;
+
+    for ((shape, value), rendered) in values.iter().zip(rendered) {
+        let expected = value.expected_rendering();
+
+        if rendered != expected {
+            return Err(format!(
+                "A value of type {} went in as\n\n    {expected}\n\nand came out as\n\n    {rendered}\n\nin this module:\n\n{}",
+                shape.roc_type(false),
+                expect_source(values),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn padding_between_fields() {
+        let shape = Shape::Record(vec![
+            ("a", Shape::Int("U8")),
+            ("b", Shape::Int("I128")),
+            ("c", Shape::Int("I16")),
+            ("d", Shape::Str),
+        ]);
+        let value = Value::Record(vec![
+            ("a", Value::Int(255)),
+            ("b", Value::Int(-170141183460469231731687303715884105727)),
+            ("c", Value::Int(-2)),
+            (
+                "d",
+                Value::Str("long enough to not be a small string".to_string()),
+            ),
+        ]);
+
+        if let Err(message) = check_round_trip(&[(shape, value)]) {
+            panic!("{message}");
+        }
+    }
+
+    #[test]
+    fn recursive_unions() {
+        let arith = Value::Tag(
+            "Add",
+            vec![
+                Value::Tag("Lit", vec![Value::Int(1)]),
+                Value::Tag("Neg", vec![Value::Tag("Lit", vec![Value::Int(2)])]),
+            ],
+        );
+        let list = Value::Tag(
+            "Cons",
+            vec![
+                Value::Tuple(vec![Value::Int(7), Value::Str("x".to_string())]),
+                Value::Tag("Nil", Vec::new()),
+            ],
+        );
+
+        let list_shape =
+            Shape::ConsList(Box::new(Shape::Tuple(vec![Shape::Int("U32"), Shape::Str])));
+
+        if let Err(message) = check_round_trip(&[(Shape::Arith, arith), (list_shape, list)]) {
+            panic!("{message}");
+        }
+    }
+
+    proptest! {
+        // every case compiles a module, so there can't be too many
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn values_survive_the_round_trip(bytes in proptest::collection::vec(any::<u8>(), 0..256)) {
+            if let Err(message) = check_round_trip(&values_from_bytes(&bytes)) {
+                panic!("{message}");
+            }
+        }
+    }
+}
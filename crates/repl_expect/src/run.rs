@@ -1,20 +1,26 @@
 use std::{
     os::unix::process::parent_id,
     sync::{
-        atomic::{AtomicBool, AtomicU32},
-        Arc,
+        atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicUsize, Ordering},
+        mpsc, Arc,
     },
+    time::{Duration, Instant},
 };
 
 use bumpalo::collections::Vec as BumpVec;
 use bumpalo::Bump;
 use inkwell::context::Context;
-use roc_build::link::llvm_module_to_dylib;
-use roc_can::expr::ExpectLookup;
-use roc_collections::{MutSet, VecMap};
+use roc_build::link::{llvm_module_to_dylib, llvm_module_to_dylib_and_bytes, llvm_module_to_wasm};
+use roc_collections::{MutMap, MutSet, VecMap};
 use roc_error_macros::internal_error;
 use roc_gen_llvm::{
-    llvm::{build::LlvmBackendMode, externs::add_default_roc_externs},
+    llvm::{
+        build::LlvmBackendMode,
+        externs::{
+            add_coverage_counters, add_default_roc_externs, add_exported_crash, add_memory_limit,
+            EXPECT_CALL_SITES_GLOBAL,
+        },
+    },
     run_roc::RocCallResult,
     run_roc_dylib,
 };
@@ -24,46 +30,93 @@ use roc_mono::{
     ir::OptLevel,
     layout::{GlobalLayoutInterner, STLayoutInterner},
 };
-use roc_region::all::Region;
-use roc_reporting::{error::expect::Renderer, report::RenderTarget};
+use roc_region::all::{LineInfo, Region};
+use roc_reporting::{
+    coverage::CoverageSummary,
+    error::expect::{CallSite, Renderer, ValueLimits},
+    report::RenderTarget,
+};
 use roc_target::TargetInfo;
-use roc_types::subs::Subs;
+use std::path::{Path, PathBuf};
 use target_lexicon::Triple;
 
+use crate::child_exit::{ChildExit, Note, NotePipe};
+use crate::coverage::Coverage;
+use crate::effect_trace::EffectCall;
+use crate::frame_dump::{DecodedFrame, FrameDump};
+use crate::json::{self, FailureFormat, Location};
+use crate::junit::{Tee, TestCase};
+use crate::memory_limit::MemoryLimit;
+use crate::profile::Profiler;
+use crate::sandbox::{self, Sandbox, SandboxDir};
+use crate::shm;
+use crate::tap;
+use crate::timeout::ExpectTimeout;
+use crate::times::ExpectTime;
+
 pub struct ExpectMemory<'a> {
     ptr: *mut u8,
     length: usize,
+    shm_fd: i32,
     shm_name: Option<std::ffi::CString>,
+    /// Whether this process created the shared memory object, and removes it when this
+    /// is dropped.
+    owned: bool,
     _marker: std::marker::PhantomData<&'a ()>,
 }
 
 impl<'a> ExpectMemory<'a> {
+    /// The size the shared memory object starts out with. It grows when an expect
+    /// needs more room to show its values.
     const SHM_SIZE: usize = 1024;
 
-    #[cfg(test)]
-    pub(crate) fn from_slice(slice: &mut [u8]) -> Self {
-        Self {
-            ptr: slice.as_mut_ptr(),
-            length: slice.len(),
-            shm_name: None,
-            _marker: std::marker::PhantomData,
-        }
-    }
+    /// How big the shared memory object can get. This much address space is mapped up front,
+    /// so the buffer never moves when the object behind it grows.
+    /// Must match SHARED_BUFFER_MAX_LENGTH in expect.zig.
+    const SHM_MAX_SIZE: usize = 1 << 30;
 
+    /// The buffer with this name, which is created if it doesn't exist. Either way, it's
+    /// removed when this is dropped.
     pub fn create_or_reuse_mmap(shm_name: &str) -> Self {
         let cstring = std::ffi::CString::new(shm_name).unwrap();
-        Self::mmap_help(cstring, libc::O_RDWR | libc::O_CREAT)
+        let mut memory = Self::mmap_help(cstring, libc::O_RDWR | libc::O_CREAT).unwrap();
+        memory.owned = true;
+
+        memory
     }
 
+    /// A new buffer, with a name that no other buffer has, which is removed when this is dropped.
+    pub fn create_unique() -> Self {
+        loop {
+            let cstring = std::ffi::CString::new(shm::unique_name()).unwrap();
+            let flags = libc::O_RDWR | libc::O_CREAT | libc::O_EXCL;
+
+            // a process that was killed can have left a buffer with that name behind
+            if let Some(mut memory) = Self::mmap_help(cstring, flags) {
+                memory.owned = true;
+
+                return memory;
+            }
+        }
+    }
+
+    /// The same buffer, mapped again, e.g. by the child that runs an effectful expect.
     fn reuse_mmap(&mut self) -> Option<Self> {
         let shm_name = self.shm_name.as_ref()?.clone();
-        Some(Self::mmap_help(shm_name, libc::O_RDWR))
+        Self::mmap_help(shm_name, libc::O_RDWR)
     }
 
-    fn mmap_help(cstring: std::ffi::CString, shm_flags: i32) -> Self {
-        let ptr = unsafe {
+    /// Returns `None` if `O_EXCL` is in `shm_flags` and the object already exists.
+    fn mmap_help(cstring: std::ffi::CString, shm_flags: i32) -> Option<Self> {
+        let (ptr, shm_fd, length) = unsafe {
             let shared_fd = libc::shm_open(cstring.as_ptr().cast(), shm_flags, 0o666);
             if shared_fd == -1 {
+                let exists = std::io::Error::last_os_error().raw_os_error() == Some(libc::EEXIST);
+
+                if exists && shm_flags & libc::O_EXCL != 0 {
+                    return None;
+                }
+
                 internal_error!("failed to shm_open fd");
             }
 
@@ -77,9 +130,12 @@ impl<'a> ExpectMemory<'a> {
                 internal_error!("failed to truncate shared file, are the permissions wrong?");
             }
 
+            // an earlier expect may have grown it already
+            let length = Self::SHM_SIZE.max(stat.st_size as usize);
+
             let ptr = libc::mmap(
                 std::ptr::null_mut(),
-                Self::SHM_SIZE,
+                Self::SHM_MAX_SIZE,
                 libc::PROT_WRITE | libc::PROT_READ,
                 libc::MAP_SHARED,
                 shared_fd,
@@ -92,26 +148,54 @@ impl<'a> ExpectMemory<'a> {
             }
 
             // fill the buffer with a fill pattern
-            libc::memset(ptr, 0xAA, Self::SHM_SIZE);
+            libc::memset(ptr, 0xAA, length);
 
-            ptr
+            (ptr, shared_fd, length)
         };
 
         // puts in the initial header
-        let _ = ExpectSequence::new(ptr as *mut u8);
+        let mut sequence = ExpectSequence::new(ptr as *mut u8);
+        sequence.set_capacity(length);
 
-        Self {
+        Some(Self {
             ptr: ptr.cast(),
-            length: Self::SHM_SIZE,
+            length,
+            shm_fd,
             shm_name: Some(cstring),
+            owned: false,
             _marker: std::marker::PhantomData,
-        }
+        })
     }
 
     fn set_shared_buffer(&mut self, lib: &libloading::Library) {
         let set_shared_buffer = run_roc_dylib!(lib, "set_shared_buffer", (*mut u8, usize), ());
         let mut result = RocCallResult::default();
         unsafe { set_shared_buffer((self.ptr, self.length), &mut result) };
+
+        // expects run in this process, so we grow the buffer when they ask, instead of a parent
+        let set_shared_buffer_grow =
+            run_roc_dylib!(lib, "set_shared_buffer_grow", (GrowSharedBuffer, i32), ());
+        let mut result = RocCallResult::default();
+        unsafe { set_shared_buffer_grow((grow_shared_buffer, self.shm_fd), &mut result) };
+
+        // for the same reason, nobody waits for a notification about each frame
+        let set_shared_buffer_frame_written =
+            run_roc_dylib!(lib, "set_shared_buffer_frame_written", FrameWritten, ());
+        let mut result = RocCallResult::default();
+        unsafe { set_shared_buffer_frame_written(frame_written, &mut result) };
+    }
+
+    /// Grows the shared memory object after the child sent [ChildProcessMsg::BufferFull],
+    /// and lets the child continue writing its frame.
+    pub fn grow(&mut self) {
+        let sequence = ExpectSequence { ptr: self.ptr };
+
+        // the child put the size it needs where the capacity goes
+        let needed = sequence.capacity();
+        self.length =
+            unsafe { grow_shared_buffer_help(self.shm_fd, self.ptr, self.length, needed) };
+
+        sequence.clear_message();
     }
 
     pub fn wait_for_child(&self, sigchld: Arc<AtomicBool>) -> ChildProcessMsg {
@@ -123,6 +207,139 @@ impl<'a> ExpectMemory<'a> {
         let mut sequence = ExpectSequence { ptr: self.ptr };
         sequence.reset();
     }
+
+    /// The effect the child is about to run, after it sent [ChildProcessMsg::EffectCalled].
+    pub fn effect_call(&self) -> EffectCall {
+        let bytes = unsafe { std::slice::from_raw_parts(self.ptr, self.length) };
+
+        EffectCall::decode(&bytes[ExpectSequence::START_OFFSET..])
+    }
+
+    /// Remove the shared memory object, so it doesn't outlive this process.
+    /// The memory stays mapped until this is dropped.
+    fn unlink(&self) {
+        if let Some(shm_name) = &self.shm_name {
+            unsafe { libc::shm_unlink(shm_name.as_ptr()) };
+        }
+    }
+}
+
+impl Drop for ExpectMemory<'_> {
+    fn drop(&mut self) {
+        // Otherwise a long-lived process (like `roc test --watch`) would run out of file
+        // descriptors and address space after enough runs.
+        unsafe {
+            libc::munmap(self.ptr.cast(), Self::SHM_MAX_SIZE);
+            libc::close(self.shm_fd);
+        }
+
+        // Otherwise the objects pile up in /dev/shm. A process that gets killed can't get
+        // here, so `roc test --clean-shm` removes the ones it leaves behind.
+        if self.owned {
+            self.unlink();
+        }
+    }
+}
+
+type GrowSharedBuffer = extern "C" fn(i32, *mut u8, usize);
+type FrameWritten = extern "C" fn(*mut u8, u32);
+
+/// A frame that an expect running in this process wrote to the shared buffer.
+#[derive(Debug, Clone, Copy)]
+struct WrittenFrame {
+    is_dbg: bool,
+    start: usize,
+}
+
+/// The frames written by the expect running on this thread, see [frame_written].
+struct FrameLog {
+    frames: Vec<WrittenFrame>,
+    /// Where the next frame starts.
+    end: usize,
+    /// The parent of an effectful expect only reads the first frame, which has to be the one of
+    /// a failed expect, so a child drops its dbg frames.
+    keep_dbg: bool,
+}
+
+thread_local! {
+    static FRAME_LOG: std::cell::RefCell<Option<FrameLog>> = std::cell::RefCell::new(None);
+}
+
+impl FrameLog {
+    /// Starts logging the frames of an expect about to run on this thread.
+    fn start(keep_dbg: bool) {
+        let log = FrameLog {
+            frames: Vec::new(),
+            end: ExpectSequence::START_OFFSET,
+            keep_dbg,
+        };
+
+        FRAME_LOG.with(|cell| *cell.borrow_mut() = Some(log));
+    }
+
+    /// The frames written since [FrameLog::start], in the order they were written.
+    fn finish() -> Vec<WrittenFrame> {
+        FRAME_LOG.with(|cell| {
+            cell.borrow_mut()
+                .take()
+                .map(|log| log.frames)
+                .unwrap_or_default()
+        })
+    }
+}
+
+/// Called by expects running in this process right after they wrote an expect or dbg frame.
+/// Frames don't say what they are, or where they end, so we keep track of that here.
+extern "C" fn frame_written(ptr: *mut u8, tag: u32) {
+    let mut sequence = ExpectSequence { ptr };
+    let is_dbg = tag == ChildProcessMsg::Dbg as u32;
+
+    FRAME_LOG.with(|cell| {
+        if let Some(log) = cell.borrow_mut().as_mut() {
+            if is_dbg && !log.keep_dbg {
+                sequence.rewind(log.frames.len(), log.end);
+            } else {
+                log.frames.push(WrittenFrame {
+                    is_dbg,
+                    start: log.end,
+                });
+                log.end = sequence.next_offset();
+            }
+        }
+    });
+}
+
+/// Called by expects running in this process when the frame they write would not fit.
+extern "C" fn grow_shared_buffer(shm_fd: i32, ptr: *mut u8, needed: usize) {
+    let current = ExpectSequence { ptr }.capacity();
+
+    unsafe { grow_shared_buffer_help(shm_fd, ptr, current, needed) };
+}
+
+/// Grows the shared memory object from `current` bytes to fit `needed` bytes, if it can, and
+/// records its new size in the header of the buffer. Returns that size; if it is still too small,
+/// the expect crashes.
+unsafe fn grow_shared_buffer_help(
+    shm_fd: i32,
+    ptr: *mut u8,
+    current: usize,
+    needed: usize,
+) -> usize {
+    let mut sequence = ExpectSequence { ptr };
+
+    let wanted = needed.max(2 * current).next_power_of_two();
+    let capacity = wanted.min(ExpectMemory::SHM_MAX_SIZE);
+
+    // This fails on macOS once the object has a size; then the expect crashes instead.
+    if needed > capacity || libc::ftruncate(shm_fd, capacity as _) == -1 {
+        sequence.set_capacity(current);
+
+        return current;
+    }
+
+    sequence.set_capacity(capacity);
+
+    capacity
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -136,8 +353,7 @@ pub fn run_inline_expects<'a, W: std::io::Write>(
     expectations: &mut VecMap<ModuleId, Expectations>,
     expects: ExpectFunctions<'_>,
 ) -> std::io::Result<(usize, usize)> {
-    let shm_name = format!("/roc_expect_buffer_{}", std::process::id());
-    let mut memory = ExpectMemory::create_or_reuse_mmap(&shm_name);
+    let mut memory = ExpectMemory::create_unique();
 
     run_expects_with_memory(
         writer,
@@ -149,10 +365,38 @@ pub fn run_inline_expects<'a, W: std::io::Write>(
         expectations,
         expects,
         &mut memory,
+        Sandbox::NONE,
+        None,
+        None,
+        None,
+        FailureFormat::Human,
     )
+    .map(|results| (results.failed, results.passed))
 }
 
 #[allow(clippy::too_many_arguments)]
+/// What happened when running the top-level expects of a program.
+#[derive(Debug, Default)]
+pub struct ExpectResults {
+    pub failed: usize,
+    pub passed: usize,
+    /// Where each failed expect is, so runs of the same program can be compared
+    /// (symbols are not stable between separate loads of a program, but paths and regions are).
+    pub failures: Vec<(std::path::PathBuf, Region)>,
+    /// How many expects were not run because they didn't match the [ExpectFilter].
+    pub filtered_out: usize,
+    /// How many expects were not run because [ExpectFunctions::max_failures] of the others
+    /// had already failed.
+    pub skipped: usize,
+    /// Every expect that ran, in order; only collected for [FailureFormat::Junit] and
+    /// [FailureFormat::Tap].
+    pub cases: Vec<TestCase>,
+    /// Which exposed definitions the expects used, if they were built to count that.
+    pub coverage: Option<CoverageSummary>,
+    /// How long each expect that ran took, in the order they ran in.
+    pub times: Vec<ExpectTime>,
+}
+
 pub fn run_toplevel_expects<'a, W: std::io::Write>(
     writer: &mut W,
     render_target: RenderTarget,
@@ -162,11 +406,17 @@ pub fn run_toplevel_expects<'a, W: std::io::Write>(
     lib: &libloading::Library,
     expectations: &mut VecMap<ModuleId, Expectations>,
     expects: ExpectFunctions<'_>,
-) -> std::io::Result<(usize, usize)> {
-    let shm_name = format!("/roc_expect_buffer_{}", std::process::id());
-    let mut memory = ExpectMemory::create_or_reuse_mmap(&shm_name);
+    sandbox: Sandbox,
+    timeout: Option<Duration>,
+    fx_timeout: Option<Duration>,
+    frame_dump: Option<&FrameDump>,
+    format: FailureFormat,
+) -> std::io::Result<ExpectResults> {
+    let mut memory = ExpectMemory::create_unique();
 
-    run_expects_with_memory(
+    let interrupt_handler = InterruptHandler::install(memory.shm_name.clone().unwrap());
+
+    let result = run_expects_with_memory(
         writer,
         render_target,
         arena,
@@ -176,7 +426,128 @@ pub fn run_toplevel_expects<'a, W: std::io::Write>(
         expectations,
         expects,
         &mut memory,
-    )
+        sandbox,
+        timeout,
+        fx_timeout,
+        frame_dump,
+        format,
+    );
+
+    interrupt_handler.uninstall();
+
+    result
+}
+
+/// How far the current test run got, so an interrupted run can still report partial results.
+static EXPECTS_PASSED: AtomicUsize = AtomicUsize::new(0);
+static EXPECTS_FAILED: AtomicUsize = AtomicUsize::new(0);
+/// The forked child running an effectful expect, or 0 if there is none. The child leads a
+/// process group of its own, which also contains every process its effects started.
+static FX_CHILD_PID: AtomicI32 = AtomicI32::new(0);
+
+/// Cleans up after `roc test` when it gets interrupted (e.g. by Ctrl-C): kills the child
+/// running an effectful expect (and everything it started), removes the shared memory buffer,
+/// and reports how far we got.
+struct InterruptHandler {
+    handle: signal_hook::iterator::Handle,
+    thread: std::thread::JoinHandle<()>,
+}
+
+impl InterruptHandler {
+    fn install(shm_name: std::ffi::CString) -> Self {
+        use signal_hook::consts::signal::{SIGHUP, SIGINT, SIGTERM};
+        use signal_hook::iterator::Signals;
+
+        EXPECTS_PASSED.store(0, Ordering::SeqCst);
+        EXPECTS_FAILED.store(0, Ordering::SeqCst);
+
+        let mut signals = Signals::new([SIGINT, SIGTERM, SIGHUP]).unwrap();
+        let handle = signals.handle();
+
+        let thread = std::thread::spawn(move || {
+            // this only ends without a signal when the handle is closed
+            if let Some(signal) = signals.forever().next() {
+                exit_interrupted(signal, &shm_name);
+            }
+        });
+
+        Self { handle, thread }
+    }
+
+    fn uninstall(self) {
+        self.handle.close();
+        let _ = self.thread.join();
+    }
+}
+
+fn exit_interrupted(signal: i32, shm_name: &std::ffi::CStr) -> ! {
+    let child_pid = FX_CHILD_PID.load(Ordering::SeqCst);
+
+    if child_pid > 0 {
+        unsafe { libc::kill(-child_pid, libc::SIGKILL) };
+    }
+
+    unsafe { libc::shm_unlink(shm_name.as_ptr()) };
+    let _ = std::fs::remove_dir_all(SandboxDir::default_path());
+
+    let failed = EXPECTS_FAILED.load(Ordering::SeqCst);
+    let passed = EXPECTS_PASSED.load(Ordering::SeqCst);
+
+    // reset the terminal colors, in case we got interrupted while rendering a report
+    println!(
+        "\x1B[39m\x1B[0m\n\nInterrupted! \x1B[31m{failed}\x1B[39m failed and \x1B[32m{passed}\x1B[39m passed before that.\n"
+    );
+
+    std::process::exit(128 + signal);
+}
+
+/// Whether our process group is in the foreground of the terminal on stdin, so that Ctrl-C
+/// and reading from the terminal go to us.
+fn has_terminal() -> bool {
+    unsafe {
+        libc::isatty(libc::STDIN_FILENO) == 1
+            && libc::tcgetpgrp(libc::STDIN_FILENO) == libc::getpgrp()
+    }
+}
+
+/// Make `pgid` the foreground process group of the terminal on stdin.
+fn give_terminal_to(pgid: libc::pid_t) {
+    unsafe {
+        // a background process group gets SIGTTOU for doing this
+        let previous = libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+        libc::tcsetpgrp(libc::STDIN_FILENO, pgid);
+        libc::signal(libc::SIGTTOU, previous);
+    }
+}
+
+/// Kills the process group of an effectful expect when it runs for longer than the timeout.
+struct Watchdog {
+    done: mpsc::Sender<()>,
+    thread: std::thread::JoinHandle<bool>,
+}
+
+impl Watchdog {
+    fn start(pgid: libc::pid_t, timeout: Duration) -> Self {
+        let (done, finished) = mpsc::channel();
+
+        let thread = std::thread::spawn(move || match finished.recv_timeout(timeout) {
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                unsafe { libc::kill(-pgid, libc::SIGKILL) };
+
+                true
+            }
+            _ => false,
+        });
+
+        Self { done, thread }
+    }
+
+    /// Returns whether the expect timed out.
+    fn stop(self) -> bool {
+        let _ = self.done.send(());
+
+        self.thread.join().unwrap_or(false)
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -190,13 +561,49 @@ pub(crate) fn run_expects_with_memory<'a, W: std::io::Write>(
     expectations: &mut VecMap<ModuleId, Expectations>,
     expects: ExpectFunctions<'_>,
     memory: &mut ExpectMemory,
-) -> std::io::Result<(usize, usize)> {
-    let mut failed = 0;
-    let mut passed = 0;
+    sandbox: Sandbox,
+    timeout: Option<Duration>,
+    fx_timeout: Option<Duration>,
+    frame_dump: Option<&FrameDump>,
+    format: FailureFormat,
+) -> std::io::Result<ExpectResults> {
+    let mut results = ExpectResults {
+        filtered_out: expects.filtered_out,
+        ..ExpectResults::default()
+    };
+
+    let sandbox_dir = match sandbox.temp_dir_only && !expects.fx.is_empty() {
+        true => Some(SandboxDir::create()?),
+        false => None,
+    };
+
+    let profiler = expects.profiler.as_ref();
+
+    let memory_limit = MemoryLimit::new(lib);
+
+    // before any child is forked, so the children count in the same place
+    if let Some(coverage) = expects.coverage.as_ref() {
+        coverage.attach(lib);
+    }
 
     for expect in expects.fx {
+        if results.should_stop(expects.max_failures) {
+            results.skipped += 1;
+            continue;
+        }
+
+        let start = Instant::now();
+        let mut tee = Tee::new(writer, format);
+
+        reset_call_sites(lib);
+
+        // the child starts out with our counts
+        if let Some(memory_limit) = memory_limit.as_ref() {
+            memory_limit.reset();
+        }
+
         let result = run_expect_fx(
-            writer,
+            &mut tee,
             render_target,
             arena,
             interns,
@@ -205,19 +612,42 @@ pub(crate) fn run_expects_with_memory<'a, W: std::io::Write>(
             expectations,
             memory,
             expect,
+            sandbox,
+            sandbox_dir.as_ref().map(SandboxDir::path),
+            fx_timeout,
+            frame_dump,
+            profiler,
+            expects.value_limits,
+            format,
         )?;
 
-        match result {
-            true => passed += 1,
-            false => failed += 1,
+        let time = start.elapsed();
+
+        results.record(expectations, &expect, result, time);
+        results.record_case(interns, expectations, &expect, result, time, tee);
+
+        if format == FailureFormat::Tap {
+            tap::write_test_point(writer, &results.cases)?;
         }
     }
 
     memory.set_shared_buffer(lib);
 
+    let timeout = timeout.and_then(|duration| ExpectTimeout::new(lib, duration));
+
     for expect in expects.pure {
+        if results.should_stop(expects.max_failures) {
+            results.skipped += 1;
+            continue;
+        }
+
+        let start = Instant::now();
+        let mut tee = Tee::new(writer, format);
+
+        reset_call_sites(lib);
+
         let result = run_expect_pure(
-            writer,
+            &mut tee,
             render_target,
             arena,
             interns,
@@ -226,15 +656,96 @@ pub(crate) fn run_expects_with_memory<'a, W: std::io::Write>(
             expectations,
             memory,
             expect,
+            timeout.as_ref(),
+            memory_limit.as_ref(),
+            frame_dump,
+            profiler,
+            expects.value_limits,
+            format,
         )?;
 
-        match result {
-            true => passed += 1,
-            false => failed += 1,
+        let time = start.elapsed();
+
+        results.record(expectations, &expect, result, time);
+        results.record_case(interns, expectations, &expect, result, time, tee);
+
+        if format == FailureFormat::Tap {
+            tap::write_test_point(writer, &results.cases)?;
+        }
+    }
+
+    results.coverage = expects.coverage.as_ref().map(Coverage::summary);
+
+    Ok(results)
+}
+
+/// Forgets the calls that the previous expect was in the middle of, if it crashed.
+/// See [EXPECT_CALL_SITES_GLOBAL].
+fn reset_call_sites(lib: &libloading::Library) {
+    unsafe {
+        if let Ok(call_sites) = lib.get::<*mut u32>(EXPECT_CALL_SITES_GLOBAL.as_bytes()) {
+            **call_sites = 0;
+        }
+    }
+}
+
+impl ExpectResults {
+    pub(crate) fn record(
+        &mut self,
+        expectations: &VecMap<ModuleId, Expectations>,
+        expect: &ToplevelExpect<'_>,
+        passed: bool,
+        time: Duration,
+    ) {
+        if let Some(data) = expectations.get(&expect.symbol.module_id()) {
+            self.times.push(ExpectTime {
+                path: data.path.clone(),
+                region: expect.region,
+                time,
+            });
+        }
+
+        if passed {
+            self.passed += 1;
+        } else {
+            self.failed += 1;
+
+            if let Some(data) = expectations.get(&expect.symbol.module_id()) {
+                self.failures.push((data.path.clone(), expect.region));
+            }
         }
+
+        EXPECTS_PASSED.store(self.passed, Ordering::SeqCst);
+        EXPECTS_FAILED.store(self.failed, Ordering::SeqCst);
+    }
+
+    /// Whether enough expects failed that the rest should not run.
+    pub(crate) fn should_stop(&self, max_failures: Option<usize>) -> bool {
+        max_failures.map_or(false, |max_failures| self.failed >= max_failures)
     }
 
-    Ok((failed, passed))
+    /// Adds the JUnit test case of an expect that took `time` to run, if `tee` kept a copy of
+    /// what was printed about it.
+    pub(crate) fn record_case<W: std::io::Write>(
+        &mut self,
+        interns: &Interns,
+        expectations: &VecMap<ModuleId, Expectations>,
+        expect: &ToplevelExpect<'_>,
+        passed: bool,
+        time: Duration,
+        tee: Tee<'_, W>,
+    ) {
+        if let Some(output) = tee.into_copy() {
+            self.cases.push(TestCase::new(
+                interns,
+                expectations,
+                expect,
+                passed,
+                time,
+                &output,
+            ));
+        }
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -248,32 +759,80 @@ fn run_expect_pure<'a, W: std::io::Write>(
     expectations: &mut VecMap<ModuleId, Expectations>,
     shared_memory: &mut ExpectMemory,
     expect: ToplevelExpect<'_>,
+    timeout: Option<&ExpectTimeout>,
+    memory_limit: Option<&MemoryLimit>,
+    frame_dump: Option<&FrameDump>,
+    profiler: Option<&Profiler>,
+    value_limits: ValueLimits,
+    format: FailureFormat,
 ) -> std::io::Result<bool> {
     use roc_gen_llvm::try_run_jit_function;
 
-    let sequence = ExpectSequence::new(shared_memory.ptr.cast());
+    // forget the frames of the previous expect
+    ExpectSequence::new(shared_memory.ptr.cast());
+
+    // every expect gets the whole limit
+    if let Some(memory_limit) = memory_limit {
+        memory_limit.reset();
+    }
+
+    let sampling = profiler.map(Profiler::start);
+
+    FrameLog::start(true);
+
+    let timer = timeout.map(ExpectTimeout::start);
 
     let result: Result<(), (String, _)> = try_run_jit_function!(lib, expect.name, (), |v: ()| v);
 
+    let timed_out = match (timeout, timer) {
+        (Some(timeout), Some(timer)) if timer.stop() => Some(timeout.duration),
+        _ => None,
+    };
+
+    let frames = FrameLog::finish();
+
+    if let (Some(profiler), Some(sampling)) = (profiler, sampling) {
+        profiler.report(writer, expectations, &expect, &sampling.finish())?;
+    }
+
     let shared_memory_ptr: *const u8 = shared_memory.ptr.cast();
 
-    if result.is_err() || sequence.count_failures() > 0 {
+    if result.is_err() || frames.iter().any(|frame| !frame.is_dbg) {
         let module_id = expect.symbol.module_id();
         let data = expectations.get_mut(&module_id).unwrap();
 
-        let path = &data.path;
-        let filename = data.path.to_owned();
-        let source = std::fs::read_to_string(path).unwrap();
+        let path = data.path.to_owned();
+        let source = std::fs::read_to_string(&path).unwrap();
 
-        let renderer = Renderer::new(arena, interns, render_target, module_id, filename, &source);
+        let mut renderer = Renderer::new(
+            arena,
+            interns,
+            render_target,
+            module_id,
+            path.clone(),
+            &source,
+        );
 
-        if let Err((roc_panic_message, _roc_panic_tag)) = result {
-            renderer.render_panic(writer, &roc_panic_message, expect.region)?;
-        } else {
-            let mut offset = ExpectSequence::START_OFFSET;
+        renderer.set_value_limits(value_limits);
 
-            for _ in 0..sequence.count_failures() {
-                offset += render_expect_failure(
+        // Show what the expect printed with `dbg` along with its failures, in the order it
+        // happened, so it's clear which values led up to each failure (or to a crash).
+        for frame in frames {
+            if frame.is_dbg {
+                render_dbg_failure(
+                    writer,
+                    render_target,
+                    arena,
+                    expectations,
+                    interns,
+                    layout_interner,
+                    shared_memory_ptr,
+                    frame.start,
+                    value_limits,
+                    format,
+                )?;
+            } else {
+                render_expect_failure(
                     writer,
                     &renderer,
                     arena,
@@ -281,13 +840,52 @@ fn run_expect_pure<'a, W: std::io::Write>(
                     expectations,
                     interns,
                     layout_interner,
+                    TargetInfo::from(&Triple::host()),
                     shared_memory_ptr,
-                    offset,
+                    frame.start,
+                    frame_dump,
+                    format,
                 )?;
             }
         }
 
-        writeln!(writer)?;
+        let location = Location {
+            module_id,
+            path: &path,
+            source: &source,
+            region: expect.region,
+        };
+
+        if let Some(duration) = timed_out {
+            match format {
+                FailureFormat::Human | FailureFormat::Junit | FailureFormat::Tap => {
+                    renderer.render_timeout(writer, duration, expect.region)?
+                }
+                FailureFormat::Json => {
+                    let message =
+                        format!("The expect timed out after {} ms.", duration.as_millis());
+
+                    json::write_crash(writer, interns, location, &message)?
+                }
+            }
+        } else if let Err((roc_panic_message, _roc_panic_tag)) = result {
+            let roc_panic_message = memory_limit
+                .and_then(|memory_limit| memory_limit.explain(&roc_panic_message, "expect"))
+                .unwrap_or(roc_panic_message);
+
+            match format {
+                FailureFormat::Human | FailureFormat::Junit | FailureFormat::Tap => {
+                    renderer.render_panic(writer, &roc_panic_message, expect.region)?
+                }
+                FailureFormat::Json => {
+                    json::write_crash(writer, interns, location, &roc_panic_message)?
+                }
+            }
+        }
+
+        if format.is_human() {
+            writeln!(writer)?;
+        }
 
         Ok(false)
     } else {
@@ -306,28 +904,70 @@ fn run_expect_fx<'a, W: std::io::Write>(
     expectations: &mut VecMap<ModuleId, Expectations>,
     parent_memory: &mut ExpectMemory,
     expect: ToplevelExpect<'_>,
+    sandbox: Sandbox,
+    sandbox_dir: Option<&Path>,
+    timeout: Option<Duration>,
+    frame_dump: Option<&FrameDump>,
+    profiler: Option<&Profiler>,
+    value_limits: ValueLimits,
+    format: FailureFormat,
 ) -> std::io::Result<bool> {
     use signal_hook::{consts::signal::SIGCHLD, consts::signal::SIGUSR1, iterator::Signals};
 
     let mut signals = Signals::new([SIGCHLD, SIGUSR1]).unwrap();
 
+    let had_terminal = has_terminal();
+
+    let notes = NotePipe::create()?;
+
     match unsafe { libc::fork() } {
         0 => unsafe {
             // we are the child
 
             use roc_gen_llvm::try_run_jit_function;
 
+            // Lead a process group of our own, so that the processes our effects start can be
+            // killed along with us. Our parent does the same, so there is no window in which a
+            // kill of the group misses us.
+            libc::setpgid(0, 0);
+
+            // Ctrl-C and reading from the terminal now go to this expect
+            if had_terminal {
+                give_terminal_to(libc::getpgrp());
+            }
+
+            if sandbox.is_active() {
+                if let Err(message) = sandbox::enter(sandbox, sandbox_dir) {
+                    // fail the expect instead of running it without the restrictions asked for
+                    notes.send(&Note::SandboxFailed(message));
+
+                    std::process::exit(1)
+                }
+            }
+
             let mut child_memory = parent_memory.reuse_mmap().unwrap();
 
             let sequence = ExpectSequence::new(child_memory.ptr);
 
             child_memory.set_shared_buffer(lib);
 
+            FrameLog::start(false);
+
+            // interval timers are not inherited by `fork`, so the child samples itself
+            let sampling = profiler.map(Profiler::start);
+
             let result: Result<(), (String, _)> =
                 try_run_jit_function!(lib, expect.name, (), |v: ()| v);
 
+            if let (Some(profiler), Some(sampling)) = (profiler, sampling) {
+                let _ = profiler.report(writer, expectations, &expect, &sampling.finish());
+                let _ = writer.flush();
+            }
+
             if let Err((msg, _)) = result {
-                panic!("roc panic {}", msg);
+                notes.send(&Note::Crashed(msg));
+
+                std::process::exit(1)
             }
 
             if sequence.count_failures() > 0 {
@@ -344,14 +984,91 @@ fn run_expect_fx<'a, W: std::io::Write>(
 
             std::process::exit(1)
         }
-        1.. => {
+        child_pid @ 1.. => {
+            unsafe { libc::setpgid(child_pid, child_pid) };
+            FX_CHILD_PID.store(child_pid, Ordering::SeqCst);
+
+            let watchdog = timeout.map(|timeout| Watchdog::start(child_pid, timeout));
+
             let mut has_succeeded = true;
 
             for sig in &mut signals {
                 match sig {
                     SIGCHLD => {
-                        // done!
-                        return Ok(has_succeeded);
+                        // Only the exit of this expect's child ends it: signals get merged, so
+                        // a SIGCHLD does not say which child (or how many) it is about.
+                        let mut status = 0;
+                        if unsafe { libc::waitpid(child_pid, &mut status, libc::WNOHANG) } == 0 {
+                            continue;
+                        }
+
+                        FX_CHILD_PID.store(0, Ordering::SeqCst);
+
+                        let timed_out = watchdog.map_or(false, Watchdog::stop);
+
+                        if had_terminal {
+                            give_terminal_to(unsafe { libc::getpgrp() });
+                        }
+
+                        if libc::WIFSIGNALED(status) && libc::WTERMSIG(status) == libc::SIGINT {
+                            // the child had the terminal, so the Ctrl-C went to it instead of us
+                            exit_interrupted(
+                                libc::SIGINT,
+                                parent_memory.shm_name.as_ref().unwrap(),
+                            );
+                        }
+
+                        let exit = match timeout {
+                            Some(timeout) if timed_out => ChildExit::TimedOut(timeout),
+                            _ => ChildExit::from_status(status, notes.receive()),
+                        };
+
+                        match exit {
+                            ChildExit::Finished => return Ok(has_succeeded),
+                            exit if format == FailureFormat::Json => {
+                                let data = expectations.get(&expect.symbol.module_id()).unwrap();
+                                let source = std::fs::read_to_string(&data.path).unwrap();
+                                let location = Location {
+                                    module_id: expect.symbol.module_id(),
+                                    path: &data.path,
+                                    source: &source,
+                                    region: expect.region,
+                                };
+                                let message = match exit {
+                                    ChildExit::Crashed(message) => message,
+                                    exit => format!("The effectful expect {exit}."),
+                                };
+
+                                json::write_crash(writer, interns, location, &message)?;
+                            }
+                            ChildExit::Crashed(message) => {
+                                // render it like a crash in a pure expect
+                                let module_id = expect.symbol.module_id();
+                                let data = expectations.get_mut(&module_id).unwrap();
+                                let filename = data.path.to_owned();
+                                let source = std::fs::read_to_string(&data.path).unwrap();
+
+                                let renderer = Renderer::new(
+                                    arena,
+                                    interns,
+                                    render_target,
+                                    module_id,
+                                    filename,
+                                    &source,
+                                );
+
+                                renderer.render_panic(writer, &message, expect.region)?;
+                            }
+                            exit => {
+                                writeln!(
+                                    writer,
+                                    "\x1B[31mThe effectful expect at {} {exit}.\x1B[39m\n",
+                                    expect_location(expectations, &expect),
+                                )?;
+                            }
+                        }
+
+                        return Ok(false);
                     }
                     SIGUSR1 => {
                         // this is the signal we use for an expect failure. Let's see what the child told us
@@ -365,7 +1082,7 @@ fn run_expect_fx<'a, W: std::io::Write>(
                         let filename = data.path.to_owned();
                         let source = std::fs::read_to_string(&data.path).unwrap();
 
-                        let renderer = Renderer::new(
+                        let mut renderer = Renderer::new(
                             arena,
                             interns,
                             render_target,
@@ -374,6 +1091,8 @@ fn run_expect_fx<'a, W: std::io::Write>(
                             &source,
                         );
 
+                        renderer.set_value_limits(value_limits);
+
                         render_expect_failure(
                             writer,
                             &renderer,
@@ -382,8 +1101,11 @@ fn run_expect_fx<'a, W: std::io::Write>(
                             expectations,
                             interns,
                             layout_interner,
+                            TargetInfo::from(&Triple::host()),
                             parent_memory.ptr,
                             ExpectSequence::START_OFFSET,
+                            frame_dump,
+                            format,
                         )?;
                     }
                     _ => println!("received signal {}", sig),
@@ -430,8 +1152,11 @@ pub fn render_expects_in_memory<'a>(
         expectations,
         interns,
         layout_interner,
+        TargetInfo::from(&Triple::host()),
         shared_ptr,
         ExpectSequence::START_OFFSET,
+        None,
+        FailureFormat::Human,
     )
 }
 
@@ -443,67 +1168,76 @@ pub fn render_dbgs_in_memory<'a>(
     layout_interner: &GlobalLayoutInterner<'a>,
     memory: &ExpectMemory,
 ) -> std::io::Result<usize> {
-    let shared_ptr = memory.ptr;
-
-    let frame = ExpectFrame::at_offset(shared_ptr, ExpectSequence::START_OFFSET);
-    let module_id = frame.module_id;
-
-    let data = expectations.get_mut(&module_id).unwrap();
-    let filename = data.path.to_owned();
-    let source = std::fs::read_to_string(&data.path).unwrap();
-
-    let renderer = Renderer::new(
-        arena,
-        interns,
-        RenderTarget::ColorTerminal,
-        module_id,
-        filename,
-        &source,
-    );
-
     render_dbg_failure(
         writer,
-        &renderer,
+        RenderTarget::ColorTerminal,
         arena,
         expectations,
         interns,
         layout_interner,
-        shared_ptr,
+        memory.ptr,
         ExpectSequence::START_OFFSET,
+        ValueLimits::default(),
+        FailureFormat::Human,
     )
 }
 
-fn split_expect_lookups(subs: &Subs, lookups: &[ExpectLookup]) -> Vec<Symbol> {
-    lookups
-        .iter()
-        .filter_map(
-            |ExpectLookup {
-                 symbol,
-                 var,
-                 ability_info: _,
-             }| {
-                // mono will have dropped lookups that resolve to functions, so we should not keep
-                // them either.
-                if subs.is_function(*var) {
-                    None
-                } else {
-                    Some(*symbol)
-                }
-            },
-        )
-        .collect()
+/// The symbols each closure captures, for every module whose expects may fail, so failures can
+/// show what the functions they look up captured.
+fn closure_captures<'a>(
+    arena: &'a Bump,
+    expectations: &VecMap<ModuleId, Expectations>,
+) -> &'a MutMap<Symbol, Vec<Symbol>> {
+    let captures = expectations
+        .values()
+        .flat_map(|data| data.closure_captures.iter())
+        .map(|(closure, captured)| (*closure, captured.clone()))
+        .collect();
+
+    arena.alloc(captures)
 }
 
 #[allow(clippy::too_many_arguments)]
 fn render_dbg_failure<'a>(
     writer: &mut impl std::io::Write,
-    renderer: &Renderer,
+    render_target: RenderTarget,
     arena: &'a Bump,
     expectations: &mut VecMap<ModuleId, Expectations>,
     interns: &'a Interns,
     layout_interner: &GlobalLayoutInterner<'a>,
     start: *const u8,
     offset: usize,
+    value_limits: ValueLimits,
+    format: FailureFormat,
+) -> std::io::Result<usize> {
+    render_recovering(writer, format, offset, "dbg", None, |rendered| {
+        render_dbg_failure_help(
+            rendered,
+            render_target,
+            arena,
+            expectations,
+            interns,
+            layout_interner,
+            start,
+            offset,
+            value_limits,
+            format,
+        )
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn render_dbg_failure_help<'a>(
+    writer: &mut impl std::io::Write,
+    render_target: RenderTarget,
+    arena: &'a Bump,
+    expectations: &mut VecMap<ModuleId, Expectations>,
+    interns: &'a Interns,
+    layout_interner: &GlobalLayoutInterner<'a>,
+    start: *const u8,
+    offset: usize,
+    value_limits: ValueLimits,
+    format: FailureFormat,
 ) -> std::io::Result<usize> {
     // we always run programs as the host
     let target_info = (&target_lexicon::Triple::host()).into();
@@ -515,6 +1249,7 @@ fn render_dbg_failure<'a>(
     let dbg_symbol = unsafe { std::mem::transmute::<_, Symbol>(failure_region) };
     let expect_region = Some(Region::zero());
 
+    let closure_captures = closure_captures(arena, expectations);
     let data = expectations.get_mut(&module_id).unwrap();
 
     let current = match data.dbgs.get(&dbg_symbol) {
@@ -523,6 +1258,10 @@ fn render_dbg_failure<'a>(
     };
     let failure_region = current.region;
 
+    // the dbg can be in a different module than the expect that ran into it
+    let filename = data.path.to_owned();
+    let source = std::fs::read_to_string(&data.path)?;
+
     let subs = arena.alloc(&mut data.subs);
 
     let (offset, expressions, _variables) = crate::get_values(
@@ -531,18 +1270,37 @@ fn render_dbg_failure<'a>(
         subs,
         interns,
         layout_interner,
+        closure_captures,
         start,
         frame.start_offset,
         1,
     );
 
-    renderer.render_dbg(writer, &expressions, expect_region, failure_region)?;
+    match format {
+        FailureFormat::Human | FailureFormat::Junit | FailureFormat::Tap => {
+            let mut renderer =
+                Renderer::new(arena, interns, render_target, module_id, filename, &source);
+
+            renderer.set_value_limits(value_limits);
+            renderer.render_dbg(writer, &expressions, expect_region, failure_region)?;
+        }
+        FailureFormat::Json => {
+            let location = Location {
+                module_id,
+                path: &filename,
+                source: &source,
+                region: failure_region,
+            };
+
+            json::write_dbg(writer, arena, interns, location, &expressions[0])?;
+        }
+    }
 
     Ok(offset)
 }
 
 #[allow(clippy::too_many_arguments)]
-fn render_expect_failure<'a>(
+pub(crate) fn render_expect_failure<'a>(
     writer: &mut impl std::io::Write,
     renderer: &Renderer,
     arena: &'a Bump,
@@ -550,18 +1308,55 @@ fn render_expect_failure<'a>(
     expectations: &mut VecMap<ModuleId, Expectations>,
     interns: &'a Interns,
     layout_interner: &GlobalLayoutInterner<'a>,
+    target_info: TargetInfo,
     start: *const u8,
     offset: usize,
+    frame_dump: Option<&FrameDump>,
+    format: FailureFormat,
 ) -> std::io::Result<usize> {
-    // we always run programs as the host
-    let target_info = (&target_lexicon::Triple::host()).into();
+    let location = expect.map(|expect| expect_location(expectations, &expect));
 
+    render_recovering(writer, format, offset, "expect", location, |rendered| {
+        render_expect_failure_help(
+            rendered,
+            renderer,
+            arena,
+            expect,
+            expectations,
+            interns,
+            layout_interner,
+            target_info,
+            start,
+            offset,
+            frame_dump,
+            format,
+        )
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn render_expect_failure_help<'a>(
+    writer: &mut impl std::io::Write,
+    renderer: &Renderer,
+    arena: &'a Bump,
+    expect: Option<ToplevelExpect>,
+    expectations: &mut VecMap<ModuleId, Expectations>,
+    interns: &'a Interns,
+    layout_interner: &GlobalLayoutInterner<'a>,
+    target_info: TargetInfo,
+    start: *const u8,
+    offset: usize,
+    frame_dump: Option<&FrameDump>,
+    format: FailureFormat,
+) -> std::io::Result<usize> {
     let frame = ExpectFrame::at_offset(start, offset);
     let module_id = frame.module_id;
 
     let failure_region = frame.region;
     let expect_region = expect.map(|e| e.region);
+    let call_sites = frame.locate_call_sites(expectations)?;
 
+    let closure_captures = closure_captures(arena, expectations);
     let data = expectations.get_mut(&module_id).unwrap();
 
     let current = match data.expectations.get(&failure_region) {
@@ -569,65 +1364,209 @@ fn render_expect_failure<'a>(
         Some(current) => current,
     };
 
-    let symbols = split_expect_lookups(&data.subs, current);
+    let symbols: Vec<Symbol> = current.lookups.iter().map(|lookup| lookup.symbol).collect();
 
-    let (offset, expressions, variables) = crate::get_values(
+    let (end_offset, expressions, variables) = crate::get_values(
         target_info,
         arena,
         &data.subs,
         interns,
         layout_interner,
+        closure_captures,
         start,
         frame.start_offset,
         symbols.len(),
     );
 
-    renderer.render_failure(
-        writer,
-        &mut data.subs,
-        &symbols,
-        &variables,
-        &expressions,
-        expect_region,
-        failure_region,
-    )?;
+    let mut rendered = Vec::new();
+
+    match format {
+        FailureFormat::Human | FailureFormat::Junit | FailureFormat::Tap => renderer
+            .render_failure(
+                &mut rendered,
+                &mut data.subs,
+                &symbols,
+                &variables,
+                &expressions,
+                &current.accessed_fields,
+                current.compared,
+                expect_region,
+                failure_region,
+                &call_sites,
+                frame.omitted_calls,
+            )?,
+        FailureFormat::Json => {
+            // the renderer has the source of the toplevel expect's module, but an expect
+            // in a function it called can be in a different one
+            let source = std::fs::read_to_string(&data.path)?;
+            let location = Location {
+                module_id,
+                path: &data.path,
+                source: &source,
+                region: failure_region,
+            };
+
+            json::write_failure(
+                &mut rendered,
+                arena,
+                interns,
+                &mut data.subs,
+                location,
+                &call_sites,
+                &symbols,
+                &variables,
+                &expressions,
+            )?
+        }
+    }
+
+    writer.write_all(&rendered)?;
+
+    if let Some(frame_dump) = frame_dump {
+        // Lookups point at their values by offset from the start of the buffer,
+        // so the frame can only be decoded along with everything before it.
+        let length = end_offset.max(frame.start_offset);
+        let bytes = unsafe { std::slice::from_raw_parts(start, length) };
+        let decoded = DecodedFrame {
+            module_id,
+            path: &data.path,
+            region: failure_region,
+            offset,
+            symbols: &symbols,
+            expressions: &expressions,
+        };
+
+        frame_dump.write(interns, bytes, &decoded, &rendered)?;
+    }
+
+    Ok(end_offset)
+}
+
+/// Decoding a frame that's malformed, e.g. because the expect that wrote it corrupted the
+/// buffer, can panic. Then nothing of the frame is written but a note about it, and `offset`
+/// is returned as where it ends, so the results of the other expects are still reported.
+///
+/// `what` is the kind of frame, and `location` where its toplevel expect is, when it's known.
+pub(crate) fn render_recovering(
+    writer: &mut impl std::io::Write,
+    format: FailureFormat,
+    offset: usize,
+    what: &str,
+    location: Option<String>,
+    render: impl FnOnce(&mut Vec<u8>) -> std::io::Result<usize>,
+) -> std::io::Result<usize> {
+    // render into a buffer, so a panic halfway through doesn't leave half a report behind
+    let mut rendered = Vec::new();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| render(&mut rendered)));
+
+    let payload = match result {
+        Ok(end_offset) => {
+            writer.write_all(&rendered)?;
+
+            return end_offset;
+        }
+        Err(payload) => payload,
+    };
+
+    let message = match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match payload.downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "the renderer panicked".to_string(),
+        },
+    };
+
+    match format {
+        FailureFormat::Human | FailureFormat::Junit | FailureFormat::Tap => {
+            let at = match &location {
+                Some(location) => format!(" at {location}"),
+                None => String::new(),
+            };
+
+            writeln!(
+                writer,
+                "\x1B[31mInternal rendering error for this {what}{at}: {message}\x1B[39m\n\n\
+                This is a bug in the compiler. The results of the other expects are still \
+                reported.\n",
+            )?;
+        }
+        FailureFormat::Json => json::write_internal_error(writer, what, location, &message)?,
+    }
 
     Ok(offset)
 }
 
-struct ExpectSequence {
+pub(crate) struct ExpectSequence {
     ptr: *const u8,
 }
 
 impl ExpectSequence {
-    const START_OFFSET: usize = 8 + 8 + 8;
+    pub(crate) const START_OFFSET: usize = 8 + 8 + 8;
 
+    // indices of usizes
     const COUNT_INDEX: usize = 0;
     const OFFSET_INDEX: usize = 1;
-    const LOCK_INDEX: usize = 2;
+
+    // indices of u32s
+    const CAPACITY_INDEX: usize = 4;
+    const MESSAGE_INDEX: usize = 5;
 
     fn new(ptr: *mut u8) -> Self {
+        let mut sequence = Self {
+            ptr: ptr as *const u8,
+        };
+
+        sequence.reset();
+
+        sequence
+    }
+
+    fn count_failures(&self) -> usize {
+        unsafe { *(self.ptr as *const usize).add(Self::COUNT_INDEX) }
+    }
+
+    /// Where the next frame is written.
+    fn next_offset(&self) -> usize {
+        unsafe { *(self.ptr as *const usize).add(Self::OFFSET_INDEX) }
+    }
+
+    /// Forgets the frames after the first `count`, which end at `offset`.
+    fn rewind(&mut self, count: usize, offset: usize) {
         unsafe {
-            let ptr = ptr as *mut usize;
-            std::ptr::write_unaligned(ptr.add(Self::COUNT_INDEX), 0);
-            std::ptr::write_unaligned(ptr.add(Self::OFFSET_INDEX), Self::START_OFFSET);
-            std::ptr::write_unaligned(ptr.add(Self::LOCK_INDEX), 0);
+            let ptr = self.ptr as *mut usize;
+            std::ptr::write_unaligned(ptr.add(Self::COUNT_INDEX), count);
+            std::ptr::write_unaligned(ptr.add(Self::OFFSET_INDEX), offset);
         }
+    }
 
-        Self {
-            ptr: ptr as *const u8,
+    /// The size of the shared memory object, or how big the child needs it to be
+    /// while it waits for [ChildProcessMsg::BufferFull] to be handled.
+    fn capacity(&self) -> usize {
+        unsafe { std::ptr::read_unaligned((self.ptr as *const u32).add(Self::CAPACITY_INDEX)) as _ }
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        let capacity = u32::try_from(capacity).unwrap_or(u32::MAX);
+
+        unsafe {
+            std::ptr::write_unaligned((self.ptr as *mut u32).add(Self::CAPACITY_INDEX), capacity)
         }
     }
 
-    fn count_failures(&self) -> usize {
-        unsafe { *(self.ptr as *const usize).add(Self::COUNT_INDEX) }
+    fn message(&self) -> &AtomicU32 {
+        let atomic_ptr: *const AtomicU32 =
+            unsafe { (self.ptr as *const u32).add(Self::MESSAGE_INDEX).cast() };
+
+        unsafe { &*atomic_ptr }
+    }
+
+    /// Lets the child continue, without touching the frames it wrote.
+    fn clear_message(&self) {
+        self.message().store(0, Ordering::Release);
     }
 
     fn wait_for_child(&self, sigchld: Arc<AtomicBool>) -> ChildProcessMsg {
-        use std::sync::atomic::Ordering;
-        let ptr = self.ptr as *const u32;
-        let atomic_ptr: *const AtomicU32 = unsafe { ptr.add(5).cast() };
-        let atomic = unsafe { &*atomic_ptr };
+        let atomic = self.message();
 
         loop {
             if sigchld.load(Ordering::Relaxed) {
@@ -638,18 +1577,23 @@ impl ExpectSequence {
                 0 => std::hint::spin_loop(),
                 1 => break ChildProcessMsg::Expect,
                 2 => break ChildProcessMsg::Dbg,
+                4 => break ChildProcessMsg::EffectCalled,
+                5 => break ChildProcessMsg::EffectReturned,
+                6 => break ChildProcessMsg::BufferFull,
                 n => panic!("invalid atomic value set by the child: {:#x}", n),
             }
         }
     }
 
+    /// Forgets the frames in the buffer, and lets the child continue. Keeps the capacity.
     fn reset(&mut self) {
         unsafe {
             let ptr = self.ptr as *mut usize;
             std::ptr::write_unaligned(ptr.add(Self::COUNT_INDEX), 0);
             std::ptr::write_unaligned(ptr.add(Self::OFFSET_INDEX), Self::START_OFFSET);
-            std::ptr::write_unaligned(ptr.add(Self::LOCK_INDEX), 0);
         }
+
+        self.clear_message();
     }
 }
 
@@ -657,32 +1601,88 @@ pub enum ChildProcessMsg {
     Expect = 1,
     Dbg = 2,
     Terminate = 3,
+    /// Only sent by programs built to trace effects; see [ExpectMemory::effect_call].
+    EffectCalled = 4,
+    EffectReturned = 5,
+    /// The frame the child is writing does not fit; see [ExpectMemory::grow].
+    BufferFull = 6,
 }
 
 struct ExpectFrame {
     region: Region,
     module_id: ModuleId,
+    /// The calls that led to the expect or dbg, innermost first.
+    call_sites: Vec<(ModuleId, Region)>,
+    /// How many more calls led to those, which the frame had no room for.
+    omitted_calls: usize,
 
     start_offset: usize,
 }
 
 impl ExpectFrame {
+    /// How many of the innermost calls a frame keeps.
+    const CALL_SITES_CAPACITY: usize = 4;
+
     fn at_offset(start: *const u8, offset: usize) -> Self {
-        let region_bytes: [u8; 8] = unsafe { *(start.add(offset).cast()) };
-        let region: Region = unsafe { std::mem::transmute(region_bytes) };
+        let (region, module_id) = Self::read_header(start, offset);
 
-        let module_id_bytes: [u8; 4] = unsafe { *(start.add(offset + 8).cast()) };
-        let module_id: ModuleId = unsafe { std::mem::transmute(module_id_bytes) };
+        // the number of calls, and then a ring of their headers; the call at
+        // depth `d` is at `d % capacity`
+        let depth_bytes: [u8; 4] = unsafe { *(start.add(offset + 8 + 4).cast()) };
+        let depth = u32::from_ne_bytes(depth_bytes) as usize;
+        let ring = offset + 8 + 4 + 4;
+
+        let call_sites = (depth.saturating_sub(Self::CALL_SITES_CAPACITY)..depth)
+            .rev()
+            .map(|d| Self::read_header(start, ring + (d % Self::CALL_SITES_CAPACITY) * (8 + 4)))
+            .map(|(region, module_id)| (module_id, region))
+            .collect();
 
         // skip to frame
-        let start_offset = offset + 8 + 4;
+        let start_offset = ring + Self::CALL_SITES_CAPACITY * (8 + 4);
 
         Self {
             region,
             module_id,
+            call_sites,
+            omitted_calls: depth.saturating_sub(Self::CALL_SITES_CAPACITY),
             start_offset,
         }
     }
+
+    fn read_header(start: *const u8, offset: usize) -> (Region, ModuleId) {
+        let region_bytes: [u8; 8] = unsafe { *(start.add(offset).cast()) };
+        let region: Region = unsafe { std::mem::transmute(region_bytes) };
+
+        let module_id_bytes: [u8; 4] = unsafe { *(start.add(offset + 8).cast()) };
+        let module_id: ModuleId = unsafe { std::mem::transmute(module_id_bytes) };
+
+        (region, module_id)
+    }
+
+    /// Where the calls that led to the expect are, for its report.
+    fn locate_call_sites(
+        &self,
+        expectations: &VecMap<ModuleId, Expectations>,
+    ) -> std::io::Result<Vec<CallSite>> {
+        let mut call_sites = Vec::with_capacity(self.call_sites.len());
+
+        for (module_id, region) in self.call_sites.iter() {
+            // calls are only tracked in modules that have expectations
+            let path = match expectations.get(module_id) {
+                Some(data) => &data.path,
+                None => continue,
+            };
+            let source = std::fs::read_to_string(path)?;
+
+            call_sites.push(CallSite {
+                path: path.clone(),
+                region: LineInfo::new(&source).convert_region(*region),
+            });
+        }
+
+        Ok(call_sites)
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -692,18 +1692,165 @@ pub struct ToplevelExpect<'a> {
     pub region: Region,
 }
 
+/// Where `expect` is, as `path:line`.
+pub(crate) fn expect_location(
+    expectations: &VecMap<ModuleId, Expectations>,
+    expect: &ToplevelExpect<'_>,
+) -> String {
+    match expectations.get(&expect.symbol.module_id()) {
+        Some(data) => {
+            let line = match std::fs::read_to_string(&data.path) {
+                Ok(src) => LineInfo::new(&src).convert_region(expect.region).start.line + 1,
+                Err(_) => 0,
+            };
+
+            format!("{}:{}", data.path.display(), line)
+        }
+        None => format!("{:?}", expect.region),
+    }
+}
+
 #[derive(Debug)]
 pub struct ExpectFunctions<'a> {
     pub pure: BumpVec<'a, ToplevelExpect<'a>>,
     pub fx: BumpVec<'a, ToplevelExpect<'a>>,
+    /// Set when the expects should be profiled, see [Profiler].
+    pub profiler: Option<Profiler>,
+    /// Set when the calls of exposed definitions should be counted, see [Coverage].
+    pub coverage: Option<Coverage>,
+    /// How many expects were left out because they didn't match the [ExpectFilter].
+    pub filtered_out: usize,
+    /// Stop running expects once this many of them failed, e.g. to get CI results sooner.
+    pub max_failures: Option<usize>,
+    /// How much of each value failures and `dbg` show.
+    pub value_limits: ValueLimits,
+}
+
+impl<'a> ExpectFunctions<'a> {
+    /// Puts the pure expects in an order that only depends on `seed`, so that expects that
+    /// only pass when they run after some other expect stand out, and the order they failed
+    /// in can be replayed.
+    pub fn shuffle(&mut self, seed: u64) {
+        use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+        self.pure.shuffle(&mut StdRng::seed_from_u64(seed));
+    }
+}
+
+/// A seed for [ExpectFunctions::shuffle] that is different every time.
+pub fn random_shuffle_seed() -> u64 {
+    // Short enough to type back in.
+    rand::random::<u32>() as u64
 }
 
+/// Which top-level expects to compile and run, e.g. to iterate on a single failing expect
+/// in a large codebase without paying for all of the others.
+#[derive(Debug, Clone, Default)]
+pub struct ExpectFilter {
+    /// Only expects whose source code contains this text.
+    pub pattern: Option<String>,
+    /// Only expects in the module with this name (or whose file has this name, without
+    /// the `.roc` extension).
+    pub module: Option<String>,
+    /// Only expects in the modules at these paths, e.g. the ones `roc test --watch` found changed.
+    pub paths: Option<Vec<PathBuf>>,
+}
+
+impl ExpectFilter {
+    pub const ALL: Self = Self {
+        pattern: None,
+        module: None,
+        paths: None,
+    };
+
+    /// Whether the expect at `region` in the module `module_id` matches.
+    pub(crate) fn matches(
+        &self,
+        interns: &Interns,
+        sources: &MutMap<ModuleId, (PathBuf, Box<str>)>,
+        module_id: ModuleId,
+        region: Region,
+    ) -> bool {
+        let source = sources.get(&module_id);
+
+        if let Some(module) = &self.module {
+            let module_name = interns.module_name(module_id).as_str();
+            let file_stem = source
+                .and_then(|(path, _)| path.file_stem())
+                .and_then(|stem| stem.to_str());
+
+            if module_name != module && file_stem != Some(module.as_str()) {
+                return false;
+            }
+        }
+
+        if let Some(paths) = &self.paths {
+            if !source.map_or(false, |(path, _)| paths.contains(path)) {
+                return false;
+            }
+        }
+
+        if let Some(pattern) = &self.pattern {
+            let start = region.start().offset as usize;
+            let end = region.end().offset as usize;
+            let text = source.and_then(|(_, src)| src.get(start..end));
+
+            if !text.map_or(false, |text| text.contains(pattern.as_str())) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Removes the expects that don't match from `loaded`, together with their procedures,
+    /// so they are not compiled. Returns how many were removed.
+    fn apply(&self, loaded: &mut MonomorphizedModule<'_>) -> usize {
+        if self.pattern.is_none() && self.module.is_none() && self.paths.is_none() {
+            return 0;
+        }
+
+        let MonomorphizedModule {
+            toplevel_expects,
+            procedures,
+            interns,
+            sources,
+            ..
+        } = loaded;
+
+        let mut removed = MutSet::default();
+
+        for expects in [&mut toplevel_expects.pure, &mut toplevel_expects.fx] {
+            let mut kept = VecMap::with_capacity(expects.len());
+
+            for (symbol, region) in std::mem::take(expects) {
+                if self.matches(interns, sources, symbol.module_id(), region) {
+                    kept.insert(symbol, region);
+                } else {
+                    removed.insert(symbol);
+                }
+            }
+
+            *expects = kept;
+        }
+
+        procedures.retain(|(symbol, _), _| !removed.contains(symbol));
+
+        removed.len()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn expect_mono_module_to_dylib<'a>(
     arena: &'a Bump,
     target: Triple,
     loaded: MonomorphizedModule<'a>,
     opt_level: OptLevel,
     mode: LlvmBackendMode,
+    profile: bool,
+    coverage: bool,
+    max_memory: Option<u64>,
+    filter: &ExpectFilter,
 ) -> Result<
     (
         libloading::Library,
@@ -712,24 +1859,104 @@ pub fn expect_mono_module_to_dylib<'a>(
     ),
     libloading::Error,
 > {
-    let target_info = TargetInfo::from(&target);
+    let ((lib, bytes), mut expects, layout_interner) = build_expect_module(
+        arena,
+        &target,
+        loaded,
+        opt_level,
+        mode,
+        coverage,
+        max_memory,
+        filter,
+        |module| {
+            if profile {
+                llvm_module_to_dylib_and_bytes(module, &target, opt_level)
+                    .map(|(lib, bytes)| (lib, Some(bytes)))
+            } else {
+                llvm_module_to_dylib(module, &target, opt_level).map(|lib| (lib, None))
+            }
+        },
+    )?;
+
+    if let Some(bytes) = bytes {
+        expects.profiler = Profiler::new(&lib, &bytes, "set_shared_buffer");
+    }
+
+    Ok((lib, expects, layout_interner))
+}
+
+/// Like [expect_mono_module_to_dylib], but builds a wasm32 module to run in the interpreter,
+/// see [crate::wasm].
+pub fn expect_mono_module_to_wasm<'a>(
+    arena: &'a Bump,
+    loaded: MonomorphizedModule<'a>,
+    opt_level: OptLevel,
+    filter: &ExpectFilter,
+) -> std::io::Result<(Vec<u8>, ExpectFunctions<'a>, STLayoutInterner<'a>)> {
+    let target = Triple {
+        architecture: target_lexicon::Architecture::Wasm32,
+        vendor: target_lexicon::Vendor::Unknown,
+        operating_system: target_lexicon::OperatingSystem::Unknown,
+        environment: target_lexicon::Environment::Unknown,
+        binary_format: target_lexicon::BinaryFormat::Wasm,
+    };
+
+    build_expect_module(
+        arena,
+        &target,
+        loaded,
+        opt_level,
+        LlvmBackendMode::CliTest,
+        false,
+        None,
+        filter,
+        |module| llvm_module_to_wasm(module, opt_level, crate::wasm::EXPORTS),
+    )
+}
+
+/// Builds the LLVM module with the (matching) top-level expects of `loaded`, and hands it to
+/// `finish` to turn it into something we can run.
+#[allow(clippy::too_many_arguments)]
+fn build_expect_module<'a, T, E>(
+    arena: &'a Bump,
+    target: &Triple,
+    mut loaded: MonomorphizedModule<'a>,
+    opt_level: OptLevel,
+    mode: LlvmBackendMode,
+    coverage: bool,
+    max_memory: Option<u64>,
+    filter: &ExpectFilter,
+    finish: impl FnOnce(&inkwell::module::Module) -> Result<T, E>,
+) -> Result<(T, ExpectFunctions<'a>, STLayoutInterner<'a>), E> {
+    let target_info = TargetInfo::from(target);
+
+    let filtered_out = filter.apply(&mut loaded);
+
+    let coverage = coverage.then(|| Coverage::new(&loaded));
 
     let MonomorphizedModule {
         toplevel_expects,
         procedures,
         interns,
         layout_interner,
+        expect_call_sites,
         ..
     } = loaded;
 
     let context = Context::create();
     let builder = context.create_builder();
     let module = arena.alloc(roc_gen_llvm::llvm::build::module_from_builtins(
-        &target, &context, "",
+        target, &context, "",
     ));
 
     let module = arena.alloc(module);
-    let (module_pass, _function_pass) =
+
+    // `build_procedures_expose_expects` already verifies every procedure and runs the function
+    // passes on it right after building it, so we only need the module passes here.
+    //
+    // Those per-procedure passes can't be moved onto a thread pool: all functions share one LLVM
+    // context, which is not thread-safe (and inkwell's values are not `Send` for that reason).
+    let (module_pass, _) =
         roc_gen_llvm::llvm::build::construct_optimization_passes(module, opt_level);
 
     let (dibuilder, compile_unit) = roc_gen_llvm::llvm::build::Env::new_debug_info(module);
@@ -747,12 +1974,27 @@ pub fn expect_mono_module_to_dylib<'a>(
         mode,
         // important! we don't want any procedures to get the C calling convention
         exposed_to_host: MutSet::default(),
+        trace_effects: false,
+        thread_safety: roc_gen_llvm::llvm::build::ThreadSafety::SingleThreaded,
+        list_growth_percent: None,
+        expect_call_sites,
     };
 
     // Add roc_alloc, roc_realloc, and roc_dealloc, since the repl has no
     // platform to provide them.
     add_default_roc_externs(&env);
 
+    // Lets a pure expect that allocates too much be stopped, see [crate::memory_limit].
+    if let Some(max_bytes) = max_memory {
+        add_memory_limit(&env, max_bytes);
+    }
+
+    // Lets a pure expect that runs for too long be stopped, see [crate::timeout]. The
+    // interpreter has no signals to do that with.
+    if target.architecture != target_lexicon::Architecture::Wasm32 {
+        add_exported_crash(&env);
+    }
+
     let capacity = toplevel_expects.pure.len() + toplevel_expects.fx.len();
     let mut expect_symbols = BumpVec::with_capacity_in(capacity, env.arena);
 
@@ -767,6 +2009,10 @@ pub fn expect_mono_module_to_dylib<'a>(
         procedures,
     );
 
+    if let Some(coverage) = coverage.as_ref() {
+        add_coverage_counters(&env, coverage.counted());
+    }
+
     let expects_fx = bumpalo::collections::Vec::from_iter_in(
         toplevel_expects
             .fx
@@ -796,6 +2042,11 @@ pub fn expect_mono_module_to_dylib<'a>(
     let expects = ExpectFunctions {
         pure: expects_pure,
         fx: expects_fx,
+        profiler: None,
+        coverage,
+        filtered_out,
+        max_failures: None,
+        value_limits: ValueLimits::default(),
     };
 
     env.dibuilder.finalize();
@@ -811,15 +2062,23 @@ pub fn expect_mono_module_to_dylib<'a>(
     // Uncomment this to see the module's optimized LLVM instruction output:
     // env.module.print_to_stderr();
 
-    // Verify the module
-    if let Err(errors) = env.module.verify() {
+    // Every procedure was verified while it was built, so for the expect dylib we only re-verify
+    // the whole module in debug builds of the compiler, where the module passes are most likely
+    // to be under development. This verification is a big part of the latency for large modules.
+    if cfg!(debug_assertions) {
+        verify_expect_module(env.module);
+    }
+
+    finish(env.module).map(|output| (output, expects, layout_interner))
+}
+
+fn verify_expect_module(module: &inkwell::module::Module) {
+    if let Err(errors) = module.verify() {
         let path = std::env::temp_dir().join("test.ll");
-        env.module.print_to_file(&path).unwrap();
+        module.print_to_file(&path).unwrap();
         panic!(
             "Errors defining module:\n{}\n\nUncomment things nearby to see more details. IR written to `{:?}`",
             errors.to_string(), path,
         );
     }
-
-    llvm_module_to_dylib(env.module, &target, opt_level).map(|lib| (lib, expects, layout_interner))
 }
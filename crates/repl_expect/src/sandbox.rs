@@ -0,0 +1,380 @@
+//! Confines the child process that runs an effectful `expect`, so a test suite can't
+//! accidentally reach the network or write outside of a scratch directory.
+//!
+//! On Linux this uses seccomp (network) and landlock (filesystem), on macOS a sandbox profile.
+//! Reading files is always allowed, because the child still needs to load shared libraries.
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sandbox {
+    /// Only allow unix domain sockets.
+    pub deny_network: bool,
+    /// Only allow writing inside of a temporary directory (which is also the working directory).
+    pub temp_dir_only: bool,
+}
+
+impl Sandbox {
+    pub const NONE: Self = Sandbox {
+        deny_network: false,
+        temp_dir_only: false,
+    };
+
+    pub fn is_active(&self) -> bool {
+        self.deny_network || self.temp_dir_only
+    }
+}
+
+/// The scratch directory that sandboxed expects are allowed to write to.
+/// It is removed again when this is dropped.
+pub(crate) struct SandboxDir {
+    path: PathBuf,
+}
+
+impl SandboxDir {
+    /// Where this process puts its scratch directory.
+    pub(crate) fn default_path() -> PathBuf {
+        std::env::temp_dir().join(format!("roc_expect_sandbox_{}", std::process::id()))
+    }
+
+    pub(crate) fn create() -> std::io::Result<Self> {
+        let path = Self::default_path();
+
+        std::fs::create_dir_all(&path)?;
+
+        // The sandbox rules are path based, and e.g. on macOS /tmp is a symlink
+        let path = path.canonicalize()?;
+
+        Ok(Self { path })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for SandboxDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Restrict the current process. This can not be undone, so only call it in a forked child.
+pub(crate) fn enter(sandbox: Sandbox, temp_dir: Option<&Path>) -> Result<(), String> {
+    if let Some(temp_dir) = temp_dir {
+        std::env::set_current_dir(temp_dir).map_err(|err| {
+            format!(
+                "I could not enter the sandbox directory {}: {err}",
+                temp_dir.display()
+            )
+        })?;
+        std::env::set_var("TMPDIR", temp_dir);
+    }
+
+    platform::enter(sandbox, temp_dir)
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::Sandbox;
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    pub(super) fn enter(sandbox: Sandbox, temp_dir: Option<&Path>) -> Result<(), String> {
+        // Required to install a seccomp filter or landlock ruleset without CAP_SYS_ADMIN
+        if unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } != 0 {
+            return Err(os_error("prctl(PR_SET_NO_NEW_PRIVS)"));
+        }
+
+        if sandbox.temp_dir_only {
+            restrict_writes(temp_dir.expect("the sandbox needs a temp dir"))?;
+        }
+
+        if sandbox.deny_network {
+            deny_network()?;
+        }
+
+        Ok(())
+    }
+
+    fn os_error(what: &str) -> String {
+        format!("{what} failed: {}", std::io::Error::last_os_error())
+    }
+
+    // seccomp
+
+    #[repr(C)]
+    struct SockFilter {
+        code: u16,
+        jt: u8,
+        jf: u8,
+        k: u32,
+    }
+
+    #[repr(C)]
+    struct SockFprog {
+        len: u16,
+        filter: *const SockFilter,
+    }
+
+    // BPF_LD | BPF_W | BPF_ABS
+    const BPF_LD_W_ABS: u16 = 0x20;
+    // BPF_JMP | BPF_JEQ | BPF_K
+    const BPF_JMP_JEQ_K: u16 = 0x15;
+    // BPF_RET | BPF_K
+    const BPF_RET_K: u16 = 0x06;
+
+    const SECCOMP_MODE_FILTER: libc::c_ulong = 2;
+    const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+    const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+
+    // offsets into `struct seccomp_data`
+    const SECCOMP_DATA_NR: u32 = 0;
+    const SECCOMP_DATA_ARCH: u32 = 4;
+    const SECCOMP_DATA_ARG0: u32 = 16;
+
+    #[cfg(target_arch = "x86_64")]
+    const AUDIT_ARCH: u32 = 0xC000_003E;
+    #[cfg(target_arch = "aarch64")]
+    const AUDIT_ARCH: u32 = 0xC000_00B7;
+
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    fn deny_network() -> Result<(), String> {
+        const fn stmt(code: u16, k: u32) -> SockFilter {
+            SockFilter {
+                code,
+                jt: 0,
+                jf: 0,
+                k,
+            }
+        }
+
+        const fn jump(k: u32, jt: u8, jf: u8) -> SockFilter {
+            SockFilter {
+                code: BPF_JMP_JEQ_K,
+                jt,
+                jf,
+                k,
+            }
+        }
+
+        let deny = SECCOMP_RET_ERRNO | libc::EPERM as u32;
+
+        // `socket(domain, ...)` fails with EPERM unless the domain is AF_UNIX
+        let filter = [
+            stmt(BPF_LD_W_ABS, SECCOMP_DATA_ARCH),
+            jump(AUDIT_ARCH, 1, 0),
+            stmt(BPF_RET_K, deny),
+            stmt(BPF_LD_W_ABS, SECCOMP_DATA_NR),
+            jump(libc::SYS_socket as u32, 0, 3),
+            stmt(BPF_LD_W_ABS, SECCOMP_DATA_ARG0),
+            jump(libc::AF_UNIX as u32, 1, 0),
+            stmt(BPF_RET_K, deny),
+            stmt(BPF_RET_K, SECCOMP_RET_ALLOW),
+        ];
+
+        let program = SockFprog {
+            len: filter.len() as u16,
+            filter: filter.as_ptr(),
+        };
+
+        let result = unsafe {
+            libc::prctl(
+                libc::PR_SET_SECCOMP,
+                SECCOMP_MODE_FILTER,
+                &program as *const SockFprog,
+            )
+        };
+
+        if result != 0 {
+            return Err(os_error("installing the seccomp network filter"));
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    fn deny_network() -> Result<(), String> {
+        Err("Denying network access is only supported on x86_64 and aarch64.".to_string())
+    }
+
+    // landlock
+
+    // These syscall numbers are the same on every architecture.
+    const SYS_LANDLOCK_CREATE_RULESET: libc::c_long = 444;
+    const SYS_LANDLOCK_ADD_RULE: libc::c_long = 445;
+    const SYS_LANDLOCK_RESTRICT_SELF: libc::c_long = 446;
+
+    const LANDLOCK_RULE_PATH_BENEATH: libc::c_int = 1;
+
+    const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+    const ACCESS_FS_REMOVE_DIR: u64 = 1 << 4;
+    const ACCESS_FS_REMOVE_FILE: u64 = 1 << 5;
+    const ACCESS_FS_MAKE_CHAR: u64 = 1 << 6;
+    const ACCESS_FS_MAKE_DIR: u64 = 1 << 7;
+    const ACCESS_FS_MAKE_REG: u64 = 1 << 8;
+    const ACCESS_FS_MAKE_SOCK: u64 = 1 << 9;
+    const ACCESS_FS_MAKE_FIFO: u64 = 1 << 10;
+    const ACCESS_FS_MAKE_BLOCK: u64 = 1 << 11;
+    const ACCESS_FS_MAKE_SYM: u64 = 1 << 12;
+
+    const ACCESS_FS_ALL_WRITES: u64 = ACCESS_FS_WRITE_FILE
+        | ACCESS_FS_REMOVE_DIR
+        | ACCESS_FS_REMOVE_FILE
+        | ACCESS_FS_MAKE_CHAR
+        | ACCESS_FS_MAKE_DIR
+        | ACCESS_FS_MAKE_REG
+        | ACCESS_FS_MAKE_SOCK
+        | ACCESS_FS_MAKE_FIFO
+        | ACCESS_FS_MAKE_BLOCK
+        | ACCESS_FS_MAKE_SYM;
+
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+    }
+
+    #[repr(C, packed)]
+    struct PathBeneathAttr {
+        allowed_access: u64,
+        parent_fd: i32,
+    }
+
+    fn restrict_writes(temp_dir: &Path) -> Result<(), String> {
+        let attr = RulesetAttr {
+            handled_access_fs: ACCESS_FS_ALL_WRITES,
+        };
+
+        let ruleset_fd = unsafe {
+            libc::syscall(
+                SYS_LANDLOCK_CREATE_RULESET,
+                &attr as *const RulesetAttr,
+                std::mem::size_of::<RulesetAttr>(),
+                0,
+            )
+        } as libc::c_int;
+
+        if ruleset_fd < 0 {
+            return Err(os_error(
+                "creating the landlock ruleset (this needs Linux 5.13 or later)",
+            ));
+        }
+
+        let result = allow_writes_beneath(ruleset_fd, temp_dir, ACCESS_FS_ALL_WRITES)
+            // programs commonly redirect output to /dev/null
+            .and_then(|()| {
+                allow_writes_beneath(ruleset_fd, Path::new("/dev/null"), ACCESS_FS_WRITE_FILE)
+            })
+            .and_then(|()| {
+                if unsafe { libc::syscall(SYS_LANDLOCK_RESTRICT_SELF, ruleset_fd, 0) } != 0 {
+                    Err(os_error("enforcing the landlock ruleset"))
+                } else {
+                    Ok(())
+                }
+            });
+
+        unsafe { libc::close(ruleset_fd) };
+
+        result
+    }
+
+    fn allow_writes_beneath(
+        ruleset_fd: libc::c_int,
+        path: &Path,
+        allowed_access: u64,
+    ) -> Result<(), String> {
+        let c_path = CString::new(path.as_os_str().as_bytes()).unwrap();
+        let parent_fd = unsafe { libc::open(c_path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+
+        if parent_fd < 0 {
+            return Err(os_error(&format!("opening {}", path.display())));
+        }
+
+        let rule = PathBeneathAttr {
+            allowed_access,
+            parent_fd,
+        };
+
+        let result = unsafe {
+            libc::syscall(
+                SYS_LANDLOCK_ADD_RULE,
+                ruleset_fd,
+                LANDLOCK_RULE_PATH_BENEATH,
+                &rule as *const PathBeneathAttr,
+                0,
+            )
+        };
+
+        unsafe { libc::close(parent_fd) };
+
+        if result != 0 {
+            return Err(os_error(&format!(
+                "adding a landlock rule for {}",
+                path.display()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::Sandbox;
+    use std::ffi::{CStr, CString};
+    use std::os::raw::c_char;
+    use std::path::Path;
+
+    extern "C" {
+        // Deprecated, but still the only way for a process to sandbox itself.
+        fn sandbox_init(profile: *const c_char, flags: u64, errorbuf: *mut *mut c_char) -> i32;
+        fn sandbox_free_error(errorbuf: *mut c_char);
+    }
+
+    pub(super) fn enter(sandbox: Sandbox, temp_dir: Option<&Path>) -> Result<(), String> {
+        let mut profile = String::from("(version 1)\n(allow default)\n");
+
+        if sandbox.deny_network {
+            profile.push_str("(deny network-outbound (remote ip))\n");
+            profile.push_str("(deny network-bind (local ip))\n");
+        }
+
+        if sandbox.temp_dir_only {
+            let temp_dir = temp_dir.expect("the sandbox needs a temp dir");
+
+            profile.push_str(&format!(
+                "(deny file-write* (require-not (require-any (subpath {:?}) (literal \"/dev/null\"))))\n",
+                temp_dir.display().to_string()
+            ));
+        }
+
+        let profile = CString::new(profile).unwrap();
+        let mut error: *mut c_char = std::ptr::null_mut();
+
+        if unsafe { sandbox_init(profile.as_ptr(), 0, &mut error) } != 0 {
+            let message = if error.is_null() {
+                "unknown error".to_string()
+            } else {
+                let message = unsafe { CStr::from_ptr(error) }
+                    .to_string_lossy()
+                    .into_owned();
+                unsafe { sandbox_free_error(error) };
+                message
+            };
+
+            return Err(format!("Entering the macOS sandbox failed: {message}"));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod platform {
+    use super::Sandbox;
+    use std::path::Path;
+
+    pub(super) fn enter(_sandbox: Sandbox, _temp_dir: Option<&Path>) -> Result<(), String> {
+        Err("Sandboxing effectful expects is only supported on Linux and macOS.".to_string())
+    }
+}
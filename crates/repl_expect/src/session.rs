@@ -0,0 +1,144 @@
+//! Building and running the top-level expects of a program again and again in a long-lived
+//! process, like a language server that runs them whenever a file is saved.
+//!
+//! [run_toplevel_expects](crate::run::run_toplevel_expects) sets up a shared buffer for every
+//! run, and every build loads a dylib of its own. An [ExpectSession] keeps a single buffer for
+//! all of its runs, and unloads the dylib of its previous build before it loads the next one, so
+//! the number of open files and mappings stays the same however often the expects run.
+//!
+//! Each build still gets an LLVM context of its own, which is disposed of as soon as the dylib
+//! is linked: a context never frees the types and constants created in it, so one that's reused
+//! would grow with every build.
+//!
+//! Sessions on different threads share nothing that a build needs, so they can build at the
+//! same time. Running expects uses process-wide state though (the signal handlers of timeouts,
+//! and the effectful expect that an interrupt kills), so the runs of all sessions take turns.
+use std::io::Write;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use bumpalo::Bump;
+use roc_collections::VecMap;
+use roc_error_macros::internal_error;
+use roc_gen_llvm::llvm::build::LlvmBackendMode;
+use roc_load::{Expectations, MonomorphizedModule};
+use roc_module::symbol::{Interns, ModuleId};
+use roc_mono::ir::OptLevel;
+use roc_mono::layout::{GlobalLayoutInterner, STLayoutInterner};
+use roc_reporting::report::RenderTarget;
+use target_lexicon::Triple;
+
+use crate::json::FailureFormat;
+use crate::run::{
+    expect_mono_module_to_dylib, run_expects_with_memory, ExpectFilter, ExpectFunctions,
+    ExpectMemory, ExpectResults,
+};
+use crate::sandbox::Sandbox;
+
+/// Held while the expects of any session run.
+static RUNNING: Mutex<()> = Mutex::new(());
+
+pub struct ExpectSession {
+    target: Triple,
+    memory: ExpectMemory<'static>,
+    /// The dylib of the latest build, until it's unloaded.
+    lib: Option<libloading::Library>,
+}
+
+// The buffer is a mapping that belongs to the session, and is only used by the thread that
+// runs the session's expects, while it holds [RUNNING].
+unsafe impl Send for ExpectSession {}
+
+impl ExpectSession {
+    pub fn new(target: Triple) -> Self {
+        Self {
+            target,
+            memory: ExpectMemory::create_unique(),
+            lib: None,
+        }
+    }
+
+    /// Builds the (matching) top-level expects of `loaded`, to run with [ExpectSession::run].
+    /// The dylib of the previous build is unloaded first, so its expects can't run anymore.
+    pub fn build<'a>(
+        &mut self,
+        arena: &'a Bump,
+        loaded: MonomorphizedModule<'a>,
+        opt_level: OptLevel,
+        filter: &ExpectFilter,
+    ) -> Result<(ExpectFunctions<'a>, STLayoutInterner<'a>), libloading::Error> {
+        // never have two builds loaded at once
+        self.unload();
+
+        let (lib, expects, layout_interner) = expect_mono_module_to_dylib(
+            arena,
+            self.target.clone(),
+            loaded,
+            opt_level,
+            LlvmBackendMode::CliTest,
+            false,
+            false,
+            None,
+            filter,
+        )?;
+
+        self.lib = Some(lib);
+
+        Ok((expects, layout_interner))
+    }
+
+    /// Runs `expects`, which must come from the latest [ExpectSession::build], and writes their
+    /// failures to `writer`. This waits for the expects of other sessions to finish first.
+    #[allow(clippy::too_many_arguments)]
+    pub fn run<'a, W: Write>(
+        &mut self,
+        writer: &mut W,
+        render_target: RenderTarget,
+        arena: &'a Bump,
+        interns: &'a Interns,
+        layout_interner: &GlobalLayoutInterner<'a>,
+        expectations: &mut VecMap<ModuleId, Expectations>,
+        expects: ExpectFunctions<'_>,
+        timeout: Option<Duration>,
+        fx_timeout: Option<Duration>,
+        format: FailureFormat,
+    ) -> std::io::Result<ExpectResults> {
+        let lib = match &self.lib {
+            Some(lib) => lib,
+            None => internal_error!("the expects of a session ran before it built them"),
+        };
+
+        // a session that panicked while running can't have left anything the next run needs
+        let _running = RUNNING.lock().unwrap_or_else(PoisonError::into_inner);
+
+        run_expects_with_memory(
+            writer,
+            render_target,
+            arena,
+            interns,
+            layout_interner,
+            lib,
+            expectations,
+            expects,
+            &mut self.memory,
+            Sandbox::NONE,
+            timeout,
+            fx_timeout,
+            None,
+            format,
+        )
+    }
+
+    /// Unloads the dylib of the latest build, e.g. while the program has errors and there is
+    /// nothing to run. Building again loads a new one.
+    pub fn unload(&mut self) {
+        self.lib = None;
+    }
+}
+
+impl Drop for ExpectSession {
+    fn drop(&mut self) {
+        // the buffer is removed when it's dropped, right after this
+        self.unload();
+    }
+}
@@ -0,0 +1,78 @@
+//! Naming the shared memory objects that expects write their frames to, and removing the ones
+//! that were left behind by processes that crashed or got killed, for `roc test --clean-shm`.
+//!
+//! Every name starts with [PREFIX], followed by the id of the process that created the object.
+//! `roc dev` has to use `/roc_expect_buffer_{pid}`, because that is the name the platform's host
+//! opens (see `expectFailedStartSharedFile` in expect.zig); everything else gets a unique name.
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub(crate) const PREFIX: &str = "roc_expect_";
+
+static NEXT_NAME: AtomicU32 = AtomicU32::new(0);
+
+/// A name that no other object of this process had, and that an object left behind by an earlier
+/// process with the same id almost certainly doesn't have. It fits in the 31 bytes macOS allows.
+pub(crate) fn unique_name() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.subsec_nanos());
+    let counter = NEXT_NAME.fetch_add(1, Ordering::Relaxed);
+
+    format!(
+        "/{PREFIX}{}_{:08x}",
+        std::process::id(),
+        nanos.wrapping_add(counter)
+    )
+}
+
+/// The process that created the object with this name (without the leading `/`), if it's
+/// one of ours.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) fn owner(name: &str) -> Option<libc::pid_t> {
+    name.strip_prefix(PREFIX)?
+        .split('_')
+        .find_map(|part| part.parse().ok())
+}
+
+#[cfg(target_os = "linux")]
+fn is_running(pid: libc::pid_t) -> bool {
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+
+    // it exists, but belongs to someone else
+    std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Removes the objects of processes that aren't running anymore, and returns their names.
+/// Only Linux can list shared memory objects.
+pub fn clean_stale() -> std::io::Result<Vec<String>> {
+    #[cfg(target_os = "linux")]
+    {
+        let mut removed = Vec::new();
+
+        for entry in std::fs::read_dir("/dev/shm")? {
+            let name = entry?.file_name().to_string_lossy().into_owned();
+
+            match owner(&name) {
+                Some(pid) if !is_running(pid) => {
+                    let path = std::ffi::CString::new(format!("/{name}")).unwrap();
+
+                    if unsafe { libc::shm_unlink(path.as_ptr()) } == 0 {
+                        removed.push(name);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(removed)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "shared memory objects can only be listed on Linux",
+    ))
+}
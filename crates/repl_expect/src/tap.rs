@@ -0,0 +1,118 @@
+//! [TAP version 13](https://testanything.org/tap-version-13-specification.html) output for
+//! `roc test --format tap`, which harnesses like `prove` know how to read.
+//!
+//! Every top-level expect becomes a test point as soon as it has run, named like the test cases
+//! of [crate::junit]. A failed or crashed expect gets a YAML block holding the report that would
+//! have been printed for it on the terminal, without colors. The plan comes last, since which
+//! expects run is only known once they have.
+use std::io::Write;
+
+use crate::junit::{failure_message, TestCase};
+
+pub const VERSION_LINE: &str = "TAP version 13";
+
+/// Writes the test point of the last of `cases`, which is numbered by its position.
+pub(crate) fn write_test_point(writer: &mut impl Write, cases: &[TestCase]) -> std::io::Result<()> {
+    let case = match cases.last() {
+        Some(case) => case,
+        None => return Ok(()),
+    };
+
+    let status = match case.failure {
+        None => "ok",
+        Some(_) => "not ok",
+    };
+
+    // `#` would start a directive, like `# SKIP`
+    let description = one_line(&case.name).replace('#', "\\#");
+
+    writeln!(writer, "{status} {} - {description}", cases.len())?;
+
+    if let Some(report) = &case.failure {
+        writeln!(writer, "  ---")?;
+        writeln!(writer, "  message: {}", quoted(&failure_message(report)))?;
+        writeln!(writer, "  severity: fail")?;
+        writeln!(writer, "  at:")?;
+        writeln!(
+            writer,
+            "    file: {}",
+            quoted(&case.path.display().to_string())
+        )?;
+        writeln!(writer, "    line: {}", case.line)?;
+        writeln!(writer, "    module: {}", quoted(&case.module))?;
+        writeln!(
+            writer,
+            "  duration_ms: {:.3}",
+            case.time.as_secs_f64() * 1000.0
+        )?;
+        writeln!(writer, "  report: |-")?;
+
+        for line in report.lines() {
+            match line.trim_end() {
+                "" => writeln!(writer)?,
+                line => writeln!(writer, "    {}", printable(line))?,
+            }
+        }
+
+        writeln!(writer, "  ...")?;
+    }
+
+    writer.flush()
+}
+
+/// The plan, which says how many test points there were, and how many expects were left out.
+pub fn write_plan(
+    writer: &mut impl Write,
+    cases: &[TestCase],
+    filtered_out: usize,
+    skipped: usize,
+) -> std::io::Result<()> {
+    if cases.is_empty() {
+        writeln!(writer, "1..0 # SKIP no expectations were found")?;
+    } else {
+        writeln!(writer, "1..{}", cases.len())?;
+    }
+
+    if filtered_out > 0 {
+        writeln!(writer, "# {filtered_out} filtered out")?;
+    }
+
+    if skipped > 0 {
+        let failed = cases.iter().filter(|case| case.failure.is_some()).count();
+
+        writeln!(writer, "# {skipped} skipped after {failed} failed")?;
+    }
+
+    Ok(())
+}
+
+fn one_line(text: &str) -> String {
+    text.lines().collect::<Vec<_>>().join(" ")
+}
+
+/// YAML can't contain most control characters, not even in block scalars.
+fn printable(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_control() || *c == '\t')
+        .collect()
+}
+
+/// A double-quoted YAML scalar.
+fn quoted(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+
+    quoted.push('"');
+
+    for c in printable(&one_line(text)).chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+
+    quoted
+}
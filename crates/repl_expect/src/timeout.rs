@@ -0,0 +1,259 @@
+//! Stopping pure expects that run for too long, for `roc test --timeout-ms`.
+//!
+//! Pure expects run in our own process, so there is no child to kill. Instead, once the timeout
+//! has passed, a watchdog thread interrupts the thread running the expect with `SIGUSR2`, and the
+//! signal handler makes the expect crash by calling the dylib's `roc_expect_crash`. That jumps
+//! back to where the expect was called, like a `crash` in Roc code does.
+//!
+//! Jumping away is only safe while the expect runs code of the dylib itself: in the middle of
+//! e.g. `malloc`, it would leave the allocator's lock taken. So when the signal interrupts
+//! anything else, the handler does nothing, and the watchdog tries again a moment later.
+//!
+//! The REPL stops expressions the same way, when they run for too long or Ctrl-C is pressed,
+//! see [ExpectTimeout::start_cancellable].
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Once};
+use std::time::{Duration, Instant};
+
+use roc_gen_llvm::llvm::externs::EXPORTED_CRASH_FN;
+use roc_std::RocStr;
+
+/// Whether we know where the interrupted code is on this platform, see [crate::profile].
+pub const IS_SUPPORTED: bool = cfg!(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+));
+
+/// How long the watchdog waits before interrupting the expect again, when the last signal came
+/// at a moment it wasn't safe to stop it.
+const RETRY_INTERVAL: Duration = Duration::from_millis(1);
+
+/// How often the watchdog checks whether it was asked to stop the code early.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The dylib can't have more executable mappings than this.
+const MAX_CODE_RANGES: usize = 8;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const NO_ADDRESS: AtomicUsize = AtomicUsize::new(0);
+
+/// The start and end of each executable mapping of the dylib, as pairs.
+static CODE_RANGES: [AtomicUsize; 2 * MAX_CODE_RANGES] = [NO_ADDRESS; 2 * MAX_CODE_RANGES];
+/// The address of the dylib's `roc_expect_crash`.
+static CRASH_FN: AtomicUsize = AtomicUsize::new(0);
+/// The thread running the expect, while a timer is armed.
+static TIMED_THREAD: AtomicUsize = AtomicUsize::new(0);
+static ARMED: AtomicBool = AtomicBool::new(false);
+static TIMED_OUT: AtomicBool = AtomicBool::new(false);
+
+/// A crash message that doesn't need allocating. The crash is reported as a timeout instead.
+static CRASH_MESSAGE: CrashMessage = CrashMessage(RocStr::empty());
+
+struct CrashMessage(RocStr);
+
+// An empty RocStr is small, so it has no pointer that could be shared.
+unsafe impl Sync for CrashMessage {}
+
+/// Times out the pure expects of one dylib.
+#[derive(Debug, Clone, Copy)]
+pub struct ExpectTimeout {
+    /// [Duration::MAX] for code that only stops when it's cancelled
+    pub duration: Duration,
+}
+
+impl ExpectTimeout {
+    /// `None` if this platform isn't supported, or `lib` wasn't built with
+    /// [add_exported_crash](roc_gen_llvm::llvm::externs::add_exported_crash).
+    pub fn new(lib: &libloading::Library, duration: Duration) -> Option<Self> {
+        if !IS_SUPPORTED {
+            return None;
+        }
+
+        let crash_fn = unsafe {
+            let symbol: libloading::Symbol<*const libc::c_void> =
+                lib.get(EXPORTED_CRASH_FN.as_bytes()).ok()?;
+
+            *symbol
+        };
+
+        let mut info: libc::Dl_info = unsafe { std::mem::zeroed() };
+        if unsafe { libc::dladdr(crash_fn, &mut info) } == 0 || info.dli_fname.is_null() {
+            return None;
+        }
+
+        let path = unsafe { std::ffi::CStr::from_ptr(info.dli_fname) };
+        let ranges = executable_mappings(path.to_str().ok()?);
+
+        if ranges.is_empty() || ranges.len() > MAX_CODE_RANGES {
+            return None;
+        }
+
+        for (index, bound) in CODE_RANGES.iter().enumerate() {
+            let value = match ranges.get(index / 2) {
+                Some((start, end)) => [*start, *end][index % 2],
+                None => 0,
+            };
+
+            bound.store(value, Ordering::SeqCst);
+        }
+
+        CRASH_FN.store(crash_fn as usize, Ordering::SeqCst);
+
+        static INSTALL: Once = Once::new();
+
+        // The handler stays installed: a signal sent just before the expect finished may still
+        // arrive afterwards, and the default action for SIGUSR2 is to exit.
+        INSTALL.call_once(|| unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = on_sigusr2 as usize;
+            // Jumping out of the handler skips restoring the signal mask, so don't block SIGUSR2
+            // while it runs, or the next expect could never be interrupted.
+            action.sa_flags = libc::SA_SIGINFO | libc::SA_NODEFER;
+            libc::sigemptyset(&mut action.sa_mask);
+
+            libc::sigaction(libc::SIGUSR2, &action, std::ptr::null_mut());
+        });
+
+        Some(Self { duration })
+    }
+
+    /// Starts the timer for an expect about to run on the current thread.
+    pub fn start(&self) -> Timer {
+        self.start_help(None)
+    }
+
+    /// Like [ExpectTimeout::start], but the code is also stopped as soon as `cancel` is set,
+    /// which a signal handler can do, e.g. for Ctrl-C.
+    pub fn start_cancellable(&self, cancel: &'static AtomicBool) -> Timer {
+        self.start_help(Some(cancel))
+    }
+
+    fn start_help(&self, cancel: Option<&'static AtomicBool>) -> Timer {
+        let thread = unsafe { libc::pthread_self() };
+
+        TIMED_THREAD.store(thread as usize, Ordering::SeqCst);
+        TIMED_OUT.store(false, Ordering::SeqCst);
+        ARMED.store(true, Ordering::SeqCst);
+
+        let (done, finished) = mpsc::channel::<()>();
+        let duration = self.duration;
+
+        let watchdog = std::thread::spawn(move || {
+            let deadline = Instant::now().checked_add(duration);
+
+            loop {
+                let mut wait = match deadline {
+                    Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+                    None => CANCEL_POLL_INTERVAL,
+                };
+
+                if cancel.is_some() {
+                    wait = wait.min(CANCEL_POLL_INTERVAL);
+                }
+
+                if finished.recv_timeout(wait) != Err(mpsc::RecvTimeoutError::Timeout) {
+                    return;
+                }
+
+                let cancelled = cancel.map_or(false, |cancel| cancel.load(Ordering::SeqCst));
+                let timed_out = deadline.map_or(false, |deadline| Instant::now() >= deadline);
+
+                if cancelled || timed_out {
+                    break;
+                }
+            }
+
+            while !TIMED_OUT.load(Ordering::SeqCst) {
+                unsafe { libc::pthread_kill(thread, libc::SIGUSR2) };
+
+                if finished.recv_timeout(RETRY_INTERVAL) != Err(mpsc::RecvTimeoutError::Timeout) {
+                    return;
+                }
+            }
+        });
+
+        Timer { done, watchdog }
+    }
+}
+
+/// A running timer, see [ExpectTimeout::start].
+pub struct Timer {
+    done: mpsc::Sender<()>,
+    watchdog: std::thread::JoinHandle<()>,
+}
+
+impl Timer {
+    /// Stops the timer after the expect returned, and returns whether it was stopped because it
+    /// timed out, or was cancelled.
+    pub fn stop(self) -> bool {
+        ARMED.store(false, Ordering::SeqCst);
+
+        let _ = self.done.send(());
+        let _ = self.watchdog.join();
+
+        TIMED_OUT.load(Ordering::SeqCst)
+    }
+}
+
+/// The executable mappings of the file at `path`, from `/proc/self/maps`.
+fn executable_mappings(path: &str) -> Vec<(usize, usize)> {
+    let maps = std::fs::read_to_string("/proc/self/maps").unwrap_or_default();
+
+    maps.lines()
+        .filter_map(|line| {
+            // like `7f3a1c000000-7f3a1c021000 r-xp 00001000 08:01 1234 /tmp/roc_app.so`
+            let mut fields = line.split_whitespace();
+            let (start, end) = fields.next()?.split_once('-')?;
+            let permissions = fields.next()?;
+            let mapped_path = fields.nth(3)?;
+
+            if !permissions.contains('x') || mapped_path != path {
+                return None;
+            }
+
+            let start = usize::from_str_radix(start, 16).ok()?;
+            let end = usize::from_str_radix(end, 16).ok()?;
+
+            Some((start, end))
+        })
+        .collect()
+}
+
+extern "C" fn on_sigusr2(
+    _signal: libc::c_int,
+    _info: *mut libc::siginfo_t,
+    context: *mut libc::c_void,
+) {
+    // only atomics in here: we may have interrupted anything, including the allocator
+    if !ARMED.load(Ordering::SeqCst)
+        || unsafe { libc::pthread_self() } as usize != TIMED_THREAD.load(Ordering::SeqCst)
+    {
+        return;
+    }
+
+    let pc = match unsafe { crate::profile::interrupted_pc(context) } {
+        Some(pc) => pc,
+        None => return,
+    };
+
+    let in_dylib = CODE_RANGES.chunks(2).any(|range| {
+        let start = range[0].load(Ordering::Relaxed);
+        let end = range[1].load(Ordering::Relaxed);
+
+        start <= pc && pc < end
+    });
+
+    if !in_dylib {
+        return;
+    }
+
+    TIMED_OUT.store(true, Ordering::SeqCst);
+
+    unsafe {
+        let crash: extern "C" fn(*const RocStr, u32) =
+            std::mem::transmute(CRASH_FN.load(Ordering::SeqCst));
+
+        // does not return
+        crash(&CRASH_MESSAGE.0, 0);
+    }
+}
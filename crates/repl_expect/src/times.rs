@@ -0,0 +1,56 @@
+//! How long each top-level expect took, for `roc test --show-times`, which lists the slowest
+//! ones, so that an expect that accidentally takes exponential time stands out.
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use roc_region::all::Region;
+
+use crate::junit::comment_and_line;
+
+/// How long one top-level expect ran, from starting it until its failure (if any) was reported.
+/// For an effectful expect, that includes starting the process it runs in.
+#[derive(Debug, Clone)]
+pub struct ExpectTime {
+    pub path: PathBuf,
+    pub region: Region,
+    pub time: Duration,
+}
+
+/// Lists the `count` expects that took the longest, slowest first, with the comment right above
+/// each one, if there is one.
+pub fn write_slowest(
+    writer: &mut impl Write,
+    times: &[ExpectTime],
+    count: usize,
+) -> std::io::Result<()> {
+    let mut slowest: Vec<&ExpectTime> = times.iter().collect();
+
+    // the sort is stable, so expects that took as long stay in the order they ran in
+    slowest.sort_by(|a, b| b.time.cmp(&a.time));
+    slowest.truncate(count);
+
+    match slowest.len() {
+        0 => return Ok(()),
+        1 => writeln!(writer, "The slowest expect:\n")?,
+        n => writeln!(writer, "The {n} slowest expects:\n")?,
+    }
+
+    for expect in slowest {
+        let (comment, line) = comment_and_line(&expect.path, expect.region);
+        let millis = expect.time.as_secs_f64() * 1000.0;
+
+        write!(
+            writer,
+            "{millis:>10.1} ms  {}:{line}",
+            expect.path.display()
+        )?;
+
+        match comment {
+            Some(comment) => writeln!(writer, "  # {comment}")?,
+            None => writeln!(writer)?,
+        }
+    }
+
+    writeln!(writer)
+}
@@ -0,0 +1,281 @@
+//! Running top-level expects compiled to wasm32, for `roc test --target wasm32`.
+//!
+//! The expects run in [roc_wasm_interp], which has no shared memory to write failures to.
+//! Instead, we allocate a buffer in the module's linear memory, the expect writes the frame of a
+//! failure to it (in the same format as native expects do), and then calls the
+//! `roc_expect_failed(ptr, len)` import. That copies the frame out, and it gets rendered by the
+//! same code that renders the frames of native expects.
+use bumpalo::Bump;
+use roc_collections::VecMap;
+use roc_error_macros::internal_error;
+use roc_gen_llvm::llvm::externs::EXPECT_FAILED_IMPORT;
+use roc_load::Expectations;
+use roc_module::symbol::{Interns, ModuleId};
+use roc_mono::layout::GlobalLayoutInterner;
+use roc_reporting::{error::expect::Renderer, report::RenderTarget};
+use roc_target::TargetInfo;
+use roc_wasm_interp::{wasi, ImportDispatcher, Instance, Value, WasiDispatcher};
+use roc_wasm_module::WasmModule;
+
+use crate::frame_dump::FrameDump;
+use crate::json::{self, FailureFormat, Location};
+use crate::junit::Tee;
+use crate::run::{render_expect_failure, ExpectFunctions, ExpectResults, ExpectSequence};
+
+/// The functions of the module that the runner calls, besides the expects themselves.
+pub(crate) const EXPORTS: &[&str] = &["malloc", "set_shared_buffer"];
+
+/// How big the buffer for frames is. There is no parent process to grow it, so a bigger frame
+/// makes the expect crash, like it does on macOS.
+const BUFFER_CAPACITY: u32 = 1 << 20;
+
+/// Enough for the `RocCallResult` an expect writes when it returns.
+const OUTPUT_SIZE: i32 = 32;
+
+/// What a wasm32 expect sent us while it ran.
+struct ExpectImports<'a> {
+    wasi: WasiDispatcher<'a>,
+    /// The frames of the failures, each as if it was the only one in the buffer.
+    frames: Vec<Vec<u8>>,
+    /// The message of a `crash`, or of a builtin that panicked.
+    panic: Option<String>,
+}
+
+impl<'a> ImportDispatcher for ExpectImports<'a> {
+    fn dispatch(
+        &mut self,
+        module_name: &str,
+        function_name: &str,
+        arguments: &[Value],
+        memory: &mut [u8],
+    ) -> Option<Value> {
+        if module_name == wasi::MODULE_NAME {
+            return self.wasi.dispatch(function_name, arguments, memory);
+        }
+
+        match function_name {
+            EXPECT_FAILED_IMPORT => {
+                let ptr = arguments[0].expect_i32().unwrap() as usize;
+                let length = arguments[1].expect_i32().unwrap() as usize;
+
+                self.frames.push(memory[ptr..ptr + length].to_vec());
+
+                // Forget the frame, so the next failure is written to the start of the buffer.
+                write_u32(memory, ptr, 0);
+                write_u32(memory, ptr + 4, ExpectSequence::START_OFFSET as u32);
+
+                None
+            }
+            "roc_panic" => {
+                let message_ptr = arguments[0].expect_i32().unwrap() as usize;
+
+                self.panic = Some(read_roc_str(memory, message_ptr));
+
+                // The instruction after the call traps, so the expect stops here.
+                None
+            }
+            _ => internal_error!(
+                "wasm32 expects don't import {}.{}",
+                module_name,
+                function_name
+            ),
+        }
+    }
+}
+
+/// Runs the top-level expects of a module built by
+/// [expect_mono_module_to_wasm](crate::run::expect_mono_module_to_wasm), each in a fresh instance
+/// so a crash in one doesn't affect the others. Effectful expects can't run in the interpreter,
+/// and are skipped.
+#[allow(clippy::too_many_arguments)]
+pub fn run_toplevel_expects_wasm<'a, W: std::io::Write>(
+    writer: &mut W,
+    render_target: RenderTarget,
+    arena: &'a Bump,
+    interns: &'a Interns,
+    layout_interner: &GlobalLayoutInterner<'a>,
+    wasm_bytes: &[u8],
+    expectations: &mut VecMap<ModuleId, Expectations>,
+    expects: ExpectFunctions<'_>,
+    frame_dump: Option<&FrameDump>,
+    format: FailureFormat,
+) -> std::io::Result<ExpectResults> {
+    let mut results = ExpectResults {
+        filtered_out: expects.filtered_out,
+        ..ExpectResults::default()
+    };
+
+    if !expects.fx.is_empty() && format.allows_notes() {
+        writeln!(
+            writer,
+            "Skipping {} effectful expectations, which can't run on wasm32 yet.\n",
+            expects.fx.len()
+        )?;
+    }
+
+    let module_arena = Bump::new();
+    let module = WasmModule::preload(&module_arena, wasm_bytes, false)
+        .unwrap_or_else(|error| internal_error!("Invalid wasm module for expects: {:?}", error));
+
+    let target_info = TargetInfo::default_wasm32();
+
+    for expect in expects.pure {
+        if results.should_stop(expects.max_failures) {
+            results.skipped += 1;
+            continue;
+        }
+
+        let start = std::time::Instant::now();
+        // the test point of a TAP stream goes here, after the report went into `tee`
+        let output = &mut *writer;
+        let mut tee = Tee::new(&mut *output, format);
+        let writer = &mut tee;
+
+        let instance_arena = Bump::new();
+        let imports = ExpectImports {
+            wasi: WasiDispatcher::default(),
+            frames: Vec::new(),
+            panic: None,
+        };
+
+        let mut instance = Instance::for_module(&instance_arena, &module, imports, false)
+            .unwrap_or_else(|error| internal_error!("Could not instantiate expects: {}", error));
+
+        let buffer = call_malloc(&mut instance, BUFFER_CAPACITY as i32);
+
+        write_u32(&mut instance.memory, buffer, 0);
+        write_u32(
+            &mut instance.memory,
+            buffer + 4,
+            ExpectSequence::START_OFFSET as u32,
+        );
+        write_u32(&mut instance.memory, buffer + 16, BUFFER_CAPACITY);
+        write_u32(&mut instance.memory, buffer + 20, 0);
+
+        let buffer_args = [
+            Value::I32(buffer as i32),
+            Value::I32(BUFFER_CAPACITY as i32),
+        ];
+        instance
+            .call_export("set_shared_buffer", buffer_args)
+            .unwrap_or_else(|error| internal_error!("set_shared_buffer failed: {}", error));
+
+        let output = call_malloc(&mut instance, OUTPUT_SIZE);
+        let result = instance.call_export(expect.name, [Value::I32(output as i32)]);
+
+        let ExpectImports { frames, panic, .. } = instance.import_dispatcher;
+
+        // A trap without a roc_panic is a bug in the compiler, or in the interpreter.
+        let crash = match (result, panic) {
+            (_, Some(message)) => Some(message),
+            (Err(error), None) => Some(error),
+            (Ok(_), None) => None,
+        };
+
+        let passed = crash.is_none() && frames.is_empty();
+
+        if !passed {
+            let module_id = expect.symbol.module_id();
+            let data = expectations.get_mut(&module_id).unwrap();
+
+            let path = data.path.to_owned();
+            let source = std::fs::read_to_string(&path)?;
+
+            let mut renderer = Renderer::new(
+                arena,
+                interns,
+                render_target,
+                module_id,
+                path.clone(),
+                &source,
+            );
+
+            renderer.set_value_limits(expects.value_limits);
+
+            for frame in frames {
+                render_expect_failure(
+                    writer,
+                    &renderer,
+                    arena,
+                    Some(expect),
+                    expectations,
+                    interns,
+                    layout_interner,
+                    target_info,
+                    frame.as_ptr(),
+                    ExpectSequence::START_OFFSET,
+                    frame_dump,
+                    format,
+                )?;
+            }
+
+            if let Some(message) = crash {
+                match format {
+                    FailureFormat::Human | FailureFormat::Junit | FailureFormat::Tap => {
+                        renderer.render_panic(writer, &message, expect.region)?
+                    }
+                    FailureFormat::Json => {
+                        let location = Location {
+                            module_id,
+                            path: &path,
+                            source: &source,
+                            region: expect.region,
+                        };
+
+                        json::write_crash(writer, interns, location, &message)?
+                    }
+                }
+            }
+
+            if format.is_human() {
+                writeln!(writer)?;
+            }
+        }
+
+        let time = start.elapsed();
+
+        results.record(expectations, &expect, passed, time);
+        results.record_case(interns, expectations, &expect, passed, time, tee);
+
+        if format == FailureFormat::Tap {
+            crate::tap::write_test_point(output, &results.cases)?;
+        }
+    }
+
+    Ok(results)
+}
+
+fn call_malloc<I: ImportDispatcher>(instance: &mut Instance<'_, I>, size: i32) -> usize {
+    instance
+        .call_export("malloc", [Value::I32(size)])
+        .ok()
+        .flatten()
+        .and_then(|value| value.expect_i32().ok())
+        .filter(|ptr| *ptr != 0)
+        .unwrap_or_else(|| internal_error!("malloc failed in the wasm32 expects")) as usize
+}
+
+fn write_u32(memory: &mut [u8], addr: usize, value: u32) {
+    memory[addr..addr + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn read_u32(memory: &[u8], addr: usize) -> u32 {
+    u32::from_le_bytes(memory[addr..addr + 4].try_into().unwrap())
+}
+
+/// Reads a wasm32 `RocStr`: a pointer, length and capacity of 4 bytes each, or up to 11 bytes
+/// inline, when the last byte has its highest bit set.
+fn read_roc_str(memory: &[u8], addr: usize) -> String {
+    let last_byte = memory[addr + 11];
+
+    let (start, length) = if last_byte & 0x80 != 0 {
+        (addr, (last_byte & 0x7F) as usize)
+    } else {
+        (
+            read_u32(memory, addr) as usize,
+            read_u32(memory, addr + 4) as usize,
+        )
+    };
+
+    String::from_utf8_lossy(&memory[start..start + length]).into_owned()
+}
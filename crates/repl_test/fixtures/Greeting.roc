@@ -0,0 +1,6 @@
+interface Greeting
+    exposes [greet]
+    imports []
+
+greet : Str -> Str
+greet = \name -> "Hello, \(name)!"
@@ -1,5 +1,8 @@
 use indoc::indoc;
-use roc_repl_cli::repl_state::{is_incomplete, ReplState, TIPS};
+use roc_repl_cli::platform::ReplPlatform;
+use roc_repl_cli::repl_state::{is_incomplete, OutputFormat, ReplState, TIPS};
+use roc_repl_eval::gen::ReplDeps;
+use std::path::{Path, PathBuf};
 
 // These are tests of the REPL state machine. They work without actually
 // running the CLI, and without using rustyline, and instead verify
@@ -101,6 +104,224 @@ fn standalone_annotation() {
     assert_eq!(state.step(&input, None), Ok(String::new()));
 }
 
+#[test]
+fn prelude_defs() {
+    let mut state = ReplState::new();
+
+    let prelude = indoc!(
+        r#"
+        x = 5
+
+        double : Num a -> Num a
+        double = \n ->
+            y = 2
+
+            n * y
+        "#
+    );
+
+    assert_eq!(state.load_prelude(prelude), Ok(()));
+
+    complete("double x", &mut state, Ok(("10 : Num *", "val1")));
+}
+
+#[test]
+fn prelude_with_problems() {
+    let mut state = ReplState::new();
+
+    let prelude = indoc!(
+        r#"
+        x = 5
+
+        y = notDefined + 1
+        "#
+    );
+
+    assert!(state.load_prelude(prelude).is_err());
+
+    // the broken def was skipped, but the others are available
+    complete("x", &mut state, Ok(("5 : Num *", "x")));
+}
+
+#[test]
+fn json_output() {
+    let mut state = ReplState::new();
+
+    state.set_output_format(OutputFormat::Json);
+
+    assert_eq!(
+        state.step("{ name: \"Roc\", tags: [Ok 1, Err \"no\"] }", None),
+        Ok(r#"{"name":"Roc","tags":[{"Ok":1},{"Err":"no"}]}"#.to_string())
+    );
+    // values still get names, so later expressions can refer to them
+    assert_eq!(state.step("val1.name", None), Ok(r#""Roc""#.to_string()));
+}
+
+#[test]
+fn set_output() {
+    let mut state = ReplState::new();
+
+    assert_eq!(state.step(":set output json", None), Ok(String::new()));
+    assert_eq!(state.step("1 == 1", None), Ok("true".to_string()));
+
+    assert_eq!(state.step(":set output roc", None), Ok(String::new()));
+    complete("1 + 1", &mut state, Ok(("2 : Num *", "val2")));
+
+    assert!(state
+        .step(":set output yaml", None)
+        .unwrap()
+        .contains(":set output json"));
+}
+
+#[test]
+fn time() {
+    let mut state = ReplState::new();
+
+    let output = state.step(":time 1 + 1", None).unwrap();
+    assert!(output.contains("2 : Num *"));
+    assert!(output.contains("Ran in "));
+    assert!(output.contains("no allocations"));
+
+    // measured expressions don't get names
+    complete("3", &mut state, Ok(("3 : Num *", "val1")));
+
+    let output = state
+        .step(
+            ":time Str.concat \"a string that is too long to be small\" \"!\"",
+            None,
+        )
+        .unwrap();
+    assert!(output.contains("1 allocation"));
+
+    assert!(state.step(":time", None).unwrap().contains("Usage"));
+}
+
+#[test]
+fn bench() {
+    let mut state = ReplState::new();
+
+    let output = state.step(":bench List.repeat 1 100", None).unwrap();
+    assert!(output.contains("times in"));
+    assert!(output.contains("median"));
+    assert!(output.contains("Each run had 1 allocation"));
+
+    assert!(is_incomplete(":bench when 1 is"));
+}
+
+#[test]
+fn eval_once() {
+    let mut state = ReplState::new();
+
+    assert_eq!(state.eval_once("1 + 2"), Ok("3".to_string()));
+    assert!(state.eval_once("notDefined + 1").is_err());
+}
+
+#[test]
+fn eval_once_with_dep() {
+    let mut state = ReplState::new();
+    let greeting = PathBuf::from(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/fixtures/Greeting.roc"
+    ));
+
+    state.set_deps(ReplDeps::from_files(&[greeting]).unwrap());
+
+    assert_eq!(
+        state.eval_once("Greeting.greet \"Roc\""),
+        Ok("\"Hello, Roc!\"".to_string())
+    );
+}
+
+#[test]
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+fn timeout() {
+    let mut state = ReplState::new();
+
+    state.set_limits(roc_repl_cli::limits::EvalLimits {
+        timeout: Some(std::time::Duration::from_millis(200)),
+        max_memory: None,
+    });
+
+    // xorshift, so LLVM can't work out the result without looping
+    state
+        .step(
+            "spin = \\n, x -> if n == 0 then x else spin (n - 1) (Num.bitwiseXor x (Num.shiftLeftBy x 13))",
+            None,
+        )
+        .unwrap();
+
+    let problems = state.eval_once("spin 100000000000000 1u64").unwrap_err();
+
+    assert!(problems.contains("TIMED OUT"));
+    assert!(problems.contains("time limit of 200 ms"));
+
+    // the session goes on
+    assert_eq!(state.eval_once("1 + 2"), Ok("3".to_string()));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn max_memory() {
+    let mut state = ReplState::new();
+
+    state.set_limits(roc_repl_cli::limits::EvalLimits {
+        timeout: None,
+        max_memory: Some(1024 * 1024),
+    });
+
+    let problems = state
+        .eval_once("List.range { start: At 0, end: At 100000000000 } |> List.len")
+        .unwrap_err();
+
+    assert!(problems.contains("OUT OF MEMORY"));
+    assert!(problems.contains("memory limit of 1.0 MiB"));
+
+    assert_eq!(state.eval_once("1 + 2"), Ok("3".to_string()));
+}
+
+#[test]
+fn deps_must_be_modules() {
+    let not_a_module = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"));
+
+    assert!(ReplDeps::from_files(&[not_a_module]).is_err());
+}
+
+const PLATFORM: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../examples/gui/breakout/platform/main.roc"
+);
+
+#[test]
+fn platform_modules_are_effectful() {
+    let platform = ReplPlatform::load(Path::new(PLATFORM)).unwrap();
+
+    assert!(platform.is_effectful("Game.translate elem"));
+    assert!(!platform.is_effectful("MyGame.translate elem"));
+    assert!(!platform.is_effectful("1 + 1"));
+}
+
+#[test]
+fn pure_expressions_ignore_the_platform() {
+    let mut state = ReplState::new();
+
+    state.set_platform(ReplPlatform::load(Path::new(PLATFORM)).unwrap());
+
+    complete("1 + 1", &mut state, Ok(("2 : Num *", "val1")));
+}
+
+#[test]
+fn platform_needs_a_platform_header() {
+    let app = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../cli_testing_examples/benchmarks/NQueens.roc"
+    );
+
+    assert!(ReplPlatform::load(Path::new(app)).is_err());
+}
+
 /// validate and step the given input, then check the Result vs the output
 /// with ANSI escape codes stripped.
 fn complete(input: &str, state: &mut ReplState, expected_step_result: Result<(&str, &str), i32>) {
@@ -1322,3 +1322,25 @@ fn ordered_tag_union_memory_layout() {
         r#"Height 1 { column: 3, line: 2 } : Node"#,
     );
 }
+
+#[cfg(not(feature = "wasm"))]
+#[test]
+fn crash_in_expression() {
+    expect_failure(
+        r#"if Bool.true then crash "oh no" else 1"#,
+        indoc!(
+            r#"
+                ── CRASH ───────────────────────────────────────────────────────────────────────
+
+                This expression crashed:
+
+                4│      if Bool.true then crash "oh no" else 1
+                        ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+
+                The crash reported this message:
+
+                oh no
+                "#
+        ),
+    );
+}
@@ -7,7 +7,7 @@ use roc_load::MonomorphizedModule;
 use roc_parse::ast::Expr;
 use roc_repl_eval::{
     eval::jit_to_ast,
-    gen::{compile_to_mono, format_answer},
+    gen::{compile_to_mono, format_answer, ReplDeps},
     ReplApp, ReplAppMemory,
 };
 use roc_reporting::report::DEFAULT_PALETTE_HTML;
@@ -186,6 +186,7 @@ pub async fn entrypoint_from_js(src: String) -> Result<String, String> {
         arena,
         std::iter::empty(),
         &src,
+        &ReplDeps::default(),
         target_info,
         DEFAULT_PALETTE_HTML,
     ) {
@@ -296,6 +297,7 @@ pub async fn entrypoint_from_js(src: String) -> Result<String, String> {
         &interns,
         layout_interner.into_global().fork(),
         target_info,
+        None,
     );
 
     let var_name = String::new(); // TODO turn this into something like " # val1"
@@ -0,0 +1,143 @@
+//! Baselines let `roc check` tell the warnings a project already had apart from new ones, so
+//! CI can fail on new warnings while the old ones get fixed over time.
+//!
+//! A baseline file lists the fingerprints of the warnings it accepts, one per line. Each line
+//! also says which warning it was, but only for humans reading the file; only the fingerprint
+//! is compared.
+use std::fmt;
+
+use roc_collections::MutMap;
+use roc_region::all::Region;
+
+/// Identifies a warning across runs, even after code above it moves it to another line.
+///
+/// It's a hash of the warning's title, the module it's in, the code the warning points at,
+/// and the lines that code is on, all with whitespace normalized.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fingerprint(u64);
+
+impl Fingerprint {
+    pub fn new(title: &str, module_name: &str, region: Option<Region>, src: &str) -> Self {
+        let mut hasher = Fnv1a::default();
+
+        hasher.write(title.as_bytes());
+        hasher.write(module_name.as_bytes());
+
+        if let Some(region) = region {
+            let start = (region.start().offset as usize).min(src.len());
+            let end = (region.end().offset as usize).clamp(start, src.len());
+            let line_start = src[..start].rfind('\n').map_or(0, |newline| newline + 1);
+            let line_end = src[end..]
+                .find('\n')
+                .map_or(src.len(), |newline| end + newline);
+
+            hasher.write_normalized(&src[start..end]);
+            hasher.write_normalized(&src[line_start..line_end]);
+        }
+
+        Fingerprint(hasher.0)
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// The 64-bit FNV-1a hash. Unlike the hashers in std, it's guaranteed to give the same
+/// fingerprints no matter which version of Rust built the compiler.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+
+        // Separates this field from the next, so "ab" + "c" and "a" + "bc" differ.
+        self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+    }
+
+    /// Reindenting code or reflowing it across lines shouldn't change a fingerprint.
+    fn write_normalized(&mut self, text: &str) {
+        let words: Vec<&str> = text.split_whitespace().collect();
+
+        self.write(words.join(" ").as_bytes());
+    }
+}
+
+/// The warnings accepted by a baseline file, and the warnings seen while checking against it.
+#[derive(Debug, Default)]
+pub struct Baseline {
+    /// The same warning can occur more than once, so this counts each fingerprint.
+    accepted: MutMap<Fingerprint, usize>,
+    seen: Vec<(Fingerprint, String)>,
+}
+
+impl Baseline {
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut accepted = MutMap::default();
+
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let hex = line.split_whitespace().next().unwrap_or_default();
+            let fingerprint = u64::from_str_radix(hex, 16)
+                .map_err(|_| format!("line {} does not start with a fingerprint", index + 1))?;
+
+            *accepted.entry(Fingerprint(fingerprint)).or_insert(0) += 1;
+        }
+
+        Ok(Baseline {
+            accepted,
+            seen: Vec::new(),
+        })
+    }
+
+    /// Records a warning, and returns whether the baseline accepts it. Each line of the
+    /// baseline accepts only one occurrence of its warning.
+    pub fn accepts(&mut self, fingerprint: Fingerprint, description: String) -> bool {
+        self.seen.push((fingerprint, description));
+
+        match self.accepted.get_mut(&fingerprint) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn warnings_seen(&self) -> usize {
+        self.seen.len()
+    }
+
+    /// A baseline file that accepts every warning seen so far.
+    pub fn to_file_contents(&self) -> String {
+        let mut seen: Vec<_> = self.seen.iter().collect();
+        seen.sort();
+
+        let mut contents = String::from(
+            "# Warnings `roc check --baseline` accepts. Regenerate with `roc check --baseline <file> --write-baseline`.\n",
+        );
+
+        for (fingerprint, description) in seen {
+            contents.push_str(&format!("{} {}\n", fingerprint, description));
+        }
+
+        contents
+    }
+}
@@ -2,14 +2,21 @@ use std::path::PathBuf;
 
 use roc_collections::MutMap;
 use roc_module::symbol::{Interns, ModuleId};
-use roc_region::all::LineInfo;
+use roc_region::all::{LineInfo, Region};
 use roc_solve_problem::TypeError;
 
+use crate::baseline::{Baseline, Fingerprint};
+
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub struct Problems {
     pub fatally_errored: bool,
     pub errors: usize,
     pub warnings: usize,
+    /// Warnings that weren't reported, because the baseline accepts them.
+    pub baselined_warnings: usize,
+    /// Type errors that weren't reported, because they follow from an earlier error
+    /// or go past the maximum number of type errors.
+    pub hidden_errors: usize,
 }
 
 impl Problems {
@@ -57,6 +64,8 @@ pub fn report_problems(
     interns: &Interns,
     can_problems: &mut MutMap<ModuleId, Vec<roc_problem::can::Problem>>,
     type_problems: &mut MutMap<ModuleId, Vec<TypeError>>,
+    mut baseline: Option<&mut Baseline>,
+    max_type_errors: Option<usize>,
 ) -> Problems {
     use crate::report::{can_problem, type_problem, Report, RocDocAllocator, DEFAULT_PALETTE};
     use roc_problem::Severity::*;
@@ -67,6 +76,9 @@ pub fn report_problems(
     let mut warnings = Vec::with_capacity(total_problems);
     let mut errors = Vec::with_capacity(total_problems);
     let mut fatally_errored = false;
+    let mut baselined_warnings = 0;
+    let mut type_errors = 0;
+    let mut hidden_errors = 0;
 
     for (home, (module_path, src)) in sources.iter() {
         let mut src_lines: Vec<&str> = Vec::new();
@@ -81,15 +93,21 @@ pub fn report_problems(
         let problems = can_problems.remove(home).unwrap_or_default();
 
         for problem in problems.into_iter() {
+            let region = problem.region();
             let report = can_problem(&alloc, &lines, module_path.clone(), problem);
             let severity = report.severity;
+            let title = report.title.clone();
             let mut buf = String::new();
 
             report.render_color_terminal(&mut buf, &alloc, &palette);
 
             match severity {
                 Warning => {
-                    warnings.push(buf);
+                    if is_baselined(&mut baseline, &title, *home, interns, region, src) {
+                        baselined_warnings += 1;
+                    } else {
+                        warnings.push(buf);
+                    }
                 }
                 RuntimeError => {
                     errors.push(buf);
@@ -104,21 +122,42 @@ pub fn report_problems(
         let problems = type_problems.remove(home).unwrap_or_default();
 
         for problem in problems {
+            let region = problem.region();
+
+            // With a maximum, only report the root causes: errors that follow from an earlier
+            // error are hidden, and so is everything past the maximum.
+            if let Some(max_type_errors) = max_type_errors {
+                if problem.severity() != Warning
+                    && (problem.is_follow_on() || type_errors >= max_type_errors)
+                {
+                    hidden_errors += 1;
+
+                    continue;
+                }
+            }
+
             if let Some(report) = type_problem(&alloc, &lines, module_path.clone(), problem) {
                 let severity = report.severity;
+                let title = report.title.clone();
                 let mut buf = String::new();
 
                 report.render_color_terminal(&mut buf, &alloc, &palette);
 
                 match severity {
                     Warning => {
-                        warnings.push(buf);
+                        if is_baselined(&mut baseline, &title, *home, interns, region, src) {
+                            baselined_warnings += 1;
+                        } else {
+                            warnings.push(buf);
+                        }
                     }
                     RuntimeError => {
+                        type_errors += 1;
                         errors.push(buf);
                     }
                     Fatal => {
                         fatally_errored = true;
+                        type_errors += 1;
                         errors.push(buf);
                     }
                 }
@@ -157,5 +196,27 @@ pub fn report_problems(
         fatally_errored,
         errors: errors.len(),
         warnings: warnings.len(),
+        baselined_warnings,
+        hidden_errors,
+    }
+}
+
+fn is_baselined(
+    baseline: &mut Option<&mut Baseline>,
+    title: &str,
+    home: ModuleId,
+    interns: &Interns,
+    region: Option<Region>,
+    src: &str,
+) -> bool {
+    match baseline {
+        Some(baseline) => {
+            let module_name = interns.module_name(home).as_str();
+            let fingerprint = Fingerprint::new(title, module_name, region, src);
+            let description = format!("{} in {}", title, module_name);
+
+            baseline.accepts(fingerprint, description)
+        }
+        None => false,
     }
 }
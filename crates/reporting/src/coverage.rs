@@ -0,0 +1,70 @@
+//! The summary that `roc test --coverage` prints: which of the definitions that the modules of
+//! a package expose were not used by any expect.
+use std::path::PathBuf;
+
+use crate::report::{pretty_header, RenderTarget};
+
+/// An exposed definition that no expect used.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Uncovered {
+    pub module_name: String,
+    pub name: String,
+    /// The file of the module that exposes the definition.
+    pub path: PathBuf,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CoverageSummary {
+    /// How many exposed definitions some expect used.
+    pub covered: usize,
+    pub uncovered: Vec<Uncovered>,
+}
+
+impl CoverageSummary {
+    pub fn render(&self, target: RenderTarget) -> String {
+        let (green, yellow, cyan, reset) = match target {
+            RenderTarget::ColorTerminal => ("\x1B[32m", "\x1B[33m", "\x1B[36m", "\x1B[39m"),
+            RenderTarget::Generic => ("", "", "", ""),
+        };
+
+        let total = self.covered + self.uncovered.len();
+        let mut buf = format!("{cyan}{}{reset}\n\n", pretty_header("COVERAGE"));
+
+        if self.uncovered.is_empty() {
+            buf.push_str(&format!(
+                "All {green}{total}{reset} exposed definitions were used by an expect.\n"
+            ));
+
+            return buf;
+        }
+
+        buf.push_str(&format!(
+            "{green}{}{reset} of {total} exposed definitions were used by an expect. {} not:\n\n",
+            self.covered,
+            match self.uncovered.len() {
+                1 => "This one was".to_string(),
+                n => format!("These {yellow}{n}{reset} were"),
+            },
+        ));
+
+        let mut uncovered = self.uncovered.clone();
+        uncovered.sort();
+
+        let width = uncovered
+            .iter()
+            .map(|def| def.module_name.len() + 1 + def.name.len())
+            .max()
+            .unwrap_or(0);
+
+        for def in uncovered {
+            let qualified = format!("{}.{}", def.module_name, def.name);
+
+            buf.push_str(&format!(
+                "    {yellow}{qualified:width$}{reset}  {}\n",
+                def.path.display()
+            ));
+        }
+
+        buf
+    }
+}
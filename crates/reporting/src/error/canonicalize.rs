@@ -59,6 +59,7 @@ const ABILITY_IMPLEMENTATION_NOT_IDENTIFIER: &str = "ABILITY IMPLEMENTATION NOT
 const DUPLICATE_IMPLEMENTATION: &str = "DUPLICATE IMPLEMENTATION";
 const UNNECESSARY_IMPLEMENTATIONS: &str = "UNNECESSARY IMPLEMENTATIONS";
 const INCOMPLETE_ABILITY_IMPLEMENTATION: &str = "INCOMPLETE ABILITY IMPLEMENTATION";
+const DEAD_EXPECT: &str = "DEAD EXPECT";
 
 pub fn can_problem<'b>(
     alloc: &'b RocDocAllocator<'b>,
@@ -1093,6 +1094,31 @@ pub fn can_problem<'b>(
             ]);
             title = "OVERAPPLIED CRASH".to_string();
         }
+        Problem::DeadExpect {
+            region,
+            always_passes,
+        } => {
+            let (outcome, known) = if always_passes {
+                (" can never fail:", "true")
+            } else {
+                (" always fails:", "false")
+            };
+
+            doc = alloc.stack([
+                alloc.concat([
+                    alloc.reflow("This "),
+                    alloc.keyword("expect"),
+                    alloc.reflow(outcome),
+                ]),
+                alloc.region(lines.convert_region(region)),
+                alloc.concat([
+                    alloc.reflow("Its condition is always "),
+                    alloc.keyword(known),
+                    alloc.reflow(", no matter what the program does. This is often a copy-paste mistake, like comparing a value to itself."),
+                ]),
+            ]);
+            title = DEAD_EXPECT.to_string();
+        }
         Problem::FileProblem { filename, error } => {
             let report = to_file_problem_report(alloc, &filename, error);
             doc = report.doc;
@@ -1,10 +1,11 @@
 use std::path::PathBuf;
 
 use bumpalo::Bump;
+use roc_module::ident::Lowercase;
 use roc_module::symbol::{Interns, ModuleId, Symbol};
-use roc_parse::ast::Expr;
+use roc_parse::ast::{AssignedField, Collection, CommentOrNewline, Expr};
 use roc_problem::Severity;
-use roc_region::all::{LineColumnRegion, LineInfo, Region};
+use roc_region::all::{LineColumnRegion, LineInfo, Loc, Region};
 use roc_types::{
     subs::{Subs, Variable},
     types::{ErrorType, Polarity},
@@ -12,12 +13,202 @@ use roc_types::{
 
 use crate::report::{RenderTarget, RocDocAllocator, RocDocBuilder};
 
+/// Records with more fields than this only show the fields the failed condition reads, so the
+/// ones that matter don't get lost among the rest.
+const MAX_FIELDS_SHOWN: usize = 8;
+
+/// When the two sides of a failed `==` differ in more places than this, the rest are only
+/// counted.
+const MAX_DIFFERENCES_SHOWN: usize = 10;
+
+/// How much of each value expect failures and `dbg` show, so that a failure over a huge value
+/// doesn't flood the terminal. `None` means there is no limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValueLimits {
+    /// How many levels of lists, records, tuples, and tag payloads are shown. Deeper ones are
+    /// shown as `…`.
+    pub max_depth: Option<usize>,
+    /// How many elements of each list are shown. The rest are only counted.
+    pub max_length: Option<usize>,
+}
+
+impl Default for ValueLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: Some(10),
+            max_length: Some(100),
+        }
+    }
+}
+
+impl ValueLimits {
+    pub const NONE: Self = Self {
+        max_depth: None,
+        max_length: None,
+    };
+
+    /// The part of `value` that is within the limits, or `None` if all of it is.
+    pub fn cut_short<'x>(&self, arena: &'x Bump, value: &Expr<'x>) -> Option<Expr<'x>> {
+        self.cut_short_at(arena, value, 0)
+    }
+
+    fn cut_short_at<'x>(
+        &self,
+        arena: &'x Bump,
+        value: &Expr<'x>,
+        depth: usize,
+    ) -> Option<Expr<'x>> {
+        let too_deep = self.max_depth.map_or(false, |max_depth| depth >= max_depth);
+
+        match value {
+            Expr::List(items) => {
+                if too_deep {
+                    return Some(ELIDED);
+                }
+
+                let shown = self
+                    .max_length
+                    .map_or(items.len(), |max_length| items.len().min(max_length));
+                let mut cut = self.cut_short_items(arena, &items.items[..shown], depth + 1);
+
+                if shown < items.len() {
+                    let more = items.len() - shown;
+                    let summary = if more == 1 {
+                        "… 1 more element".to_string()
+                    } else {
+                        format!("… {} more elements", more)
+                    };
+
+                    let kept = cut.get_or_insert_with(|| items.items[..shown].to_vec());
+                    kept.push(arena.alloc(Loc::at_zero(Expr::Var {
+                        module_name: "",
+                        ident: arena.alloc_str(&summary),
+                    })));
+                }
+
+                let kept = cut?;
+
+                Some(Expr::List(
+                    items.replace_items(arena.alloc_slice_copy(&kept)),
+                ))
+            }
+            Expr::Tuple(items) => {
+                if too_deep {
+                    return Some(ELIDED);
+                }
+
+                let kept = self.cut_short_items(arena, items.items, depth + 1)?;
+
+                Some(Expr::Tuple(
+                    items.replace_items(arena.alloc_slice_copy(&kept)),
+                ))
+            }
+            Expr::Record(fields) => {
+                if too_deep {
+                    return Some(ELIDED);
+                }
+
+                let mut changed = false;
+                let kept = bumpalo::collections::Vec::from_iter_in(
+                    fields.iter().map(|field| match &field.value {
+                        AssignedField::RequiredValue(name, spaces, value) => {
+                            match self.cut_short_at(arena, &value.value, depth + 1) {
+                                Some(cut) => {
+                                    changed = true;
+
+                                    let value = arena.alloc(Loc::at(value.region, cut));
+
+                                    Loc::at(
+                                        field.region,
+                                        AssignedField::RequiredValue(*name, spaces, value),
+                                    )
+                                }
+                                None => *field,
+                            }
+                        }
+                        _ => *field,
+                    }),
+                    arena,
+                );
+
+                if !changed {
+                    return None;
+                }
+
+                Some(Expr::Record(fields.replace_items(kept.into_bump_slice())))
+            }
+            Expr::Apply(function, args, called_via) if !args.is_empty() => {
+                if too_deep {
+                    return Some(ELIDED);
+                }
+
+                let kept = self.cut_short_items(arena, args, depth + 1)?;
+
+                Some(Expr::Apply(
+                    function,
+                    arena.alloc_slice_copy(&kept),
+                    *called_via,
+                ))
+            }
+            Expr::SpaceBefore(inner, spaces) => Some(Expr::SpaceBefore(
+                arena.alloc(self.cut_short_at(arena, inner, depth)?),
+                spaces,
+            )),
+            Expr::SpaceAfter(inner, spaces) => Some(Expr::SpaceAfter(
+                arena.alloc(self.cut_short_at(arena, inner, depth)?),
+                spaces,
+            )),
+            Expr::ParensAround(inner) => Some(Expr::ParensAround(
+                arena.alloc(self.cut_short_at(arena, inner, depth)?),
+            )),
+            _ => None,
+        }
+    }
+
+    /// `items` with each of them cut short, or `None` if none of them had to be.
+    fn cut_short_items<'x>(
+        &self,
+        arena: &'x Bump,
+        items: &[&'x Loc<Expr<'x>>],
+        depth: usize,
+    ) -> Option<Vec<&'x Loc<Expr<'x>>>> {
+        let mut changed = false;
+        let kept = items
+            .iter()
+            .map(|item| match self.cut_short_at(arena, &item.value, depth) {
+                Some(cut) => {
+                    changed = true;
+
+                    &*arena.alloc(Loc::at(item.region, cut))
+                }
+                None => *item,
+            })
+            .collect();
+
+        changed.then_some(kept)
+    }
+}
+
+/// What a value that is nested too deeply is shown as
+const ELIDED: Expr<'static> = Expr::Var {
+    module_name: "",
+    ident: "…",
+};
+
+/// A call that led to a failed expect in the function it called.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallSite {
+    pub path: PathBuf,
+    pub region: LineColumnRegion,
+}
+
 pub struct Renderer<'a> {
     arena: &'a Bump,
     alloc: RocDocAllocator<'a>,
     filename: PathBuf,
     line_info: LineInfo,
     render_target: RenderTarget,
+    value_limits: ValueLimits,
 }
 
 impl<'a> Renderer<'a> {
@@ -40,9 +231,41 @@ impl<'a> Renderer<'a> {
             line_info,
             filename,
             render_target,
+            value_limits: ValueLimits::default(),
+        }
+    }
+
+    /// Show this much of each value, instead of [ValueLimits::default].
+    pub fn set_value_limits(&mut self, value_limits: ValueLimits) {
+        self.value_limits = value_limits;
+    }
+
+    /// The part of `value` to show, and whether that is all of it.
+    fn within_limits<'x>(&self, value: &Expr<'x>) -> (Expr<'x>, bool)
+    where
+        'a: 'x,
+    {
+        match self.value_limits.cut_short(self.arena, value) {
+            Some(cut) => (cut, false),
+            None => (*value, true),
         }
     }
 
+    /// A hint about how to see more of the values that were cut short.
+    fn render_cut_short_hint(&'a self) -> RocDocBuilder<'a> {
+        use ven_pretty::DocAllocator;
+
+        self.alloc.concat([
+            self.alloc.hint(""),
+            self.alloc
+                .reflow("Some values were cut short. To see more of them, use "),
+            self.alloc.parser_suggestion("--max-value-depth"),
+            self.alloc.reflow(" or "),
+            self.alloc.parser_suggestion("--max-value-length"),
+            self.alloc.reflow("."),
+        ])
+    }
+
     fn render_expr(&'a self, error_type: ErrorType) -> RocDocBuilder<'a> {
         use crate::error::r#type::error_type_to_doc;
 
@@ -54,61 +277,195 @@ impl<'a> Renderer<'a> {
         symbol: Symbol,
         expr: &Expr<'_>,
         error_type: ErrorType,
+        accessed_fields: &[(Symbol, Lowercase)],
     ) -> RocDocBuilder<'a> {
-        use roc_fmt::annotation::Formattable;
-
-        let mut buf = roc_fmt::Buf::new_in(self.arena);
-        expr.format(&mut buf, 0);
+        use crate::error::r#type::record_type_to_doc_showing;
+
+        let shown: Vec<&str> = accessed_fields
+            .iter()
+            .filter(|(accessed, _)| *accessed == symbol)
+            .map(|(_, field)| field.as_str())
+            .collect();
+
+        let (value, typ) = match elide_record_fields(self.arena, expr, &shown) {
+            Some(elided) => (
+                roc_value_render::render(self.arena, &elided),
+                record_type_to_doc_showing(&self.alloc, error_type, &shown),
+            ),
+            None => (
+                roc_value_render::render(self.arena, expr),
+                self.render_expr(error_type),
+            ),
+        };
 
         self.alloc.vcat([
             self.alloc
                 .symbol_unqualified(symbol)
                 .append(" : ")
-                .append(self.render_expr(error_type)),
+                .append(typ),
             self.alloc
                 .symbol_unqualified(symbol)
                 .append(" = ")
-                .append(buf.into_bump_str()),
+                .append(value),
         ])
     }
 
+    /// Where the values on both sides of a failed `actual == expected` differ, or `None` if
+    /// they are not lookups, or only differ as a whole (e.g. two different numbers).
+    fn render_differences(
+        &'a self,
+        symbols: &[Symbol],
+        expressions: &[Expr<'_>],
+        compared: Option<(Symbol, Symbol)>,
+    ) -> Option<RocDocBuilder<'a>> {
+        use ven_pretty::DocAllocator;
+
+        let (left, right) = compared?;
+        let value_of = |symbol| {
+            let index = symbols.iter().position(|s| *s == symbol)?;
+
+            expressions.get(index)
+        };
+
+        let mut differences = Vec::new();
+        diff_values(
+            self.arena,
+            &mut String::new(),
+            value_of(left)?,
+            value_of(right)?,
+            &mut differences,
+        );
+
+        if differences
+            .iter()
+            .all(|difference| difference.path.is_empty())
+        {
+            return None;
+        }
+
+        let mut lines: Vec<RocDocBuilder<'a>> = differences
+            .iter()
+            .take(MAX_DIFFERENCES_SHOWN)
+            .map(|difference| {
+                self.alloc.text(format!(
+                    "{}: {} ≠ {}",
+                    difference.path, difference.left, difference.right
+                ))
+            })
+            .collect();
+
+        match differences.len().saturating_sub(MAX_DIFFERENCES_SHOWN) {
+            0 => {}
+            1 => lines.push(self.alloc.text("… and 1 more difference")),
+            more => lines.push(self.alloc.text(format!("… and {} more differences", more))),
+        }
+
+        Some(self.alloc.stack([
+            self.alloc.concat([
+                self.alloc.symbol_unqualified(left),
+                self.alloc.reflow(" and "),
+                self.alloc.symbol_unqualified(right),
+                self.alloc.reflow(" differ here:"),
+            ]),
+            self.alloc.vcat(lines).indent(4),
+        ]))
+    }
+
+    /// Where the expect was called from, innermost call first. `omitted` more calls led to
+    /// those, which the frame had no room for. Calls in other files only show where they are.
+    fn render_call_sites(&'a self, call_sites: &[CallSite], omitted: usize) -> RocDocBuilder<'a> {
+        use ven_pretty::DocAllocator;
+
+        let mut docs = vec![self.alloc.reflow("It was called from here:")];
+
+        for call_site in call_sites {
+            if call_site.path == self.filename {
+                docs.push(self.alloc.region(call_site.region));
+            } else {
+                docs.push(
+                    self.alloc
+                        .text(format!(
+                            "{}:{}:{}",
+                            call_site.path.display(),
+                            call_site.region.start.line + 1,
+                            call_site.region.start.column + 1
+                        ))
+                        .indent(4),
+                );
+            }
+        }
+
+        match omitted {
+            0 => {}
+            1 => docs.push(self.alloc.text("… and 1 more call").indent(4)),
+            more => docs.push(
+                self.alloc
+                    .text(format!("… and {} more calls", more))
+                    .indent(4),
+            ),
+        }
+
+        self.alloc.stack(docs)
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn render_lookups(
         &'a self,
         subs: &mut Subs,
         line_col_region: LineColumnRegion,
+        call_sites: &[CallSite],
+        omitted_calls: usize,
 
         symbols: &[Symbol],
         variables: &[Variable],
         expressions: &[Expr<'_>],
+        accessed_fields: &[(Symbol, Lowercase)],
+        compared: Option<(Symbol, Symbol)>,
     ) -> RocDocBuilder<'a> {
         use ven_pretty::DocAllocator;
 
-        let it =
-            symbols
-                .iter()
-                .zip(variables)
-                .zip(expressions)
-                .map(|((symbol, variable), expr)| {
-                    let error_type = subs.var_to_error_type(*variable, Polarity::OF_VALUE);
-                    self.render_lookup(*symbol, expr, error_type)
-                });
+        let differences = self.render_differences(symbols, expressions, compared);
+
+        let mut cut_short = false;
+        let it = symbols
+            .iter()
+            .zip(variables)
+            .zip(expressions)
+            .map(|((symbol, variable), expr)| {
+                let error_type = subs.var_to_error_type(*variable, Polarity::OF_VALUE);
+                let (expr, whole) = self.within_limits(expr);
+
+                cut_short |= !whole;
+
+                self.render_lookup(*symbol, &expr, error_type, accessed_fields)
+            })
+            .collect::<Vec<_>>();
 
-        if it.len() > 0 {
-            self.alloc.stack([
-                self.alloc.text("This expectation failed:"),
-                self.alloc.region(line_col_region),
+        let mut docs = vec![
+            self.alloc.text("This expectation failed:"),
+            self.alloc.region(line_col_region),
+        ];
+
+        if !call_sites.is_empty() {
+            docs.push(self.render_call_sites(call_sites, omitted_calls));
+        }
+
+        if !it.is_empty() {
+            docs.push(
                 self.alloc
                     .text("When it failed, these variables had these values:"),
-                self.alloc.stack(it),
-                self.alloc.text(""), // Blank line at the end
-            ])
-        } else {
-            self.alloc.stack([
-                self.alloc.text("This expectation failed:"),
-                self.alloc.region(line_col_region),
-                self.alloc.text(""), // Blank line at the end
-            ])
+            );
+            docs.push(self.alloc.stack(it));
+            docs.extend(differences);
+
+            if cut_short {
+                docs.push(self.render_cut_short_hint());
+            }
         }
+
+        docs.push(self.alloc.text("")); // Blank line at the end
+
+        self.alloc.stack(docs)
     }
 
     fn to_line_col_region(
@@ -140,8 +497,12 @@ impl<'a> Renderer<'a> {
         symbols: &[Symbol],
         variables: &[Variable],
         expressions: &[Expr<'_>],
+        accessed_fields: &[(Symbol, Lowercase)],
+        compared: Option<(Symbol, Symbol)>,
         expect_region: Option<Region>,
         failure_region: Region,
+        call_sites: &[CallSite],
+        omitted_calls: usize,
     ) -> std::io::Result<()>
     where
         W: std::io::Write,
@@ -149,7 +510,17 @@ impl<'a> Renderer<'a> {
         use crate::report::Report;
 
         let line_col_region = self.to_line_col_region(expect_region, failure_region);
-        let doc = self.render_lookups(subs, line_col_region, symbols, variables, expressions);
+        let doc = self.render_lookups(
+            subs,
+            line_col_region,
+            call_sites,
+            omitted_calls,
+            symbols,
+            variables,
+            expressions,
+            accessed_fields,
+            compared,
+        );
 
         let report = Report {
             title: "EXPECT FAILED".into(),
@@ -190,15 +561,18 @@ impl<'a> Renderer<'a> {
             line_col_region.start.column + 1
         )?;
 
-        let expr = expressions[0];
+        let (value, whole) = self.within_limits(&expressions[0]);
+        let value = roc_value_render::render(self.arena, &value);
 
-        let mut buf = roc_fmt::Buf::new_in(self.arena);
-        {
-            use roc_fmt::annotation::Formattable;
-            expr.format(&mut buf, 0);
+        if whole {
+            writeln!(writer, "{}", value)
+        } else {
+            writeln!(
+                writer,
+                "{}  # cut short, see --max-value-depth and --max-value-length",
+                value
+            )
         }
-
-        writeln!(writer, "{}", buf.as_str())
     }
 
     pub fn render_panic<W>(
@@ -240,4 +614,260 @@ impl<'a> Renderer<'a> {
 
         write!(writer, "{}", buf)
     }
+
+    pub fn render_timeout<W>(
+        &self,
+        writer: &mut W,
+        timeout: std::time::Duration,
+        expect_region: Region,
+    ) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+    {
+        use crate::report::Report;
+        use ven_pretty::DocAllocator;
+
+        let line_col_region = self.line_info.convert_region(expect_region);
+
+        let doc = self.alloc.stack([
+            self.alloc.text(format!(
+                "This expectation took longer than {} ms, so I stopped it:",
+                timeout.as_millis()
+            )),
+            self.alloc.region(line_col_region),
+            self.alloc.concat([
+                self.alloc.hint(""),
+                self.alloc
+                    .reflow("If it is just slow, give it more time with "),
+                self.alloc.parser_suggestion("--timeout-ms"),
+                self.alloc.reflow("."),
+            ]),
+        ]);
+
+        let report = Report {
+            title: "EXPECT TIMED OUT".into(),
+            doc,
+            filename: self.filename.clone(),
+            severity: Severity::RuntimeError,
+        };
+
+        let mut buf = String::new();
+
+        report.render(
+            self.render_target,
+            &mut buf,
+            &self.alloc,
+            &crate::report::DEFAULT_PALETTE,
+        );
+
+        write!(writer, "{}", buf)
+    }
+}
+
+/// A big record with only the `shown` fields left, and a comment saying how many were left out.
+/// `None` if the value isn't a big record, or the condition didn't read any of its fields.
+fn elide_record_fields<'x>(arena: &'x Bump, expr: &Expr<'x>, shown: &[&str]) -> Option<Expr<'x>> {
+    let fields = match expr {
+        Expr::Record(fields) if fields.len() > MAX_FIELDS_SHOWN && !shown.is_empty() => fields,
+        _ => return None,
+    };
+
+    let kept = bumpalo::collections::Vec::from_iter_in(
+        fields
+            .iter()
+            .filter(|field| match field.value {
+                AssignedField::RequiredValue(name, _, _) => shown.contains(&name.value),
+                _ => true,
+            })
+            .copied(),
+        arena,
+    );
+
+    let omitted = fields.len() - kept.len();
+
+    if omitted == 0 {
+        return None;
+    }
+
+    let summary = if omitted == 1 {
+        " … 1 more field".to_string()
+    } else {
+        format!(" … {} more fields", omitted)
+    };
+
+    let comments = arena.alloc([CommentOrNewline::LineComment(arena.alloc_str(&summary))]);
+
+    Some(Expr::Record(Collection::with_items_and_comments(
+        arena,
+        kept.into_bump_slice(),
+        comments,
+    )))
+}
+
+/// One place where two values differ.
+struct Difference<'x> {
+    /// How to get there from the whole value, e.g. `.items[2].name`, or empty if the
+    /// values differ as a whole
+    path: String,
+    left: &'x str,
+    right: &'x str,
+}
+
+/// Collects the places where `left` and `right` differ: the fields of records, the elements
+/// of lists and tuples, and the payloads of tags with the same name are compared one by one,
+/// and anything else as a whole.
+fn diff_values<'x>(
+    arena: &'x Bump,
+    path: &mut String,
+    left: &Expr<'_>,
+    right: &Expr<'_>,
+    differences: &mut Vec<Difference<'x>>,
+) {
+    match (strip_spaces(left), strip_spaces(right)) {
+        (Expr::Record(left_fields), Expr::Record(right_fields)) => {
+            let left_fields: Vec<_> = left_fields.iter().filter_map(field_value).collect();
+            let right_fields: Vec<_> = right_fields.iter().filter_map(field_value).collect();
+
+            for (name, left) in left_fields.iter() {
+                let segment = format!(".{}", name);
+
+                match right_fields.iter().find(|(other, _)| other == name) {
+                    Some((_, right)) => diff_at(arena, path, &segment, left, right, differences),
+                    None => differences.push(Difference {
+                        path: format!("{}{}", path, segment),
+                        left: roc_value_render::render(arena, left),
+                        right: "(missing)",
+                    }),
+                }
+            }
+
+            for (name, right) in right_fields.iter() {
+                if !left_fields.iter().any(|(other, _)| other == name) {
+                    differences.push(Difference {
+                        path: format!("{}.{}", path, name),
+                        left: "(missing)",
+                        right: roc_value_render::render(arena, right),
+                    });
+                }
+            }
+        }
+        (Expr::List(left_elems), Expr::List(right_elems)) => {
+            let left_elems: Vec<_> = left_elems.iter().collect();
+            let right_elems: Vec<_> = right_elems.iter().collect();
+
+            for index in 0..left_elems.len().max(right_elems.len()) {
+                let segment = format!("[{}]", index);
+
+                match (left_elems.get(index), right_elems.get(index)) {
+                    (Some(left), Some(right)) => diff_at(
+                        arena,
+                        path,
+                        &segment,
+                        &left.value,
+                        &right.value,
+                        differences,
+                    ),
+                    (left, right) => differences.push(Difference {
+                        path: format!("{}{}", path, segment),
+                        left: left.map_or("(missing)", |elem| {
+                            roc_value_render::render(arena, &elem.value)
+                        }),
+                        right: right.map_or("(missing)", |elem| {
+                            roc_value_render::render(arena, &elem.value)
+                        }),
+                    }),
+                }
+            }
+        }
+        (Expr::Tuple(left_elems), Expr::Tuple(right_elems))
+            if left_elems.len() == right_elems.len() =>
+        {
+            for (index, (left, right)) in left_elems.iter().zip(right_elems.iter()).enumerate() {
+                diff_at(
+                    arena,
+                    path,
+                    &format!(".{}", index),
+                    &left.value,
+                    &right.value,
+                    differences,
+                );
+            }
+        }
+        (Expr::Apply(left_tag, left_args, _), Expr::Apply(right_tag, right_args, _))
+            if left_args.len() == right_args.len()
+                && roc_value_render::render(arena, &left_tag.value)
+                    == roc_value_render::render(arena, &right_tag.value) =>
+        {
+            let tag = roc_value_render::render(arena, &left_tag.value);
+
+            for (index, (left, right)) in left_args.iter().zip(right_args.iter()).enumerate() {
+                let segment = match left_args.len() {
+                    1 => format!("({})", tag),
+                    _ => format!("({} #{})", tag, index + 1),
+                };
+
+                diff_at(
+                    arena,
+                    path,
+                    &segment,
+                    &left.value,
+                    &right.value,
+                    differences,
+                );
+            }
+        }
+        (left, right) => {
+            let left = roc_value_render::render(arena, left);
+            let right = roc_value_render::render(arena, right);
+
+            if left != right {
+                differences.push(Difference {
+                    path: path.clone(),
+                    left,
+                    right,
+                });
+            }
+        }
+    }
+}
+
+/// Diffs `left` and `right`, which are at `segment` within the values being diffed.
+fn diff_at<'x>(
+    arena: &'x Bump,
+    path: &mut String,
+    segment: &str,
+    left: &Expr<'_>,
+    right: &Expr<'_>,
+    differences: &mut Vec<Difference<'x>>,
+) {
+    let len = path.len();
+
+    path.push_str(segment);
+    diff_values(arena, path, left, right, differences);
+    path.truncate(len);
+}
+
+fn strip_spaces<'e, 'x>(expr: &'e Expr<'x>) -> &'e Expr<'x> {
+    match expr {
+        Expr::SpaceBefore(expr, _) | Expr::SpaceAfter(expr, _) | Expr::ParensAround(expr) => {
+            strip_spaces(expr)
+        }
+        _ => expr,
+    }
+}
+
+fn field_value<'x>(field: &Loc<AssignedField<'x, Expr<'x>>>) -> Option<(&'x str, &'x Expr<'x>)> {
+    let mut field = &field.value;
+
+    loop {
+        match field {
+            AssignedField::RequiredValue(name, _, value) => {
+                return Some((name.value, &value.value))
+            }
+            AssignedField::SpaceBefore(inner, _) | AssignedField::SpaceAfter(inner, _) => {
+                field = inner
+            }
+            _ => return None,
+        }
+    }
 }
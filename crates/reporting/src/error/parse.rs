@@ -3365,6 +3365,81 @@ fn to_header_report<'a>(
         EHeader::GeneratesWith(generates_with, pos) => {
             to_generates_with_report(alloc, lines, filename, generates_with, *pos)
         }
+        EHeader::Threading(threading, pos) => {
+            to_threading_report(alloc, lines, filename, threading, *pos)
+        }
+    }
+}
+
+fn to_threading_report<'a>(
+    alloc: &'a RocDocAllocator<'a>,
+    lines: &LineInfo,
+    filename: PathBuf,
+    parse_problem: &roc_parse::parser::EThreading,
+    start: Position,
+) -> Report<'a> {
+    use roc_parse::parser::EThreading;
+
+    match *parse_problem {
+        EThreading::Mode(pos) | EThreading::IndentMode(pos) => {
+            let surroundings = Region::new(start, pos);
+            let region = LineColumnRegion::from_pos(lines.convert_pos(pos));
+
+            let doc = alloc.stack([
+                alloc.reflow(
+                    r"I am partway through parsing a platform header, but I got stuck here:",
+                ),
+                alloc.region_with_subregion(lines.convert_region(surroundings), region),
+                alloc.concat([
+                    alloc.reflow(
+                        "I am expecting how the host calls into Roc next, which is either ",
+                    ),
+                    alloc.parser_suggestion("singleThreaded"),
+                    alloc.reflow(" or "),
+                    alloc.parser_suggestion("multiThreaded"),
+                    alloc.reflow(", like"),
+                ]),
+                alloc
+                    .parser_suggestion("threading singleThreaded")
+                    .indent(4),
+            ]);
+
+            Report {
+                filename,
+                doc,
+                title: "WEIRD THREADING".to_string(),
+                severity: Severity::RuntimeError,
+            }
+        }
+
+        EThreading::Threading(pos) | EThreading::IndentThreading(pos) => {
+            let surroundings = Region::new(start, pos);
+            let region = LineColumnRegion::from_pos(lines.convert_pos(pos));
+
+            let doc = alloc.stack([
+                alloc.reflow(
+                    r"I am partway through parsing a platform header, but I got stuck here:",
+                ),
+                alloc.region_with_subregion(lines.convert_region(surroundings), region),
+                alloc.concat([
+                    alloc.reflow("I am expecting the "),
+                    alloc.keyword("threading"),
+                    alloc.reflow(" keyword next, like"),
+                ]),
+                alloc
+                    .parser_suggestion("threading singleThreaded")
+                    .indent(4),
+            ]);
+
+            Report {
+                filename,
+                doc,
+                title: "WEIRD THREADING".to_string(),
+                severity: Severity::RuntimeError,
+            }
+        }
+
+        EThreading::Space(error, pos) => to_space_report(alloc, lines, filename, &error, pos),
     }
 }
 
@@ -11,7 +11,7 @@ use roc_error_macros::internal_error;
 use roc_exhaustive::{CtorName, ListArity};
 use roc_module::called_via::{BinOp, CalledVia};
 use roc_module::ident::{IdentStr, Lowercase, TagName};
-use roc_module::symbol::Symbol;
+use roc_module::symbol::{ModuleId, Symbol};
 use roc_problem::Severity;
 use roc_region::all::{LineInfo, Region};
 use roc_solve_problem::{
@@ -248,6 +248,70 @@ pub fn type_problem<'b>(
                 severity,
             })
         }
+        LossyF32Literal {
+            region,
+            literal,
+            stored_as,
+        } => {
+            let (title, stack) = if stored_as.is_infinite() {
+                let stack = [
+                    alloc.concat([
+                        alloc.reflow("This number is an "),
+                        alloc.type_str("F32"),
+                        alloc.reflow(", but it's too big to fit in one:"),
+                    ]),
+                    alloc.region(lines.convert_region(region)),
+                    alloc.concat([
+                        alloc.reflow("An "),
+                        alloc.type_str("F32"),
+                        alloc.reflow(" can only hold numbers between "),
+                        text!(alloc, "{:e}", f32::MIN),
+                        alloc.reflow(" and "),
+                        text!(alloc, "{:e}", f32::MAX),
+                        alloc.reflow(", so this would become infinity."),
+                    ]),
+                    alloc.tip().append(alloc.concat([
+                        alloc.reflow("An "),
+                        alloc.type_str("F64"),
+                        alloc.reflow(" can hold much bigger numbers."),
+                    ])),
+                ];
+
+                ("NUMBER OVERFLOWS F32", stack)
+            } else {
+                let stack = [
+                    alloc.concat([
+                        alloc.reflow("This number is an "),
+                        alloc.type_str("F32"),
+                        alloc.reflow(", which can't store all of its digits:"),
+                    ]),
+                    alloc.region(lines.convert_region(region)),
+                    alloc.concat([
+                        alloc.reflow("Instead of "),
+                        text!(alloc, "{}", literal),
+                        alloc.reflow(", it will be "),
+                        text!(alloc, "{}", stored_as),
+                        alloc.reflow("."),
+                    ]),
+                    alloc.tip().append(alloc.concat([
+                        alloc.reflow("An "),
+                        alloc.type_str("F64"),
+                        alloc.reflow(" or a "),
+                        alloc.type_str("Dec"),
+                        alloc.reflow(" can store more digits."),
+                    ])),
+                ];
+
+                ("F32 LOSES PRECISION", stack)
+            };
+
+            Some(Report {
+                title: title.to_string(),
+                filename,
+                doc: alloc.stack(stack),
+                severity,
+            })
+        }
     }
 }
 
@@ -2212,6 +2276,9 @@ pub enum Problem {
     OptionalRequiredMismatch(Lowercase),
     OpaqueComparedToNonOpaque,
     BoolVsBoolTag(TagName),
+    /// A number literal compared to a number type it doesn't fit in.
+    /// Has the smallest types it does fit in.
+    LiteralOutOfRange(Vec<ErrorType>),
 }
 
 fn problems_to_tip<'b>(
@@ -2781,6 +2848,44 @@ pub fn error_type_to_doc<'b>(
     type_with_able_vars(alloc, typ, able_vars)
 }
 
+/// Like [error_type_to_doc], but a record type only shows the fields in `shown`, with an
+/// ellipsis standing in for the others.
+pub fn record_type_to_doc_showing<'b>(
+    alloc: &'b RocDocAllocator<'b>,
+    error_type: ErrorType,
+    shown: &[&str],
+) -> RocDocBuilder<'b> {
+    match error_type {
+        ErrorType::Record(fields_map, ext) => {
+            let total = fields_map.len();
+
+            let mut fields = fields_map
+                .into_iter()
+                .filter(|(name, _)| shown.contains(&name.as_str()))
+                .collect::<Vec<_>>();
+            fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            let fields_omitted = total - fields.len();
+
+            report_text::record(
+                alloc,
+                fields
+                    .into_iter()
+                    .map(|(name, field)| {
+                        (
+                            alloc.string(name.as_str().to_string()),
+                            field.map_owned(|typ| to_doc(alloc, Parens::Unnecessary, typ).0),
+                        )
+                    })
+                    .collect(),
+                record_ext_to_doc(alloc, ext),
+                fields_omitted,
+            )
+        }
+        other => error_type_to_doc(alloc, other),
+    }
+}
+
 fn compact_builtin_aliases(typ: ErrorType) -> ErrorType {
     use ErrorType::*;
     match typ {
@@ -3044,10 +3149,44 @@ fn to_diff<'b>(
                 _ => false,
             };
 
+            // A number literal has the range of types it fits in, so when one is found where
+            // another number type was expected, the literal doesn't fit in that type.
+            let is_number = |t: &ErrorType| match t {
+                ErrorType::Type(symbol, _) | ErrorType::Alias(symbol, _, _, _) => {
+                    symbol.module_id() == ModuleId::NUM
+                }
+                _ => false,
+            };
+            let is_frac = |t: &ErrorType| {
+                matches!(
+                    t,
+                    ErrorType::Type(Symbol::NUM_F32 | Symbol::NUM_F64 | Symbol::NUM_DEC, _)
+                        | ErrorType::Alias(
+                            Symbol::NUM_F32 | Symbol::NUM_F64 | Symbol::NUM_DEC,
+                            _,
+                            _,
+                            _
+                        )
+                )
+            };
+
             let problems = match pair {
                 (a, b) if (is_int(&a) && is_float(&b)) || (is_float(&a) && is_int(&b)) => {
                     vec![Problem::IntFloat]
                 }
+                (Range(types), other) if is_number(&other) => {
+                    // Suggest integers in place of integers, unless only fractions will do
+                    let (fracs, ints): (Vec<_>, Vec<_>) = types.into_iter().partition(is_frac);
+                    let fits_in = if is_frac(&other) || ints.is_empty() {
+                        fracs
+                    } else {
+                        ints
+                    };
+
+                    vec![Problem::LiteralOutOfRange(
+                        fits_in.into_iter().take(2).collect(),
+                    )]
+                }
                 _ => vec![],
             };
 
@@ -4661,6 +4800,19 @@ fn type_problem_to_pretty<'b>(
             }
         }
 
+        (LiteralOutOfRange(types), _) => {
+            let types = types
+                .into_iter()
+                .map(|typ| to_doc(alloc, Parens::Unnecessary, typ).0)
+                .collect();
+
+            alloc.tip().append(alloc.concat([
+                alloc.reflow("This number doesn't fit in that type, but it would fit in "),
+                report_text::range(alloc, types),
+                alloc.reflow("."),
+            ]))
+        }
+
         (IntFloat, _) => alloc.tip().append(alloc.concat([
             alloc.reflow("You can convert between "),
             alloc.type_str("Int"),
@@ -4876,13 +5028,12 @@ fn exhaustive_problem<'a>(
     let severity = problem.severity();
 
     match problem {
-        Incomplete(region, context, missing) => match context {
+        Incomplete(region, context, missing, example) => match context {
             BadArg => {
                 let doc = alloc.stack([
                     alloc.reflow("This pattern does not cover all the possibilities:"),
                     alloc.region(lines.convert_region(region)),
-                    alloc.reflow("Other possibilities include:"),
-                    unhandled_patterns_to_doc_block(alloc, missing),
+                    unhandled_patterns_and_example(alloc, missing, example),
                     alloc.concat([
                         alloc.reflow(
                             "I would have to crash if I saw one of those! \
@@ -4904,8 +5055,7 @@ fn exhaustive_problem<'a>(
                 let doc = alloc.stack([
                     alloc.reflow("This pattern does not cover all the possibilities:"),
                     alloc.region(lines.convert_region(region)),
-                    alloc.reflow("Other possibilities include:"),
-                    unhandled_patterns_to_doc_block(alloc, missing),
+                    unhandled_patterns_and_example(alloc, missing, example),
                     alloc.concat([
                         alloc.reflow(
                             "I would have to crash if I saw one of those! \
@@ -4932,8 +5082,7 @@ fn exhaustive_problem<'a>(
                         alloc.reflow(" does not cover all the possibilities:"),
                     ]),
                     alloc.region(lines.convert_region(region)),
-                    alloc.reflow("Other possibilities include:"),
-                    unhandled_patterns_to_doc_block(alloc, missing),
+                    unhandled_patterns_and_example(alloc, missing, example),
                     alloc.reflow(
                         "I would have to crash if I saw one of those! \
                         Add branches for them!",
@@ -5008,6 +5157,24 @@ fn exhaustive_problem<'a>(
     }
 }
 
+fn unhandled_patterns_and_example<'b>(
+    alloc: &'b RocDocAllocator<'b>,
+    missing: Vec<roc_exhaustive::Pattern>,
+    example: Option<roc_exhaustive::Pattern>,
+) -> RocDocBuilder<'b> {
+    let mut stack = vec![
+        alloc.reflow("Other possibilities include:"),
+        unhandled_patterns_to_doc_block(alloc, missing),
+    ];
+
+    if let Some(example) = example {
+        stack.push(alloc.reflow("For example, this value isn't handled:"));
+        stack.push(unhandled_patterns_to_doc_block(alloc, vec![example]));
+    }
+
+    alloc.stack(stack)
+}
+
 pub fn unhandled_patterns_to_doc_block<'b>(
     alloc: &'b RocDocAllocator<'b>,
     patterns: Vec<roc_exhaustive::Pattern>,
@@ -5053,7 +5220,7 @@ fn pattern_to_doc_help<'b>(
             Byte(b) => alloc.text(b.to_string()),
             Float(f) => alloc.text(f.to_string()),
             Decimal(d) => alloc.text(RocDec::from_ne_bytes(d).to_string()),
-            Str(s) => alloc.string(s.into()),
+            Str(s) => alloc.string(format!("\"{}\"", s)),
         },
         List(arity, patterns) => {
             let inner = match arity {
@@ -5122,6 +5289,10 @@ fn pattern_to_doc_help<'b>(
                         }
                     }
 
+                    if arg_docs.is_empty() {
+                        return alloc.text("{}");
+                    }
+
                     alloc
                         .text("{ ")
                         .append(alloc.intersperse(arg_docs, alloc.reflow(", ")))
@@ -3,6 +3,8 @@
 // See github.com/roc-lang/roc/issues/800 for discussion of the large_enum_variant check.
 #![allow(clippy::large_enum_variant)]
 
+pub mod baseline;
 pub mod cli;
+pub mod coverage;
 pub mod error;
 pub mod report;
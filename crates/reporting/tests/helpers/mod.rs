@@ -49,6 +49,7 @@ pub fn infer_expr(
         pending_derives,
         exposed_by_module: &Default::default(),
         derived_module,
+        max_errors: None,
     };
 
     let (solved, _) = solve::run(config, problems, subs, aliases, abilities_store);
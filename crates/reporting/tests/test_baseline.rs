@@ -0,0 +1,60 @@
+extern crate roc_reporting;
+
+#[cfg(test)]
+mod test_baseline {
+    use roc_region::all::{Position, Region};
+    use roc_reporting::baseline::{Baseline, Fingerprint};
+
+    fn region_of(src: &str, needle: &str) -> Option<Region> {
+        let start = src.find(needle).unwrap();
+
+        Some(Region::new(
+            Position::new(start as u32),
+            Position::new((start + needle.len()) as u32),
+        ))
+    }
+
+    fn fingerprint(src: &str, needle: &str) -> Fingerprint {
+        Fingerprint::new("UNUSED DEFINITION", "Main", region_of(src, needle), src)
+    }
+
+    #[test]
+    fn fingerprint_survives_moving_and_reindenting() {
+        let before = "main =\n    unused = 1\n    2\n";
+        let after = "x = 0\n\nmain =\n        unused  =   1\n        2\n";
+
+        assert_eq!(fingerprint(before, "unused"), fingerprint(after, "unused"));
+    }
+
+    #[test]
+    fn fingerprint_depends_on_context() {
+        let src = "main =\n    unused = 1\n    2\n";
+        let other = "main =\n    unused = 3\n    2\n";
+
+        assert_ne!(fingerprint(src, "unused"), fingerprint(other, "unused"));
+        assert_ne!(
+            fingerprint(src, "unused"),
+            Fingerprint::new("UNUSED IMPORT", "Main", region_of(src, "unused"), src)
+        );
+    }
+
+    #[test]
+    fn baseline_accepts_each_recorded_warning_once() {
+        let src = "main =\n    unused = 1\n    2\n";
+        let warning = fingerprint(src, "unused");
+
+        let mut recorded = Baseline::default();
+        assert!(!recorded.accepts(warning, "UNUSED DEFINITION in Main".to_string()));
+
+        let mut baseline = Baseline::parse(&recorded.to_file_contents()).unwrap();
+
+        assert!(baseline.accepts(warning, String::new()));
+        assert!(!baseline.accepts(warning, String::new()));
+        assert_eq!(baseline.warnings_seen(), 2);
+    }
+
+    #[test]
+    fn invalid_baseline_is_rejected() {
+        assert!(Baseline::parse("# comment\n\nnot-a-fingerprint x\n").is_err());
+    }
+}
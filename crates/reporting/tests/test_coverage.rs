@@ -0,0 +1,45 @@
+extern crate roc_reporting;
+
+#[cfg(test)]
+mod test_coverage {
+    use std::path::PathBuf;
+
+    use roc_reporting::coverage::{CoverageSummary, Uncovered};
+    use roc_reporting::report::{pretty_header, RenderTarget};
+
+    fn uncovered(module_name: &str, name: &str) -> Uncovered {
+        Uncovered {
+            module_name: module_name.to_string(),
+            name: name.to_string(),
+            path: PathBuf::from(format!("{module_name}.roc")),
+        }
+    }
+
+    #[test]
+    fn lists_uncovered_definitions_sorted() {
+        let summary = CoverageSummary {
+            covered: 3,
+            uncovered: vec![uncovered("Json", "parse"), uncovered("Csv", "row")],
+        };
+
+        assert_eq!(
+            summary.render(RenderTarget::Generic),
+            format!(
+                "{}\n\n3 of 5 exposed definitions were used by an expect. These 2 were not:\n\n    Csv.row     Csv.roc\n    Json.parse  Json.roc\n",
+                pretty_header("COVERAGE")
+            )
+        );
+    }
+
+    #[test]
+    fn everything_covered() {
+        let summary = CoverageSummary {
+            covered: 2,
+            uncovered: Vec::new(),
+        };
+
+        assert!(summary
+            .render(RenderTarget::Generic)
+            .ends_with("All 2 exposed definitions were used by an expect.\n"));
+    }
+}
@@ -127,6 +127,9 @@ mod test_reporting {
                 palette: DEFAULT_PALETTE,
                 threading: Threading::Single,
                 exec_mode: ExecutionMode::Check,
+                platform: None,
+                max_type_errors: None,
+                keep_module_types: false,
             };
             let result = roc_load::load_and_typecheck(
                 arena,
@@ -2612,6 +2615,10 @@ mod test_reporting {
 
         Right _
 
+    For example, this value isn't handled:
+
+        Right ""
+
     I would have to crash if I saw one of those! So rather than pattern
     matching in function arguments, put a `when` in the function body to
     account for all possibilities.
@@ -2781,6 +2788,10 @@ mod test_reporting {
         Loading
         Success _
 
+    For example, this value isn't handled:
+
+        Failure 0
+
     I would have to crash if I saw one of those! Add branches for them!
     "###
     );
@@ -2808,6 +2819,10 @@ mod test_reporting {
 
         { a }
 
+    For example, this value isn't handled:
+
+        { a: 0 }
+
     I would have to crash if I saw one of those! Add branches for them!
     "###
     );
@@ -2838,6 +2853,10 @@ mod test_reporting {
 
         { a: Just _ }
 
+    For example, this value isn't handled:
+
+        { a: Just 0 }
+
     I would have to crash if I saw one of those! Add branches for them!
     "###
     );
@@ -2864,6 +2883,10 @@ mod test_reporting {
 
         Record (Just _) _
 
+    For example, this value isn't handled:
+
+        Record (Just 0) 0
+
     I would have to crash if I saw one of those! Add branches for them!
     "###
     );
@@ -7477,6 +7500,9 @@ In roc, functions are always written as a lambda, like{}
     But `get` needs its 2nd argument to be:
 
         Nat
+
+    Tip: This number doesn't fit in that type, but it would fit in `I8` or
+    `I16`.
     "###
     );
 
@@ -7503,6 +7529,9 @@ In roc, functions are always written as a lambda, like{}
     But `get` needs its 2nd argument to be:
 
         Nat
+
+    Tip: This number doesn't fit in that type, but it would fit in `I64` or
+    `I128`.
     "###
     );
 
@@ -7530,6 +7559,9 @@ In roc, functions are always written as a lambda, like{}
     But `get` needs its 2nd argument to be:
 
         Nat
+
+    Tip: This number doesn't fit in that type, but it would fit in `I64` or
+    `I128`.
     "###
     );
 
@@ -7560,6 +7592,9 @@ In roc, functions are always written as a lambda, like{}
         U8
 
     The branches must be cases of the `when` condition's type!
+
+    Tip: This number doesn't fit in that type, but it would fit in `I8` or
+    `I16`.
     "###
     );
 
@@ -8046,6 +8081,10 @@ In roc, functions are always written as a lambda, like{}
 
         @F _
 
+    For example, this value isn't handled:
+
+        @F 0
+
     I would have to crash if I saw one of those! Add branches for them!
     "###
     );
@@ -11600,6 +11639,9 @@ In roc, functions are always written as a lambda, like{}
 
         U8
 
+    Tip: This number doesn't fit in that type, but it would fit in `I8` or
+    `I16`.
+
     ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `shiftRightBy` has an unexpected type:
@@ -11615,6 +11657,9 @@ In roc, functions are always written as a lambda, like{}
 
         U8
 
+    Tip: This number doesn't fit in that type, but it would fit in `I8` or
+    `I16`.
+
     ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `shiftLeftBy` has an unexpected type:
@@ -11629,6 +11674,9 @@ In roc, functions are always written as a lambda, like{}
     But `shiftLeftBy` needs its 2nd argument to be:
 
         U8
+
+    Tip: This number doesn't fit in that type, but it would fit in `I8` or
+    `I16`.
     "###
     );
 
@@ -11657,6 +11705,9 @@ In roc, functions are always written as a lambda, like{}
     But `contains` needs its 2nd argument to be:
 
         Int Unsigned8
+
+    Tip: This number doesn't fit in that type, but it would fit in `U16` or
+    `I32`.
     "###
     );
 
@@ -12501,6 +12552,10 @@ In roc, functions are always written as a lambda, like{}
 
         [_, ..]
 
+    For example, this value isn't handled:
+
+        [A]
+
     I would have to crash if I saw one of those! Add branches for them!
     "###
     );
@@ -12543,6 +12598,10 @@ In roc, functions are always written as a lambda, like{}
 
         [_, _, _, ..]
 
+    For example, this value isn't handled:
+
+        [A, A, A]
+
     I would have to crash if I saw one of those! Add branches for them!
     "###
     );
@@ -13841,4 +13900,124 @@ In roc, functions are always written as a lambda, like{}
     Tip: It looks like it takes too many arguments. I'm seeing 1 extra.
     "###
     );
+
+    test_report!(
+        f32_literal_loses_precision,
+        indoc!(
+            r#"
+            x : F32
+            x = 3.14159265358979
+
+            x
+            "#
+        ),
+        @r###"
+    ── F32 LOSES PRECISION ─────────────────────────────────── /code/proj/Main.roc ─
+
+    This number is an F32, which can't store all of its digits:
+
+    5│      x = 3.14159265358979
+                ^^^^^^^^^^^^^^^^
+
+    Instead of 3.14159265358979, it will be 3.1415927.
+
+    Tip: An F64 or a Dec can store more digits.
+    "###
+    );
+
+    test_report!(
+        f32_literal_overflows,
+        indoc!(
+            r#"
+            x : F32
+            x = 1e40
+
+            x
+            "#
+        ),
+        @r###"
+    ── NUMBER OVERFLOWS F32 ────────────────────────────────── /code/proj/Main.roc ─
+
+    This number is an F32, but it's too big to fit in one:
+
+    5│      x = 1e40
+                ^^^^
+
+    An F32 can only hold numbers between -3.4028235e38 and 3.4028235e38,
+    so this would become infinity.
+
+    Tip: An F64 can hold much bigger numbers.
+    "###
+    );
+
+    test_report!(
+        int_literal_does_not_fit_annotation,
+        indoc!(
+            r#"
+            x : U8
+            x = 300
+
+            x
+            "#
+        ),
+        @r###"
+    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+
+    Something is off with the body of the `x` definition:
+
+    4│      x : U8
+    5│      x = 300
+                ^^^
+
+    The body is a number of type:
+
+        I16, U16, F32, I32, U32, F64, I64, Nat, U64, I128, Dec, or U128
+
+    But the type annotation on `x` says it should be:
+
+        U8
+
+    Tip: This number doesn't fit in that type, but it would fit in `I16` or
+    `U16`.
+    "###
+    );
+
+    test_report!(
+        nested_patterns_not_exhaustive_example,
+        indoc!(
+            r#"
+            Msg : [Move { x : I64, y : I64 }, Say Str, Quit]
+
+            update : Msg -> Str
+            update = \msg ->
+                when msg is
+                    Move { x: 0, y } -> Num.toStr y
+                    Say "" -> "nothing"
+                    Quit -> "bye"
+
+            update Quit
+            "#
+        ),
+        @r###"
+    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+
+    This `when` does not cover all the possibilities:
+
+     8│>          when msg is
+     9│>              Move { x: 0, y } -> Num.toStr y
+    10│>              Say "" -> "nothing"
+    11│>              Quit -> "bye"
+
+    Other possibilities include:
+
+        Move { x, y }
+        Say _
+
+    For example, this value isn't handled:
+
+        Move { x: 1, y: 1 }
+
+    I would have to crash if I saw one of those! Add branches for them!
+    "###
+    );
 }
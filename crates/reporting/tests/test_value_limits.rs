@@ -0,0 +1,80 @@
+extern crate roc_reporting;
+
+#[cfg(test)]
+mod test_value_limits {
+    use bumpalo::Bump;
+    use roc_parse::ast::{Collection, Expr};
+    use roc_region::all::Loc;
+    use roc_reporting::error::expect::ValueLimits;
+
+    fn list<'a>(arena: &'a Bump, items: Vec<Expr<'a>>) -> Expr<'a> {
+        let items: Vec<&'a Loc<Expr<'a>>> = items
+            .into_iter()
+            .map(|item| &*arena.alloc(Loc::at_zero(item)))
+            .collect();
+
+        Expr::List(Collection::with_items(arena.alloc_slice_copy(&items)))
+    }
+
+    fn numbers(arena: &Bump, count: usize) -> Expr<'_> {
+        let items = (0..count)
+            .map(|n| Expr::Num(arena.alloc_str(&n.to_string())))
+            .collect();
+
+        list(arena, items)
+    }
+
+    fn render(arena: &Bump, limits: ValueLimits, value: &Expr<'_>) -> String {
+        match limits.cut_short(arena, value) {
+            Some(cut) => roc_value_render::render(arena, &cut).to_string(),
+            None => roc_value_render::render(arena, value).to_string(),
+        }
+    }
+
+    #[test]
+    fn long_lists_are_counted() {
+        let arena = Bump::new();
+        let limits = ValueLimits {
+            max_depth: None,
+            max_length: Some(3),
+        };
+
+        assert_eq!(
+            render(&arena, limits, &numbers(&arena, 100_000)),
+            "[0, 1, 2, … 99997 more elements]"
+        );
+        assert_eq!(
+            render(&arena, limits, &numbers(&arena, 4)),
+            "[0, 1, 2, … 1 more element]"
+        );
+    }
+
+    #[test]
+    fn deep_values_are_elided() {
+        let arena = Bump::new();
+        let limits = ValueLimits {
+            max_depth: Some(2),
+            max_length: None,
+        };
+
+        let inner = numbers(&arena, 2);
+        let middle = list(&arena, vec![inner]);
+        let outer = list(&arena, vec![middle, Expr::Num("7")]);
+
+        assert_eq!(render(&arena, limits, &outer), "[[…], 7]");
+    }
+
+    #[test]
+    fn values_within_the_limits_are_left_alone() {
+        let arena = Bump::new();
+
+        assert_eq!(
+            ValueLimits::default().cut_short(&arena, &numbers(&arena, 100)),
+            None
+        );
+        assert_eq!(
+            ValueLimits::NONE.cut_short(&arena, &numbers(&arena, 1000)),
+            None
+        );
+    }
+}
@@ -0,0 +1,143 @@
+//! Reference implementations of the effects that give a Roc app access to its environment:
+//! environment variables, command line arguments, the clock, and randomness.
+//!
+//! Rather than writing these externs by hand, a Rust host can depend on this crate and link it in
+//! ```ignore
+//! // Nothing in the host calls these functions, so make sure they end up in the binary.
+//! extern crate roc_host_env;
+//! ```
+//! and then declare whichever effects it wants in its platform's `hosted Effect` module,
+//! with the names and types listed in [HOSTED_EFFECTS]. The symbols follow the usual
+//! `roc_fx_<name>` convention, so nothing else needs to match up.
+//!
+//! Like any host code using [roc_std], the host must provide `roc_alloc` and friends.
+use rand::RngCore;
+use roc_std::{RocDict, RocList, RocResult, RocStr, U128};
+use std::borrow::Borrow;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// An effect implemented by this crate, as a platform declares it in its `hosted Effect` module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HostedEffect {
+    pub name: &'static str,
+    pub roc_type: &'static str,
+}
+
+impl HostedEffect {
+    /// The line declaring this effect in a `hosted Effect` module.
+    pub fn annotation(&self) -> String {
+        format!("{} : {}", self.name, self.roc_type)
+    }
+
+    /// The name of the function the host has to export for this effect.
+    pub fn symbol(&self) -> String {
+        format!("roc_fx_{}", self.name)
+    }
+}
+
+pub const HOSTED_EFFECTS: &[HostedEffect] = &[
+    HostedEffect {
+        name: "envVar",
+        roc_type: "Str -> Effect (Result Str {})",
+    },
+    HostedEffect {
+        name: "envDict",
+        roc_type: "Effect (Dict Str Str)",
+    },
+    HostedEffect {
+        name: "args",
+        roc_type: "Effect (List Str)",
+    },
+    HostedEffect {
+        name: "posixTime",
+        roc_type: "Effect U128",
+    },
+    HostedEffect {
+        name: "sleepMillis",
+        roc_type: "U64 -> Effect {}",
+    },
+    HostedEffect {
+        name: "randomU64",
+        roc_type: "Effect U64",
+    },
+    HostedEffect {
+        name: "randomBytes",
+        roc_type: "Nat -> Effect (List U8)",
+    },
+];
+
+/// `envVar : Str -> Effect (Result Str {})`
+///
+/// The value of an environment variable, or an error if it isn't set.
+/// Invalid Unicode in the value is replaced with `�`.
+#[no_mangle]
+pub extern "C" fn roc_fx_envVar(name: &RocStr) -> RocResult<RocStr, ()> {
+    match std::env::var_os(name.as_str()) {
+        Some(os_str) => RocResult::ok(RocStr::from(os_str.to_string_lossy().borrow())),
+        None => RocResult::err(()),
+    }
+}
+
+/// `envDict : Effect (Dict Str Str)`
+///
+/// All environment variables. Invalid Unicode in names and values is replaced with `�`.
+#[no_mangle]
+pub extern "C" fn roc_fx_envDict() -> RocDict<RocStr, RocStr> {
+    std::env::vars_os()
+        .map(|(key, val)| {
+            (
+                RocStr::from(key.to_string_lossy().borrow()),
+                RocStr::from(val.to_string_lossy().borrow()),
+            )
+        })
+        .collect()
+}
+
+/// `args : Effect (List Str)`
+///
+/// The command line arguments, starting with the path the program was run as.
+/// Invalid Unicode is replaced with `�`.
+#[no_mangle]
+pub extern "C" fn roc_fx_args() -> RocList<RocStr> {
+    std::env::args_os()
+        .map(|os_str| RocStr::from(os_str.to_string_lossy().borrow()))
+        .collect()
+}
+
+/// `posixTime : Effect U128`
+///
+/// Nanoseconds since the UNIX epoch, or 0 if the system clock is set to before it.
+#[no_mangle]
+pub extern "C" fn roc_fx_posixTime() -> U128 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_nanos())
+        .unwrap_or(0);
+
+    U128::from(nanos)
+}
+
+/// `sleepMillis : U64 -> Effect {}`
+#[no_mangle]
+pub extern "C" fn roc_fx_sleepMillis(milliseconds: u64) {
+    std::thread::sleep(Duration::from_millis(milliseconds));
+}
+
+/// `randomU64 : Effect U64`
+///
+/// A random number from a generator seeded by the operating system, suitable for cryptography.
+#[no_mangle]
+pub extern "C" fn roc_fx_randomU64() -> u64 {
+    rand::thread_rng().next_u64()
+}
+
+/// `randomBytes : Nat -> Effect (List U8)`
+///
+/// This many random bytes, from the same generator as [roc_fx_randomU64].
+#[no_mangle]
+pub extern "C" fn roc_fx_randomBytes(count: usize) -> RocList<u8> {
+    let mut bytes = vec![0; count];
+    rand::thread_rng().fill_bytes(&mut bytes);
+
+    RocList::from_slice(&bytes)
+}
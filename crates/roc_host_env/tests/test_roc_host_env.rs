@@ -0,0 +1,97 @@
+#![allow(clippy::missing_safety_doc)]
+
+use core::ffi::c_void;
+
+#[no_mangle]
+pub unsafe extern "C" fn roc_alloc(size: usize, _alignment: u32) -> *mut c_void {
+    libc::malloc(size)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn roc_realloc(
+    c_ptr: *mut c_void,
+    new_size: usize,
+    _old_size: usize,
+    _alignment: u32,
+) -> *mut c_void {
+    libc::realloc(c_ptr, new_size)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn roc_dealloc(c_ptr: *mut c_void, _alignment: u32) {
+    libc::free(c_ptr)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn roc_memset(dst: *mut c_void, c: i32, n: usize) -> *mut c_void {
+    libc::memset(dst, c, n)
+}
+
+#[cfg(test)]
+mod test_roc_host_env {
+    use roc_host_env::*;
+    use roc_std::RocStr;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn env_var() {
+        std::env::set_var("ROC_HOST_ENV_TEST_VAR", "some value");
+
+        let found = roc_fx_envVar(&RocStr::from("ROC_HOST_ENV_TEST_VAR"));
+        let missing = roc_fx_envVar(&RocStr::from("ROC_HOST_ENV_TEST_MISSING"));
+
+        assert_eq!(Result::from(found), Ok(RocStr::from("some value")));
+        assert_eq!(Result::from(missing), Err(()));
+    }
+
+    #[test]
+    fn args_start_with_the_program() {
+        let args = roc_fx_args();
+
+        assert_eq!(
+            args.as_slice().first().map(RocStr::as_str),
+            std::env::args().next().as_deref()
+        );
+    }
+
+    #[test]
+    fn posix_time() {
+        let before = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let time = u128::from(roc_fx_posixTime());
+
+        assert!(before <= time);
+        assert!(time - before < 60 * 1_000_000_000);
+    }
+
+    #[test]
+    fn random_bytes() {
+        assert_eq!(roc_fx_randomBytes(0).len(), 0);
+        assert_eq!(roc_fx_randomBytes(100).len(), 100);
+    }
+
+    #[test]
+    fn cli_platform_declares_the_same_types() {
+        let effect_module = std::fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../examples/cli/cli-platform/Effect.roc"
+        ))
+        .unwrap();
+
+        let mut declared = Vec::new();
+
+        for effect in HOSTED_EFFECTS {
+            let prefix = format!("{} : ", effect.name);
+
+            if let Some(line) = effect_module.lines().find(|line| line.starts_with(&prefix)) {
+                assert_eq!(line.trim_end(), effect.annotation());
+                declared.push(effect.name);
+            }
+        }
+
+        // These are the ones the cli platform gets from this crate.
+        assert_eq!(declared, ["envVar", "envDict", "args"]);
+    }
+}
@@ -109,9 +109,11 @@ fn valgrind_test_linux(source: &str) {
     match res_binary_path {
         Ok(BuiltFile {
             binary_path,
+            host_path: _,
             problems,
             total_time: _,
             expect_metadata: _,
+            stats: _,
         }) => {
             if problems.exit_code() != 0 {
                 panic!("there are problems")
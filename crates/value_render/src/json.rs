@@ -0,0 +1,244 @@
+//! Renders decoded values as JSON, e.g. for `roc repl --output json`.
+use bumpalo::collections::String;
+use bumpalo::Bump;
+use roc_parse::ast::{AssignedField, Collection, Expr, StrLiteral};
+
+use crate::{Inspector, RocSyntax};
+
+/// Renders values as JSON, so they can be consumed by tools like `jq`.
+///
+/// Records become objects, lists and tuples become arrays, `Bool`s become booleans, and tags
+/// without a payload become strings. A tag with a payload becomes an object with the tag name
+/// as its only key, e.g. `Ok 1` is `{"Ok":1}` and `Pair 1 2` is `{"Pair":[1,2]}`. Opaque types
+/// and boxes are rendered as what they wrap.
+///
+/// Values that JSON can't represent (like functions, or a `NaN`) become a string with
+/// their Roc syntax, so the output is always valid JSON.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Json;
+
+impl Inspector for Json {
+    fn inspect<'a>(&self, arena: &'a Bump, value: &Expr<'_>) -> &'a str {
+        let mut buf = String::new_in(arena);
+
+        write_value(arena, &mut buf, value);
+
+        buf.into_bump_str()
+    }
+}
+
+fn write_value<'a>(arena: &'a Bump, buf: &mut String<'a>, value: &Expr<'_>) {
+    match value {
+        Expr::Num(number) | Expr::Float(number) if is_json_number(number) => buf.push_str(number),
+        Expr::Str(StrLiteral::PlainLine(string)) => write_string(buf, string),
+        Expr::Var {
+            module_name: "Bool",
+            ident: ident @ ("true" | "false"),
+        } => buf.push_str(ident),
+        Expr::Tag(tag) => write_string(buf, tag),
+        Expr::List(items) | Expr::Tuple(items) => {
+            buf.push('[');
+
+            for (index, item) in items.items.iter().enumerate() {
+                if index > 0 {
+                    buf.push(',');
+                }
+
+                write_value(arena, buf, &item.value);
+            }
+
+            buf.push(']');
+        }
+        Expr::Record(fields) => {
+            buf.push('{');
+
+            for (index, field) in fields.items.iter().enumerate() {
+                if index > 0 {
+                    buf.push(',');
+                }
+
+                write_field(arena, buf, &field.value);
+            }
+
+            buf.push('}');
+        }
+        Expr::Apply(function, args, _) => match &function.value {
+            Expr::Tag(tag) if args.is_empty() => write_string(buf, tag),
+            Expr::Tag(tag) => {
+                buf.push('{');
+                write_string(buf, tag);
+                buf.push(':');
+
+                match args {
+                    [arg] => write_value(arena, buf, &arg.value),
+                    _ => write_value(arena, buf, &Expr::Tuple(Collection::with_items(args))),
+                }
+
+                buf.push('}');
+            }
+            Expr::OpaqueRef(_)
+            | Expr::Var {
+                module_name: "Box",
+                ident: "box",
+            } if args.len() == 1 => write_value(arena, buf, &args[0].value),
+            _ => write_roc_syntax(arena, buf, value),
+        },
+        Expr::SpaceBefore(inner, _) | Expr::SpaceAfter(inner, _) | Expr::ParensAround(inner) => {
+            write_value(arena, buf, inner)
+        }
+        _ => write_roc_syntax(arena, buf, value),
+    }
+}
+
+fn write_field<'a>(arena: &'a Bump, buf: &mut String<'a>, field: &AssignedField<'_, Expr<'_>>) {
+    match field {
+        AssignedField::RequiredValue(label, _, value) => {
+            write_string(buf, label.value);
+            buf.push(':');
+            write_value(arena, buf, &value.value);
+        }
+        AssignedField::SpaceBefore(inner, _) | AssignedField::SpaceAfter(inner, _) => {
+            write_field(arena, buf, inner)
+        }
+        AssignedField::OptionalValue(label, _, _) | AssignedField::LabelOnly(label) => {
+            // decoded values always have a value for every field
+            write_string(buf, label.value);
+            buf.push_str(":null");
+        }
+        AssignedField::Malformed(text) => {
+            write_string(buf, text);
+            buf.push_str(":null");
+        }
+    }
+}
+
+fn write_roc_syntax<'a>(arena: &'a Bump, buf: &mut String<'a>, value: &Expr<'_>) {
+    write_string(buf, RocSyntax.inspect(arena, value))
+}
+
+fn write_string(buf: &mut String<'_>, string: &str) {
+    use std::fmt::Write;
+
+    buf.push('"');
+
+    for c in string.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            c if c.is_control() => write!(buf, "\\u{:04x}", c as u32).unwrap(),
+            c => buf.push(c),
+        }
+    }
+
+    buf.push('"');
+}
+
+/// Numbers are decoded with Rust's `Display`, which never uses exponents, but does print
+/// `NaN` and `inf`, which JSON has no numbers for.
+fn is_json_number(number: &str) -> bool {
+    let digits = number.strip_prefix('-').unwrap_or(number);
+
+    !digits.is_empty()
+        && digits.starts_with(|c: char| c.is_ascii_digit())
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && !digits.ends_with('.')
+        && digits.matches('.').count() <= 1
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use roc_parse::ast::CalledVia;
+    use roc_region::all::Loc;
+
+    fn json<'a>(arena: &'a Bump, value: &Expr<'_>) -> &'a str {
+        Json.inspect(arena, value)
+    }
+
+    #[test]
+    fn json_literals() {
+        let arena = Bump::new();
+
+        assert_eq!(json(&arena, &Expr::Num("-42")), "-42");
+        assert_eq!(json(&arena, &Expr::Num("1.5")), "1.5");
+        assert_eq!(json(&arena, &Expr::Num("NaN")), "\"NaN\"");
+        assert_eq!(
+            json(&arena, &Expr::Str(StrLiteral::PlainLine("say \"hi\"\n"))),
+            r#""say \"hi\"\n""#
+        );
+        assert_eq!(
+            json(
+                &arena,
+                &Expr::Var {
+                    module_name: "Bool",
+                    ident: "true"
+                }
+            ),
+            "true"
+        );
+    }
+
+    #[test]
+    fn json_collections() {
+        let arena = Bump::new();
+        let one = &*arena.alloc(Loc::at_zero(Expr::Num("1")));
+        let two = &*arena.alloc(Loc::at_zero(Expr::Num("2")));
+        let items = &*arena.alloc([one, two]);
+
+        assert_eq!(
+            json(&arena, &Expr::List(Collection::with_items(items))),
+            "[1,2]"
+        );
+
+        let field = Loc::at_zero(AssignedField::RequiredValue(
+            Loc::at_zero("answer"),
+            &[],
+            one,
+        ));
+        let fields = &*arena.alloc([field]);
+
+        assert_eq!(
+            json(&arena, &Expr::Record(Collection::with_items(fields))),
+            r#"{"answer":1}"#
+        );
+    }
+
+    #[test]
+    fn json_tags() {
+        let arena = Bump::new();
+        let one = &*arena.alloc(Loc::at_zero(Expr::Num("1")));
+        let two = &*arena.alloc(Loc::at_zero(Expr::Num("2")));
+        let ok = &*arena.alloc(Loc::at_zero(Expr::Tag("Ok")));
+        let pair = &*arena.alloc(Loc::at_zero(Expr::Tag("Pair")));
+
+        assert_eq!(json(&arena, &Expr::Tag("Red")), r#""Red""#);
+        assert_eq!(
+            json(
+                &arena,
+                &Expr::Apply(ok, arena.alloc([one]), CalledVia::Space)
+            ),
+            r#"{"Ok":1}"#
+        );
+        assert_eq!(
+            json(
+                &arena,
+                &Expr::Apply(pair, arena.alloc([one, two]), CalledVia::Space)
+            ),
+            r#"{"Pair":[1,2]}"#
+        );
+    }
+
+    #[test]
+    fn json_function() {
+        let arena = Bump::new();
+        let body = arena.alloc(Loc::at_zero(Expr::Num("1")));
+
+        assert_eq!(
+            json(&arena, &Expr::Closure(&[], body)),
+            format!("\"{}\"", crate::FUNCTION_PLACEHOLDER)
+        );
+    }
+}
@@ -0,0 +1,75 @@
+//! Renders Roc values that were decoded from memory (e.g. by `roc_repl_eval::eval::jit_to_ast`)
+//! as text. The REPL, `expect` failure reports, and `dbg` all render values through this crate,
+//! so a value is printed the same way no matter where it shows up.
+//!
+//! Tools and platforms that want a different rendering can implement [`Inspector`]; [`Json`]
+//! is one such rendering.
+use bumpalo::Bump;
+use roc_fmt::annotation::{Formattable, Newlines, Parens};
+use roc_parse::ast::Expr;
+
+mod json;
+
+pub use json::Json;
+
+/// What we print for function values when we don't show what they captured.
+pub const FUNCTION_PLACEHOLDER: &str = "<function>";
+
+/// Turns a decoded value into text.
+pub trait Inspector {
+    fn inspect<'a>(&self, arena: &'a Bump, value: &Expr<'_>) -> &'a str;
+}
+
+/// Renders values the way they would be written in Roc source code.
+/// This is what the REPL, `expect`, and `dbg` use.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RocSyntax;
+
+impl Inspector for RocSyntax {
+    fn inspect<'a>(&self, arena: &'a Bump, value: &Expr<'_>) -> &'a str {
+        match value {
+            Expr::Closure(_, _) | Expr::MalformedClosure => FUNCTION_PLACEHOLDER,
+            _ => {
+                let mut buf = roc_fmt::Buf::new_in(arena);
+
+                value.format_with_options(&mut buf, Parens::NotNeeded, Newlines::Yes, 0);
+
+                buf.into_bump_str()
+            }
+        }
+    }
+}
+
+/// Render a value with the default [`RocSyntax`] inspector.
+pub fn render<'a>(arena: &'a Bump, value: &Expr<'_>) -> &'a str {
+    RocSyntax.inspect(arena, value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use roc_parse::ast::StrLiteral;
+    use roc_region::all::Loc;
+
+    #[test]
+    fn render_literals() {
+        let arena = Bump::new();
+
+        assert_eq!(render(&arena, &Expr::Num("42")), "42");
+        assert_eq!(
+            render(&arena, &Expr::Str(StrLiteral::PlainLine("hello"))),
+            "\"hello\""
+        );
+    }
+
+    #[test]
+    fn render_function() {
+        let arena = Bump::new();
+        let body = arena.alloc(Loc::at_zero(Expr::Num("1")));
+
+        assert_eq!(
+            render(&arena, &Expr::Closure(&[], body)),
+            FUNCTION_PLACEHOLDER
+        );
+    }
+}
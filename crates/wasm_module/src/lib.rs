@@ -1,5 +1,6 @@
 pub mod linking;
 pub mod opcodes;
+mod optimize;
 pub mod parse;
 pub mod sections;
 pub mod serialize;
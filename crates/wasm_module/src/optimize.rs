@@ -0,0 +1,708 @@
+//! Size optimizations on the code section, similar to a few of Binaryen's `wasm-opt` passes:
+//! constant folding, `local.tee` formation, dead store removal, and local coalescing.
+//!
+//! These run on a fully linked module, after dead code elimination. By then there are no
+//! relocations left pointing into function bodies, so the bodies are free to change size.
+
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+
+use crate::opcodes::{immediates_for, OpCode, OpImmediates};
+use crate::parse::{Parse, ParseError, SkipBytes};
+use crate::serialize::SerialBuffer;
+use crate::{ValueType, WasmModule};
+
+const DROP_BYTES: &[u8] = &[OpCode::DROP as u8];
+
+/// A decoded instruction.
+/// Anything the optimizer doesn't need to look inside just keeps its original bytes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Instruction<'b> {
+    Local(OpCode, u32),
+    I32Const(i32),
+    I64Const(i64),
+    Leb32(OpCode, u32),
+    Leb32x2(OpCode, u32, u32),
+    Other(OpCode, &'b [u8]),
+    Deleted,
+}
+
+impl<'a> WasmModule<'a> {
+    /// Shrink the function bodies in the code section. Each body is only replaced if it got smaller.
+    /// Call this just before serializing, since it invalidates all code relocations.
+    pub fn optimize_code(&mut self, arena: &'a Bump) {
+        let function_count = self.code.function_count as usize;
+        if function_count == 0 {
+            return;
+        }
+
+        // FunctionSection also has signatures for the dummies that replaced dead imports
+        let first_signature = self.function.signatures.len() - function_count;
+        let bodies_start = self.code.function_offsets[0] as usize;
+
+        let mut bytes = Vec::with_capacity_in(self.code.bytes.len(), arena);
+        bytes.extend_from_slice(&self.code.bytes[..bodies_start]);
+        let mut function_offsets = Vec::with_capacity_in(function_count, arena);
+
+        let mut cursor = bodies_start;
+        for i in 0..function_count {
+            // If we can't even find the function boundaries, leave the code section alone
+            let size = match u32::parse((), &self.code.bytes, &mut cursor) {
+                Ok(size) => size as usize,
+                Err(_) => return,
+            };
+            let body = match self.code.bytes.get(cursor..cursor + size) {
+                Some(body) => body,
+                None => return,
+            };
+            cursor += size;
+
+            let signature = self.function.signatures[first_signature + i];
+            let param_count = self.types.param_count(signature);
+            let optimized = optimize_function(arena, body, param_count);
+            let new_body = optimized.as_deref().unwrap_or(body);
+
+            function_offsets.push(bytes.len() as u32);
+            bytes.encode_u32(new_body.len() as u32);
+            bytes.extend_from_slice(new_body);
+        }
+
+        self.code.bytes = bytes;
+        self.code.function_offsets = function_offsets;
+    }
+}
+
+/// Optimize one function body (local declarations followed by instructions).
+/// Returns None if the body contains anything we can't decode, or if it didn't get any smaller.
+fn optimize_function<'a>(arena: &'a Bump, body: &[u8], param_count: u32) -> Option<Vec<'a, u8>> {
+    let mut cursor = 0;
+    let locals = parse_locals(arena, body, &mut cursor).ok()?;
+
+    let mut instructions = Vec::with_capacity_in(body.len() / 2, arena);
+    while cursor < body.len() {
+        let instruction = parse_instruction(body, &mut cursor).ok()?;
+        push_folded(&mut instructions, instruction);
+    }
+
+    let new_locals = coalesce_locals(arena, param_count, &locals, &mut instructions)?;
+
+    let mut buffer = Vec::with_capacity_in(body.len(), arena);
+    serialize_function(&mut buffer, &new_locals, &instructions);
+    (buffer.len() < body.len()).then_some(buffer)
+}
+
+fn parse_locals<'a>(
+    arena: &'a Bump,
+    bytes: &[u8],
+    cursor: &mut usize,
+) -> Result<Vec<'a, ValueType>, ParseError> {
+    let group_count = u32::parse((), bytes, cursor)?;
+    let mut locals = Vec::new_in(arena);
+    for _ in 0..group_count {
+        let count = u32::parse((), bytes, cursor)?;
+        let ty = match bytes.get(*cursor) {
+            Some(&byte @ 0x7c..=0x7f) => ValueType::from(byte),
+            _ => {
+                return Err(ParseError {
+                    offset: *cursor,
+                    message: "Invalid local variable type".into(),
+                })
+            }
+        };
+        *cursor += 1;
+        locals.extend(std::iter::repeat(ty).take(count as usize));
+    }
+    Ok(locals)
+}
+
+fn parse_instruction<'b>(
+    bytes: &'b [u8],
+    cursor: &mut usize,
+) -> Result<Instruction<'b>, ParseError> {
+    use OpCode::*;
+
+    let start = *cursor;
+    if !is_known_opcode(bytes[start]) {
+        return Err(ParseError {
+            offset: start,
+            message: format!("Unknown Wasm instruction 0x{:02x}", bytes[start]),
+        });
+    }
+    let op = OpCode::from(bytes[start]);
+    let immediates = immediates_for(op).map_err(|message| ParseError {
+        offset: start,
+        message,
+    })?;
+
+    let instruction = match (op, immediates) {
+        (I32CONST, _) => {
+            *cursor += 1;
+            Instruction::I32Const(i32::parse((), bytes, cursor)?)
+        }
+        (I64CONST, _) => {
+            *cursor += 1;
+            Instruction::I64Const(i64::parse((), bytes, cursor)?)
+        }
+        (GETLOCAL | SETLOCAL | TEELOCAL, _) => {
+            *cursor += 1;
+            Instruction::Local(op, u32::parse((), bytes, cursor)?)
+        }
+        (_, OpImmediates::Leb32x1) => {
+            *cursor += 1;
+            Instruction::Leb32(op, u32::parse((), bytes, cursor)?)
+        }
+        (_, OpImmediates::Leb32x2) => {
+            *cursor += 1;
+            let a = u32::parse((), bytes, cursor)?;
+            let b = u32::parse((), bytes, cursor)?;
+            Instruction::Leb32x2(op, a, b)
+        }
+        _ => {
+            OpCode::skip_bytes(bytes, cursor)?;
+            Instruction::Other(op, &bytes[start..*cursor])
+        }
+    };
+
+    Ok(instruction)
+}
+
+/// Check that a byte is in our OpCode enum before transmuting it.
+/// Hosts can contain newer instructions, like the 0xfc prefix for bulk memory operations.
+fn is_known_opcode(byte: u8) -> bool {
+    matches!(
+        byte,
+        0x00..=0x05 | 0x0b..=0x11 | 0x1a | 0x1b | 0x20..=0x24 | 0x28..=0xbf
+    )
+}
+
+/// Append an instruction, then apply any peephole rewrites that it makes possible.
+/// One rewrite can expose another (`1 + 2 + 3`), so keep going until nothing matches.
+fn push_folded<'b>(instructions: &mut Vec<'_, Instruction<'b>>, instruction: Instruction<'b>) {
+    instructions.push(instruction);
+    while let Some((pop_count, replacement)) = peephole(instructions) {
+        instructions.truncate(instructions.len() - pop_count);
+        if let Some(replacement) = replacement {
+            instructions.push(replacement);
+        }
+    }
+}
+
+/// Look for a rewrite at the end of the instruction list.
+/// Consecutive instructions are always straight-line code, since branches only target blocks.
+fn peephole<'b>(instructions: &[Instruction<'b>]) -> Option<(usize, Option<Instruction<'b>>)> {
+    use Instruction::*;
+    use OpCode::*;
+
+    match *instructions {
+        [.., I32Const(a), I32Const(b), Other(op, _)] => {
+            if let Some(folded) = fold_i32(op, a, b) {
+                return Some((3, Some(I32Const(folded))));
+            }
+        }
+        [.., I64Const(a), I64Const(b), Other(op, _)] => {
+            if let Some(folded) = fold_i64(op, a, b) {
+                return Some((3, Some(I64Const(folded))));
+            }
+        }
+        _ => {}
+    }
+
+    match *instructions {
+        [.., Local(SETLOCAL, set), Local(GETLOCAL, get)] if set == get => {
+            Some((2, Some(Local(TEELOCAL, set))))
+        }
+        [.., I32Const(0), Other(op, _)] if is_i32_zero_identity(op) => Some((2, None)),
+        [.., I64Const(0), Other(op, _)] if is_i64_zero_identity(op) => Some((2, None)),
+        _ => None,
+    }
+}
+
+fn fold_i32(op: OpCode, a: i32, b: i32) -> Option<i32> {
+    use OpCode::*;
+    let folded = match op {
+        I32ADD => a.wrapping_add(b),
+        I32SUB => a.wrapping_sub(b),
+        I32MUL => a.wrapping_mul(b),
+        I32AND => a & b,
+        I32OR => a | b,
+        I32XOR => a ^ b,
+        // Wasm takes shift amounts modulo the bit width, just like the wrapping methods
+        I32SHL => a.wrapping_shl(b as u32),
+        I32SHRS => a.wrapping_shr(b as u32),
+        I32SHRU => (a as u32).wrapping_shr(b as u32) as i32,
+        _ => return None,
+    };
+    Some(folded)
+}
+
+fn fold_i64(op: OpCode, a: i64, b: i64) -> Option<i64> {
+    use OpCode::*;
+    let folded = match op {
+        I64ADD => a.wrapping_add(b),
+        I64SUB => a.wrapping_sub(b),
+        I64MUL => a.wrapping_mul(b),
+        I64AND => a & b,
+        I64OR => a | b,
+        I64XOR => a ^ b,
+        I64SHL => a.wrapping_shl(b as u32),
+        I64SHRS => a.wrapping_shr(b as u32),
+        I64SHRU => (a as u64).wrapping_shr(b as u32) as i64,
+        _ => return None,
+    };
+    Some(folded)
+}
+
+/// Binary operators that leave their first operand unchanged when the second one is zero
+fn is_i32_zero_identity(op: OpCode) -> bool {
+    use OpCode::*;
+    matches!(
+        op,
+        I32ADD | I32SUB | I32OR | I32XOR | I32SHL | I32SHRS | I32SHRU | I32ROTL | I32ROTR
+    )
+}
+
+fn is_i64_zero_identity(op: OpCode) -> bool {
+    use OpCode::*;
+    matches!(
+        op,
+        I64ADD | I64SUB | I64OR | I64XOR | I64SHL | I64SHRS | I64SHRU | I64ROTL | I64ROTR
+    )
+}
+
+/// A block, loop, `if` or `else` body, or the whole function
+#[derive(Debug)]
+struct Scope {
+    parent: usize,
+    is_loop: bool,
+    start: usize,
+    end: usize,
+}
+
+/// Find the innermost scope of every instruction
+fn build_scopes<'a>(
+    arena: &'a Bump,
+    instructions: &[Instruction],
+) -> (Vec<'a, Scope>, Vec<'a, usize>) {
+    use OpCode::*;
+
+    let mut scopes = Vec::with_capacity_in(8, arena);
+    scopes.push(Scope {
+        parent: 0,
+        is_loop: false,
+        start: 0,
+        end: instructions.len(),
+    });
+    let mut instruction_scopes = Vec::with_capacity_in(instructions.len(), arena);
+    let mut current = 0;
+
+    for (i, instruction) in instructions.iter().enumerate() {
+        match instruction {
+            Instruction::Other(op @ (BLOCK | LOOP | IF), _) => {
+                instruction_scopes.push(current);
+                scopes.push(Scope {
+                    parent: current,
+                    is_loop: *op == LOOP,
+                    start: i,
+                    end: instructions.len(),
+                });
+                current = scopes.len() - 1;
+            }
+            Instruction::Other(ELSE, _) => {
+                // The `else` body is a sibling of the `if` body, not nested inside it
+                scopes[current].end = i;
+                scopes.push(Scope {
+                    parent: scopes[current].parent,
+                    is_loop: false,
+                    start: i,
+                    end: instructions.len(),
+                });
+                current = scopes.len() - 1;
+                instruction_scopes.push(current);
+            }
+            Instruction::Other(END, _) => {
+                instruction_scopes.push(current);
+                scopes[current].end = i;
+                current = scopes[current].parent;
+            }
+            _ => instruction_scopes.push(current),
+        }
+    }
+
+    (scopes, instruction_scopes)
+}
+
+fn is_nested_in(scopes: &[Scope], mut scope: usize, ancestor: usize) -> bool {
+    loop {
+        if scope == ancestor {
+            return true;
+        }
+        if scope == 0 {
+            return false;
+        }
+        scope = scopes[scope].parent;
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct LocalUsage {
+    /// Instruction range where the local may hold a live value
+    start: usize,
+    end: usize,
+    /// Innermost scope of the first access
+    scope: usize,
+    /// The first access is a write, and every other access is nested in the same scope.
+    /// Then the write runs before any read, so the local doesn't need its initial zero value,
+    /// and it can share its slot with any other local whose range doesn't overlap.
+    shareable: bool,
+    is_read: bool,
+}
+
+#[derive(Debug)]
+struct Slot {
+    ty: ValueType,
+    end: usize,
+    shareable: bool,
+}
+
+/// Remove locals that are never read, and give locals with non-overlapping ranges the same index.
+/// Parameters are left alone. Returns the new local declarations.
+fn coalesce_locals<'a>(
+    arena: &'a Bump,
+    param_count: u32,
+    locals: &[ValueType],
+    instructions: &mut [Instruction],
+) -> Option<Vec<'a, ValueType>> {
+    use OpCode::*;
+
+    let (scopes, instruction_scopes) = build_scopes(arena, instructions);
+
+    let mut usages: Vec<Option<LocalUsage>> = Vec::from_iter_in(locals.iter().map(|_| None), arena);
+
+    for (i, instruction) in instructions.iter().enumerate() {
+        let (op, local) = match *instruction {
+            Instruction::Local(op, index) if index >= param_count => {
+                (op, (index - param_count) as usize)
+            }
+            _ => continue,
+        };
+        let scope = instruction_scopes[i];
+        let usage = usages.get_mut(local)?.get_or_insert(LocalUsage {
+            start: i,
+            end: i,
+            scope,
+            shareable: op != GETLOCAL,
+            is_read: false,
+        });
+
+        usage.shareable &= is_nested_in(&scopes, scope, usage.scope);
+        usage.is_read |= op == GETLOCAL;
+        usage.end = usage.end.max(i);
+
+        // Values can flow around a loop, so a local used inside a loop is live for all of it
+        let mut s = scope;
+        loop {
+            if scopes[s].is_loop {
+                usage.start = usage.start.min(scopes[s].start);
+                usage.end = usage.end.max(scopes[s].end);
+            }
+            if s == 0 {
+                break;
+            }
+            s = scopes[s].parent;
+        }
+    }
+
+    // Dead stores: a local that is never read doesn't need a slot at all
+    for instruction in instructions.iter_mut() {
+        if let Instruction::Local(op, index) = *instruction {
+            if index < param_count {
+                continue;
+            }
+            let is_read = usages[(index - param_count) as usize].map_or(false, |u| u.is_read);
+            if !is_read {
+                *instruction = match op {
+                    TEELOCAL => Instruction::Deleted,
+                    _ => Instruction::Other(DROP, DROP_BYTES),
+                };
+            }
+        }
+    }
+
+    // Greedily give each local the first free slot of its type, in order of where its range starts
+    let mut order = Vec::from_iter_in(
+        (0..locals.len()).filter(|&local| matches!(usages[local], Some(u) if u.is_read)),
+        arena,
+    );
+    order.sort_by_key(|&local| usages[local].map(|u| u.start));
+
+    let mut slots: Vec<Slot> = Vec::with_capacity_in(order.len(), arena);
+    let mut local_slots = Vec::from_iter_in(std::iter::repeat(0).take(locals.len()), arena);
+    for local in order {
+        let usage = usages[local]?;
+        let ty = locals[local];
+        let free_slot = if usage.shareable {
+            slots
+                .iter()
+                .position(|slot| slot.shareable && slot.ty == ty && slot.end < usage.start)
+        } else {
+            None
+        };
+        let slot = match free_slot {
+            Some(slot) => slot,
+            None => {
+                slots.push(Slot {
+                    ty,
+                    end: 0,
+                    shareable: usage.shareable,
+                });
+                slots.len() - 1
+            }
+        };
+        slots[slot].end = usage.end;
+        local_slots[local] = slot;
+    }
+
+    // Group the slots by type, so that the declarations take as few bytes as possible
+    let mut slot_order = Vec::from_iter_in(0..slots.len(), arena);
+    slot_order.sort_by_key(|&slot| slots[slot].ty as u8);
+
+    let mut slot_indices = Vec::from_iter_in(std::iter::repeat(0).take(slots.len()), arena);
+    let mut new_locals = Vec::with_capacity_in(slots.len(), arena);
+    for (position, &slot) in slot_order.iter().enumerate() {
+        slot_indices[slot] = param_count + position as u32;
+        new_locals.push(slots[slot].ty);
+    }
+
+    for instruction in instructions.iter_mut() {
+        if let Instruction::Local(_, index) = instruction {
+            if *index >= param_count {
+                *index = slot_indices[local_slots[(*index - param_count) as usize]];
+            }
+        }
+    }
+
+    Some(new_locals)
+}
+
+fn serialize_function<T: SerialBuffer>(
+    buffer: &mut T,
+    locals: &[ValueType],
+    instructions: &[Instruction],
+) {
+    // Local declarations are run-length encoded
+    let group_count = locals.windows(2).filter(|pair| pair[0] != pair[1]).count()
+        + usize::from(!locals.is_empty());
+    buffer.encode_u32(group_count as u32);
+    let mut i = 0;
+    while i < locals.len() {
+        let ty = locals[i];
+        let count = locals[i..].iter().take_while(|&&t| t == ty).count();
+        buffer.encode_u32(count as u32);
+        buffer.append_u8(ty as u8);
+        i += count;
+    }
+
+    for instruction in instructions {
+        match *instruction {
+            Instruction::Local(op, x) | Instruction::Leb32(op, x) => {
+                buffer.append_u8(op as u8);
+                buffer.encode_u32(x);
+            }
+            Instruction::I32Const(x) => {
+                buffer.append_u8(OpCode::I32CONST as u8);
+                buffer.encode_i32(x);
+            }
+            Instruction::I64Const(x) => {
+                buffer.append_u8(OpCode::I64CONST as u8);
+                buffer.encode_i64(x);
+            }
+            Instruction::Leb32x2(op, a, b) => {
+                buffer.append_u8(op as u8);
+                buffer.encode_u32(a);
+                buffer.encode_u32(b);
+            }
+            Instruction::Other(_, bytes) => buffer.append_slice(bytes),
+            Instruction::Deleted => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use OpCode::*;
+
+    const I32: u8 = ValueType::I32 as u8;
+    const VOID: u8 = ValueType::VOID;
+
+    fn optimize(arena: &Bump, body: &[u8]) -> std::vec::Vec<u8> {
+        match optimize_function(arena, body, 0) {
+            Some(optimized) => optimized.to_vec(),
+            None => body.to_vec(),
+        }
+    }
+
+    #[test]
+    fn fold_constants() {
+        let arena = &Bump::new();
+        #[rustfmt::skip]
+        let body = [
+            0,
+            I32CONST as u8, 2,
+            I32CONST as u8, 3,
+            I32ADD as u8,
+            I32CONST as u8, 4,
+            I32MUL as u8,
+            I32CONST as u8, 0,
+            I32OR as u8,
+            END as u8,
+        ];
+        assert_eq!(optimize(arena, &body), [0, I32CONST as u8, 20, END as u8]);
+    }
+
+    #[test]
+    fn coalesce_disjoint_locals() {
+        let arena = &Bump::new();
+        #[rustfmt::skip]
+        let body = [
+            1, 2, I32,
+            I32CONST as u8, 1,
+            SETLOCAL as u8, 0,
+            GETLOCAL as u8, 0,
+            GETLOCAL as u8, 0,
+            I32ADD as u8,
+            DROP as u8,
+            I32CONST as u8, 2,
+            SETLOCAL as u8, 1,
+            GETLOCAL as u8, 1,
+            GETLOCAL as u8, 1,
+            I32MUL as u8,
+            DROP as u8,
+            END as u8,
+        ];
+        #[rustfmt::skip]
+        let expected = [
+            1, 1, I32,
+            I32CONST as u8, 1,
+            TEELOCAL as u8, 0,
+            GETLOCAL as u8, 0,
+            I32ADD as u8,
+            DROP as u8,
+            I32CONST as u8, 2,
+            TEELOCAL as u8, 0,
+            GETLOCAL as u8, 0,
+            I32MUL as u8,
+            DROP as u8,
+            END as u8,
+        ];
+        assert_eq!(optimize(arena, &body), expected);
+    }
+
+    #[test]
+    fn remove_dead_stores() {
+        let arena = &Bump::new();
+        #[rustfmt::skip]
+        let body = [
+            1, 1, I32,
+            I32CONST as u8, 1,
+            SETLOCAL as u8, 0,
+            END as u8,
+        ];
+        #[rustfmt::skip]
+        let expected = [
+            0,
+            I32CONST as u8, 1,
+            DROP as u8,
+            END as u8,
+        ];
+        assert_eq!(optimize(arena, &body), expected);
+    }
+
+    #[test]
+    fn keep_locals_apart_across_loop_iterations() {
+        let arena = &Bump::new();
+        // Local 0 is read on every iteration, so local 1 can't reuse its slot inside the loop
+        #[rustfmt::skip]
+        let body = [
+            1, 2, I32,
+            I32CONST as u8, 7,
+            SETLOCAL as u8, 0,
+            LOOP as u8, VOID,
+            GETLOCAL as u8, 0,
+            DROP as u8,
+            I32CONST as u8, 1,
+            SETLOCAL as u8, 1,
+            GETLOCAL as u8, 1,
+            GETLOCAL as u8, 1,
+            I32ADD as u8,
+            BRIF as u8, 0,
+            END as u8,
+            END as u8,
+        ];
+        #[rustfmt::skip]
+        let expected = [
+            1, 2, I32,
+            I32CONST as u8, 7,
+            SETLOCAL as u8, 0,
+            LOOP as u8, VOID,
+            GETLOCAL as u8, 0,
+            DROP as u8,
+            I32CONST as u8, 1,
+            TEELOCAL as u8, 1,
+            GETLOCAL as u8, 1,
+            I32ADD as u8,
+            BRIF as u8, 0,
+            END as u8,
+            END as u8,
+        ];
+        assert_eq!(optimize(arena, &body), expected);
+    }
+
+    #[test]
+    fn keep_zero_initialized_locals() {
+        let arena = &Bump::new();
+        // Local 0 is read before it's written, so it relies on its initial zero value
+        #[rustfmt::skip]
+        let body = [
+            1, 2, I32,
+            GETLOCAL as u8, 0,
+            DROP as u8,
+            I32CONST as u8, 1,
+            SETLOCAL as u8, 1,
+            GETLOCAL as u8, 1,
+            GETLOCAL as u8, 1,
+            I32ADD as u8,
+            DROP as u8,
+            END as u8,
+        ];
+        #[rustfmt::skip]
+        let expected = [
+            1, 2, I32,
+            GETLOCAL as u8, 0,
+            DROP as u8,
+            I32CONST as u8, 1,
+            TEELOCAL as u8, 1,
+            GETLOCAL as u8, 1,
+            I32ADD as u8,
+            DROP as u8,
+            END as u8,
+        ];
+        assert_eq!(optimize(arena, &body), expected);
+    }
+
+    #[test]
+    fn skip_unknown_instructions() {
+        let arena = &Bump::new();
+        // memory.copy is not in our OpCode enum
+        #[rustfmt::skip]
+        let body = [
+            0,
+            I32CONST as u8, 1,
+            I32CONST as u8, 2,
+            I32ADD as u8,
+            0xfc, 0x0a, 0, 0,
+            END as u8,
+        ];
+        assert!(optimize_function(arena, &body, 0).is_none());
+    }
+}
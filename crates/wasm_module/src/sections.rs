@@ -298,6 +298,12 @@ impl<'a> TypeSection<'a> {
         self.bytes.is_empty()
     }
 
+    pub fn param_count(&self, sig_index: u32) -> u32 {
+        let mut offset = self.offsets[sig_index as usize];
+        offset += 1; // separator
+        u32::parse((), &self.bytes, &mut offset).unwrap()
+    }
+
     pub fn look_up(&'a self, sig_index: u32) -> (SignatureParamsIter<'a>, Option<ValueType>) {
         let mut offset = self.offsets[sig_index as usize];
         offset += 1; // separator
@@ -3,12 +3,15 @@
 mod file_glue;
 mod glue;
 
+// Provides roc_fx_envDict, roc_fx_args and roc_fx_envVar.
+extern crate roc_host_env;
+
 use core::alloc::Layout;
 use core::ffi::c_void;
 use core::mem::MaybeUninit;
 use glue::Metadata;
-use roc_std::{RocDict, RocList, RocResult, RocStr};
-use std::borrow::{Borrow, Cow};
+use roc_std::{RocList, RocResult, RocStr};
+use std::borrow::Cow;
 use std::ffi::OsStr;
 use std::fs::File;
 use std::io::Write;
@@ -239,36 +242,6 @@ unsafe fn call_the_closure(closure_data_ptr: *const u8) -> u8 {
     0
 }
 
-#[no_mangle]
-pub extern "C" fn roc_fx_envDict() -> RocDict<RocStr, RocStr> {
-    // TODO: can we be more efficient about reusing the String's memory for RocStr?
-    std::env::vars_os()
-        .map(|(key, val)| {
-            (
-                RocStr::from(key.to_string_lossy().borrow()),
-                RocStr::from(val.to_string_lossy().borrow()),
-            )
-        })
-        .collect()
-}
-
-#[no_mangle]
-pub extern "C" fn roc_fx_args() -> RocList<RocStr> {
-    // TODO: can we be more efficient about reusing the String's memory for RocStr?
-    std::env::args_os()
-        .map(|os_str| RocStr::from(os_str.to_string_lossy().borrow()))
-        .collect()
-}
-
-#[no_mangle]
-pub extern "C" fn roc_fx_envVar(roc_str: &RocStr) -> RocResult<RocStr, ()> {
-    // TODO: can we be more efficient about reusing the String's memory for RocStr?
-    match std::env::var_os(roc_str.as_str()) {
-        Some(os_str) => RocResult::ok(RocStr::from(os_str.to_string_lossy().borrow())),
-        None => RocResult::err(()),
-    }
-}
-
 #[no_mangle]
 pub extern "C" fn roc_fx_setCwd(roc_path: &RocList<u8>) -> RocResult<(), ()> {
     match std::env::set_current_dir(path_from_roc_path(roc_path)) {
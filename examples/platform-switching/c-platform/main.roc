@@ -4,6 +4,7 @@ platform "echo-in-c"
     packages {}
     imports []
     provides [mainForHost]
+    threading singleThreaded
 
 mainForHost : Str
 mainForHost = main